@@ -6,7 +6,8 @@ use criterion::{
     Criterion, Throughput,
 };
 use mpc_stark::{
-    algebra::scalar::Scalar, beaver::PartyIDBeaverSource, network::NoRecvNetwork, MpcFabric, PARTY0,
+    algebra::scalar::Scalar, beaver::PartyIDBeaverSource, network::NoRecvNetwork, FabricConfig,
+    MpcFabric, PARTY0,
 };
 use rand::{rngs::OsRng, thread_rng};
 use tokio::runtime::Builder as RuntimeBuilder;
@@ -42,11 +43,21 @@ pub fn config() -> Criterion {
 
 /// Create a mock fabric for testing
 pub fn mock_fabric(size_hint: usize) -> MpcFabric {
-    let network = NoRecvNetwork::default();
+    let network = NoRecvNetwork;
     let beaver_source = PartyIDBeaverSource::new(PARTY0);
     MpcFabric::new_with_size_hint(size_hint, network, beaver_source)
 }
 
+/// Create a mock fabric whose executor drains its queue in batches of the given size
+pub fn mock_fabric_with_batch_size(size_hint: usize, batch_size: usize) -> MpcFabric {
+    let network = NoRecvNetwork;
+    let beaver_source = PartyIDBeaverSource::new(PARTY0);
+    let config = FabricConfig::default()
+        .with_size_hint(size_hint)
+        .with_executor_batch_size(batch_size);
+    MpcFabric::with_config(network, beaver_source, config)
+}
+
 // --------------
 // | Benchmarks |
 // --------------
@@ -106,9 +117,47 @@ pub fn circuit_scalar_addition(c: &mut Criterion) {
     }
 }
 
+/// Measures the throughput of the executor thread for scalar operations as the number of
+/// jobs it drains from its queue at once varies
+pub fn circuit_scalar_addition_batched(c: &mut Criterion) {
+    const CIRCUIT_SIZE: usize = 10_000;
+    let runtime = RuntimeBuilder::new_multi_thread()
+        .worker_threads(3)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("circuit_scalar_addition_batched");
+    group.throughput(Throughput::Elements(CIRCUIT_SIZE as u64));
+    for batch_size in [1, 16, 64, 256].into_iter() {
+        group.bench_function(BenchmarkId::from_parameter(batch_size), |b| {
+            let mut b = b.to_async(&runtime);
+            b.iter_batched(
+                || {
+                    let mut rng = OsRng {};
+                    let mock_fabric = mock_fabric_with_batch_size(CIRCUIT_SIZE * 2, batch_size);
+                    let mock_scalar = mock_fabric.allocate_scalar(Scalar::random(&mut rng));
+
+                    (mock_fabric, mock_scalar)
+                },
+                |(mock_fabric, mock_scalar)| async move {
+                    let mut res = mock_scalar;
+                    for _ in 0..CIRCUIT_SIZE {
+                        res = &res + &res;
+                    }
+
+                    res.await;
+                    mock_fabric.shutdown();
+                },
+                criterion::BatchSize::SmallInput,
+            );
+        });
+    }
+}
+
 criterion_group! {
     name = scalar_ops;
     config = config();
-    targets = scalar_addition, circuit_scalar_addition
+    targets = scalar_addition, circuit_scalar_addition, circuit_scalar_addition_batched
 }
 criterion_main!(scalar_ops);
@@ -7,7 +7,8 @@ use clap::Parser;
 use cpuprofiler::PROFILER;
 use gperftools::HEAP_PROFILER;
 use mpc_stark::{
-    algebra::scalar::Scalar, beaver::PartyIDBeaverSource, network::NoRecvNetwork, MpcFabric, PARTY0,
+    algebra::scalar::Scalar, beaver::PartyIDBeaverSource, network::NoRecvNetwork, FabricConfig,
+    MpcFabric, PARTY0,
 };
 use rand::thread_rng;
 
@@ -18,11 +19,15 @@ use rand::thread_rng;
 /// The number of gates to use in the benchmark
 const NUM_GATES: usize = 10_000_000;
 
-/// Create a mock fabric for testing
-pub fn mock_fabric(size_hint: usize) -> MpcFabric {
+/// Create a mock fabric for testing, draining the executor's queue in batches of the given
+/// size
+pub fn mock_fabric(size_hint: usize, batch_size: usize) -> MpcFabric {
     let network = NoRecvNetwork::default();
     let beaver_source = PartyIDBeaverSource::new(PARTY0);
-    MpcFabric::new_with_size_hint(size_hint, network, beaver_source)
+    let config = FabricConfig::default()
+        .with_size_hint(size_hint)
+        .with_executor_batch_size(batch_size);
+    MpcFabric::with_config(network, beaver_source, config)
 }
 
 pub fn start_cpu_profiler(profiled: bool) {
@@ -66,6 +71,9 @@ struct Args {
     /// Whether to enable heap profiling
     #[clap(long, takes_value = false, value_parser)]
     heap_profiled: bool,
+    /// The number of jobs the executor drains from its queue at once
+    #[clap(long, default_value = "1", value_parser)]
+    batch_size: usize,
     /// The bench argument, needed for all benchmarks
     #[clap(long, takes_value = true, value_parser)]
     bench: bool,
@@ -80,7 +88,7 @@ async fn main() {
     let start_time = Instant::now();
 
     // Setup benchmark
-    let fabric = mock_fabric(NUM_GATES * 2);
+    let fabric = mock_fabric(NUM_GATES * 2, args.batch_size);
     let allocation_time = start_time.elapsed();
 
     let mut rng = thread_rng();
@@ -96,6 +104,7 @@ async fn main() {
     let _res = res.await;
     let res_time = start_time.elapsed() - allocation_time;
 
+    println!("executor batch size {}", args.batch_size);
     println!("memory allocation took {allocation_time:?}");
     println!("circuit construction took {circuit_creation_time:?}");
     println!("circuit evaluation took {res_time:?}");
@@ -7,6 +7,7 @@ use mpc_stark::{
         authenticated_scalar::{test_helpers::*, AuthenticatedScalarResult},
         scalar::Scalar,
     },
+    network::Tamper,
     ResultValue, PARTY0, PARTY1,
 };
 use rand::thread_rng;
@@ -14,9 +15,10 @@ use std::ops::Neg;
 
 use crate::{
     helpers::{
-        assert_err, assert_scalar_batches_eq, assert_scalars_eq, await_batch_result_with_error,
-        await_result, await_result_batch, await_result_with_error, share_authenticated_scalar,
-        share_authenticated_scalar_batch, share_plaintext_value, share_plaintext_values_batch,
+        assert_err, assert_scalar_batches_eq, assert_scalars_eq, assert_tampering_detected,
+        await_batch_result_with_error, await_result, await_result_batch, await_result_with_error,
+        share_authenticated_scalar, share_authenticated_scalar_batch, share_plaintext_value,
+        share_plaintext_values_batch,
     },
     IntegrationTest, IntegrationTestArgs,
 };
@@ -93,6 +95,41 @@ fn test_open_authenticated__bad_public_modifier(
     assert_err(await_result_with_error(res))
 }
 
+/// Tests that tampering with any message of an authenticated opening is caught
+#[allow(non_snake_case)]
+fn test_open_authenticated__tampered(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    let my_val = Scalar::random(&mut thread_rng());
+    let party0_value = share_authenticated_scalar(my_val, PARTY0, test_args);
+
+    assert_tampering_detected(
+        || vec![party0_value.open_authenticated()],
+        || Tamper::Flip,
+        test_args,
+    )
+}
+
+/// Tests that tampering with any message of a batch authenticated opening is caught
+#[allow(non_snake_case)]
+fn test_open_authenticated_batch__tampered(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    let n = 5;
+    let mut rng = thread_rng();
+    let my_vals = (0..n).map(|_| Scalar::random(&mut rng)).collect_vec();
+    let party0_values = share_authenticated_scalar_batch(my_vals, PARTY0, test_args);
+
+    let open = || AuthenticatedScalarResult::open_authenticated_batch(&party0_values);
+    assert_tampering_detected(open, || Tamper::Flip, test_args)?;
+    assert_tampering_detected(open, || Tamper::SwapBatchElements(0, 1), test_args)?;
+
+    let open_single_commitment =
+        || AuthenticatedScalarResult::open_authenticated_batch_single_commitment(&party0_values);
+    assert_tampering_detected(open_single_commitment, || Tamper::Flip, test_args)?;
+    assert_tampering_detected(
+        open_single_commitment,
+        || Tamper::SwapBatchElements(0, 1),
+        test_args,
+    )
+}
+
 // --------------
 // | Arithmetic |
 // --------------
@@ -502,6 +539,16 @@ inventory::submit!(IntegrationTest {
     test_fn: test_open_authenticated__bad_public_modifier,
 });
 
+inventory::submit!(IntegrationTest {
+    name: "authenticated_scalar::test_open_authenticated__tampered",
+    test_fn: test_open_authenticated__tampered,
+});
+
+inventory::submit!(IntegrationTest {
+    name: "authenticated_scalar::test_open_authenticated_batch__tampered",
+    test_fn: test_open_authenticated_batch__tampered,
+});
+
 inventory::submit!(IntegrationTest {
     name: "authenticated_scalar::test_add_public_value",
     test_fn: test_add_public_value,
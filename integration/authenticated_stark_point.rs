@@ -9,15 +9,17 @@ use mpc_stark::{
         },
         scalar::Scalar,
     },
+    network::Tamper,
     random_point, PARTY0, PARTY1,
 };
 use rand::thread_rng;
 
 use crate::{
     helpers::{
-        assert_err, assert_point_batches_eq, assert_points_eq, await_batch_result_with_error,
-        await_result, await_result_batch, await_result_with_error, share_authenticated_point,
-        share_authenticated_point_batch, share_authenticated_scalar, share_plaintext_values_batch,
+        assert_err, assert_point_batches_eq, assert_points_eq, assert_tampering_detected,
+        await_batch_result_with_error, await_result, await_result_batch, await_result_with_error,
+        share_authenticated_point, share_authenticated_point_batch, share_authenticated_scalar,
+        share_plaintext_values_batch,
     },
     IntegrationTest, IntegrationTestArgs,
 };
@@ -80,6 +82,31 @@ fn test_open_authenticated__bad_public_modifier(
     assert_err(res_open)
 }
 
+/// Test that tampering with any message of an authenticated opening is caught
+#[allow(non_snake_case)]
+fn test_open_authenticated__tampered(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    let my_val = random_point();
+    let shared_val = share_authenticated_point(my_val, PARTY0, test_args);
+
+    assert_tampering_detected(
+        || vec![shared_val.open_authenticated()],
+        || Tamper::Flip,
+        test_args,
+    )
+}
+
+/// Test that tampering with any message of a batch authenticated opening is caught
+#[allow(non_snake_case)]
+fn test_open_authenticated_batch__tampered(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    let n = 5;
+    let my_vals = (0..n).map(|_| random_point()).collect_vec();
+    let shared_vals = share_authenticated_point_batch(my_vals, PARTY0, test_args);
+
+    let open = || AuthenticatedStarkPointResult::open_authenticated_batch(&shared_vals);
+    assert_tampering_detected(open, || Tamper::Flip, test_args)?;
+    assert_tampering_detected(open, || Tamper::SwapBatchElements(0, 1), test_args)
+}
+
 // --------------
 // | Arithmetic |
 // --------------
@@ -438,6 +465,16 @@ inventory::submit!(IntegrationTest {
     test_fn: test_open_authenticated__bad_public_modifier
 });
 
+inventory::submit!(IntegrationTest {
+    name: "authenticated_stark_point::test_open_authenticated__tampered",
+    test_fn: test_open_authenticated__tampered
+});
+
+inventory::submit!(IntegrationTest {
+    name: "authenticated_stark_point::test_open_authenticated_batch__tampered",
+    test_fn: test_open_authenticated_batch__tampered
+});
+
 inventory::submit!(IntegrationTest {
     name: "authenticated_stark_point::test_addition_public_point",
     test_fn: test_addition_public_point
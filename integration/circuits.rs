@@ -116,6 +116,64 @@ fn test_msm(test_args: &IntegrationTestArgs) -> Result<(), String> {
     assert_points_eq(res_open, expected_res)
 }
 
+/// Tests a batch of multiscalar multiplications of different lengths
+///
+/// Party 0 selects all the scalars, party 1 selects the points
+fn test_batch_msm(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    let lengths = [1, 10, 0, 25];
+    let fabric = &test_args.fabric;
+    let mut rng = thread_rng();
+
+    let n = lengths.iter().sum();
+    let my_scalars = (0..n).map(|_| Scalar::random(&mut rng)).collect_vec();
+    let my_points = (0..n).map(|_| random_point()).collect_vec();
+
+    // Share the values in plaintext
+    let allocd_scalars = fabric.allocate_scalars(my_scalars.clone());
+    let allocd_points = fabric.allocate_points(my_points.clone());
+    let plaintext_scalars = await_result_batch(&share_plaintext_values_batch(
+        &allocd_scalars,
+        PARTY0,
+        fabric,
+    ));
+    let plaintext_points = await_result_batch(&share_plaintext_values_batch(
+        &allocd_points,
+        PARTY1,
+        fabric,
+    ));
+
+    // Share the values in an MPC circuit
+    let shared_scalars = fabric.batch_share_scalar(my_scalars, PARTY0);
+    let shared_points = fabric.batch_share_point(my_points, PARTY1);
+
+    // Split the values into msms of the given lengths
+    let mut scalars = Vec::new();
+    let mut points = Vec::new();
+    let mut expected = Vec::new();
+    let mut offset = 0;
+    for len in lengths {
+        let range = offset..offset + len;
+        offset += len;
+
+        scalars.push(shared_scalars[range.clone()].to_vec());
+        points.push(shared_points[range.clone()].to_vec());
+        expected.push(StarkPoint::msm(
+            &plaintext_scalars[range.clone()],
+            &plaintext_points[range],
+        ));
+    }
+
+    // Compare results
+    let res = AuthenticatedStarkPointResult::batch_msm(&scalars, &points);
+    for (res, expected) in res.into_iter().zip(expected) {
+        let res_open = await_result(res.open_authenticated())
+            .map_err(|err| format!("error opening msm result: {err:?}"))?;
+        assert_points_eq(res_open, expected)?;
+    }
+
+    Ok(())
+}
+
 /// Tests evaluation of a shared polynomial on a public input
 fn test_polynomial_eval(test_args: &IntegrationTestArgs) -> Result<(), String> {
     let fabric = &test_args.fabric;
@@ -202,6 +260,11 @@ inventory::submit!(IntegrationTest {
     test_fn: test_msm
 });
 
+inventory::submit!(IntegrationTest {
+    name: "circuits::test_batch_msm",
+    test_fn: test_batch_msm
+});
+
 inventory::submit!(IntegrationTest {
     name: "circuits::test_polynomial_eval",
     test_fn: test_polynomial_eval
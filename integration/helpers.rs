@@ -10,9 +10,8 @@ use mpc_stark::{
         authenticated_stark_point::AuthenticatedStarkPointResult, mpc_scalar::MpcScalarResult,
         mpc_stark_point::MpcStarkPointResult, scalar::Scalar, stark_curve::StarkPoint,
     },
-    beaver::SharedValueSource,
-    network::{NetworkPayload, PartyId},
-    {MpcFabric, ResultHandle, ResultValue},
+    network::{NetworkPayload, PartyId, PayloadType, Tamper},
+    {MpcFabric, ResultHandle, PARTY0, PARTY1},
 };
 use tokio::runtime::Handle;
 
@@ -80,6 +79,59 @@ pub(crate) fn assert_err<T, E>(res: Result<T, E>) -> Result<(), String> {
     }
 }
 
+/// Check that tampering with any message party 0 sends in an authenticated opening is caught
+/// by party 1's MAC check
+///
+/// The opening is run once honestly to find the range of result IDs it allocates, then once
+/// for each ID in the range with party 0 tampering with the message of that ID, if it sends one
+pub(crate) fn assert_tampering_detected<T, E, R, F>(
+    open: F,
+    tamper: fn() -> Tamper,
+    test_args: &IntegrationTestArgs,
+) -> Result<(), String>
+where
+    E: Debug,
+    R: Future<Output = Result<T, E>>,
+    F: Fn() -> Vec<R>,
+{
+    let fabric = &test_args.fabric;
+    let start = fabric.peek_next_result_id();
+    let honest = open();
+    let n_ids = fabric.peek_next_result_id() - start;
+    await_batch_result_with_error(honest)?;
+
+    let mut n_tampered = 0;
+    for offset in 0..n_ids {
+        if test_args.party_id == PARTY0 {
+            let target = fabric.peek_next_result_id() + offset;
+            test_args.tamper.tamper(target, tamper());
+        }
+
+        let res = await_batch_result_with_error(open());
+        test_args.tamper.clear();
+
+        // Party 1's opening depends on every message party 0 sends in it, so once party 1
+        // signals that its opening resolved, party 0 knows whether it modified a message
+        await_result(fabric.share_plaintext(Scalar::one(), PARTY1));
+        let tampered = !test_args.tamper.take_applied().is_empty();
+        let tampered = await_result(fabric.share_plaintext(Scalar::from(tampered), PARTY0));
+        if tampered == Scalar::one() {
+            n_tampered += 1;
+            if test_args.party_id == PARTY1 && res.is_ok() {
+                return Err(format!(
+                    "tampering with result {offset} of the opening went undetected"
+                ));
+            }
+        }
+    }
+
+    if n_tampered == 0 {
+        return Err("no message of the opening was tampered with".to_string());
+    }
+
+    Ok(())
+}
+
 /// Await a result in the computation graph by blocking the current task
 pub(crate) fn await_result<R, T: Future<Output = R>>(res: T) -> R {
     Handle::current().block_on(res)
@@ -198,7 +250,7 @@ pub(crate) fn share_authenticated_point_batch(
 }
 
 /// Share a value with the counterparty by sender ID, the sender sends and the receiver receives
-pub(crate) fn share_plaintext_value<T: From<ResultValue> + Into<NetworkPayload>>(
+pub(crate) fn share_plaintext_value<T: PayloadType + Into<NetworkPayload>>(
     value: ResultHandle<T>,
     sender: PartyId,
     fabric: &MpcFabric,
@@ -211,7 +263,7 @@ pub(crate) fn share_plaintext_value<T: From<ResultValue> + Into<NetworkPayload>>
 }
 
 /// Share a batch of values in the plaintext
-pub(crate) fn share_plaintext_values_batch<T: From<ResultValue> + Into<NetworkPayload> + Clone>(
+pub(crate) fn share_plaintext_values_batch<T: PayloadType + Into<NetworkPayload> + Clone>(
     values: &[ResultHandle<T>],
     sender: PartyId,
     fabric: &MpcFabric,
@@ -221,45 +273,3 @@ pub(crate) fn share_plaintext_values_batch<T: From<ResultValue> + Into<NetworkPa
         .map(|v| share_plaintext_value(v.clone(), sender, fabric))
         .collect_vec()
 }
-
-// ---------
-// | Mocks |
-// ---------
-
-/// Returns beaver triples (0, 0, 0) for party 0 and (1, 1, 1) for party 1
-#[derive(Clone, Debug)]
-pub(crate) struct PartyIDBeaverSource {
-    party_id: u64,
-}
-
-impl PartyIDBeaverSource {
-    pub fn new(party_id: u64) -> Self {
-        Self { party_id }
-    }
-}
-
-/// The PartyIDBeaverSource returns beaver triplets split statically between the
-/// parties. We assume a = 2, b = 3 ==> c = 6. [a] = (1, 1); [b] = (3, 0) [c] = (2, 4)
-impl SharedValueSource for PartyIDBeaverSource {
-    fn next_shared_bit(&mut self) -> Scalar {
-        // Simply output partyID, assume partyID \in {0, 1}
-        assert!(self.party_id == 0 || self.party_id == 1);
-        Scalar::from(self.party_id)
-    }
-
-    fn next_triplet(&mut self) -> (Scalar, Scalar, Scalar) {
-        if self.party_id == 0 {
-            (Scalar::from(1u64), Scalar::from(3u64), Scalar::from(2u64))
-        } else {
-            (Scalar::from(1u64), Scalar::from(0u64), Scalar::from(4u64))
-        }
-    }
-
-    fn next_shared_inverse_pair(&mut self) -> (Scalar, Scalar) {
-        (Scalar::from(1), Scalar::from(1))
-    }
-
-    fn next_shared_value(&mut self) -> Scalar {
-        Scalar::from(self.party_id)
-    }
-}
@@ -5,9 +5,13 @@ use colored::Colorize;
 use dns_lookup::lookup_host;
 use env_logger::Builder;
 use futures::{SinkExt, StreamExt};
-use helpers::PartyIDBeaverSource;
 use mpc_stark::{
-    network::{NetworkOutbound, NetworkPayload, QuicTwoPartyNet},
+    algebra::scalar::Scalar,
+    network::{
+        IdentityKeypair, NetworkOutbound, NetworkPayload, QuicTwoPartyNet, TamperHandle,
+        TamperingNetwork,
+    },
+    test_helpers::PartyIDBeaverSource,
     MpcFabric, PARTY0,
 };
 use tokio::runtime::{Builder as RuntimeBuilder, Handle};
@@ -29,6 +33,8 @@ const SHUTDOWN_TIMEOUT_MS: u64 = 3_000; // 3 seconds
 struct IntegrationTestArgs {
     party_id: u64,
     fabric: MpcFabric,
+    /// A handle used to tamper with the messages the local party sends
+    tamper: TamperHandle,
 }
 
 /// Integration test format
@@ -110,8 +116,16 @@ fn main() {
 
         println!("Lookup successful, found peer at {:?}", peer_addr);
 
-        // Build and connect to the network
-        let mut net = QuicTwoPartyNet::new(args.party, local_addr, peer_addr);
+        // Build and connect to the network, each party authenticates with a fixed identity key
+        let identity = IdentityKeypair::from_secret_key(Scalar::from(args.party + 1));
+        let peer_identity = IdentityKeypair::from_secret_key(Scalar::from(2 - args.party));
+        let mut net = QuicTwoPartyNet::new_with_identity(
+            args.party,
+            local_addr,
+            peer_addr,
+            identity,
+            Some(peer_identity.public_key()),
+        );
         Handle::current().block_on(net.connect()).unwrap();
 
         // Send a byte to give the connection time to establish
@@ -126,6 +140,10 @@ fn main() {
             let _recv_bytes = Handle::current().block_on(net.next()).unwrap();
         }
 
+        // Wrap the network so that tests may tamper with outbound messages
+        let net = TamperingNetwork::new(net);
+        let tamper = net.handle();
+
         let beaver_source = PartyIDBeaverSource::new(args.party);
         let fabric =
             MpcFabric::new_with_size_hint(10_000_000 /* size_hint */, net, beaver_source);
@@ -141,6 +159,7 @@ fn main() {
         let test_args = IntegrationTestArgs {
             party_id: args.party,
             fabric: fabric.clone(),
+            tamper,
         };
         let mut all_success = true;
 
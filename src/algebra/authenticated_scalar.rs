@@ -8,21 +8,28 @@ use std::{
     task::{Context, Poll},
 };
 
-use futures::{Future, FutureExt};
+use futures::Future;
 use itertools::{izip, Itertools};
 
 use crate::{
-    commitment::{PedersenCommitment, PedersenCommitmentResult},
+    commitment::{CommitmentResult, CommitmentScheme, HashCommitment},
     error::MpcError,
-    fabric::{MpcFabric, ResultId, ResultValue},
+    fabric::{
+        parallel::par_map, MpcFabric, ResultId, ResultValue, SecurityMode, TranscriptEntryKind,
+    },
+    network::{NetworkPayload, PartyId, PayloadType, SessionId},
     ResultHandle, PARTY0,
 };
 
 use super::{
     authenticated_stark_point::AuthenticatedStarkPointResult,
+    mac_check::{decode_mac_check, encode_mac_check},
     macros::{impl_borrow_variants, impl_commutative},
     mpc_scalar::MpcScalarResult,
-    scalar::{BatchScalarResult, Scalar, ScalarResult},
+    scalar::{
+        add_scalars, scalar_args, scalar_results, scale_scalar_chunks, sub_scalars,
+        BatchScalarResult, Scalar, ScalarResult,
+    },
     stark_curve::{StarkPoint, StarkPointResult},
 };
 
@@ -151,13 +158,27 @@ impl AuthenticatedScalarResult {
     }
 
     /// Open the value without checking its MAC
+    ///
+    /// If the fabric defers MAC checks, the opening is recorded and checked in `finalize`
     pub fn open(&self) -> ScalarResult {
-        self.share.open()
+        let opened = self.share.open();
+        self.fabric()
+            .record_scalar_openings(std::slice::from_ref(&opened), std::slice::from_ref(self));
+
+        opened
     }
 
     /// Open a batch of values without checking their MACs
+    ///
+    /// If the fabric defers MAC checks, the openings are recorded and checked in `finalize`
     pub fn open_batch(values: &[Self]) -> Vec<ScalarResult> {
-        MpcScalarResult::open_batch(&values.iter().map(|val| val.share.clone()).collect_vec())
+        let opened =
+            MpcScalarResult::open_batch(&values.iter().map(|val| val.share.clone()).collect_vec());
+        if let Some(val) = values.first() {
+            val.fabric().record_scalar_openings(&opened, values);
+        }
+
+        opened
     }
 
     /// Convert a flattened iterator into a batch of `AuthenticatedScalarResult`s
@@ -179,110 +200,204 @@ impl AuthenticatedScalarResult {
     }
 
     /// Check the commitment to a MAC check and that the MAC checks sum to zero
-    pub fn verify_mac_check(
+    pub fn verify_mac_check<C: CommitmentScheme<Scalar>>(
+        session_id: &SessionId,
+        result_id: ResultId,
         my_mac_share: Scalar,
         peer_mac_share: Scalar,
-        peer_mac_commitment: StarkPoint,
+        peer_mac_commitment: C::Commitment,
         peer_commitment_blinder: Scalar,
     ) -> bool {
-        let their_comm = PedersenCommitment {
-            value: peer_mac_share,
-            blinder: peer_commitment_blinder,
-            commitment: peer_mac_commitment,
-        };
-
         // Verify that the commitment to the MAC check opens correctly
-        if !their_comm.verify() {
+        if !C::verify(
+            session_id,
+            result_id,
+            &peer_mac_share,
+            &peer_commitment_blinder,
+            &peer_mac_commitment,
+        ) {
             return false;
         }
 
         // Sum of the commitments should be zero
-        if peer_mac_share + my_mac_share != Scalar::from(0) {
+        if !(peer_mac_share + my_mac_share).ct_eq(&Scalar::zero()) {
             return false;
         }
 
         true
     }
 
-    /// Open the value and check its MAC
-    ///
-    /// This follows the protocol detailed in:
-    ///     https://securecomputation.org/docs/pragmaticmpc.pdf
-    /// Section 6.6.2
-    pub fn open_authenticated(&self) -> AuthenticatedScalarOpenResult {
-        // Both parties open the underlying value
-        let recovered_value = self.share.open();
+    /// Check the commitment to a MAC check and that the MAC checks sum to zero, encoding
+    /// the outcome as checked by the given party
+    fn mac_check_outcome<C: CommitmentScheme<Scalar>>(
+        party_id: PartyId,
+        session_id: &SessionId,
+        result_id: ResultId,
+        my_mac_share: Scalar,
+        peer_mac_share: Scalar,
+        peer_mac_commitment: C::Commitment,
+        peer_commitment_blinder: Scalar,
+    ) -> Scalar {
+        let commitment_valid = C::verify(
+            session_id,
+            result_id,
+            &peer_mac_share,
+            &peer_commitment_blinder,
+            &peer_mac_commitment,
+        );
+        let shares_valid = (peer_mac_share + my_mac_share).ct_eq(&Scalar::zero());
 
-        // Add a gate to compute the MAC check value: `key_share * opened_value - mac_share`
-        let mac_check_value: ScalarResult = self.fabric().new_gate_op(
-            vec![
-                self.fabric().borrow_mac_key().id(),
-                recovered_value.id,
-                self.public_modifier.id,
-                self.mac.id(),
-            ],
-            move |mut args| {
-                let mac_key_share: Scalar = args.remove(0).into();
-                let value: Scalar = args.remove(0).into();
-                let modifier: Scalar = args.remove(0).into();
-                let mac_share: Scalar = args.remove(0).into();
+        encode_mac_check(party_id, commitment_valid, shares_valid)
+    }
 
-                ResultValue::Scalar(mac_key_share * (value + modifier) - mac_share)
-            },
-        );
+    /// Verify the local share of a MAC check value against the peer's share
+    ///
+    /// The parties commit to their shares of the MAC check value before opening them, and
+    /// the check passes if the shares sum to zero. Returns a result that resolves to one if
+    /// the check passes, see `decode_mac_check` for the outcomes of a failed check
+    pub(crate) fn check_mac_shares<C: CommitmentScheme<Scalar>>(
+        mac_check_value: ScalarResult,
+    ) -> ScalarResult {
+        let fabric = mac_check_value.fabric().clone();
+        let session_id = fabric.session_id();
+        let party_id = fabric.party_id();
 
         // Compute a commitment to this value and share it with the peer
-        let my_comm = PedersenCommitmentResult::commit(mac_check_value);
-        let peer_commit = self.fabric().exchange_value(my_comm.commitment);
+        let my_comm = CommitmentResult::<Scalar, C>::commit(mac_check_value);
+        let peer_commit = fabric.exchange_commitment(my_comm.commitment);
 
         // Once the parties have exchanged their commitments, they can open them, they have already exchanged
         // the underlying values and their commitments so all that is left is the blinder
-        let peer_mac_check = self.fabric().exchange_value(my_comm.value.clone());
+        let peer_mac_check = fabric.exchange_value(my_comm.value.clone());
 
-        let blinder_result: ScalarResult = self.fabric().allocate_scalar(my_comm.blinder);
-        let peer_blinder = self.fabric().exchange_value(blinder_result);
+        let blinder_result: ScalarResult = fabric.allocate_scalar(my_comm.blinder);
+        let peer_blinder = fabric.exchange_value(blinder_result);
 
         // Check the commitment and the MAC result
-        let commitment_check: ScalarResult = self.fabric().new_gate_op(
+        let result_id = my_comm.value.id;
+        let check: ScalarResult = fabric.new_gate_op(
             vec![
                 my_comm.value.id,
                 peer_mac_check.id,
                 peer_blinder.id,
                 peer_commit.id,
             ],
-            |mut args| {
+            move |mut args| {
                 let my_comm_value: Scalar = args.remove(0).into();
                 let peer_value: Scalar = args.remove(0).into();
                 let blinder: Scalar = args.remove(0).into();
-                let commitment: StarkPoint = args.remove(0).into();
+                let commitment: C::Commitment = args.remove(0).into();
 
                 // Build a commitment from the gate inputs
-                ResultValue::Scalar(Scalar::from(Self::verify_mac_check(
+                ResultValue::Scalar(Self::mac_check_outcome::<C>(
+                    party_id,
+                    &session_id,
+                    result_id,
                     my_comm_value,
                     peer_value,
                     commitment,
                     blinder,
-                )))
+                ))
+            },
+        );
+        fabric.record_transcript(TranscriptEntryKind::MacCheck, &[check.id()]);
+
+        check
+    }
+
+    /// Open a batch of values without checking their MACs
+    ///
+    /// Used in place of the MAC check when the fabric is in semi-honest mode, the MAC check
+    /// of each opening resolves to one
+    fn open_unchecked(values: &[Self]) -> Vec<AuthenticatedScalarOpenResult> {
+        let fabric = values[0].fabric();
+        MpcScalarResult::open_batch(&values.iter().map(|val| val.share.clone()).collect_vec())
+            .into_iter()
+            .map(|value| AuthenticatedScalarOpenResult {
+                value,
+                mac_check: fabric.one(),
+            })
+            .collect_vec()
+    }
+
+    /// Open the value and check its MAC
+    ///
+    /// This follows the protocol detailed in:
+    ///     https://securecomputation.org/docs/pragmaticmpc.pdf
+    /// Section 6.6.2
+    ///
+    /// The shares of the MAC check are committed to with a `HashCommitment`
+    pub fn open_authenticated(&self) -> AuthenticatedScalarOpenResult {
+        self.open_authenticated_with::<HashCommitment>()
+    }
+
+    /// Open the value and check its MAC, committing to the shares of the MAC check under
+    /// the given commitment scheme
+    pub fn open_authenticated_with<C: CommitmentScheme<Scalar>>(
+        &self,
+    ) -> AuthenticatedScalarOpenResult {
+        if self.fabric().security_mode() == SecurityMode::SemiHonest {
+            return Self::open_unchecked(std::slice::from_ref(self)).remove(0);
+        }
+
+        // Both parties open the underlying value
+        let recovered_value = self.share.open();
+
+        // Add a gate to compute the MAC check value: `key_share * opened_value - mac_share`
+        let mac_check_value: ScalarResult = self.fabric().new_gate_op(
+            vec![
+                self.fabric().borrow_mac_key().id(),
+                recovered_value.id,
+                self.public_modifier.id,
+                self.mac.id(),
+            ],
+            move |mut args| {
+                let mac_key_share: Scalar = args.remove(0).into();
+                let value: Scalar = args.remove(0).into();
+                let modifier: Scalar = args.remove(0).into();
+                let mac_share: Scalar = args.remove(0).into();
+
+                ResultValue::Scalar(mac_key_share * (value + modifier) - mac_share)
             },
         );
 
         AuthenticatedScalarOpenResult {
             value: recovered_value,
-            mac_check: commitment_check,
+            mac_check: Self::check_mac_shares::<C>(mac_check_value),
         }
     }
 
     /// Open a batch of values and check their MACs
+    ///
+    /// The shares of the MAC checks are committed to with a `HashCommitment`
     pub fn open_authenticated_batch(values: &[Self]) -> Vec<AuthenticatedScalarOpenResult> {
+        Self::open_authenticated_batch_with::<HashCommitment>(values)
+    }
+
+    /// Open a batch of values and check their MACs, committing to the shares of the MAC
+    /// checks under the given commitment scheme
+    pub fn open_authenticated_batch_with<C>(values: &[Self]) -> Vec<AuthenticatedScalarOpenResult>
+    where
+        C: CommitmentScheme<Scalar>,
+        Vec<C::Commitment>: PayloadType + Into<NetworkPayload>,
+    {
         if values.is_empty() {
             return vec![];
         }
 
+        if values[0].fabric().security_mode() == SecurityMode::SemiHonest {
+            return Self::open_unchecked(values);
+        }
+
         let n = values.len();
         let fabric = &values[0].fabric();
+        let session_id = fabric.session_id();
+        let party_id = fabric.party_id();
 
-        // Both parties open the underlying values
-        let values_open = Self::open_batch(values);
+        // Both parties open the underlying values, the MACs are checked below so the openings
+        // are not recorded for a deferred check
+        let values_open =
+            MpcScalarResult::open_batch(&values.iter().map(|val| val.share.clone()).collect_vec());
 
         // --- Mac Checks --- //
 
@@ -296,19 +411,15 @@ impl AuthenticatedScalarResult {
         }
 
         let mac_checks: Vec<ScalarResult> =
-            fabric.new_batch_gate_op(mac_check_deps, n /* output_arity */, move |mut args| {
-                let mac_key_share: Scalar = args.remove(0).into();
-                let mut check_result = Vec::with_capacity(n);
-
-                for _ in 0..n {
-                    let value: Scalar = args.remove(0).into();
-                    let modifier: Scalar = args.remove(0).into();
-                    let mac_share: Scalar = args.remove(0).into();
-
-                    check_result.push(mac_key_share * (value + modifier) - mac_share);
-                }
-
-                check_result.into_iter().map(ResultValue::Scalar).collect()
+            fabric.new_batch_gate_op(mac_check_deps, n /* output_arity */, move |args| {
+                let mac_key_share = Scalar::from(&args[0]);
+                let values = scalar_args(&args[1..]);
+
+                // Each check is over a value, its public modifier, and its MAC share
+                let checks = values.chunks_exact(3).collect_vec();
+                scalar_results(par_map(&checks, |check| {
+                    mac_key_share * (check[0] + check[1]) - check[2]
+                }))
             });
 
         // --- Commit to MAC Checks --- //
@@ -316,9 +427,9 @@ impl AuthenticatedScalarResult {
         let my_comms = mac_checks
             .iter()
             .cloned()
-            .map(PedersenCommitmentResult::commit)
+            .map(CommitmentResult::<Scalar, C>::commit)
             .collect_vec();
-        let peer_comms = fabric.exchange_values(
+        let peer_comms = fabric.exchange_commitments(
             &my_comms
                 .iter()
                 .map(|comm| comm.commitment.clone())
@@ -327,8 +438,8 @@ impl AuthenticatedScalarResult {
 
         // --- Exchange the MAC Checks and Commitment Blinders --- //
 
-        let peer_mac_checks = fabric.exchange_values(&mac_checks);
-        let peer_blinders = fabric.exchange_values(
+        let peer_mac_checks = fabric.exchange_values::<Scalar>(&mac_checks);
+        let peer_blinders = fabric.exchange_values::<Scalar>(
             &my_comms
                 .iter()
                 .map(|comm| fabric.allocate_scalar(comm.blinder))
@@ -337,7 +448,8 @@ impl AuthenticatedScalarResult {
 
         // --- Check the MAC Checks --- //
 
-        let mut mac_check_gate_deps = my_comms.iter().map(|comm| comm.value.id).collect_vec();
+        let my_comm_ids = my_comms.iter().map(|comm| comm.value.id).collect_vec();
+        let mut mac_check_gate_deps = my_comm_ids.clone();
         mac_check_gate_deps.push(peer_mac_checks.id);
         mac_check_gate_deps.push(peer_blinders.id);
         mac_check_gate_deps.push(peer_comms.id);
@@ -349,34 +461,173 @@ impl AuthenticatedScalarResult {
                 let my_comms: Vec<Scalar> = args.drain(..n).map(|comm| comm.into()).collect();
                 let peer_mac_checks: Vec<Scalar> = args.remove(0).into();
                 let peer_blinders: Vec<Scalar> = args.remove(0).into();
-                let peer_comms: Vec<StarkPoint> = args.remove(0).into();
+                let peer_comms: Vec<C::Commitment> = args.remove(0).into();
+
+                // Verify the peer's commitments and the MAC checks
+                let checks = izip!(
+                    my_comm_ids,
+                    my_comms,
+                    peer_mac_checks,
+                    peer_blinders,
+                    peer_comms
+                )
+                .collect_vec();
+                let mac_checks = par_map(&checks, |check| {
+                    let (result_id, my_mac_share, peer_mac_share, peer_blinder, peer_commitment) =
+                        check;
+                    Self::mac_check_outcome::<C>(
+                        party_id,
+                        &session_id,
+                        *result_id,
+                        *my_mac_share,
+                        *peer_mac_share,
+                        peer_commitment.clone(),
+                        *peer_blinder,
+                    )
+                });
 
-                // Build a commitment from the gate inputs
-                let mut mac_checks = Vec::with_capacity(n);
-                for (my_mac_share, peer_mac_share, peer_blinder, peer_commitment) in izip!(
-                    my_comms.into_iter(),
-                    peer_mac_checks.into_iter(),
-                    peer_blinders.into_iter(),
-                    peer_comms.into_iter()
-                ) {
-                    let mac_check = Self::verify_mac_check(
-                        my_mac_share,
-                        peer_mac_share,
-                        peer_commitment,
-                        peer_blinder,
-                    );
-                    mac_checks.push(ResultValue::Scalar(Scalar::from(mac_check)));
+                scalar_results(mac_checks)
+            },
+        );
+        fabric.record_transcript(
+            TranscriptEntryKind::MacCheck,
+            &commitment_checks
+                .iter()
+                .map(|check| check.id())
+                .collect_vec(),
+        );
+
+        // --- Return the results --- //
+
+        values_open
+            .into_iter()
+            .zip(commitment_checks.into_iter())
+            .map(|(value, check)| AuthenticatedScalarOpenResult {
+                value,
+                mac_check: check,
+            })
+            .collect_vec()
+    }
+
+    /// Open a batch of values and check their MACs, committing to the shares of all the
+    /// MAC checks with a single hash commitment
+    ///
+    /// This exchanges one commitment for the whole batch rather than one per value. If the
+    /// peer's commitment does not open correctly, the MAC check of every value in the batch fails
+    pub fn open_authenticated_batch_single_commitment(
+        values: &[Self],
+    ) -> Vec<AuthenticatedScalarOpenResult> {
+        if values.is_empty() {
+            return Vec::new();
+        }
+
+        if values[0].fabric().security_mode() == SecurityMode::SemiHonest {
+            return Self::open_unchecked(values);
+        }
+
+        let n = values.len();
+        let fabric = values[0].fabric();
+        let session_id = fabric.session_id();
+        let party_id = fabric.party_id();
+
+        // Open the values, the MACs are checked below so the openings are not recorded for a
+        // deferred check
+        let values_open =
+            MpcScalarResult::open_batch(&values.iter().map(|v| v.share.clone()).collect_vec());
+
+        // --- MAC Check --- //
+
+        // Compute the shares of the MAC check as a single batch result
+        let mut mac_check_deps = Vec::with_capacity(1 + 3 * n);
+        mac_check_deps.push(fabric.borrow_mac_key().id());
+        for i in 0..n {
+            mac_check_deps.push(values_open[i].id());
+            mac_check_deps.push(values[i].public_modifier.id());
+            mac_check_deps.push(values[i].mac.id());
+        }
+
+        let mac_checks: ResultHandle<Vec<Scalar>> =
+            fabric.new_gate_op(mac_check_deps, move |mut args| {
+                let mac_key_share: Scalar = args.remove(0).into();
+                let mut check_result = Vec::with_capacity(n);
+
+                for _ in 0..n {
+                    let value: Scalar = args.remove(0).into();
+                    let modifier: Scalar = args.remove(0).into();
+                    let mac_share: Scalar = args.remove(0).into();
+
+                    check_result.push(mac_key_share * (value + modifier) - mac_share);
                 }
 
-                mac_checks
+                ResultValue::ScalarBatch(check_result)
+            });
+
+        // --- Commit to the MAC Checks --- //
+
+        let my_comm = CommitmentResult::<Vec<Scalar>, HashCommitment>::commit(mac_checks);
+        let peer_comm = fabric.exchange_commitment(my_comm.commitment.clone());
+
+        // Only reveal the MAC checks once the peer is bound by its commitment
+        let my_mac_checks: ResultHandle<Vec<Scalar>> = fabric
+            .new_gate_op(vec![my_comm.value.id(), peer_comm.id()], |mut args| {
+                args.remove(0)
+            });
+        let peer_mac_checks = fabric.exchange_value(my_mac_checks);
+        let blinder: ScalarResult = fabric.allocate_scalar(my_comm.blinder);
+        let peer_blinder = fabric.exchange_value(blinder);
+
+        // --- Check the MAC Checks --- //
+
+        let result_id = my_comm.value.id();
+        let commitment_checks: Vec<ScalarResult> = fabric.new_batch_gate_op(
+            vec![
+                result_id,
+                peer_mac_checks.id(),
+                peer_blinder.id(),
+                peer_comm.id(),
+            ],
+            n, /* output_arity */
+            move |mut args| {
+                let my_mac_checks: Vec<Scalar> = args.remove(0).into();
+                let peer_mac_checks: Vec<Scalar> = args.remove(0).into();
+                let peer_blinder: Scalar = args.remove(0).into();
+                let peer_comm: Scalar = args.remove(0).into();
+
+                // The peer's MAC checks must open its commitment and sum with the local MAC
+                // checks to zero
+                let comm_valid = peer_mac_checks.len() == n
+                    && <HashCommitment>::verify(
+                        &session_id,
+                        result_id,
+                        &peer_mac_checks,
+                        &peer_blinder,
+                        &peer_comm,
+                    );
+
+                (0..n)
+                    .map(|i| {
+                        let shares_valid = comm_valid && {
+                            let (mine, peer) = (my_mac_checks[i], peer_mac_checks[i]);
+                            (mine + peer).ct_eq(&Scalar::zero())
+                        };
+                        ResultValue::Scalar(encode_mac_check(party_id, comm_valid, shares_valid))
+                    })
+                    .collect()
             },
         );
+        fabric.record_transcript(
+            TranscriptEntryKind::MacCheck,
+            &commitment_checks
+                .iter()
+                .map(|check| check.id())
+                .collect_vec(),
+        );
 
         // --- Return the results --- //
 
         values_open
             .into_iter()
-            .zip(commitment_checks.into_iter())
+            .zip(commitment_checks)
             .map(|(value, check)| AuthenticatedScalarOpenResult {
                 value,
                 mac_check: check,
@@ -398,16 +649,13 @@ pub struct AuthenticatedScalarOpenResult {
 impl Future for AuthenticatedScalarOpenResult {
     type Output = Result<Scalar, MpcError>;
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        // Await both of the underlying values
-        let value = futures::ready!(self.as_mut().value.poll_unpin(cx));
-        let mac_check = futures::ready!(self.as_mut().mac_check.poll_unpin(cx));
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Await both of the underlying values, either fails if the computation of the
+        // opened value failed
+        let value = futures::ready!(self.value.poll_result(cx));
+        let mac_check = futures::ready!(self.mac_check.poll_result(cx));
 
-        if mac_check == Scalar::from(1) {
-            Poll::Ready(Ok(value))
-        } else {
-            Poll::Ready(Err(MpcError::AuthenticationError))
-        }
+        Poll::Ready(value.and_then(|value| decode_mac_check(mac_check?).map(|_| value)))
     }
 }
 
@@ -494,37 +742,12 @@ impl AuthenticatedScalarResult {
         let gate_results: Vec<ScalarResult> = fabric.new_batch_gate_op(
             all_ids,
             AUTHENTICATED_SCALAR_RESULT_LEN * n, /* output_arity */
-            move |mut args| {
-                let arg_len = args.len();
-                let a_vals = args.drain(..arg_len / 2).collect_vec();
-                let b_vals = args;
-
-                let mut result = Vec::with_capacity(AUTHENTICATED_SCALAR_RESULT_LEN * n);
-                for (mut a_vals, mut b_vals) in a_vals
-                    .into_iter()
-                    .chunks(AUTHENTICATED_SCALAR_RESULT_LEN)
-                    .into_iter()
-                    .zip(
-                        b_vals
-                            .into_iter()
-                            .chunks(AUTHENTICATED_SCALAR_RESULT_LEN)
-                            .into_iter(),
-                    )
-                {
-                    let a_share: Scalar = a_vals.next().unwrap().into();
-                    let a_mac_share: Scalar = a_vals.next().unwrap().into();
-                    let a_modifier: Scalar = a_vals.next().unwrap().into();
-
-                    let b_share: Scalar = b_vals.next().unwrap().into();
-                    let b_mac_share: Scalar = b_vals.next().unwrap().into();
-                    let b_modifier: Scalar = b_vals.next().unwrap().into();
-
-                    result.push(ResultValue::Scalar(a_share + b_share));
-                    result.push(ResultValue::Scalar(a_mac_share + b_mac_share));
-                    result.push(ResultValue::Scalar(a_modifier + b_modifier));
-                }
-
-                result
+            move |args| {
+                // The shares, MACs, and modifiers are added componentwise, so the flattened
+                // batches may be added elementwise
+                let scalars = scalar_args(&args);
+                let (a_vals, b_vals) = scalars.split_at(scalars.len() / 2);
+                scalar_results(add_scalars(a_vals, b_vals))
             },
         );
 
@@ -714,37 +937,11 @@ impl AuthenticatedScalarResult {
         let gate_results: Vec<ScalarResult> = fabric.new_batch_gate_op(
             all_ids,
             AUTHENTICATED_SCALAR_RESULT_LEN * n, /* output_arity */
-            move |mut args| {
-                let arg_len = args.len();
-                let a_vals = args.drain(..arg_len / 2).collect_vec();
-                let b_vals = args;
-
-                let mut result = Vec::with_capacity(AUTHENTICATED_SCALAR_RESULT_LEN * n);
-                for (mut a_vals, mut b_vals) in a_vals
-                    .into_iter()
-                    .chunks(AUTHENTICATED_SCALAR_RESULT_LEN)
-                    .into_iter()
-                    .zip(
-                        b_vals
-                            .into_iter()
-                            .chunks(AUTHENTICATED_SCALAR_RESULT_LEN)
-                            .into_iter(),
-                    )
-                {
-                    let a_share: Scalar = a_vals.next().unwrap().into();
-                    let a_mac_share: Scalar = a_vals.next().unwrap().into();
-                    let a_modifier: Scalar = a_vals.next().unwrap().into();
-
-                    let b_share: Scalar = b_vals.next().unwrap().into();
-                    let b_mac_share: Scalar = b_vals.next().unwrap().into();
-                    let b_modifier: Scalar = b_vals.next().unwrap().into();
-
-                    result.push(ResultValue::Scalar(a_share - b_share));
-                    result.push(ResultValue::Scalar(a_mac_share - b_mac_share));
-                    result.push(ResultValue::Scalar(a_modifier - b_modifier));
-                }
-
-                result
+            move |args| {
+                // The shares, MACs, and modifiers are subtracted componentwise, see `batch_add`
+                let scalars = scalar_args(&args);
+                let (a_vals, b_vals) = scalars.split_at(scalars.len() / 2);
+                scalar_results(sub_scalars(a_vals, b_vals))
             },
         );
 
@@ -971,29 +1168,13 @@ impl AuthenticatedScalarResult {
         let scalars = fabric.new_batch_gate_op(
             all_ids,
             AUTHENTICATED_SCALAR_RESULT_LEN * n, /* output_arity */
-            move |mut args| {
-                let a_vals = args
-                    .drain(..AUTHENTICATED_SCALAR_RESULT_LEN * n)
-                    .collect_vec();
-                let public_values = args;
-
-                let mut result = Vec::with_capacity(AUTHENTICATED_SCALAR_RESULT_LEN * n);
-                for (a_vals, public_values) in a_vals
-                    .chunks(AUTHENTICATED_SCALAR_RESULT_LEN)
-                    .zip(public_values.into_iter())
-                {
-                    let a_share: Scalar = a_vals[0].to_owned().into();
-                    let a_mac_share: Scalar = a_vals[1].to_owned().into();
-                    let a_modifier: Scalar = a_vals[2].to_owned().into();
-
-                    let public_value: Scalar = public_values.into();
-
-                    result.push(ResultValue::Scalar(a_share * public_value));
-                    result.push(ResultValue::Scalar(a_mac_share * public_value));
-                    result.push(ResultValue::Scalar(a_modifier * public_value));
-                }
-
-                result
+            move |args| {
+                let (a_vals, public_values) = args.split_at(AUTHENTICATED_SCALAR_RESULT_LEN * n);
+                scalar_results(scale_scalar_chunks(
+                    &scalar_args(a_vals),
+                    &scalar_args(public_values),
+                    AUTHENTICATED_SCALAR_RESULT_LEN,
+                ))
             },
         );
 
@@ -1036,7 +1217,7 @@ impl_commutative!(StarkPointResult, Mul, mul, *, AuthenticatedScalarResult, Outp
 
 /// Contains unsafe helpers for modifying values, methods in this module should *only* be used
 /// for testing
-#[cfg(feature = "test_helpers")]
+#[cfg(any(feature = "test_helpers", test))]
 pub mod test_helpers {
     use crate::algebra::scalar::Scalar;
 
@@ -1062,7 +1243,12 @@ pub mod test_helpers {
 mod tests {
     use rand::thread_rng;
 
-    use crate::{algebra::scalar::Scalar, test_helpers::execute_mock_mpc, PARTY0};
+    use crate::{
+        algebra::scalar::Scalar, commitment::PedersenCommitment, test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    use super::{test_helpers::modify_share, AuthenticatedScalarResult};
 
     /// Test subtraction across non-commutative types
     #[tokio::test]
@@ -1137,4 +1323,63 @@ mod tests {
 
         assert_eq!(res.unwrap(), 0.into());
     }
+
+    /// Tests opening values with the MAC checks committed to under a Pedersen commitment
+    #[tokio::test]
+    async fn test_open_with_pedersen_commitment() {
+        let mut rng = thread_rng();
+        let value = Scalar::random(&mut rng);
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared = fabric.share_scalar(value, PARTY0);
+            let mut corrupted = shared.clone();
+            modify_share(&mut corrupted, Scalar::one());
+
+            let opened = shared.open_authenticated_with::<PedersenCommitment>().await;
+            let batch = AuthenticatedScalarResult::open_authenticated_batch_with::<
+                PedersenCommitment,
+            >(&[shared, corrupted]);
+            let batch = futures::future::join_all(batch).await;
+
+            (opened, batch)
+        })
+        .await;
+
+        assert_eq!(res.0, Ok(value));
+        assert_eq!(res.1[0], Ok(value));
+        assert!(res.1[1].is_err());
+    }
+
+    /// Tests opening a batch of values under a single commitment to their MAC checks
+    #[tokio::test]
+    async fn test_open_batch_single_commitment() {
+        let mut rng = thread_rng();
+        let values = vec![Scalar::random(&mut rng), Scalar::random(&mut rng)];
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let values = values.clone();
+            async move {
+                let shared = fabric.batch_share_scalar(values, PARTY0);
+                let honest = futures::future::join_all(
+                    AuthenticatedScalarResult::open_authenticated_batch_single_commitment(&shared),
+                )
+                .await;
+
+                let mut corrupted = shared.clone();
+                modify_share(&mut corrupted[1], Scalar::one());
+                let corrupted = futures::future::join_all(
+                    AuthenticatedScalarResult::open_authenticated_batch_single_commitment(
+                        &corrupted,
+                    ),
+                )
+                .await;
+
+                (honest, corrupted)
+            }
+        })
+        .await;
+
+        assert_eq!(res.0, vec![Ok(values[0]), Ok(values[1])]);
+        assert!(res.1[1].is_err());
+    }
 }
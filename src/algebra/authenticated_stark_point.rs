@@ -9,23 +9,28 @@ use std::{
     task::{Context, Poll},
 };
 
-use futures::{Future, FutureExt};
+use futures::Future;
 use itertools::{izip, Itertools};
 
 use crate::{
     algebra::stark_curve::StarkPoint,
-    commitment::{HashCommitment, HashCommitmentResult},
+    commitment::{CommitmentResult, CommitmentScheme, HashCommitment},
     error::MpcError,
-    fabric::{MpcFabric, ResultValue},
-    ResultId, PARTY0,
+    fabric::{MpcFabric, ResultValue, SecurityMode, TranscriptEntryKind},
+    network::{NetworkPayload, PartyId, PayloadType, SessionId},
+    ResultHandle, ResultId, PARTY0,
 };
 
 use super::{
     authenticated_scalar::AuthenticatedScalarResult,
+    mac_check::{decode_mac_check, encode_mac_check},
     macros::{impl_borrow_variants, impl_commutative},
     mpc_stark_point::MpcStarkPointResult,
-    scalar::{Scalar, ScalarResult},
-    stark_curve::{BatchStarkPointResult, StarkPointResult},
+    scalar::{scalar_args, Scalar, ScalarResult},
+    stark_curve::{
+        add_points, point_args, point_results, scale_point_chunks, BatchStarkPointResult,
+        StarkPointResult,
+    },
 };
 
 /// The number of underlying results in an `AuthenticatedStarkPointResult`
@@ -148,13 +153,27 @@ impl AuthenticatedStarkPointResult {
     }
 
     /// Open the value without checking the MAC
+    ///
+    /// If the fabric defers MAC checks, the opening is recorded and checked in `finalize`
     pub fn open(&self) -> StarkPointResult {
-        self.share.open()
+        let opened = self.share.open();
+        self.fabric()
+            .record_point_openings(std::slice::from_ref(&opened), std::slice::from_ref(self));
+
+        opened
     }
 
     /// Open a batch of values without checking the MAC
+    ///
+    /// If the fabric defers MAC checks, the openings are recorded and checked in `finalize`
     pub fn open_batch(values: &[Self]) -> Vec<StarkPointResult> {
-        MpcStarkPointResult::open_batch(&values.iter().map(|v| v.share.clone()).collect_vec())
+        let opened =
+            MpcStarkPointResult::open_batch(&values.iter().map(|v| v.share.clone()).collect_vec());
+        if let Some(val) = values.first() {
+            val.fabric().record_point_openings(&opened, values);
+        }
+
+        opened
     }
 
     /// Convert a flattened iterator into a batch of `AuthenticatedStarkPointResult`s
@@ -175,71 +194,59 @@ impl AuthenticatedStarkPointResult {
             .collect_vec()
     }
 
-    /// Verify the MAC check on an authenticated opening
-    fn verify_mac_check(
+    /// Verify the MAC check on an authenticated opening, encoding the outcome as checked by
+    /// the given party
+    fn mac_check_outcome<C: CommitmentScheme<StarkPoint>>(
+        party_id: PartyId,
+        session_id: &SessionId,
+        result_id: ResultId,
         my_mac_share: StarkPoint,
         peer_mac_share: StarkPoint,
-        peer_mac_commitment: Scalar,
+        peer_mac_commitment: C::Commitment,
         peer_blinder: Scalar,
-    ) -> bool {
+    ) -> Scalar {
         // Check that the MAC check value is the correct opening of the
         // given commitment
-        let peer_comm = HashCommitment {
-            value: peer_mac_share,
-            blinder: peer_blinder,
-            commitment: peer_mac_commitment,
-        };
-        if !peer_comm.verify() {
-            return false;
-        }
+        let commitment_valid = C::verify(
+            session_id,
+            result_id,
+            &peer_mac_share,
+            &peer_blinder,
+            &peer_mac_commitment,
+        );
 
         // Check that the MAC check shares add up to the additive identity in
         // the Starknet curve group
-        if my_mac_share + peer_mac_share != StarkPoint::identity() {
-            return false;
-        }
+        let shares_valid = (my_mac_share + peer_mac_share).ct_eq(&StarkPoint::identity());
 
-        true
+        encode_mac_check(party_id, commitment_valid, shares_valid)
     }
 
-    /// Open the value and check the MAC
+    /// Verify the local share of a MAC check value against the peer's share
     ///
-    /// This follows the protocol detailed in
-    ///     https://securecomputation.org/docs/pragmaticmpc.pdf
-    pub fn open_authenticated(&self) -> AuthenticatedStarkPointOpenResult {
-        // Both parties open the underlying value
-        let recovered_value = self.share.open();
-
-        // Add a gate to compute hte MAC check value: `key_share * opened_value - mac_share`
-        let mac_check: StarkPointResult = self.fabric().new_gate_op(
-            vec![
-                self.fabric().borrow_mac_key().id(),
-                recovered_value.id(),
-                self.public_modifier.id(),
-                self.mac.id(),
-            ],
-            |mut args| {
-                let mac_key_share: Scalar = args.remove(0).into();
-                let value: StarkPoint = args.remove(0).into();
-                let modifier: StarkPoint = args.remove(0).into();
-                let mac_share: StarkPoint = args.remove(0).into();
-
-                ResultValue::Point((value + modifier) * mac_key_share - mac_share)
-            },
-        );
+    /// The parties commit to their shares of the MAC check value before opening them, and
+    /// the check passes if the shares sum to the identity. Returns a result that resolves to
+    /// one if the check passes, see `decode_mac_check` for the outcomes of a failed check
+    pub(crate) fn check_mac_shares<C: CommitmentScheme<StarkPoint>>(
+        mac_check: StarkPointResult,
+    ) -> ScalarResult {
+        let fabric = mac_check.fabric().clone();
+        let session_id = fabric.session_id();
+        let party_id = fabric.party_id();
 
         // Compute a commitment to this value and share it with the peer
-        let my_comm = HashCommitmentResult::commit(mac_check.clone());
-        let peer_commit = self.fabric().exchange_value(my_comm.commitment);
+        let my_comm = CommitmentResult::<StarkPoint, C>::commit(mac_check.clone());
+        let peer_commit = fabric.exchange_commitment(my_comm.commitment);
 
         // Once the parties have exchanged their commitments, they can open the underlying MAC check value
         // as they are bound by the commitment
-        let peer_mac_check = self.fabric().exchange_value(my_comm.value.clone());
-        let blinder_result: ScalarResult = self.fabric().allocate_scalar(my_comm.blinder);
-        let peer_blinder = self.fabric().exchange_value(blinder_result);
+        let peer_mac_check = fabric.exchange_value(my_comm.value.clone());
+        let blinder_result: ScalarResult = fabric.allocate_scalar(my_comm.blinder);
+        let peer_blinder = fabric.exchange_value(blinder_result);
 
         // Check the peer's commitment and the sum of the MAC checks
-        let commitment_check: ScalarResult = self.fabric().new_gate_op(
+        let result_id = mac_check.id;
+        let check: ScalarResult = fabric.new_gate_op(
             vec![
                 mac_check.id,
                 peer_mac_check.id,
@@ -250,34 +257,118 @@ impl AuthenticatedStarkPointResult {
                 let my_mac_check: StarkPoint = args.remove(0).into();
                 let peer_mac_check: StarkPoint = args.remove(0).into();
                 let peer_blinder: Scalar = args.remove(0).into();
-                let peer_commitment: Scalar = args.remove(0).into();
+                let peer_commitment: C::Commitment = args.remove(0).into();
 
-                ResultValue::Scalar(Scalar::from(Self::verify_mac_check(
+                ResultValue::Scalar(Self::mac_check_outcome::<C>(
+                    party_id,
+                    &session_id,
+                    result_id,
                     my_mac_check,
                     peer_mac_check,
                     peer_commitment,
                     peer_blinder,
-                )))
+                ))
+            },
+        );
+        fabric.record_transcript(TranscriptEntryKind::MacCheck, &[check.id()]);
+
+        check
+    }
+
+    /// Open a batch of values without checking their MACs
+    ///
+    /// Used in place of the MAC check when the fabric is in semi-honest mode, the MAC check
+    /// of each opening resolves to one
+    fn open_unchecked(values: &[Self]) -> Vec<AuthenticatedStarkPointOpenResult> {
+        let fabric = values[0].fabric();
+        MpcStarkPointResult::open_batch(&values.iter().map(|val| val.share.clone()).collect_vec())
+            .into_iter()
+            .map(|value| AuthenticatedStarkPointOpenResult {
+                value,
+                mac_check: fabric.one(),
+            })
+            .collect_vec()
+    }
+
+    /// Open the value and check the MAC
+    ///
+    /// This follows the protocol detailed in
+    ///     https://securecomputation.org/docs/pragmaticmpc.pdf
+    ///
+    /// The shares of the MAC check are committed to with a `HashCommitment`
+    pub fn open_authenticated(&self) -> AuthenticatedStarkPointOpenResult {
+        self.open_authenticated_with::<HashCommitment>()
+    }
+
+    /// Open the value and check the MAC, committing to the shares of the MAC check under
+    /// the given commitment scheme
+    pub fn open_authenticated_with<C: CommitmentScheme<StarkPoint>>(
+        &self,
+    ) -> AuthenticatedStarkPointOpenResult {
+        if self.fabric().security_mode() == SecurityMode::SemiHonest {
+            return Self::open_unchecked(std::slice::from_ref(self)).remove(0);
+        }
+
+        // Both parties open the underlying value
+        let recovered_value = self.share.open();
+
+        // Add a gate to compute hte MAC check value: `key_share * opened_value - mac_share`
+        let mac_check: StarkPointResult = self.fabric().new_gate_op(
+            vec![
+                self.fabric().borrow_mac_key().id(),
+                recovered_value.id(),
+                self.public_modifier.id(),
+                self.mac.id(),
+            ],
+            |mut args| {
+                let mac_key_share: Scalar = args.remove(0).into();
+                let value: StarkPoint = args.remove(0).into();
+                let modifier: StarkPoint = args.remove(0).into();
+                let mac_share: StarkPoint = args.remove(0).into();
+
+                ResultValue::Point((value + modifier) * mac_key_share - mac_share)
             },
         );
 
         AuthenticatedStarkPointOpenResult {
             value: recovered_value,
-            mac_check: commitment_check,
+            mac_check: Self::check_mac_shares::<C>(mac_check),
         }
     }
 
     /// Open a batch of values and check the MACs
+    ///
+    /// The shares of the MAC checks are committed to with a `HashCommitment`
     pub fn open_authenticated_batch(values: &[Self]) -> Vec<AuthenticatedStarkPointOpenResult> {
+        Self::open_authenticated_batch_with::<HashCommitment>(values)
+    }
+
+    /// Open a batch of values and check the MACs, committing to the shares of the MAC
+    /// checks under the given commitment scheme
+    pub fn open_authenticated_batch_with<C>(
+        values: &[Self],
+    ) -> Vec<AuthenticatedStarkPointOpenResult>
+    where
+        C: CommitmentScheme<StarkPoint>,
+        Vec<C::Commitment>: PayloadType + Into<NetworkPayload>,
+    {
         if values.is_empty() {
             return Vec::new();
         }
 
+        if values[0].fabric().security_mode() == SecurityMode::SemiHonest {
+            return Self::open_unchecked(values);
+        }
+
         let n = values.len();
         let fabric = values[0].fabric();
+        let session_id = fabric.session_id();
+        let party_id = fabric.party_id();
 
-        // Open the values
-        let opened_values = Self::open_batch(values);
+        // Open the values, the MACs are checked below so the openings are not recorded for a
+        // deferred check
+        let opened_values =
+            MpcStarkPointResult::open_batch(&values.iter().map(|v| v.share.clone()).collect_vec());
 
         // --- MAC Check --- //
 
@@ -311,9 +402,9 @@ impl AuthenticatedStarkPointResult {
         let my_comms = mac_checks
             .iter()
             .cloned()
-            .map(HashCommitmentResult::commit)
+            .map(CommitmentResult::<StarkPoint, C>::commit)
             .collect_vec();
-        let peer_comms = fabric.exchange_values(
+        let peer_comms = fabric.exchange_commitments(
             &my_comms
                 .iter()
                 .map(|comm| comm.commitment.clone())
@@ -322,8 +413,8 @@ impl AuthenticatedStarkPointResult {
 
         // --- Exchange the MAC Checks and Commitment Blinders --- //
 
-        let peer_mac_checks = fabric.exchange_values(&mac_checks);
-        let peer_blinders = fabric.exchange_values(
+        let peer_mac_checks = fabric.exchange_values::<StarkPoint>(&mac_checks);
+        let peer_blinders = fabric.exchange_values::<Scalar>(
             &my_comms
                 .iter()
                 .map(|comm| fabric.allocate_scalar(comm.blinder))
@@ -332,7 +423,8 @@ impl AuthenticatedStarkPointResult {
 
         // --- Check the MAC Checks --- //
 
-        let mut mac_check_gate_deps = my_comms.iter().map(|comm| comm.value.id).collect_vec();
+        let my_comm_ids = my_comms.iter().map(|comm| comm.value.id).collect_vec();
+        let mut mac_check_gate_deps = my_comm_ids.clone();
         mac_check_gate_deps.push(peer_mac_checks.id);
         mac_check_gate_deps.push(peer_blinders.id);
         mac_check_gate_deps.push(peer_comms.id);
@@ -344,28 +436,39 @@ impl AuthenticatedStarkPointResult {
                 let my_comms: Vec<StarkPoint> = args.drain(..n).map(|comm| comm.into()).collect();
                 let peer_mac_checks: Vec<StarkPoint> = args.remove(0).into();
                 let peer_blinders: Vec<Scalar> = args.remove(0).into();
-                let peer_comms: Vec<Scalar> = args.remove(0).into();
+                let peer_comms: Vec<C::Commitment> = args.remove(0).into();
 
                 // Build a commitment from the gate inputs
                 let mut mac_checks = Vec::with_capacity(n);
-                for (my_mac_share, peer_mac_share, peer_blinder, peer_commitment) in izip!(
+                for (result_id, my_mac_share, peer_mac_share, peer_blinder, peer_commitment) in izip!(
+                    my_comm_ids,
                     my_comms.into_iter(),
                     peer_mac_checks.into_iter(),
                     peer_blinders.into_iter(),
                     peer_comms.into_iter()
                 ) {
-                    let mac_check = Self::verify_mac_check(
+                    let mac_check = Self::mac_check_outcome::<C>(
+                        party_id,
+                        &session_id,
+                        result_id,
                         my_mac_share,
                         peer_mac_share,
                         peer_commitment,
                         peer_blinder,
                     );
-                    mac_checks.push(ResultValue::Scalar(Scalar::from(mac_check)));
+                    mac_checks.push(ResultValue::Scalar(mac_check));
                 }
 
                 mac_checks
             },
         );
+        fabric.record_transcript(
+            TranscriptEntryKind::MacCheck,
+            &commitment_checks
+                .iter()
+                .map(|check| check.id())
+                .collect_vec(),
+        );
 
         // --- Return the results --- //
 
@@ -378,6 +481,132 @@ impl AuthenticatedStarkPointResult {
             })
             .collect_vec()
     }
+
+    /// Open a batch of values and check their MACs, committing to the shares of all the
+    /// MAC checks with a single hash commitment
+    ///
+    /// This exchanges one commitment for the whole batch rather than one per value. If the
+    /// peer's commitment does not open correctly, the MAC check of every value in the batch fails
+    pub fn open_authenticated_batch_single_commitment(
+        values: &[Self],
+    ) -> Vec<AuthenticatedStarkPointOpenResult> {
+        if values.is_empty() {
+            return Vec::new();
+        }
+
+        if values[0].fabric().security_mode() == SecurityMode::SemiHonest {
+            return Self::open_unchecked(values);
+        }
+
+        let n = values.len();
+        let fabric = values[0].fabric();
+        let session_id = fabric.session_id();
+        let party_id = fabric.party_id();
+
+        // Open the values, the MACs are checked below so the openings are not recorded for a
+        // deferred check
+        let opened_values =
+            MpcStarkPointResult::open_batch(&values.iter().map(|v| v.share.clone()).collect_vec());
+
+        // --- MAC Check --- //
+
+        // Compute the shares of the MAC check as a single batch result
+        let mut mac_check_deps = Vec::with_capacity(1 + 3 * n);
+        mac_check_deps.push(fabric.borrow_mac_key().id());
+        for i in 0..n {
+            mac_check_deps.push(opened_values[i].id());
+            mac_check_deps.push(values[i].public_modifier.id());
+            mac_check_deps.push(values[i].mac.id());
+        }
+
+        let mac_checks: ResultHandle<Vec<StarkPoint>> =
+            fabric.new_gate_op(mac_check_deps, move |mut args| {
+                let mac_key_share: Scalar = args.remove(0).into();
+                let mut check_result = Vec::with_capacity(n);
+
+                for _ in 0..n {
+                    let value: StarkPoint = args.remove(0).into();
+                    let modifier: StarkPoint = args.remove(0).into();
+                    let mac_share: StarkPoint = args.remove(0).into();
+
+                    check_result.push(mac_key_share * (value + modifier) - mac_share);
+                }
+
+                ResultValue::PointBatch(check_result)
+            });
+
+        // --- Commit to the MAC Checks --- //
+
+        let my_comm = CommitmentResult::<Vec<StarkPoint>, HashCommitment>::commit(mac_checks);
+        let peer_comm = fabric.exchange_commitment(my_comm.commitment.clone());
+
+        // Only reveal the MAC checks once the peer is bound by its commitment
+        let my_mac_checks: ResultHandle<Vec<StarkPoint>> = fabric
+            .new_gate_op(vec![my_comm.value.id(), peer_comm.id()], |mut args| {
+                args.remove(0)
+            });
+        let peer_mac_checks = fabric.exchange_value(my_mac_checks);
+        let blinder: ScalarResult = fabric.allocate_scalar(my_comm.blinder);
+        let peer_blinder = fabric.exchange_value(blinder);
+
+        // --- Check the MAC Checks --- //
+
+        let result_id = my_comm.value.id();
+        let commitment_checks: Vec<ScalarResult> = fabric.new_batch_gate_op(
+            vec![
+                result_id,
+                peer_mac_checks.id(),
+                peer_blinder.id(),
+                peer_comm.id(),
+            ],
+            n, /* output_arity */
+            move |mut args| {
+                let my_mac_checks: Vec<StarkPoint> = args.remove(0).into();
+                let peer_mac_checks: Vec<StarkPoint> = args.remove(0).into();
+                let peer_blinder: Scalar = args.remove(0).into();
+                let peer_comm: Scalar = args.remove(0).into();
+
+                // The peer's MAC checks must open its commitment and sum with the local MAC
+                // checks to the identity
+                let comm_valid = peer_mac_checks.len() == n
+                    && <HashCommitment>::verify(
+                        &session_id,
+                        result_id,
+                        &peer_mac_checks,
+                        &peer_blinder,
+                        &peer_comm,
+                    );
+
+                (0..n)
+                    .map(|i| {
+                        let shares_valid = comm_valid && {
+                            let (mine, peer) = (my_mac_checks[i], peer_mac_checks[i]);
+                            (mine + peer).ct_eq(&StarkPoint::identity())
+                        };
+                        ResultValue::Scalar(encode_mac_check(party_id, comm_valid, shares_valid))
+                    })
+                    .collect()
+            },
+        );
+        fabric.record_transcript(
+            TranscriptEntryKind::MacCheck,
+            &commitment_checks
+                .iter()
+                .map(|check| check.id())
+                .collect_vec(),
+        );
+
+        // --- Return the results --- //
+
+        opened_values
+            .into_iter()
+            .zip(commitment_checks)
+            .map(|(value, check)| AuthenticatedStarkPointOpenResult {
+                value,
+                mac_check: check,
+            })
+            .collect_vec()
+    }
 }
 
 /// The value that results from opening an `AuthenticatedStarkPointResult` and checking its MAC. This encapsulates
@@ -402,16 +631,13 @@ impl Debug for AuthenticatedStarkPointOpenResult {
 impl Future for AuthenticatedStarkPointOpenResult {
     type Output = Result<StarkPoint, MpcError>;
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        // Await both of the underlying values
-        let value = futures::ready!(self.as_mut().value.poll_unpin(cx));
-        let mac_check = futures::ready!(self.as_mut().mac_check.poll_unpin(cx));
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Await both of the underlying values, either fails if the computation of the
+        // opened value failed
+        let value = futures::ready!(self.value.poll_result(cx));
+        let mac_check = futures::ready!(self.mac_check.poll_result(cx));
 
-        if mac_check == Scalar::from(1) {
-            Poll::Ready(Ok(value))
-        } else {
-            Poll::Ready(Err(MpcError::AuthenticationError))
-        }
+        Poll::Ready(value.and_then(|value| decode_mac_check(mac_check?).map(|_| value)))
     }
 }
 
@@ -516,30 +742,12 @@ impl AuthenticatedStarkPointResult {
         let res: Vec<StarkPointResult> = fabric.new_batch_gate_op(
             all_ids,
             AUTHENTICATED_STARK_POINT_RESULT_LEN * n,
-            move |mut args| {
-                let len = args.len();
-                let a_vals = args.drain(..len / 2).collect_vec();
-                let b_vals = args;
-
-                let mut result = Vec::with_capacity(AUTHENTICATED_STARK_POINT_RESULT_LEN * n);
-                for (a_chunk, b_chunk) in a_vals
-                    .chunks(AUTHENTICATED_STARK_POINT_RESULT_LEN)
-                    .zip(b_vals.chunks(AUTHENTICATED_STARK_POINT_RESULT_LEN))
-                {
-                    let a_share: StarkPoint = a_chunk[0].clone().into();
-                    let a_mac: StarkPoint = a_chunk[1].clone().into();
-                    let a_modifier: StarkPoint = a_chunk[2].clone().into();
-
-                    let b_share: StarkPoint = b_chunk[0].clone().into();
-                    let b_mac: StarkPoint = b_chunk[1].clone().into();
-                    let b_modifier: StarkPoint = b_chunk[2].clone().into();
-
-                    result.push(ResultValue::Point(a_share + b_share));
-                    result.push(ResultValue::Point(a_mac + b_mac));
-                    result.push(ResultValue::Point(a_modifier + b_modifier));
-                }
-
-                result
+            move |args| {
+                // The shares, MACs, and modifiers are added componentwise, so the flattened
+                // batches may be added elementwise
+                let points = point_args(&args);
+                let (a_vals, b_vals) = points.split_at(points.len() / 2);
+                point_results(add_points(a_vals, b_vals))
             },
         );
 
@@ -671,7 +879,7 @@ impl Sub<&AuthenticatedStarkPointResult> for &AuthenticatedStarkPointResult {
         AuthenticatedStarkPointResult {
             share: new_share,
             mac: new_mac,
-            public_modifier: self.public_modifier.clone(),
+            public_modifier: &self.public_modifier - &other.public_modifier,
         }
     }
 }
@@ -800,7 +1008,7 @@ impl Neg for &AuthenticatedStarkPointResult {
         AuthenticatedStarkPointResult {
             share: new_share,
             mac: new_mac,
-            public_modifier: self.public_modifier.clone(),
+            public_modifier: -&self.public_modifier,
         }
     }
 }
@@ -959,25 +1167,14 @@ impl AuthenticatedStarkPointResult {
         let results: Vec<StarkPointResult> = fabric.new_batch_gate_op(
             all_ids,
             AUTHENTICATED_STARK_POINT_RESULT_LEN * n, /* output_arity */
-            move |mut args| {
-                let scalars: Vec<Scalar> = args.drain(..n).map(Scalar::from).collect_vec();
-                let points: Vec<StarkPoint> = args.into_iter().map(StarkPoint::from).collect_vec();
-
-                let mut result = Vec::with_capacity(AUTHENTICATED_STARK_POINT_RESULT_LEN * n);
-                for (scalar, points) in scalars
-                    .into_iter()
-                    .zip(points.chunks(AUTHENTICATED_STARK_POINT_RESULT_LEN))
-                {
-                    let share: StarkPoint = points[0];
-                    let mac: StarkPoint = points[1];
-                    let modifier: StarkPoint = points[2];
-
-                    result.push(ResultValue::Point(share * scalar));
-                    result.push(ResultValue::Point(mac * scalar));
-                    result.push(ResultValue::Point(modifier * scalar));
-                }
-
-                result
+            move |args| {
+                let scalars = scalar_args(&args[..n]);
+                let points = point_args(&args[n..]);
+                point_results(scale_point_chunks(
+                    &points,
+                    &scalars,
+                    AUTHENTICATED_STARK_POINT_RESULT_LEN,
+                ))
             },
         );
 
@@ -995,21 +1192,13 @@ impl AuthenticatedStarkPointResult {
         let n = a.len();
         let fabric = a[0].fabric();
         let all_ids = a.iter().flat_map(|v| v.ids()).collect_vec();
+        let generator = fabric.generator_table();
 
         // Multiply the shares in a batch gate
         let results = fabric.new_batch_gate_op(
             all_ids,
             AUTHENTICATED_STARK_POINT_RESULT_LEN * n, /* output_arity */
-            move |args| {
-                let scalars = args.into_iter().map(Scalar::from).collect_vec();
-                let generator = StarkPoint::generator();
-
-                scalars
-                    .into_iter()
-                    .map(|x| x * generator)
-                    .map(ResultValue::Point)
-                    .collect_vec()
-            },
+            move |args| point_results(generator.batch_mul(&scalar_args(&args))),
         );
 
         Self::from_flattened_iterator(results.into_iter())
@@ -1088,6 +1277,77 @@ impl AuthenticatedStarkPointResult {
 
         Self::msm(&scalars, &points)
     }
+
+    /// Compute a batch of independent multiscalar multiplications
+    ///
+    /// The multiplications of every MSM are evaluated together, so all the MSMs share a single
+    /// round of triple sampling and openings. An empty MSM evaluates to the identity
+    pub fn batch_msm(
+        scalars: &[Vec<AuthenticatedScalarResult>],
+        points: &[Vec<AuthenticatedStarkPointResult>],
+    ) -> Vec<AuthenticatedStarkPointResult> {
+        assert_eq!(
+            scalars.len(),
+            points.len(),
+            "batch_msm requires equal length vectors"
+        );
+
+        let lengths = scalars
+            .iter()
+            .zip(points.iter())
+            .map(|(s, p)| {
+                assert_eq!(s.len(), p.len(), "batch_msm requires equal length vectors");
+                s.len()
+            })
+            .collect_vec();
+        if scalars.is_empty() {
+            return Vec::new();
+        }
+
+        let flat_scalars = scalars.concat();
+        let flat_points = points.concat();
+        assert!(
+            !flat_scalars.is_empty(),
+            "batch_msm requires at least one non-empty msm"
+        );
+
+        let mul_out = AuthenticatedStarkPointResult::batch_mul(&flat_scalars, &flat_points);
+
+        // Create a gate to sum the points of each msm
+        let m = lengths.len();
+        let fabric = flat_scalars[0].fabric();
+        let all_ids = mul_out.iter().flat_map(|p| p.ids()).collect_vec();
+
+        let results = fabric.new_batch_gate_op(
+            all_ids,
+            AUTHENTICATED_STARK_POINT_RESULT_LEN * m, /* output_arity */
+            move |args| {
+                let mut args = args.into_iter().map(StarkPoint::from);
+                let mut result = Vec::with_capacity(AUTHENTICATED_STARK_POINT_RESULT_LEN * m);
+
+                for len in lengths {
+                    // Accumulators
+                    let mut share = StarkPoint::identity();
+                    let mut mac = StarkPoint::identity();
+                    let mut modifier = StarkPoint::identity();
+
+                    for _ in 0..len {
+                        share += args.next().unwrap();
+                        mac += args.next().unwrap();
+                        modifier += args.next().unwrap();
+                    }
+
+                    result.push(ResultValue::Point(share));
+                    result.push(ResultValue::Point(mac));
+                    result.push(ResultValue::Point(modifier));
+                }
+
+                result
+            },
+        );
+
+        Self::from_flattened_iterator(results.into_iter())
+    }
 }
 
 // ----------------
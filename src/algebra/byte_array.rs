@@ -0,0 +1,90 @@
+//! Defines the serde encoding of the fixed length byte strings that scalars and points are
+//! serialized as
+//!
+//! The bytes are encoded as a sequence, exactly as a `Vec<u8>` is, but are serialized from and
+//! deserialized into an array on the stack, so that encoding a batch of values does not
+//! allocate for each value
+
+use std::fmt::{Formatter, Result as FmtResult};
+
+use serde::{
+    de::{Error as DeError, SeqAccess, Visitor},
+    Deserializer, Serializer,
+};
+
+/// Serialize a byte string as a sequence of bytes
+pub(crate) fn serialize_byte_array<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(bytes)
+}
+
+/// Deserialize a sequence of exactly `N` bytes
+pub(crate) fn deserialize_byte_array<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    deserializer.deserialize_seq(ByteArrayVisitor::<N>)
+}
+
+/// Visits a sequence of exactly `N` bytes
+struct ByteArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ByteArrayVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "a sequence of {N} bytes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| DeError::invalid_length(i, &self))?;
+        }
+
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(DeError::invalid_length(N + 1, &self));
+        }
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{deserialize_byte_array, serialize_byte_array};
+
+    /// A byte string encoded through the array helpers
+    #[derive(Debug, PartialEq)]
+    struct Array([u8; 4]);
+
+    impl Serialize for Array {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_byte_array(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Array {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_byte_array(deserializer).map(Array)
+        }
+    }
+
+    /// Tests that arrays are encoded as byte vectors are, and that only sequences of the
+    /// array's length are accepted
+    #[test]
+    fn test_byte_array_encoding() {
+        let array = Array([1, 2, 3, 4]);
+        let encoded = serde_json::to_string(&array).unwrap();
+        assert_eq!(encoded, serde_json::to_string(&vec![1u8, 2, 3, 4]).unwrap());
+        assert_eq!(serde_json::from_str::<Array>(&encoded).unwrap(), array);
+
+        assert!(serde_json::from_str::<Array>("[1,2,3]").is_err());
+        assert!(serde_json::from_str::<Array>("[1,2,3,4,5]").is_err());
+    }
+}
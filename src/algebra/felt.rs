@@ -0,0 +1,175 @@
+//! Conversions between the crate's field and curve types and the `FieldElement` (felt252) type
+//! of starknet-rs, so that opened values may be used directly in Starknet transactions
+//!
+//! Both crates encode a field element as 32 big endian bytes, and the conversions go through
+//! this encoding. The Stark curve's scalar field is smaller than its base field, which is the
+//! felt252 field, so every `Scalar` is a valid felt but a felt is a valid `Scalar` only if it
+//! is less than the curve's order
+
+use std::{error::Error, fmt::Display};
+
+use ark_ec::short_weierstrass::Affine;
+use ark_ff::{BigInteger, PrimeField};
+use starknet_ff::FieldElement;
+
+use super::{
+    scalar::{Scalar, StarknetBaseFelt},
+    stark_curve::StarkPoint,
+};
+
+/// An error converting a felt into one of the crate's types
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FeltConversionError {
+    /// The felt is not less than the order of the Stark curve's scalar field
+    ScalarOutOfRange,
+    /// The point is the identity, which has no affine coordinates
+    PointAtInfinity,
+    /// The coordinates are not those of a point on the Stark curve
+    NotOnCurve,
+}
+
+impl Display for FeltConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl Error for FeltConversionError {}
+
+/// Convert an element of the curve's base field to a felt
+fn base_field_to_felt(val: &StarknetBaseFelt) -> FieldElement {
+    let bytes: [u8; 32] = val
+        .into_bigint()
+        .to_bytes_be()
+        .try_into()
+        .expect("base field elements encode to 32 bytes");
+    FieldElement::from_bytes_be(&bytes).expect("the base field is the felt field")
+}
+
+/// Convert a felt to an element of the curve's base field
+fn felt_to_base_field(felt: &FieldElement) -> StarknetBaseFelt {
+    StarknetBaseFelt::from_be_bytes_mod_order(&felt.to_bytes_be())
+}
+
+impl From<Scalar> for FieldElement {
+    fn from(scalar: Scalar) -> Self {
+        let bytes: [u8; 32] = scalar
+            .to_bytes_be()
+            .try_into()
+            .expect("scalars encode to 32 bytes");
+        FieldElement::from_bytes_be(&bytes).expect("the scalar field is smaller than a felt")
+    }
+}
+
+impl Scalar {
+    /// Convert a felt to a scalar, failing if the felt is not less than the curve's order
+    ///
+    /// This is not a `TryFrom` implementation, as that conflicts with core's blanket
+    /// `TryFrom` for `Into` types: `Scalar` implements `From<T>` for any `T` convertible into
+    /// the underlying field element, which starknet-ff may implement for `FieldElement`
+    pub fn from_felt(felt: &FieldElement) -> Result<Scalar, FeltConversionError> {
+        // A felt at least the scalar field's order is reduced, and then encodes differently
+        let bytes = felt.to_bytes_be();
+        let scalar = Scalar::from_be_bytes_mod_order(&bytes);
+        if scalar.to_bytes_be() != bytes {
+            return Err(FeltConversionError::ScalarOutOfRange);
+        }
+
+        Ok(scalar)
+    }
+}
+
+/// Converts a point to its affine `(x, y)` coordinates
+impl TryFrom<StarkPoint> for (FieldElement, FieldElement) {
+    type Error = FeltConversionError;
+
+    fn try_from(point: StarkPoint) -> Result<Self, Self::Error> {
+        if point.is_identity() {
+            return Err(FeltConversionError::PointAtInfinity);
+        }
+
+        let affine = point.to_affine();
+        Ok((base_field_to_felt(&affine.x), base_field_to_felt(&affine.y)))
+    }
+}
+
+/// Converts affine `(x, y)` coordinates to a point
+impl TryFrom<(FieldElement, FieldElement)> for StarkPoint {
+    type Error = FeltConversionError;
+
+    fn try_from((x, y): (FieldElement, FieldElement)) -> Result<Self, Self::Error> {
+        // The Stark curve has cofactor one, so every point on the curve is in the group
+        let affine = Affine::new_unchecked(felt_to_base_field(&x), felt_to_base_field(&y));
+        if !affine.is_on_curve() {
+            return Err(FeltConversionError::NotOnCurve);
+        }
+
+        Ok(StarkPoint(affine.into()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::thread_rng;
+    use starknet_ff::FieldElement;
+
+    use crate::algebra::{
+        scalar::Scalar,
+        stark_curve::StarkPoint,
+        test_helper::{arkworks_point_to_starknet, prime_field_to_starknet_felt, random_point},
+    };
+
+    use super::FeltConversionError;
+
+    /// Tests converting scalars to and from felts
+    #[test]
+    fn test_scalar_conversion() {
+        let mut rng = thread_rng();
+        let scalar = Scalar::random(&mut rng);
+
+        let felt = FieldElement::from(scalar);
+        assert_eq!(felt, prime_field_to_starknet_felt(&scalar.inner()));
+        assert_eq!(felt.to_bytes_be().to_vec(), scalar.to_bytes_be());
+        assert_eq!(Scalar::from_felt(&felt), Ok(scalar));
+
+        // The extremes of the scalar field round trip
+        for scalar in [Scalar::zero(), -Scalar::one()] {
+            assert_eq!(Scalar::from_felt(&FieldElement::from(scalar)), Ok(scalar));
+        }
+    }
+
+    /// Tests that a felt at least the scalar field's order does not convert to a scalar
+    #[test]
+    fn test_scalar_out_of_range() {
+        let max_scalar = FieldElement::from(-Scalar::one());
+        let order = max_scalar + FieldElement::ONE;
+
+        assert_eq!(
+            Scalar::from_felt(&order),
+            Err(FeltConversionError::ScalarOutOfRange)
+        );
+        assert_eq!(
+            Scalar::from_felt(&(FieldElement::ZERO - FieldElement::ONE)),
+            Err(FeltConversionError::ScalarOutOfRange)
+        );
+    }
+
+    /// Tests converting points to and from affine felt coordinates
+    #[test]
+    fn test_point_conversion() {
+        let point = random_point();
+        let (x, y) = <(FieldElement, FieldElement)>::try_from(point).unwrap();
+
+        let expected = arkworks_point_to_starknet(&point);
+        assert_eq!((x, y), (expected.x, expected.y));
+        assert_eq!(StarkPoint::try_from((x, y)), Ok(point));
+
+        assert_eq!(
+            StarkPoint::try_from((x, y + FieldElement::ONE)),
+            Err(FeltConversionError::NotOnCurve)
+        );
+        assert_eq!(
+            <(FieldElement, FieldElement)>::try_from(StarkPoint::identity()),
+            Err(FeltConversionError::PointAtInfinity)
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Defines precomputed window tables for multiplying scalars by a fixed base point
+//!
+//! A table holds the multiples of its base for every value of every window of a scalar's
+//! bits, so that a multiplication is one table lookup and one addition per window instead of
+//! a double-and-add over the scalar's bits. Every fabric holds the table of the group
+//! generator, and tables of any other bases the user registers
+
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+use ark_ec::{scalar_mul::fixed_base::FixedBase, short_weierstrass::Affine};
+use ark_ff::PrimeField;
+use once_cell::sync::Lazy;
+
+use crate::fabric::parallel::par_map;
+
+use super::{
+    scalar::{Scalar, ScalarInner},
+    stark_curve::{StarkPoint, StarkPointInner, StarknetCurveConfig},
+};
+
+/// The number of scalar bits in each window of a table
+const WINDOW_BITS: usize = 8;
+/// The number of bits in a scalar
+const SCALAR_BITS: usize = ScalarInner::MODULUS_BIT_SIZE as usize;
+
+/// The table of the group generator, built once and shared between fabrics
+static GENERATOR_TABLE: Lazy<Arc<FixedBaseTable>> =
+    Lazy::new(|| Arc::new(FixedBaseTable::new(StarkPoint::generator())));
+
+/// The precomputed multiples of a fixed base point
+pub struct FixedBaseTable {
+    /// The base point
+    base: StarkPoint,
+    /// The multiples of the base, indexed by window and then by the value of the window
+    windows: Vec<Vec<Affine<StarknetCurveConfig>>>,
+}
+
+impl Debug for FixedBaseTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "FixedBaseTable({:?})", self.base)
+    }
+}
+
+impl FixedBaseTable {
+    /// Precompute the table of a base point
+    pub fn new(base: StarkPoint) -> Self {
+        let windows = FixedBase::get_window_table(SCALAR_BITS, WINDOW_BITS, base.0);
+        Self { base, windows }
+    }
+
+    /// The table of the group generator
+    pub fn generator() -> Arc<Self> {
+        GENERATOR_TABLE.clone()
+    }
+
+    /// The base point of the table
+    pub fn base(&self) -> StarkPoint {
+        self.base
+    }
+
+    /// Multiply the base by a scalar
+    pub fn mul(&self, scalar: &Scalar) -> StarkPoint {
+        StarkPoint(FixedBase::windowed_mul::<StarkPointInner>(
+            self.windows.len(),
+            WINDOW_BITS,
+            &self.windows,
+            &scalar.0,
+        ))
+    }
+
+    /// Multiply the base by a batch of scalars
+    pub fn batch_mul(&self, scalars: &[Scalar]) -> Vec<StarkPoint> {
+        par_map(scalars, |scalar| self.mul(scalar))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::thread_rng;
+
+    use crate::algebra::{scalar::Scalar, stark_curve::StarkPoint, test_helper::random_point};
+
+    use super::FixedBaseTable;
+
+    /// Tests that multiplying through a table matches plain scalar multiplication
+    #[test]
+    fn test_fixed_base_mul() {
+        let mut rng = thread_rng();
+        let scalars = [Scalar::zero(), Scalar::one(), -Scalar::one()]
+            .into_iter()
+            .chain((0..10).map(|_| Scalar::random(&mut rng)))
+            .collect_vec();
+
+        for base in [StarkPoint::generator(), random_point()] {
+            let table = FixedBaseTable::new(base);
+            let expected = scalars.iter().map(|s| base * s).collect_vec();
+
+            assert_eq!(table.batch_mul(&scalars), expected);
+        }
+
+        let generator = FixedBaseTable::generator();
+        assert_eq!(generator.base(), StarkPoint::generator());
+        assert_eq!(
+            generator.mul(&scalars[3]),
+            StarkPoint::generator() * scalars[3]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Defines the outcome of a MAC check, encoded in the scalar that the check resolves to
+//!
+//! The parties commit to their shares of a MAC check before revealing them, under a binding
+//! commitment scheme. A check passes if the peer's revealed share opens its commitment and
+//! the shares sum to zero. A revealed share that does not open the peer's commitment
+//! identifies the peer as the party that cheated, as it deviated from the opening protocol
+//! in a way only the peer can.
+//!
+//! This is the only case in which a culprit is identified. Shares that open their
+//! commitments but do not sum to zero cannot be attributed to either party: either may have
+//! sent an inconsistent share of the opened value or of its MAC check, and neither party
+//! can tell which from its own view, so the check fails without a culprit
+use crate::{error::MpcError, network::PartyId, PARTY0, PARTY1};
+
+use super::scalar::Scalar;
+
+/// The value a check resolves to when it passes
+const CHECK_PASSED: u64 = 1;
+/// The value a check resolves to when it fails without an identified culprit
+const CHECK_FAILED: u64 = 0;
+/// The offset at which a check that identifies a culprit encodes the culprit's party ID
+const CULPRIT_OFFSET: u64 = 2;
+
+/// Encode the outcome of a MAC check as checked by the given party
+///
+/// `commitment_valid` is whether the peer's share opened its commitment, `shares_valid`
+/// whether the shares summed to zero
+pub(crate) fn encode_mac_check(
+    party_id: PartyId,
+    commitment_valid: bool,
+    shares_valid: bool,
+) -> Scalar {
+    let outcome = match (commitment_valid, shares_valid) {
+        (false, _) => CULPRIT_OFFSET + (1 - party_id),
+        (true, true) => CHECK_PASSED,
+        (true, false) => CHECK_FAILED,
+    };
+
+    Scalar::from(outcome)
+}
+
+/// Decode the value a MAC check resolved to, returning the culprit if one was identified
+pub(crate) fn decode_mac_check(check: Scalar) -> Result<(), MpcError> {
+    if check == Scalar::from(CHECK_PASSED) {
+        return Ok(());
+    }
+
+    match [PARTY0, PARTY1]
+        .into_iter()
+        .find(|party| check == Scalar::from(CULPRIT_OFFSET + party))
+    {
+        Some(culprit) => Err(MpcError::MacCheckFailed { culprit }),
+        None => Err(MpcError::AuthenticationError),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::MpcError, PARTY0, PARTY1};
+
+    use super::{decode_mac_check, encode_mac_check};
+
+    /// Tests that a failed commitment identifies the peer of the checking party, and that
+    /// inconsistent shares identify neither party
+    #[test]
+    fn test_mac_check_outcome() {
+        let decode = |party_id, commitment_valid, shares_valid| {
+            decode_mac_check(encode_mac_check(party_id, commitment_valid, shares_valid))
+        };
+
+        assert_eq!(decode(PARTY0, true, true), Ok(()));
+        assert_eq!(
+            decode(PARTY0, true, false),
+            Err(MpcError::AuthenticationError)
+        );
+        assert_eq!(
+            decode(PARTY0, false, true),
+            Err(MpcError::MacCheckFailed { culprit: PARTY1 })
+        );
+        assert_eq!(
+            decode(PARTY1, false, false),
+            Err(MpcError::MacCheckFailed { culprit: PARTY0 })
+        );
+    }
+}
@@ -2,10 +2,18 @@
 
 pub mod authenticated_scalar;
 pub mod authenticated_stark_point;
+mod byte_array;
+#[cfg(feature = "starknet_interop")]
+pub mod felt;
+pub mod fixed_base;
+pub(crate) mod mac_check;
 pub mod macros;
 pub mod mpc_scalar;
 pub mod mpc_stark_point;
+#[cfg(test)]
+mod random_circuit;
 pub mod scalar;
+pub mod share;
 pub mod stark_curve;
 
 /// Helpers useful for testing throughout the `algebra` module
@@ -1,13 +1,19 @@
 //! Defines an unauthenticated shared scalar type which forms the basis of the
 //! authenticated scalar type
+//!
+//! These values are only secure against a semi-honest counterparty. Enable the `semi_honest`
+//! feature to share values directly as `MpcScalarResult`s via the fabric
 
-use std::ops::{Add, Mul, Neg, Sub};
+use std::{
+    iter::Sum,
+    ops::{Add, Mul, Neg, Sub},
+};
 
 use itertools::Itertools;
 
 use crate::{
     algebra::scalar::BatchScalarResult,
-    fabric::{MpcFabric, ResultHandle, ResultValue},
+    fabric::{MpcFabric, ResultHandle, ResultValue, TranscriptEntryKind},
     network::NetworkPayload,
     PARTY0,
 };
@@ -15,7 +21,7 @@ use crate::{
 use super::{
     macros::{impl_borrow_variants, impl_commutative},
     mpc_stark_point::MpcStarkPointResult,
-    scalar::{Scalar, ScalarResult},
+    scalar::{add_scalars, mul_scalars, scalar_args, scalar_results, Scalar, ScalarResult},
     stark_curve::{StarkPoint, StarkPointResult},
 };
 
@@ -73,7 +79,11 @@ impl MpcScalarResult {
         };
 
         // Create the new value by combining the additive shares
-        &val0 + &val1
+        let opened = &val0 + &val1;
+        self.fabric()
+            .record_transcript(TranscriptEntryKind::Opening, &[opened.id()]);
+
+        opened
     }
 
     /// Open a batch of values
@@ -105,23 +115,49 @@ impl MpcScalarResult {
         };
 
         // Create the new values by combining the additive shares
-        fabric.new_batch_gate_op(vec![party0_vals.id, party1_vals.id], n, move |args| {
-            let party0_vals: Vec<Scalar> = args[0].to_owned().into();
-            let party1_vals: Vec<Scalar> = args[1].to_owned().into();
+        let opened: Vec<ScalarResult> =
+            fabric.new_batch_gate_op(vec![party0_vals.id, party1_vals.id], n, move |args| {
+                let party0_vals: Vec<Scalar> = args[0].to_owned().into();
+                let party1_vals: Vec<Scalar> = args[1].to_owned().into();
 
-            let mut results = Vec::with_capacity(n);
-            for i in 0..n {
-                results.push(ResultValue::Scalar(party0_vals[i] + party1_vals[i]));
-            }
+                let mut results = Vec::with_capacity(n);
+                for i in 0..n {
+                    results.push(ResultValue::Scalar(party0_vals[i] + party1_vals[i]));
+                }
 
-            results
-        })
+                results
+            });
+        fabric.record_transcript(
+            TranscriptEntryKind::Opening,
+            &opened.iter().map(|val| val.id()).collect_vec(),
+        );
+
+        opened
     }
 
     /// Convert the underlying value to a `Scalar`
     pub fn to_scalar(&self) -> ScalarResult {
         self.share.clone()
     }
+
+    /// Create a batch of shared values from a batch network result
+    ///
+    /// The batch result combines the batch into one result, so it must be split out
+    /// first before creating the `MpcScalarResult`s
+    pub fn new_shared_from_batch_result(values: BatchScalarResult, n: usize) -> Vec<Self> {
+        let scalar_results: Vec<ScalarResult> =
+            values
+                .fabric()
+                .new_batch_gate_op(vec![values.id()], n, |mut args| {
+                    let scalars: Vec<Scalar> = args.pop().unwrap().into();
+                    scalars.into_iter().map(ResultValue::Scalar).collect()
+                });
+
+        scalar_results
+            .into_iter()
+            .map(MpcScalarResult::new_shared)
+            .collect_vec()
+    }
 }
 
 // --------------
@@ -195,6 +231,14 @@ impl Add<&MpcScalarResult> for &MpcScalarResult {
 }
 impl_borrow_variants!(MpcScalarResult, Add, add, +, MpcScalarResult);
 
+impl Sum for MpcScalarResult {
+    /// Assumes the iterator is non-empty
+    fn sum<I: Iterator<Item = Self>>(mut iter: I) -> Self {
+        let seed = iter.next().expect("Cannot sum empty iterator");
+        iter.fold(seed, |acc, val| acc + &val)
+    }
+}
+
 impl MpcScalarResult {
     /// Add two batches of `MpcScalarResult`s using a single batched gate
     pub fn batch_add(a: &[MpcScalarResult], b: &[MpcScalarResult]) -> Vec<MpcScalarResult> {
@@ -210,15 +254,11 @@ impl MpcScalarResult {
 
         let scalars = fabric.new_batch_gate_op(ids, n /* output_arity */, move |args| {
             // Split the args
-            let scalars = args.into_iter().map(Scalar::from).collect_vec();
+            let scalars = scalar_args(&args);
             let (a_res, b_res) = scalars.split_at(n);
 
             // Add the values
-            a_res
-                .iter()
-                .zip(b_res.iter())
-                .map(|(a, b)| ResultValue::Scalar(a + b))
-                .collect_vec()
+            scalar_results(add_scalars(a_res, b_res))
         });
 
         scalars.into_iter().map(|s| s.into()).collect_vec()
@@ -578,15 +618,9 @@ impl MpcScalarResult {
 
         let scalars: Vec<ScalarResult> =
             fabric.new_batch_gate_op(ids, n /* output_arity */, move |args| {
-                let mut res: Vec<ResultValue> = Vec::with_capacity(n);
-                for i in 0..n {
-                    let lhs: Scalar = args[i].to_owned().into();
-                    let rhs: Scalar = args[i + n].to_owned().into();
-
-                    res.push(ResultValue::Scalar(lhs * rhs));
-                }
-
-                res
+                let scalars = scalar_args(&args);
+                let (lhs, rhs) = scalars.split_at(n);
+                scalar_results(mul_scalars(lhs, rhs))
             });
 
         scalars.into_iter().map(|s| s.into()).collect_vec()
@@ -600,11 +634,15 @@ impl Mul<&MpcScalarResult> for &StarkPoint {
 
     fn mul(self, rhs: &MpcScalarResult) -> Self::Output {
         let self_owned = *self;
+        let table = rhs.fabric().fixed_base_table(self);
         rhs.fabric()
             .new_gate_op(vec![rhs.id()], move |mut args| {
                 let rhs: Scalar = args.remove(0).into();
 
-                ResultValue::Point(self_owned * rhs)
+                ResultValue::Point(match table {
+                    Some(table) => table.mul(&rhs),
+                    None => self_owned * rhs,
+                })
             })
             .into()
     }
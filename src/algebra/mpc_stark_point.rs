@@ -1,12 +1,18 @@
 //! Defines an unauthenticated shared curve point type which forms the basis
 //! of the authenticated curve point type
+//!
+//! These values are only secure against a semi-honest counterparty. Enable the `semi_honest`
+//! feature to share values directly as `MpcStarkPointResult`s via the fabric
 
-use std::ops::{Add, Mul, Neg, Sub};
+use std::{
+    iter::Sum,
+    ops::{Add, Mul, Neg, Sub},
+};
 
 use itertools::Itertools;
 
 use crate::{
-    fabric::{ResultHandle, ResultValue},
+    fabric::{ResultHandle, ResultValue, TranscriptEntryKind},
     network::NetworkPayload,
     MpcFabric, ResultId, PARTY0,
 };
@@ -14,8 +20,11 @@ use crate::{
 use super::{
     macros::{impl_borrow_variants, impl_commutative},
     mpc_scalar::MpcScalarResult,
-    scalar::{Scalar, ScalarResult},
-    stark_curve::{BatchStarkPointResult, StarkPoint, StarkPointResult},
+    scalar::{scalar_args, Scalar, ScalarResult},
+    stark_curve::{
+        add_points, mul_points, point_args, point_results, BatchStarkPointResult, StarkPoint,
+        StarkPointResult,
+    },
 };
 
 /// Defines a secret shared type of a curve point
@@ -67,7 +76,11 @@ impl MpcStarkPointResult {
                 (party0_value, party1_value)
             };
 
-        share0 + share1
+        let opened = share0 + share1;
+        self.fabric()
+            .record_transcript(TranscriptEntryKind::Opening, &[opened.id()]);
+
+        opened
     }
 
     /// Open a batch of values
@@ -98,7 +111,7 @@ impl MpcStarkPointResult {
             };
 
         // Create a gate to component-wise add the shares
-        fabric.new_batch_gate_op(
+        let opened: Vec<StarkPointResult> = fabric.new_batch_gate_op(
             vec![party0_values.id(), party1_values.id()],
             n, /* output_arity */
             |mut args| {
@@ -112,7 +125,32 @@ impl MpcStarkPointResult {
                     .map(ResultValue::Point)
                     .collect_vec()
             },
-        )
+        );
+        fabric.record_transcript(
+            TranscriptEntryKind::Opening,
+            &opened.iter().map(|val| val.id()).collect_vec(),
+        );
+
+        opened
+    }
+
+    /// Create a batch of shared values from a batch network result
+    ///
+    /// The batch result combines the batch into one result, so it must be split out
+    /// first before creating the `MpcStarkPointResult`s
+    pub fn new_shared_from_batch_result(values: BatchStarkPointResult, n: usize) -> Vec<Self> {
+        let point_results: Vec<StarkPointResult> =
+            values
+                .fabric()
+                .new_batch_gate_op(vec![values.id()], n, |mut args| {
+                    let points: Vec<StarkPoint> = args.pop().unwrap().into();
+                    points.into_iter().map(ResultValue::Point).collect()
+                });
+
+        point_results
+            .into_iter()
+            .map(MpcStarkPointResult::new_shared)
+            .collect_vec()
     }
 }
 
@@ -184,6 +222,14 @@ impl Add<&MpcStarkPointResult> for &MpcStarkPointResult {
 }
 impl_borrow_variants!(MpcStarkPointResult, Add, add, +, MpcStarkPointResult);
 
+impl Sum for MpcStarkPointResult {
+    /// Assumes the iterator is non-empty
+    fn sum<I: Iterator<Item = Self>>(mut iter: I) -> Self {
+        let seed = iter.next().expect("Cannot sum empty iterator");
+        iter.fold(seed, |acc, val| acc + &val)
+    }
+}
+
 impl MpcStarkPointResult {
     /// Add two batches of values
     pub fn batch_add(
@@ -202,14 +248,9 @@ impl MpcStarkPointResult {
         // Create a gate to component-wise add the shares
         fabric
             .new_batch_gate_op(all_ids, n /* output_arity */, move |args| {
-                let points = args.into_iter().map(StarkPoint::from).collect_vec();
+                let points = point_args(&args);
                 let (a, b) = points.split_at(n);
-
-                a.iter()
-                    .zip(b.iter())
-                    .map(|(x, y)| x + y)
-                    .map(ResultValue::Point)
-                    .collect_vec()
+                point_results(add_points(a, b))
             })
             .into_iter()
             .map(MpcStarkPointResult::from)
@@ -541,16 +582,10 @@ impl MpcStarkPointResult {
 
         // Multiply the shares in a batch gate
         fabric
-            .new_batch_gate_op(all_ids, n /* output_arity */, move |mut args| {
-                let scalars = args.drain(..n).map(Scalar::from).collect_vec();
-                let points = args.into_iter().map(StarkPoint::from).collect_vec();
-
-                scalars
-                    .into_iter()
-                    .zip(points.into_iter())
-                    .map(|(x, y)| x * y)
-                    .map(ResultValue::Point)
-                    .collect_vec()
+            .new_batch_gate_op(all_ids, n /* output_arity */, move |args| {
+                let scalars = scalar_args(&args[..n]);
+                let points = point_args(&args[n..]);
+                point_results(mul_points(&scalars, &points))
             })
             .into_iter()
             .map(MpcStarkPointResult::from)
@@ -566,21 +601,55 @@ impl MpcStarkPointResult {
         let n = a.len();
         let fabric = a[0].fabric();
         let all_ids = a.iter().map(|v| v.id()).collect_vec();
+        let generator = fabric.generator_table();
 
         // Multiply the shares in a batch gate
         fabric
             .new_batch_gate_op(all_ids, n /* output_arity */, move |args| {
-                let scalars = args.into_iter().map(Scalar::from).collect_vec();
-                let generator = StarkPoint::generator();
-
-                scalars
-                    .into_iter()
-                    .map(|x| x * generator)
-                    .map(ResultValue::Point)
-                    .collect_vec()
+                point_results(generator.batch_mul(&scalar_args(&args)))
             })
             .into_iter()
             .map(MpcStarkPointResult::from)
             .collect_vec()
     }
 }
+
+// === Multiscalar Multiplication === //
+
+impl MpcStarkPointResult {
+    /// Multiscalar multiplication
+    pub fn msm(scalars: &[MpcScalarResult], points: &[MpcStarkPointResult]) -> MpcStarkPointResult {
+        assert_eq!(
+            scalars.len(),
+            points.len(),
+            "multiscalar_mul requires equal length vectors"
+        );
+        assert!(
+            !scalars.is_empty(),
+            "multiscalar_mul requires non-empty vectors"
+        );
+
+        let mul_out = MpcStarkPointResult::batch_mul(scalars, points);
+
+        // Create a gate to sum the points
+        let fabric = scalars[0].fabric();
+        let all_ids = mul_out.iter().map(|p| p.id()).collect_vec();
+        let share: StarkPointResult = fabric.new_gate_op(all_ids, |args| {
+            ResultValue::Point(args.into_iter().map(StarkPoint::from).sum())
+        });
+
+        MpcStarkPointResult::new_shared(share)
+    }
+
+    /// Multiscalar multiplication on iterator types
+    pub fn msm_iter<S, P>(scalars: S, points: P) -> MpcStarkPointResult
+    where
+        S: IntoIterator<Item = MpcScalarResult>,
+        P: IntoIterator<Item = MpcStarkPointResult>,
+    {
+        let scalars = scalars.into_iter().collect_vec();
+        let points = points.into_iter().collect_vec();
+
+        Self::msm(&scalars, &points)
+    }
+}
@@ -0,0 +1,253 @@
+//! Property tests that evaluate randomly generated circuits in a mock MPC and check their
+//! outputs against a plaintext evaluation of the same circuit
+//!
+//! Circuits mix arithmetic on shared scalars and points with openings whose values are used
+//! later in the circuit, so that both the algebra and the executor's scheduling of dependent
+//! network operations are exercised
+
+use futures::future::join_all;
+use itertools::Itertools;
+use proptest::{collection::vec, prelude::*, sample::Index};
+use tokio::runtime::Runtime;
+
+use crate::{test_helpers::execute_mock_mpc, PARTY0, PARTY1};
+
+use super::{
+    authenticated_scalar::AuthenticatedScalarResult,
+    authenticated_stark_point::AuthenticatedStarkPointResult, scalar::Scalar,
+    stark_curve::StarkPoint,
+};
+
+/// The number of random circuits evaluated per test
+const N_CASES: u32 = 32;
+/// The maximum number of gates in a random circuit
+const MAX_GATES: usize = 24;
+
+/// A gate in a random circuit
+///
+/// Operands index into the scalar or point wires allocated so far, each gate allocates a new
+/// wire of its output type
+#[derive(Clone, Debug)]
+enum Gate {
+    /// The sum of two scalars
+    AddScalars(Index, Index),
+    /// The difference of two scalars
+    SubScalars(Index, Index),
+    /// The product of two scalars
+    MulScalars(Index, Index),
+    /// The negation of a scalar
+    NegScalar(Index),
+    /// The sum of a scalar and a public constant
+    AddPublicScalar(Index, Scalar),
+    /// The product of a scalar and a public constant
+    MulPublicScalar(Index, Scalar),
+    /// The sum of two points
+    AddPoints(Index, Index),
+    /// The difference of two points
+    SubPoints(Index, Index),
+    /// The product of a scalar and a point
+    MulPoint(Index, Index),
+    /// The product of a point and a public constant
+    MulPublicPoint(Index, Scalar),
+    /// Open a scalar, then multiply a scalar by the opened value
+    OpenMulScalar(Index, Index),
+    /// Open a scalar, then multiply a point by the opened value
+    OpenMulPoint(Index, Index),
+}
+
+/// A random circuit over scalar and point inputs
+#[derive(Clone, Debug)]
+struct Circuit {
+    /// The scalar inputs, input `i` is shared by party `i % 2`
+    scalar_inputs: Vec<Scalar>,
+    /// The point inputs, input `i` is shared by party `i % 2`
+    point_inputs: Vec<StarkPoint>,
+    /// The gates of the circuit, in evaluation order
+    gates: Vec<Gate>,
+}
+
+/// The party that shares the `i`th input of a kind
+fn input_owner(i: usize) -> u64 {
+    [PARTY0, PARTY1][i % 2]
+}
+
+/// The wire at the given index
+fn wire<T: Clone>(wires: &[T], i: &Index) -> T {
+    wires[i.index(wires.len())].clone()
+}
+
+// --------------
+// | Strategies |
+// --------------
+
+/// A strategy for a uniformly random scalar
+fn scalar() -> impl Strategy<Value = Scalar> {
+    any::<[u8; 32]>().prop_map(|bytes| Scalar::from_be_bytes_mod_order(&bytes))
+}
+
+/// A strategy for a random point, a random multiple of the generator
+fn point() -> impl Strategy<Value = StarkPoint> {
+    scalar().prop_map(|scalar| StarkPoint::generator() * scalar)
+}
+
+/// A strategy for a random gate
+fn gate() -> impl Strategy<Value = Gate> {
+    prop_oneof![
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::AddScalars(a, b)),
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::SubScalars(a, b)),
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::MulScalars(a, b)),
+        any::<Index>().prop_map(Gate::NegScalar),
+        (any::<Index>(), scalar()).prop_map(|(a, c)| Gate::AddPublicScalar(a, c)),
+        (any::<Index>(), scalar()).prop_map(|(a, c)| Gate::MulPublicScalar(a, c)),
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::AddPoints(a, b)),
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::SubPoints(a, b)),
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::MulPoint(a, b)),
+        (any::<Index>(), scalar()).prop_map(|(a, c)| Gate::MulPublicPoint(a, c)),
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::OpenMulScalar(a, b)),
+        (any::<Index>(), any::<Index>()).prop_map(|(a, b)| Gate::OpenMulPoint(a, b)),
+    ]
+}
+
+/// A strategy for a random circuit
+fn circuit() -> impl Strategy<Value = Circuit> {
+    (
+        vec(scalar(), 1..4),
+        vec(point(), 1..4),
+        vec(gate(), 1..MAX_GATES),
+    )
+        .prop_map(|(scalar_inputs, point_inputs, gates)| Circuit {
+            scalar_inputs,
+            point_inputs,
+            gates,
+        })
+}
+
+// --------------
+// | Evaluators |
+// --------------
+
+impl Circuit {
+    /// Evaluate the circuit in the plaintext, returning the values of all scalar and point
+    /// wires
+    fn evaluate_plaintext(&self) -> (Vec<Scalar>, Vec<StarkPoint>) {
+        let mut scalars = self.scalar_inputs.clone();
+        let mut points = self.point_inputs.clone();
+
+        for gate in self.gates.iter() {
+            match gate {
+                Gate::AddScalars(a, b) => scalars.push(wire(&scalars, a) + wire(&scalars, b)),
+                Gate::SubScalars(a, b) => scalars.push(wire(&scalars, a) - wire(&scalars, b)),
+                Gate::MulScalars(a, b) | Gate::OpenMulScalar(a, b) => {
+                    scalars.push(wire(&scalars, a) * wire(&scalars, b))
+                }
+                Gate::NegScalar(a) => scalars.push(-wire(&scalars, a)),
+                Gate::AddPublicScalar(a, c) => scalars.push(wire(&scalars, a) + *c),
+                Gate::MulPublicScalar(a, c) => scalars.push(wire(&scalars, a) * *c),
+                Gate::AddPoints(a, b) => points.push(wire(&points, a) + wire(&points, b)),
+                Gate::SubPoints(a, b) => points.push(wire(&points, a) - wire(&points, b)),
+                Gate::MulPoint(a, b) | Gate::OpenMulPoint(a, b) => {
+                    points.push(wire(&points, b) * wire(&scalars, a))
+                }
+                Gate::MulPublicPoint(a, c) => points.push(wire(&points, a) * *c),
+            }
+        }
+
+        (scalars, points)
+    }
+
+    /// Evaluate the circuit in a two party mock MPC, returning each party's opening of all
+    /// scalar and point wires
+    ///
+    /// Returns an error if any MAC check of an opening fails
+    fn evaluate_mpc(&self) -> Result<(Vec<Scalar>, Vec<StarkPoint>), String> {
+        let runtime = Runtime::new().unwrap();
+        let (party0_res, party1_res) = runtime.block_on(execute_mock_mpc(|fabric| {
+            let circuit = self.clone();
+            async move {
+                let mut scalars: Vec<AuthenticatedScalarResult> = circuit
+                    .scalar_inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| fabric.share_scalar(*value, input_owner(i)))
+                    .collect_vec();
+                let mut points: Vec<AuthenticatedStarkPointResult> = circuit
+                    .point_inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| fabric.share_point(*value, input_owner(i)))
+                    .collect_vec();
+
+                // The MAC checks of the openings made within the circuit
+                let mut mac_checks = Vec::new();
+                for gate in circuit.gates.iter() {
+                    match gate {
+                        Gate::AddScalars(a, b) => {
+                            scalars.push(wire(&scalars, a) + wire(&scalars, b))
+                        }
+                        Gate::SubScalars(a, b) => {
+                            scalars.push(wire(&scalars, a) - wire(&scalars, b))
+                        }
+                        Gate::MulScalars(a, b) => {
+                            scalars.push(wire(&scalars, a) * wire(&scalars, b))
+                        }
+                        Gate::NegScalar(a) => scalars.push(-wire(&scalars, a)),
+                        Gate::AddPublicScalar(a, c) => scalars.push(wire(&scalars, a) + *c),
+                        Gate::MulPublicScalar(a, c) => scalars.push(wire(&scalars, a) * *c),
+                        Gate::AddPoints(a, b) => points.push(wire(&points, a) + wire(&points, b)),
+                        Gate::SubPoints(a, b) => points.push(wire(&points, a) - wire(&points, b)),
+                        Gate::MulPoint(a, b) => points.push(wire(&points, b) * wire(&scalars, a)),
+                        Gate::MulPublicPoint(a, c) => points.push(wire(&points, a) * *c),
+                        Gate::OpenMulScalar(a, b) => {
+                            let opened = wire(&scalars, a).open_authenticated();
+                            mac_checks.push(opened.mac_check);
+                            scalars.push(wire(&scalars, b) * opened.value);
+                        }
+                        Gate::OpenMulPoint(a, b) => {
+                            let opened = wire(&scalars, a).open_authenticated();
+                            mac_checks.push(opened.mac_check);
+                            points.push(wire(&points, b) * opened.value);
+                        }
+                    }
+                }
+
+                let opened_scalars = AuthenticatedScalarResult::open_authenticated_batch(&scalars);
+                let opened_points =
+                    AuthenticatedStarkPointResult::open_authenticated_batch(&points);
+                if join_all(mac_checks)
+                    .await
+                    .iter()
+                    .any(|c| *c != Scalar::one())
+                {
+                    return Err("MAC check of an intermediate opening failed".to_string());
+                }
+
+                let scalars = join_all(opened_scalars).await.into_iter().collect();
+                let points = join_all(opened_points).await.into_iter().collect();
+                match (scalars, points) {
+                    (Ok(scalars), Ok(points)) => Ok((scalars, points)),
+                    (Err(err), _) | (_, Err(err)) => Err(err.to_string()),
+                }
+            }
+        }));
+
+        let party0_res = party0_res?;
+        if party0_res != party1_res? {
+            return Err("parties opened different values".to_string());
+        }
+
+        Ok(party0_res)
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(N_CASES))]
+
+    /// Tests that random circuits evaluate in the MPC to the same values as in the plaintext
+    #[test]
+    fn test_random_circuit(circuit in circuit()) {
+        let expected = circuit.evaluate_plaintext();
+        let res = circuit.evaluate_mpc();
+
+        prop_assert_eq!(res, Ok(expected));
+    }
+}
@@ -6,24 +6,33 @@
 
 use std::{
     fmt::{Display, Formatter, Result as FmtResult},
+    hint::black_box,
     iter::{Product, Sum},
     ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
-use ark_ff::{batch_inversion, Field, Fp256, MontBackend, MontConfig, PrimeField};
+use ark_ff::{
+    batch_inversion, BigInt, Field, Fp256, LegendreSymbol, MontBackend, MontConfig, PrimeField,
+};
 use itertools::Itertools;
 use num_bigint::BigUint;
 use rand::{CryptoRng, Rng, RngCore};
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as DeError, Deserialize, Serialize};
+use zeroize::Zeroize;
 
 use crate::fabric::{ResultHandle, ResultValue};
 
-use super::macros::{impl_borrow_variants, impl_commutative};
+use super::{
+    byte_array::{deserialize_byte_array, serialize_byte_array},
+    macros::{impl_borrow_variants, impl_commutative},
+};
 
 /// The number of bytes needed to represent an element of the base field
 pub const BASE_FIELD_BYTES: usize = 32;
 /// The number of bytes in a `Scalar`
 pub const SCALAR_BYTES: usize = 32;
+/// The number of bytes in a limb of a scalar's integer representation
+const BYTES_PER_LIMB: usize = 8;
 
 /// The config for finite field that the Starknet curve is defined over
 #[derive(MontConfig)]
@@ -80,6 +89,19 @@ impl Scalar {
         Scalar(inner)
     }
 
+    /// Compare two scalars in constant time
+    ///
+    /// Every limb of the two values is compared, regardless of where they first differ
+    pub fn ct_eq(&self, other: &Scalar) -> bool {
+        let (my_limbs, other_limbs) = (self.0 .0 .0, other.0 .0 .0);
+        let diff = my_limbs
+            .iter()
+            .zip(other_limbs.iter())
+            .fold(0u64, |acc, (a, b)| acc | black_box(a ^ b));
+
+        black_box(diff) == 0
+    }
+
     /// Compute the multiplicative inverse of the scalar in its field
     pub fn inverse(&self) -> Scalar {
         Scalar(self.0.inverse().unwrap())
@@ -95,6 +117,21 @@ impl Scalar {
         }
     }
 
+    /// Compute the Legendre symbol of the scalar, i.e. zero for zero, one for a non-zero
+    /// quadratic residue, and minus one for a quadratic non-residue
+    pub fn legendre(&self) -> Scalar {
+        match self.0.legendre() {
+            LegendreSymbol::Zero => Scalar::zero(),
+            LegendreSymbol::QuadraticResidue => Scalar::one(),
+            LegendreSymbol::QuadraticNonResidue => -Scalar::one(),
+        }
+    }
+
+    /// Compute a square root of the scalar, if one exists
+    pub fn sqrt(&self) -> Option<Scalar> {
+        self.0.sqrt().map(Scalar)
+    }
+
     /// Construct a scalar from the given bytes and reduce modulo the field's modulus
     pub fn from_be_bytes_mod_order(bytes: &[u8]) -> Scalar {
         let inner = ScalarInner::from_be_bytes_mod_order(bytes);
@@ -106,13 +143,36 @@ impl Scalar {
     /// Pad to the maximum amount of bytes needed so that the resulting bytes are
     /// of predictable length
     pub fn to_bytes_be(&self) -> Vec<u8> {
-        let val_biguint = self.to_biguint();
-        let mut bytes = val_biguint.to_bytes_be();
+        self.to_be_byte_array().to_vec()
+    }
+
+    /// Convert to padded big endian bytes without allocating
+    fn to_be_byte_array(self) -> [u8; SCALAR_BYTES] {
+        let mut bytes = [0u8; SCALAR_BYTES];
+        let limbs = self.0.into_bigint().0;
+        for (chunk, limb) in bytes
+            .chunks_exact_mut(BYTES_PER_LIMB)
+            .zip(limbs.iter().rev())
+        {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
 
-        let mut padding = vec![0u8; SCALAR_BYTES - bytes.len()];
-        padding.append(&mut bytes);
+        bytes
+    }
 
-        padding
+    /// Convert from padded big endian bytes, returning `None` if the bytes encode a value
+    /// not less than the modulus
+    fn from_canonical_be_byte_array(bytes: &[u8; SCALAR_BYTES]) -> Option<Scalar> {
+        let mut limbs = [0u64; SCALAR_BYTES / BYTES_PER_LIMB];
+        for (limb, chunk) in limbs
+            .iter_mut()
+            .rev()
+            .zip(bytes.chunks_exact(BYTES_PER_LIMB))
+        {
+            *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+
+        ScalarInner::from_bigint(BigInt(limbs)).map(Scalar)
     }
 
     /// Convert the underlying value to a BigUint
@@ -128,6 +188,12 @@ impl Scalar {
     }
 }
 
+impl Zeroize for Scalar {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl Display for Scalar {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.to_biguint())
@@ -136,16 +202,17 @@ impl Display for Scalar {
 
 impl Serialize for Scalar {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let bytes = self.to_bytes_be();
-        bytes.serialize(serializer)
+        serialize_byte_array(&self.to_be_byte_array(), serializer)
     }
 }
 
 impl<'de> Deserialize<'de> for Scalar {
     fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let bytes = <Vec<u8>>::deserialize(deserializer)?;
-        let scalar = Scalar::from_be_bytes_mod_order(&bytes);
-        Ok(scalar)
+        // Only the canonical encoding is accepted, i.e. the padded big endian encoding of a
+        // value less than the modulus
+        let bytes = deserialize_byte_array(deserializer)?;
+        Scalar::from_canonical_be_byte_array(&bytes)
+            .ok_or_else(|| DeError::custom("non-canonical scalar encoding"))
     }
 }
 
@@ -167,6 +234,26 @@ impl ScalarResult {
             ResultValue::Scalar(Scalar(val.0.inverse().unwrap()))
         })
     }
+
+    /// Compute the multiplicative inverses of a batch of scalars in a single gate
+    ///
+    /// The inverses are computed with Montgomery's trick, at the cost of a single field
+    /// inversion. As with `Scalar::batch_inverse`, a zero has no inverse and is left as zero
+    pub fn batch_inverse(values: &[ScalarResult]) -> Vec<ScalarResult> {
+        if values.is_empty() {
+            return Vec::new();
+        }
+
+        let n = values.len();
+        let fabric = &values[0].fabric;
+        let ids = values.iter().map(|v| v.id).collect_vec();
+        fabric.new_batch_gate_op(ids, n /* output_arity */, move |args| {
+            let mut vals = args.into_iter().map(Scalar::from).collect_vec();
+            Scalar::batch_inverse(&mut vals);
+
+            vals.into_iter().map(ResultValue::Scalar).collect_vec()
+        })
+    }
 }
 
 impl Add<&Scalar> for &Scalar {
@@ -211,18 +298,17 @@ impl ScalarResult {
     pub fn batch_add(a: &[ScalarResult], b: &[ScalarResult]) -> Vec<ScalarResult> {
         assert_eq!(a.len(), b.len(), "Batch add requires equal length inputs");
 
+        if a.is_empty() {
+            return Vec::new();
+        }
+
         let n = a.len();
         let fabric = &a[0].fabric;
         let ids = a.iter().chain(b.iter()).map(|v| v.id).collect_vec();
         fabric.new_batch_gate_op(ids, n /* output_arity */, move |args| {
-            let mut res = Vec::with_capacity(n);
-            for i in 0..n {
-                let lhs: Scalar = args[i].to_owned().into();
-                let rhs: Scalar = args[i + n].to_owned().into();
-                res.push(ResultValue::Scalar(Scalar(lhs.0 + rhs.0)));
-            }
-
-            res
+            let scalars = scalar_args(&args);
+            let (lhs, rhs) = scalars.split_at(n);
+            scalar_results(add_scalars(lhs, rhs))
         })
     }
 }
@@ -291,18 +377,17 @@ impl ScalarResult {
     pub fn batch_sub(a: &[ScalarResult], b: &[ScalarResult]) -> Vec<ScalarResult> {
         assert_eq!(a.len(), b.len(), "Batch sub requires equal length inputs");
 
+        if a.is_empty() {
+            return Vec::new();
+        }
+
         let n = a.len();
         let fabric = &a[0].fabric;
         let ids = a.iter().chain(b.iter()).map(|v| v.id).collect_vec();
         fabric.new_batch_gate_op(ids, n /* output_arity */, move |args| {
-            let mut res = Vec::with_capacity(n);
-            for i in 0..n {
-                let lhs: Scalar = args[i].to_owned().into();
-                let rhs: Scalar = args[i + n].to_owned().into();
-                res.push(ResultValue::Scalar(Scalar(lhs.0 - rhs.0)));
-            }
-
-            res
+            let scalars = scalar_args(&args);
+            let (lhs, rhs) = scalars.split_at(n);
+            scalar_results(sub_scalars(lhs, rhs))
         })
     }
 }
@@ -359,18 +444,17 @@ impl ScalarResult {
     pub fn batch_mul(a: &[ScalarResult], b: &[ScalarResult]) -> Vec<ScalarResult> {
         assert_eq!(a.len(), b.len(), "Batch mul requires equal length inputs");
 
+        if a.is_empty() {
+            return Vec::new();
+        }
+
         let n = a.len();
         let fabric = &a[0].fabric;
         let ids = a.iter().chain(b.iter()).map(|v| v.id).collect_vec();
         fabric.new_batch_gate_op(ids, n /* output_arity */, move |args| {
-            let mut res = Vec::with_capacity(n);
-            for i in 0..n {
-                let lhs: Scalar = args[i].to_owned().into();
-                let rhs: Scalar = args[i + n].to_owned().into();
-                res.push(ResultValue::Scalar(Scalar(lhs.0 * rhs.0)));
-            }
-
-            res
+            let scalars = scalar_args(&args);
+            let (lhs, rhs) = scalars.split_at(n);
+            scalar_results(mul_scalars(lhs, rhs))
         })
     }
 }
@@ -399,6 +483,10 @@ impl_borrow_variants!(ScalarResult, Neg, neg, -);
 impl ScalarResult {
     /// Negate a batch of `ScalarResult`s
     pub fn batch_neg(a: &[ScalarResult]) -> Vec<ScalarResult> {
+        if a.is_empty() {
+            return Vec::new();
+        }
+
         let n = a.len();
         let fabric = &a[0].fabric;
         let ids = a.iter().map(|v| v.id).collect_vec();
@@ -420,6 +508,53 @@ impl MulAssign for Scalar {
     }
 }
 
+// === Slice Arithmetic === //
+
+// The inner loops of the batch gates, operating on contiguous slices so that the loops are
+// free of bounds checks and conversions
+
+/// Add two equal length slices of scalars elementwise
+pub(crate) fn add_scalars(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    debug_assert_eq!(a.len(), b.len());
+    a.iter().zip(b).map(|(a, b)| Scalar(a.0 + b.0)).collect()
+}
+
+/// Subtract two equal length slices of scalars elementwise
+pub(crate) fn sub_scalars(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    debug_assert_eq!(a.len(), b.len());
+    a.iter().zip(b).map(|(a, b)| Scalar(a.0 - b.0)).collect()
+}
+
+/// Multiply two equal length slices of scalars elementwise
+pub(crate) fn mul_scalars(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    debug_assert_eq!(a.len(), b.len());
+    a.iter().zip(b).map(|(a, b)| Scalar(a.0 * b.0)).collect()
+}
+
+/// Multiply each chunk of `chunk_len` values by the corresponding scalar
+pub(crate) fn scale_scalar_chunks(
+    values: &[Scalar],
+    scalars: &[Scalar],
+    chunk_len: usize,
+) -> Vec<Scalar> {
+    debug_assert_eq!(values.len(), scalars.len() * chunk_len);
+    values
+        .chunks_exact(chunk_len)
+        .zip(scalars)
+        .flat_map(|(chunk, scalar)| chunk.iter().map(move |value| Scalar(value.0 * scalar.0)))
+        .collect()
+}
+
+/// Convert the arguments of a batch gate into scalars
+pub(crate) fn scalar_args(args: &[ResultValue]) -> Vec<Scalar> {
+    args.iter().map(Scalar::from).collect()
+}
+
+/// Convert the outputs of a slice kernel into the results of a batch gate
+pub(crate) fn scalar_results(scalars: Vec<Scalar>) -> Vec<ResultValue> {
+    scalars.into_iter().map(ResultValue::Scalar).collect()
+}
+
 // ---------------
 // | Conversions |
 // ---------------
@@ -449,10 +584,15 @@ impl Product for Scalar {
 #[cfg(test)]
 mod test {
     use crate::{
-        algebra::scalar::{Scalar, SCALAR_BYTES},
+        algebra::scalar::{scale_scalar_chunks, Scalar, ScalarInner, ScalarResult, SCALAR_BYTES},
         test_helpers::mock_fabric,
     };
+    use ark_ff::PrimeField;
+    use futures::future::join_all;
+    use itertools::Itertools;
+    use num_bigint::BigUint;
     use rand::thread_rng;
+    use zeroize::Zeroize;
 
     /// Tests serializing and deserializing a scalar
     #[test]
@@ -469,6 +609,40 @@ mod test {
         assert_eq!(scalar, scalar_deserialized);
     }
 
+    /// Tests constant time equality and zeroization of scalars
+    #[test]
+    fn test_scalar_ct_eq_zeroize() {
+        let mut rng = thread_rng();
+        let mut a = Scalar::random(&mut rng);
+        let b = a + Scalar::one();
+
+        assert!(a.ct_eq(&a));
+        assert!(!a.ct_eq(&b));
+
+        a.zeroize();
+        assert!(a.ct_eq(&Scalar::zero()));
+    }
+
+    /// Tests that deserializing a non-canonical scalar encoding fails
+    #[test]
+    fn test_scalar_deserialize_non_canonical() {
+        let one = serde_json::to_vec(&Scalar::one()).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Scalar>(&one).unwrap(),
+            Scalar::one()
+        );
+
+        // The modulus plus one reduces to one, but is not its canonical encoding
+        let modulus: BigUint = ScalarInner::MODULUS.into();
+        let bytes = (modulus + 1u8).to_bytes_be();
+        let encoded = serde_json::to_vec(&bytes).unwrap();
+        assert!(serde_json::from_slice::<Scalar>(&encoded).is_err());
+
+        // An encoding of the wrong length
+        let encoded = serde_json::to_vec(&vec![1u8]).unwrap();
+        assert!(serde_json::from_slice::<Scalar>(&encoded).is_err());
+    }
+
     /// Tests addition of raw scalars in a circuit
     #[tokio::test]
     async fn test_scalar_add() {
@@ -550,4 +724,75 @@ mod test {
         assert_eq!(res_final, expected_res);
         fabric.shutdown();
     }
+
+    /// Tests batch arithmetic on public scalars in a circuit
+    #[tokio::test]
+    async fn test_scalar_batch_arithmetic() {
+        let mut rng = thread_rng();
+        let a = (0..10).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let b = (0..10).map(|_| Scalar::random(&mut rng)).collect_vec();
+
+        let fabric = mock_fabric();
+        let a_alloc = a.iter().map(|v| fabric.allocate_scalar(*v)).collect_vec();
+        let b_alloc = b.iter().map(|v| fabric.allocate_scalar(*v)).collect_vec();
+
+        let sum = join_all(ScalarResult::batch_add(&a_alloc, &b_alloc)).await;
+        let diff = join_all(ScalarResult::batch_sub(&a_alloc, &b_alloc)).await;
+        let prod = join_all(ScalarResult::batch_mul(&a_alloc, &b_alloc)).await;
+        let neg = join_all(ScalarResult::batch_neg(&a_alloc)).await;
+
+        assert_eq!(
+            sum,
+            a.iter().zip(b.iter()).map(|(a, b)| a + b).collect_vec()
+        );
+        assert_eq!(
+            diff,
+            a.iter().zip(b.iter()).map(|(a, b)| a - b).collect_vec()
+        );
+        assert_eq!(
+            prod,
+            a.iter().zip(b.iter()).map(|(a, b)| a * b).collect_vec()
+        );
+        assert_eq!(neg, a.iter().map(|a| -a).collect_vec());
+
+        // Empty batches allocate no gates
+        assert!(ScalarResult::batch_add(&[], &[]).is_empty());
+        assert!(ScalarResult::batch_neg(&[]).is_empty());
+
+        fabric.shutdown();
+    }
+
+    /// Tests scaling chunks of a slice of scalars by a slice of scalars
+    #[test]
+    fn test_scale_scalar_chunks() {
+        let values = (1u8..=6).map(Scalar::from).collect_vec();
+        let scalars = [2u8, 3].map(Scalar::from);
+
+        let expected = [2u8, 4, 6, 12, 15, 18].map(Scalar::from).to_vec();
+        assert_eq!(scale_scalar_chunks(&values, &scalars, 3), expected);
+    }
+
+    /// Tests inverting a batch of scalars in a circuit
+    #[tokio::test]
+    async fn test_scalar_batch_inverse() {
+        let mut rng = thread_rng();
+        let values = (0..10).map(|_| Scalar::random(&mut rng)).collect_vec();
+
+        let fabric = mock_fabric();
+        let allocated = values
+            .iter()
+            .map(|v| fabric.allocate_scalar(*v))
+            .collect_vec();
+        let res = join_all(ScalarResult::batch_inverse(&allocated)).await;
+
+        let expected = values.iter().map(|v| v.inverse()).collect_vec();
+        assert_eq!(res, expected);
+
+        // A zero is left as zero without affecting the other inverses
+        let allocated = [fabric.zero(), allocated[0].clone()];
+        let res = join_all(ScalarResult::batch_inverse(&allocated)).await;
+        assert_eq!(res, vec![Scalar::zero(), expected[0]]);
+
+        fabric.shutdown();
+    }
 }
@@ -0,0 +1,300 @@
+//! Defines traits for secret sharing and opening structs of scalars and points as a unit
+//!
+//! A struct is shared by flattening its fields into one batch of scalars and one batch of
+//! points, so that sharing or opening a struct costs a single round regardless of its number
+//! of fields. The `share_struct!` macro derives both traits for a struct along with its
+//! shared counterpart
+
+use futures::future::{join_all, BoxFuture};
+use itertools::Itertools;
+
+use crate::{error::MpcError, network::PartyId, MpcFabric};
+
+use super::{
+    authenticated_scalar::AuthenticatedScalarResult,
+    authenticated_stark_point::AuthenticatedStarkPointResult, scalar::Scalar,
+    stark_curve::StarkPoint,
+};
+
+/// A plaintext struct of scalars and points that may be secret shared as a unit
+pub trait Share: Sized {
+    /// The secret shared form of the struct
+    type Shared: Open<Plaintext = Self>;
+
+    /// The scalar fields of the struct, in field order
+    fn scalars(&self) -> Vec<Scalar>;
+
+    /// The point fields of the struct, in field order
+    fn points(&self) -> Vec<StarkPoint>;
+
+    /// Rebuild the struct from its scalar and point fields, each consumed in field order
+    fn from_elements(
+        scalars: &mut dyn Iterator<Item = Scalar>,
+        points: &mut dyn Iterator<Item = StarkPoint>,
+    ) -> Self;
+
+    /// Secret share the struct, sending its values from the given party
+    ///
+    /// The scalars and points are each shared in a single batch. As with the fabric's other
+    /// sharing methods, the receiving party passes a struct of the same shape whose values
+    /// are ignored
+    fn share(&self, sender: PartyId, fabric: &MpcFabric) -> Self::Shared {
+        let scalars = self.scalars();
+        let scalars = if scalars.is_empty() {
+            Vec::new()
+        } else {
+            fabric.batch_share_scalar(scalars, sender)
+        };
+
+        let points = self.points();
+        let points = if points.is_empty() {
+            Vec::new()
+        } else {
+            fabric.batch_share_point(points, sender)
+        };
+
+        Self::Shared::from_shares(&mut scalars.into_iter(), &mut points.into_iter())
+    }
+}
+
+/// A secret shared struct of scalars and points that may be opened as a unit
+pub trait Open: Sized {
+    /// The plaintext form of the struct
+    type Plaintext: Share<Shared = Self>;
+
+    /// The scalar fields of the struct, in field order
+    fn scalars(&self) -> Vec<AuthenticatedScalarResult>;
+
+    /// The point fields of the struct, in field order
+    fn points(&self) -> Vec<AuthenticatedStarkPointResult>;
+
+    /// Rebuild the struct from its scalar and point fields, each consumed in field order
+    fn from_shares(
+        scalars: &mut dyn Iterator<Item = AuthenticatedScalarResult>,
+        points: &mut dyn Iterator<Item = AuthenticatedStarkPointResult>,
+    ) -> Self;
+
+    /// Open the struct and check the MACs of its fields
+    ///
+    /// The scalars and points are each opened in a single batch, the opening fails if any
+    /// field's MAC check fails
+    fn open_authenticated(&self) -> BoxFuture<'static, Result<Self::Plaintext, MpcError>> {
+        let scalars = AuthenticatedScalarResult::open_authenticated_batch(&self.scalars());
+        let points = AuthenticatedStarkPointResult::open_authenticated_batch(&self.points());
+
+        Box::pin(async move {
+            let scalars: Vec<Scalar> = join_all(scalars).await.into_iter().try_collect()?;
+            let points: Vec<StarkPoint> = join_all(points).await.into_iter().try_collect()?;
+
+            Ok(Self::Plaintext::from_elements(
+                &mut scalars.into_iter(),
+                &mut points.into_iter(),
+            ))
+        })
+    }
+}
+
+/// Define a struct of scalars and points along with its secret shared counterpart, deriving
+/// `Share` and `Open` for the pair
+///
+/// Each field is declared as either a `Scalar` or a `StarkPoint`, the shared struct has the
+/// same fields as `AuthenticatedScalarResult`s and `AuthenticatedStarkPointResult`s:
+///
+/// ```
+/// use mpc_stark::share_struct;
+///
+/// share_struct! {
+///     /// A limit order
+///     #[derive(Clone, Debug, PartialEq)]
+///     pub struct Order {
+///         pub price: Scalar,
+///         pub amount: Scalar,
+///         pub owner: StarkPoint,
+///     }
+///
+///     /// A secret shared limit order
+///     pub struct SharedOrder;
+/// }
+/// ```
+#[macro_export]
+macro_rules! share_struct {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_attr:meta])* $field_vis:vis $field:ident: $kind:ident),* $(,)?
+        }
+
+        $(#[$shared_attr:meta])*
+        $shared_vis:vis struct $shared:ident;
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $crate::share_struct!(@plaintext $kind)),*
+        }
+
+        $(#[$shared_attr])*
+        #[derive(Clone, Debug)]
+        $shared_vis struct $shared {
+            $($(#[$field_attr])* $field_vis $field: $crate::share_struct!(@shared $kind)),*
+        }
+
+        impl $crate::algebra::share::Share for $name {
+            type Shared = $shared;
+
+            fn scalars(&self) -> Vec<$crate::algebra::scalar::Scalar> {
+                [$($crate::share_struct!(@scalar $kind, self.$field)),*]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+
+            fn points(&self) -> Vec<$crate::algebra::stark_curve::StarkPoint> {
+                [$($crate::share_struct!(@point $kind, self.$field)),*]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+
+            #[allow(unused_variables)]
+            fn from_elements(
+                scalars: &mut dyn Iterator<Item = $crate::algebra::scalar::Scalar>,
+                points: &mut dyn Iterator<Item = $crate::algebra::stark_curve::StarkPoint>,
+            ) -> Self {
+                Self {
+                    $($field: $crate::share_struct!(@take $kind, scalars, points)),*
+                }
+            }
+        }
+
+        impl $crate::algebra::share::Open for $shared {
+            type Plaintext = $name;
+
+            fn scalars(
+                &self,
+            ) -> Vec<$crate::algebra::authenticated_scalar::AuthenticatedScalarResult> {
+                [$($crate::share_struct!(@scalar $kind, self.$field)),*]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+
+            fn points(
+                &self,
+            ) -> Vec<$crate::algebra::authenticated_stark_point::AuthenticatedStarkPointResult> {
+                [$($crate::share_struct!(@point $kind, self.$field)),*]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+
+            #[allow(unused_variables)]
+            fn from_shares(
+                scalars: &mut dyn Iterator<
+                    Item = $crate::algebra::authenticated_scalar::AuthenticatedScalarResult,
+                >,
+                points: &mut dyn Iterator<
+                    Item = $crate::algebra::authenticated_stark_point::AuthenticatedStarkPointResult,
+                >,
+            ) -> Self {
+                Self {
+                    $($field: $crate::share_struct!(@take $kind, scalars, points)),*
+                }
+            }
+        }
+    };
+
+    // The plaintext type of a field
+    (@plaintext Scalar) => { $crate::algebra::scalar::Scalar };
+    (@plaintext StarkPoint) => { $crate::algebra::stark_curve::StarkPoint };
+
+    // The shared type of a field
+    (@shared Scalar) => { $crate::algebra::authenticated_scalar::AuthenticatedScalarResult };
+    (@shared StarkPoint) => {
+        $crate::algebra::authenticated_stark_point::AuthenticatedStarkPointResult
+    };
+
+    // A field's value if it is a scalar
+    (@scalar Scalar, $value:expr) => { Some($value.clone()) };
+    (@scalar StarkPoint, $value:expr) => { None };
+
+    // A field's value if it is a point
+    (@point Scalar, $value:expr) => { None };
+    (@point StarkPoint, $value:expr) => { Some($value.clone()) };
+
+    // Take the next value of a field's kind
+    (@take Scalar, $scalars:ident, $points:ident) => {
+        $scalars.next().expect("too few scalars for struct")
+    };
+    (@take StarkPoint, $scalars:ident, $points:ident) => {
+        $points.next().expect("too few points for struct")
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::{scalar::Scalar, stark_curve::StarkPoint},
+        test_helpers::execute_mock_mpc,
+        PARTY0, PARTY1,
+    };
+
+    use super::{Open, Share};
+
+    crate::share_struct! {
+        /// A balance held by an account
+        #[derive(Clone, Debug, PartialEq)]
+        struct Balance {
+            amount: Scalar,
+            mint: Scalar,
+            owner: StarkPoint,
+        }
+
+        /// A secret shared balance
+        struct SharedBalance;
+    }
+
+    /// Tests sharing, operating on, and opening a struct as a unit
+    #[tokio::test]
+    async fn test_share_struct() {
+        let balance0 = Balance {
+            amount: Scalar::from(10u8),
+            mint: Scalar::from(1u8),
+            owner: StarkPoint::generator(),
+        };
+        let balance1 = Balance {
+            amount: Scalar::from(5u8),
+            mint: Scalar::from(2u8),
+            owner: StarkPoint::generator() * Scalar::from(3u8),
+        };
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let (balance0, balance1) = (balance0.clone(), balance1.clone());
+            async move {
+                // The receiver's placeholder only determines the shape of the shared struct
+                let (party0_input, party1_input) = if fabric.party_id() == PARTY0 {
+                    (balance0, balance1.clone())
+                } else {
+                    (balance0.clone(), balance1)
+                };
+
+                let a = party0_input.share(PARTY0, &fabric);
+                let b = party1_input.share(PARTY1, &fabric);
+                let sum = SharedBalance {
+                    amount: &a.amount + &b.amount,
+                    mint: &a.mint * &b.mint,
+                    owner: &a.owner + &b.owner,
+                };
+
+                sum.open_authenticated().await
+            }
+        })
+        .await;
+
+        let expected = Balance {
+            amount: Scalar::from(15u8),
+            mint: Scalar::from(2u8),
+            owner: StarkPoint::generator() * Scalar::from(4u8),
+        };
+        assert_eq!(res, Ok(expected));
+    }
+}
@@ -1,6 +1,7 @@
 //! Defines the `Scalar` type of the Starknet field
 
 use std::{
+    hint::black_box,
     iter::Sum,
     mem::size_of,
     ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
@@ -18,24 +19,31 @@ use ark_ec::{
 use ark_ff::{MontFp, PrimeField, Zero};
 
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use digest::{core_api::BlockSizeUser, Digest};
 use itertools::Itertools;
 use serde::{de::Error as DeError, Deserialize, Serialize};
+use sha3::Sha3_256;
+use zeroize::Zeroize;
 
 use crate::{
     algebra::{
         authenticated_scalar::AUTHENTICATED_SCALAR_RESULT_LEN,
         authenticated_stark_point::AUTHENTICATED_STARK_POINT_RESULT_LEN,
     },
-    fabric::{ResultHandle, ResultValue},
+    fabric::{
+        parallel::{par_map, par_reduce_ranges},
+        ResultHandle, ResultValue,
+    },
 };
 
 use super::{
     authenticated_scalar::AuthenticatedScalarResult,
     authenticated_stark_point::AuthenticatedStarkPointResult,
+    byte_array::{deserialize_byte_array, serialize_byte_array},
     macros::{impl_borrow_variants, impl_commutative},
     mpc_scalar::MpcScalarResult,
     mpc_stark_point::MpcStarkPointResult,
-    scalar::{Scalar, ScalarInner, ScalarResult, StarknetBaseFelt, BASE_FIELD_BYTES},
+    scalar::{scalar_args, Scalar, ScalarInner, ScalarResult, StarknetBaseFelt, BASE_FIELD_BYTES},
 };
 
 /// The number of points and scalars to pull from an iterated MSM when
@@ -87,14 +95,24 @@ pub struct StarkPoint(pub(crate) StarkPointInner);
 
 impl Serialize for StarkPoint {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let bytes = self.to_bytes();
-        bytes.serialize(serializer)
+        let mut bytes = [0u8; STARK_POINT_BYTES];
+        self.0
+            .serialize_compressed(&mut bytes[..])
+            .expect("Failed to serialize point");
+
+        serialize_byte_array(&bytes, serializer)
+    }
+}
+
+impl Zeroize for StarkPoint {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
     }
 }
 
 impl<'de> Deserialize<'de> for StarkPoint {
     fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let bytes = <Vec<u8>>::deserialize(deserializer)?;
+        let bytes: [u8; STARK_POINT_BYTES] = deserialize_byte_array(deserializer)?;
         StarkPoint::from_bytes(&bytes)
             .map_err(|err| DeError::custom(format!("Failed to deserialize point: {err:?}")))
     }
@@ -115,6 +133,20 @@ impl StarkPoint {
         self == &StarkPoint::identity()
     }
 
+    /// Compare two points in constant time
+    ///
+    /// The points are compared in their canonical encoding, and every byte of the encodings
+    /// is compared regardless of where they first differ
+    pub fn ct_eq(&self, other: &StarkPoint) -> bool {
+        let diff = self
+            .to_bytes()
+            .iter()
+            .zip(other.to_bytes().iter())
+            .fold(0u8, |acc, (a, b)| acc | black_box(a ^ b));
+
+        black_box(diff) == 0
+    }
+
     /// Convert the point to affine
     pub fn to_affine(&self) -> Affine<StarknetCurveConfig> {
         self.0.into_affine()
@@ -136,6 +168,8 @@ impl StarkPoint {
     }
 
     /// Deserialize a point from a byte buffer
+    ///
+    /// This fails if the point is not on the curve or not in the prime order subgroup
     pub fn from_bytes(bytes: &[u8]) -> Result<StarkPoint, SerializationError> {
         let point = StarkPointInner::deserialize_compressed(bytes)?;
         Ok(StarkPoint(point))
@@ -169,6 +203,86 @@ impl StarkPoint {
     fn hash_to_field(buf: &[u8]) -> StarknetBaseFelt {
         StarknetBaseFelt::from_be_bytes_mod_order(buf)
     }
+
+    /// Hash a message to a point on the curve, following the `hash_to_curve` construction of
+    /// RFC 9380 with `expand_message_xmd` over SHA3-256 and the simplified SWU map
+    ///
+    /// The domain separation tag should be unique to the protocol and its use of the hash,
+    /// see RFC 9380 section 3.1
+    pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Result<StarkPoint, HashToCurveError> {
+        let uniform_bytes = expand_message_xmd::<Sha3_256>(msg, dst, STARK_UNIFORM_BYTES);
+        Self::from_uniform_bytes(
+            uniform_bytes
+                .try_into()
+                .expect("expanded message has the requested length"),
+        )
+    }
+
+    /// Derive `n` generators whose discrete logs relative to each other and to the group
+    /// generator are unknown, by hashing their indices to the curve under the given domain
+    /// separation tag
+    pub fn hash_to_generators(dst: &[u8], n: usize) -> Result<Vec<StarkPoint>, HashToCurveError> {
+        (0..n as u64)
+            .map(|i| Self::hash_to_curve(&i.to_be_bytes(), dst))
+            .collect()
+    }
+}
+
+/// The prefix used to hash a domain separation tag that is longer than 255 bytes
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+/// Expand a message into `len` uniformly distributed bytes, as in `expand_message_xmd` of
+/// RFC 9380 section 5.3.1
+///
+/// Panics if `len` exceeds the 255 hash outputs the expansion may produce
+fn expand_message_xmd<H: Digest + BlockSizeUser>(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let b_in_bytes = <H as Digest>::output_size();
+    let ell = len.saturating_sub(1) / b_in_bytes + 1;
+    assert!(
+        ell <= 255 && len <= u16::MAX as usize,
+        "expanded length too large"
+    );
+
+    // A tag longer than 255 bytes is replaced with its hash
+    let dst = if dst.len() > 255 {
+        H::new()
+            .chain_update(OVERSIZE_DST_PREFIX)
+            .chain_update(dst)
+            .finalize()
+            .to_vec()
+    } else {
+        dst.to_vec()
+    };
+    let dst_prime = [dst.as_slice(), &[dst.len() as u8]].concat();
+
+    let b_0 = H::new()
+        .chain_update(vec![0u8; H::block_size()])
+        .chain_update(msg)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+
+    let mut uniform_bytes = Vec::with_capacity(ell * b_in_bytes);
+    let mut b_i = H::new()
+        .chain_update(&b_0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    uniform_bytes.extend_from_slice(&b_i);
+
+    for i in 2..=ell {
+        let xored = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect_vec();
+        b_i = H::new()
+            .chain_update(xored)
+            .chain_update([i as u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+
+    uniform_bytes.truncate(len);
+    uniform_bytes
 }
 
 impl From<StarkPointInner> for StarkPoint {
@@ -247,15 +361,10 @@ impl StarkPointResult {
         let fabric = a[0].fabric();
         let all_ids = a.iter().chain(b.iter()).map(|r| r.id).collect_vec();
 
-        fabric.new_batch_gate_op(all_ids, n /* output_arity */, move |mut args| {
-            let a = args.drain(..n).map(StarkPoint::from).collect_vec();
-            let b = args.into_iter().map(StarkPoint::from).collect_vec();
-
-            a.into_iter()
-                .zip(b.into_iter())
-                .map(|(a, b)| a + b)
-                .map(ResultValue::Point)
-                .collect_vec()
+        fabric.new_batch_gate_op(all_ids, n /* output_arity */, move |args| {
+            let points = point_args(&args);
+            let (a, b) = points.split_at(n);
+            point_results(add_points(a, b))
         })
     }
 }
@@ -330,15 +439,10 @@ impl StarkPointResult {
         let fabric = a[0].fabric();
         let all_ids = a.iter().chain(b.iter()).map(|r| r.id).collect_vec();
 
-        fabric.new_batch_gate_op(all_ids, n /* output_arity */, move |mut args| {
-            let a = args.drain(..n).map(StarkPoint::from).collect_vec();
-            let b = args.into_iter().map(StarkPoint::from).collect_vec();
-
-            a.into_iter()
-                .zip(b.into_iter())
-                .map(|(a, b)| a - b)
-                .map(ResultValue::Point)
-                .collect_vec()
+        fabric.new_batch_gate_op(all_ids, n /* output_arity */, move |args| {
+            let points = point_args(&args);
+            let (a, b) = points.split_at(n);
+            point_results(sub_points(a, b))
         })
     }
 }
@@ -422,9 +526,13 @@ impl Mul<&ScalarResult> for &StarkPoint {
 
     fn mul(self, rhs: &ScalarResult) -> Self::Output {
         let self_owned = *self;
+        let table = rhs.fabric.fixed_base_table(self);
         rhs.fabric.new_gate_op(vec![rhs.id], move |args| {
             let rhs: Scalar = args[0].to_owned().into();
-            ResultValue::Point(StarkPoint(self_owned.0 * rhs.0))
+            ResultValue::Point(match table {
+                Some(table) => table.mul(&rhs),
+                None => self_owned * rhs,
+            })
         })
     }
 }
@@ -463,15 +571,10 @@ impl StarkPointResult {
             .chain(b.iter().map(|b| b.id()))
             .collect_vec();
 
-        fabric.new_batch_gate_op(all_ids, n /* output_arity */, move |mut args| {
-            let a = args.drain(..n).map(Scalar::from).collect_vec();
-            let b = args.into_iter().map(StarkPoint::from).collect_vec();
-
-            a.into_iter()
-                .zip(b.into_iter())
-                .map(|(a, b)| a * b)
-                .map(ResultValue::Point)
-                .collect_vec()
+        fabric.new_batch_gate_op(all_ids, n /* output_arity */, move |args| {
+            let scalars = scalar_args(&args[..n]);
+            let points = point_args(&args[n..]);
+            point_results(mul_points(&scalars, &points))
         })
     }
 
@@ -495,15 +598,10 @@ impl StarkPointResult {
             .collect_vec();
 
         fabric
-            .new_batch_gate_op(all_ids, n /* output_arity */, move |mut args| {
-                let a = args.drain(..n).map(Scalar::from).collect_vec();
-                let b = args.into_iter().map(StarkPoint::from).collect_vec();
-
-                a.into_iter()
-                    .zip(b.into_iter())
-                    .map(|(a, b)| a * b)
-                    .map(ResultValue::Point)
-                    .collect_vec()
+            .new_batch_gate_op(all_ids, n /* output_arity */, move |args| {
+                let scalars = scalar_args(&args[..n]);
+                let points = point_args(&args[n..]);
+                point_results(mul_points(&scalars, &points))
             })
             .into_iter()
             .map(MpcStarkPointResult::from)
@@ -566,6 +664,62 @@ impl MulAssign<&Scalar> for StarkPoint {
     }
 }
 
+// === Slice Arithmetic === //
+
+// The inner loops of the batch gates, see the scalar equivalents
+
+/// Add two equal length slices of points elementwise
+pub(crate) fn add_points(a: &[StarkPoint], b: &[StarkPoint]) -> Vec<StarkPoint> {
+    debug_assert_eq!(a.len(), b.len());
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| StarkPoint(a.0 + b.0))
+        .collect()
+}
+
+/// Subtract two equal length slices of points elementwise
+pub(crate) fn sub_points(a: &[StarkPoint], b: &[StarkPoint]) -> Vec<StarkPoint> {
+    debug_assert_eq!(a.len(), b.len());
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| StarkPoint(a.0 - b.0))
+        .collect()
+}
+
+/// Multiply two equal length slices of scalars and points elementwise, across the
+/// executor's gate pool for large batches
+pub(crate) fn mul_points(scalars: &[Scalar], points: &[StarkPoint]) -> Vec<StarkPoint> {
+    debug_assert_eq!(scalars.len(), points.len());
+    let pairs = scalars.iter().zip(points).collect_vec();
+    par_map(&pairs, |(scalar, point)| StarkPoint(point.0 * scalar.0))
+}
+
+/// Multiply each chunk of `chunk_len` points by the corresponding scalar, across the
+/// executor's gate pool for large batches
+pub(crate) fn scale_point_chunks(
+    points: &[StarkPoint],
+    scalars: &[Scalar],
+    chunk_len: usize,
+) -> Vec<StarkPoint> {
+    debug_assert_eq!(points.len(), scalars.len() * chunk_len);
+    let pairs = points
+        .chunks_exact(chunk_len)
+        .zip(scalars)
+        .flat_map(|(chunk, scalar)| chunk.iter().map(move |point| (point, scalar)))
+        .collect_vec();
+    par_map(&pairs, |(point, scalar)| StarkPoint(point.0 * scalar.0))
+}
+
+/// Convert the arguments of a batch gate into points
+pub(crate) fn point_args(args: &[ResultValue]) -> Vec<StarkPoint> {
+    args.iter().map(StarkPoint::from).collect()
+}
+
+/// Convert the outputs of a slice kernel into the results of a batch gate
+pub(crate) fn point_results(points: Vec<StarkPoint>) -> Vec<ResultValue> {
+    points.into_iter().map(ResultValue::Point).collect()
+}
+
 // -------------------
 // | Iterator Traits |
 // -------------------
@@ -578,12 +732,35 @@ impl Sum for StarkPoint {
 
 impl Sum for StarkPointResult {
     /// Assumes the iterator is non-empty
-    fn sum<I: Iterator<Item = Self>>(mut iter: I) -> Self {
-        let first = iter.next().expect("empty iterator");
-        iter.fold(first, |acc, x| acc + x)
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        StarkPointResult::sum_all(&iter.collect_vec())
     }
 }
 
+impl StarkPointResult {
+    /// Sum a non-empty slice of `StarkPointResult`s in a single gate
+    pub fn sum_all(values: &[StarkPointResult]) -> StarkPointResult {
+        assert!(!values.is_empty(), "cannot sum an empty slice");
+
+        let fabric = &values[0].fabric;
+        let ids = values.iter().map(|v| v.id).collect_vec();
+        fabric.new_gate_op(ids, |args| {
+            ResultValue::Point(args.into_iter().map(StarkPoint::from).sum())
+        })
+    }
+}
+
+/// Compute the multiscalar multiplication of affine points and raw scalars, splitting it
+/// across the executor's gate pool for large batches
+fn affine_msm(points: &[Affine<StarknetCurveConfig>], scalars: &[ScalarInner]) -> StarkPointInner {
+    debug_assert_eq!(points.len(), scalars.len());
+    par_reduce_ranges(
+        points.len(),
+        |range| StarkPointInner::msm_unchecked(&points[range.clone()], &scalars[range]),
+        |a, b| a + b,
+    )
+}
+
 /// MSM Implementation
 impl StarkPoint {
     /// Compute the multiscalar multiplication of the given scalars and points
@@ -596,9 +773,7 @@ impl StarkPoint {
 
         let affine_points = points.iter().map(|p| p.0.into_affine()).collect_vec();
         let stripped_scalars = scalars.iter().map(|s| s.0).collect_vec();
-        StarkPointInner::msm(&affine_points, &stripped_scalars)
-            .map(StarkPoint)
-            .unwrap()
+        StarkPoint(affine_msm(&affine_points, &stripped_scalars))
     }
 
     /// Compute the multiscalar multiplication of the given scalars and points
@@ -650,10 +825,7 @@ impl StarkPoint {
                 .map(|s| s.inner())
                 .collect_vec();
 
-            StarkPointInner::msm(&points, &scalars)
-                .map(StarkPoint)
-                .map(ResultValue::Point)
-                .unwrap()
+            ResultValue::Point(StarkPoint(affine_msm(&points, &scalars)))
         })
     }
 
@@ -702,9 +874,9 @@ impl StarkPoint {
 
                 // Compute the MSM of the point
                 vec![
-                    StarkPointInner::msm(&points, &shares).unwrap(),
-                    StarkPointInner::msm(&points, &macs).unwrap(),
-                    StarkPointInner::msm(&points, &modifiers).unwrap(),
+                    affine_msm(&points, &shares),
+                    affine_msm(&points, &macs),
+                    affine_msm(&points, &modifiers),
                 ]
                 .into_iter()
                 .map(StarkPoint::from)
@@ -765,7 +937,7 @@ impl StarkPointResult {
                 .map(|p| p.to_affine())
                 .collect_vec();
 
-            let res = StarkPointInner::msm(&points, &scalars).unwrap();
+            let res = affine_msm(&points, &scalars);
             ResultValue::Point(res.into())
         })
     }
@@ -830,9 +1002,9 @@ impl StarkPointResult {
                     .collect_vec();
 
                 vec![
-                    StarkPointInner::msm(&points, &shares).unwrap(),
-                    StarkPointInner::msm(&points, &macs).unwrap(),
-                    StarkPointInner::msm(&points, &modifiers).unwrap(),
+                    affine_msm(&points, &shares),
+                    affine_msm(&points, &macs),
+                    affine_msm(&points, &modifiers),
                 ]
                 .into_iter()
                 .map(StarkPoint::from)
@@ -879,6 +1051,8 @@ mod test {
     };
 
     use super::*;
+    use crate::test_helpers::mock_fabric;
+
     /// Test that the generators are the same between the two curve representations
     #[test]
     fn test_generators() {
@@ -957,4 +1131,78 @@ mod test {
         let res = StarkPoint::from_uniform_bytes(buf);
         assert!(res.is_ok())
     }
+
+    /// Tests hashing messages to the curve under a domain separation tag
+    #[test]
+    fn test_hash_to_curve_dst() {
+        let dst = b"mpc-stark-test-v1";
+        let point = StarkPoint::hash_to_curve(b"msg", dst).unwrap();
+
+        assert!(point.to_affine().is_on_curve());
+        assert_eq!(point, StarkPoint::hash_to_curve(b"msg", dst).unwrap());
+        assert_ne!(point, StarkPoint::hash_to_curve(b"msg2", dst).unwrap());
+        assert_ne!(
+            point,
+            StarkPoint::hash_to_curve(b"msg", b"mpc-stark-test-v2").unwrap()
+        );
+
+        // An oversized tag is hashed rather than rejected
+        let long_dst = [1u8; 300];
+        assert!(StarkPoint::hash_to_curve(b"msg", &long_dst).is_ok());
+
+        let generators = StarkPoint::hash_to_generators(dst, 4).unwrap();
+        for (i, g) in generators.iter().enumerate() {
+            assert!(!generators[..i].contains(g));
+            assert_ne!(*g, StarkPoint::generator());
+        }
+    }
+
+    /// Tests summing point results in a single gate
+    #[tokio::test]
+    async fn test_point_result_sum() {
+        let points = (0..10).map(|_| random_point()).collect_vec();
+        let expected: StarkPoint = points.iter().copied().sum();
+
+        let fabric = mock_fabric();
+        let allocated = points
+            .iter()
+            .map(|p| fabric.allocate_point(*p))
+            .collect_vec();
+
+        assert_eq!(StarkPointResult::sum_all(&allocated).await, expected);
+        assert_eq!(
+            allocated.into_iter().sum::<StarkPointResult>().await,
+            expected
+        );
+
+        fabric.shutdown();
+    }
+
+    /// Tests `expand_message_xmd` against the layout of RFC 9380 section 5.3.1
+    #[test]
+    fn test_expand_message_xmd() {
+        let dst = b"QUUX-V01-CS02-with-expander-SHA3-256";
+        let expanded = expand_message_xmd::<Sha3_256>(b"abc", dst, 80);
+        assert_eq!(expanded.len(), 80);
+
+        // The first block is the hash of `b_0 || 1 || dst_prime`
+        let dst_prime = [dst.as_slice(), &[dst.len() as u8]].concat();
+        let b_0 = Sha3_256::new()
+            .chain_update([0u8; 136])
+            .chain_update(b"abc")
+            .chain_update(80u16.to_be_bytes())
+            .chain_update([0u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        let b_1 = Sha3_256::new()
+            .chain_update(b_0)
+            .chain_update([1u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        assert_eq!(&expanded[..32], b_1.as_slice());
+
+        // A shorter expansion is not a prefix of a longer one, as the length is hashed
+        let shorter = expand_message_xmd::<Sha3_256>(b"abc", dst, 32);
+        assert_ne!(shorter, expanded[..32]);
+    }
 }
@@ -1,16 +1,28 @@
 //! Defines the Beaver value generation interface
 //! as well as a dummy beaver interface for testing
 
-use itertools::Itertools;
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    ops::{Deref, DerefMut},
+};
 
-use crate::algebra::scalar::Scalar;
+use itertools::{izip, Itertools};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{algebra::scalar::Scalar, error::MpcError};
 
 /// SharedValueSource implements both the functionality for:
 ///     1. Single additively shared values [x] where party 1 holds
 ///        x_1 and party 2 holds x_2 such that x_1 + x_2 = x
 ///     2. Beaver triplets; additively shared values [a], [b], [c] such
 ///        that a * b = c
-pub trait SharedValueSource: Send + Sync {
+///
+/// Sources that buffer preprocessed values should scrub values from their buffers as they
+/// are consumed. The fabric zeroizes its source when it is dropped, at which point any
+/// remaining values should be scrubbed
+pub trait SharedValueSource: Send + Sync + Zeroize {
     /// Fetch the next shared single bit
     fn next_shared_bit(&mut self) -> Scalar;
     /// Fetch the next shared batch of bits
@@ -56,6 +68,224 @@ pub trait SharedValueSource: Send + Sync {
         (a_vals, b_vals, c_vals)
     }
 }
+
+/// A batch of beaver triplets, the shares of `a`, `b`, and `c` respectively
+pub type TripletBatch = (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>);
+
+/// A shared value source that may run out of values, e.g. one backed by a finite amount
+/// of preprocessing
+///
+/// A source that cannot provide the requested values returns an error, usually
+/// `MpcError::PreprocessingExhausted`. The fabric fails the computation with this error, so
+/// that the handles awaiting the values resolve to it. Every `SharedValueSource` is an
+/// infallible `FallibleSharedValueSource`
+pub trait FallibleSharedValueSource: Send + Sync + Zeroize {
+    /// Fetch a batch of shared bits
+    fn try_next_shared_bit_batch(&mut self, num_values: usize) -> Result<Vec<Scalar>, MpcError>;
+    /// Fetch a batch of shared single values
+    fn try_next_shared_value_batch(&mut self, num_values: usize) -> Result<Vec<Scalar>, MpcError>;
+    /// Fetch a batch of multiplicative inverse pairs
+    fn try_next_shared_inverse_pair_batch(
+        &mut self,
+        num_pairs: usize,
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>), MpcError>;
+    /// Fetch a batch of beaver triplets
+    fn try_next_triplet_batch(&mut self, num_triplets: usize) -> Result<TripletBatch, MpcError>;
+}
+
+impl<S: SharedValueSource> FallibleSharedValueSource for S {
+    fn try_next_shared_bit_batch(&mut self, num_values: usize) -> Result<Vec<Scalar>, MpcError> {
+        Ok(self.next_shared_bit_batch(num_values))
+    }
+
+    fn try_next_shared_value_batch(&mut self, num_values: usize) -> Result<Vec<Scalar>, MpcError> {
+        Ok(self.next_shared_value_batch(num_values))
+    }
+
+    fn try_next_shared_inverse_pair_batch(
+        &mut self,
+        num_pairs: usize,
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>), MpcError> {
+        Ok(self.next_shared_inverse_pair_batch(num_pairs))
+    }
+
+    fn try_next_triplet_batch(&mut self, num_triplets: usize) -> Result<TripletBatch, MpcError> {
+        Ok(self.next_triplet_batch(num_triplets))
+    }
+}
+
+// -----------------
+// | Preprocessing |
+// -----------------
+
+/// The amount of each kind of preprocessed material to generate ahead of the online phase
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PreprocessingSpec {
+    /// The number of beaver triplets
+    pub triples: usize,
+    /// The number of shared bits
+    pub bits: usize,
+    /// The number of multiplicative inverse pairs
+    pub inverse_pairs: usize,
+    /// The number of shared single values
+    pub values: usize,
+}
+
+/// A finite store of preprocessed material drawn from a shared value source
+///
+/// The material may be serialized to persist it between the offline and online phases, and
+/// serves as a `FallibleSharedValueSource` that fails with `MpcError::PreprocessingExhausted`
+/// once a kind of material is used up. The material holds secret shares, so it is scrubbed as
+/// it is consumed and zeroized when dropped. It is not `Clone`, so that no copy of the shares
+/// outlives the consumed material, and its `Debug` output shows only the remaining amounts
+#[derive(Default, Serialize, Deserialize)]
+pub struct PreprocessedMaterial {
+    /// The beaver triplets
+    triples: VecDeque<[Scalar; 3]>,
+    /// The shared bits
+    bits: VecDeque<Scalar>,
+    /// The multiplicative inverse pairs
+    inverse_pairs: VecDeque<[Scalar; 2]>,
+    /// The shared single values
+    values: VecDeque<Scalar>,
+}
+
+impl PreprocessedMaterial {
+    /// Draw the material described by the spec from the given source
+    pub fn generate<S: FallibleSharedValueSource + ?Sized>(
+        source: &mut S,
+        spec: PreprocessingSpec,
+    ) -> Result<Self, MpcError> {
+        let (a_vals, b_vals, c_vals) = source.try_next_triplet_batch(spec.triples)?;
+        let bits = source.try_next_shared_bit_batch(spec.bits)?;
+        let (left, right) = source.try_next_shared_inverse_pair_batch(spec.inverse_pairs)?;
+        let values = source.try_next_shared_value_batch(spec.values)?;
+
+        Ok(Self {
+            triples: izip!(a_vals, b_vals, c_vals)
+                .map(|(a, b, c)| [a, b, c])
+                .collect(),
+            bits: bits.into(),
+            inverse_pairs: left.into_iter().zip(right).map(|(l, r)| [l, r]).collect(),
+            values: values.into(),
+        })
+    }
+
+    /// The amount of each kind of material that remains
+    pub fn remaining(&self) -> PreprocessingSpec {
+        PreprocessingSpec {
+            triples: self.triples.len(),
+            bits: self.bits.len(),
+            inverse_pairs: self.inverse_pairs.len(),
+            values: self.values.len(),
+        }
+    }
+
+    /// Take `n` items from the front of a queue, scrubbing them from the queue
+    fn take<T: Copy + Zeroize>(queue: &mut VecDeque<T>, n: usize) -> Result<Vec<T>, MpcError> {
+        if queue.len() < n {
+            return Err(MpcError::PreprocessingExhausted);
+        }
+
+        let taken = queue.iter().take(n).copied().collect_vec();
+        queue.iter_mut().take(n).for_each(Zeroize::zeroize);
+        queue.drain(..n);
+
+        Ok(taken)
+    }
+}
+
+impl Debug for PreprocessedMaterial {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("PreprocessedMaterial")
+            .field("remaining", &self.remaining())
+            .finish()
+    }
+}
+
+impl Zeroize for PreprocessedMaterial {
+    fn zeroize(&mut self) {
+        self.triples.iter_mut().for_each(Zeroize::zeroize);
+        self.bits.iter_mut().for_each(Zeroize::zeroize);
+        self.inverse_pairs.iter_mut().for_each(Zeroize::zeroize);
+        self.values.iter_mut().for_each(Zeroize::zeroize);
+
+        self.triples.clear();
+        self.bits.clear();
+        self.inverse_pairs.clear();
+        self.values.clear();
+    }
+}
+
+impl Drop for PreprocessedMaterial {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl FallibleSharedValueSource for PreprocessedMaterial {
+    fn try_next_shared_bit_batch(&mut self, num_values: usize) -> Result<Vec<Scalar>, MpcError> {
+        Self::take(&mut self.bits, num_values)
+    }
+
+    fn try_next_shared_value_batch(&mut self, num_values: usize) -> Result<Vec<Scalar>, MpcError> {
+        Self::take(&mut self.values, num_values)
+    }
+
+    fn try_next_shared_inverse_pair_batch(
+        &mut self,
+        num_pairs: usize,
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>), MpcError> {
+        Ok(Self::take(&mut self.inverse_pairs, num_pairs)?
+            .into_iter()
+            .map(|[l, r]| (l, r))
+            .unzip())
+    }
+
+    fn try_next_triplet_batch(&mut self, num_triplets: usize) -> Result<TripletBatch, MpcError> {
+        let mut a_vals = Vec::with_capacity(num_triplets);
+        let mut b_vals = Vec::with_capacity(num_triplets);
+        let mut c_vals = Vec::with_capacity(num_triplets);
+        for [a, b, c] in Self::take(&mut self.triples, num_triplets)? {
+            a_vals.push(a);
+            b_vals.push(b);
+            c_vals.push(c);
+        }
+
+        Ok((a_vals, b_vals, c_vals))
+    }
+}
+
+/// A wrapper around the fabric's shared value source that zeroizes the source when dropped
+pub(crate) struct ZeroizingSource(Box<dyn FallibleSharedValueSource>);
+
+impl ZeroizingSource {
+    /// Constructor
+    pub fn new<S: 'static + FallibleSharedValueSource>(source: S) -> Self {
+        Self(Box::new(source))
+    }
+}
+
+impl Deref for ZeroizingSource {
+    type Target = dyn FallibleSharedValueSource;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl DerefMut for ZeroizingSource {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut()
+    }
+}
+
+impl Drop for ZeroizingSource {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 /// An implementation of a beaver value source that returns
 /// beaver triples (0, 0, 0) for party 0 and (1, 1, 1) for party 1
 #[cfg(any(feature = "test_helpers", test))]
@@ -73,6 +303,13 @@ impl PartyIDBeaverSource {
     }
 }
 
+/// The values of the `PartyIDBeaverSource` are derived from the party ID and are not secret,
+/// so there is nothing to scrub
+#[cfg(any(feature = "test_helpers", test))]
+impl Zeroize for PartyIDBeaverSource {
+    fn zeroize(&mut self) {}
+}
+
 /// The PartyIDBeaverSource returns beaver triplets split statically between the
 /// parties. We assume a = 2, b = 3 ==> c = 6. [a] = (1, 1); [b] = (3, 0) [c] = (2, 4)
 #[cfg(any(feature = "test_helpers", test))]
@@ -99,3 +336,109 @@ impl SharedValueSource for PartyIDBeaverSource {
         Scalar::from(self.party_id)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use zeroize::Zeroize;
+
+    use crate::algebra::scalar::Scalar;
+
+    use crate::error::MpcError;
+
+    use super::{
+        FallibleSharedValueSource, PartyIDBeaverSource, PreprocessedMaterial, PreprocessingSpec,
+        SharedValueSource, ZeroizingSource,
+    };
+
+    /// A source that records whether it has been zeroized
+    struct RecordingSource {
+        /// The underlying source
+        inner: PartyIDBeaverSource,
+        /// Whether the source has been zeroized
+        zeroized: Arc<AtomicBool>,
+    }
+
+    impl Zeroize for RecordingSource {
+        fn zeroize(&mut self) {
+            self.zeroized.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl SharedValueSource for RecordingSource {
+        fn next_shared_bit(&mut self) -> Scalar {
+            self.inner.next_shared_bit()
+        }
+
+        fn next_shared_value(&mut self) -> Scalar {
+            self.inner.next_shared_value()
+        }
+
+        fn next_shared_inverse_pair(&mut self) -> (Scalar, Scalar) {
+            self.inner.next_shared_inverse_pair()
+        }
+
+        fn next_triplet(&mut self) -> (Scalar, Scalar, Scalar) {
+            self.inner.next_triplet()
+        }
+    }
+
+    /// Tests that the fabric's source is zeroized when dropped
+    #[test]
+    fn test_zeroizing_source() {
+        let zeroized = Arc::new(AtomicBool::new(false));
+        let mut source = ZeroizingSource::new(RecordingSource {
+            inner: PartyIDBeaverSource::default(),
+            zeroized: zeroized.clone(),
+        });
+
+        source.try_next_triplet_batch(1).unwrap();
+        assert!(!zeroized.load(Ordering::SeqCst));
+
+        drop(source);
+        assert!(zeroized.load(Ordering::SeqCst));
+    }
+
+    /// Tests that preprocessed material survives serialization, redacts its shares from its
+    /// debug output, and is exhausted after use
+    #[test]
+    fn test_preprocessed_material() {
+        let spec = PreprocessingSpec {
+            triples: 2,
+            bits: 1,
+            inverse_pairs: 1,
+            values: 3,
+        };
+        let material =
+            PreprocessedMaterial::generate(&mut PartyIDBeaverSource::new(1), spec).unwrap();
+
+        let serialized = serde_json::to_vec(&material).unwrap();
+        let mut material: PreprocessedMaterial = serde_json::from_slice(&serialized).unwrap();
+        assert_eq!(material.remaining(), spec);
+        assert_eq!(
+            format!("{material:?}"),
+            format!("PreprocessedMaterial {{ remaining: {spec:?} }}")
+        );
+
+        let (a, b, c) = material.try_next_triplet_batch(2).unwrap();
+        assert_eq!(
+            (a[1], b[1], c[1]),
+            PartyIDBeaverSource::new(1).next_triplet()
+        );
+        assert_eq!(material.remaining().triples, 0);
+
+        assert_eq!(
+            material.try_next_triplet_batch(1),
+            Err(MpcError::PreprocessingExhausted)
+        );
+        assert_eq!(
+            material.try_next_shared_value_batch(4),
+            Err(MpcError::PreprocessingExhausted)
+        );
+        assert_eq!(material.try_next_shared_value_batch(3).unwrap().len(), 3);
+    }
+}
@@ -1,70 +1,152 @@
 //! Defines a buffer type used for operations, results, etc in an MPC fabric
 //!
 //! This buffer allows the creator to pre-allocate buffer space for results to fill, and
-//! automatically grows as access to the buffer goes out of bounds
+//! automatically grows as access to the buffer goes out of bounds. The buffer is segmented so
+//! that growth never copies existing slots, which would stall the executor for large buffers,
+//! and so that the segments emptied out by a completed computation may be freed
 
-/// A thin wrapper around a vector that auto-allocates as the buffer grows
-pub struct GrowableBuffer<T: Clone> {
-    /// The underlying buffer
-    buf: Vec<Option<T>>,
+/// The minimum number of slots in a segment of the buffer
+const MIN_SEGMENT_LEN: usize = 1 << 10;
+/// The maximum number of slots in a segment of the buffer
+const MAX_SEGMENT_LEN: usize = 1 << 16;
+
+/// A segmented buffer that auto-allocates as the buffer grows
+///
+/// Slots are stored in fixed size segments, so growing the buffer allocates new segments
+/// rather than copying existing slots into a larger allocation
+pub struct GrowableBuffer<T> {
+    /// The segments of the buffer, each of length `1 << segment_bits` or empty if the segment
+    /// was freed by a compaction
+    segments: Vec<Box<[Option<T>]>>,
+    /// The log2 of the segment length
+    segment_bits: u32,
 }
 
-impl<T: Clone> GrowableBuffer<T> {
+impl<T> GrowableBuffer<T> {
     /// Constructor, takes a size-hint to pre-allocate buffer slots
     pub fn new(size_hint: usize) -> Self {
+        let segment_len = size_hint
+            .next_power_of_two()
+            .clamp(MIN_SEGMENT_LEN, MAX_SEGMENT_LEN);
+        let segment_bits = segment_len.trailing_zeros();
+        let n_segments = (size_hint.saturating_sub(1) >> segment_bits) + 1;
+
         Self {
-            buf: vec![None; size_hint],
+            segments: (0..n_segments)
+                .map(|_| Self::new_segment(segment_len))
+                .collect(),
+            segment_bits,
         }
     }
 
-    /// Grow the underlying buffer
+    /// Allocate an empty segment
+    fn new_segment(len: usize) -> Box<[Option<T>]> {
+        (0..len).map(|_| None).collect()
+    }
+
+    /// The number of slots allocated in the buffer
+    fn capacity(&self) -> usize {
+        self.segments.len() << self.segment_bits
+    }
+
+    /// Split an index into its segment and its offset within the segment
+    fn locate(&self, idx: usize) -> (usize, usize) {
+        (
+            idx >> self.segment_bits,
+            idx & ((1 << self.segment_bits) - 1),
+        )
+    }
+
+    /// Grow the underlying buffer, allocating segments up to the one holding `access_idx`
     fn grow(&mut self, access_idx: usize) {
-        let new_size = usize::max(access_idx + 1, self.buf.len() * 2);
-        self.buf.resize(new_size, None);
+        let (segment, _) = self.locate(access_idx);
+        while self.segments.len() <= segment {
+            self.segments
+                .push(Self::new_segment(1 << self.segment_bits));
+        }
+    }
+
+    /// Get the slot at the given index, growing the buffer if necessary
+    fn slot_mut(&mut self, idx: usize) -> &mut Option<T> {
+        if idx >= self.capacity() {
+            self.grow(idx)
+        }
+
+        // Reallocate a segment freed by a compaction
+        let (segment, offset) = self.locate(idx);
+        if self.segments[segment].is_empty() {
+            self.segments[segment] = Self::new_segment(1 << self.segment_bits);
+        }
+
+        &mut self.segments[segment][offset]
     }
 
     /// Get the element at the given index in the buffer, returns `None` if the element
     /// has not been set
     pub fn get(&self, idx: usize) -> Option<&T> {
-        if idx >= self.buf.len() {
-            return None;
-        }
-
-        self.buf[idx].as_ref()
+        let (segment, offset) = self.locate(idx);
+        self.segments.get(segment)?.get(offset)?.as_ref()
     }
 
     /// Get an entry as a mutable reference
     pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
-        if idx >= self.buf.len() {
-            self.grow(idx)
-        }
-
-        self.buf[idx].as_mut()
+        self.slot_mut(idx).as_mut()
     }
 
     /// Get a mutable reference to the entry at a given index
     pub fn entry_mut(&mut self, idx: usize) -> &mut Option<T> {
-        // Grow the buffer if necessary
-        if idx >= self.buf.len() {
-            self.grow(idx)
-        }
-
-        &mut self.buf[idx]
+        self.slot_mut(idx)
     }
 
     /// Insert value at the given index
     pub fn insert(&mut self, idx: usize, val: T) -> Option<T> {
-        if idx >= self.buf.len() {
-            self.grow(idx)
-        }
-
-        self.buf.get_mut(idx).unwrap().replace(val)
+        self.slot_mut(idx).replace(val)
     }
 
     /// Take ownership of a value at a given index
     pub fn take(&mut self, idx: usize) -> Option<T> {
-        let val = self.buf.get_mut(idx)?;
-        val.take()
+        let (segment, offset) = self.locate(idx);
+        self.segments.get_mut(segment)?.get_mut(offset)?.take()
+    }
+
+    /// Iterate over the elements that are set in the buffer, with their indices
+    ///
+    /// Visits every slot of the buffer, so this is linear in its capacity
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        let segment_bits = self.segment_bits;
+        self.segments
+            .iter()
+            .enumerate()
+            .flat_map(move |(segment, slots)| {
+                slots.iter().enumerate().filter_map(move |(offset, val)| {
+                    Some(((segment << segment_bits) + offset, val.as_ref()?))
+                })
+            })
+    }
+
+    /// Free the segments of the buffer that hold no elements, returning the number of
+    /// segments freed
+    ///
+    /// A freed segment is reallocated when an element is next inserted into it. Trailing
+    /// empty segments are removed entirely, so the buffer regrows from its last element
+    pub fn compact(&mut self) -> usize {
+        let mut n_freed = 0;
+        for segment in self.segments.iter_mut() {
+            if !segment.is_empty() && segment.iter().all(Option::is_none) {
+                *segment = Box::new([]);
+                n_freed += 1;
+            }
+        }
+
+        let n_occupied = self
+            .segments
+            .iter()
+            .rposition(|segment| !segment.is_empty())
+            .map_or(0, |last| last + 1);
+        self.segments.truncate(n_occupied);
+        self.segments.shrink_to_fit();
+
+        n_freed
     }
 }
 
@@ -121,4 +203,55 @@ mod test {
         assert_eq!(buf.take(2), Some(2));
         assert_eq!(buf.get(2), None);
     }
+
+    /// Tests growing a buffer across many segments, leaving earlier values in place
+    #[test]
+    fn test_grow_segments() {
+        let mut buf: GrowableBuffer<usize> = GrowableBuffer::new(2);
+        let indices = [0, 1, 1023, 1024, 5000, 100_000];
+        for idx in indices {
+            buf.insert(idx, idx);
+        }
+
+        for idx in indices {
+            assert_eq!(buf.get(idx), Some(&idx));
+        }
+        assert_eq!(buf.get(1025), None);
+        assert_eq!(buf.get(1_000_000), None);
+        assert_eq!(buf.take(1_000_000), None);
+    }
+
+    /// Tests iterating over the elements that are set in a buffer
+    #[test]
+    fn test_iter() {
+        let mut buf: GrowableBuffer<u64> = GrowableBuffer::new(2);
+        buf.insert(1, 1);
+        buf.insert(5000, 2);
+
+        assert_eq!(buf.iter().collect::<Vec<_>>(), vec![(1, &1), (5000, &2)]);
+    }
+
+    /// Tests freeing the empty segments of a buffer and reusing them afterwards
+    #[test]
+    fn test_compact() {
+        let mut buf: GrowableBuffer<usize> = GrowableBuffer::new(2);
+        for idx in [1, 1024, 5000, 100_000] {
+            buf.insert(idx, idx);
+        }
+        buf.take(1024);
+        buf.take(100_000);
+
+        // The segments holding 1024 and 100_000 are freed, along with those after 5000
+        let capacity = buf.capacity();
+        assert!(buf.compact() >= 2);
+        assert!(buf.capacity() < capacity);
+        assert_eq!(buf.iter().collect::<Vec<_>>(), vec![(1, &1), (5000, &5000)]);
+        assert_eq!(buf.get(1024), None);
+        assert_eq!(buf.take(100_000), None);
+
+        buf.insert(1024, 1);
+        buf.insert(100_000, 2);
+        assert_eq!(buf.get(1024), Some(&1));
+        assert_eq!(buf.get(100_000), Some(&2));
+    }
 }
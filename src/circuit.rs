@@ -0,0 +1,387 @@
+//! Defines reusable circuits, i.e. subcircuits that are defined once as a template of
+//! inputs, gates, and outputs and instantiated repeatedly against the fabric
+//!
+//! The topology of a circuit is analyzed once when it is built: each gate is assigned the
+//! multiplicative depth at which it may be evaluated. Instantiating the circuit then
+//! evaluates all multiplications at the same depth in a single batch, across all instances
+//! of a batched instantiation, rather than scheduling each multiplication separately
+
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar};
+
+/// A wire in a circuit, i.e. the output of an input or a gate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Wire(usize);
+
+/// A gate in a circuit template
+#[derive(Clone, Copy, Debug)]
+enum Gate {
+    /// The `i`th input of the circuit
+    Input(usize),
+    /// The sum of two wires
+    Add(Wire, Wire),
+    /// The difference of two wires
+    Sub(Wire, Wire),
+    /// The negation of a wire
+    Neg(Wire),
+    /// The sum of a wire and a public constant
+    AddConstant(Wire, Scalar),
+    /// The product of a wire and a public constant
+    MulConstant(Wire, Scalar),
+    /// The product of two wires
+    Mul(Wire, Wire),
+}
+
+impl Gate {
+    /// The wires the gate takes as operands
+    fn operands(&self) -> Vec<Wire> {
+        match *self {
+            Gate::Input(_) => vec![],
+            Gate::Neg(a) | Gate::AddConstant(a, _) | Gate::MulConstant(a, _) => vec![a],
+            Gate::Add(a, b) | Gate::Sub(a, b) | Gate::Mul(a, b) => vec![a, b],
+        }
+    }
+}
+
+/// A builder for a circuit template
+#[derive(Clone, Debug, Default)]
+pub struct CircuitBuilder {
+    /// The gates of the circuit, in the order they were added
+    gates: Vec<Gate>,
+    /// The number of inputs to the circuit
+    num_inputs: usize,
+}
+
+impl CircuitBuilder {
+    /// Create a new, empty circuit builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a gate to the circuit, returning its output wire
+    fn add_gate(&mut self, gate: Gate) -> Wire {
+        for operand in gate.operands() {
+            assert!(
+                operand.0 < self.gates.len(),
+                "wire {operand:?} not in circuit"
+            );
+        }
+
+        self.gates.push(gate);
+        Wire(self.gates.len() - 1)
+    }
+
+    /// Add an input to the circuit
+    ///
+    /// Inputs are bound in the order they are added when the circuit is instantiated
+    pub fn input(&mut self) -> Wire {
+        self.num_inputs += 1;
+        self.add_gate(Gate::Input(self.num_inputs - 1))
+    }
+
+    /// Add two wires
+    pub fn add(&mut self, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::Add(a, b))
+    }
+
+    /// Subtract one wire from another
+    pub fn sub(&mut self, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::Sub(a, b))
+    }
+
+    /// Negate a wire
+    pub fn neg(&mut self, a: Wire) -> Wire {
+        self.add_gate(Gate::Neg(a))
+    }
+
+    /// Add a public constant to a wire
+    pub fn add_constant(&mut self, a: Wire, constant: Scalar) -> Wire {
+        self.add_gate(Gate::AddConstant(a, constant))
+    }
+
+    /// Multiply a wire by a public constant
+    pub fn mul_constant(&mut self, a: Wire, constant: Scalar) -> Wire {
+        self.add_gate(Gate::MulConstant(a, constant))
+    }
+
+    /// Multiply two wires
+    pub fn mul(&mut self, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::Mul(a, b))
+    }
+
+    /// Build the circuit with the given output wires
+    pub fn build(self, outputs: &[Wire]) -> Circuit {
+        for output in outputs.iter() {
+            assert!(
+                output.0 < self.gates.len(),
+                "wire {output:?} not in circuit"
+            );
+        }
+
+        // Assign each gate the multiplicative depth at which its operands are available
+        let mut depths: Vec<usize> = Vec::with_capacity(self.gates.len());
+        for gate in self.gates.iter() {
+            let operand_depth = gate
+                .operands()
+                .into_iter()
+                .map(|w| depths[w.0])
+                .max()
+                .unwrap_or_default();
+
+            let depth = match gate {
+                Gate::Mul(..) => operand_depth + 1,
+                _ => operand_depth,
+            };
+            depths.push(depth);
+        }
+
+        let num_layers = depths.iter().max().map(|d| d + 1).unwrap_or_default();
+        let mut layers = vec![Layer::default(); num_layers];
+        for (i, (gate, depth)) in self.gates.iter().zip(depths).enumerate() {
+            match gate {
+                Gate::Mul(..) => layers[depth].multiplications.push(i),
+                _ => layers[depth].linear.push(i),
+            }
+        }
+
+        Circuit {
+            gates: Arc::new(self.gates),
+            layers: Arc::new(layers),
+            num_inputs: self.num_inputs,
+            outputs: outputs.to_vec(),
+        }
+    }
+}
+
+/// The gates evaluated at a single multiplicative depth of a circuit
+#[derive(Clone, Debug, Default)]
+struct Layer {
+    /// The multiplication gates of the layer, whose operands are all at lower depths
+    multiplications: Vec<usize>,
+    /// The linear gates of the layer, in topological order
+    linear: Vec<usize>,
+}
+
+/// A circuit template that may be instantiated repeatedly against the fabric
+///
+/// Cloning a circuit is cheap, its topology is shared between clones
+#[derive(Clone, Debug)]
+pub struct Circuit {
+    /// The gates of the circuit
+    gates: Arc<Vec<Gate>>,
+    /// The gates of the circuit grouped by multiplicative depth
+    layers: Arc<Vec<Layer>>,
+    /// The number of inputs to the circuit
+    num_inputs: usize,
+    /// The output wires of the circuit
+    outputs: Vec<Wire>,
+}
+
+impl Circuit {
+    /// The number of inputs to the circuit
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    /// The number of outputs of the circuit
+    pub fn num_outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// The number of multiplications in the circuit
+    pub fn num_multiplications(&self) -> usize {
+        self.layers.iter().map(|l| l.multiplications.len()).sum()
+    }
+
+    /// The multiplicative depth of the circuit, i.e. the number of rounds of communication
+    /// an instantiation takes
+    pub fn multiplicative_depth(&self) -> usize {
+        self.layers.len().saturating_sub(1)
+    }
+
+    /// Instantiate the circuit on the given inputs, returning its outputs
+    pub fn instantiate(
+        &self,
+        inputs: &[AuthenticatedScalarResult],
+    ) -> Vec<AuthenticatedScalarResult> {
+        self.instantiate_batch(&[inputs.to_vec()]).pop().unwrap()
+    }
+
+    /// Instantiate the circuit on a batch of inputs, returning the outputs of each instance
+    ///
+    /// The multiplications at each depth are evaluated in a single batch across all instances
+    pub fn instantiate_batch(
+        &self,
+        instances: &[Vec<AuthenticatedScalarResult>],
+    ) -> Vec<Vec<AuthenticatedScalarResult>> {
+        for inputs in instances.iter() {
+            assert_eq!(
+                inputs.len(),
+                self.num_inputs,
+                "circuit expects {} inputs",
+                self.num_inputs
+            );
+        }
+
+        let mut wires: Vec<Vec<Option<AuthenticatedScalarResult>>> =
+            vec![vec![None; self.gates.len()]; instances.len()];
+        for layer in self.layers.iter() {
+            self.eval_multiplications(&layer.multiplications, &mut wires);
+            for (inputs, wires) in instances.iter().zip(wires.iter_mut()) {
+                for &i in layer.linear.iter() {
+                    let value = Self::eval_linear(self.gates[i], inputs, wires);
+                    wires[i] = Some(value);
+                }
+            }
+        }
+
+        wires
+            .into_iter()
+            .map(|wires| {
+                self.outputs
+                    .iter()
+                    .map(|w| wires[w.0].clone().unwrap())
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    /// Evaluate the multiplications of a layer in a single batch across all instances
+    fn eval_multiplications(
+        &self,
+        multiplications: &[usize],
+        wires: &mut [Vec<Option<AuthenticatedScalarResult>>],
+    ) {
+        if multiplications.is_empty() || wires.is_empty() {
+            return;
+        }
+
+        let mut lhs = Vec::with_capacity(multiplications.len() * wires.len());
+        let mut rhs = Vec::with_capacity(multiplications.len() * wires.len());
+        for wires in wires.iter() {
+            for &i in multiplications.iter() {
+                let (a, b) = match self.gates[i] {
+                    Gate::Mul(a, b) => (a, b),
+                    _ => unreachable!("only multiplications are batched"),
+                };
+
+                lhs.push(wires[a.0].clone().unwrap());
+                rhs.push(wires[b.0].clone().unwrap());
+            }
+        }
+
+        let mut products = AuthenticatedScalarResult::batch_mul(&lhs, &rhs).into_iter();
+        for wires in wires.iter_mut() {
+            for &i in multiplications.iter() {
+                wires[i] = products.next();
+            }
+        }
+    }
+
+    /// Evaluate a linear gate on the wires of an instance
+    fn eval_linear(
+        gate: Gate,
+        inputs: &[AuthenticatedScalarResult],
+        wires: &[Option<AuthenticatedScalarResult>],
+    ) -> AuthenticatedScalarResult {
+        let wire = |w: Wire| wires[w.0].as_ref().unwrap();
+        match gate {
+            Gate::Input(i) => inputs[i].clone(),
+            Gate::Add(a, b) => wire(a) + wire(b),
+            Gate::Sub(a, b) => wire(a) - wire(b),
+            Gate::Neg(a) => -wire(a),
+            Gate::AddConstant(a, constant) => wire(a) + constant,
+            Gate::MulConstant(a, constant) => wire(a) * constant,
+            Gate::Mul(..) => unreachable!("multiplications are evaluated in batches"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        circuit::{Circuit, CircuitBuilder},
+        test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    /// Build a circuit computing `(x * y + 3) * (x - y) * 2`
+    fn test_circuit() -> Circuit {
+        let mut builder = CircuitBuilder::new();
+        let x = builder.input();
+        let y = builder.input();
+
+        let xy = builder.mul(x, y);
+        let xy_plus_3 = builder.add_constant(xy, Scalar::from(3u8));
+        let x_minus_y = builder.sub(x, y);
+        let prod = builder.mul(xy_plus_3, x_minus_y);
+        let out = builder.mul_constant(prod, Scalar::from(2u8));
+
+        builder.build(&[out, x_minus_y])
+    }
+
+    /// Evaluate the test circuit in the clear
+    fn expected_outputs(x: Scalar, y: Scalar) -> Vec<Scalar> {
+        vec![
+            (x * y + Scalar::from(3u8)) * (x - y) * Scalar::from(2u8),
+            x - y,
+        ]
+    }
+
+    /// Tests the topology analysis of a circuit
+    #[test]
+    fn test_topology() {
+        let circuit = test_circuit();
+        assert_eq!(circuit.num_inputs(), 2);
+        assert_eq!(circuit.num_outputs(), 2);
+        assert_eq!(circuit.num_multiplications(), 2);
+        assert_eq!(circuit.multiplicative_depth(), 2);
+    }
+
+    /// Tests instantiating a circuit repeatedly
+    #[tokio::test]
+    async fn test_instantiate() {
+        const N: usize = 3;
+        let mut rng = thread_rng();
+        let inputs = (0..2 * N).map(|_| Scalar::random(&mut rng)).collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let inputs = inputs.clone();
+            async move {
+                let circuit = test_circuit();
+                let shared = fabric.batch_share_scalar(inputs, PARTY0);
+
+                // Instantiate once per pair of inputs, and once over all pairs in a batch
+                let mut outputs = shared
+                    .chunks(2)
+                    .flat_map(|inputs| circuit.instantiate(inputs))
+                    .collect_vec();
+                let instances = shared.chunks(2).map(|c| c.to_vec()).collect_vec();
+                outputs.extend(circuit.instantiate_batch(&instances).into_iter().flatten());
+
+                futures::future::join_all(AuthenticatedScalarResult::open_authenticated_batch(
+                    &outputs,
+                ))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+            }
+        })
+        .await;
+
+        let expected = inputs
+            .chunks(2)
+            .flat_map(|inputs| expected_outputs(inputs[0], inputs[1]))
+            .collect_vec();
+        let res = res.unwrap();
+        assert_eq!(res[..2 * N], expected);
+        assert_eq!(res[2 * N..], expected);
+    }
+}
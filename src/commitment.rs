@@ -1,137 +1,396 @@
-//! Defines Pedersen commitments over the Stark curve used to commit to a value
-//! before opening it
+//! Defines the commitment schemes used to commit to a value before opening it
 
-use rand::thread_rng;
-use sha3::{Digest, Sha3_256};
+use std::marker::PhantomData;
+
+use digest::Digest;
+use itertools::Itertools;
+use sha3::Sha3_256;
 
 use crate::{
-    algebra::{
-        scalar::{Scalar, ScalarResult},
-        stark_curve::{StarkPoint, StarkPointResult},
-    },
-    fabric::ResultValue,
+    algebra::{scalar::Scalar, stark_curve::StarkPoint},
+    fabric::{ResultHandle, ResultId, ResultValue},
+    network::{NetworkPayload, PayloadType, SessionId},
 };
 
-/// A handle on the result of a Pedersen commitment, including the committed secret
+/// The domain separation tag prepended to the preimage of a hash commitment
+const HASH_COMMITMENT_DOMAIN: &[u8] = b"mpc-stark-hash-commitment";
+
+/// A commitment scheme used to commit to a value of type `T` before it is opened
 ///
-/// Of the form `value * G + blinder * H`
-pub(crate) struct PedersenCommitment {
-    /// The committed value
-    pub(crate) value: Scalar,
-    /// The commitment blinder
-    pub(crate) blinder: Scalar,
-    /// The value of the commitment
-    pub(crate) commitment: StarkPoint,
-}
+/// Authenticated openings commit to the shares of their MAC checks under a scheme before
+/// exchanging them, both parties must use the same scheme for an opening
+pub trait CommitmentScheme<T>: 'static + Send + Sync {
+    /// The type of the commitment, sent to the peer before the committed value is opened
+    type Commitment: 'static + PayloadType + Into<NetworkPayload> + Clone + Send + Sync;
 
-impl PedersenCommitment {
-    /// Verify that the given commitment is valid
-    pub(crate) fn verify(&self) -> bool {
-        let generator = StarkPoint::generator();
-        let commitment = generator * self.value + generator * self.blinder;
+    /// Commit to a value under the given blinder
+    ///
+    /// The commitment may be bound to the session and the ID of the committed result, so that
+    /// it cannot be opened in a different context
+    fn commit(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &T,
+        blinder: &Scalar,
+    ) -> Self::Commitment;
 
-        commitment == self.commitment
-    }
+    /// Verify that a commitment opens to the given value and blinder in the given context
+    fn verify(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &T,
+        blinder: &Scalar,
+        commitment: &Self::Commitment,
+    ) -> bool;
 }
 
-/// A Pedersen commitment that has been allocated in an MPC computation graph
-pub(crate) struct PedersenCommitmentResult {
-    /// The committed value
-    pub(crate) value: ScalarResult,
-    /// The commitment blinder
-    pub(crate) blinder: Scalar,
-    /// The value of the commitment
-    pub(crate) commitment: StarkPointResult,
-}
+/// A Pedersen commitment to a scalar
+///
+/// Of the form `value * G + blinder * H`, with the curve generator used for both `G` and
+/// `H`, so the commitment is hiding but not binding
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PedersenCommitment;
+
+impl CommitmentScheme<Scalar> for PedersenCommitment {
+    type Commitment = StarkPoint;
 
-impl PedersenCommitmentResult {
-    /// Create a new Pedersen commitment to an underlying value
-    pub(crate) fn commit(value: ScalarResult) -> PedersenCommitmentResult {
+    fn commit(
+        _session_id: &SessionId,
+        _result_id: ResultId,
+        value: &Scalar,
+        blinder: &Scalar,
+    ) -> StarkPoint {
         // Concretely, we use the curve generator for both `G` and `H` as is done
         // in dalek-cryptography: https://github.com/dalek-cryptography/bulletproofs/blob/main/src/generators.rs#L44-L53
-        let mut rng = thread_rng();
-        let blinder = Scalar::random(&mut rng);
         let generator = StarkPoint::generator();
-        let commitment = generator * &value + generator * blinder;
+        generator * value + generator * blinder
+    }
 
-        PedersenCommitmentResult {
-            value,
-            blinder,
-            commitment,
-        }
+    fn verify(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &Scalar,
+        blinder: &Scalar,
+        commitment: &StarkPoint,
+    ) -> bool {
+        Self::commit(session_id, result_id, value, blinder).ct_eq(commitment)
     }
 }
 
-/// A handle on the result of a salted Sha256 hash commitment, including the committed secret
+/// A hash function used to compute a `HashCommitment`
 ///
-/// Of the form `H(salt || value)`
+/// This is implemented for all `Digest`s, e.g. `Sha3_256` or, with the `blake3` feature,
+/// `blake3::Hasher`, and may be implemented directly for hashes that are not byte oriented,
+/// e.g. an algebraic hash
+pub trait CommitmentHasher: 'static + Send + Sync {
+    /// Hash the preimage of a commitment into a scalar
+    fn hash_to_scalar(preimage: &[u8]) -> Scalar;
+}
+
+impl<D: 'static + Digest + Send + Sync> CommitmentHasher for D {
+    fn hash_to_scalar(preimage: &[u8]) -> Scalar {
+        Scalar::from_be_bytes_mod_order(D::digest(preimage).as_slice())
+    }
+}
+
+/// A salted hash commitment, by default over Sha3-256
+///
+/// Of the form `H(domain || session_id || result_id || value || salt)`. The domain separation tag
+/// separates the commitments from other uses of the hash, the session ID prevents a commitment
+/// from one session being replayed in another, and the result ID prevents a commitment from
+/// being opened in place of another within a session
 ///
 /// We use hash commitments to commit to curve points before opening them. There is no straightforward
 /// way to adapt Pedersen commitments to curve points, and we do not need the homomorphic properties
 /// of a Pedersen commitment
-pub(crate) struct HashCommitment {
-    /// The committed value
-    pub(crate) value: StarkPoint,
-    /// The blinder used in the commitment
-    pub(crate) blinder: Scalar,
-    /// The value of the commitment
-    pub(crate) commitment: Scalar,
+pub struct HashCommitment<H: CommitmentHasher = Sha3_256>(PhantomData<fn() -> H>);
+
+impl<H: CommitmentHasher> HashCommitment<H> {
+    /// Compute a hash commitment to the serialized value under the given blinder and context
+    fn hash(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value_bytes: &[u8],
+        blinder: &Scalar,
+    ) -> Scalar {
+        let mut bytes = HASH_COMMITMENT_DOMAIN.to_vec();
+        bytes.extend_from_slice(session_id);
+        bytes.extend_from_slice(&(result_id as u64).to_be_bytes());
+        bytes.extend_from_slice(value_bytes);
+        bytes.append(&mut blinder.to_bytes_be());
+
+        H::hash_to_scalar(&bytes)
+    }
+}
+
+impl<H: CommitmentHasher> CommitmentScheme<StarkPoint> for HashCommitment<H> {
+    type Commitment = Scalar;
+
+    fn commit(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &StarkPoint,
+        blinder: &Scalar,
+    ) -> Scalar {
+        Self::hash(session_id, result_id, &value.to_bytes(), blinder)
+    }
+
+    fn verify(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &StarkPoint,
+        blinder: &Scalar,
+        commitment: &Scalar,
+    ) -> bool {
+        Self::commit(session_id, result_id, value, blinder).ct_eq(commitment)
+    }
+}
+
+impl<H: CommitmentHasher> CommitmentScheme<Scalar> for HashCommitment<H> {
+    type Commitment = Scalar;
+
+    fn commit(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &Scalar,
+        blinder: &Scalar,
+    ) -> Scalar {
+        Self::hash(session_id, result_id, &value.to_bytes_be(), blinder)
+    }
+
+    fn verify(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &Scalar,
+        blinder: &Scalar,
+        commitment: &Scalar,
+    ) -> bool {
+        Self::commit(session_id, result_id, value, blinder).ct_eq(commitment)
+    }
 }
 
-impl HashCommitment {
-    /// Verify that the given commitment is valid
-    pub(crate) fn verify(&self) -> bool {
-        // Create the bytes buffer
-        let mut bytes = self.value.to_bytes();
-        bytes.append(&mut self.blinder.to_bytes_be());
+impl<H: CommitmentHasher> CommitmentScheme<Vec<Scalar>> for HashCommitment<H> {
+    type Commitment = Scalar;
 
-        // Hash the bytes, squeeze an output, verify that it is equal to the commitment
-        let mut hasher = Sha3_256::new();
-        hasher.update(bytes);
+    fn commit(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &Vec<Scalar>,
+        blinder: &Scalar,
+    ) -> Scalar {
+        let value_bytes = value.iter().flat_map(|s| s.to_bytes_be()).collect_vec();
+        Self::hash(session_id, result_id, &value_bytes, blinder)
+    }
 
-        let out_bytes = hasher.finalize();
-        let out = Scalar::from_be_bytes_mod_order(out_bytes.as_slice());
+    fn verify(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &Vec<Scalar>,
+        blinder: &Scalar,
+        commitment: &Scalar,
+    ) -> bool {
+        Self::commit(session_id, result_id, value, blinder).ct_eq(commitment)
+    }
+}
+
+impl<H: CommitmentHasher> CommitmentScheme<Vec<StarkPoint>> for HashCommitment<H> {
+    type Commitment = Scalar;
+
+    fn commit(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &Vec<StarkPoint>,
+        blinder: &Scalar,
+    ) -> Scalar {
+        let value_bytes = value.iter().flat_map(|p| p.to_bytes()).collect_vec();
+        Self::hash(session_id, result_id, &value_bytes, blinder)
+    }
 
-        out == self.commitment
+    fn verify(
+        session_id: &SessionId,
+        result_id: ResultId,
+        value: &Vec<StarkPoint>,
+        blinder: &Scalar,
+        commitment: &Scalar,
+    ) -> bool {
+        Self::commit(session_id, result_id, value, blinder).ct_eq(commitment)
     }
 }
 
-/// A hash commitment that has been allocated in an MPC computation graph
-pub(crate) struct HashCommitmentResult {
+/// A commitment that has been allocated in an MPC computation graph
+pub(crate) struct CommitmentResult<T: From<ResultValue>, C: CommitmentScheme<T>> {
     /// The committed value
-    pub(crate) value: StarkPointResult,
-    /// The blinder used in the commitment
+    pub(crate) value: ResultHandle<T>,
+    /// The commitment blinder
     pub(crate) blinder: Scalar,
     /// The value of the commitment
-    pub(crate) commitment: ScalarResult,
+    pub(crate) commitment: ResultHandle<C::Commitment>,
+}
+
+impl<T, C> CommitmentResult<T, C>
+where
+    T: 'static + From<ResultValue>,
+    C: CommitmentScheme<T>,
+{
+    /// Create a new commitment to an underlying value
+    pub(crate) fn commit(value: ResultHandle<T>) -> Self {
+        let fabric = value.fabric();
+        let blinder = fabric.random_scalar();
+        let session_id = fabric.session_id();
+        let result_id = value.id();
+        let commitment = fabric.new_gate_op(vec![result_id], move |mut args| {
+            let value: T = args.remove(0).into();
+            let commitment: NetworkPayload =
+                C::commit(&session_id, result_id, &value, &blinder).into();
+
+            commitment.into()
+        });
+
+        CommitmentResult {
+            value,
+            blinder,
+            commitment,
+        }
+    }
 }
 
-impl HashCommitmentResult {
-    /// Create a new hash commitment to an underlying value
-    pub(crate) fn commit(value: StarkPointResult) -> HashCommitmentResult {
+#[cfg(test)]
+mod test {
+    use rand::thread_rng;
+    use sha3::Sha3_512;
+
+    use crate::algebra::{scalar::Scalar, stark_curve::StarkPoint};
+
+    use super::{CommitmentScheme, HashCommitment, PedersenCommitment};
+
+    /// Tests that a hash commitment does not verify in a different session or for a
+    /// different result
+    #[test]
+    fn test_hash_commitment_binding() {
         let mut rng = thread_rng();
+        let value = StarkPoint::generator() * Scalar::random(&mut rng);
         let blinder = Scalar::random(&mut rng);
-        let comm = value.fabric.new_gate_op(vec![value.id], move |mut args| {
-            let value: StarkPoint = args.remove(0).into();
 
-            // Create the bytes buffer
-            let mut bytes = value.to_bytes();
-            bytes.append(&mut blinder.to_bytes_be());
+        let session_id = [1u8; 32];
+        let comm = <HashCommitment>::commit(&session_id, 1, &value, &blinder);
 
-            // Hash the bytes, squeeze an output, verify that it is equal to the commitment
-            let mut hasher = Sha3_256::new();
-            hasher.update(bytes);
+        assert!(<HashCommitment>::verify(
+            &session_id,
+            1,
+            &value,
+            &blinder,
+            &comm
+        ));
+        assert!(!<HashCommitment>::verify(
+            &[2u8; 32], 1, &value, &blinder, &comm
+        ));
+        assert!(!<HashCommitment>::verify(
+            &session_id,
+            2,
+            &value,
+            &blinder,
+            &comm
+        ));
+    }
 
-            let out_bytes = hasher.finalize();
-            let out = Scalar::from_be_bytes_mod_order(out_bytes.as_slice());
+    /// Tests that hash commitments under different hashes do not verify for one another
+    #[test]
+    fn test_hash_commitment_hasher() {
+        let mut rng = thread_rng();
+        let value = Scalar::random(&mut rng);
+        let blinder = Scalar::random(&mut rng);
+        let session_id = [1u8; 32];
 
-            ResultValue::Scalar(out)
-        });
+        let comm = HashCommitment::<Sha3_512>::commit(&session_id, 1, &value, &blinder);
+        assert!(HashCommitment::<Sha3_512>::verify(
+            &session_id,
+            1,
+            &value,
+            &blinder,
+            &comm
+        ));
+        assert!(!<HashCommitment>::verify(
+            &session_id,
+            1,
+            &value,
+            &blinder,
+            &comm
+        ));
+    }
 
-        HashCommitmentResult {
-            value,
-            blinder,
-            commitment: comm,
-        }
+    /// Tests committing to a value under blake3
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_hash_commitment_blake3() {
+        let mut rng = thread_rng();
+        let value = Scalar::random(&mut rng);
+        let other = value + Scalar::one();
+        let blinder = Scalar::random(&mut rng);
+        let session_id = [1u8; 32];
+
+        let comm = HashCommitment::<blake3::Hasher>::commit(&session_id, 1, &value, &blinder);
+        assert!(HashCommitment::<blake3::Hasher>::verify(
+            &session_id,
+            1,
+            &value,
+            &blinder,
+            &comm
+        ));
+        assert!(!HashCommitment::<blake3::Hasher>::verify(
+            &session_id,
+            1,
+            &other,
+            &blinder,
+            &comm
+        ));
+        assert!(!<HashCommitment>::verify(
+            &session_id,
+            1,
+            &value,
+            &blinder,
+            &comm
+        ));
+    }
+
+    /// Tests that commitments to scalars do not verify for a different value
+    #[test]
+    fn test_scalar_commitments() {
+        let mut rng = thread_rng();
+        let value = Scalar::random(&mut rng);
+        let other = value + Scalar::one();
+        let blinder = Scalar::random(&mut rng);
+        let session_id = [1u8; 32];
+
+        let comm = PedersenCommitment::commit(&session_id, 1, &value, &blinder);
+        assert!(PedersenCommitment::verify(
+            &session_id,
+            1,
+            &value,
+            &blinder,
+            &comm
+        ));
+        assert!(!PedersenCommitment::verify(
+            &session_id,
+            1,
+            &other,
+            &blinder,
+            &comm
+        ));
+
+        let comm = <HashCommitment>::commit(&session_id, 1, &value, &blinder);
+        assert!(<HashCommitment>::verify(
+            &session_id,
+            1,
+            &value,
+            &blinder,
+            &comm
+        ));
+        assert!(!<HashCommitment>::verify(
+            &session_id,
+            1,
+            &other,
+            &blinder,
+            &comm
+        ));
     }
 }
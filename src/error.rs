@@ -1,27 +1,144 @@
 //! Errors defined across the MPC implementation
-use std::{error::Error, fmt::Display};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::Duration,
+};
 
 use quinn::{ConnectError, ConnectionError};
 
+use crate::{fabric::ResultId, network::PartyId};
+
 /// An application level error that results from an error deeper in the MPC stack
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MpcError {
     /// An error on the network
     NetworkError(MpcNetworkError),
+    /// An error sending the value of a result to the peer
+    SendFailed {
+        /// The result whose value was being sent
+        result_id: ResultId,
+        /// The label of the operation that produced the result, if any
+        label: Option<String>,
+        /// The underlying network error
+        error: MpcNetworkError,
+    },
     /// An error authenticating an MPC value
     AuthenticationError,
+    /// An error authenticating an MPC value in which the party that cheated is identified,
+    /// because its revealed share of the MAC check does not open its commitment
+    MacCheckFailed {
+        /// The party whose share of the MAC check was inconsistent
+        culprit: PartyId,
+    },
+    /// An error indicating that the parties hold different values after a broadcast
+    BroadcastError,
+    /// An error indicating that the peer closed the connection or stopped responding
+    PeerDisconnected,
+    /// An error indicating that no message was received from the peer within the liveness
+    /// timeout
+    Timeout(Duration),
+    /// An error indicating that the peer aborted the computation, holding the peer's reason
+    PeerAborted(String),
+    /// An error indicating that the local party aborted the computation, holding the reason
+    Aborted(String),
     /// An error resulting from visibility mismatch between two values
     VisibilityError(String),
     /// An error performing an arithmetic operation
     ArithmeticError(String),
+    /// An error indicating that the shared value source ran out of preprocessed values
+    PreprocessingExhausted,
+    /// An error indicating that a result holds a value of a different type than it was
+    /// awaited as
+    TypeMismatch {
+        /// The result that was awaited
+        result_id: ResultId,
+        /// The type the result was awaited as
+        expected: &'static str,
+        /// The type of the value the result holds
+        found: &'static str,
+    },
+    /// An error indicating that a gate's function panicked
+    GatePanicked {
+        /// The message the gate panicked with
+        message: String,
+        /// The first result of the gate
+        result_id: ResultId,
+        /// The label of the gate, if any
+        label: Option<String>,
+    },
+}
+
+/// Format a result ID along with the label of the operation that produced it, if any
+fn fmt_result(f: &mut Formatter<'_>, result_id: ResultId, label: &Option<String>) -> FmtResult {
+    match label {
+        Some(label) => write!(f, "result {result_id} ({label})"),
+        None => write!(f, "result {result_id}"),
+    }
 }
 
 impl Display for MpcError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            MpcError::NetworkError(err) => write!(f, "network error: {err}"),
+            MpcError::SendFailed {
+                result_id,
+                label,
+                error,
+            } => {
+                write!(f, "error sending ")?;
+                fmt_result(f, *result_id, label)?;
+                write!(f, " to the peer: {error}")
+            }
+            MpcError::AuthenticationError => write!(f, "MAC check failed"),
+            MpcError::MacCheckFailed { culprit } => {
+                write!(
+                    f,
+                    "MAC check failed, party {culprit} did not open its commitment"
+                )
+            }
+            MpcError::BroadcastError => write!(f, "parties hold different broadcast values"),
+            MpcError::PeerDisconnected => write!(f, "peer disconnected"),
+            MpcError::Timeout(timeout) => {
+                write!(
+                    f,
+                    "peer sent no message within the liveness timeout of {timeout:?}"
+                )
+            }
+            MpcError::PeerAborted(reason) => write!(f, "peer aborted the computation: {reason}"),
+            MpcError::Aborted(reason) => write!(f, "computation aborted: {reason}"),
+            MpcError::VisibilityError(msg) => write!(f, "visibility error: {msg}"),
+            MpcError::ArithmeticError(msg) => write!(f, "arithmetic error: {msg}"),
+            MpcError::PreprocessingExhausted => write!(f, "preprocessed values exhausted"),
+            MpcError::TypeMismatch {
+                result_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "result {result_id} holds a {found}, awaited as {expected}"
+            ),
+            MpcError::GatePanicked {
+                message,
+                result_id,
+                label,
+            } => {
+                write!(f, "gate computing ")?;
+                fmt_result(f, *result_id, label)?;
+                write!(f, " panicked: {message}")
+            }
+        }
+    }
+}
+
+impl Error for MpcError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MpcError::NetworkError(err) | MpcError::SendFailed { error: err, .. } => Some(err),
+            _ => None,
+        }
     }
 }
-impl Error for MpcError {}
 
 /// An error on the MPC network during communication
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -30,6 +147,9 @@ pub enum MpcNetworkError {
     SendError(String),
     /// An error receiving a value from the counterparty
     RecvError(String),
+    /// An error emitted when no message is received from the counterparty within the
+    /// given timeout
+    Timeout(Duration),
     /// An error setting up the underlying connection
     ConnectionSetupError(SetupError),
     /// An error tearing down the underlying connection
@@ -39,14 +159,40 @@ pub enum MpcNetworkError {
     NetworkUninitialized,
     /// An error serializing a value
     SerializationError(String),
+    /// An error emitted when the peer sends a payload that does not match the shape
+    /// expected by the receiving result
+    InvalidPayload(String),
 }
 
 impl Display for MpcNetworkError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            MpcNetworkError::SendError(msg) => write!(f, "send error: {msg}"),
+            MpcNetworkError::RecvError(msg) => write!(f, "receive error: {msg}"),
+            MpcNetworkError::Timeout(timeout) => {
+                write!(f, "no message received within {timeout:?}")
+            }
+            MpcNetworkError::ConnectionSetupError(err) => {
+                write!(f, "error setting up the connection: {err}")
+            }
+            MpcNetworkError::ConnectionTeardownError => {
+                write!(f, "error tearing down the connection")
+            }
+            MpcNetworkError::NetworkUninitialized => write!(f, "network is not connected"),
+            MpcNetworkError::SerializationError(msg) => write!(f, "serialization error: {msg}"),
+            MpcNetworkError::InvalidPayload(msg) => write!(f, "invalid payload: {msg}"),
+        }
+    }
+}
+
+impl Error for MpcNetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MpcNetworkError::ConnectionSetupError(err) => Some(err),
+            _ => None,
+        }
     }
 }
-impl Error for MpcNetworkError {}
 
 /// An error setting up the MPC fabric
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -61,4 +207,80 @@ pub enum SetupError {
     NoIncomingConnection,
     /// An error setting up the QUIC server on the local node
     ServerSetupError,
+    /// An error authenticating the peer's identity during the handshake
+    PeerAuthenticationError,
+    /// An error in the gRPC transport while connecting to the peer
+    #[cfg(feature = "grpc")]
+    GrpcError(String),
+    /// An error connecting to or through the relay
+    #[cfg(feature = "relay")]
+    RelayError(String),
+}
+
+impl Display for SetupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SetupError::ConnectError(err) => write!(f, "error connecting to the peer: {err}"),
+            SetupError::ConnectionError(err) => write!(f, "connection error: {err}"),
+            SetupError::KeygenError => write!(f, "error generating the TLS certificate"),
+            SetupError::NoIncomingConnection => write!(f, "no incoming connection from the peer"),
+            SetupError::ServerSetupError => write!(f, "error setting up the local server"),
+            SetupError::PeerAuthenticationError => {
+                write!(f, "error authenticating the peer's identity")
+            }
+            #[cfg(feature = "grpc")]
+            SetupError::GrpcError(msg) => write!(f, "gRPC error: {msg}"),
+            #[cfg(feature = "relay")]
+            SetupError::RelayError(msg) => write!(f, "relay error: {msg}"),
+        }
+    }
+}
+
+impl Error for SetupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SetupError::ConnectError(err) => Some(err),
+            SetupError::ConnectionError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{error::Error, time::Duration};
+
+    use super::{MpcError, MpcNetworkError};
+
+    /// Tests the messages of errors and the errors they wrap
+    #[test]
+    fn test_error_context() {
+        let err = MpcError::SendFailed {
+            result_id: 5,
+            label: Some("open".to_string()),
+            error: MpcNetworkError::SendError("closed".to_string()),
+        };
+        assert_eq!(
+            err.to_string(),
+            "error sending result 5 (open) to the peer: send error: closed"
+        );
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            MpcNetworkError::SendError("closed".to_string()).to_string()
+        );
+
+        let err = MpcError::GatePanicked {
+            message: "failed".to_string(),
+            result_id: 7,
+            label: None,
+        };
+        assert_eq!(err.to_string(), "gate computing result 7 panicked: failed");
+        assert!(err.source().is_none());
+
+        let err = MpcError::Timeout(Duration::from_secs(1));
+        assert_eq!(
+            err.to_string(),
+            "peer sent no message within the liveness timeout of 1s"
+        );
+    }
 }
@@ -5,51 +5,97 @@
 //! cleaner interface for consumers of the library; i.e. clients do not have to hold onto
 //! references of the network layer or the beaver sources to allocate values.
 
+mod arena;
+mod config;
+mod cost;
 mod executor;
+mod metrics;
 mod network_sender;
+pub(crate) mod parallel;
+mod profile;
 mod result;
+mod simulation;
+mod transcript;
+mod worker;
 
+pub use config::{FabricConfig, SchedulingPolicy, SecurityMode};
+pub use cost::CostEstimate;
 #[cfg(feature = "benchmarks")]
-pub use executor::{Executor, ExecutorMessage};
+pub use executor::{Executor, ExecutorMessage, ExecutorQueue};
 #[cfg(not(feature = "benchmarks"))]
-use executor::{Executor, ExecutorMessage};
-use rand::thread_rng;
-pub use result::{ResultHandle, ResultId, ResultValue};
+use executor::{Executor, ExecutorMessage, ExecutorQueue};
+pub use metrics::FabricMetrics;
+#[cfg(any(feature = "test_helpers", test))]
+pub(crate) use network_sender::HEARTBEAT_RESULT_ID;
+pub use profile::{GateProfile, OperationKind, TimingHistogram, HISTOGRAM_BUCKETS};
+use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+pub use result::{
+    join_results, BarrierResult, BroadcastResult, Custom, CustomResult, CustomValue,
+    FallibleResultHandle, JoinResults, OwnedResultHandle, ResultHandle, ResultId, ResultValue,
+};
+pub use simulation::SimulationFabric;
+pub use transcript::{SignedTranscript, Transcript, TranscriptEntry, TranscriptEntryKind};
+pub use worker::ShutdownHandle;
 
-use futures::executor::block_on;
+use futures::{channel::oneshot, executor::block_on, Future};
+use sha3::{Digest, Sha3_256, Sha3_512};
 use tracing::log;
 
 use crossbeam::queue::SegQueue;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     fmt::{Debug, Formatter, Result as FmtResult},
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicU64, AtomicUsize, Ordering},
         Arc, Mutex, RwLock,
     },
     task::Waker,
 };
-use tokio::sync::broadcast::{self, Sender as BroadcastSender};
-use tokio::sync::mpsc::UnboundedSender as TokioSender;
+use tokio::{
+    runtime::Handle,
+    sync::{
+        broadcast::{self, Sender as BroadcastSender},
+        mpsc::{self, UnboundedSender},
+    },
+};
 
-use itertools::Itertools;
+use itertools::{izip, Itertools};
 
 use crate::{
     algebra::{
         authenticated_scalar::AuthenticatedScalarResult,
         authenticated_stark_point::AuthenticatedStarkPointResult,
+        fixed_base::FixedBaseTable,
+        mac_check::decode_mac_check,
         mpc_scalar::MpcScalarResult,
         mpc_stark_point::MpcStarkPointResult,
         scalar::{BatchScalarResult, Scalar, ScalarResult},
-        stark_curve::{BatchStarkPointResult, StarkPoint, StarkPointResult},
+        stark_curve::{sub_points, BatchStarkPointResult, StarkPoint, StarkPointResult},
     },
-    beaver::SharedValueSource,
+    beaver::{FallibleSharedValueSource, PreprocessingSpec, ZeroizingSource},
     buffer::GrowableBuffer,
-    network::{MpcNetwork, NetworkOutbound, NetworkPayload, PartyId},
-    Shared, PARTY0,
+    commitment::HashCommitment,
+    error::{MpcError, MpcNetworkError},
+    gadgets::{bits::AuthenticatedBit, linear_combination},
+    network::{
+        MpcNetwork, NetworkOutbound, NetworkPayload, PartyId, PayloadShape, PayloadType, SessionId,
+    },
+    Shared, PARTY0, PARTY1,
 };
 
-use self::{network_sender::NetworkSender, result::OpResult};
+use self::{
+    cost::{CostRecorder, DryRunSource},
+    metrics::MetricsCounters,
+    network_sender::{
+        abort_message, outbound_channel, FlushRequest, InboundPayloads, NetworkSender,
+        OutboundSender, ERR_FLUSH_AFTER_EXIT,
+    },
+    profile::GateProfiler,
+    result::OpResult,
+    simulation::SimulatedPeer,
+    transcript::TranscriptRecorder,
+    worker::{spawn_worker, WorkerThread},
+};
 
 /// The result id that is hardcoded to zero
 const RESULT_ZERO: ResultId = 0;
@@ -59,44 +105,95 @@ const RESULT_ONE: ResultId = 1;
 const RESULT_IDENTITY: ResultId = 2;
 
 /// The number of constant results allocated in the fabric, i.e. those defined above
-const N_CONSTANT_RESULTS: usize = 3;
+pub(crate) const N_CONSTANT_RESULTS: usize = 3;
+
+/// A cryptographically secure RNG that the fabric samples its local randomness from,
+/// e.g. the masks used to secret share values and the blinders of commitments
+pub trait FabricRng: RngCore + CryptoRng + Send {}
+impl<R: RngCore + CryptoRng + Send> FabricRng for R {}
 
-/// The default size hint to give the fabric for buffer pre-allocation
-const DEFAULT_SIZE_HINT: usize = 10_000;
+/// The number of bytes each party contributes to the seed of the correlated mask PRG
+const MASK_SEED_CONTRIBUTION_BYTES: usize = 32;
+/// The domain separator for the seed of the correlated mask PRG
+const MASK_SEED_DOMAIN: &[u8] = b"mpc-stark-correlated-mask-seed";
+
+/// The number of bytes in the salt of a committed exchange
+const COMMITTED_EXCHANGE_SALT_BYTES: usize = 32;
+/// The domain separator for the commitments of a committed exchange
+const COMMITTED_EXCHANGE_DOMAIN: &[u8] = b"mpc-stark-committed-exchange";
+
+/// The domain separator for the coefficients of the check that shared bits are binary
+const BIT_CHECK_DOMAIN: &[u8] = b"mpc-stark-shared-bits";
 
 /// A type alias for the identifier used for a gate
 pub type OperationId = usize;
+/// The number of bytes a result ID is encoded in when exchanged with the peer
+const BYTES_PER_RESULT_ID: usize = 8;
+
+/// A hook called with each operation as it is scheduled, see `MpcFabric::set_op_observer`
+pub type OpObserver = Arc<dyn Fn(&Operation) + Send + Sync>;
 
 /// An operation within the network, describes the arguments and function to evaluate
 /// once the arguments are ready
 ///
 /// `N` represents the number of results that this operation outputs
-#[derive(Clone)]
+///
+/// Operations own the closures they evaluate, which are called at most once, so they are
+/// deliberately not `Clone`
 pub struct Operation {
     /// Identifier of the result that this operation emits
     id: OperationId,
-    /// The result ID of the first result in the outputs
-    result_id: ResultId,
-    /// The number of outputs this operation produces
-    output_arity: usize,
+    /// The IDs of the results this operation produces
+    ///
+    /// Recycled result IDs are handed out individually, so the IDs need not be contiguous
+    result_ids: Vec<ResultId>,
     /// The number of arguments that are still in-flight for this operation
     inflight_args: usize,
     /// The IDs of the inputs to this operation
     args: Vec<ResultId>,
     /// The type of the operation
     op_type: OperationType,
+    /// The label the operation was allocated with, if any
+    label: Option<Arc<str>>,
 }
 
 impl Operation {
+    /// Get the ID of the operation
+    pub fn id(&self) -> OperationId {
+        self.id
+    }
+
+    /// Get the kind of the operation
+    pub fn kind(&self) -> OperationKind {
+        match self.op_type {
+            OperationType::Gate { .. } => OperationKind::Gate,
+            OperationType::GateBatch { .. } => OperationKind::GateBatch,
+            OperationType::Network { .. } => OperationKind::Network,
+        }
+    }
+
+    /// Get the IDs of the results the operation takes as arguments
+    pub fn args(&self) -> &[ResultId] {
+        &self.args
+    }
+
     /// Get the result IDs for an operation
     pub fn result_ids(&self) -> Vec<ResultId> {
-        (self.result_id..self.result_id + self.output_arity).collect_vec()
+        self.result_ids.clone()
+    }
+
+    /// Get the label the operation was allocated with, if any
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
     }
 }
 
 impl Debug for Operation {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "Operation {}", self.id)
+        match self.label.as_ref() {
+            Some(label) => write!(f, "Operation {} ({label})", self.id),
+            None => write!(f, "Operation {}", self.id),
+        }
     }
 }
 
@@ -121,14 +218,6 @@ pub enum OperationType {
     },
 }
 
-/// A clone implementation, never concretely called but used as a Marker type to allow
-/// pre-allocating buffer space for `Operation`s
-impl Clone for OperationType {
-    fn clone(&self) -> Self {
-        panic!("cannot clone `OperationType`")
-    }
-}
-
 impl Debug for OperationType {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
@@ -139,6 +228,194 @@ impl Debug for OperationType {
     }
 }
 
+/// The result IDs of an opened value whose MAC check has been deferred
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DeferredOpening {
+    /// The ID of the opened value
+    value: ResultId,
+    /// The ID of the value's public modifier
+    public_modifier: ResultId,
+    /// The ID of the local share of the value's MAC
+    mac: ResultId,
+}
+
+/// The openings whose MAC checks have been deferred until the fabric is finalized
+#[derive(Debug, Default)]
+pub(crate) struct DeferredOpenings {
+    /// The opened scalars
+    scalars: Vec<DeferredOpening>,
+    /// The opened points
+    points: Vec<DeferredOpening>,
+}
+
+impl DeferredOpenings {
+    /// The number of openings that have not yet been checked
+    fn len(&self) -> usize {
+        self.scalars.len() + self.points.len()
+    }
+
+    /// Whether there are no unchecked openings
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The IDs of the opened values
+    fn value_ids(&self) -> Vec<ResultId> {
+        self.scalars
+            .iter()
+            .chain(self.points.iter())
+            .map(|opening| opening.value)
+            .collect_vec()
+    }
+
+    /// The IDs of all results the openings refer to
+    fn result_ids(&self) -> Vec<ResultId> {
+        self.scalars
+            .iter()
+            .chain(self.points.iter())
+            .flat_map(|opening| [opening.value, opening.public_modifier, opening.mac])
+            .collect_vec()
+    }
+}
+
+/// The result IDs of an authenticated value allocated during preprocessing
+#[derive(Clone, Copy, Debug)]
+struct PreprocessedValue {
+    /// The ID of the local share of the value
+    share: ResultId,
+    /// The ID of the local share of the value's MAC
+    mac: ResultId,
+}
+
+/// The authenticated material allocated by `MpcFabric::preprocess` that has not yet been
+/// consumed by the online phase
+#[derive(Debug, Default)]
+pub(crate) struct PreprocessedPool {
+    /// The beaver triplets
+    triples: VecDeque<[PreprocessedValue; 3]>,
+    /// The shared bits
+    bits: VecDeque<PreprocessedValue>,
+    /// The multiplicative inverse pairs
+    inverse_pairs: VecDeque<[PreprocessedValue; 2]>,
+    /// The shared single values
+    values: VecDeque<PreprocessedValue>,
+}
+
+/// A PRG seeded with a value known to both parties, from which the masks of shared values
+/// are derived
+///
+/// Both parties derive the same mask for a shared value, so the sender holds its value minus
+/// the mask and the receiver holds the mask without the sender sending the receiver its share
+#[derive(Debug)]
+pub(crate) struct CorrelatedMaskPrg {
+    /// The ID of the PRG's seed, agreed on by the parties when the fabric is constructed
+    seed: ResultId,
+    /// The index of the next mask to derive
+    next_index: AtomicU64,
+}
+
+impl CorrelatedMaskPrg {
+    /// Agree on a seed with the peer, each party contributes random bytes and the seed is the
+    /// hash of both contributions under the session ID
+    ///
+    /// The contributions are committed to before they are revealed, so that neither party
+    /// can choose its contribution after seeing its peer's
+    fn setup(fabric: &MpcFabric) -> Self {
+        let mut contribution = vec![0u8; MASK_SEED_CONTRIBUTION_BYTES];
+        fabric
+            .inner
+            .rng
+            .lock()
+            .expect("rng poisoned")
+            .fill_bytes(&mut contribution);
+
+        let my_contribution: ResultHandle<Vec<u8>> = ResultHandle::new(
+            fabric
+                .inner
+                .allocate_value(ResultValue::Bytes(contribution)),
+            fabric.clone(),
+        );
+        let peer_contribution = fabric.exchange_value_committed(my_contribution.clone());
+
+        let party_id = fabric.party_id();
+        let session_id = fabric.session_id();
+        let seed: ResultHandle<Vec<u8>> = fabric.new_gate_op(
+            vec![my_contribution.id(), peer_contribution.id()],
+            move |mut args| {
+                let mine: Vec<u8> = args.remove(0).into();
+                let theirs: Vec<u8> = args.remove(0).into();
+                let (p0, p1) = if party_id == PARTY0 {
+                    (mine, theirs)
+                } else {
+                    (theirs, mine)
+                };
+
+                let mut hasher = Sha3_256::new();
+                hasher.update(MASK_SEED_DOMAIN);
+                hasher.update(session_id);
+                hasher.update(p0);
+                hasher.update(p1);
+                ResultValue::Bytes(hasher.finalize().to_vec())
+            },
+        );
+
+        Self {
+            seed: seed.id(),
+            next_index: AtomicU64::new(0),
+        }
+    }
+
+    /// Reserve the indices of the next `n` masks, returning the first index
+    ///
+    /// Both parties must share values in the same order to reserve the same indices
+    fn reserve(&self, n: usize) -> u64 {
+        self.next_index.fetch_add(n as u64, Ordering::Relaxed)
+    }
+
+    /// Derive the mask at the given index from the seed
+    ///
+    /// A 512 bit digest is reduced into the field so that the mask is statistically close
+    /// to uniform
+    fn derive_mask(seed: &[u8], index: u64) -> Scalar {
+        let mut hasher = Sha3_512::new();
+        hasher.update(seed);
+        hasher.update(index.to_be_bytes());
+        Scalar::from_be_bytes_mod_order(&hasher.finalize())
+    }
+}
+
+/// Compute a party's commitment to a value in a committed exchange
+///
+/// The commitment is bound to the committing party so that a party cannot mirror its peer's
+/// commitment and reveal
+fn committed_exchange_digest(
+    session_id: &SessionId,
+    party_id: PartyId,
+    payload: &NetworkPayload,
+    salt: &[u8],
+) -> Vec<u8> {
+    let serialized = serde_json::to_vec(payload).expect("error serializing payload");
+
+    let mut hasher = Sha3_256::new();
+    hasher.update(COMMITTED_EXCHANGE_DOMAIN);
+    hasher.update(session_id);
+    hasher.update(party_id.to_be_bytes());
+    hasher.update(salt);
+    hasher.update(serialized);
+    hasher.finalize().to_vec()
+}
+
+tokio::task_local! {
+    /// The label of the current task's innermost label scope, applied to the operations the
+    /// task allocates
+    static ACTIVE_LABEL: Option<Arc<str>>;
+}
+
+/// The label of the current task's innermost label scope, if any
+pub(crate) fn active_label() -> Option<Arc<str>> {
+    ACTIVE_LABEL.try_with(Clone::clone).ok().flatten()
+}
+
 /// A fabric for the MPC protocol, defines a dependency injection layer that dynamically schedules
 /// circuit gate evaluations onto the network to be executed
 ///
@@ -170,6 +447,22 @@ pub struct MpcFabric {
     /// The shutdown channel, made publicly available for benchmark mocking
     #[cfg(feature = "benchmarks")]
     pub shutdown: BroadcastSender<()>,
+    /// Resolves once the fabric's workers have exited
+    workers: ShutdownHandle,
+    /// The PRG from which the masks of shared values are derived, if the fabric uses
+    /// correlated masks
+    mask_prg: Option<Arc<CorrelatedMaskPrg>>,
+}
+
+/// Shuts down a fabric when dropped, including while unwinding from a panic
+struct ShutdownGuard(Option<MpcFabric>);
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        if let Some(fabric) = self.0.take() {
+            fabric.shutdown();
+        }
+    }
 }
 
 impl Debug for MpcFabric {
@@ -184,20 +477,61 @@ impl Debug for MpcFabric {
 pub struct FabricInner {
     /// The ID of the local party in the MPC execution
     party_id: u64,
+    /// The identity key of the peer, if the network authenticated it
+    peer_identity: Option<StarkPoint>,
+    /// The ID of the session, used to domain separate commitments and challenges
+    session_id: SessionId,
     /// The next identifier to assign to a result
     next_result_id: Arc<AtomicUsize>,
+    /// The IDs of released results that both parties have dropped, handed out before new IDs
+    recycled_result_ids: Arc<SegQueue<ResultId>>,
+    /// The IDs of released results that the executor has dropped, awaiting recycling
+    dropped_result_ids: Arc<SegQueue<ResultId>>,
     /// The next identifier to assign to an operation
     next_op_id: Arc<AtomicUsize>,
     /// The completed results of operations
     results: Shared<GrowableBuffer<OpResult>>,
     /// A map of operations to wakers of tasks that are waiting on the operation to complete
     wakers: Shared<HashMap<ResultId, Vec<Waker>>>,
+    /// The values of results taken by their handles, held by the fabric until the handle
+    /// collects them
+    handoffs: Shared<HashMap<ResultId, ResultValue>>,
+    /// The error that the computation failed with, if any
+    ///
+    /// Once set, all results that are still pending resolve to this error
+    failure: Shared<Option<MpcError>>,
     /// A sender to the executor
-    execution_queue: Arc<SegQueue<ExecutorMessage>>,
+    execution_queue: Arc<ExecutorQueue>,
+    /// The validator for payloads received from the peer
+    inbound: Arc<InboundPayloads>,
     /// The underlying queue to the network
-    outbound_queue: TokioSender<NetworkOutbound>,
-    /// The underlying shared randomness source
-    beaver_source: Arc<Mutex<Box<dyn SharedValueSource>>>,
+    outbound_queue: OutboundSender,
+    /// The queue of flush requests to the network sender, if the fabric has a network
+    flush_requests: Option<UnboundedSender<FlushRequest>>,
+    /// The underlying shared randomness source, zeroized when the fabric is dropped
+    beaver_source: Arc<Mutex<ZeroizingSource>>,
+    /// The RNG the fabric samples local randomness from
+    rng: Arc<Mutex<Box<dyn FabricRng>>>,
+    /// The precomputed tables of the fixed bases, starting with the generator's
+    fixed_bases: Shared<Vec<Arc<FixedBaseTable>>>,
+    /// The openings whose MAC checks are deferred, if the fabric defers MAC checks
+    deferred_openings: Option<Arc<Mutex<DeferredOpenings>>>,
+    /// The preprocessed material that the online phase draws from before the beaver source
+    preprocessed: Arc<Mutex<PreprocessedPool>>,
+    /// The adversary model the fabric defends against
+    security_mode: SecurityMode,
+    /// The execution metrics of the fabric, if it records them
+    metrics: Option<Arc<MetricsCounters>>,
+    /// The profiler of the gates the executor evaluates, if the fabric profiles gates
+    profiler: Option<Arc<GateProfiler>>,
+    /// The recorder of the circuit's cost, if the fabric is a dry run
+    cost: Option<Arc<CostRecorder>>,
+    /// The simulated peer, if the fabric is a simulation
+    simulated_peer: Option<Arc<SimulatedPeer>>,
+    /// The recorder of the fabric's transcript, if the fabric records one
+    transcript: Option<Arc<TranscriptRecorder>>,
+    /// The hook called with each operation as it is scheduled, if any
+    op_observer: Shared<Option<OpObserver>>,
 }
 
 impl Debug for FabricInner {
@@ -208,11 +542,11 @@ impl Debug for FabricInner {
 
 impl FabricInner {
     /// Constructor
-    pub fn new<S: 'static + SharedValueSource>(
+    pub(crate) fn new<S: 'static + FallibleSharedValueSource>(
         size_hint: usize,
         party_id: u64,
-        execution_queue: Arc<SegQueue<ExecutorMessage>>,
-        outbound_queue: TokioSender<NetworkOutbound>,
+        execution_queue: Arc<ExecutorQueue>,
+        outbound_queue: OutboundSender,
         beaver_source: S,
     ) -> Self {
         // Allocate a zero and a one as well as the curve identity in the fabric to begin,
@@ -237,13 +571,32 @@ impl FabricInner {
 
         Self {
             party_id,
+            peer_identity: None,
+            session_id: SessionId::default(),
             next_result_id,
+            recycled_result_ids: Arc::new(SegQueue::new()),
+            dropped_result_ids: Arc::new(SegQueue::new()),
             next_op_id,
             results: Arc::new(RwLock::new(results)),
             wakers: Arc::new(RwLock::new(HashMap::new())),
+            handoffs: Arc::new(RwLock::new(HashMap::new())),
+            failure: Arc::new(RwLock::new(None)),
+            inbound: Arc::new(InboundPayloads::new(execution_queue.clone())),
             execution_queue,
             outbound_queue,
-            beaver_source: Arc::new(Mutex::new(Box::new(beaver_source))),
+            flush_requests: None,
+            beaver_source: Arc::new(Mutex::new(ZeroizingSource::new(beaver_source))),
+            rng: Arc::new(Mutex::new(Box::new(StdRng::from_entropy()))),
+            fixed_bases: Arc::new(RwLock::new(vec![FixedBaseTable::generator()])),
+            deferred_openings: None,
+            preprocessed: Arc::new(Mutex::new(PreprocessedPool::default())),
+            security_mode: SecurityMode::default(),
+            metrics: None,
+            profiler: None,
+            cost: None,
+            simulated_peer: None,
+            transcript: None,
+            op_observer: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -252,13 +605,57 @@ impl FabricInner {
         self.execution_queue.push(ExecutorMessage::Shutdown)
     }
 
+    /// Abort the computation, notifying the peer and cancelling the pending operations
+    pub(crate) fn abort(&self, reason: String) {
+        log::warn!("aborting the computation: {reason}");
+
+        // A dry run has no peer to notify, nor does a fabric that lost its connection
+        if self.cost.is_none() && !self.outbound_queue.is_failed() {
+            if let Err(e) = self.outbound_queue.send(abort_message(&reason)) {
+                log::error!("error sending abort to counterparty: {e:?}");
+            }
+        }
+
+        // Stop network operations before the executor cancels the pending operations
+        self.outbound_queue.fail(MpcError::Aborted(reason.clone()));
+        self.execution_queue.push(ExecutorMessage::Abort(reason));
+    }
+
+    /// Release a result after the given number of uses, or after the operations already
+    /// allocated on it if no count is given
+    pub(crate) fn release_result(&self, id: ResultId, uses: Option<usize>) {
+        // The constant results are shared by all circuits, and a dry run stores no results
+        if id < N_CONSTANT_RESULTS || self.cost.is_some() {
+            return;
+        }
+
+        self.execution_queue.push(ExecutorMessage::Release {
+            id,
+            uses,
+            handoff: false,
+        })
+    }
+
+    /// Release a result once the operations already allocated on it have executed, handing
+    /// its value to the handle that took it rather than dropping it
+    pub(crate) fn take_result(&self, id: ResultId) {
+        self.execution_queue.push(ExecutorMessage::Release {
+            id,
+            uses: None,
+            handoff: true,
+        })
+    }
+
     /// -----------
     /// | Getters |
     /// -----------
 
-    /// Increment the operation counter and return the existing value
+    /// Take a recycled result ID if any are available, otherwise increment the result counter
+    /// and return the existing value
     fn new_result_id(&self) -> ResultId {
-        self.next_result_id.fetch_add(1, Ordering::Relaxed)
+        self.recycled_result_ids
+            .pop()
+            .unwrap_or_else(|| self.next_result_id.fetch_add(1, Ordering::Relaxed))
     }
 
     /// Increment the operation counter and return the existing value
@@ -306,6 +703,14 @@ impl FabricInner {
         // Acquire locks
         let mut locked_results = self.results.write().expect("results poisoned");
 
+        // A simulation holds the plaintext of the value in place of a share
+        if self.simulated_peer.is_some() {
+            let id = self.new_result_id();
+            let value = SimulatedPeer::combine_shares(my_share, their_share);
+            locked_results.insert(id, OpResult { id, value });
+            return id;
+        }
+
         // Add my share to the results
         let id = self.new_result_id();
         locked_results.insert(
@@ -316,12 +721,29 @@ impl FabricInner {
             },
         );
 
-        // Send the counterparty their share
-        if let Err(e) = self.outbound_queue.send(NetworkOutbound {
+        // Send the counterparty their share, a dry run only records the send
+        if let Some(cost) = self.cost.as_ref() {
+            cost.record_share_sent();
+        } else if let Some(err) = self.outbound_queue.failure() {
+            log::error!("connection to the peer is lost, not sending share {id}");
+            self.execution_queue.push(ExecutorMessage::Error(err));
+        } else if let Err(e) = self.outbound_queue.send(NetworkOutbound {
             result_id: id,
             payload: their_share.into(),
         }) {
             log::error!("error sending share to counterparty: {e:?}");
+            let label = active_label().as_deref().map(str::to_string);
+            let err = MpcError::SendFailed {
+                result_id: id,
+                label,
+                error: e,
+            };
+            self.outbound_queue.fail(err.clone());
+            self.execution_queue.push(ExecutorMessage::Error(err));
+        } else {
+            self.record_labeled(active_label().as_deref(), |metrics| {
+                metrics.messages_sent += 1
+            });
         }
 
         id
@@ -330,21 +752,61 @@ impl FabricInner {
     /// Receive a value from a network operation initiated by a peer
     ///
     /// The peer will already send the value with the corresponding ID, so all that is needed
-    /// is to allocate a slot in the result buffer for the receipt
-    pub(crate) fn receive_value(&self) -> ResultId {
-        self.new_result_id()
+    /// is to allocate a slot in the result buffer for the receipt. The payload is validated
+    /// against the given shape when it arrives
+    pub(crate) fn receive_value(&self, shape: Option<PayloadShape>) -> ResultId {
+        if let Some(peer) = self.simulated_peer.as_ref() {
+            return peer.receive(self, shape);
+        }
+
+        let id = self.new_result_id();
+        match self.cost.as_ref() {
+            Some(cost) => cost.record_receive(id, shape),
+            None => {
+                self.inbound.expect(id, shape);
+                self.record_labeled(active_label().as_deref(), |metrics| {
+                    metrics.messages_received += 1
+                });
+            }
+        }
+
+        id
+    }
+
+    /// Record an event in the metrics of the given label, if the fabric records metrics and
+    /// the label is set
+    pub(crate) fn record_labeled<F: FnOnce(&mut FabricMetrics)>(
+        &self,
+        label: Option<&str>,
+        record: F,
+    ) {
+        if let (Some(metrics), Some(label)) = (self.metrics.as_ref(), label) {
+            metrics.record_labeled(label, record);
+        }
     }
 
     // --------------
     // | Operations |
     // --------------
 
-    /// Allocate a new in-flight gate operation in the fabric
+    /// Allocate a new in-flight gate operation in the fabric, labeled with the active label
+    /// scope's label if any
     pub(crate) fn new_op(
         &self,
         args: Vec<ResultId>,
         output_arity: usize,
         op_type: OperationType,
+    ) -> Vec<ResultId> {
+        self.new_labeled_op(args, output_arity, op_type, active_label())
+    }
+
+    /// Allocate a new in-flight gate operation in the fabric with the given label
+    pub(crate) fn new_labeled_op(
+        &self,
+        args: Vec<ResultId>,
+        output_arity: usize,
+        op_type: OperationType,
+        label: Option<Arc<str>>,
     ) -> Vec<ResultId> {
         if matches!(op_type, OperationType::Gate { .. }) {
             assert_eq!(output_arity, 1, "gate operations must have arity 1");
@@ -355,17 +817,38 @@ impl FabricInner {
             .map(|_| self.new_result_id())
             .collect_vec();
 
+        // A simulation evaluates network operations locally
+        let op_type = match self.simulated_peer.as_ref() {
+            Some(peer) => peer.simulate_op(op_type, ids[0]),
+            None => op_type,
+        };
+
         // Build the operation
         let op = Operation {
             id: self.new_op_id(),
-            result_id: ids[0],
-            output_arity,
+            result_ids: ids.clone(),
             args,
             inflight_args: 0,
             op_type,
+            label,
         };
 
+        if let Some(observer) = self.op_observer.read().expect("observer poisoned").as_ref() {
+            observer(&op);
+        }
+
+        // A dry run records the op in place of executing it
+        if let Some(cost) = self.cost.as_ref() {
+            let network = matches!(op.op_type, OperationType::Network { .. });
+            cost.record_op(&op.args, &ids, network, op.label.as_deref());
+            return ids;
+        }
+
         // Forward the op to the executor
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.record_op_allocated();
+        }
+        self.record_labeled(op.label.as_deref(), |metrics| metrics.ops_allocated += 1);
         self.execution_queue.push(ExecutorMessage::Op(op));
         ids
     }
@@ -373,66 +856,187 @@ impl FabricInner {
 
 impl MpcFabric {
     /// Constructor
-    pub fn new<N: 'static + MpcNetwork, S: 'static + SharedValueSource>(
+    pub fn new<N: 'static + MpcNetwork, S: 'static + FallibleSharedValueSource>(
         network: N,
         beaver_source: S,
     ) -> Self {
-        Self::new_with_size_hint(DEFAULT_SIZE_HINT, network, beaver_source)
+        Self::with_config(network, beaver_source, FabricConfig::default())
     }
 
     /// Constructor that takes an additional size hint, indicating how much buffer space
     /// the fabric should allocate for results. The size is given in number of gates
-    pub fn new_with_size_hint<N: 'static + MpcNetwork, S: 'static + SharedValueSource>(
+    pub fn new_with_size_hint<N: 'static + MpcNetwork, S: 'static + FallibleSharedValueSource>(
         size_hint: usize,
         network: N,
         beaver_source: S,
+    ) -> Self {
+        Self::with_config(
+            network,
+            beaver_source,
+            FabricConfig::default().with_size_hint(size_hint),
+        )
+    }
+
+    /// Constructor that applies the given configuration
+    ///
+    /// Must be called within a runtime context unless the configuration sets a runtime
+    pub fn with_config<N: 'static + MpcNetwork, S: 'static + FallibleSharedValueSource>(
+        network: N,
+        beaver_source: S,
+        config: FabricConfig,
     ) -> Self {
         // Build communication primitives
-        let execution_queue = Arc::new(SegQueue::new());
-        let (outbound_sender, outbound_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let execution_queue = Arc::new(ExecutorQueue::new());
+        let (outbound_sender, outbound_receiver) = outbound_channel(config.outbound_queue_bound);
         let (shutdown_sender, shutdown_receiver) = broadcast::channel(1 /* capacity */);
 
         // Build a fabric
-        let fabric = FabricInner::new(
-            size_hint,
+        let mut fabric = FabricInner::new(
+            config.size_hint,
             network.party_id(),
             execution_queue.clone(),
             outbound_sender,
             beaver_source,
         );
+        fabric.peer_identity = network.peer_identity();
+        fabric.session_id = network.session_id().unwrap_or_default();
+        fabric.security_mode = config.security_mode;
+        if let Some(rng) = config.rng {
+            fabric.rng = Arc::new(Mutex::new(rng));
+        }
+        if config.deferred_mac_check {
+            fabric.deferred_openings = Some(Arc::new(Mutex::new(DeferredOpenings::default())));
+        }
+        if config.metrics {
+            fabric.metrics = Some(Arc::new(MetricsCounters::default()));
+        }
+        if config.profile_gates {
+            fabric.profiler = Some(Arc::new(GateProfiler::default()));
+        }
+        if config.transcript {
+            fabric.transcript = Some(Arc::new(TranscriptRecorder::default()));
+        }
+
+        let (flush_sender, flush_receiver) = mpsc::unbounded_channel();
+        fabric.flush_requests = Some(flush_sender);
+
+        // Start an operator executor and a network sender
+        let mut executor = Executor::new(config.size_hint, execution_queue.clone(), fabric.clone())
+            .with_scheduling_policy(config.scheduling_policy)
+            .with_batch_size(config.executor_batch_size);
+        if let Some(parallelism) = config.gate_parallelism {
+            executor = executor.with_gate_parallelism(parallelism);
+        }
+        if let Some(timeout) = config.stall_timeout {
+            executor = executor.with_stall_timeout(timeout);
+        }
+        if let Some(coalescing) = config.open_coalescing {
+            executor = executor.with_open_coalescing(coalescing);
+        }
+
+        let runtime = config.runtime.unwrap_or_else(Handle::current);
+        let (executor_thread, network_thread) = if config.dedicated_threads {
+            #[cfg(feature = "thread_affinity")]
+            let (executor_core, network_core) = (config.executor_core, config.network_core);
+            #[cfg(not(feature = "thread_affinity"))]
+            let (executor_core, network_core) = (None, None);
+
+            (
+                WorkerThread::Dedicated {
+                    core: executor_core,
+                },
+                WorkerThread::Dedicated { core: network_core },
+            )
+        } else {
+            (WorkerThread::BlockingPool, WorkerThread::BlockingPool)
+        };
+
+        let executor_exit = spawn_worker("mpc-executor", &runtime, executor_thread, move || {
+            executor.run()
+        });
 
-        // Start a network sender and operator executor
         let network_sender = NetworkSender::new(
             outbound_receiver,
             execution_queue.clone(),
+            fabric.inbound.clone(),
             network,
+            config.liveness_timeout,
+            config.send_coalescing,
+            fabric.metrics.clone(),
             shutdown_receiver,
+            executor_exit.clone(),
+            flush_receiver,
         );
-        tokio::task::spawn_blocking(move || block_on(network_sender.run()));
-
-        let executor = Executor::new(size_hint, execution_queue, fabric.clone());
-        tokio::task::spawn_blocking(move || executor.run());
+        let network_sender_exit =
+            spawn_worker("mpc-network-sender", &runtime, network_thread, move || {
+                block_on(network_sender.run())
+            });
 
         // Create the fabric and fill in the MAC key after
         let mut self_ = Self {
             inner: Arc::new(fabric.clone()),
             shutdown: shutdown_sender,
+            workers: ShutdownHandle::new(Some(executor_exit), Some(network_sender_exit)),
             mac_key: None,
+            mask_prg: None,
         };
 
-        // Sample a MAC key from the pre-shared values in the beaver source
-        let mac_key_id = fabric.allocate_value(ResultValue::Scalar(
-            fabric
-                .beaver_source
-                .lock()
-                .expect("beaver source poisoned")
-                .next_shared_value(),
-        ));
-        let mac_key = MpcScalarResult::new_shared(ResultHandle::new(mac_key_id, self_.clone()));
+        // Sample the MAC key share locally, or take it from the pre-shared values in the beaver
+        // source
+        let mac_key_share = if config.local_mac_key {
+            self_.allocate_scalar(self_.random_scalar())
+        } else {
+            let share = self_.sample_preprocessing(|source| source.try_next_shared_value_batch(1));
+            self_.allocate_preprocessed(share, 1).remove(0)
+        };
+        let mac_key = MpcScalarResult::new_shared(mac_key_share);
 
         // Set the MAC key
         self_.mac_key.replace(Arc::new(mac_key));
 
+        // Agree on a seed for the correlated mask PRG
+        if config.correlated_masks {
+            let mask_prg = CorrelatedMaskPrg::setup(&self_);
+            self_.mask_prg.replace(Arc::new(mask_prg));
+        }
+
+        self_
+    }
+
+    /// Construct a dry-run fabric, which records the cost of the circuits allocated in it
+    /// without evaluating any gates or communicating with a peer
+    ///
+    /// The fabric takes the view of the first party. Results allocated in a dry run never
+    /// resolve, so circuits must be allocated without awaiting any intermediate values. The
+    /// estimate is read via `MpcFabric::cost_estimate`
+    pub fn dry_run() -> Self {
+        let execution_queue = Arc::new(ExecutorQueue::new());
+        let (outbound_sender, _) = outbound_channel(None /* bound */);
+        let (shutdown_sender, _) = broadcast::channel(1 /* capacity */);
+
+        let cost = Arc::new(CostRecorder::default());
+        let mut fabric = FabricInner::new(
+            FabricConfig::default().size_hint,
+            PARTY0,
+            execution_queue,
+            outbound_sender,
+            DryRunSource::new(cost.clone()),
+        );
+        fabric.cost = Some(cost);
+
+        // The MAC key is allocated directly so that it is not recorded in the estimate
+        let mut self_ = Self {
+            inner: Arc::new(fabric),
+            shutdown: shutdown_sender,
+            workers: ShutdownHandle::new(None, None),
+            mac_key: None,
+            mask_prg: None,
+        };
+        let mac_key_share = self_.allocate_scalar(Scalar::zero());
+        self_
+            .mac_key
+            .replace(Arc::new(MpcScalarResult::new_shared(mac_key_share)));
+
         self_
     }
 
@@ -441,13 +1045,235 @@ impl MpcFabric {
         self.inner.party_id
     }
 
+    /// Get the ID the next result allocated in the fabric will take, if no recycled IDs are
+    /// available
+    ///
+    /// Result IDs are allocated in the same order by both parties, so tests may use this to
+    /// target the messages of the operations they allocate next
+    #[cfg(any(feature = "test_helpers", test))]
+    pub fn peek_next_result_id(&self) -> ResultId {
+        self.inner.next_result_id.load(Ordering::Relaxed)
+    }
+
+    /// Get the identity key of the peer, if the network authenticated it during setup
+    ///
+    /// Higher layers may use this to bind shares to a verified counterparty
+    pub fn peer_identity(&self) -> Option<StarkPoint> {
+        self.inner.peer_identity
+    }
+
+    /// Get the ID of the session the fabric is executing in
+    ///
+    /// This is all zeros if the network did not establish a session ID
+    pub fn session_id(&self) -> SessionId {
+        self.inner.session_id
+    }
+
+    /// Sample a random scalar from the fabric's RNG
+    pub(crate) fn random_scalar(&self) -> Scalar {
+        let mut rng = self.inner.rng.lock().expect("rng poisoned");
+        Scalar::random(&mut *rng)
+    }
+
+    /// Sample a batch of random scalars from the fabric's RNG
+    pub(crate) fn random_scalars(&self, n: usize) -> Vec<Scalar> {
+        let mut rng = self.inner.rng.lock().expect("rng poisoned");
+        (0..n).map(|_| Scalar::random(&mut *rng)).collect_vec()
+    }
+
+    /// Get the precomputed table of the group generator
+    pub fn generator_table(&self) -> Arc<FixedBaseTable> {
+        self.inner.fixed_bases.read().expect("fixed bases poisoned")[0].clone()
+    }
+
+    /// Register a fixed base whose multiples are precomputed
+    ///
+    /// Multiplications of the base by results allocated in the fabric use the base's table
+    /// from then on. Returns the base's table, which is the existing table if the base was
+    /// already registered
+    pub fn register_fixed_base(&self, base: StarkPoint) -> Arc<FixedBaseTable> {
+        if let Some(table) = self.fixed_base_table(&base) {
+            return table;
+        }
+
+        // Build the table outside of the lock, and check again for a concurrent registration
+        let table = Arc::new(FixedBaseTable::new(base));
+        let mut tables = self
+            .inner
+            .fixed_bases
+            .write()
+            .expect("fixed bases poisoned");
+        if let Some(existing) = tables.iter().find(|existing| existing.base() == base) {
+            return existing.clone();
+        }
+
+        tables.push(table.clone());
+        table
+    }
+
+    /// Get the precomputed table of a fixed base, if the base is the generator or is
+    /// registered
+    pub fn fixed_base_table(&self, base: &StarkPoint) -> Option<Arc<FixedBaseTable>> {
+        self.inner
+            .fixed_bases
+            .read()
+            .expect("fixed bases poisoned")
+            .iter()
+            .find(|table| table.base() == *base)
+            .cloned()
+    }
+
     /// Shutdown the fabric and the threads it has spawned
-    pub fn shutdown(self) {
+    ///
+    /// Operations already allocated are executed and the messages they send are flushed to
+    /// the peer before the network sender exits. The returned handle may be awaited to wait
+    /// for the drain to complete, or dropped to shut down in the background
+    pub fn shutdown(self) -> ShutdownHandle {
         log::debug!("shutting down fabric");
+        if let Some(openings) = self.inner.deferred_openings.as_ref() {
+            let n_unchecked = openings.lock().expect("deferred openings poisoned").len();
+            if n_unchecked > 0 {
+                log::warn!(
+                    "shutting down with {n_unchecked} unchecked openings, call `finalize` first"
+                );
+            }
+        }
+
+        // The network sender may have already exited if the connection to the peer was lost
         self.inner.shutdown();
-        self.shutdown
-            .send(())
-            .expect("error sending shutdown signal");
+        if self.shutdown.send(()).is_err() {
+            log::debug!("network sender already exited");
+        }
+
+        self.workers.clone()
+    }
+
+    /// Abort the computation, e.g. on detecting that the peer cheated
+    ///
+    /// The peer is sent an abort message holding the reason, on the same connection as every
+    /// other message so that it is authenticated wherever the transport authenticates frames,
+    /// and its computation fails with `MpcError::PeerAborted`. Locally the pending operations
+    /// are cancelled, no further operations are executed, and every result not yet computed
+    /// resolves to `MpcError::Aborted`
+    pub fn abort<S: Into<String>>(&self, reason: S) {
+        self.inner.abort(reason.into())
+    }
+
+    /// Flush the messages queued for the peer
+    ///
+    /// Resolves once the messages sent by the operations executed so far, i.e. those whose
+    /// inputs are available, are written to the network. Messages of operations still waiting
+    /// on their inputs are sent as usual once the inputs are available
+    pub async fn flush(&self) -> Result<(), MpcError> {
+        let (reply, flushed) = oneshot::channel();
+        self.inner
+            .execution_queue
+            .push(ExecutorMessage::Flush(reply));
+
+        flushed
+            .await
+            .unwrap_or_else(|_| Err(MpcNetworkError::SendError(ERR_FLUSH_AFTER_EXIT.to_string())))
+            .map_err(MpcError::NetworkError)
+    }
+
+    /// Recycle the IDs of the results released so far, so that a long-lived fabric reuses
+    /// the IDs and buffer slots of dropped results rather than growing without bound
+    ///
+    /// Both parties must call this at the same point in their computations. Each party's
+    /// executor drops released results at its own pace, so the parties exchange the IDs they
+    /// have dropped and recycle those dropped by both, the others are recycled by a later call.
+    /// Recycled IDs are handed out to new results in the same order by both parties. As with
+    /// `ResultHandle::release`, a released result must not be used again, as its ID may now
+    /// refer to another result
+    ///
+    /// Returns the number of IDs recycled
+    pub async fn recycle_result_ids(&self) -> Result<usize, MpcError> {
+        // A dry run stores no results
+        if self.inner.cost.is_some() {
+            return Ok(0);
+        }
+
+        // Let the executor process the releases issued so far
+        self.flush().await?;
+        let dropped = std::iter::from_fn(|| self.inner.dropped_result_ids.pop())
+            .sorted()
+            .collect_vec();
+
+        let encoded = dropped
+            .iter()
+            .flat_map(|id| (*id as u64).to_be_bytes())
+            .collect_vec();
+        let party0_dropped = self.share_plaintext(encoded.clone(), PARTY0).fallible();
+        let party1_dropped = self.share_plaintext(encoded, PARTY1).fallible();
+        let (party0_dropped, party1_dropped) = (party0_dropped.await?, party1_dropped.await?);
+
+        let peer_dropped = if self.party_id() == PARTY0 {
+            party1_dropped
+        } else {
+            party0_dropped
+        };
+        let peer_dropped: HashSet<ResultId> = peer_dropped
+            .chunks_exact(BYTES_PER_RESULT_ID)
+            .map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()) as ResultId)
+            .collect();
+
+        let (recycled, pending): (Vec<_>, Vec<_>) = dropped
+            .into_iter()
+            .partition(|id| peer_dropped.contains(id));
+        pending
+            .into_iter()
+            .for_each(|id| self.inner.dropped_result_ids.push(id));
+        recycled
+            .iter()
+            .for_each(|id| self.inner.recycled_result_ids.push(*id));
+
+        Ok(recycled.len())
+    }
+
+    /// Return the memory held by the results buffer and waker map to the allocator
+    ///
+    /// Meant to be called between the phases of a long-running application, once a large
+    /// batch has completed and its results have been released. Segments of the results buffer
+    /// that hold no results are freed and reallocated when next written to. Results that are
+    /// still held are unaffected. Returns the number of buffer segments freed
+    pub fn compact(&self) -> usize {
+        let n_freed = self
+            .inner
+            .results
+            .write()
+            .expect("results poisoned")
+            .compact();
+        self.inner
+            .wakers
+            .write()
+            .expect("wakers poisoned")
+            .shrink_to_fit();
+
+        n_freed
+    }
+
+    /// Run a computation on the fabric, shutting the fabric down once the computation
+    /// completes
+    ///
+    /// The fabric's outbound messages are drained before the scope returns. The fabric is also
+    /// shut down if the computation panics or its future is dropped before completing, so
+    /// that the fabric's workers are not leaked
+    pub async fn scope<T, F, Fut>(self, f: F) -> T
+    where
+        F: FnOnce(MpcFabric) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let mut guard = ShutdownGuard(Some(self.clone()));
+        let res = f(self).await;
+
+        // Wait for the fabric to drain on completion, the guard only shuts down the fabric
+        // in the background
+        let fabric = guard.0.take().unwrap();
+        if let Err(err) = fabric.shutdown().await {
+            log::warn!("error draining scoped fabric: {err}");
+        }
+
+        res
     }
 
     /// Immutably borrow the MAC key
@@ -573,28 +1399,37 @@ impl MpcFabric {
         ResultHandle::new(id, self.clone())
     }
 
+    /// Allocate the local shares of `n` values shared under masks derived from the
+    /// correlated PRG, `f` maps the masks to the local party's shares
+    fn allocate_correlated_shares<F, T>(
+        &self,
+        prg: &CorrelatedMaskPrg,
+        n: usize,
+        f: F,
+    ) -> ResultHandle<T>
+    where
+        F: 'static + FnOnce(Vec<Scalar>) -> ResultValue + Send + Sync,
+        T: From<ResultValue>,
+    {
+        let start = prg.reserve(n);
+        self.new_gate_op(vec![prg.seed], move |mut args| {
+            let seed: Vec<u8> = args.remove(0).into();
+            let masks = (start..start + n as u64)
+                .map(|index| CorrelatedMaskPrg::derive_mask(&seed, index))
+                .collect_vec();
+
+            f(masks)
+        })
+    }
+
     /// Share a `Scalar` value with the counterparty
     pub fn share_scalar<T: Into<Scalar>>(
         &self,
         val: T,
         sender: PartyId,
     ) -> AuthenticatedScalarResult {
-        let scalar: ScalarResult = if self.party_id() == sender {
-            let scalar_val = val.into();
-            let mut rng = thread_rng();
-            let random = Scalar::random(&mut rng);
-
-            let (my_share, their_share) = (scalar_val - random, random);
-            self.allocate_shared_value(
-                ResultValue::Scalar(my_share),
-                ResultValue::Scalar(their_share),
-            )
-        } else {
-            self.receive_value()
-        };
-
-        AuthenticatedScalarResult::new_shared(scalar)
-    }
+        AuthenticatedScalarResult::new_shared(self.share_scalar_value(val, sender))
+    }
 
     /// Share a batch of `Scalar` values with the counterparty
     pub fn batch_share_scalar<T: Into<Scalar>>(
@@ -603,13 +1438,258 @@ impl MpcFabric {
         sender: PartyId,
     ) -> Vec<AuthenticatedScalarResult> {
         let n = vals.len();
-        let shares: BatchScalarResult = if self.party_id() == sender {
-            let vals = vals.into_iter().map(|val| val.into()).collect_vec();
-            let mut rng = thread_rng();
+        let shares = self.batch_share_scalar_values(vals, sender);
+        AuthenticatedScalarResult::new_shared_from_batch_result(shares, n)
+    }
 
-            let peer_shares = (0..vals.len())
-                .map(|_| Scalar::random(&mut rng))
-                .collect_vec();
+    /// Share a `StarkPoint` value with the counterparty
+    pub fn share_point(&self, val: StarkPoint, sender: PartyId) -> AuthenticatedStarkPointResult {
+        AuthenticatedStarkPointResult::new_shared(self.share_point_value(val, sender))
+    }
+
+    /// Share a batch of `StarkPoint`s with the counterparty
+    pub fn batch_share_point(
+        &self,
+        vals: Vec<StarkPoint>,
+        sender: PartyId,
+    ) -> Vec<AuthenticatedStarkPointResult> {
+        let n = vals.len();
+        let shares = self.batch_share_point_values(vals, sender);
+        AuthenticatedStarkPointResult::new_shared_from_batch_result(shares, n)
+    }
+
+    /// Share a `Scalar` value with the counterparty, failing if the value could not be sent
+    ///
+    /// Resolves once the value is written to the network, or returns the error that stopped
+    /// the network sender if it failed before the value was written. A value whose share is
+    /// derived from correlated randomness is not sent, and resolves once the messages queued
+    /// before it are written
+    pub async fn try_share_scalar<T: Into<Scalar>>(
+        &self,
+        val: T,
+        sender: PartyId,
+    ) -> Result<AuthenticatedScalarResult, MpcError> {
+        self.check_outbound()?;
+        let share = self.share_scalar(val, sender);
+        self.flush_outbound().await.map(|_| share)
+    }
+
+    /// Share a batch of `Scalar` values with the counterparty, failing if the values could not
+    /// be sent
+    pub async fn try_batch_share_scalar<T: Into<Scalar>>(
+        &self,
+        vals: Vec<T>,
+        sender: PartyId,
+    ) -> Result<Vec<AuthenticatedScalarResult>, MpcError> {
+        self.check_outbound()?;
+        let shares = self.batch_share_scalar(vals, sender);
+        self.flush_outbound().await.map(|_| shares)
+    }
+
+    /// Share a `StarkPoint` value with the counterparty, failing if the value could not be sent
+    pub async fn try_share_point(
+        &self,
+        val: StarkPoint,
+        sender: PartyId,
+    ) -> Result<AuthenticatedStarkPointResult, MpcError> {
+        self.check_outbound()?;
+        let share = self.share_point(val, sender);
+        self.flush_outbound().await.map(|_| share)
+    }
+
+    /// Share a batch of `StarkPoint`s with the counterparty, failing if the values could not
+    /// be sent
+    pub async fn try_batch_share_point(
+        &self,
+        vals: Vec<StarkPoint>,
+        sender: PartyId,
+    ) -> Result<Vec<AuthenticatedStarkPointResult>, MpcError> {
+        self.check_outbound()?;
+        let shares = self.batch_share_point(vals, sender);
+        self.flush_outbound().await.map(|_| shares)
+    }
+
+    /// Check that no error has stopped values from being sent to the counterparty
+    fn check_outbound(&self) -> Result<(), MpcError> {
+        match self.inner.outbound_queue.failure() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait for the messages of the operations executed so far to be written to the network
+    ///
+    /// Fails with the error that stopped the network sender if it has failed
+    async fn flush_outbound(&self) -> Result<(), MpcError> {
+        let res = self.flush().await;
+        self.check_outbound().and(res)
+    }
+
+    /// Share a bit with the counterparty, checking that the sender shared a zero or a one
+    ///
+    /// Fails with `MpcError::AuthenticationError` if the shared value is not a bit
+    pub async fn share_bit(
+        &self,
+        bit: bool,
+        sender: PartyId,
+    ) -> Result<AuthenticatedBit, MpcError> {
+        Ok(self.batch_share_bit(vec![bit], sender).await?.remove(0))
+    }
+
+    /// Share a batch of bits with the counterparty, checking that the sender shared only
+    /// zeros and ones
+    ///
+    /// A value `b` is a bit if and only if `b * (b - 1) = 0`, so the parties open a random
+    /// linear combination of these products over the batch, which costs one multiplication
+    /// per bit and a single opening. Fails with `MpcError::AuthenticationError` if any shared
+    /// value is not a bit
+    pub async fn batch_share_bit(
+        &self,
+        bits: Vec<bool>,
+        sender: PartyId,
+    ) -> Result<Vec<AuthenticatedBit>, MpcError> {
+        if bits.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let shared = self.batch_share_scalar(bits.into_iter().map(u8::from).collect_vec(), sender);
+        self.check_shared_bits(shared).await
+    }
+
+    /// Check that a non-empty batch of freshly shared values are all bits
+    pub(crate) async fn check_shared_bits(
+        &self,
+        shared: Vec<AuthenticatedScalarResult>,
+    ) -> Result<Vec<AuthenticatedBit>, MpcError> {
+        let n = shared.len();
+        let shifted = shared.iter().map(|b| b - Scalar::one()).collect_vec();
+        let products = AuthenticatedScalarResult::batch_mul(&shared, &shifted);
+
+        // Toss the seed only once the sender has committed to its inputs, so that neither
+        // party can choose its inputs or its contribution after seeing the other's
+        let seed = self
+            .committed_challenge(shared.iter().flat_map(|b| b.ids()).collect_vec())
+            .await;
+        let coeffs =
+            Self::deferred_check_coefficients(self.session_id(), seed, BIT_CHECK_DOMAIN, n);
+
+        let combination = linear_combination(&products, &coeffs)
+            .open_authenticated()
+            .await?;
+        if combination != Scalar::zero() {
+            return Err(MpcError::AuthenticationError);
+        }
+
+        Ok(shared)
+    }
+
+    /// Share a `Scalar` value with the counterparty without authenticating it
+    ///
+    /// The resulting value is only secure against a semi-honest counterparty
+    #[cfg(feature = "semi_honest")]
+    pub fn share_scalar_semi_honest<T: Into<Scalar>>(
+        &self,
+        val: T,
+        sender: PartyId,
+    ) -> MpcScalarResult {
+        MpcScalarResult::new_shared(self.share_scalar_value(val, sender))
+    }
+
+    /// Share a batch of `Scalar` values with the counterparty without authenticating them
+    ///
+    /// The resulting values are only secure against a semi-honest counterparty
+    #[cfg(feature = "semi_honest")]
+    pub fn batch_share_scalar_semi_honest<T: Into<Scalar>>(
+        &self,
+        vals: Vec<T>,
+        sender: PartyId,
+    ) -> Vec<MpcScalarResult> {
+        let n = vals.len();
+        let shares = self.batch_share_scalar_values(vals, sender);
+        MpcScalarResult::new_shared_from_batch_result(shares, n)
+    }
+
+    /// Share a `StarkPoint` value with the counterparty without authenticating it
+    ///
+    /// The resulting value is only secure against a semi-honest counterparty
+    #[cfg(feature = "semi_honest")]
+    pub fn share_point_semi_honest(&self, val: StarkPoint, sender: PartyId) -> MpcStarkPointResult {
+        MpcStarkPointResult::new_shared(self.share_point_value(val, sender))
+    }
+
+    /// Share a batch of `StarkPoint`s with the counterparty without authenticating them
+    ///
+    /// The resulting values are only secure against a semi-honest counterparty
+    #[cfg(feature = "semi_honest")]
+    pub fn batch_share_point_semi_honest(
+        &self,
+        vals: Vec<StarkPoint>,
+        sender: PartyId,
+    ) -> Vec<MpcStarkPointResult> {
+        let n = vals.len();
+        let shares = self.batch_share_point_values(vals, sender);
+        MpcStarkPointResult::new_shared_from_batch_result(shares, n)
+    }
+
+    /// The party whose sharing path a value shared by `sender` is allocated through
+    ///
+    /// A simulation holds the plaintext of values shared by either party, so allocates them
+    /// as though the local party shared them
+    fn simulated_sender(&self, sender: PartyId) -> PartyId {
+        match self.inner.simulated_peer {
+            Some(_) => self.party_id(),
+            None => sender,
+        }
+    }
+
+    /// Allocate the local party's share of a `Scalar` shared by the sender
+    fn share_scalar_value<T: Into<Scalar>>(&self, val: T, sender: PartyId) -> ScalarResult {
+        let sender = self.simulated_sender(sender);
+        // The sender holds its value minus the mask, the receiver holds the mask
+        if let Some(prg) = self.mask_prg.as_ref() {
+            let val = (self.party_id() == sender).then(|| val.into());
+            return self.allocate_correlated_shares(prg, 1, move |masks| {
+                let mask = masks[0];
+                ResultValue::Scalar(val.map_or(mask, |val| val - mask))
+            });
+        }
+
+        if self.party_id() == sender {
+            let scalar_val = val.into();
+            let random = self.random_scalar();
+
+            let (my_share, their_share) = (scalar_val - random, random);
+            self.allocate_shared_value(
+                ResultValue::Scalar(my_share),
+                ResultValue::Scalar(their_share),
+            )
+        } else {
+            self.receive_value()
+        }
+    }
+
+    /// Allocate the local party's shares of a batch of `Scalar`s shared by the sender
+    fn batch_share_scalar_values<T: Into<Scalar>>(
+        &self,
+        vals: Vec<T>,
+        sender: PartyId,
+    ) -> BatchScalarResult {
+        let sender = self.simulated_sender(sender);
+        let n = vals.len();
+        if let Some(prg) = self.mask_prg.as_ref() {
+            let vals = (self.party_id() == sender)
+                .then(|| vals.into_iter().map(|val| val.into()).collect_vec());
+            return self.allocate_correlated_shares(prg, n, |masks| {
+                ResultValue::ScalarBatch(match vals {
+                    Some(vals) => vals.iter().zip(masks.iter()).map(|(v, m)| v - m).collect(),
+                    None => masks,
+                })
+            });
+        }
+
+        if self.party_id() == sender {
+            let vals = vals.into_iter().map(|val| val.into()).collect_vec();
+            let peer_shares = self.random_scalars(vals.len());
             let my_shares = vals
                 .iter()
                 .zip(peer_shares.iter())
@@ -621,23 +1701,30 @@ impl MpcFabric {
                 ResultValue::ScalarBatch(peer_shares),
             )
         } else {
-            self.receive_value()
-        };
-
-        AuthenticatedScalarResult::new_shared_from_batch_result(shares, n)
+            self.receive_batch_value(n)
+        }
     }
 
-    /// Share a `StarkPoint` value with the counterparty
-    pub fn share_point(&self, val: StarkPoint, sender: PartyId) -> AuthenticatedStarkPointResult {
-        let point: StarkPointResult = if self.party_id() == sender {
+    /// Allocate the local party's share of a `StarkPoint` shared by the sender
+    fn share_point_value(&self, val: StarkPoint, sender: PartyId) -> StarkPointResult {
+        let sender = self.simulated_sender(sender);
+        if let Some(prg) = self.mask_prg.as_ref() {
+            let val = (self.party_id() == sender).then_some(val);
+            let generator = self.generator_table();
+            return self.allocate_correlated_shares(prg, 1, move |masks| {
+                let mask = generator.mul(&masks[0]);
+                ResultValue::Point(val.map_or(mask, |val| val - mask))
+            });
+        }
+
+        if self.party_id() == sender {
             // As mentioned in https://eprint.iacr.org/2009/226.pdf
             // it is okay to sample a random point by sampling a random `Scalar` and multiplying
             // by the generator in the case that the discrete log of the output may be leaked with
             // respect to the generator. Leaking the discrete log (i.e. the random `Scalar`) is okay
             // when it is used to generate secret shares
-            let mut rng = thread_rng();
-            let random = Scalar::random(&mut rng);
-            let random_point = random * StarkPoint::generator();
+            let random = self.random_scalar();
+            let random_point = self.generator_table().mul(&random);
 
             let (my_share, their_share) = (val - random_point, random_point);
             self.allocate_shared_value(
@@ -646,42 +1733,42 @@ impl MpcFabric {
             )
         } else {
             self.receive_value()
-        };
-
-        AuthenticatedStarkPointResult::new_shared(point)
+        }
     }
 
-    /// Share a batch of `StarkPoint`s with the counterparty
-    pub fn batch_share_point(
+    /// Allocate the local party's shares of a batch of `StarkPoint`s shared by the sender
+    fn batch_share_point_values(
         &self,
         vals: Vec<StarkPoint>,
         sender: PartyId,
-    ) -> Vec<AuthenticatedStarkPointResult> {
+    ) -> BatchStarkPointResult {
+        let sender = self.simulated_sender(sender);
         let n = vals.len();
-        let shares: BatchStarkPointResult = if self.party_id() == sender {
-            let mut rng = thread_rng();
-            let generator = StarkPoint::generator();
-            let peer_shares = (0..vals.len())
-                .map(|_| {
-                    let discrete_log = Scalar::random(&mut rng);
-                    discrete_log * generator
+        if let Some(prg) = self.mask_prg.as_ref() {
+            let vals = (self.party_id() == sender).then_some(vals);
+            let generator = self.generator_table();
+            return self.allocate_correlated_shares(prg, n, move |masks| {
+                let masks = generator.batch_mul(&masks);
+                ResultValue::PointBatch(match vals {
+                    Some(vals) => sub_points(&vals, &masks),
+                    None => masks,
                 })
-                .collect_vec();
-            let my_shares = vals
-                .iter()
-                .zip(peer_shares.iter())
-                .map(|(val, share)| val - share)
-                .collect_vec();
+            });
+        }
+
+        if self.party_id() == sender {
+            let peer_shares = self
+                .generator_table()
+                .batch_mul(&self.random_scalars(vals.len()));
+            let my_shares = sub_points(&vals, &peer_shares);
 
             self.allocate_shared_value(
                 ResultValue::PointBatch(my_shares),
                 ResultValue::PointBatch(peer_shares),
             )
         } else {
-            self.receive_value()
-        };
-
-        AuthenticatedStarkPointResult::new_shared_from_batch_result(shares, n)
+            self.receive_batch_value(n)
+        }
     }
 
     /// Allocate a public value in the fabric
@@ -730,6 +1817,21 @@ impl MpcFabric {
             .collect_vec()
     }
 
+    /// Allocate a point as a secret share of an already shared value
+    pub fn allocate_preshared_point(&self, value: StarkPoint) -> AuthenticatedStarkPointResult {
+        let allocated = self.allocate_point(value);
+        AuthenticatedStarkPointResult::new_shared(allocated)
+    }
+
+    /// Allocate a batch of points as secret shares of already shared values
+    pub fn batch_allocate_preshared_point(
+        &self,
+        values: Vec<StarkPoint>,
+    ) -> Vec<AuthenticatedStarkPointResult> {
+        let values = self.allocate_points(values);
+        AuthenticatedStarkPointResult::new_shared_batch(&values)
+    }
+
     /// Send a value to the peer, placing the identity in the local result buffer at the send ID
     pub fn send_value<T: From<ResultValue> + Into<NetworkPayload>>(
         &self,
@@ -752,8 +1854,16 @@ impl MpcFabric {
     }
 
     /// Receive a value from the peer
-    pub fn receive_value<T: From<ResultValue>>(&self) -> ResultHandle<T> {
-        let id = self.inner.receive_value();
+    pub fn receive_value<T: PayloadType>(&self) -> ResultHandle<T> {
+        let id = self.inner.receive_value(Some(T::payload_shape()));
+        ResultHandle::new(id, self.clone())
+    }
+
+    /// Receive a batch of `n` values from the peer
+    fn receive_batch_value<T: PayloadType>(&self, n: usize) -> ResultHandle<T> {
+        let id = self
+            .inner
+            .receive_value(Some(T::payload_shape().with_len(n)));
         ResultHandle::new(id, self.clone())
     }
 
@@ -761,7 +1871,7 @@ impl MpcFabric {
     /// based on the party ID
     ///
     /// Returns a handle to the received value, which will be different for different parties
-    pub fn exchange_value<T: From<ResultValue> + Into<NetworkPayload>>(
+    pub fn exchange_value<T: PayloadType + Into<NetworkPayload>>(
         &self,
         value: ResultHandle<T>,
     ) -> ResultHandle<T> {
@@ -777,27 +1887,169 @@ impl MpcFabric {
         }
     }
 
+    /// Place a barrier that resolves once both parties have reached it
+    ///
+    /// The parties exchange an empty token, so the barrier aligns the rounds of the parties,
+    /// e.g. to separate the phases of a protocol or to time a phase from a common start
+    pub fn barrier(&self) -> BarrierResult {
+        BarrierResult {
+            peer_token: self.exchange_value(self.zero()),
+        }
+    }
+
+    /// Exchange a commitment with the peer, recording both parties' commitments in the
+    /// transcript if the fabric records one
+    pub(crate) fn exchange_commitment<T: PayloadType + Into<NetworkPayload>>(
+        &self,
+        commitment: ResultHandle<T>,
+    ) -> ResultHandle<T> {
+        self.record_transcript(TranscriptEntryKind::LocalCommitment, &[commitment.id()]);
+        let peer_commitment = self.exchange_value(commitment);
+        self.record_transcript(TranscriptEntryKind::PeerCommitment, &[peer_commitment.id()]);
+
+        peer_commitment
+    }
+
+    /// Exchange a batch of commitments with the peer, recording both parties' commitments in
+    /// the transcript if the fabric records one
+    pub(crate) fn exchange_commitments<T>(
+        &self,
+        commitments: &[ResultHandle<T>],
+    ) -> ResultHandle<Vec<T>>
+    where
+        T: From<ResultValue>,
+        Vec<T>: PayloadType + Into<NetworkPayload>,
+    {
+        let ids = commitments.iter().map(|comm| comm.id()).collect_vec();
+        self.record_transcript(TranscriptEntryKind::LocalCommitment, &ids);
+        let peer_commitments = self.exchange_values(commitments);
+        self.record_transcript(
+            TranscriptEntryKind::PeerCommitment,
+            &[peer_commitments.id()],
+        );
+
+        peer_commitments
+    }
+
     /// Exchange a batch of values with the peer, i.e. send then receive or receive then send
     /// based on party ID
     pub fn exchange_values<T>(&self, values: &[ResultHandle<T>]) -> ResultHandle<Vec<T>>
     where
         T: From<ResultValue>,
-        Vec<T>: From<ResultValue> + Into<NetworkPayload>,
+        Vec<T>: PayloadType + Into<NetworkPayload>,
     {
         if self.party_id() == PARTY0 {
             self.send_values(values);
-            self.receive_value()
+            self.receive_batch_value(values.len())
         } else {
-            let handle = self.receive_value();
+            let handle = self.receive_batch_value(values.len());
             self.send_values(values);
             handle
         }
     }
 
+    /// Exchange a value with the peer by committing to it before revealing it
+    ///
+    /// In `exchange_value` the first party's value is sent before the second party's, so the
+    /// second party may choose its value after seeing the first. Here both parties exchange
+    /// salted commitments to their values first, and reveal their values only once the peer's
+    /// commitment is received, so neither party's value depends on the other's. This should be
+    /// used wherever a party must not choose its value after seeing its peer's, e.g. when
+    /// contributing to a coin toss or a challenge
+    ///
+    /// If the peer's reveal does not open its commitment the computation fails with
+    /// `MpcError::AuthenticationError`
+    pub fn exchange_value_committed<T: PayloadType + Into<NetworkPayload>>(
+        &self,
+        value: ResultHandle<T>,
+    ) -> ResultHandle<T> {
+        let mut salt = vec![0u8; COMMITTED_EXCHANGE_SALT_BYTES];
+        self.inner
+            .rng
+            .lock()
+            .expect("rng poisoned")
+            .fill_bytes(&mut salt);
+        let salt: ResultHandle<Vec<u8>> = ResultHandle::new(
+            self.inner.allocate_value(ResultValue::Bytes(salt)),
+            self.clone(),
+        );
+
+        // Commit to the value and exchange commitments
+        let party_id = self.party_id();
+        let session_id = self.session_id();
+        let my_commitment: ResultHandle<Vec<u8>> =
+            self.new_gate_op(vec![value.id(), salt.id()], move |mut args| {
+                let payload: NetworkPayload = args.remove(0).into();
+                let salt: Vec<u8> = args.remove(0).into();
+                ResultValue::Bytes(committed_exchange_digest(
+                    &session_id,
+                    party_id,
+                    &payload,
+                    &salt,
+                ))
+            });
+        let peer_commitment = self.exchange_commitment(my_commitment);
+
+        // Reveal the value and salt only once the peer's commitment is received
+        let reveal = || -> [ResultHandle<Vec<u8>>; 2] {
+            [value.id(), salt.id()].map(|id| {
+                self.new_network_op(vec![peer_commitment.id(), id], |mut args| {
+                    args.remove(1).into()
+                })
+            })
+        };
+        let (peer_value, peer_salt): (ResultHandle<T>, ResultHandle<Vec<u8>>) =
+            if party_id == PARTY0 {
+                reveal();
+                (self.receive_value(), self.receive_value())
+            } else {
+                let handles = (self.receive_value(), self.receive_value());
+                reveal();
+                handles
+            };
+
+        // Check that the peer's reveal opens its commitment
+        let execution_queue = self.inner.execution_queue.clone();
+        self.new_gate_op(
+            vec![peer_value.id(), peer_salt.id(), peer_commitment.id()],
+            move |mut args| {
+                let peer_value = args.remove(0);
+                let peer_salt: Vec<u8> = args.remove(0).into();
+                let peer_commitment: Vec<u8> = args.remove(0).into();
+
+                let payload: NetworkPayload = peer_value.clone().into();
+                let expected =
+                    committed_exchange_digest(&session_id, 1 - party_id, &payload, &peer_salt);
+                if expected != peer_commitment {
+                    log::error!("peer's exchanged value does not open its commitment");
+                    execution_queue.push(ExecutorMessage::Error(MpcError::AuthenticationError));
+                }
+
+                peer_value
+            },
+        )
+    }
+
+    /// Exchange a batch of values with the peer by committing to them before revealing them,
+    /// see `exchange_value_committed`
+    pub fn exchange_values_committed<T>(&self, values: &[ResultHandle<T>]) -> ResultHandle<Vec<T>>
+    where
+        T: From<ResultValue>,
+        Vec<T>: PayloadType + Into<NetworkPayload>,
+    {
+        let ids = values.iter().map(|v| v.id()).collect_vec();
+        let batch: ResultHandle<Vec<T>> = self.new_gate_op(ids, |args| {
+            let batch: Vec<T> = args.into_iter().map(T::from).collect();
+            ResultValue::from(batch.into())
+        });
+
+        self.exchange_value_committed(batch)
+    }
+
     /// Share a public value with the counterparty
     pub fn share_plaintext<T>(&self, value: T, sender: PartyId) -> ResultHandle<T>
     where
-        T: 'static + From<ResultValue> + Into<NetworkPayload> + Send + Sync,
+        T: 'static + PayloadType + Into<NetworkPayload> + Send + Sync,
     {
         if self.party_id() == sender {
             self.new_network_op(vec![], move |_args| value.into())
@@ -806,11 +2058,42 @@ impl MpcFabric {
         }
     }
 
+    /// Share a public value with the counterparty and check that both parties received the
+    /// same value
+    ///
+    /// This is an echo broadcast: the recipient echoes a hash of the value it received, and
+    /// both parties compare it to their own hash of the value. This prevents a malicious sender
+    /// from giving different "public" values to different consumers
+    pub fn consistent_share_plaintext<T>(&self, value: T, sender: PartyId) -> BroadcastResult<T>
+    where
+        T: 'static + PayloadType + Into<NetworkPayload> + Send + Sync,
+    {
+        let value = self.share_plaintext(value, sender);
+
+        // Both parties hash the value they hold and exchange hashes
+        let digest: ResultHandle<Vec<u8>> = self.new_gate_op(vec![value.id()], |mut args| {
+            let payload: NetworkPayload = args.remove(0).into();
+            let serialized = serde_json::to_vec(&payload).expect("error serializing payload");
+
+            ResultValue::Bytes(Sha3_256::digest(serialized).to_vec())
+        });
+        let peer_digest = self.exchange_value(digest.clone());
+
+        let check = self.new_gate_op(vec![digest.id(), peer_digest.id()], |mut args| {
+            let digest: Vec<u8> = args.remove(0).into();
+            let peer_digest: Vec<u8> = args.remove(0).into();
+
+            ResultValue::Scalar(Scalar::from(digest == peer_digest))
+        });
+
+        BroadcastResult { value, check }
+    }
+
     /// Share a batch of public values with the counterparty
     pub fn batch_share_plaintext<T>(&self, values: Vec<T>, sender: PartyId) -> ResultHandle<Vec<T>>
     where
         T: 'static + From<ResultValue> + Send + Sync,
-        Vec<T>: Into<NetworkPayload> + From<ResultValue>,
+        Vec<T>: Into<NetworkPayload> + PayloadType,
     {
         self.share_plaintext(values, sender)
     }
@@ -875,59 +2158,569 @@ impl MpcFabric {
         ResultHandle::new(id, self.clone())
     }
 
-    // -----------------
-    // | Beaver Source |
-    // -----------------
+    /// Construct a new gate operation in the fabric with the given label
+    pub fn new_labeled_gate_op<F, T>(
+        &self,
+        label: &str,
+        args: Vec<ResultId>,
+        function: F,
+    ) -> ResultHandle<T>
+    where
+        F: 'static + FnOnce(Vec<ResultValue>) -> ResultValue + Send + Sync,
+        T: From<ResultValue>,
+    {
+        let function = Box::new(function);
+        let id = self.inner.new_labeled_op(
+            args,
+            1, /* output_arity */
+            OperationType::Gate { function },
+            Some(label.into()),
+        )[0];
+        ResultHandle::new(id, self.clone())
+    }
 
-    /// Sample the next beaver triplet from the beaver source
-    pub fn next_beaver_triple(&self) -> (MpcScalarResult, MpcScalarResult, MpcScalarResult) {
-        // Sample the triple and allocate it in the fabric, the counterparty will do the same
-        let (a, b, c) = self
-            .inner
-            .beaver_source
-            .lock()
-            .expect("beaver source poisoned")
-            .next_triplet();
+    /// Construct a new network operation in the fabric with the given label
+    pub fn new_labeled_network_op<F, T>(
+        &self,
+        label: &str,
+        args: Vec<ResultId>,
+        function: F,
+    ) -> ResultHandle<T>
+    where
+        F: 'static + FnOnce(Vec<ResultValue>) -> NetworkPayload + Send + Sync,
+        T: From<ResultValue>,
+    {
+        let function = Box::new(function);
+        let id = self.inner.new_labeled_op(
+            args,
+            1, /* output_arity */
+            OperationType::Network { function },
+            Some(label.into()),
+        )[0];
+        ResultHandle::new(id, self.clone())
+    }
 
-        let a_val = self.allocate_scalar(a);
-        let b_val = self.allocate_scalar(b);
-        let c_val = self.allocate_scalar(c);
+    // ----------
+    // | Labels |
+    // ----------
 
-        (
-            MpcScalarResult::new_shared(a_val),
-            MpcScalarResult::new_shared(b_val),
-            MpcScalarResult::new_shared(c_val),
-        )
+    /// Label the operations allocated by the given future
+    ///
+    /// Labels appear in the executor's diagnostics, gate profiles, metrics, cost estimates,
+    /// and the errors of panicking gates, connecting them back to application code, e.g. to
+    /// break down the cost of a protocol by phase. The label is held in a task-local, so it
+    /// applies only to the operations the future allocates, not to those of other tasks
+    /// sharing the fabric or of tasks the future spawns. Scopes may be nested, the label of
+    /// the innermost scope applies
+    ///
+    /// This is named `label_scope` rather than `scope` as `MpcFabric::scope` runs a
+    /// computation to completion on the fabric
+    pub fn label_scope<F: Future>(&self, label: &str, fut: F) -> impl Future<Output = F::Output> {
+        ACTIVE_LABEL.scope(Some(label.into()), fut)
     }
 
-    /// Sample a batch of beaver triples
-    pub fn next_beaver_triple_batch(
-        &self,
-        n: usize,
-    ) -> (
-        Vec<MpcScalarResult>,
-        Vec<MpcScalarResult>,
-        Vec<MpcScalarResult>,
+    /// Label the operations allocated in the given closure
+    pub fn with_label<T, F: FnOnce() -> T>(&self, label: &str, f: F) -> T {
+        ACTIVE_LABEL.sync_scope(Some(label.into()), f)
+    }
+
+    // -------------
+    // | Observers |
+    // -------------
+
+    /// Set a hook that is called with each operation as it is scheduled, replacing any
+    /// previous hook
+    ///
+    /// The hook sees each operation's ID, kind, arguments, result IDs, and label before the
+    /// operation reaches the executor, so external schedulers, cost models, and debuggers may
+    /// follow the computation graph as it is built. The hook runs on the task that allocates
+    /// the operation and should return quickly
+    pub fn set_op_observer<F: Fn(&Operation) + Send + Sync + 'static>(&self, observer: F) {
+        self.inner
+            .op_observer
+            .write()
+            .expect("observer poisoned")
+            .replace(Arc::new(observer));
+    }
+
+    /// Remove the hook set by `set_op_observer`, if any
+    pub fn clear_op_observer(&self) {
+        self.inner
+            .op_observer
+            .write()
+            .expect("observer poisoned")
+            .take();
+    }
+
+    // ---------------------------
+    // | Deferred MAC Checking |
+    // ---------------------------
+
+    /// Whether the fabric defers the MAC checks of opened values until `finalize`
+    pub fn defers_mac_check(&self) -> bool {
+        self.inner.deferred_openings.is_some()
+    }
+
+    /// The adversary model the fabric defends against
+    pub fn security_mode(&self) -> SecurityMode {
+        self.inner.security_mode
+    }
+
+    /// A snapshot of the fabric's execution metrics, if it records them
+    pub fn metrics(&self) -> Option<FabricMetrics> {
+        self.inner
+            .metrics
+            .as_ref()
+            .map(|metrics| metrics.snapshot())
+    }
+
+    /// A snapshot of the execution metrics of the operations allocated under each label, if
+    /// the fabric records metrics
+    ///
+    /// Messages are attributed to the label of the operation that sends them, or to the
+    /// active label scope when a share is sent or a receipt is allocated
+    pub fn metrics_by_label(&self) -> Option<HashMap<String, FabricMetrics>> {
+        self.inner
+            .metrics
+            .as_ref()
+            .map(|metrics| metrics.snapshot_by_label())
+    }
+
+    /// A snapshot of the timing profile of the gates evaluated so far, if the fabric profiles
+    /// gates
+    pub fn gate_profile(&self) -> Option<GateProfile> {
+        self.inner
+            .profiler
+            .as_ref()
+            .map(|profiler| profiler.snapshot())
+    }
+
+    /// The cost estimate of the circuits allocated so far, if the fabric is a dry run
+    pub fn cost_estimate(&self) -> Option<CostEstimate> {
+        self.inner.cost.as_ref().map(|cost| cost.snapshot())
+    }
+
+    /// The cost estimates of the operations allocated under each label so far, if the fabric
+    /// is a dry run
+    ///
+    /// Values shared, received, and consumed from the beaver source are attributed to the
+    /// active label scope
+    pub fn cost_estimate_by_label(&self) -> Option<HashMap<String, CostEstimate>> {
+        self.inner
+            .cost
+            .as_ref()
+            .map(|cost| cost.snapshot_by_label())
+    }
+
+    /// The transcript of the values opened, the commitments exchanged, and the MAC checks
+    /// run so far, if the fabric records one
+    ///
+    /// The values of results not yet computed are `None`, so the computation's results should
+    /// be awaited before exporting the transcript for audit
+    pub fn transcript(&self) -> Option<Transcript> {
+        self.inner.transcript.as_ref().map(|recorder| Transcript {
+            party_id: self.party_id(),
+            session_id: self.session_id(),
+            entries: recorder.entries(),
+        })
+    }
+
+    /// Record the given results in the transcript as entries of the given kind
+    ///
+    /// This is a no-op if the fabric does not record a transcript
+    pub(crate) fn record_transcript(&self, kind: TranscriptEntryKind, ids: &[ResultId]) {
+        if let Some(recorder) = self.inner.transcript.as_ref() {
+            let label = active_label();
+            let locked_results = self.inner.results.read().expect("results lock poisoned");
+            recorder.record(kind, ids, label, &locked_results);
+        }
+    }
+
+    /// Record a batch of opened scalars for a deferred MAC check
+    ///
+    /// This is a no-op if the fabric does not defer MAC checks
+    pub(crate) fn record_scalar_openings(
+        &self,
+        opened: &[ScalarResult],
+        values: &[AuthenticatedScalarResult],
+    ) {
+        if let Some(openings) = self.inner.deferred_openings.as_ref() {
+            let ids = opened
+                .iter()
+                .zip(values.iter())
+                .flat_map(|(opened, value)| {
+                    [opened.id(), value.public_modifier.id(), value.mac.id()]
+                })
+                .collect_vec();
+            let pinned = self.pin_deferred_openings(ids);
+            openings
+                .lock()
+                .expect("deferred openings poisoned")
+                .scalars
+                .extend(pinned);
+        }
+    }
+
+    /// Record a batch of opened points for a deferred MAC check
+    ///
+    /// This is a no-op if the fabric does not defer MAC checks
+    pub(crate) fn record_point_openings(
+        &self,
+        opened: &[StarkPointResult],
+        values: &[AuthenticatedStarkPointResult],
     ) {
-        let (a_vals, b_vals, c_vals) = self
+        if let Some(openings) = self.inner.deferred_openings.as_ref() {
+            let ids = opened
+                .iter()
+                .zip(values.iter())
+                .flat_map(|(opened, value)| {
+                    [opened.id(), value.public_modifier.id(), value.mac.id()]
+                })
+                .collect_vec();
+            let pinned = self.pin_deferred_openings(ids);
+            openings
+                .lock()
+                .expect("deferred openings poisoned")
+                .points
+                .extend(pinned);
+        }
+    }
+
+    /// Copy the opened values, public modifiers, and MACs of a batch of openings, given as
+    /// consecutive triples of IDs, into results that only the deferred MAC check refers to
+    ///
+    /// The caller's handles may be released or taken before `finalize`, the copies are not,
+    /// so the check neither waits on a dropped result nor reads a recycled one
+    fn pin_deferred_openings(&self, ids: Vec<ResultId>) -> Vec<DeferredOpening> {
+        let n = ids.len();
+        let copies = self.inner.new_op(
+            ids,
+            n, /* output_arity */
+            OperationType::GateBatch {
+                function: Box::new(|args| args),
+            },
+        );
+
+        copies
+            .chunks(3)
+            .map(|ids| DeferredOpening {
+                value: ids[0],
+                public_modifier: ids[1],
+                mac: ids[2],
+            })
+            .collect_vec()
+    }
+
+    /// Derive the coefficients of the random linear combination used in the deferred MAC check
+    /// from a public seed
+    ///
+    /// The coefficients are derived by hashing so that a party that biases the seed cannot
+    /// choose coefficients that cancel out errors in the openings, and are bound to the
+    /// session so that checks cannot be replayed across sessions
+    pub(crate) fn deferred_check_coefficients(
+        session_id: SessionId,
+        seed: Scalar,
+        domain: &[u8],
+        n: usize,
+    ) -> Vec<Scalar> {
+        let seed_bytes = seed.to_bytes_be();
+        (0..n)
+            .map(|i| {
+                let mut hasher = Sha3_256::new();
+                hasher.update(session_id);
+                hasher.update(domain);
+                hasher.update(&seed_bytes);
+                hasher.update((i as u64).to_le_bytes());
+
+                Scalar::from_be_bytes_mod_order(&hasher.finalize())
+            })
+            .collect_vec()
+    }
+
+    /// Run a single aggregate MAC check over all values opened since the last call to
+    /// `finalize`
+    ///
+    /// The parties toss a random seed, from which each opening is assigned a random
+    /// coefficient. The MAC checks of the openings are then combined into a single check
+    /// over their random linear combination. The seed is tossed only once every deferred
+    /// opening is computed, so a party cannot choose errors in its opening shares that cancel
+    /// under the coefficients. This is a no-op if the fabric does not defer MAC checks
+    pub async fn finalize(&self) -> Result<(), MpcError> {
+        let openings = match self.inner.deferred_openings.as_ref() {
+            Some(openings) => {
+                std::mem::take(&mut *openings.lock().expect("deferred openings poisoned"))
+            }
+            None => return Ok(()),
+        };
+
+        if openings.is_empty() {
+            return Ok(());
+        }
+
+        // Toss a random seed for the linear combination once the openings are fixed
+        let seed = self.committed_challenge(openings.value_ids());
+        let session_id = self.session_id();
+
+        let mut checks = Vec::with_capacity(2);
+        if !openings.scalars.is_empty() {
+            let n = openings.scalars.len();
+            let mut deps = vec![self.borrow_mac_key().id(), seed.id()];
+            deps.extend(
+                openings
+                    .scalars
+                    .iter()
+                    .flat_map(|opening| [opening.value, opening.public_modifier, opening.mac]),
+            );
+
+            let mac_check: ScalarResult = self.new_gate_op(deps, move |mut args| {
+                let mac_key_share: Scalar = args.remove(0).into();
+                let seed: Scalar = args.remove(0).into();
+                let coeffs = Self::deferred_check_coefficients(session_id, seed, b"scalars", n);
+
+                let mut res = Scalar::zero();
+                for (mut opening, coeff) in args.into_iter().chunks(3).into_iter().zip(coeffs) {
+                    let value: Scalar = opening.next().unwrap().into();
+                    let modifier: Scalar = opening.next().unwrap().into();
+                    let mac_share: Scalar = opening.next().unwrap().into();
+
+                    res += coeff * (mac_key_share * (value + modifier) - mac_share);
+                }
+
+                ResultValue::Scalar(res)
+            });
+            checks.push(AuthenticatedScalarResult::check_mac_shares::<HashCommitment>(mac_check));
+        }
+
+        if !openings.points.is_empty() {
+            let n = openings.points.len();
+            let mut deps = vec![self.borrow_mac_key().id(), seed.id()];
+            deps.extend(
+                openings
+                    .points
+                    .iter()
+                    .flat_map(|opening| [opening.value, opening.public_modifier, opening.mac]),
+            );
+
+            let mac_check: StarkPointResult = self.new_gate_op(deps, move |mut args| {
+                let mac_key_share: Scalar = args.remove(0).into();
+                let seed: Scalar = args.remove(0).into();
+                let coeffs = Self::deferred_check_coefficients(session_id, seed, b"points", n);
+
+                let mut res = StarkPoint::identity();
+                for (mut opening, coeff) in args.into_iter().chunks(3).into_iter().zip(coeffs) {
+                    let value: StarkPoint = opening.next().unwrap().into();
+                    let modifier: StarkPoint = opening.next().unwrap().into();
+                    let mac_share: StarkPoint = opening.next().unwrap().into();
+
+                    res += coeff * (mac_key_share * (value + modifier) - mac_share);
+                }
+
+                ResultValue::Point(res)
+            });
+            checks.push(AuthenticatedStarkPointResult::check_mac_shares::<
+                HashCommitment,
+            >(mac_check));
+        }
+
+        // The copies of the openings are only used by the checks
+        for id in openings.result_ids() {
+            self.inner.release_result(id, None /* uses */);
+        }
+
+        for check in checks {
+            decode_mac_check(check.await)?;
+        }
+
+        Ok(())
+    }
+
+    /// Toss a public random challenge with the peer once the given results are computed
+    ///
+    /// Each party commits to a random contribution and reveals it once the peer's commitment
+    /// arrives, the challenge is the sum of the contributions. A party's commitment is only
+    /// sent once the given results are computed, so a challenge over values opened with the
+    /// peer is not revealed before the peer's shares of those values are fixed
+    pub(crate) fn committed_challenge(&self, deps: Vec<ResultId>) -> ScalarResult {
+        let contribution = self.random_scalar();
+        let my_contribution: ScalarResult =
+            self.new_gate_op(deps, move |_args| ResultValue::Scalar(contribution));
+        let peer_contribution = self.exchange_value_committed(my_contribution.clone());
+
+        my_contribution + peer_contribution
+    }
+
+    // -----------------
+    // | Beaver Source |
+    // -----------------
+
+    /// Sample values from the beaver source, returns `None` if the source cannot provide them
+    ///
+    /// A source that fails, e.g. because its preprocessing is exhausted, fails the computation
+    /// so that all pending results resolve to the source's error
+    fn sample_preprocessing<T, F>(&self, sample: F) -> Option<T>
+    where
+        F: FnOnce(&mut dyn FallibleSharedValueSource) -> Result<T, MpcError>,
+    {
+        let mut locked_source = self
             .inner
             .beaver_source
             .lock()
-            .expect("beaver source poisoned")
-            .next_triplet_batch(n);
+            .expect("beaver source poisoned");
+
+        match sample(&mut **locked_source) {
+            Ok(values) => Some(values),
+            Err(err) => {
+                log::error!("error sampling from beaver source: {err}");
+                self.inner.execution_queue.push(ExecutorMessage::Error(err));
+                None
+            }
+        }
+    }
+
+    /// Allocate `n` values sampled from the beaver source
+    ///
+    /// If the values could not be sampled, the results are left pending and resolve to the
+    /// error that failed the computation
+    fn allocate_preprocessed(&self, values: Option<Vec<Scalar>>, n: usize) -> Vec<ScalarResult> {
+        match values {
+            Some(values) => self.allocate_scalars(values),
+            None => (0..n)
+                .map(|_| ResultHandle::new(self.inner.new_result_id(), self.clone()))
+                .collect_vec(),
+        }
+    }
+
+    /// Sample a batch of beaver triples from the beaver source and allocate them in the fabric
+    fn sample_triples(
+        &self,
+        n: usize,
+    ) -> (Vec<ScalarResult>, Vec<ScalarResult>, Vec<ScalarResult>) {
+        let (a_vals, b_vals, c_vals) =
+            match self.sample_preprocessing(|source| source.try_next_triplet_batch(n)) {
+                Some((a_vals, b_vals, c_vals)) => (Some(a_vals), Some(b_vals), Some(c_vals)),
+                None => (None, None, None),
+            };
+
+        (
+            self.allocate_preprocessed(a_vals, n),
+            self.allocate_preprocessed(b_vals, n),
+            self.allocate_preprocessed(c_vals, n),
+        )
+    }
+
+    /// Generate and authenticate preprocessed material ahead of the online phase
+    ///
+    /// The material is drawn from the beaver source and its MACs are computed up front, later
+    /// calls that sample authenticated triples, bits, inverse pairs, or shared values draw
+    /// from this material before falling back to the source. Both parties must preprocess the
+    /// same spec at the same point in the computation
+    ///
+    /// Resolves once all of the material is authenticated
+    pub async fn preprocess(&self, spec: PreprocessingSpec) -> Result<(), MpcError> {
+        let (a_vals, b_vals, c_vals) = self.sample_triples(spec.triples);
+        let triples = izip!(
+            AuthenticatedScalarResult::new_shared_batch(&a_vals),
+            AuthenticatedScalarResult::new_shared_batch(&b_vals),
+            AuthenticatedScalarResult::new_shared_batch(&c_vals)
+        )
+        .map(|(a, b, c)| [a, b, c])
+        .collect_vec();
+
+        let bits = self.sample_shared_bits(spec.bits);
+        let (left, right) = self.sample_inverse_pairs(spec.inverse_pairs);
+        let inverse_pairs = left
+            .into_iter()
+            .zip(right)
+            .map(|(l, r)| [l, r])
+            .collect_vec();
+        let values = self.sample_shared_values_authenticated(spec.values);
+
+        // Await the MACs of the material before making it available to the online phase
+        let macs = triples
+            .iter()
+            .flatten()
+            .chain(bits.iter())
+            .chain(inverse_pairs.iter().flatten())
+            .chain(values.iter())
+            .map(|value| value.mac.to_scalar().fallible())
+            .collect_vec();
+        for mac in futures::future::join_all(macs).await {
+            mac?;
+        }
+
+        let to_ids = |value: &AuthenticatedScalarResult| PreprocessedValue {
+            share: value.share.id(),
+            mac: value.mac.id(),
+        };
+        let mut locked_pool = self
+            .inner
+            .preprocessed
+            .lock()
+            .expect("preprocessed poisoned");
+        locked_pool
+            .triples
+            .extend(triples.iter().map(|triple| triple.each_ref().map(to_ids)));
+        locked_pool.bits.extend(bits.iter().map(to_ids));
+        locked_pool
+            .inverse_pairs
+            .extend(inverse_pairs.iter().map(|pair| pair.each_ref().map(to_ids)));
+        locked_pool.values.extend(values.iter().map(to_ids));
+
+        Ok(())
+    }
+
+    /// Take up to `n` items of preprocessed material from the pool
+    fn take_preprocessed<T, F>(&self, n: usize, select: F) -> Vec<T>
+    where
+        F: FnOnce(&mut PreprocessedPool) -> &mut VecDeque<T>,
+    {
+        let mut locked_pool = self
+            .inner
+            .preprocessed
+            .lock()
+            .expect("preprocessed poisoned");
+        let queue = select(&mut locked_pool);
+        let n = n.min(queue.len());
+
+        queue.drain(..n).collect_vec()
+    }
 
-        let a_vals = self
-            .allocate_scalars(a_vals)
+    /// Rebuild an authenticated value from the IDs of its preprocessed shares
+    fn preprocessed_value(&self, value: PreprocessedValue) -> AuthenticatedScalarResult {
+        AuthenticatedScalarResult {
+            share: MpcScalarResult::new_shared(ResultHandle::new(value.share, self.clone())),
+            mac: MpcScalarResult::new_shared(ResultHandle::new(value.mac, self.clone())),
+            public_modifier: self.zero(),
+        }
+    }
+
+    /// Sample the next beaver triplet from the beaver source
+    pub fn next_beaver_triple(&self) -> (MpcScalarResult, MpcScalarResult, MpcScalarResult) {
+        // Sample the triple and allocate it in the fabric, the counterparty will do the same
+        let (mut a_vals, mut b_vals, mut c_vals) = self.next_beaver_triple_batch(1 /* n */);
+        (a_vals.remove(0), b_vals.remove(0), c_vals.remove(0))
+    }
+
+    /// Sample a batch of beaver triples
+    pub fn next_beaver_triple_batch(
+        &self,
+        n: usize,
+    ) -> (
+        Vec<MpcScalarResult>,
+        Vec<MpcScalarResult>,
+        Vec<MpcScalarResult>,
+    ) {
+        let (a_vals, b_vals, c_vals) = self.sample_triples(n);
+
+        let a_vals = a_vals
             .into_iter()
             .map(MpcScalarResult::new_shared)
             .collect_vec();
-        let b_vals = self
-            .allocate_scalars(b_vals)
+        let b_vals = b_vals
             .into_iter()
             .map(MpcScalarResult::new_shared)
             .collect_vec();
-        let c_vals = self
-            .allocate_scalars(c_vals)
+        let c_vals = c_vals
             .into_iter()
             .map(MpcScalarResult::new_shared)
             .collect_vec();
@@ -946,22 +2739,9 @@ impl MpcFabric {
         AuthenticatedScalarResult,
         AuthenticatedScalarResult,
     ) {
-        let (a, b, c) = self
-            .inner
-            .beaver_source
-            .lock()
-            .expect("beaver source poisoned")
-            .next_triplet();
-
-        let a_val = self.allocate_scalar(a);
-        let b_val = self.allocate_scalar(b);
-        let c_val = self.allocate_scalar(c);
-
-        (
-            AuthenticatedScalarResult::new_shared(a_val),
-            AuthenticatedScalarResult::new_shared(b_val),
-            AuthenticatedScalarResult::new_shared(c_val),
-        )
+        let (mut a_vals, mut b_vals, mut c_vals) =
+            self.next_authenticated_triple_batch(1 /* n */);
+        (a_vals.remove(0), b_vals.remove(0), c_vals.remove(0))
     }
 
     /// Sample the next batch of beaver triples as `AuthenticatedScalar`s
@@ -973,71 +2753,56 @@ impl MpcFabric {
         Vec<AuthenticatedScalarResult>,
         Vec<AuthenticatedScalarResult>,
     ) {
-        let (a_vals, b_vals, c_vals) = self
-            .inner
-            .beaver_source
-            .lock()
-            .expect("beaver source poisoned")
-            .next_triplet_batch(n);
+        let mut a_vals = Vec::with_capacity(n);
+        let mut b_vals = Vec::with_capacity(n);
+        let mut c_vals = Vec::with_capacity(n);
+        for [a, b, c] in self.take_preprocessed(n, |pool| &mut pool.triples) {
+            a_vals.push(self.preprocessed_value(a));
+            b_vals.push(self.preprocessed_value(b));
+            c_vals.push(self.preprocessed_value(c));
+        }
 
-        let a_allocated = self.allocate_scalars(a_vals);
-        let b_allocated = self.allocate_scalars(b_vals);
-        let c_allocated = self.allocate_scalars(c_vals);
+        // Sample the remainder from the beaver source
+        let (a_allocated, b_allocated, c_allocated) = self.sample_triples(n - a_vals.len());
+        a_vals.extend(AuthenticatedScalarResult::new_shared_batch(&a_allocated));
+        b_vals.extend(AuthenticatedScalarResult::new_shared_batch(&b_allocated));
+        c_vals.extend(AuthenticatedScalarResult::new_shared_batch(&c_allocated));
 
-        (
-            AuthenticatedScalarResult::new_shared_batch(&a_allocated),
-            AuthenticatedScalarResult::new_shared_batch(&b_allocated),
-            AuthenticatedScalarResult::new_shared_batch(&c_allocated),
-        )
+        (a_vals, b_vals, c_vals)
     }
 
     /// Sample a batch of random shared values from the beaver source
     pub fn random_shared_scalars(&self, n: usize) -> Vec<ScalarResult> {
-        let values_raw = self
-            .inner
-            .beaver_source
-            .lock()
-            .expect("beaver source poisoned")
-            .next_shared_value_batch(n);
-
-        // Wrap the values in a result handle
-        values_raw
-            .into_iter()
-            .map(|value| self.allocate_scalar(value))
-            .collect_vec()
+        let values_raw = self.sample_preprocessing(|source| source.try_next_shared_value_batch(n));
+        self.allocate_preprocessed(values_raw, n)
     }
 
     /// Sample a batch of random shared values from the beaver source and allocate them as `AuthenticatedScalars`
     pub fn random_shared_scalars_authenticated(&self, n: usize) -> Vec<AuthenticatedScalarResult> {
-        let values_raw = self
-            .inner
-            .beaver_source
-            .lock()
-            .expect("beaver source poisoned")
-            .next_shared_value_batch(n);
+        let mut values = self
+            .take_preprocessed(n, |pool| &mut pool.values)
+            .into_iter()
+            .map(|value| self.preprocessed_value(value))
+            .collect_vec();
+        values.extend(self.sample_shared_values_authenticated(n - values.len()));
+
+        values
+    }
 
+    /// Sample a batch of random shared values from the beaver source, bypassing any
+    /// preprocessed material
+    fn sample_shared_values_authenticated(&self, n: usize) -> Vec<AuthenticatedScalarResult> {
         // Wrap the values in an authenticated wrapper
-        values_raw
+        self.random_shared_scalars(n)
             .into_iter()
-            .map(|value| {
-                let value = self.allocate_scalar(value);
-                AuthenticatedScalarResult::new_shared(value)
-            })
+            .map(AuthenticatedScalarResult::new_shared)
             .collect_vec()
     }
 
     /// Sample a pair of values that are multiplicative inverses of one another
     pub fn random_inverse_pair(&self) -> (AuthenticatedScalarResult, AuthenticatedScalarResult) {
-        let (l, r) = self
-            .inner
-            .beaver_source
-            .lock()
-            .unwrap()
-            .next_shared_inverse_pair();
-        (
-            AuthenticatedScalarResult::new_shared(self.allocate_scalar(l)),
-            AuthenticatedScalarResult::new_shared(self.allocate_scalar(r)),
-        )
+        let (mut left, mut right) = self.random_inverse_pairs(1 /* n */);
+        (left.remove(0), right.remove(0))
     }
 
     /// Sample a batch of values that are multiplicative inverses of one another
@@ -1048,15 +2813,32 @@ impl MpcFabric {
         Vec<AuthenticatedScalarResult>,
         Vec<AuthenticatedScalarResult>,
     ) {
-        let (left, right) = self
-            .inner
-            .beaver_source
-            .lock()
-            .unwrap()
-            .next_shared_inverse_pair_batch(n);
+        let (mut left, mut right): (Vec<_>, Vec<_>) = self
+            .take_preprocessed(n, |pool| &mut pool.inverse_pairs)
+            .into_iter()
+            .map(|[l, r]| (self.preprocessed_value(l), self.preprocessed_value(r)))
+            .unzip();
+
+        let (sampled_left, sampled_right) = self.sample_inverse_pairs(n - left.len());
+        left.extend(sampled_left);
+        right.extend(sampled_right);
+
+        (left, right)
+    }
 
-        let left_right = left.into_iter().chain(right.into_iter()).collect_vec();
-        let allocated_left_right = self.allocate_scalars(left_right);
+    /// Sample a batch of inverse pairs from the beaver source, bypassing any preprocessed
+    /// material
+    fn sample_inverse_pairs(
+        &self,
+        n: usize,
+    ) -> (
+        Vec<AuthenticatedScalarResult>,
+        Vec<AuthenticatedScalarResult>,
+    ) {
+        let left_right = self
+            .sample_preprocessing(|source| source.try_next_shared_inverse_pair_batch(n))
+            .map(|(left, right)| left.into_iter().chain(right).collect_vec());
+        let allocated_left_right = self.allocate_preprocessed(left_right, 2 * n);
         let authenticated_left_right =
             AuthenticatedScalarResult::new_shared_batch(&allocated_left_right);
 
@@ -1067,27 +2849,1602 @@ impl MpcFabric {
 
     /// Sample a random shared bit from the beaver source
     pub fn random_shared_bit(&self) -> AuthenticatedScalarResult {
-        let bit = self
-            .inner
-            .beaver_source
-            .lock()
-            .expect("beaver source poisoned")
-            .next_shared_bit();
-
-        let bit = self.allocate_scalar(bit);
-        AuthenticatedScalarResult::new_shared(bit)
+        self.random_shared_bits(1 /* n */).remove(0)
     }
 
     /// Sample a batch of random shared bits from the beaver source
     pub fn random_shared_bits(&self, n: usize) -> Vec<AuthenticatedScalarResult> {
-        let bits = self
-            .inner
-            .beaver_source
-            .lock()
-            .expect("beaver source poisoned")
-            .next_shared_bit_batch(n);
+        let mut bits = self
+            .take_preprocessed(n, |pool| &mut pool.bits)
+            .into_iter()
+            .map(|bit| self.preprocessed_value(bit))
+            .collect_vec();
+        bits.extend(self.sample_shared_bits(n - bits.len()));
 
-        let bits = self.allocate_scalars(bits);
+        bits
+    }
+
+    /// Sample a batch of shared bits from the beaver source, bypassing any preprocessed
+    /// material
+    fn sample_shared_bits(&self, n: usize) -> Vec<AuthenticatedScalarResult> {
+        let bits = self.sample_preprocessing(|source| source.try_next_shared_bit_batch(n));
+        let bits = self.allocate_preprocessed(bits, n);
         AuthenticatedScalarResult::new_shared_batch(&bits)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use futures::{future::join_all, Future};
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, thread_rng, SeedableRng};
+
+    use crate::{
+        algebra::{
+            authenticated_scalar::AuthenticatedScalarResult,
+            authenticated_stark_point::AuthenticatedStarkPointResult,
+            scalar::{Scalar, ScalarResult},
+            stark_curve::{StarkPoint, StarkPointResult},
+        },
+        beaver::{FallibleSharedValueSource, PartyIDBeaverSource, PreprocessingSpec, TripletBatch},
+        error::{MpcError, MpcNetworkError},
+        network::{
+            IdentityKeypair, MockNetwork, NetworkOutbound, NetworkPayload, NoRecvNetwork,
+            SessionId, UnboundedDuplexStream,
+        },
+        random_point,
+        test_helpers::execute_mock_mpc,
+        CostEstimate, Custom, CustomResult, FabricConfig, FabricMetrics, MpcFabric, OperationKind,
+        ResultHandle, SecurityMode, SignedTranscript, TranscriptEntryKind, PARTY0, PARTY1,
+    };
+
+    use super::{
+        committed_exchange_digest, join_results, ResultValue, COMMITTED_EXCHANGE_SALT_BYTES,
+        HEARTBEAT_RESULT_ID,
+    };
+
+    /// The liveness timeout used in tests
+    const TEST_LIVENESS_TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// Builds the configuration of a fabric under test
+    type ConfigBuilder = fn() -> FabricConfig;
+
+    /// Run a two party MPC in which both fabrics defer their MAC checks
+    async fn execute_deferred_mpc<T, S, F>(f: F) -> (T, T)
+    where
+        T: Send + 'static,
+        S: Future<Output = T> + Send + 'static,
+        F: FnMut(MpcFabric) -> S,
+    {
+        execute_mpc_with(|| FabricConfig::default().with_deferred_mac_check(), f).await
+    }
+
+    /// Run a two party MPC with fabrics built from the given configuration
+    async fn execute_mpc_with<T, S, F>(config: ConfigBuilder, mut f: F) -> (T, T)
+    where
+        T: Send + 'static,
+        S: Future<Output = T> + Send + 'static,
+        F: FnMut(MpcFabric) -> S,
+    {
+        let (party0_stream, party1_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let party0_fabric = MpcFabric::with_config(
+            MockNetwork::new(PARTY0, party0_stream),
+            PartyIDBeaverSource::new(PARTY0),
+            config(),
+        );
+        let party1_fabric = MpcFabric::with_config(
+            MockNetwork::new(PARTY1, party1_stream),
+            PartyIDBeaverSource::new(PARTY1),
+            config(),
+        );
+
+        let party0_task = tokio::spawn(f(party0_fabric.clone()));
+        let party1_task = tokio::spawn(f(party1_fabric.clone()));
+        let party0_output = party0_task.await.unwrap();
+        let party1_output = party1_task.await.unwrap();
+
+        party0_fabric.shutdown();
+        party1_fabric.shutdown();
+
+        (party0_output, party1_output)
+    }
+
+    /// Tests that honestly opened values pass the deferred MAC check
+    #[tokio::test]
+    async fn test_deferred_mac_check() {
+        let mut rng = thread_rng();
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        let point = random_point();
+
+        let (res, _) = execute_deferred_mpc(|fabric| async move {
+            let shared_a = fabric.share_scalar(a, PARTY0);
+            let shared_b = fabric.share_scalar(b, PARTY1);
+            let shared_point = fabric.share_point(point, PARTY0);
+
+            let product = (&shared_a * &shared_b).open().await;
+            let point_product = (&shared_a * &shared_point).open().await;
+            let check = fabric.finalize().await;
+
+            (product, point_product, check)
+        })
+        .await;
+
+        assert_eq!(res.0, a * b);
+        assert_eq!(res.1, a * point);
+        assert!(res.2.is_ok());
+    }
+
+    /// Tests that a corrupted opening fails the deferred MAC check
+    #[tokio::test]
+    async fn test_deferred_mac_check_corrupted() {
+        let (res, _) = execute_deferred_mpc(|fabric| async move {
+            let mut shared = fabric.share_scalar(Scalar::one(), PARTY0);
+            shared.share = fabric.allocate_scalar(Scalar::from(2u64)).into();
+
+            shared.open().await;
+            fabric.finalize().await
+        })
+        .await;
+
+        assert!(matches!(res, Err(MpcError::AuthenticationError)));
+    }
+
+    /// Tests that an offset one party adds to its share of a deferred opening fails the
+    /// deferred MAC check of both parties
+    #[tokio::test]
+    async fn test_deferred_mac_check_offset() {
+        let (res0, res1) = execute_deferred_mpc(|fabric| async move {
+            // Both parties allocate the offset so that their result IDs stay aligned
+            let mut shared = fabric.share_scalar(Scalar::one(), PARTY0);
+            let offset = if fabric.party_id() == PARTY1 {
+                7u8
+            } else {
+                0u8
+            };
+            shared.share = (shared.share() + Scalar::from(offset)).into();
+
+            shared.open().await;
+            fabric.finalize().await
+        })
+        .await;
+
+        assert!(res0.is_err());
+        assert!(res1.is_err());
+    }
+
+    /// Tests that releasing an opened value before the deferred MAC check does not stall or
+    /// fail the check
+    #[tokio::test]
+    async fn test_deferred_mac_check_released() {
+        let (res, _) = execute_deferred_mpc(|fabric| async move {
+            let shared = fabric.share_scalar(Scalar::one(), PARTY0);
+            let opened = shared.open();
+            opened.clone().await;
+            opened.release();
+
+            // Reuse the released IDs before the check
+            fabric.recycle_result_ids().await.unwrap();
+            let reused = fabric.allocate_scalars(vec![Scalar::from(2u8); 10]);
+            join_results(reused).await;
+
+            tokio::time::timeout(Duration::from_secs(10), fabric.finalize()).await
+        })
+        .await;
+
+        assert_eq!(res, Ok(Ok(())));
+    }
+
+    /// Tests an echo broadcast of a public value
+    #[tokio::test]
+    async fn test_consistent_share_plaintext() {
+        let mut rng = thread_rng();
+        let value = Scalar::random(&mut rng);
+
+        let (res0, res1) = execute_deferred_mpc(|fabric| async move {
+            fabric.consistent_share_plaintext(value, PARTY0).await
+        })
+        .await;
+
+        assert_eq!(res0.unwrap(), value);
+        assert_eq!(res1.unwrap(), value);
+    }
+
+    /// Tests exchanging single values and batches by committing to them before revealing them
+    #[tokio::test]
+    async fn test_exchange_value_committed() {
+        let (res0, res1) = execute_mock_mpc(|fabric| async move {
+            let party_id = Scalar::from(fabric.party_id());
+            let value = fabric.allocate_scalar(party_id);
+            let batch = fabric.allocate_scalars(vec![party_id; 3]);
+
+            let peer_value = fabric.exchange_value_committed(value).await;
+            let peer_batch = fabric.exchange_values_committed(&batch).await;
+
+            (peer_value, peer_batch)
+        })
+        .await;
+
+        assert_eq!(res0, (Scalar::one(), vec![Scalar::one(); 3]));
+        assert_eq!(res1, (Scalar::zero(), vec![Scalar::zero(); 3]));
+
+        // A party's commitment cannot be mirrored as its peer's
+        let payload = NetworkPayload::Scalar(Scalar::one());
+        assert_ne!(
+            committed_exchange_digest(&SessionId::default(), PARTY0, &payload, &[0; 32]),
+            committed_exchange_digest(&SessionId::default(), PARTY1, &payload, &[0; 32]),
+        );
+    }
+
+    /// Tests that pending results fail when the peer goes silent
+    #[tokio::test]
+    async fn test_liveness_timeout() {
+        let fabric = MpcFabric::with_config(
+            NoRecvNetwork,
+            PartyIDBeaverSource::default(),
+            FabricConfig::default().with_liveness_timeout(TEST_LIVENESS_TIMEOUT),
+        );
+
+        let res: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY1);
+        let res = res.fallible().await;
+        fabric.shutdown();
+
+        assert_eq!(res, Err(MpcError::Timeout(TEST_LIVENESS_TIMEOUT)));
+    }
+
+    /// Tests that results awaiting the peer fail once the peer disconnects, and that no more
+    /// network operations are executed after
+    #[tokio::test]
+    async fn test_peer_disconnect() {
+        let (stream, peer_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let fabric = MpcFabric::new(
+            MockNetwork::new(PARTY0, stream),
+            PartyIDBeaverSource::default(),
+        );
+
+        let received: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY1);
+        drop(peer_stream);
+        assert_eq!(received.fallible().await, Err(MpcError::PeerDisconnected));
+
+        // A send is not executed, so the sender's copy of the value never resolves
+        let sent: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY0);
+        assert_eq!(sent.fallible().await, Err(MpcError::PeerDisconnected));
+        assert_eq!(
+            fabric.try_share_scalar(1u8, PARTY0).await.map(|_| ()),
+            Err(MpcError::PeerDisconnected)
+        );
+        fabric.shutdown();
+    }
+
+    /// Tests that a peer closing the connection once it has sent every awaited payload fails
+    /// only the results that need the peer
+    #[tokio::test]
+    async fn test_peer_close() {
+        let (stream, mut peer_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let fabric = MpcFabric::new(
+            MockNetwork::new(PARTY0, stream),
+            PartyIDBeaverSource::default(),
+        );
+
+        let received: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY1);
+        peer_stream.send(NetworkOutbound {
+            result_id: received.id(),
+            payload: NetworkPayload::Scalar(Scalar::from(2u8)),
+        });
+        assert_eq!(received.fallible().await, Ok(Scalar::from(2u8)));
+
+        drop(peer_stream);
+        assert!(network_sender_exits(&fabric).await);
+
+        let sum = fabric.allocate_scalar(1u8) + fabric.allocate_scalar(2u8);
+        assert_eq!(sum.fallible().await, Ok(Scalar::from(3u8)));
+
+        let pending: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY1);
+        assert_eq!(pending.fallible().await, Err(MpcError::PeerDisconnected));
+        fabric.shutdown();
+    }
+
+    /// Tests that a committed challenge is only revealed once both commitments arrive, and
+    /// that the commitment is only sent once the challenge's dependencies are computed
+    #[tokio::test]
+    async fn test_committed_challenge_ordering() {
+        const SILENCE: Duration = Duration::from_millis(100);
+
+        async fn recv_message(stream: &mut UnboundedDuplexStream) -> NetworkOutbound {
+            loop {
+                let msg = stream.recv().await;
+                if msg.result_id != HEARTBEAT_RESULT_ID {
+                    return msg;
+                }
+            }
+        }
+
+        let (stream, mut peer_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let fabric = MpcFabric::new(
+            MockNetwork::new(PARTY0, stream),
+            PartyIDBeaverSource::default(),
+        );
+
+        // The challenge depends on a value received from the peer
+        let received: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY1);
+        let challenge = fabric.committed_challenge(vec![received.id()]);
+        assert!(
+            tokio::time::timeout(SILENCE, recv_message(&mut peer_stream))
+                .await
+                .is_err()
+        );
+
+        peer_stream.send(NetworkOutbound {
+            result_id: received.id(),
+            payload: NetworkPayload::Scalar(Scalar::one()),
+        });
+        let commitment = recv_message(&mut peer_stream).await;
+        assert!(matches!(commitment.payload, NetworkPayload::Bytes(_)));
+
+        // Nothing is revealed before the peer's commitment arrives
+        assert!(
+            tokio::time::timeout(SILENCE, recv_message(&mut peer_stream))
+                .await
+                .is_err()
+        );
+
+        let peer_contribution = Scalar::from(5u8);
+        let peer_payload = NetworkPayload::Scalar(peer_contribution);
+        let peer_salt = vec![1u8; COMMITTED_EXCHANGE_SALT_BYTES];
+        peer_stream.send(NetworkOutbound {
+            result_id: commitment.result_id + 1,
+            payload: NetworkPayload::Bytes(committed_exchange_digest(
+                &fabric.session_id(),
+                PARTY1,
+                &peer_payload,
+                &peer_salt,
+            )),
+        });
+
+        let revealed = recv_message(&mut peer_stream).await;
+        let my_contribution = match revealed.payload {
+            NetworkPayload::Scalar(val) => val,
+            payload => panic!("expected a scalar reveal, got {payload:?}"),
+        };
+        let salt = recv_message(&mut peer_stream).await;
+        peer_stream.send(NetworkOutbound {
+            result_id: salt.result_id + 1,
+            payload: peer_payload,
+        });
+        peer_stream.send(NetworkOutbound {
+            result_id: salt.result_id + 2,
+            payload: NetworkPayload::Bytes(peer_salt),
+        });
+
+        assert_eq!(
+            challenge.fallible().await,
+            Ok(my_contribution + peer_contribution)
+        );
+        fabric.shutdown();
+    }
+
+    /// Tests that an abort fails the pending results of the aborting party, and those of the
+    /// peer with the aborting party's reason
+    #[tokio::test]
+    async fn test_abort() {
+        let (res0, res1) = execute_mock_mpc(|fabric| async move {
+            if fabric.party_id() == PARTY0 {
+                fabric.abort("cheating detected");
+                let sum = fabric.allocate_scalar(1u8) + fabric.allocate_scalar(2u8);
+                sum.fallible().await.map(|_| ())
+            } else {
+                let res: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY0);
+                res.fallible().await.map(|_| ())
+            }
+        })
+        .await;
+
+        let reason = "cheating detected".to_string();
+        assert_eq!(res0, Err(MpcError::Aborted(reason.clone())));
+        assert_eq!(res1, Err(MpcError::PeerAborted(reason)));
+    }
+
+    /// Tests that a barrier resolves only once the peer reaches it
+    #[tokio::test]
+    async fn test_barrier() {
+        const PEER_DELAY: Duration = Duration::from_millis(100);
+
+        let (res0, res1) = execute_mock_mpc(|fabric| async move {
+            if fabric.party_id() == PARTY1 {
+                tokio::time::sleep(PEER_DELAY).await;
+            }
+
+            let start = Instant::now();
+            fabric.barrier().await.map(|_| start.elapsed())
+        })
+        .await;
+
+        assert!(res0.unwrap() >= PEER_DELAY);
+        assert!(res1.is_ok());
+    }
+
+    /// Tests that fallible sharing fails once the network sender has stopped
+    #[tokio::test]
+    async fn test_try_share() {
+        let fabric = MpcFabric::with_config(
+            NoRecvNetwork,
+            PartyIDBeaverSource::default(),
+            FabricConfig::default().with_liveness_timeout(TEST_LIVENESS_TIMEOUT),
+        );
+
+        assert!(fabric.try_share_scalar(1u8, PARTY0).await.is_ok());
+
+        // Wait for the silent peer to fail the computation
+        let expected = Err(MpcError::Timeout(TEST_LIVENESS_TIMEOUT));
+        let res: ScalarResult = fabric.share_plaintext(Scalar::one(), PARTY1);
+        assert_eq!(res.fallible().await.map(|_| ()), expected);
+
+        assert_eq!(
+            fabric.try_share_scalar(1u8, PARTY0).await.map(|_| ()),
+            expected
+        );
+        assert_eq!(
+            fabric
+                .try_share_point(StarkPoint::generator(), PARTY0)
+                .await
+                .map(|_| ()),
+            expected
+        );
+        fabric.shutdown();
+    }
+
+    /// Tests that fallible sharing fails when the value is enqueued but cannot be written
+    #[tokio::test]
+    async fn test_try_share_write_failure() {
+        let (stream, peer_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let fabric = MpcFabric::new(
+            MockNetwork::new(PARTY0, stream),
+            PartyIDBeaverSource::default(),
+        );
+
+        drop(peer_stream);
+        assert!(fabric.try_share_scalar(1u8, PARTY0).await.is_err());
+        fabric.shutdown();
+    }
+
+    /// Whether the fabric's network sender exits within the liveness timeout
+    async fn network_sender_exits(fabric: &MpcFabric) -> bool {
+        let exited = async {
+            while fabric.shutdown.receiver_count() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+
+        tokio::time::timeout(TEST_LIVENESS_TIMEOUT, exited)
+            .await
+            .is_ok()
+    }
+
+    /// Tests that a scoped fabric is shut down when the scope completes or panics
+    #[tokio::test]
+    async fn test_scope_shutdown() {
+        let fabric = MpcFabric::new(NoRecvNetwork, PartyIDBeaverSource::default());
+        let res = fabric
+            .clone()
+            .scope(|fabric| async move { fabric.allocate_scalar(2u8).await })
+            .await;
+
+        assert_eq!(res, Scalar::from(2u8));
+        assert!(network_sender_exits(&fabric).await);
+
+        let fabric = MpcFabric::new(NoRecvNetwork, PartyIDBeaverSource::default());
+        let scoped = fabric.clone().scope(|_| async { panic!("scope panicked") });
+        let res = tokio::spawn(scoped).await;
+
+        assert!(res.unwrap_err().is_panic());
+        assert!(network_sender_exits(&fabric).await);
+    }
+
+    /// Tests that shutting down a fabric flushes the messages of operations already allocated
+    #[tokio::test]
+    async fn test_draining_shutdown() {
+        let (stream, mut peer_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let fabric = MpcFabric::new(
+            MockNetwork::new(PARTY0, stream),
+            PartyIDBeaverSource::new(PARTY0),
+        );
+
+        // Shut down immediately after allocating a send, before the executor runs it
+        let value = Scalar::random(&mut thread_rng());
+        let _sent: ScalarResult = fabric.share_plaintext(value, PARTY0);
+        let res = fabric.shutdown().await;
+
+        assert_eq!(res, Ok(()));
+        loop {
+            let msg = tokio::time::timeout(TEST_LIVENESS_TIMEOUT, peer_stream.recv())
+                .await
+                .expect("sent value was not flushed");
+            if matches!(msg.payload, NetworkPayload::Scalar(sent) if sent == value) {
+                break;
+            }
+        }
+    }
+
+    /// Tests that flushing a fabric writes the messages of the operations executed so far
+    #[tokio::test]
+    async fn test_flush() {
+        let (stream, mut peer_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let fabric = MpcFabric::new(
+            MockNetwork::new(PARTY0, stream),
+            PartyIDBeaverSource::new(PARTY0),
+        );
+
+        let value = Scalar::random(&mut thread_rng());
+        let _sent: ScalarResult = fabric.share_plaintext(value, PARTY0);
+        let res = fabric.flush().await;
+
+        // All messages written before the flush resolves are already in the peer's stream
+        let mut flushed = false;
+        while let Ok(msg) = tokio::time::timeout(Duration::ZERO, peer_stream.recv()).await {
+            flushed |= matches!(msg.payload, NetworkPayload::Scalar(sent) if sent == value);
+        }
+        fabric.shutdown();
+
+        assert_eq!(res, Ok(()));
+        assert!(flushed);
+    }
+
+    /// Tests that heartbeats keep an idle connection alive past the liveness timeout
+    #[tokio::test]
+    async fn test_heartbeats() {
+        let (party0_stream, party1_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let party0_fabric = MpcFabric::with_config(
+            MockNetwork::new(PARTY0, party0_stream),
+            PartyIDBeaverSource::new(PARTY0),
+            FabricConfig::default().with_liveness_timeout(TEST_LIVENESS_TIMEOUT),
+        );
+        let party1_fabric = MpcFabric::with_config(
+            MockNetwork::new(PARTY1, party1_stream),
+            PartyIDBeaverSource::new(PARTY1),
+            FabricConfig::default().with_liveness_timeout(TEST_LIVENESS_TIMEOUT),
+        );
+
+        // Idle for longer than the liveness timeout before communicating
+        tokio::time::sleep(TEST_LIVENESS_TIMEOUT * 3).await;
+        let res0: ScalarResult = party0_fabric.share_plaintext(Scalar::one(), PARTY0);
+        let res1: ScalarResult = party1_fabric.share_plaintext(Scalar::one(), PARTY0);
+
+        let res0 = res0.fallible().await;
+        let res1 = res1.fallible().await;
+        party0_fabric.shutdown();
+        party1_fabric.shutdown();
+
+        assert_eq!(res0, Ok(Scalar::one()));
+        assert_eq!(res1, Ok(Scalar::one()));
+    }
+
+    /// Tests exchanging and computing on values of a user-defined type
+    #[tokio::test]
+    async fn test_custom_values() {
+        /// A user-defined type
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        struct Range {
+            /// The start of the range
+            start: u32,
+            /// The end of the range
+            end: u32,
+        }
+
+        impl CustomResult for Range {
+            fn to_bytes(&self) -> Vec<u8> {
+                [self.start.to_le_bytes(), self.end.to_le_bytes()].concat()
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                let start = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+                let end = u32::from_le_bytes(bytes.get(4..8)?.try_into().ok()?);
+                Some(Range { start, end })
+            }
+        }
+
+        let (res0, res1) = execute_mock_mpc(|fabric| async move {
+            let range = Custom(Range { start: 1, end: 5 });
+            let shared: ResultHandle<Custom<Range>> = fabric.share_plaintext(range, PARTY0);
+
+            // Widen the range in a gate
+            let widened: ResultHandle<Custom<Range>> =
+                fabric.new_gate_op(vec![shared.id()], |mut args| {
+                    let Custom(range) = Custom::<Range>::from(args.remove(0));
+                    Custom(Range {
+                        start: range.start - 1,
+                        end: range.end + 1,
+                    })
+                    .into()
+                });
+
+            widened.await.0
+        })
+        .await;
+
+        assert_eq!(res0, Range { start: 0, end: 6 });
+        assert_eq!(res1, Range { start: 0, end: 6 });
+    }
+
+    /// Tests lifting additive shares of points into authenticated points
+    #[tokio::test]
+    async fn test_preshared_points() {
+        let (res0, res1) = execute_mock_mpc(|fabric| async move {
+            // Each party holds an additive share of the generator multiples `3G` and `7G`
+            let share = |k: u8| {
+                let k = Scalar::from(k);
+                let party0_share = StarkPoint::generator() * Scalar::from(2u8);
+                if fabric.party_id() == PARTY0 {
+                    party0_share
+                } else {
+                    StarkPoint::generator() * k - party0_share
+                }
+            };
+
+            let single = fabric.allocate_preshared_point(share(3));
+            let batch = fabric.batch_allocate_preshared_point(vec![share(3), share(7)]);
+
+            let single = single.open_authenticated().await;
+            let batch = join_all(AuthenticatedStarkPointResult::open_authenticated_batch(
+                &batch,
+            ))
+            .await;
+            (single, batch)
+        })
+        .await;
+
+        let expected = |k: u8| StarkPoint::generator() * Scalar::from(k);
+        assert_eq!(res0, res1);
+        assert_eq!(res0.0, Ok(expected(3)));
+        assert_eq!(res0.1, vec![Ok(expected(3)), Ok(expected(7))]);
+    }
+
+    /// Tests sharing bits with a check that they are binary
+    #[tokio::test]
+    async fn test_share_bits() {
+        let (res0, res1) = execute_mock_mpc(|fabric| async move {
+            let single = fabric.share_bit(true, PARTY0).await?;
+            let batch = fabric
+                .batch_share_bit(vec![false, true, true], PARTY1)
+                .await?;
+
+            let mut bits = vec![single];
+            bits.extend(batch);
+            let opened = join_all(AuthenticatedScalarResult::open_authenticated_batch(&bits))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, MpcError>(opened)
+        })
+        .await;
+
+        let expected = [1u8, 0, 1, 1].into_iter().map(Scalar::from).collect_vec();
+        assert_eq!(res0, Ok(expected.clone()));
+        assert_eq!(res1, Ok(expected));
+    }
+
+    /// Tests that a sender cannot pass off a non-binary value as a bit
+    #[tokio::test]
+    async fn test_share_non_bit() {
+        let (res0, res1) = execute_mock_mpc(|fabric| async move {
+            let shared = fabric.batch_share_scalar(vec![1u8, 2u8], PARTY0);
+            fabric.check_shared_bits(shared).await.map(|_| ())
+        })
+        .await;
+
+        assert_eq!(res0, Err(MpcError::AuthenticationError));
+        assert_eq!(res1, Err(MpcError::AuthenticationError));
+    }
+
+    /// Tests that a payload of the wrong shape fails the receiving result
+    #[tokio::test]
+    async fn test_invalid_payload_shape() {
+        let (_, res) = execute_mock_mpc(|fabric| async move {
+            // Party 0 sends a point where party 1 expects a scalar
+            if fabric.party_id() == PARTY0 {
+                let sent: StarkPointResult = fabric.share_plaintext(random_point(), PARTY0);
+                sent.await;
+                Ok(Scalar::zero())
+            } else {
+                let received: ScalarResult = fabric.receive_value();
+                received.fallible().await
+            }
+        })
+        .await;
+
+        assert!(matches!(
+            res,
+            Err(MpcError::NetworkError(MpcNetworkError::InvalidPayload(_)))
+        ));
+    }
+
+    /// Tests that fabrics seeded with the same RNG sample the same shares
+    #[tokio::test]
+    async fn test_seeded_rng() {
+        /// Share a value from a fabric with the given RNG seed and return the local share
+        async fn local_share(seed: u64) -> Scalar {
+            let fabric = MpcFabric::with_config(
+                NoRecvNetwork,
+                PartyIDBeaverSource::default(),
+                FabricConfig::default().with_rng(StdRng::seed_from_u64(seed)),
+            );
+
+            let share = fabric.share_scalar(Scalar::one(), PARTY0).share.share.await;
+            fabric.shutdown();
+            share
+        }
+
+        assert_eq!(local_share(0).await, local_share(0).await);
+        assert_ne!(local_share(0).await, local_share(1).await);
+    }
+
+    /// Tests sharing values under masks derived from the correlated PRG
+    #[tokio::test]
+    async fn test_correlated_masks() {
+        let mut rng = thread_rng();
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        let point = random_point();
+
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_correlated_masks(),
+            |fabric| async move {
+                let shared_a = fabric.share_scalar(a, PARTY0);
+                let shared_b = fabric.batch_share_scalar(vec![b, b], PARTY1);
+                let shared_point = fabric.share_point(point, PARTY1);
+                let shared_points = fabric.batch_share_point(vec![point], PARTY0);
+
+                let product = (&shared_a * &shared_b[1]).open_authenticated().await;
+                let point_product = (&shared_a * &shared_point).open_authenticated().await;
+                let points = shared_points[0].open_authenticated().await;
+
+                (product, point_product, points)
+            },
+        )
+        .await;
+
+        assert_eq!(res.0, Ok(a * b));
+        assert_eq!(res.1, Ok(a * point));
+        assert_eq!(res.2, Ok(point));
+    }
+
+    /// Tests sharing, computing on, and opening unauthenticated values
+    #[cfg(feature = "semi_honest")]
+    #[tokio::test]
+    async fn test_semi_honest_sharing() {
+        use crate::algebra::{mpc_scalar::MpcScalarResult, mpc_stark_point::MpcStarkPointResult};
+
+        let mut rng = thread_rng();
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        let point = random_point();
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared_a = fabric.share_scalar_semi_honest(a, PARTY0);
+            let shared_b = fabric.batch_share_scalar_semi_honest(vec![b, b], PARTY1);
+            let shared_point = fabric.share_point_semi_honest(point, PARTY1);
+            let shared_points = fabric.batch_share_point_semi_honest(vec![point, point], PARTY0);
+
+            let product = (&shared_a * &shared_b[0]).open().await;
+            let sum = shared_b.into_iter().sum::<MpcScalarResult>().open().await;
+            let msm = MpcStarkPointResult::msm(&[shared_a.clone(), shared_a], &shared_points)
+                .open()
+                .await;
+            let point = shared_point.open().await;
+
+            (product, sum, msm, point)
+        })
+        .await;
+
+        assert_eq!(res.0, a * b);
+        assert_eq!(res.1, b + b);
+        assert_eq!(res.2, a * point + a * point);
+        assert_eq!(res.3, point);
+    }
+
+    /// Tests that authenticated openings in semi-honest mode skip the MAC check
+    #[tokio::test]
+    async fn test_semi_honest_mode() {
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_security_mode(SecurityMode::SemiHonest),
+            |fabric| async move {
+                // Corrupt the MAC of the shared value, the opening still succeeds
+                let mut shared = fabric.share_scalar(Scalar::from(2u8), PARTY0);
+                shared.mac = fabric.allocate_scalar(Scalar::zero()).into();
+
+                shared.open_authenticated().await
+            },
+        )
+        .await;
+
+        assert_eq!(res, Ok(Scalar::from(2u8)));
+    }
+
+    /// Tests that a fabric configured to record metrics counts its operations and messages
+    #[tokio::test]
+    async fn test_metrics() {
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_metrics(),
+            |fabric| async move {
+                let shared = fabric.share_scalar(Scalar::one(), PARTY0);
+                (&shared * &shared).open_authenticated().await.unwrap();
+
+                fabric.metrics()
+            },
+        )
+        .await;
+
+        let metrics = res.unwrap();
+        assert!(metrics.ops_executed > 0);
+        assert!(metrics.ops_executed <= metrics.ops_allocated);
+        assert!(metrics.messages_sent > 0);
+        assert!(metrics.messages_received > 0);
+        assert_ne!(metrics, FabricMetrics::default());
+    }
+
+    /// Tests that the metrics of a computation are broken down by label
+    #[tokio::test]
+    async fn test_metrics_by_label() {
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_metrics(),
+            |fabric| async move {
+                let shared = fabric.with_label("input", || fabric.share_scalar(1u8, PARTY0));
+                let opened = fabric.with_label("output", || (&shared * &shared).open());
+                opened.await;
+
+                (
+                    fabric.metrics().unwrap(),
+                    fabric.metrics_by_label().unwrap(),
+                )
+            },
+        )
+        .await;
+
+        let (metrics, by_label) = res;
+        let (input, output) = (by_label["input"], by_label["output"]);
+        assert!(input.messages_sent + input.messages_received > 0);
+        assert!(output.ops_allocated > 0);
+        assert!(output.ops_executed > 0);
+        assert!(output.messages_sent > 0);
+        assert!(output.messages_received > 0);
+        assert!(input.ops_allocated + output.ops_allocated <= metrics.ops_allocated);
+    }
+
+    /// Tests a computation in which both parties coalesce their messages to the peer
+    #[tokio::test]
+    async fn test_send_coalescing() {
+        const N: u64 = 20;
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_send_coalescing(8, Duration::from_millis(1)),
+            |fabric| async move {
+                let values = (0..N).map(Scalar::from).collect_vec();
+                let shared = fabric.batch_share_scalar(values, PARTY0);
+                let squares = shared.iter().map(|x| x * x).collect_vec();
+
+                futures::future::join_all(squares.iter().map(|x| x.open_authenticated()))
+                    .await
+                    .into_iter()
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )
+        .await;
+
+        let expected = (0..N).map(|x| Scalar::from(x * x)).collect_vec();
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    /// Tests a computation in which both parties coalesce independent openings into batches
+    #[tokio::test]
+    async fn test_open_coalescing() {
+        const N: u64 = 20;
+        let (res, _) = execute_mpc_with(
+            || {
+                FabricConfig::default()
+                    .with_open_coalescing(64, Duration::from_millis(50))
+                    .with_metrics()
+            },
+            |fabric| async move {
+                let values = (0..N).map(Scalar::from).collect_vec();
+                let shared = fabric.batch_share_scalar(values, PARTY0);
+                let point = fabric.share_point(StarkPoint::generator(), PARTY1);
+
+                let opened = join_all(shared.iter().map(|x| x.open())).await;
+                let opened_point = point.open().await;
+
+                (opened, opened_point, fabric.metrics().unwrap())
+            },
+        )
+        .await;
+
+        let (opened, opened_point, metrics) = res;
+        assert_eq!(opened, (0..N).map(Scalar::from).collect_vec());
+        assert_eq!(opened_point, StarkPoint::generator());
+        assert!(metrics.messages_sent < N);
+    }
+
+    /// Tests evaluating a circuit with the executor draining its queue in batches
+    #[tokio::test]
+    async fn test_executor_batching() {
+        const N: usize = 100;
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_executor_batch_size(32),
+            |fabric| async move {
+                let values = fabric.batch_share_scalar((1..=N as u64).collect_vec(), PARTY0);
+                let squares = values.iter().map(|v| v * v).collect_vec();
+                let sum = squares
+                    .iter()
+                    .fold(fabric.zero_authenticated(), |acc, x| &acc + x);
+
+                sum.open_authenticated().await
+            },
+        )
+        .await;
+
+        let expected: u64 = (1..=N as u64).map(|x| x * x).sum();
+        assert_eq!(res, Ok(Scalar::from(expected)));
+    }
+
+    /// Tests registering fixed bases and multiplying shared values by them
+    #[tokio::test]
+    async fn test_fixed_bases() {
+        let mut rng = thread_rng();
+        let base = random_point();
+        let value = Scalar::random(&mut rng);
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            assert!(fabric.fixed_base_table(&base).is_none());
+            let table = fabric.register_fixed_base(base);
+            assert!(Arc::ptr_eq(&table, &fabric.register_fixed_base(base)));
+            assert!(Arc::ptr_eq(
+                &fabric.generator_table(),
+                &fabric.fixed_base_table(&StarkPoint::generator()).unwrap()
+            ));
+
+            let shared = fabric.share_scalar(value, PARTY0);
+            let product = (&shared * base).open_authenticated().await;
+            let point = fabric.share_point(base, PARTY1).open_authenticated().await;
+
+            (product, point)
+        })
+        .await;
+
+        assert_eq!(res, (Ok(base * value), Ok(base)));
+    }
+
+    /// Tests that large batch gates computed across the executor's gate pool match the
+    /// plaintext computation
+    #[tokio::test]
+    async fn test_gate_parallelism() {
+        const N: usize = 64;
+        let mut rng = thread_rng();
+        let scalars = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let points = (0..N).map(|_| random_point()).collect_vec();
+
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_gate_parallelism(2, 8 /* min_batch_len */),
+            |fabric| {
+                let (scalars, points) = (scalars.clone(), points.clone());
+                async move {
+                    let shared = fabric.batch_share_scalar(scalars, PARTY0);
+                    let msm = StarkPoint::msm_authenticated(&shared, &points);
+                    let opened = AuthenticatedScalarResult::open_authenticated_batch(&shared);
+
+                    (
+                        msm.open_authenticated().await,
+                        join_all(opened)
+                            .await
+                            .into_iter()
+                            .collect::<Result<Vec<_>, _>>(),
+                    )
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(res.0, Ok(StarkPoint::msm(&scalars, &points)));
+        assert_eq!(res.1, Ok(scalars));
+    }
+
+    /// Tests that a fabric configured to profile gates records their execution times
+    #[tokio::test]
+    async fn test_gate_profile() {
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_gate_profiling(),
+            |fabric| async move {
+                let shared = fabric.share_scalar(Scalar::one(), PARTY0);
+                (&shared * &shared).open_authenticated().await.unwrap();
+
+                fabric.gate_profile()
+            },
+        )
+        .await;
+
+        let profile = res.unwrap();
+        assert!(profile.histogram(OperationKind::Gate).count > 0);
+        assert!(profile.network.count > 0);
+        assert_eq!(profile.gate.buckets.iter().sum::<u64>(), profile.gate.count);
+    }
+
+    /// Tests running fabrics on a caller-provided current-thread runtime, constructing them
+    /// outside of any runtime context
+    #[test]
+    fn test_caller_provided_runtime() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let config = || FabricConfig::default().with_runtime(runtime.handle().clone());
+
+        let (stream0, stream1) = UnboundedDuplexStream::new_duplex_pair();
+        let fabric0 = MpcFabric::with_config(
+            MockNetwork::new(PARTY0, stream0),
+            PartyIDBeaverSource::new(PARTY0),
+            config(),
+        );
+        let fabric1 = MpcFabric::with_config(
+            MockNetwork::new(PARTY1, stream1),
+            PartyIDBeaverSource::new(PARTY1),
+            config(),
+        );
+
+        let square = |fabric: &MpcFabric| {
+            let shared = fabric.share_scalar(Scalar::from(3u8), PARTY0);
+            (&shared * &shared).open_authenticated()
+        };
+        let (res0, res1) =
+            runtime.block_on(async { futures::join!(square(&fabric0), square(&fabric1)) });
+
+        assert_eq!(res0.unwrap(), Scalar::from(9u8));
+        assert_eq!(res1.unwrap(), Scalar::from(9u8));
+        fabric0.shutdown();
+        fabric1.shutdown();
+    }
+
+    /// Tests that an executor parked on an idle queue wakes when work is scheduled
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_executor_wakes_from_idle() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            // Let the executor go idle and park before scheduling any work
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            let shared = fabric.share_scalar(Scalar::from(3u8), PARTY0);
+            (&shared * &shared).open_authenticated().await
+        })
+        .await;
+
+        assert_eq!(res.unwrap(), Scalar::from(9u8));
+    }
+
+    /// Tests running the executor and network sender on dedicated threads
+    #[tokio::test]
+    async fn test_dedicated_threads() {
+        #[cfg(feature = "thread_affinity")]
+        let config = || {
+            FabricConfig::default()
+                .with_executor_core(0)
+                .with_network_core(0)
+        };
+        #[cfg(not(feature = "thread_affinity"))]
+        let config = || FabricConfig::default().with_dedicated_threads();
+
+        let (res, _) = execute_mpc_with(config, |fabric| async move {
+            let shared = fabric.share_scalar(Scalar::from(3u8), PARTY0);
+            (&shared * &shared).open_authenticated().await
+        })
+        .await;
+
+        assert_eq!(res.unwrap(), Scalar::from(9u8));
+    }
+
+    /// Tests that a panic in a gate's function fails the computation with the panic's message
+    #[tokio::test]
+    async fn test_gate_panic() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let one = fabric.one();
+            let panicked: ScalarResult =
+                fabric.new_gate_op(vec![one.id()], |_| panic!("gate failed"));
+            let dependent = &panicked + Scalar::one();
+
+            let expected = Err(MpcError::GatePanicked {
+                message: "gate failed".to_string(),
+                result_id: panicked.id(),
+                label: None,
+            });
+            (
+                panicked.fallible().await,
+                dependent.fallible().await,
+                expected,
+            )
+        })
+        .await;
+
+        let (panicked, dependent, expected) = res;
+        assert_eq!((panicked, dependent), (expected.clone(), expected));
+    }
+
+    /// Tests that awaiting a result as the wrong type fails with the types involved
+    #[tokio::test]
+    async fn test_type_mismatch() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let one = fabric.one();
+            let point: StarkPointResult =
+                fabric.new_gate_op(vec![one.id()], |mut args| args.remove(0));
+            let result_id = point.id();
+
+            (point.fallible().await.map(|_| ()), result_id)
+        })
+        .await;
+
+        let (res, result_id) = res;
+        assert_eq!(
+            res,
+            Err(MpcError::TypeMismatch {
+                result_id,
+                expected: "point",
+                found: "scalar",
+            })
+        );
+    }
+
+    /// Tests labeling operations, explicitly and within nested label scopes
+    #[tokio::test]
+    async fn test_operation_labels() {
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_gate_profiling(),
+            |fabric| async move {
+                let one = fabric.one();
+                fabric
+                    .label_scope("outer", async {
+                        let a = &one + &one;
+                        let b = fabric.with_label("inner", || &a * Scalar::from(2u8));
+                        (&b + &one).await
+                    })
+                    .await;
+
+                let panicked: ScalarResult =
+                    fabric.new_labeled_gate_op("explicit", vec![one.id()], |_| panic!("failed"));
+                let result_id = panicked.id();
+                let err = panicked.fallible().await;
+                let expected = Err(MpcError::GatePanicked {
+                    message: "failed".to_string(),
+                    result_id,
+                    label: Some("explicit".to_string()),
+                });
+
+                let profile = fabric.gate_profile().unwrap();
+                let count = |label: &str| profile.labels.get(label).map(|h| h.count);
+                (
+                    err == expected,
+                    count("outer"),
+                    count("inner"),
+                    profile.labels.len(),
+                )
+            },
+        )
+        .await;
+
+        assert_eq!(res, (true, Some(2), Some(1), 3));
+    }
+
+    /// Tests that the label scopes of concurrent tasks sharing a fabric do not interfere
+    #[tokio::test]
+    async fn test_concurrent_label_scopes() {
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_gate_profiling(),
+            |fabric| async move {
+                let one = fabric.one();
+                let spawn_labeled = |label: &'static str, n: usize| {
+                    let one = one.clone();
+                    let scope = fabric.label_scope(label, async move {
+                        let mut sum = one.clone();
+                        for _ in 0..n {
+                            sum = &sum + &one;
+                            tokio::task::yield_now().await;
+                        }
+
+                        sum.await
+                    });
+                    tokio::spawn(scope)
+                };
+
+                let a = spawn_labeled("a", 3);
+                let b = spawn_labeled("b", 5);
+                let sums = (a.await.unwrap(), b.await.unwrap());
+
+                let profile = fabric.gate_profile().unwrap();
+                let count = |label: &str| profile.labels.get(label).map(|h| h.count);
+                (sums, count("a"), count("b"))
+            },
+        )
+        .await;
+
+        let sums = (Scalar::from(4u8), Scalar::from(6u8));
+        assert_eq!(res, (sums, Some(3), Some(5)));
+    }
+
+    /// Tests observing the operations scheduled in a fabric
+    #[tokio::test]
+    async fn test_op_observer() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let observed = Arc::new(Mutex::new(Vec::new()));
+            let observed_clone = observed.clone();
+            fabric.set_op_observer(move |op| {
+                let entry = (op.kind(), op.args().to_vec(), op.result_ids());
+                observed_clone.lock().unwrap().push(entry);
+            });
+
+            let a = fabric.allocate_scalar(1u8);
+            let b = fabric.allocate_scalar(2u8);
+            let sum = &a + &b;
+            let opened = fabric.share_scalar(sum.clone().await, PARTY0).open().await;
+
+            // No operations are observed once the hook is removed
+            fabric.clear_op_observer();
+            let n_observed = observed.lock().unwrap().len();
+            let _product = &a * &b;
+
+            let observed = observed.lock().unwrap().clone();
+            (observed, n_observed, vec![a.id(), b.id()], sum.id(), opened)
+        })
+        .await;
+
+        let (observed, n_observed, args, sum_id, opened) = res;
+        assert_eq!(observed.len(), n_observed);
+        assert_eq!(observed[0], (OperationKind::Gate, args, vec![sum_id]));
+        assert!(observed
+            .iter()
+            .any(|(kind, ..)| *kind == OperationKind::Network));
+        assert_eq!(opened, Scalar::from(3u8));
+    }
+
+    /// Tests recording, signing, and exporting a transcript of an authenticated opening
+    #[tokio::test]
+    async fn test_signed_transcript() {
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_transcript(),
+            |fabric| async move {
+                // Sharing the value opens masked values outside the audited scope
+                let shared = fabric.share_scalar(42u8, PARTY0);
+                let opened = fabric.with_label("audit", || shared.open_authenticated());
+                opened.await.unwrap();
+
+                let transcript = fabric.transcript().unwrap();
+                let audited = transcript
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.label.as_deref() == Some("audit"))
+                    .collect_vec();
+                let kinds = audited.iter().map(|entry| entry.kind).collect_vec();
+                let values = audited
+                    .iter()
+                    .filter(|entry| {
+                        matches!(
+                            entry.kind,
+                            TranscriptEntryKind::Opening | TranscriptEntryKind::MacCheck
+                        )
+                    })
+                    .map(|entry| match entry.value {
+                        Some(NetworkPayload::Scalar(value)) => value,
+                        _ => panic!("expected a scalar value"),
+                    })
+                    .collect_vec();
+
+                // Sign the transcript and round trip it through a file
+                let keypair = IdentityKeypair::random(&mut thread_rng());
+                let signed = transcript.sign(&keypair, &mut thread_rng());
+                let path = std::env::temp_dir().join(format!(
+                    "mpc-stark-transcript-{}-{}.json",
+                    std::process::id(),
+                    fabric.party_id()
+                ));
+                signed.write_to_file(&path).unwrap();
+                let mut read = SignedTranscript::read_from_file(&path).unwrap();
+                std::fs::remove_file(&path).unwrap();
+                let verified = read.verify();
+
+                // Tamper with the opened value
+                read.transcript.entries[0].value = Some(NetworkPayload::Scalar(Scalar::zero()));
+                (kinds, values, verified, read.verify())
+            },
+        )
+        .await;
+
+        let kinds = vec![
+            TranscriptEntryKind::Opening,
+            TranscriptEntryKind::LocalCommitment,
+            TranscriptEntryKind::PeerCommitment,
+            TranscriptEntryKind::MacCheck,
+        ];
+        let values = vec![Scalar::from(42u8), Scalar::one()];
+        assert_eq!(res, (kinds, values, true, false));
+    }
+
+    /// Tests releasing results early, explicitly and after a single use
+    #[tokio::test]
+    async fn test_release_results() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let stored = |id| fabric.inner.results.read().unwrap().get(id).is_some();
+
+            // Release a result after the operations allocated on it
+            let a = fabric.allocate_scalar(2u8);
+            let a_id = a.id();
+            let square = &a * &a;
+            a.release();
+
+            // Release a result after its first use
+            let b = fabric.allocate_scalar(3u8).consume_once();
+            let b_id = b.id();
+            let incremented = &b + Scalar::one();
+
+            // Flush the executor's queue before checking the results are released
+            let res = (square.await, incremented.await);
+            (&fabric.allocate_scalar(1u8) + Scalar::one()).await;
+
+            (res, stored(a_id), stored(b_id))
+        })
+        .await;
+
+        assert_eq!(res, ((Scalar::from(4u8), Scalar::from(4u8)), false, false));
+    }
+
+    /// Tests moving a result's value out of the fabric, after the operations allocated on it
+    #[tokio::test]
+    async fn test_into_owned() {
+        const N: u64 = 100;
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let ids = fabric
+                .allocate_scalars((0..N).collect_vec())
+                .iter()
+                .map(|v| v.id())
+                .collect_vec();
+            let batch: ResultHandle<Vec<Scalar>> = fabric.new_gate_op(ids, |args| {
+                ResultValue::ScalarBatch(args.into_iter().map(Scalar::from).collect())
+            });
+            let batch_id = batch.id();
+
+            // An operation allocated before the batch is taken still receives it
+            let sum: ResultHandle<Scalar> = fabric.new_gate_op(vec![batch_id], |mut args| {
+                let batch: Vec<Scalar> = args.remove(0).into();
+                ResultValue::Scalar(batch.into_iter().fold(Scalar::zero(), |acc, x| acc + x))
+            });
+
+            let batch = batch.into_owned().await;
+            let zero = fabric.zero().into_owned().await;
+            let stored = fabric.inner.results.read().unwrap().get(batch_id).is_some();
+            (batch, sum.await, zero, stored)
+        })
+        .await;
+
+        let expected = (0..N).map(Scalar::from).collect_vec();
+        assert_eq!(
+            res,
+            (
+                expected,
+                Scalar::from(N * (N - 1) / 2),
+                Scalar::zero(),
+                false
+            )
+        );
+    }
+
+    /// Tests joining a batch of results, some of which wait on the network
+    #[tokio::test]
+    async fn test_join_results() {
+        const N: u64 = 10;
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let opened = (0..N)
+                .map(|i| fabric.share_scalar(i, PARTY0).open())
+                .collect_vec();
+
+            let empty: Vec<Scalar> = join_results(Vec::new()).await;
+            (join_results(opened).await, empty)
+        })
+        .await;
+
+        assert_eq!(res, ((0..N).map(Scalar::from).collect_vec(), vec![]));
+    }
+
+    /// Tests recycling the IDs of released results and computing with the recycled IDs
+    #[tokio::test]
+    async fn test_recycle_result_ids() {
+        const N: usize = 10;
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let values = fabric.allocate_scalars((0..N as u64).collect_vec());
+            let released = values.iter().map(|v| v.id()).collect_vec();
+            let sum = values.iter().fold(fabric.zero(), |acc, v| &acc + v).await;
+            values.into_iter().for_each(|v| v.release());
+
+            let n_recycled = fabric.recycle_result_ids().await.unwrap();
+            let a = fabric.share_scalar(sum, PARTY0);
+            let b = fabric.share_scalar(2u8, PARTY1);
+            let product = (&a * &b).open_authenticated().await;
+
+            let reused = a.ids().iter().any(|id| released.contains(id));
+            (n_recycled, reused, product)
+        })
+        .await;
+
+        let sum: u64 = (0..N as u64).sum();
+        assert_eq!(res, (N, true, Ok(Scalar::from(2 * sum))));
+    }
+
+    /// Tests compacting the fabric after a large batch is released, and computing afterwards
+    #[tokio::test]
+    async fn test_compact() {
+        const N: usize = 50_000;
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let values = fabric.allocate_scalars(vec![Scalar::one(); N]);
+            values.into_iter().for_each(|v| v.release());
+
+            // Let the executor drop the released results before compacting
+            fabric.flush().await.unwrap();
+            let n_freed = fabric.compact();
+
+            let a = fabric.share_scalar(3u8, PARTY0);
+            let b = fabric.share_scalar(2u8, PARTY1);
+            let product = (&a * &b).open_authenticated().await;
+
+            (n_freed > 0, product)
+        })
+        .await;
+
+        assert_eq!(res, (true, Ok(Scalar::from(6u8))));
+    }
+
+    /// Tests estimating the cost of a circuit in a dry run
+    #[test]
+    fn test_dry_run() {
+        let fabric = MpcFabric::dry_run();
+        assert_eq!(fabric.cost_estimate(), Some(CostEstimate::default()));
+
+        let a = fabric.share_scalar(Scalar::one(), PARTY0);
+        let b = fabric.share_scalar(Scalar::one(), PARTY1);
+        let shared_estimate = fabric.cost_estimate().unwrap();
+        let _opened = (&a * &b).open_authenticated();
+
+        // The multiplication consumes a triple, and the MACs of its shares consume more
+        let estimate = fabric.cost_estimate().unwrap();
+        assert!(estimate.triples > shared_estimate.triples);
+        assert!(estimate.gates > 0);
+        assert!(estimate.messages_sent > 0);
+        assert!(estimate.messages_received > 0);
+        assert!(estimate.bytes_sent > 0);
+
+        // Sharing, the multiplication, and the opening each take a round, the MAC check
+        // commits to its shares before opening them
+        assert!(estimate.rounds >= 4);
+    }
+
+    /// Tests that the cost estimate of a dry run is broken down by label
+    #[test]
+    fn test_dry_run_by_label() {
+        let fabric = MpcFabric::dry_run();
+        let (a, b) = fabric.with_label("input", || {
+            (
+                fabric.share_scalar(Scalar::one(), PARTY0),
+                fabric.share_scalar(Scalar::one(), PARTY1),
+            )
+        });
+        let product = fabric.with_label("multiply", || &a * &b);
+        // The opening is not labeled, so its messages are only counted in the total
+        let _unlabeled = product.open();
+
+        let estimate = fabric.cost_estimate().unwrap();
+        let by_label = fabric.cost_estimate_by_label().unwrap();
+        let (input, multiply) = (by_label["input"], by_label["multiply"]);
+        assert!(input.messages_sent > 0);
+        assert!(input.messages_received > 0);
+        assert!(multiply.triples > 0);
+        assert_eq!(input.triples + multiply.triples, estimate.triples);
+        assert!(multiply.gates > 0);
+        assert!(multiply.bytes_sent > 0);
+        assert!(input.bytes_sent + multiply.bytes_sent < estimate.bytes_sent);
+    }
+
+    /// Tests that exhausting the beaver source fails the computation rather than panicking
+    #[tokio::test]
+    async fn test_preprocessing_exhausted() {
+        /// A source that holds no beaver triples
+        #[derive(Default)]
+        struct NoTripleSource(PartyIDBeaverSource);
+
+        impl zeroize::Zeroize for NoTripleSource {
+            fn zeroize(&mut self) {}
+        }
+
+        impl FallibleSharedValueSource for NoTripleSource {
+            fn try_next_shared_bit_batch(&mut self, n: usize) -> Result<Vec<Scalar>, MpcError> {
+                self.0.try_next_shared_bit_batch(n)
+            }
+
+            fn try_next_shared_value_batch(&mut self, n: usize) -> Result<Vec<Scalar>, MpcError> {
+                self.0.try_next_shared_value_batch(n)
+            }
+
+            fn try_next_shared_inverse_pair_batch(
+                &mut self,
+                n: usize,
+            ) -> Result<(Vec<Scalar>, Vec<Scalar>), MpcError> {
+                self.0.try_next_shared_inverse_pair_batch(n)
+            }
+
+            fn try_next_triplet_batch(&mut self, _: usize) -> Result<TripletBatch, MpcError> {
+                Err(MpcError::PreprocessingExhausted)
+            }
+        }
+
+        let fabric = MpcFabric::new(NoRecvNetwork, NoTripleSource::default());
+        let (a, _, _) = fabric.next_beaver_triple();
+        let res = a.to_scalar().fallible().await;
+        fabric.shutdown();
+
+        assert_eq!(res, Err(MpcError::PreprocessingExhausted));
+    }
+
+    /// Tests that the online phase draws from preprocessed material
+    #[tokio::test]
+    async fn test_preprocess() {
+        let mut rng = thread_rng();
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let spec = PreprocessingSpec {
+                triples: 1,
+                bits: 1,
+                inverse_pairs: 1,
+                values: 0,
+            };
+            fabric.preprocess(spec).await.unwrap();
+
+            let shared_a = fabric.share_scalar(a, PARTY0);
+            let shared_b = fabric.share_scalar(b, PARTY1);
+            let product = (&shared_a * &shared_b).open_authenticated().await;
+            let bit = fabric.random_shared_bit().open_authenticated().await;
+            let (l, r) = fabric.random_inverse_pair();
+            let inverse_product = (&l * &r).open_authenticated().await;
+
+            let locked_pool = fabric.inner.preprocessed.lock().unwrap();
+            let pool_empty = locked_pool.triples.is_empty()
+                && locked_pool.bits.is_empty()
+                && locked_pool.inverse_pairs.is_empty();
+
+            (product, bit, inverse_product, pool_empty)
+        })
+        .await;
+
+        assert_eq!(res.0, Ok(a * b));
+        assert_eq!(res.1, Ok(Scalar::one()));
+        assert_eq!(res.2, Ok(Scalar::one()));
+        assert!(res.3);
+    }
+
+    /// Tests authenticated arithmetic under a MAC key sampled locally by the parties
+    #[tokio::test]
+    async fn test_local_mac_key() {
+        let mut rng = thread_rng();
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+
+        let (res, _) = execute_mpc_with(
+            || FabricConfig::default().with_local_mac_key(),
+            |fabric| async move {
+                let shared_a = fabric.share_scalar(a, PARTY0);
+                let shared_b = fabric.share_scalar(b, PARTY1);
+                let product = (&shared_a * &shared_b).open_authenticated().await;
+                let mac_key = fabric.borrow_mac_key().open().await;
+
+                (product, mac_key)
+            },
+        )
+        .await;
+
+        assert_eq!(res.0, Ok(a * b));
+        // The key is not the one held by the beaver source
+        assert_ne!(res.1, Scalar::one());
+    }
+}
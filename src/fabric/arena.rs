@@ -0,0 +1,141 @@
+//! Defines the arena that the executor holds in-flight operations in
+//!
+//! Operation IDs grow without bound over the course of a computation, so rather than
+//! indexing operations by their ID the arena stores them in a dense set of slots and reuses
+//! the slot of an operation once it executes. The arena is then sized by the number of
+//! operations in flight at once rather than the number allocated over the computation
+
+use super::{Operation, OperationId};
+
+/// A key referencing an operation in the arena
+///
+/// A slot is reused once its operation executes, so the key carries the operation's ID and
+/// lookups through a key whose operation has executed find nothing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct OpKey {
+    /// The slot the operation is stored in
+    slot: usize,
+    /// The ID of the operation
+    pub id: OperationId,
+}
+
+/// A slab of in-flight operations
+pub(crate) struct OperationArena {
+    /// The slots of the arena
+    slots: Vec<Option<Operation>>,
+    /// The indices of the empty slots, reused before the arena grows
+    free: Vec<usize>,
+}
+
+impl OperationArena {
+    /// Constructor, takes a size hint to pre-allocate slots
+    pub fn new(size_hint: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(size_hint),
+            free: Vec::new(),
+        }
+    }
+
+    /// Store an operation in a free slot, returning its key
+    pub fn insert(&mut self, op: Operation) -> OpKey {
+        let id = op.id;
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(op);
+                slot
+            }
+            None => {
+                self.slots.push(Some(op));
+                self.slots.len() - 1
+            }
+        };
+
+        OpKey { slot, id }
+    }
+
+    /// Get the operation referenced by a key, if it has not yet been taken
+    pub fn get(&self, key: OpKey) -> Option<&Operation> {
+        self.slots
+            .get(key.slot)?
+            .as_ref()
+            .filter(|op| op.id == key.id)
+    }
+
+    /// Get a mutable reference to the operation referenced by a key, if it has not yet been
+    /// taken
+    pub fn get_mut(&mut self, key: OpKey) -> Option<&mut Operation> {
+        self.slots
+            .get_mut(key.slot)?
+            .as_mut()
+            .filter(|op| op.id == key.id)
+    }
+
+    /// Take ownership of the operation referenced by a key, freeing its slot
+    pub fn take(&mut self, key: OpKey) -> Option<Operation> {
+        self.get(key)?;
+        self.free.push(key.slot);
+        self.slots[key.slot].take()
+    }
+
+    /// Drop all operations in the arena
+    ///
+    /// Keys to the dropped operations find nothing, as their slots are reused only by
+    /// operations with other IDs
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+    }
+
+    /// Iterate over the operations in the arena
+    pub fn iter(&self) -> impl Iterator<Item = &Operation> {
+        self.slots.iter().flatten()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fabric::{Operation, OperationType};
+
+    use super::OperationArena;
+
+    /// Build an operation with the given ID
+    fn op(id: usize) -> Operation {
+        Operation {
+            id,
+            result_ids: vec![id],
+            inflight_args: 0,
+            args: vec![],
+            op_type: OperationType::Gate {
+                function: Box::new(|mut args| args.remove(0)),
+            },
+            label: None,
+        }
+    }
+
+    /// Tests that slots are reused once their operation is taken, and that keys to taken
+    /// operations find nothing
+    #[test]
+    fn test_operation_arena() {
+        let mut arena = OperationArena::new(2 /* size_hint */);
+        let key1 = arena.insert(op(1));
+        let key2 = arena.insert(op(2));
+        assert_eq!(arena.get(key1).map(|op| op.id), Some(1));
+
+        assert_eq!(arena.take(key1).map(|op| op.id), Some(1));
+        assert!(arena.get(key1).is_none());
+        assert!(arena.take(key1).is_none());
+
+        // The freed slot is reused without invalidating the stale key
+        let key3 = arena.insert(op(3));
+        assert!(arena.get(key1).is_none());
+        assert_eq!(arena.get_mut(key3).map(|op| op.id), Some(3));
+        assert_eq!(arena.iter().map(|op| op.id).collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(arena.slots.len(), 2);
+
+        assert_eq!(arena.take(key2).map(|op| op.id), Some(2));
+
+        arena.clear();
+        assert!(arena.get(key3).is_none());
+        assert_eq!(arena.iter().count(), 0);
+    }
+}
@@ -0,0 +1,397 @@
+//! Defines the configuration a fabric is constructed with
+
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    time::Duration,
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+use tokio::runtime::Handle;
+
+use super::{
+    executor::OpenCoalescing, network_sender::SendCoalescing, parallel::GateParallelism, FabricRng,
+};
+
+/// The default size hint to give the fabric for buffer pre-allocation
+const DEFAULT_SIZE_HINT: usize = 10_000;
+/// The default amount of time the peer may go silent before it is considered disconnected
+const DEFAULT_LIVENESS_TIMEOUT_MS: u64 = 30_000; // 30 seconds
+/// The default number of jobs the executor drains from its queue at once
+const DEFAULT_EXECUTOR_BATCH_SIZE: usize = 1;
+
+/// The adversary model a fabric defends against
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SecurityMode {
+    /// Authenticated openings check the MACs of opened values, detecting a counterparty
+    /// that deviates from the protocol
+    #[default]
+    Malicious,
+    /// Authenticated openings skip their MAC checks, which is only secure against a
+    /// counterparty that follows the protocol
+    SemiHonest,
+}
+
+/// How the executor divides its time between results received from the peer and local jobs
+///
+/// Results received from the peer are queued separately from the operations and results
+/// produced locally, so that a heavy local gate load does not hold back the results the peer
+/// sends, nor a burst of inbound results the local gates. When one queue is empty the
+/// executor serves the other regardless of the policy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchedulingPolicy {
+    /// Alternate between inbound results and local jobs
+    #[default]
+    RoundRobin,
+    /// Serve up to `inbound` results received from the peer for every `local` local jobs
+    ///
+    /// A zero share is treated as one
+    Ratio {
+        /// The number of inbound results served in each round
+        inbound: usize,
+        /// The number of local jobs served in each round
+        local: usize,
+    },
+}
+
+impl SchedulingPolicy {
+    /// The number of inbound results and local jobs served in each round
+    pub(crate) fn shares(&self) -> (usize, usize) {
+        match *self {
+            SchedulingPolicy::RoundRobin => (1, 1),
+            SchedulingPolicy::Ratio { inbound, local } => (inbound.max(1), local.max(1)),
+        }
+    }
+}
+
+/// The configuration of an `MpcFabric`, applied via `MpcFabric::with_config`
+///
+/// Both parties must agree on the security mode and on whether masks are correlated or
+/// MAC checks are deferred, the remaining options are local to each party
+pub struct FabricConfig {
+    /// The number of gates to pre-allocate buffer space for
+    pub(crate) size_hint: usize,
+    /// The maximum number of messages that may be queued for the peer, if bounded
+    pub(crate) outbound_queue_bound: Option<usize>,
+    /// The amount of time the peer may go silent before it is considered disconnected
+    pub(crate) liveness_timeout: Duration,
+    /// How messages to the peer are coalesced into wire messages, if at all
+    pub(crate) send_coalescing: Option<SendCoalescing>,
+    /// How the executor coalesces single openings into batches, if at all
+    pub(crate) open_coalescing: Option<OpenCoalescing>,
+    /// The adversary model the fabric defends against
+    pub(crate) security_mode: SecurityMode,
+    /// Whether the MAC checks of values opened with `open` are deferred until `finalize`
+    pub(crate) deferred_mac_check: bool,
+    /// Whether the masks of shared values are derived from a PRG agreed on with the peer
+    pub(crate) correlated_masks: bool,
+    /// Whether the fabric records execution metrics
+    pub(crate) metrics: bool,
+    /// Whether the executor records the execution time of each gate
+    pub(crate) profile_gates: bool,
+    /// Whether the fabric records a transcript of its openings, commitments, and MAC checks
+    pub(crate) transcript: bool,
+    /// Whether the parties sample their MAC key shares locally rather than from the source
+    pub(crate) local_mac_key: bool,
+    /// The RNG the fabric samples local randomness from, if not seeded from the OS
+    pub(crate) rng: Option<Box<dyn FabricRng>>,
+    /// The runtime the fabric's tasks are spawned onto, if not the ambient runtime
+    pub(crate) runtime: Option<Handle>,
+    /// Whether the executor and network sender run on dedicated OS threads rather than the
+    /// runtime's blocking pool
+    pub(crate) dedicated_threads: bool,
+    /// The amount of time the executor may sit idle with operations in flight before it
+    /// reports them as stalled, if stall detection is enabled
+    pub(crate) stall_timeout: Option<Duration>,
+    /// How the executor divides its time between inbound results and local jobs
+    pub(crate) scheduling_policy: SchedulingPolicy,
+    /// The maximum number of jobs the executor drains from its queue at once
+    pub(crate) executor_batch_size: usize,
+    /// How the executor parallelizes large batch gates, if at all
+    pub(crate) gate_parallelism: Option<GateParallelism>,
+    /// The CPU core the executor's dedicated thread is pinned to, if any
+    #[cfg(feature = "thread_affinity")]
+    pub(crate) executor_core: Option<usize>,
+    /// The CPU core the network sender's dedicated thread is pinned to, if any
+    #[cfg(feature = "thread_affinity")]
+    pub(crate) network_core: Option<usize>,
+}
+
+impl Default for FabricConfig {
+    fn default() -> Self {
+        Self {
+            size_hint: DEFAULT_SIZE_HINT,
+            outbound_queue_bound: None,
+            liveness_timeout: Duration::from_millis(DEFAULT_LIVENESS_TIMEOUT_MS),
+            send_coalescing: None,
+            open_coalescing: None,
+            security_mode: SecurityMode::default(),
+            deferred_mac_check: false,
+            correlated_masks: false,
+            metrics: false,
+            profile_gates: false,
+            transcript: false,
+            local_mac_key: false,
+            rng: None,
+            runtime: None,
+            dedicated_threads: false,
+            stall_timeout: None,
+            scheduling_policy: SchedulingPolicy::default(),
+            executor_batch_size: DEFAULT_EXECUTOR_BATCH_SIZE,
+            gate_parallelism: None,
+            #[cfg(feature = "thread_affinity")]
+            executor_core: None,
+            #[cfg(feature = "thread_affinity")]
+            network_core: None,
+        }
+    }
+}
+
+impl Debug for FabricConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mut debug = f.debug_struct("FabricConfig");
+        debug
+            .field("size_hint", &self.size_hint)
+            .field("outbound_queue_bound", &self.outbound_queue_bound)
+            .field("liveness_timeout", &self.liveness_timeout)
+            .field("send_coalescing", &self.send_coalescing)
+            .field("open_coalescing", &self.open_coalescing)
+            .field("security_mode", &self.security_mode)
+            .field("deferred_mac_check", &self.deferred_mac_check)
+            .field("correlated_masks", &self.correlated_masks)
+            .field("metrics", &self.metrics)
+            .field("profile_gates", &self.profile_gates)
+            .field("transcript", &self.transcript)
+            .field("local_mac_key", &self.local_mac_key)
+            .field("custom_rng", &self.rng.is_some())
+            .field("custom_runtime", &self.runtime.is_some())
+            .field("dedicated_threads", &self.dedicated_threads)
+            .field("stall_timeout", &self.stall_timeout)
+            .field("scheduling_policy", &self.scheduling_policy)
+            .field("executor_batch_size", &self.executor_batch_size)
+            .field("gate_parallelism", &self.gate_parallelism);
+        #[cfg(feature = "thread_affinity")]
+        debug
+            .field("executor_core", &self.executor_core)
+            .field("network_core", &self.network_core);
+        debug.finish()
+    }
+}
+
+impl FabricConfig {
+    /// Set how much buffer space the fabric should allocate for results, given in number
+    /// of gates
+    pub fn with_size_hint(mut self, size_hint: usize) -> Self {
+        self.size_hint = size_hint;
+        self
+    }
+
+    /// Bound the number of messages that may be queued for the peer
+    ///
+    /// If the queue fills, e.g. because the peer stopped reading, the computation fails
+    /// rather than buffering without limit. The queue is unbounded by default
+    pub fn with_outbound_queue_bound(mut self, bound: usize) -> Self {
+        self.outbound_queue_bound = Some(bound);
+        self
+    }
+
+    /// Set the amount of time the peer may go silent before it is considered disconnected
+    ///
+    /// The parties send each other heartbeats while connected, if no message is received from
+    /// the peer within the timeout all pending results resolve to `MpcError::Timeout`
+    pub fn with_liveness_timeout(mut self, liveness_timeout: Duration) -> Self {
+        self.liveness_timeout = liveness_timeout;
+        self
+    }
+
+    /// Coalesce messages to the peer, packing up to `max_messages` outbound results into a
+    /// single wire message
+    ///
+    /// Once a message is ready to send, the sender waits up to `window` for more messages to
+    /// pack with it, a zero window packs only the messages already queued. Coalescing saves
+    /// framing and syscall overhead on many small messages at the cost of up to `window` of
+    /// added latency. The peer unpacks coalesced messages regardless of its own configuration
+    pub fn with_send_coalescing(mut self, max_messages: usize, window: Duration) -> Self {
+        self.send_coalescing = Some(SendCoalescing {
+            max_messages,
+            window,
+        });
+        self
+    }
+
+    /// Coalesce single scalar and point openings into batches, packing up to `max_openings`
+    /// of them into a single `ScalarBatch` or `PointBatch` message
+    ///
+    /// Openings are held back until the executor runs out of work and `window` has passed
+    /// since the oldest of them was executed, then sent together; a zero window sends them as
+    /// soon as the executor runs out of work. Independent openings then share one message
+    /// rather than sending one each. The peer unpacks batched openings regardless of its own configuration
+    pub fn with_open_coalescing(mut self, max_openings: usize, window: Duration) -> Self {
+        self.open_coalescing = Some(OpenCoalescing {
+            max_openings,
+            window,
+        });
+        self
+    }
+
+    /// Set the adversary model the fabric defends against
+    pub fn with_security_mode(mut self, security_mode: SecurityMode) -> Self {
+        self.security_mode = security_mode;
+        self
+    }
+
+    /// Defer the MAC checks of values opened with `open`
+    ///
+    /// Openings are treated as unauthenticated during execution, and a single aggregate
+    /// MAC check over all of them is run when `finalize` is called
+    pub fn with_deferred_mac_check(mut self) -> Self {
+        self.deferred_mac_check = true;
+        self
+    }
+
+    /// Derive the masks of shared values from a PRG whose seed is agreed on with the peer
+    /// when the fabric is constructed
+    ///
+    /// Sharing a value then requires no communication, as the receiver derives its share
+    /// locally. Both parties must share values in the same order
+    pub fn with_correlated_masks(mut self) -> Self {
+        self.correlated_masks = true;
+        self
+    }
+
+    /// Record execution metrics, readable via `MpcFabric::metrics`
+    pub fn with_metrics(mut self) -> Self {
+        self.metrics = true;
+        self
+    }
+
+    /// Record the execution time of each gate into histograms per kind of operation,
+    /// readable via `MpcFabric::gate_profile`
+    ///
+    /// Timing each gate adds overhead to the executor, so profiling is intended for
+    /// identifying hot gate kinds rather than for production deployments
+    pub fn with_gate_profiling(mut self) -> Self {
+        self.profile_gates = true;
+        self
+    }
+
+    /// Record a transcript of the values opened, the commitments exchanged, and the outcomes
+    /// of the MAC checks, readable via `MpcFabric::transcript`
+    ///
+    /// The transcript holds every recorded value until the fabric is dropped, so its memory
+    /// grows with the number of openings in the computation
+    pub fn with_transcript(mut self) -> Self {
+        self.transcript = true;
+        self
+    }
+
+    /// Sample the local share of the MAC key from the fabric's RNG rather than taking it from
+    /// the beaver source, so that the source need not be trusted with the key
+    ///
+    /// The key is the sum of the parties' shares, so it is uniformly random and unknown to
+    /// either party as long as one party samples its share honestly. The parties need not
+    /// agree on any randomness to generate the key, so no messages are exchanged
+    ///
+    /// Both parties must enable this option
+    pub fn with_local_mac_key(mut self) -> Self {
+        self.local_mac_key = true;
+        self
+    }
+
+    /// Set the RNG the fabric samples its local randomness from
+    ///
+    /// By default the fabric uses a `StdRng` seeded from the OS, a caller may provide their
+    /// own RNG to control, audit, or source this randomness from hardware
+    pub fn with_rng<R: 'static + FabricRng>(mut self, rng: R) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Seed the fabric's local randomness deterministically, e.g. for reproducible tests
+    pub fn with_rng_seed(self, seed: [u8; 32]) -> Self {
+        self.with_rng(StdRng::from_seed(seed))
+    }
+
+    /// Set the runtime that the fabric's executor and network tasks are spawned onto
+    ///
+    /// By default the fabric spawns onto the ambient runtime, so it must be constructed
+    /// within a runtime context. With an explicit handle it may be constructed anywhere.
+    /// Current-thread runtimes are supported, the fabric's results resolve while the runtime
+    /// is driven, e.g. by awaiting them within `Runtime::block_on`
+    pub fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Run the executor and network sender on dedicated OS threads rather than the runtime's
+    /// blocking pool
+    ///
+    /// The threads are not shared with other blocking tasks, which bounds the latency of
+    /// scheduling gates in deployments that also use the blocking pool. The network sender
+    /// still drives its IO on the runtime
+    pub fn with_dedicated_threads(mut self) -> Self {
+        self.dedicated_threads = true;
+        self
+    }
+
+    /// Detect operations that are stalled on results with no pending producer
+    ///
+    /// If the executor sits idle for the grace period with operations in flight, it logs a
+    /// warning for each result those operations wait on that no in-flight operation produces,
+    /// e.g. a value expected from the peer that the peer never sent, along with the chain of
+    /// operations it blocks
+    pub fn with_stall_detection(mut self, grace_period: Duration) -> Self {
+        self.stall_timeout = Some(grace_period);
+        self
+    }
+
+    /// Set how the executor divides its time between results received from the peer and
+    /// local jobs, see `SchedulingPolicy`
+    pub fn with_scheduling_policy(mut self, policy: SchedulingPolicy) -> Self {
+        self.scheduling_policy = policy;
+        self
+    }
+
+    /// Drain up to `batch_size` jobs from the executor's queue per iteration of its loop
+    ///
+    /// Consecutive results in a batch are applied under a single acquisition of the results
+    /// buffer's lock, which amortizes the cost of locking over circuits with many cheap gates.
+    /// Larger batches hold the lock for longer, delaying tasks polling their results
+    pub fn with_executor_batch_size(mut self, batch_size: usize) -> Self {
+        self.executor_batch_size = batch_size;
+        self
+    }
+
+    /// Parallelize the inner loops of large batch gates, e.g. MSMs and batched MAC checks,
+    /// across a pool of `threads` threads owned by the executor
+    ///
+    /// Batches of fewer than `min_batch_len` elements run serially on the executor's thread,
+    /// as splitting them costs more than it saves
+    pub fn with_gate_parallelism(mut self, threads: usize, min_batch_len: usize) -> Self {
+        self.gate_parallelism = Some(GateParallelism {
+            threads,
+            min_batch_len,
+        });
+        self
+    }
+
+    /// Pin the executor to the given CPU core, running it on a dedicated thread
+    ///
+    /// Pinning is best effort, if the core does not exist or the thread may not be pinned the
+    /// executor runs unpinned and a warning is logged
+    #[cfg(feature = "thread_affinity")]
+    pub fn with_executor_core(mut self, core: usize) -> Self {
+        self.dedicated_threads = true;
+        self.executor_core = Some(core);
+        self
+    }
+
+    /// Pin the network sender to the given CPU core, running it on a dedicated thread
+    ///
+    /// Pinning is best effort, as with `with_executor_core`
+    #[cfg(feature = "thread_affinity")]
+    pub fn with_network_core(mut self, core: usize) -> Self {
+        self.dedicated_threads = true;
+        self.network_core = Some(core);
+        self
+    }
+}
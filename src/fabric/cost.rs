@@ -0,0 +1,245 @@
+//! Defines the cost estimate a dry-run fabric records for the circuits allocated in it
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use zeroize::Zeroize;
+
+use crate::{
+    algebra::scalar::{Scalar, SCALAR_BYTES},
+    beaver::SharedValueSource,
+    network::PayloadShape,
+};
+
+use super::{active_label, ResultId};
+
+/// The estimated size of a single value sent over the network, scalars and compressed points
+/// are both serialized to this many bytes
+const VALUE_BYTES: u64 = SCALAR_BYTES as u64;
+
+/// An estimate of the cost of a circuit, recorded by a dry-run fabric
+///
+/// The estimate is taken from the local party's view of the circuit. Communication is
+/// estimated at one value per argument of each send, and rounds as the longest chain of
+/// dependent network operations. In the estimate of a label, rounds count the chain up to
+/// the label's last network operation, including those of earlier phases
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CostEstimate {
+    /// The number of gates allocated
+    pub gates: u64,
+    /// The number of messages sent to the peer
+    pub messages_sent: u64,
+    /// The number of messages received from the peer
+    pub messages_received: u64,
+    /// The estimated number of bytes sent to the peer
+    pub bytes_sent: u64,
+    /// The estimated number of bytes received from the peer
+    pub bytes_received: u64,
+    /// The number of communication rounds on the critical path of the circuit
+    pub rounds: u64,
+    /// The number of beaver triples consumed
+    pub triples: u64,
+    /// The number of shared bits consumed
+    pub bits: u64,
+    /// The number of shared inverse pairs consumed
+    pub inverse_pairs: u64,
+    /// The number of shared random values consumed
+    pub shared_values: u64,
+}
+
+/// The state of a cost recorder
+#[derive(Debug, Default)]
+struct CostState {
+    /// The estimate recorded so far
+    estimate: CostEstimate,
+    /// The estimates of labeled operations, by label
+    labels: HashMap<String, CostEstimate>,
+    /// The number of rounds needed to compute each result, results not present need none
+    depths: HashMap<ResultId, u64>,
+    /// The round of the most recently allocated network operation
+    ///
+    /// The peer's messages are assumed to arrive in the same round as the local party's last
+    /// send, as the parties exchange values symmetrically
+    last_send_round: u64,
+}
+
+impl CostState {
+    /// Update the estimate, and that of the given label if any
+    fn update<F: Fn(&mut CostEstimate)>(&mut self, label: Option<&str>, update: F) {
+        update(&mut self.estimate);
+        if let Some(label) = label {
+            update(self.labels.entry(label.to_string()).or_default());
+        }
+    }
+}
+
+/// Records the cost of the operations allocated in a dry-run fabric, shared between the
+/// fabric and its beaver source
+///
+/// Values received and consumed are attributed to the label of the current task's label scope
+#[derive(Debug, Default)]
+pub(crate) struct CostRecorder {
+    /// The recorded state
+    state: Mutex<CostState>,
+}
+
+impl CostRecorder {
+    /// Lock the recorded state
+    fn lock(&self) -> MutexGuard<'_, CostState> {
+        self.state.lock().expect("cost recorder poisoned")
+    }
+
+    /// Record an operation allocated in the computation graph with the given label
+    pub fn record_op(
+        &self,
+        args: &[ResultId],
+        outputs: &[ResultId],
+        network: bool,
+        label: Option<&str>,
+    ) {
+        let mut state = self.lock();
+        let args_depth = args
+            .iter()
+            .filter_map(|id| state.depths.get(id))
+            .copied()
+            .max()
+            .unwrap_or_default();
+
+        let depth = if network {
+            state.last_send_round = args_depth + 1;
+            args_depth + 1
+        } else {
+            args_depth
+        };
+        state.update(label, |estimate| {
+            if network {
+                estimate.messages_sent += 1;
+                estimate.bytes_sent += VALUE_BYTES * args.len() as u64;
+            } else {
+                estimate.gates += 1;
+            }
+            estimate.rounds = estimate.rounds.max(depth);
+        });
+
+        if depth > 0 {
+            for id in outputs.iter() {
+                state.depths.insert(*id, depth);
+            }
+        }
+    }
+
+    /// Record a share sent to the peer when a shared value is allocated
+    pub fn record_share_sent(&self) {
+        let label = active_label();
+        self.lock().update(label.as_deref(), |estimate| {
+            estimate.messages_sent += 1;
+            estimate.bytes_sent += VALUE_BYTES;
+        });
+    }
+
+    /// Record a value received from the peer
+    pub fn record_receive(&self, id: ResultId, shape: Option<PayloadShape>) {
+        let label = active_label();
+        let mut state = self.lock();
+        let n_values = match shape {
+            Some(PayloadShape::ScalarBatch(Some(n))) | Some(PayloadShape::PointBatch(Some(n))) => {
+                n as u64
+            }
+            _ => 1,
+        };
+
+        let round = state.last_send_round.max(1);
+        state.depths.insert(id, round);
+        state.update(label.as_deref(), |estimate| {
+            estimate.messages_received += 1;
+            estimate.bytes_received += VALUE_BYTES * n_values;
+            estimate.rounds = estimate.rounds.max(round);
+        });
+    }
+
+    /// Take a snapshot of the estimate
+    pub fn snapshot(&self) -> CostEstimate {
+        self.lock().estimate
+    }
+
+    /// Take a snapshot of the estimate of each label
+    pub fn snapshot_by_label(&self) -> HashMap<String, CostEstimate> {
+        self.lock().labels.clone()
+    }
+}
+
+/// The beaver source of a dry-run fabric, records the values consumed and returns zeros
+pub(crate) struct DryRunSource {
+    /// The recorder that consumption is recorded in
+    recorder: Arc<CostRecorder>,
+}
+
+impl DryRunSource {
+    /// Constructor
+    pub fn new(recorder: Arc<CostRecorder>) -> Self {
+        Self { recorder }
+    }
+
+    /// Record a consumption of `n` values in the given counter of the estimate
+    fn record<F: Fn(&mut CostEstimate) -> &mut u64>(&self, n: usize, counter: F) {
+        let label = active_label();
+        self.recorder
+            .lock()
+            .update(label.as_deref(), |estimate| *counter(estimate) += n as u64);
+    }
+}
+
+impl Zeroize for DryRunSource {
+    fn zeroize(&mut self) {
+        // The source holds no secret values
+    }
+}
+
+impl SharedValueSource for DryRunSource {
+    fn next_shared_bit(&mut self) -> Scalar {
+        self.next_shared_bit_batch(1).remove(0)
+    }
+
+    fn next_shared_bit_batch(&mut self, num_values: usize) -> Vec<Scalar> {
+        self.record(num_values, |estimate| &mut estimate.bits);
+        vec![Scalar::zero(); num_values]
+    }
+
+    fn next_shared_value(&mut self) -> Scalar {
+        self.next_shared_value_batch(1).remove(0)
+    }
+
+    fn next_shared_value_batch(&mut self, num_values: usize) -> Vec<Scalar> {
+        self.record(num_values, |estimate| &mut estimate.shared_values);
+        vec![Scalar::zero(); num_values]
+    }
+
+    fn next_shared_inverse_pair(&mut self) -> (Scalar, Scalar) {
+        let (mut a, mut b) = self.next_shared_inverse_pair_batch(1);
+        (a.remove(0), b.remove(0))
+    }
+
+    fn next_shared_inverse_pair_batch(&mut self, num_pairs: usize) -> (Vec<Scalar>, Vec<Scalar>) {
+        self.record(num_pairs, |estimate| &mut estimate.inverse_pairs);
+        (
+            vec![Scalar::zero(); num_pairs],
+            vec![Scalar::zero(); num_pairs],
+        )
+    }
+
+    fn next_triplet(&mut self) -> (Scalar, Scalar, Scalar) {
+        let (mut a, mut b, mut c) = self.next_triplet_batch(1);
+        (a.remove(0), b.remove(0), c.remove(0))
+    }
+
+    fn next_triplet_batch(
+        &mut self,
+        num_triplets: usize,
+    ) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>) {
+        self.record(num_triplets, |estimate| &mut estimate.triples);
+        let zeros = vec![Scalar::zero(); num_triplets];
+        (zeros.clone(), zeros.clone(), zeros)
+    }
+}
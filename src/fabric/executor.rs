@@ -1,29 +1,274 @@
 //! The executor receives IDs of operations that are ready for execution, executes
 //! them, and places the result back into the fabric for further executions
 
-use std::sync::Arc;
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
 
 use crossbeam::queue::SegQueue;
 use itertools::Itertools;
+use tokio::sync::mpsc::error::SendError;
 use tracing::log;
 
+use crate::algebra::{scalar::Scalar, stark_curve::StarkPoint};
 use crate::buffer::GrowableBuffer;
-use crate::network::NetworkOutbound;
+use crate::error::{MpcError, MpcNetworkError};
+use crate::network::{buffer_pool::clone_payload, NetworkOutbound, NetworkPayload};
 
-use super::{result::OpResult, FabricInner};
+use super::arena::{OpKey, OperationArena};
+use super::network_sender::{FlushRequest, ERR_FLUSH_AFTER_EXIT};
+use super::parallel::{GateParallelism, GatePool};
+use super::SchedulingPolicy;
+use super::{profile::OperationKind, result::OpResult, FabricInner};
 use super::{Operation, OperationType, ResultId, ResultValue};
 
+/// The number of times the executor polls an empty queue before parking
+const SPINS_BEFORE_PARK: usize = 64;
+/// The maximum amount of time the executor parks for before polling its queue again
+///
+/// Pushes to the queue unpark the executor, this bounds the cost of a missed notification
+const PARK_TIMEOUT: Duration = Duration::from_millis(10);
+/// The number of consecutive jobs the executor runs before yielding its thread
+const JOBS_BEFORE_YIELD: usize = 1024;
+/// The maximum number of blocked operations listed when reporting a stalled result
+const MAX_REPORTED_OPS: usize = 16;
+
+/// The queue of jobs for the executor
+///
+/// Pushing to the queue notifies the executor, which parks its thread while the queue is
+/// empty rather than busy-looping. Results received from the peer are queued separately from
+/// local jobs, and the executor alternates between the two according to its
+/// `SchedulingPolicy`
+#[derive(Debug, Default)]
+pub struct ExecutorQueue {
+    /// The queue of local jobs
+    queue: SegQueue<ExecutorMessage>,
+    /// The queue of results received from the peer
+    inbound: SegQueue<ExecutorMessage>,
+    /// The thread the executor runs on, once it has started
+    executor_thread: Mutex<Option<Thread>>,
+}
+
+impl ExecutorQueue {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a job onto the queue, unparking the executor
+    pub fn push(&self, job: ExecutorMessage) {
+        self.queue.push(job);
+        self.unpark_executor();
+    }
+
+    /// Push a result received from the peer onto the queue, unparking the executor
+    pub fn push_inbound(&self, job: ExecutorMessage) {
+        self.inbound.push(job);
+        self.unpark_executor();
+    }
+
+    /// Pop a job from the queue, local jobs are popped before inbound results
+    pub fn pop(&self) -> Option<ExecutorMessage> {
+        self.queue.pop().or_else(|| self.inbound.pop())
+    }
+
+    /// The number of jobs in the queue
+    pub fn len(&self) -> usize {
+        self.queue.len() + self.inbound.len()
+    }
+
+    /// Whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty() && self.inbound.is_empty()
+    }
+
+    /// Unpark the executor's thread, if it has started
+    fn unpark_executor(&self) {
+        if let Some(thread) = self
+            .executor_thread
+            .lock()
+            .expect("executor thread poisoned")
+            .as_ref()
+        {
+            thread.unpark();
+        }
+    }
+
+    /// Register the calling thread as the executor's, to be unparked on pushes
+    fn register_executor(&self) {
+        self.executor_thread
+            .lock()
+            .expect("executor thread poisoned")
+            .replace(thread::current());
+    }
+}
+
+// --------------
+// | Scheduling |
+// --------------
+
+/// Tracks the executor's progress through a round of its `SchedulingPolicy`
+#[derive(Debug, Default)]
+struct Scheduler {
+    /// The policy the executor schedules jobs by
+    policy: SchedulingPolicy,
+    /// The number of inbound results served in the current round
+    served_inbound: usize,
+    /// The number of local jobs served in the current round
+    served_local: usize,
+}
+
+impl Scheduler {
+    /// Constructor
+    fn new(policy: SchedulingPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Pop the next job from the queue
+    ///
+    /// Inbound results are preferred until the round's share of them has been served, then
+    /// local jobs are. A job from the other queue is served if the preferred one is empty, and
+    /// a new round starts once both shares have been served
+    fn next(&mut self, queue: &ExecutorQueue) -> Option<ExecutorMessage> {
+        let (inbound_share, local_share) = self.policy.shares();
+        let (job, inbound) = if self.served_inbound < inbound_share {
+            match queue.inbound.pop() {
+                Some(job) => (job, true),
+                None => (queue.queue.pop()?, false),
+            }
+        } else {
+            match queue.queue.pop() {
+                Some(job) => (job, false),
+                None => (queue.inbound.pop()?, true),
+            }
+        };
+
+        if inbound {
+            self.served_inbound += 1;
+        } else {
+            self.served_local += 1;
+        }
+        if self.served_inbound >= inbound_share && self.served_local >= local_share {
+            self.served_inbound = 0;
+            self.served_local = 0;
+        }
+
+        Some(job)
+    }
+}
+
+// -------------------
+// | Open Coalescing |
+// -------------------
+
+/// How the executor coalesces single scalar and point openings into batches
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct OpenCoalescing {
+    /// The maximum number of openings packed into a batch
+    pub max_openings: usize,
+    /// The amount of time an opening may be held back while the executor is idle
+    pub window: Duration,
+}
+
+/// The openings the executor has held back to send as a batch
+#[derive(Debug, Default)]
+struct PendingOpenings {
+    /// The results and values of the held scalar openings
+    scalars: Vec<(ResultId, Scalar)>,
+    /// The results and values of the held point openings
+    points: Vec<(ResultId, StarkPoint)>,
+    /// The time at which the oldest held opening was executed
+    since: Option<Instant>,
+}
+
+impl PendingOpenings {
+    /// The number of held openings
+    fn len(&self) -> usize {
+        self.scalars.len() + self.points.len()
+    }
+
+    /// Hold an opening back, returning the payload if it is not a single scalar or point
+    fn hold(&mut self, result_id: ResultId, payload: NetworkPayload) -> Option<NetworkPayload> {
+        match payload {
+            NetworkPayload::Scalar(scalar) => self.scalars.push((result_id, scalar)),
+            NetworkPayload::Point(point) => self.points.push((result_id, point)),
+            _ => return Some(payload),
+        }
+
+        self.since.get_or_insert_with(Instant::now);
+        None
+    }
+
+    /// The amount of time left before the held openings must be sent, if any are held
+    fn remaining(&self, window: Duration) -> Option<Duration> {
+        self.since
+            .map(|since| window.saturating_sub(since.elapsed()))
+    }
+
+    /// Take the held openings as one message per kind of value
+    fn take(&mut self) -> Vec<NetworkOutbound> {
+        self.since = None;
+        let (scalar_ids, scalars): (Vec<_>, Vec<_>) = self.scalars.drain(..).unzip();
+        let (point_ids, points): (Vec<_>, Vec<_>) = self.points.drain(..).unzip();
+
+        [
+            (scalar_ids, NetworkPayload::ScalarBatch(scalars)),
+            (point_ids, NetworkPayload::PointBatch(points)),
+        ]
+        .into_iter()
+        .filter(|(result_ids, _)| !result_ids.is_empty())
+        .map(|(result_ids, values)| NetworkOutbound {
+            result_id: result_ids[0],
+            payload: NetworkPayload::Openings {
+                result_ids,
+                values: Box::new(values),
+            },
+        })
+        .collect()
+    }
+}
+
 /// The executor is responsible for executing operation that are ready for execution, either
 /// passed explicitly by the fabric or as a result of a dependency being satisfied
 pub struct Executor {
     /// The job queue for the executor
     ///
     /// TODO: Use an `ArrayQueue` here for slightly improved performance
-    job_queue: Arc<SegQueue<ExecutorMessage>>,
-    /// The operation buffer, stores in-flight operations
-    operations: GrowableBuffer<Operation>,
+    job_queue: Arc<ExecutorQueue>,
+    /// Divides the executor's time between inbound results and local jobs
+    scheduler: Scheduler,
+    /// The maximum number of jobs drained from the queue per iteration of the executor loop
+    batch_size: usize,
+    /// The operation arena, stores in-flight operations
+    operations: OperationArena,
     /// The dependency map; maps in-flight results to operations that are waiting for them
-    dependencies: GrowableBuffer<Vec<ResultId>>,
+    dependencies: GrowableBuffer<Vec<OpKey>>,
+    /// The results marked for release, mapped to the number of uses remaining before the
+    /// result is dropped
+    release_hints: HashMap<ResultId, usize>,
+    /// The results marked for release whose values are handed to their handles
+    handoffs: HashSet<ResultId>,
+    /// The amount of time the executor may sit idle with operations in flight before it
+    /// reports them as stalled, if stall detection is enabled
+    stall_timeout: Option<Duration>,
+    /// How single openings are coalesced into batches, if at all
+    open_coalescing: Option<OpenCoalescing>,
+    /// The openings held back to be sent as a batch
+    pending_openings: RefCell<PendingOpenings>,
+    /// The pool that large batch gates are parallelized across, if any
+    gate_pool: Option<Arc<GatePool>>,
+    /// Whether the local party aborted the computation
+    aborted: bool,
     /// The underlying fabric that the executor is a part of
     fabric: FabricInner,
     /// The total sampled queue length of the executor's work queue
@@ -45,6 +290,25 @@ pub enum ExecutorMessage {
     Result(OpResult),
     /// An operation that is ready for execution
     Op(Operation),
+    /// Indicates that the computation has failed, all pending results resolve to the error
+    Error(MpcError),
+    /// Indicates that the local party aborted the computation with the given reason, pending
+    /// operations are cancelled and all pending results resolve to `MpcError::Aborted`
+    Abort(String),
+    /// Release a result once it has been used by the given number of operations, or by the
+    /// operations already allocated on it if no count is given
+    Release {
+        /// The ID of the result to release
+        id: ResultId,
+        /// The number of uses after which the result is released
+        uses: Option<usize>,
+        /// Whether the result's value is handed to the handle that took it rather than
+        /// dropped
+        handoff: bool,
+    },
+    /// Flush the messages sent by the operations executed so far, answering the request once
+    /// they are written to the network
+    Flush(FlushRequest),
     /// Indicates that the executor should shut down
     Shutdown,
 }
@@ -53,15 +317,24 @@ impl Executor {
     /// Constructor
     pub fn new(
         circuit_size_hint: usize,
-        job_queue: Arc<SegQueue<ExecutorMessage>>,
+        job_queue: Arc<ExecutorQueue>,
         fabric: FabricInner,
     ) -> Self {
         #[cfg(feature = "debug_info")]
         {
             Self {
                 job_queue,
-                operations: GrowableBuffer::new(circuit_size_hint),
+                scheduler: Scheduler::default(),
+                batch_size: 1,
+                operations: OperationArena::new(circuit_size_hint),
                 dependencies: GrowableBuffer::new(circuit_size_hint),
+                release_hints: HashMap::new(),
+                handoffs: HashSet::new(),
+                stall_timeout: None,
+                open_coalescing: None,
+                pending_openings: RefCell::default(),
+                gate_pool: None,
+                aborted: false,
                 fabric,
                 summed_queue_length: 0,
                 queue_length_sample_count: 0,
@@ -72,32 +345,130 @@ impl Executor {
         {
             Self {
                 job_queue,
-                operations: GrowableBuffer::new(circuit_size_hint),
+                scheduler: Scheduler::default(),
+                batch_size: 1,
+                operations: OperationArena::new(circuit_size_hint),
                 dependencies: GrowableBuffer::new(circuit_size_hint),
+                release_hints: HashMap::new(),
+                handoffs: HashSet::new(),
+                stall_timeout: None,
+                open_coalescing: None,
+                pending_openings: RefCell::default(),
+                gate_pool: None,
+                aborted: false,
                 fabric,
             }
         }
     }
 
+    /// Report operations that are stalled once the executor has been idle for the given
+    /// amount of time
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how the executor divides its time between results received from the peer and
+    /// local jobs
+    pub fn with_scheduling_policy(mut self, policy: SchedulingPolicy) -> Self {
+        self.scheduler = Scheduler::new(policy);
+        self
+    }
+
+    /// Drain up to `batch_size` jobs from the queue per iteration of the executor loop
+    ///
+    /// Consecutive results in a batch are applied under a single acquisition of the results
+    /// and wakers locks. A zero batch size is treated as one
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Coalesce the single scalar and point openings executed while the executor is busy
+    /// into batches
+    pub(crate) fn with_open_coalescing(mut self, coalescing: OpenCoalescing) -> Self {
+        self.open_coalescing = Some(coalescing);
+        self
+    }
+
+    /// Parallelize large batch gates across a pool of threads owned by the executor
+    ///
+    /// If the pool cannot be built the executor runs all gates serially
+    pub(crate) fn with_gate_parallelism(mut self, parallelism: GateParallelism) -> Self {
+        match GatePool::new(parallelism) {
+            Ok(pool) => self.gate_pool = Some(Arc::new(pool)),
+            Err(e) => log::warn!("error building gate pool, running gates serially: {e}"),
+        }
+        self
+    }
+
     /// Run the executor until a shutdown message is received
+    ///
+    /// The executor spins briefly on an empty queue, then parks until a job is pushed. It
+    /// yields its thread periodically while busy, so that it does not monopolize a core
+    /// shared with the runtime
     pub fn run(mut self) {
+        self.job_queue.register_executor();
+        let _gate_pool = self.gate_pool.as_ref().map(GatePool::register);
+
+        let mut idle_spins = 0;
+        let mut idle_since = None;
+        let mut stall_reported = false;
+        let mut jobs_since_yield = 0;
+        let mut batch = Vec::with_capacity(self.batch_size);
         loop {
-            if let Some(job) = self.job_queue.pop() {
-                match job {
-                    ExecutorMessage::Result(res) => self.handle_new_result(res),
-                    ExecutorMessage::Op(operation) => self.handle_new_operation(operation),
-                    ExecutorMessage::Shutdown => {
-                        log::debug!("executor shutting down");
-
-                        // In benchmarks print the average queue length
-                        #[cfg(feature = "debug_info")]
-                        {
-                            println!("average queue length: {}", self.avg_queue_length());
-                        }
+            batch.extend(
+                std::iter::from_fn(|| self.scheduler.next(&self.job_queue)).take(self.batch_size),
+            );
+            if batch.is_empty() {
+                // Once idle, send the held openings when their window has passed
+                let remaining = self.open_coalescing.and_then(|coalescing| {
+                    self.pending_openings.borrow().remaining(coalescing.window)
+                });
+                if remaining == Some(Duration::ZERO) {
+                    self.send_pending_openings();
+                    continue;
+                }
 
-                        break;
+                idle_spins += 1;
+                if idle_spins < SPINS_BEFORE_PARK {
+                    std::hint::spin_loop();
+                } else {
+                    thread::park_timeout(
+                        remaining.map_or(PARK_TIMEOUT, |remaining| remaining.min(PARK_TIMEOUT)),
+                    );
+                    if let Some(timeout) = self.stall_timeout {
+                        let idle = idle_since.get_or_insert_with(Instant::now).elapsed();
+                        if !stall_reported && idle >= timeout {
+                            self.report_stalled_operations(idle);
+                            stall_reported = true;
+                        }
                     }
                 }
+
+                continue;
+            }
+
+            idle_spins = 0;
+            idle_since = None;
+            stall_reported = false;
+            jobs_since_yield += batch.len();
+            if jobs_since_yield >= JOBS_BEFORE_YIELD {
+                jobs_since_yield = 0;
+                thread::yield_now();
+            }
+
+            if !self.handle_batch(&mut batch) {
+                log::debug!("executor shutting down");
+                self.send_pending_openings();
+
+                // In benchmarks print the average queue length
+                #[cfg(feature = "debug_info")]
+                {
+                    println!("average queue length: {}", self.avg_queue_length());
+                }
+
+                break;
             }
 
             #[cfg(feature = "debug_info")]
@@ -114,20 +485,90 @@ impl Executor {
         (self.summed_queue_length as f64) / (self.queue_length_sample_count as f64)
     }
 
-    /// Handle a new result
-    fn handle_new_result(&mut self, result: OpResult) {
-        let id = result.id;
+    /// Handle a batch of jobs drained from the queue, returning `false` once a shutdown
+    /// message is handled
+    ///
+    /// Runs of consecutive results are applied together, see `handle_new_results`
+    fn handle_batch(&mut self, batch: &mut Vec<ExecutorMessage>) -> bool {
+        let mut jobs = batch.drain(..).peekable();
+        while let Some(job) = jobs.next() {
+            match job {
+                ExecutorMessage::Result(res) => {
+                    let rest = std::iter::from_fn(|| match jobs.peek() {
+                        Some(ExecutorMessage::Result(_)) => match jobs.next() {
+                            Some(ExecutorMessage::Result(res)) => Some(res),
+                            _ => unreachable!(),
+                        },
+                        _ => None,
+                    });
+                    self.handle_new_results(std::iter::once(res).chain(rest))
+                }
+                ExecutorMessage::Op(operation) => self.handle_new_operation(operation),
+                ExecutorMessage::Error(err) => self.handle_error(err),
+                ExecutorMessage::Abort(reason) => self.handle_abort(reason),
+                ExecutorMessage::Release { id, uses, handoff } => {
+                    self.handle_release(id, uses, handoff)
+                }
+                ExecutorMessage::Flush(reply) => self.handle_flush(reply),
+                ExecutorMessage::Shutdown => return false,
+            }
+        }
+
+        true
+    }
 
+    /// Handle a run of new results, holding the results and wakers locks across the run
+    fn handle_new_results<I: IntoIterator<Item = OpResult>>(&mut self, results: I) {
         // Lock the fabric elements needed
-        let mut locked_results = self.fabric.results.write().expect("results lock poisoned");
-        let prev = locked_results.insert(result.id, result);
-        assert!(prev.is_none(), "duplicate result id: {id:?}");
+        let (results_buf, wakers) = (self.fabric.results.clone(), self.fabric.wakers.clone());
+        let mut locked_results = results_buf.write().expect("results lock poisoned");
+        let mut locked_wakers = wakers.write().expect("wakers lock poisoned");
+
+        // Execute any ready dependencies, recording the arguments they use if any results are
+        // marked for release
+        let track_uses = !self.release_hints.is_empty();
+        let mut used = Vec::new();
+        let mut applied = Vec::new();
+        for result in results {
+            let id = result.id;
+            if let Some(transcript) = self.fabric.transcript.as_ref() {
+                transcript.capture(&result);
+            }
+            let prev = locked_results.insert(result.id, result);
+            assert!(prev.is_none(), "duplicate result id: {id:?}");
+
+            self.execute_dependents(id, &locked_results, track_uses.then_some(&mut used));
 
-        // Execute any ready dependencies
+            // Wake all tasks awaiting this result
+            for waker in locked_wakers.remove(&id).unwrap_or_default().into_iter() {
+                waker.wake();
+            }
+
+            if track_uses {
+                applied.push(id);
+            }
+        }
+        drop(locked_wakers);
+        drop(locked_results);
+
+        if track_uses {
+            self.record_uses(&used);
+            applied.into_iter().for_each(|id| self.try_release(id));
+        }
+    }
+
+    /// Execute the operations waiting on a result that are now ready, recording the
+    /// arguments they use if requested
+    fn execute_dependents(
+        &mut self,
+        id: ResultId,
+        locked_results: &GrowableBuffer<OpResult>,
+        mut used: Option<&mut Vec<ResultId>>,
+    ) {
         if let Some(deps) = self.dependencies.get(id) {
-            for op_id in deps.iter() {
+            for key in deps.iter() {
                 {
-                    let mut operation = self.operations.get_mut(*op_id).unwrap();
+                    let operation = self.operations.get_mut(*key).unwrap();
 
                     operation.inflight_args -= 1;
                     if operation.inflight_args > 0 {
@@ -136,7 +577,7 @@ impl Executor {
                 } // explicitly drop the mutable `self` reference
 
                 // Take ownership of the operation
-                let op = self.operations.take(*op_id).unwrap();
+                let op = self.operations.take(*key).unwrap();
 
                 // Get the inputs and execute the method to produce the output
                 let inputs = op
@@ -144,18 +585,203 @@ impl Executor {
                     .iter()
                     .map(|id| locked_results.get(*id).unwrap().value.clone())
                     .collect::<Vec<_>>();
+                if let Some(used) = used.as_mut() {
+                    used.extend(op.args.iter().unique().copied());
+                }
                 self.execute_operation(op, inputs);
             }
         }
-        // Wake all tasks awaiting this result
+    }
+
+    /// Handle a failure of the computation
+    ///
+    /// The error is recorded in the fabric and all tasks awaiting a result are woken so that
+    /// they may observe the failure
+    fn handle_error(&mut self, err: MpcError) {
+        log::error!("computation failed: {err}");
+
+        // Hold the wakers lock while recording the failure so that no task registers a waker
+        // after the failure is recorded without observing it
+        let mut locked_wakers = self.fabric.wakers.write().expect("wakers lock poisoned");
+        self.fabric
+            .failure
+            .write()
+            .expect("failure lock poisoned")
+            .get_or_insert(err);
+
+        for waker in locked_wakers.drain().flat_map(|(_, wakers)| wakers) {
+            waker.wake();
+        }
+    }
+
+    /// Handle an abort of the computation by the local party
+    ///
+    /// The operations in flight and the openings held back are dropped, and operations
+    /// allocated after the abort are not executed
+    fn handle_abort(&mut self, reason: String) {
+        self.aborted = true;
+        self.operations.clear();
+        self.pending_openings.borrow_mut().take();
+        self.handle_error(MpcError::Aborted(reason));
+    }
+
+    /// Handle a request to flush the outbound queue by forwarding it to the network sender
+    ///
+    /// The messages of the operations executed before the request are queued first, including
+    /// any held openings, so the network sender flushes them before answering
+    fn handle_flush(&self, reply: FlushRequest) {
+        self.send_pending_openings();
+        let Some(flush_requests) = self.fabric.flush_requests.as_ref() else {
+            // A fabric without a network has nothing to flush
+            let _ = reply.send(Ok(()));
+            return;
+        };
+
+        if let Err(SendError(reply)) = flush_requests.send(reply) {
+            let err = MpcNetworkError::SendError(ERR_FLUSH_AFTER_EXIT.to_string());
+            let _ = reply.send(Err(err));
+        }
+    }
+
+    /// Handle a release hint for a result
+    fn handle_release(&mut self, id: ResultId, uses: Option<usize>, handoff: bool) {
+        // Without a count, the result is released once the operations waiting on it execute
+        let uses = uses.unwrap_or_else(|| {
+            self.dependencies
+                .get(id)
+                .map(|deps| {
+                    deps.iter()
+                        .unique()
+                        .filter(|key| self.operations.get(**key).is_some())
+                        .count()
+                })
+                .unwrap_or_default()
+        });
+
+        self.release_hints.insert(id, uses);
+        if handoff {
+            self.handoffs.insert(id);
+        }
+        self.try_release(id);
+    }
+
+    /// Record a use of each of the given results by an executed operation
+    fn record_uses(&mut self, used: &[ResultId]) {
+        for id in used.iter() {
+            if let Some(remaining) = self.release_hints.get_mut(id) {
+                *remaining = remaining.saturating_sub(1);
+                self.try_release(*id);
+            }
+        }
+    }
+
+    /// Release a result if it is marked for release, has been computed, and has no uses
+    /// remaining
+    fn try_release(&mut self, id: ResultId) {
+        if self.release_hints.get(&id) != Some(&0) {
+            return;
+        }
+
+        let mut locked_results = self.fabric.results.write().expect("results lock poisoned");
+        if let Some(mut result) = locked_results.take(id) {
+            self.release_hints.remove(&id);
+            self.dependencies.take(id);
+
+            // A handed off result's ID is recycled once its handle collects the value
+            if self.handoffs.remove(&id) {
+                self.hand_off(&mut result);
+            } else {
+                self.fabric.dropped_result_ids.push(id);
+            }
+        }
+    }
+
+    /// Move a released result's value to the fabric's handoffs and wake the handle that took it
+    fn hand_off(&self, result: &mut OpResult) {
+        let value = mem::replace(&mut result.value, ResultValue::Bytes(Vec::new()));
+        self.fabric
+            .handoffs
+            .write()
+            .expect("handoffs lock poisoned")
+            .insert(result.id, value);
+
         let mut locked_wakers = self.fabric.wakers.write().expect("wakers lock poisoned");
-        for waker in locked_wakers.remove(&id).unwrap_or_default().into_iter() {
+        for waker in locked_wakers.remove(&result.id).unwrap_or_default() {
             waker.wake();
         }
     }
 
+    /// Log a warning for each result that stalled operations are waiting on
+    fn report_stalled_operations(&self, idle: Duration) {
+        for (id, blocked) in self.find_stalled_results() {
+            let listed = blocked
+                .iter()
+                .take(MAX_REPORTED_OPS)
+                .filter_map(|key| self.operations.get(*key))
+                .collect_vec();
+            let n_unlisted = blocked.len() - listed.len();
+            log::warn!(
+                "executor idle for {idle:?}, result {id} has no pending producer and blocks \
+                operations {listed:?} and {n_unlisted} more"
+            );
+        }
+    }
+
+    /// Find the results that in-flight operations wait on but that no in-flight operation
+    /// produces, along with the operations each blocks, directly or transitively
+    fn find_stalled_results(&self) -> Vec<(ResultId, Vec<OpKey>)> {
+        let locked_results = self.fabric.results.read().expect("results lock poisoned");
+        let produced: HashSet<ResultId> = self
+            .operations
+            .iter()
+            .flat_map(|op| op.result_ids())
+            .collect();
+
+        let stalled = self
+            .operations
+            .iter()
+            .flat_map(|op| op.args.iter().copied())
+            .filter(|id| locked_results.get(*id).is_none() && !produced.contains(id))
+            .unique()
+            .sorted()
+            .collect_vec();
+        drop(locked_results);
+
+        stalled
+            .into_iter()
+            .map(|id| (id, self.blocked_operations(id)))
+            .collect()
+    }
+
+    /// The in-flight operations blocked on a result, directly or transitively, in the order
+    /// they are reached from the result
+    fn blocked_operations(&self, id: ResultId) -> Vec<OpKey> {
+        let mut blocked = Vec::new();
+        let mut visited = HashSet::new();
+        let mut frontier = VecDeque::from([id]);
+        while let Some(id) = frontier.pop_front() {
+            for key in self.dependencies.get(id).into_iter().flatten() {
+                let Some(op) = self.operations.get(*key) else {
+                    continue;
+                };
+
+                if visited.insert(key.id) {
+                    blocked.push(*key);
+                    frontier.extend(op.result_ids());
+                }
+            }
+        }
+
+        blocked
+    }
+
     /// Handle a new operation
     fn handle_new_operation(&mut self, mut op: Operation) {
+        // An aborted computation executes no more operations
+        if self.aborted {
+            return;
+        }
+
         // Acquire all necessary locks
         let locked_results = self.fabric.results.read().expect("results lock poisoned");
 
@@ -171,37 +797,94 @@ impl Executor {
 
         // If the operation is ready for execution, do so
         if inflight_args == 0 {
-            self.execute_operation(op, ready);
+            drop(locked_results);
+            if self.release_hints.is_empty() {
+                self.execute_operation(op, ready);
+            } else {
+                let used = op.args.iter().unique().copied().collect_vec();
+                self.execute_operation(op, ready);
+                self.record_uses(&used);
+            }
+
             return;
         }
 
-        // Otherwise, add the operation to the in-flight operations list and the dependency map
-        for arg in op.args.iter() {
+        // Otherwise, add the operation to the in-flight operations arena and the dependency map
+        let key = self.operations.insert(op);
+        for arg in self.operations.get(key).unwrap().args.iter() {
             let entry = self.dependencies.entry_mut(*arg);
             if entry.is_none() {
                 *entry = Some(Vec::new());
             }
 
-            entry.as_mut().unwrap().push(op.id);
+            entry.as_mut().unwrap().push(key);
+        }
+    }
+
+    /// Run a user provided function, recording its execution time if the fabric profiles
+    /// gates and failing the computation if it panics
+    ///
+    /// The parties' views of the circuit diverge once a gate fails, so rather than erroring
+    /// the gate's result alone the computation is failed, resolving all pending results to the
+    /// panic's message. This keeps the executor alive to report the failure
+    fn call<T, F: FnOnce() -> T>(
+        &self,
+        kind: OperationKind,
+        result_id: ResultId,
+        label: Option<&str>,
+        f: F,
+    ) -> Option<T> {
+        let start = self.fabric.profiler.as_ref().map(|_| Instant::now());
+        let res = panic::catch_unwind(AssertUnwindSafe(f));
+        if let (Some(profiler), Some(start)) = (self.fabric.profiler.as_ref(), start) {
+            profiler.record(kind, label, start.elapsed());
         }
 
-        self.operations.insert(op.id, op);
+        match res {
+            Ok(res) => Some(res),
+            Err(payload) => {
+                self.job_queue
+                    .push(ExecutorMessage::Error(MpcError::GatePanicked {
+                        message: panic_message(payload.as_ref()),
+                        result_id,
+                        label: label.map(str::to_string),
+                    }));
+                None
+            }
+        }
     }
 
     /// Executes an operation whose arguments are ready
     fn execute_operation(&self, op: Operation, inputs: Vec<ResultValue>) {
+        if let Some(metrics) = self.fabric.metrics.as_ref() {
+            metrics.record_op_executed();
+        }
+
         let result_ids = op.result_ids();
+        let label = op.label.as_deref();
+        self.fabric
+            .record_labeled(label, |metrics| metrics.ops_executed += 1);
         match op.op_type {
             OperationType::Gate { function } => {
-                let value = (function)(inputs);
+                let Some(value) = self.call(OperationKind::Gate, result_ids[0], label, || {
+                    (function)(inputs)
+                }) else {
+                    return;
+                };
                 self.job_queue.push(ExecutorMessage::Result(OpResult {
-                    id: op.result_id,
+                    id: result_ids[0],
                     value,
                 }))
             }
 
             OperationType::GateBatch { function } => {
-                let output = (function)(inputs);
+                let Some(output) =
+                    self.call(OperationKind::GateBatch, result_ids[0], label, || {
+                        (function)(inputs)
+                    })
+                else {
+                    return;
+                };
                 for (result_id, value) in result_ids.into_iter().zip(output.into_iter()) {
                     self.job_queue.push(ExecutorMessage::Result(OpResult {
                         id: result_id,
@@ -211,18 +894,37 @@ impl Executor {
             }
 
             OperationType::Network { function } => {
+                // Once the connection to the peer is lost no more network operations are
+                // executed, the computation fails as it needs the peer
+                if let Some(err) = self.fabric.outbound_queue.failure() {
+                    self.job_queue.push(ExecutorMessage::Error(err));
+                    return;
+                }
+
                 // Derive a network payload from the gate inputs and forward it to the outbound buffer
                 let result_id = result_ids[0];
-                let payload = (function)(inputs);
-                let outbound = NetworkOutbound {
-                    result_id,
-                    payload: payload.clone(),
+                let Some(payload) = self.call(OperationKind::Network, result_id, label, || {
+                    (function)(inputs)
+                }) else {
+                    return;
                 };
-
+                let outbound = match self.open_coalescing {
+                    Some(_) => self
+                        .pending_openings
+                        .borrow_mut()
+                        .hold(result_id, clone_payload(&payload)),
+                    None => Some(clone_payload(&payload)),
+                };
+                if let Some(payload) = outbound {
+                    self.send_outbound(NetworkOutbound { result_id, payload }, label);
+                }
                 self.fabric
-                    .outbound_queue
-                    .send(outbound)
-                    .expect("error sending network payload");
+                    .record_labeled(label, |metrics| metrics.messages_sent += 1);
+                if let Some(coalescing) = self.open_coalescing {
+                    if self.pending_openings.borrow().len() >= coalescing.max_openings {
+                        self.send_pending_openings();
+                    }
+                }
 
                 // On a `send`, the local party receives a copy of the value placed as the result of
                 // the network operation, so we must re-enqueue the result
@@ -233,4 +935,123 @@ impl Executor {
             }
         }
     }
+
+    /// Send the held openings to the peer as batches
+    fn send_pending_openings(&self) {
+        let batches = self.pending_openings.borrow_mut().take();
+        for batch in batches {
+            self.send_outbound(batch, None /* label */);
+        }
+    }
+
+    /// Enqueue a message for the peer, failing the computation if it cannot be sent
+    fn send_outbound(&self, outbound: NetworkOutbound, label: Option<&str>) {
+        let result_id = outbound.result_id;
+        if let Err(error) = self.fabric.outbound_queue.send(outbound) {
+            log::error!("error sending network payload: {error:?}");
+            let err = MpcError::SendFailed {
+                result_id,
+                label: label.map(str::to_string),
+                error,
+            };
+            self.fabric.outbound_queue.fail(err.clone());
+            self.job_queue.push(ExecutorMessage::Error(err));
+        }
+    }
+}
+
+/// Get the message a panic was raised with
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::{
+        beaver::PartyIDBeaverSource,
+        fabric::{
+            network_sender::outbound_channel, FabricInner, Operation, OperationType, ResultId,
+        },
+        PARTY0,
+    };
+
+    use super::{Executor, ExecutorMessage, ExecutorQueue, Scheduler, SchedulingPolicy};
+
+    /// Build a gate that waits on `arg` and produces `result_id`
+    fn gate(id: usize, result_id: ResultId, arg: ResultId) -> Operation {
+        Operation {
+            id,
+            result_ids: vec![result_id],
+            inflight_args: 0,
+            args: vec![arg],
+            op_type: OperationType::Gate {
+                function: Box::new(|mut args| args.remove(0)),
+            },
+            label: None,
+        }
+    }
+
+    /// Tests finding a result with no producer and the chain of operations it blocks
+    #[test]
+    fn test_find_stalled_results() {
+        let queue = Arc::new(ExecutorQueue::new());
+        let (outbound_sender, _) = outbound_channel(None /* bound */);
+        let fabric = FabricInner::new(
+            100, /* size_hint */
+            PARTY0,
+            queue.clone(),
+            outbound_sender,
+            PartyIDBeaverSource::new(PARTY0),
+        );
+        let mut executor = Executor::new(100 /* size_hint */, queue, fabric);
+
+        // Result 50 is never produced, result 51 is produced by a stalled operation
+        executor.handle_new_operation(gate(10, 51, 50));
+        executor.handle_new_operation(gate(11, 52, 51));
+        executor.handle_new_operation(gate(12, 53, 52));
+
+        let stalled = executor
+            .find_stalled_results()
+            .into_iter()
+            .map(|(id, blocked)| (id, blocked.iter().map(|key| key.id).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(stalled, vec![(50, vec![10, 11, 12])]);
+    }
+
+    /// Tests that the scheduler serves inbound results and local jobs in the policy's ratio
+    #[test]
+    fn test_scheduling_policy() {
+        let release = |id| ExecutorMessage::Release {
+            id,
+            uses: None,
+            handoff: false,
+        };
+        let queue = ExecutorQueue::new();
+        for id in 0..4 {
+            queue.push(release(id));
+            queue.push_inbound(release(100 + id));
+        }
+
+        // Serve one inbound result for every two local jobs, then drain the remaining results
+        let mut scheduler = Scheduler::new(SchedulingPolicy::Ratio {
+            inbound: 1,
+            local: 2,
+        });
+        let order = std::iter::from_fn(|| scheduler.next(&queue))
+            .map(|job| match job {
+                ExecutorMessage::Release { id, .. } => id,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(order, vec![100, 0, 1, 101, 2, 3, 102, 103]);
+    }
 }
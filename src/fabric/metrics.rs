@@ -0,0 +1,91 @@
+//! Defines the execution metrics a fabric records when configured to
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// A snapshot of the execution metrics of a fabric
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FabricMetrics {
+    /// The number of operations allocated in the computation graph
+    pub ops_allocated: u64,
+    /// The number of operations the executor has evaluated
+    pub ops_executed: u64,
+    /// The number of messages sent to the peer, excluding heartbeats
+    pub messages_sent: u64,
+    /// The number of messages received from the peer, excluding heartbeats
+    pub messages_received: u64,
+}
+
+/// The counters backing a `FabricMetrics` snapshot, shared between the fabric, the
+/// executor, and the network sender
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCounters {
+    /// The number of operations allocated in the computation graph
+    ops_allocated: AtomicU64,
+    /// The number of operations the executor has evaluated
+    ops_executed: AtomicU64,
+    /// The number of messages sent to the peer
+    messages_sent: AtomicU64,
+    /// The number of messages received from the peer
+    messages_received: AtomicU64,
+    /// The metrics of labeled operations, by label
+    labels: Mutex<HashMap<String, FabricMetrics>>,
+}
+
+impl MetricsCounters {
+    /// Record an operation allocated in the computation graph
+    pub fn record_op_allocated(&self) {
+        self.ops_allocated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an operation evaluated by the executor
+    pub fn record_op_executed(&self) {
+        self.ops_executed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a message sent to the peer
+    pub fn record_message_sent(&self) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a message received from the peer
+    pub fn record_message_received(&self) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an event attributed to the given label
+    ///
+    /// Operations are attributed to their labels, and messages to the labels of the
+    /// operations that send them or of the scopes that allocate their receipt
+    pub fn record_labeled<F: FnOnce(&mut FabricMetrics)>(&self, label: &str, record: F) {
+        let mut labels = self.labels.lock().expect("label metrics poisoned");
+        match labels.get_mut(label) {
+            Some(metrics) => record(metrics),
+            None => {
+                let mut metrics = FabricMetrics::default();
+                record(&mut metrics);
+                labels.insert(label.to_string(), metrics);
+            }
+        }
+    }
+
+    /// Take a snapshot of the metrics of each label
+    pub fn snapshot_by_label(&self) -> HashMap<String, FabricMetrics> {
+        self.labels.lock().expect("label metrics poisoned").clone()
+    }
+
+    /// Take a snapshot of the counters
+    pub fn snapshot(&self) -> FabricMetrics {
+        FabricMetrics {
+            ops_allocated: self.ops_allocated.load(Ordering::Relaxed),
+            ops_executed: self.ops_executed.load(Ordering::Relaxed),
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -1,24 +1,351 @@
 //! Defines an abstraction over the network that receives jobs scheduled onto the
 //! network and re-enqueues them in the result buffer for dependent instructions
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
-use crossbeam::queue::SegQueue;
+use futures::channel::oneshot::{self, Receiver as OneshotReceiver};
 use futures::stream::SplitSink;
 use futures::SinkExt;
 use futures::{stream::SplitStream, StreamExt};
+use once_cell::sync::OnceCell;
 use tokio::sync::broadcast::Receiver as BroadcastReceiver;
-use tokio::sync::mpsc::UnboundedReceiver as TokioReceiver;
+use tokio::sync::mpsc::{
+    self, error::TrySendError, Receiver as BoundedReceiver, Sender as BoundedSender,
+    UnboundedReceiver, UnboundedSender,
+};
+use tokio::time::{interval, timeout, timeout_at, Instant, MissedTickBehavior};
 use tracing::log;
 
-use crate::error::MpcNetworkError;
-use crate::network::{MpcNetwork, NetworkOutbound};
+use crate::error::{MpcError, MpcNetworkError};
+use crate::network::{MpcNetwork, NetworkOutbound, NetworkPayload, PayloadShape};
 
-use super::executor::ExecutorMessage;
-use super::result::OpResult;
+use super::executor::{ExecutorMessage, ExecutorQueue};
+use super::metrics::MetricsCounters;
+use super::result::{OpResult, ResultId};
+use super::worker::WorkerExit;
 
 /// Error message emitted when a stream closes early
 const ERR_STREAM_FINISHED_EARLY: &str = "stream finished early";
+/// Error message emitted when a message is sent on a full outbound queue
+const ERR_OUTBOUND_QUEUE_FULL: &str = "outbound queue is full";
+/// Error message emitted when a message is sent after the network sender has shut down
+const ERR_OUTBOUND_QUEUE_CLOSED: &str = "outbound queue is closed";
+/// Error message emitted when the write loop panics while draining the outbound queue
+const ERR_WRITE_LOOP_PANICKED: &str = "write loop panicked while draining";
+/// Error message emitted when a flush is requested after the network sender has exited
+pub(crate) const ERR_FLUSH_AFTER_EXIT: &str = "network sender exited before flushing";
+
+/// A request to flush the outbound queue, answered once the queued messages are written
+pub(crate) type FlushRequest = oneshot::Sender<Result<(), MpcNetworkError>>;
+
+/// The result ID reserved for heartbeat messages, these are not forwarded to the executor
+pub(crate) const HEARTBEAT_RESULT_ID: ResultId = ResultId::MAX;
+/// The result ID reserved for coalesced messages, whose payloads hold the packed messages
+const COALESCED_RESULT_ID: ResultId = ResultId::MAX - 1;
+/// The result ID reserved for abort messages, whose payloads hold the sender's reason
+const ABORT_RESULT_ID: ResultId = ResultId::MAX - 2;
+/// The number of heartbeats sent per liveness timeout period when the connection is idle
+const HEARTBEATS_PER_TIMEOUT: u32 = 4;
+
+// ------------------
+// | Outbound Queue |
+// ------------------
+
+/// Create the queue of messages to the peer, bounded to the given number of messages if any
+pub(crate) fn outbound_channel(bound: Option<usize>) -> (OutboundSender, OutboundReceiver) {
+    let failure = Arc::new(OnceCell::new());
+    let (send, recv) = match bound {
+        Some(bound) => {
+            let (send, recv) = mpsc::channel(bound);
+            (QueueSender::Bounded(send), QueueReceiver::Bounded(recv))
+        }
+        None => {
+            let (send, recv) = mpsc::unbounded_channel();
+            (QueueSender::Unbounded(send), QueueReceiver::Unbounded(recv))
+        }
+    };
+
+    (
+        OutboundSender {
+            queue: send,
+            failure: failure.clone(),
+        },
+        OutboundReceiver {
+            queue: recv,
+            failure,
+        },
+    )
+}
+
+/// The sending half of the queue of messages to the peer
+///
+/// The queue carries the error that stopped messages from reaching the peer, if any, so
+/// that senders learn of the failure without waiting on the executor to record it
+#[derive(Clone, Debug)]
+pub(crate) struct OutboundSender {
+    /// The underlying queue
+    queue: QueueSender,
+    /// The error that stopped messages from reaching the peer, set once
+    failure: Arc<OnceCell<MpcError>>,
+}
+
+/// The sending half of the underlying queue
+#[derive(Clone, Debug)]
+enum QueueSender {
+    /// A queue that buffers without limit
+    Unbounded(UnboundedSender<NetworkOutbound>),
+    /// A queue that holds a bounded number of messages
+    Bounded(BoundedSender<NetworkOutbound>),
+}
+
+impl OutboundSender {
+    /// Enqueue a message for the peer
+    ///
+    /// This never blocks, a message sent on a full bounded queue returns an error
+    pub fn send(&self, msg: NetworkOutbound) -> Result<(), MpcNetworkError> {
+        match &self.queue {
+            QueueSender::Unbounded(sender) => sender
+                .send(msg)
+                .map_err(|_| MpcNetworkError::SendError(ERR_OUTBOUND_QUEUE_CLOSED.to_string())),
+            QueueSender::Bounded(sender) => sender.try_send(msg).map_err(|e| {
+                let reason = match e {
+                    TrySendError::Full(_) => ERR_OUTBOUND_QUEUE_FULL,
+                    TrySendError::Closed(_) => ERR_OUTBOUND_QUEUE_CLOSED,
+                };
+                MpcNetworkError::SendError(reason.to_string())
+            }),
+        }
+    }
+
+    /// Record the error that stopped a message from reaching the peer, if none is recorded
+    pub fn fail(&self, err: MpcError) {
+        let _ = self.failure.set(err);
+    }
+
+    /// The error that stopped messages from reaching the peer, if any
+    pub fn failure(&self) -> Option<MpcError> {
+        self.failure.get().cloned()
+    }
+
+    /// Whether messages have stopped reaching the peer
+    pub fn is_failed(&self) -> bool {
+        self.failure.get().is_some()
+    }
+}
+
+/// The receiving half of the queue of messages to the peer
+#[derive(Debug)]
+pub(crate) struct OutboundReceiver {
+    /// The underlying queue
+    queue: QueueReceiver,
+    /// The error that stopped messages from reaching the peer, shared with the senders
+    failure: Arc<OnceCell<MpcError>>,
+}
+
+/// The receiving half of the underlying queue
+#[derive(Debug)]
+enum QueueReceiver {
+    /// A queue that buffers without limit
+    Unbounded(UnboundedReceiver<NetworkOutbound>),
+    /// A queue that holds a bounded number of messages
+    Bounded(BoundedReceiver<NetworkOutbound>),
+}
+
+impl OutboundReceiver {
+    /// Receive the next message for the peer, returns `None` once all senders are dropped
+    pub async fn recv(&mut self) -> Option<NetworkOutbound> {
+        match &mut self.queue {
+            QueueReceiver::Unbounded(receiver) => receiver.recv().await,
+            QueueReceiver::Bounded(receiver) => receiver.recv().await,
+        }
+    }
+
+    /// Receive the next message for the peer if one is queued
+    pub fn try_recv(&mut self) -> Option<NetworkOutbound> {
+        match &mut self.queue {
+            QueueReceiver::Unbounded(receiver) => receiver.try_recv().ok(),
+            QueueReceiver::Bounded(receiver) => receiver.try_recv().ok(),
+        }
+    }
+}
+
+// -------------------
+// | Send Coalescing |
+// -------------------
+
+/// How the network sender coalesces messages to the peer into wire messages
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SendCoalescing {
+    /// The maximum number of messages packed into a wire message
+    pub max_messages: usize,
+    /// The amount of time to wait for more messages once a message is ready to send
+    pub window: Duration,
+}
+
+impl SendCoalescing {
+    /// Pack the messages queued behind `first` into a single wire message
+    async fn collect(
+        &self,
+        first: NetworkOutbound,
+        outbound: &mut OutboundReceiver,
+    ) -> NetworkOutbound {
+        let deadline = Instant::now() + self.window;
+        let mut batch = vec![first];
+        while batch.len() < self.max_messages {
+            let next = match outbound.try_recv() {
+                Some(msg) => Some(msg),
+                None if self.window.is_zero() => None,
+                None => timeout_at(deadline, outbound.recv()).await.ok().flatten(),
+            };
+
+            match next {
+                Some(msg) => batch.push(msg),
+                None => break,
+            }
+        }
+
+        if batch.len() == 1 {
+            return batch.remove(0);
+        }
+
+        NetworkOutbound {
+            result_id: COALESCED_RESULT_ID,
+            payload: NetworkPayload::Coalesced(batch),
+        }
+    }
+}
+
+// ---------------------
+// | Inbound Validation |
+// ---------------------
+
+/// The state of a result that is received from the peer
+#[derive(Debug)]
+enum InboundEntry {
+    /// The local party has allocated the result, but the payload has not arrived
+    Expected(Option<PayloadShape>),
+    /// The payload has arrived before the local party allocated the result
+    Received(NetworkPayload),
+}
+
+/// Validates payloads received from the peer against the shape expected by the receiving
+/// result before forwarding them to the executor
+///
+/// The peer may run ahead of the local party, so a payload may arrive before its result is
+/// allocated locally; such payloads are held until the local party allocates the result
+#[derive(Debug)]
+pub(crate) struct InboundPayloads {
+    /// The results that are awaiting either their allocation or their payload
+    entries: Mutex<HashMap<ResultId, InboundEntry>>,
+    /// Whether the peer closed the connection, set while holding the entries lock
+    closed: AtomicBool,
+    /// The queue of completed results
+    result_queue: Arc<ExecutorQueue>,
+}
+
+impl InboundPayloads {
+    /// Constructor
+    pub fn new(result_queue: Arc<ExecutorQueue>) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+            result_queue,
+        }
+    }
+
+    /// Register a result allocated locally that the peer will send, optionally with the
+    /// shape its payload must have
+    pub fn expect(&self, id: ResultId, shape: Option<PayloadShape>) {
+        let mut locked_entries = self.entries.lock().expect("inbound payloads poisoned");
+        match locked_entries.remove(&id) {
+            Some(InboundEntry::Received(payload)) => self.forward(id, payload, shape),
+            // The payload never arrives once the peer has closed the connection
+            _ if self.closed.load(Ordering::Acquire) => self
+                .result_queue
+                .push(ExecutorMessage::Error(MpcError::PeerDisconnected)),
+            _ => {
+                locked_entries.insert(id, InboundEntry::Expected(shape));
+            }
+        }
+    }
+
+    /// Record that the peer closed the connection, returning whether any result allocated
+    /// locally still awaits its payload
+    pub fn close(&self) -> bool {
+        let locked_entries = self.entries.lock().expect("inbound payloads poisoned");
+        self.closed.store(true, Ordering::Release);
+
+        locked_entries
+            .values()
+            .any(|entry| matches!(entry, InboundEntry::Expected(_)))
+    }
+
+    /// Handle a payload received from the peer
+    ///
+    /// Coalesced openings are unpacked and each opening is handled separately
+    pub fn receive(&self, id: ResultId, payload: NetworkPayload) {
+        if let NetworkPayload::Openings { result_ids, values } = payload {
+            return match NetworkPayload::unpack_openings(result_ids, *values) {
+                Some(openings) => openings
+                    .into_iter()
+                    .for_each(|msg| self.receive_single(msg.result_id, msg.payload)),
+                None => self.fail(format!("malformed openings in {id}")),
+            };
+        }
+
+        self.receive_single(id, payload)
+    }
+
+    /// Handle a single payload received from the peer
+    fn receive_single(&self, id: ResultId, payload: NetworkPayload) {
+        let mut locked_entries = self.entries.lock().expect("inbound payloads poisoned");
+        match locked_entries.remove(&id) {
+            Some(InboundEntry::Expected(shape)) => self.forward(id, payload, shape),
+            Some(InboundEntry::Received(_)) => self.fail(format!("duplicate payload for {id}")),
+            None => {
+                locked_entries.insert(id, InboundEntry::Received(payload));
+            }
+        }
+    }
+
+    /// Validate a payload and forward it to the executor
+    fn forward(&self, id: ResultId, payload: NetworkPayload, shape: Option<PayloadShape>) {
+        if matches!(
+            payload,
+            NetworkPayload::Coalesced(_) | NetworkPayload::Openings { .. }
+        ) {
+            return self.fail(format!("nested coalesced payload for {id}"));
+        }
+
+        if let Some(shape) = shape {
+            if !shape.matches(&payload) {
+                return self.fail(format!("expected {shape:?} for {id}, got {payload:?}"));
+            }
+        }
+
+        self.result_queue
+            .push_inbound(ExecutorMessage::Result(OpResult {
+                id,
+                value: payload.into(),
+            }));
+    }
+
+    /// Fail the computation on an invalid payload
+    fn fail(&self, reason: String) {
+        log::error!("invalid payload from peer: {reason}");
+        self.result_queue
+            .push(ExecutorMessage::Error(MpcError::NetworkError(
+                MpcNetworkError::InvalidPayload(reason),
+            )));
+    }
+}
 
 // -------------------------
 // | Sender Implementation |
@@ -28,97 +355,349 @@ const ERR_STREAM_FINISHED_EARLY: &str = "stream finished early";
 /// onto the network and pulling results off the network, re-enqueuing them for processing
 pub(crate) struct NetworkSender<N: MpcNetwork> {
     /// The outbound queue of messages to send
-    outbound: TokioReceiver<NetworkOutbound>,
+    outbound: OutboundReceiver,
     /// The queue of completed results
-    result_queue: Arc<SegQueue<ExecutorMessage>>,
+    result_queue: Arc<ExecutorQueue>,
+    /// The validator for payloads received from the peer
+    inbound: Arc<InboundPayloads>,
     /// The underlying network connection
     network: N,
+    /// The amount of time the peer may go silent before it is considered disconnected
+    liveness_timeout: Duration,
+    /// How messages to the peer are coalesced into wire messages, if at all
+    coalescing: Option<SendCoalescing>,
+    /// The counters that messages are recorded in, if the fabric records metrics
+    metrics: Option<Arc<MetricsCounters>>,
     /// The broadcast channel on which shutdown signals are sent
     shutdown: BroadcastReceiver<()>,
+    /// Resolves once the executor exits, after which it enqueues no more messages
+    executor_exit: WorkerExit<()>,
+    /// The requests to flush the outbound queue, forwarded by the executor
+    flush_requests: UnboundedReceiver<FlushRequest>,
 }
 
 impl<N: MpcNetwork + 'static> NetworkSender<N> {
     /// Creates a new network sender
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        outbound: TokioReceiver<NetworkOutbound>,
-        result_queue: Arc<SegQueue<ExecutorMessage>>,
+        outbound: OutboundReceiver,
+        result_queue: Arc<ExecutorQueue>,
+        inbound: Arc<InboundPayloads>,
         network: N,
+        liveness_timeout: Duration,
+        coalescing: Option<SendCoalescing>,
+        metrics: Option<Arc<MetricsCounters>>,
         shutdown: BroadcastReceiver<()>,
+        executor_exit: WorkerExit<()>,
+        flush_requests: UnboundedReceiver<FlushRequest>,
     ) -> Self {
         NetworkSender {
             outbound,
             result_queue,
+            inbound,
             network,
+            liveness_timeout,
+            coalescing,
+            metrics,
             shutdown,
+            executor_exit,
+            flush_requests,
         }
     }
 
-    /// A helper for the `run` method that allows error handling in the caller
-    pub async fn run(self) {
+    /// Run the network sender until it is shut down or the connection to the peer fails
+    ///
+    /// On shutdown the sender waits for the executor to exit, then sends the messages still
+    /// queued for the peer and flushes the network before returning. Returns an error if the
+    /// connection failed before the sender was shut down
+    pub async fn run(self) -> Result<(), MpcError> {
         // Destructure `self` to take ownership of each field
         let NetworkSender {
             outbound,
             result_queue,
+            inbound,
             network,
+            liveness_timeout,
+            coalescing,
+            metrics,
             mut shutdown,
+            executor_exit,
+            flush_requests,
         } = self;
 
         // Start a read and write loop separately
+        let failure = outbound.failure.clone();
         let (send, recv) = network.split();
-        let read_loop_fut = tokio::spawn(Self::read_loop(recv, result_queue));
-        let write_loop_fut = tokio::spawn(Self::write_loop(outbound, send));
+        let mut read_loop_fut = tokio::spawn(Self::read_loop(
+            recv,
+            inbound,
+            liveness_timeout,
+            metrics.clone(),
+        ));
+        let (drain_send, drain_recv) = oneshot::channel();
+        let mut write_loop_fut = tokio::spawn(Self::write_loop(
+            outbound,
+            send,
+            liveness_timeout / HEARTBEATS_PER_TIMEOUT,
+            coalescing,
+            metrics,
+            flush_requests,
+            drain_recv,
+        ));
 
         // Await either of the loops to finish or the shutdown signal
-        tokio::select! {
-            err = read_loop_fut => {
-                log::error!("error in `NetworkSender::read_loop`: {err:?}");
+        let err = tokio::select! {
+            res = &mut read_loop_fut => match res {
+                Ok(Some(err)) => {
+                    log::error!("error in `NetworkSender::read_loop`: {err}");
+                    err
+                },
+                // The peer finished its part of the computation, the local computation fails
+                // only if it awaits another payload or sends another message
+                Ok(None) => {
+                    log::info!("peer closed the connection");
+                    let _ = failure.set(MpcError::PeerDisconnected);
+                    write_loop_fut.abort();
+                    return Ok(());
+                },
+                Err(err) => {
+                    log::error!("`NetworkSender::read_loop` panicked: {err:?}");
+                    MpcError::PeerDisconnected
+                },
             },
-            err = write_loop_fut => {
-                log::error!("error in `NetworkSender::write_loop`: {err:?}")
+            err = &mut write_loop_fut => {
+                log::error!("error in `NetworkSender::write_loop`: {err:?}");
+                // The write loop only returns successfully once signalled to drain
+                err.map(|res| connection_failure(res.expect_err("write loop exited without draining")))
+                    .unwrap_or(MpcError::PeerDisconnected)
             },
             _ = shutdown.recv() => {
-                log::info!("received shutdown signal")
+                log::info!("received shutdown signal, draining outbound queue");
+
+                // An executor that panicked has also exited
+                let _ = executor_exit.await;
+                let _ = drain_send.send(());
+                return match write_loop_fut.await {
+                    Ok(res) => res.map_err(MpcError::NetworkError),
+                    Err(_) => Err(MpcError::NetworkError(MpcNetworkError::SendError(
+                        ERR_WRITE_LOOP_PANICKED.to_string(),
+                    ))),
+                };
             },
-        }
+        };
+
+        // Fail all pending results
+        let _ = failure.set(err.clone());
+        result_queue.push(ExecutorMessage::Error(err.clone()));
+
+        // Stop the other loop, no more messages are exchanged with the peer
+        read_loop_fut.abort();
+        write_loop_fut.abort();
+
+        Err(err)
     }
 
     /// The read loop for the network, reads messages from the network and re-enqueues them
     /// with the executor
+    ///
+    /// Returns the error that fails the computation once the connection to the peer fails,
+    /// no message, including heartbeats, is received from the peer within the liveness
+    /// timeout, or the peer aborts. Returns `None` if the peer closes the connection once
+    /// every result allocated locally has received its payload
     async fn read_loop(
         mut network_stream: SplitStream<N>,
-        result_queue: Arc<SegQueue<ExecutorMessage>>,
-    ) -> MpcNetworkError {
-        while let Some(msg) = network_stream.next().await {
-            match msg {
-                Ok(msg) => {
-                    result_queue.push(ExecutorMessage::Result(OpResult {
-                        id: msg.result_id,
-                        value: msg.payload.into(),
-                    }));
+        inbound: Arc<InboundPayloads>,
+        liveness_timeout: Duration,
+        metrics: Option<Arc<MetricsCounters>>,
+    ) -> Option<MpcError> {
+        loop {
+            let msg = match timeout(liveness_timeout, network_stream.next()).await {
+                Ok(Some(msg)) => msg,
+                Ok(None) => break,
+                Err(_) => return Some(MpcError::Timeout(liveness_timeout)),
+            };
+
+            let mut deliver = |msg: NetworkOutbound| {
+                if msg.result_id == ABORT_RESULT_ID {
+                    return Err(peer_aborted(msg.payload));
                 }
+
+                if let Some(metrics) = metrics.as_ref() {
+                    metrics.record_message_received();
+                }
+                inbound.receive(msg.result_id, msg.payload);
+                Ok(())
+            };
+
+            let res = match msg {
+                Ok(msg) if msg.result_id == HEARTBEAT_RESULT_ID => continue,
+                Ok(NetworkOutbound {
+                    payload: NetworkPayload::Coalesced(batch),
+                    ..
+                }) => batch.into_iter().try_for_each(&mut deliver),
+                Ok(msg) => deliver(msg),
                 Err(e) => {
                     log::error!("error receiving message: {e}");
-                    return e;
+                    return Some(connection_failure(e));
                 }
+            };
+
+            if let Err(err) = res {
+                return Some(err);
             }
         }
 
-        MpcNetworkError::RecvError(ERR_STREAM_FINISHED_EARLY.to_string())
+        inbound.close().then(|| {
+            log::error!("{ERR_STREAM_FINISHED_EARLY}");
+            MpcError::PeerDisconnected
+        })
     }
 
     /// The write loop for the network, reads messages from the outbound queue and sends them
     /// onto the network
+    ///
+    /// When no message has been sent for the heartbeat interval, a heartbeat is sent so that
+    /// the peer knows the connection is still alive
+    ///
+    /// On a flush request, and once signalled to drain, the loop sends the messages already
+    /// queued and flushes the network; a drain returns once they are written
+    #[allow(clippy::too_many_arguments)]
     async fn write_loop(
-        mut outbound_stream: TokioReceiver<NetworkOutbound>,
+        mut outbound_stream: OutboundReceiver,
         mut network: SplitSink<N, NetworkOutbound>,
-    ) -> MpcNetworkError {
-        while let Some(msg) = outbound_stream.recv().await {
-            if let Err(e) = network.send(msg).await {
-                log::error!("error sending outbound: {e:?}");
-                return e;
+        heartbeat_interval: Duration,
+        coalescing: Option<SendCoalescing>,
+        metrics: Option<Arc<MetricsCounters>>,
+        mut flush_requests: UnboundedReceiver<FlushRequest>,
+        mut drain: OneshotReceiver<()>,
+    ) -> Result<(), MpcNetworkError> {
+        let mut heartbeat = interval(heartbeat_interval);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let msg = tokio::select! {
+                msg = outbound_stream.recv() => match msg {
+                    Some(msg) => {
+                        Self::prepare_outbound(msg, &mut outbound_stream, &coalescing, &metrics)
+                            .await
+                    },
+                    None => {
+                        return Err(MpcNetworkError::RecvError(
+                            ERR_STREAM_FINISHED_EARLY.to_string(),
+                        ))
+                    },
+                },
+                Some(reply) = flush_requests.recv() => {
+                    let res =
+                        Self::flush_queued(&mut outbound_stream, &mut network, &coalescing, &metrics)
+                            .await;
+
+                    // The requester may have stopped waiting on the flush
+                    let _ = reply.send(res.clone());
+                    res?;
+
+                    heartbeat.reset();
+                    continue;
+                },
+                _ = &mut drain => break,
+                _ = heartbeat.tick() => NetworkOutbound {
+                    result_id: HEARTBEAT_RESULT_ID,
+                    payload: NetworkPayload::Bytes(Vec::new()),
+                },
+            };
+
+            Self::send_outbound(&mut network, msg).await?;
+            heartbeat.reset();
+        }
+
+        Self::flush_queued(&mut outbound_stream, &mut network, &coalescing, &metrics).await
+    }
+
+    /// Send the messages already queued and flush the network
+    ///
+    /// Queued messages are coalesced without waiting for more messages to arrive
+    async fn flush_queued(
+        outbound_stream: &mut OutboundReceiver,
+        network: &mut SplitSink<N, NetworkOutbound>,
+        coalescing: &Option<SendCoalescing>,
+        metrics: &Option<Arc<MetricsCounters>>,
+    ) -> Result<(), MpcNetworkError> {
+        let coalescing = coalescing.map(|coalescing| SendCoalescing {
+            window: Duration::ZERO,
+            ..coalescing
+        });
+
+        while let Some(msg) = outbound_stream.try_recv() {
+            let msg = Self::prepare_outbound(msg, outbound_stream, &coalescing, metrics).await;
+            Self::send_outbound(network, msg).await?;
+        }
+
+        network.flush().await
+    }
+
+    /// Coalesce the messages queued behind an outbound message if configured to, and record
+    /// the messages sent
+    async fn prepare_outbound(
+        msg: NetworkOutbound,
+        outbound_stream: &mut OutboundReceiver,
+        coalescing: &Option<SendCoalescing>,
+        metrics: &Option<Arc<MetricsCounters>>,
+    ) -> NetworkOutbound {
+        let msg = match coalescing.as_ref() {
+            Some(coalescing) => coalescing.collect(msg, outbound_stream).await,
+            None => msg,
+        };
+
+        if let Some(metrics) = metrics.as_ref() {
+            match &msg.payload {
+                NetworkPayload::Coalesced(batch) => {
+                    batch.iter().for_each(|_| metrics.record_message_sent())
+                }
+                _ => metrics.record_message_sent(),
             }
         }
 
-        MpcNetworkError::RecvError(ERR_STREAM_FINISHED_EARLY.to_string())
+        msg
+    }
+
+    /// Send a message onto the network
+    async fn send_outbound(
+        network: &mut SplitSink<N, NetworkOutbound>,
+        msg: NetworkOutbound,
+    ) -> Result<(), MpcNetworkError> {
+        network.send(msg).await.map_err(|e| {
+            log::error!("error sending outbound: {e:?}");
+            e
+        })
+    }
+}
+
+/// Build the message that notifies the peer that the local party aborted the computation
+pub(crate) fn abort_message(reason: &str) -> NetworkOutbound {
+    NetworkOutbound {
+        result_id: ABORT_RESULT_ID,
+        payload: NetworkPayload::Bytes(reason.as_bytes().to_vec()),
+    }
+}
+
+/// The error that fails the computation when the peer aborts with the given message
+fn peer_aborted(payload: NetworkPayload) -> MpcError {
+    let reason = match payload {
+        NetworkPayload::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        _ => String::new(),
+    };
+
+    MpcError::PeerAborted(reason)
+}
+
+/// The error that fails the computation when the connection to the peer fails
+///
+/// A malformed message from the peer or a silent peer is reported as such, any other error
+/// means the connection to the peer is lost
+fn connection_failure(err: MpcNetworkError) -> MpcError {
+    match err {
+        err @ MpcNetworkError::SerializationError(_) => MpcError::NetworkError(err),
+        MpcNetworkError::Timeout(timeout) => MpcError::Timeout(timeout),
+        _ => MpcError::PeerDisconnected,
     }
 }
@@ -0,0 +1,151 @@
+//! Defines the thread pool that the executor runs the inner loops of large batch gates on
+//!
+//! The pool is owned by the executor and registered on the executor's thread while it runs,
+//! batch gates split their inner loops across the pool through the helpers below when their
+//! batch is large enough, and run serially otherwise. Gates run outside of an executor with
+//! a pool, e.g. in a dry run, are always serial
+
+use std::{cell::RefCell, ops::Range, sync::Arc};
+
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+/// The prefix of the names of the pool's threads
+const GATE_THREAD_NAME: &str = "mpc-gate";
+
+thread_local! {
+    /// The pool registered on the current thread, if any
+    static GATE_POOL: RefCell<Option<Arc<GatePool>>> = const { RefCell::new(None) };
+}
+
+/// How the executor parallelizes large batch gates
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct GateParallelism {
+    /// The number of threads in the pool
+    pub threads: usize,
+    /// The minimum batch length that is split across the pool
+    pub min_batch_len: usize,
+}
+
+/// A thread pool that large batch gates are parallelized across
+#[derive(Debug)]
+pub(crate) struct GatePool {
+    /// The underlying pool
+    pool: ThreadPool,
+    /// The minimum batch length that is split across the pool
+    min_batch_len: usize,
+}
+
+impl GatePool {
+    /// Build a pool with the given parallelism
+    pub fn new(parallelism: GateParallelism) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(parallelism.threads)
+            .thread_name(|i| format!("{GATE_THREAD_NAME}-{i}"))
+            .build()?;
+
+        Ok(Self {
+            pool,
+            min_batch_len: parallelism.min_batch_len,
+        })
+    }
+
+    /// Register the pool on the current thread until the returned guard is dropped
+    pub fn register(self: &Arc<Self>) -> GatePoolGuard {
+        GATE_POOL.with(|pool| pool.borrow_mut().replace(self.clone()));
+        GatePoolGuard
+    }
+}
+
+/// Unregisters the pool from the current thread when dropped
+pub(crate) struct GatePoolGuard;
+
+impl Drop for GatePoolGuard {
+    fn drop(&mut self) {
+        GATE_POOL.with(|pool| pool.borrow_mut().take());
+    }
+}
+
+/// Run a parallel computation on the registered pool if the batch is large enough, otherwise
+/// run the serial computation on the current thread
+fn run_batch<T: Send>(
+    len: usize,
+    parallel: impl FnOnce() -> T + Send,
+    serial: impl FnOnce() -> T,
+) -> T {
+    let pool = GATE_POOL.with(|pool| pool.borrow().clone());
+    match pool {
+        Some(pool) if len >= pool.min_batch_len => pool.pool.install(parallel),
+        _ => serial(),
+    }
+}
+
+/// Map a function over a batch, across the registered pool if the batch is large enough
+pub(crate) fn par_map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Send + Sync,
+{
+    run_batch(
+        items.len(),
+        || items.par_iter().map(&f).collect(),
+        || items.iter().map(&f).collect(),
+    )
+}
+
+/// Reduce a batch of length `len` by splitting its indices into one range per thread of the
+/// registered pool, if the batch is large enough, and combining the reductions of the ranges
+///
+/// `reduce` is applied to the whole batch when run serially, and must accept empty ranges
+pub(crate) fn par_reduce_ranges<U, F, G>(len: usize, reduce: F, combine: G) -> U
+where
+    U: Send,
+    F: Fn(Range<usize>) -> U + Send + Sync,
+    G: Fn(U, U) -> U + Send + Sync,
+{
+    run_batch(
+        len,
+        || {
+            let n_ranges = rayon::current_num_threads();
+            (0..n_ranges)
+                .into_par_iter()
+                .map(|i| reduce(i * len / n_ranges..(i + 1) * len / n_ranges))
+                .reduce_with(&combine)
+                .unwrap()
+        },
+        || reduce(0..len),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{par_map, par_reduce_ranges, GateParallelism, GatePool};
+
+    /// Tests that the helpers compute the same results with and without a registered pool
+    #[test]
+    fn test_parallel_helpers() {
+        let items = (0..1000u64).collect::<Vec<_>>();
+        let run = || {
+            (
+                par_map(&items, |x| x * 2),
+                par_reduce_ranges(items.len(), |r| items[r].iter().sum::<u64>(), |a, b| a + b),
+            )
+        };
+        let serial = run();
+
+        let pool = Arc::new(
+            GatePool::new(GateParallelism {
+                threads: 4,
+                min_batch_len: 16,
+            })
+            .unwrap(),
+        );
+        let _guard = pool.register();
+        let parallel = run();
+
+        assert_eq!(serial, parallel);
+        assert_eq!(serial.1, items.iter().sum::<u64>());
+    }
+}
@@ -0,0 +1,179 @@
+//! Defines the timing profile an executor records for the gates it evaluates when the fabric
+//! is configured to profile gates
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+/// The number of buckets in a timing histogram
+///
+/// Bucket `i` counts the executions that took at least `2^i` and less than `2^(i + 1)`
+/// nanoseconds, the last bucket counts all longer executions
+pub const HISTOGRAM_BUCKETS: usize = 40;
+
+/// The kind of an operation, the granularity at which gates are profiled
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    /// A gate evaluated locally, producing a single result
+    Gate,
+    /// A gate evaluated locally, producing a batch of results
+    GateBatch,
+    /// A network operation, computing the payload sent to the peer
+    Network,
+}
+
+/// A histogram of the execution times of a kind of operation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimingHistogram {
+    /// The number of executions recorded
+    pub count: u64,
+    /// The total time spent in the recorded executions
+    pub total: Duration,
+    /// The longest recorded execution
+    pub max: Duration,
+    /// The number of executions in each log2-nanosecond bucket
+    pub buckets: [u64; HISTOGRAM_BUCKETS],
+}
+
+impl Default for TimingHistogram {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+            buckets: [0; HISTOGRAM_BUCKETS],
+        }
+    }
+}
+
+impl TimingHistogram {
+    /// Record an execution time
+    pub fn record(&mut self, elapsed: Duration) {
+        let nanos = elapsed.as_nanos().max(1);
+        let bucket = (nanos.ilog2() as usize).min(HISTOGRAM_BUCKETS - 1);
+
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        self.buckets[bucket] += 1;
+    }
+
+    /// The mean execution time
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+    }
+
+    /// An upper bound on the given percentile of the execution times, `percentile` is given
+    /// in the range [0, 100]
+    ///
+    /// The bound is the upper edge of the bucket the percentile falls in, capped at the
+    /// longest recorded execution
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let rank = ((percentile / 100.) * self.count as f64).ceil().max(1.) as u64;
+
+        let mut seen = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                let upper = Duration::from_nanos(1u64 << (i + 1));
+                return upper.min(self.max);
+            }
+        }
+
+        self.max
+    }
+}
+
+/// The timing profile of the gates a fabric has evaluated
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GateProfile {
+    /// The execution times of gates producing a single result
+    pub gate: TimingHistogram,
+    /// The execution times of gates producing a batch of results
+    pub gate_batch: TimingHistogram,
+    /// The execution times of the functions computing network payloads
+    pub network: TimingHistogram,
+    /// The execution times of labeled operations of any kind, by label
+    pub labels: HashMap<String, TimingHistogram>,
+}
+
+impl GateProfile {
+    /// Get the histogram of the given kind of operation
+    pub fn histogram(&self, kind: OperationKind) -> &TimingHistogram {
+        match kind {
+            OperationKind::Gate => &self.gate,
+            OperationKind::GateBatch => &self.gate_batch,
+            OperationKind::Network => &self.network,
+        }
+    }
+
+    /// Get a mutable reference to the histogram of the given kind of operation
+    fn histogram_mut(&mut self, kind: OperationKind) -> &mut TimingHistogram {
+        match kind {
+            OperationKind::Gate => &mut self.gate,
+            OperationKind::GateBatch => &mut self.gate_batch,
+            OperationKind::Network => &mut self.network,
+        }
+    }
+}
+
+/// Records the profile of the gates the executor evaluates, shared between the fabric and
+/// the executor
+#[derive(Debug, Default)]
+pub(crate) struct GateProfiler {
+    /// The recorded profile
+    profile: Mutex<GateProfile>,
+}
+
+impl GateProfiler {
+    /// Record the execution time of an operation
+    pub fn record(&self, kind: OperationKind, label: Option<&str>, elapsed: Duration) {
+        let mut profile = self.profile.lock().expect("gate profile poisoned");
+        profile.histogram_mut(kind).record(elapsed);
+
+        if let Some(label) = label {
+            match profile.labels.get_mut(label) {
+                Some(histogram) => histogram.record(elapsed),
+                None => {
+                    let mut histogram = TimingHistogram::default();
+                    histogram.record(elapsed);
+                    profile.labels.insert(label.to_string(), histogram);
+                }
+            }
+        }
+    }
+
+    /// Take a snapshot of the profile
+    pub fn snapshot(&self) -> GateProfile {
+        self.profile.lock().expect("gate profile poisoned").clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::TimingHistogram;
+
+    /// Tests recording execution times into a histogram and reading its statistics
+    #[test]
+    fn test_histogram() {
+        let mut histogram = TimingHistogram::default();
+        for nanos in [100, 200, 300, 5_000] {
+            histogram.record(Duration::from_nanos(nanos));
+        }
+
+        assert_eq!(histogram.count, 4);
+        assert_eq!(histogram.mean(), Duration::from_nanos(1_400));
+        assert_eq!(histogram.max, Duration::from_nanos(5_000));
+        assert_eq!(histogram.buckets[6], 1); // [64, 128)
+        assert_eq!(histogram.buckets[7], 1); // [128, 256)
+        assert_eq!(histogram.buckets[8], 1); // [256, 512)
+        assert_eq!(histogram.buckets[12], 1); // [4096, 8192)
+
+        assert_eq!(histogram.percentile(50.), Duration::from_nanos(256));
+        assert_eq!(histogram.percentile(100.), Duration::from_nanos(5_000));
+    }
+}
@@ -3,19 +3,28 @@
 //! Beaver multiplication
 
 use std::{
+    any::{Any, TypeId},
+    fmt::{Debug, Formatter, Result as FmtResult},
     marker::PhantomData,
+    mem,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
-use futures::Future;
+use futures::{Future, FutureExt};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::{
-    algebra::{scalar::Scalar, stark_curve::StarkPoint},
-    network::NetworkPayload,
+    algebra::{
+        scalar::{Scalar, ScalarResult},
+        stark_curve::StarkPoint,
+    },
+    error::MpcError,
+    network::{buffer_pool::recycle, NetworkPayload},
 };
 
-use super::MpcFabric;
+use super::{MpcFabric, N_CONSTANT_RESULTS};
 
 // ---------------------
 // | Result Value Type |
@@ -25,6 +34,8 @@ use super::MpcFabric;
 pub type ResultId = usize;
 
 /// The result of an MPC operation
+///
+/// Results may hold secret share material, so their values are zeroized when dropped
 #[derive(Clone, Debug)]
 pub struct OpResult {
     /// The ID of the result's output
@@ -33,6 +44,19 @@ pub struct OpResult {
     pub value: ResultValue,
 }
 
+impl Drop for OpResult {
+    fn drop(&mut self) {
+        // Batches are zeroized as they are returned to the network's buffer pools
+        match &mut self.value {
+            ResultValue::ScalarBatch(scalars) => recycle(mem::take(scalars)),
+            ResultValue::PointBatch(points) => recycle(mem::take(points)),
+            value => value.zeroize(),
+        }
+    }
+}
+
+impl ZeroizeOnDrop for OpResult {}
+
 /// The value of a result
 #[derive(Clone, Debug)]
 pub enum ResultValue {
@@ -46,6 +70,144 @@ pub enum ResultValue {
     Point(StarkPoint),
     /// A batch of points on the curve
     PointBatch(Vec<StarkPoint>),
+    /// A value of a user-defined type
+    Custom(CustomValue),
+}
+
+impl ResultValue {
+    /// The name of the kind of value held
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ResultValue::Bytes(_) => "bytes",
+            ResultValue::Scalar(_) => "scalar",
+            ResultValue::ScalarBatch(_) => "scalar batch",
+            ResultValue::Point(_) => "point",
+            ResultValue::PointBatch(_) => "point batch",
+            ResultValue::Custom(_) => "custom value",
+        }
+    }
+}
+
+impl Zeroize for ResultValue {
+    fn zeroize(&mut self) {
+        match self {
+            ResultValue::Bytes(bytes) => bytes.zeroize(),
+            ResultValue::Scalar(scalar) => scalar.zeroize(),
+            ResultValue::ScalarBatch(scalars) => scalars.zeroize(),
+            ResultValue::Point(point) => point.zeroize(),
+            ResultValue::PointBatch(points) => points.zeroize(),
+            ResultValue::Custom(value) => value.zeroize(),
+        }
+    }
+}
+
+// ----------------
+// | Custom Types |
+// ----------------
+
+/// A user-defined type that may flow through the fabric's gates
+///
+/// Values are held locally as is, and serialized only when sent to the peer. A value
+/// received from the peer is held serialized until it is downcast to its type
+pub trait CustomResult: Any + Clone + Send + Sync {
+    /// Serialize the value to send it to the peer
+    fn to_bytes(&self) -> Vec<u8>;
+    /// Deserialize a value received from the peer, returning `None` if the bytes are
+    /// malformed
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+/// A type erased value of a user-defined type
+///
+/// Values held locally are not zeroized when dropped, a type holding secret material should
+/// zeroize itself on drop
+#[derive(Clone)]
+pub enum CustomValue {
+    /// A value allocated locally
+    Local {
+        /// The value
+        value: Arc<dyn Any + Send + Sync>,
+        /// Serializes the value
+        to_bytes: fn(&(dyn Any + Send + Sync)) -> Vec<u8>,
+    },
+    /// A value received from the peer, not yet deserialized
+    Serialized(Vec<u8>),
+}
+
+impl Debug for CustomValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CustomValue::Local { .. } => write!(f, "CustomValue::Local"),
+            CustomValue::Serialized(bytes) => write!(f, "CustomValue::Serialized({bytes:?})"),
+        }
+    }
+}
+
+impl CustomValue {
+    /// Wrap a value of a user-defined type
+    pub fn new<T: CustomResult>(value: T) -> Self {
+        CustomValue::Local {
+            value: Arc::new(value),
+            to_bytes: |value| value.downcast_ref::<T>().unwrap().to_bytes(),
+        }
+    }
+
+    /// Serialize the value to send it to the peer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            CustomValue::Local { value, to_bytes } => to_bytes(value.as_ref()),
+            CustomValue::Serialized(bytes) => bytes.clone(),
+        }
+    }
+
+    /// Downcast the value to its type, returning `None` if the value is of another type or
+    /// was received from the peer and does not deserialize
+    pub fn downcast<T: CustomResult>(&self) -> Option<T> {
+        match self {
+            CustomValue::Local { value, .. } => value.downcast_ref::<T>().cloned(),
+            CustomValue::Serialized(bytes) => T::from_bytes(bytes),
+        }
+    }
+}
+
+impl Zeroize for CustomValue {
+    fn zeroize(&mut self) {
+        if let CustomValue::Serialized(bytes) = self {
+            bytes.zeroize();
+        }
+    }
+}
+
+/// A wrapper that allows a user-defined type to be the value of a `ResultHandle`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Custom<T>(pub T);
+
+impl<T: CustomResult> From<ResultValue> for Custom<T> {
+    fn from(value: ResultValue) -> Self {
+        match value {
+            ResultValue::Custom(ref custom) => match custom.downcast() {
+                Some(value) => Custom(value),
+                None => panic!(
+                    "Cannot downcast {:?} to {}",
+                    value,
+                    std::any::type_name::<T>()
+                ),
+            },
+            _ => panic!("Cannot cast {:?} to custom value", value),
+        }
+    }
+}
+
+impl<T: CustomResult> From<Custom<T>> for ResultValue {
+    fn from(value: Custom<T>) -> Self {
+        ResultValue::Custom(CustomValue::new(value.0))
+    }
+}
+
+impl<T: CustomResult> From<Custom<T>> for NetworkPayload {
+    fn from(value: Custom<T>) -> Self {
+        NetworkPayload::Custom(value.0.to_bytes())
+    }
 }
 
 impl From<NetworkPayload> for ResultValue {
@@ -56,6 +218,10 @@ impl From<NetworkPayload> for ResultValue {
             NetworkPayload::ScalarBatch(scalars) => ResultValue::ScalarBatch(scalars),
             NetworkPayload::Point(point) => ResultValue::Point(point),
             NetworkPayload::PointBatch(points) => ResultValue::PointBatch(points),
+            NetworkPayload::Custom(bytes) => ResultValue::Custom(CustomValue::Serialized(bytes)),
+            NetworkPayload::Coalesced(_) | NetworkPayload::Openings { .. } => {
+                unreachable!("coalesced payloads are unpacked before delivery")
+            }
         }
     }
 }
@@ -68,6 +234,7 @@ impl From<ResultValue> for NetworkPayload {
             ResultValue::ScalarBatch(scalars) => NetworkPayload::ScalarBatch(scalars),
             ResultValue::Point(point) => NetworkPayload::Point(point),
             ResultValue::PointBatch(points) => NetworkPayload::PointBatch(points),
+            ResultValue::Custom(value) => NetworkPayload::Custom(value.to_bytes()),
         }
     }
 }
@@ -136,6 +303,35 @@ impl From<ResultValue> for Vec<StarkPoint> {
     }
 }
 
+/// Convert the value of a result to the type it is awaited as
+///
+/// Values awaited as one of the built in types are checked to be of the type's kind, an
+/// error is returned rather than panicking in the conversion
+fn cast_value<T: From<ResultValue> + 'static>(
+    result_id: ResultId,
+    value: ResultValue,
+) -> Result<T, MpcError> {
+    let expected = [
+        (TypeId::of::<Vec<u8>>(), "bytes"),
+        (TypeId::of::<Scalar>(), "scalar"),
+        (TypeId::of::<Vec<Scalar>>(), "scalar batch"),
+        (TypeId::of::<StarkPoint>(), "point"),
+        (TypeId::of::<Vec<StarkPoint>>(), "point batch"),
+    ]
+    .into_iter()
+    .find(|(ty, _)| *ty == TypeId::of::<T>())
+    .map(|(_, name)| name);
+
+    match expected {
+        Some(expected) if expected != value.type_name() => Err(MpcError::TypeMismatch {
+            result_id,
+            expected,
+            found: value.type_name(),
+        }),
+        _ => Ok(value.into()),
+    }
+}
+
 // ---------------
 // | Handle Type |
 // ---------------
@@ -185,22 +381,276 @@ impl<T: From<ResultValue>> ResultHandle<T> {
     }
 }
 
-impl<T: From<ResultValue>> Future for ResultHandle<T> {
-    type Output = T;
+impl<T: From<ResultValue>> ResultHandle<T> {
+    /// Release the result once the operations already allocated on it have executed
+    ///
+    /// The fabric drops the stored result early, keeping its memory flat in streaming
+    /// computations. The result must not be awaited or used in new operations after it is
+    /// released, through this handle or any clone of it
+    pub fn release(self) {
+        self.fabric.inner.release_result(self.id, None /* uses */);
+    }
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    /// Mark the result as consumed once, releasing it after the first operation that uses it
+    /// executes
+    ///
+    /// As with `release`, the result must not be used again once it has been consumed
+    pub fn consume_once(self) -> Self {
+        self.fabric
+            .inner
+            .release_result(self.id, Some(1) /* uses */);
+        self
+    }
+}
+
+impl<T: From<ResultValue> + 'static> ResultHandle<T> {
+    /// Convert the handle into a future that resolves to an error if the computation fails
+    /// before the result is ready
+    ///
+    /// Awaiting the handle directly panics in this case
+    pub fn fallible(self) -> FallibleResultHandle<T> {
+        FallibleResultHandle { handle: self }
+    }
+
+    /// Convert the handle into a future that moves the result's value out of the fabric
+    /// rather than copying it, e.g. to await a large batch without cloning it
+    ///
+    /// The value is handed over once the operations already allocated on the result have
+    /// executed. As with `release`, the result must not be used in new operations after it is
+    /// taken, through this handle or any clone of it
+    pub fn into_owned(self) -> OwnedResultHandle<T> {
+        // The constant results are shared by all circuits and are copied instead
+        if self.id >= N_CONSTANT_RESULTS {
+            self.fabric.inner.take_result(self.id);
+        }
+
+        OwnedResultHandle { handle: self }
+    }
+
+    /// Poll the result, returning an error if the computation has failed
+    pub(crate) fn poll_result(&self, cx: &mut Context<'_>) -> Poll<Result<T, MpcError>> {
         let locked_results = self.fabric.inner.results.read().expect("results poisoned");
         let mut locked_wakers = self.fabric.inner.wakers.write().expect("wakers poisoned");
 
-        match locked_results.get(self.id) {
-            Some(res) => Poll::Ready(res.value.clone().into()),
-            None => {
-                locked_wakers
-                    .entry(self.id)
-                    .or_insert_with(Vec::new)
-                    .push(cx.waker().clone());
-                Poll::Pending
+        if let Some(res) = locked_results.get(self.id) {
+            return Poll::Ready(cast_value(self.id, res.value.clone()));
+        }
+
+        // The failure is checked while holding the wakers lock, the executor takes the same
+        // lock when recording a failure so the waker registered below is not missed
+        if let Some(err) = self
+            .fabric
+            .inner
+            .failure
+            .read()
+            .expect("failure poisoned")
+            .clone()
+        {
+            return Poll::Ready(Err(err));
+        }
+
+        locked_wakers
+            .entry(self.id)
+            .or_insert_with(Vec::new)
+            .push(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T: From<ResultValue> + 'static> Future for ResultHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_result(cx)
+            .map(|res| res.unwrap_or_else(|err| panic!("error awaiting result: {err}")))
+    }
+}
+
+/// A handle to a result that resolves to an error if the computation fails before the
+/// result is ready, e.g. because the peer disconnected
+#[derive(Clone, Debug)]
+pub struct FallibleResultHandle<T: From<ResultValue>> {
+    /// The underlying result handle
+    handle: ResultHandle<T>,
+}
+
+impl<T: From<ResultValue> + 'static> Future for FallibleResultHandle<T> {
+    type Output = Result<T, MpcError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.handle.poll_result(cx)
+    }
+}
+
+/// A handle to a result that moves the result's value out of the fabric when it is ready,
+/// see `ResultHandle::into_owned`
+#[derive(Debug)]
+pub struct OwnedResultHandle<T: From<ResultValue>> {
+    /// The underlying result handle
+    handle: ResultHandle<T>,
+}
+
+impl<T: From<ResultValue> + 'static> OwnedResultHandle<T> {
+    /// Poll the fabric's handoffs for the value, returning an error if the computation has
+    /// failed
+    fn poll_handoff(&self, cx: &mut Context<'_>) -> Poll<Result<T, MpcError>> {
+        let (id, inner) = (self.handle.id, &self.handle.fabric.inner);
+        if id < N_CONSTANT_RESULTS {
+            return self.handle.poll_result(cx);
+        }
+
+        // The executor takes the handoffs lock before the wakers lock when handing a value off,
+        // so holding the handoffs lock until the waker is registered avoids missing the wake
+        let mut locked_handoffs = inner.handoffs.write().expect("handoffs poisoned");
+        let mut locked_wakers = inner.wakers.write().expect("wakers poisoned");
+        if let Some(value) = locked_handoffs.remove(&id) {
+            inner.dropped_result_ids.push(id);
+            return Poll::Ready(cast_value(id, value));
+        }
+
+        if let Some(err) = inner.failure.read().expect("failure poisoned").clone() {
+            return Poll::Ready(Err(err));
+        }
+
+        locked_wakers
+            .entry(id)
+            .or_insert_with(Vec::new)
+            .push(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T: From<ResultValue> + 'static> Future for OwnedResultHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_handoff(cx)
+            .map(|res| res.unwrap_or_else(|err| panic!("error awaiting result: {err}")))
+    }
+}
+
+/// Join a batch of result handles into a future that resolves to their values in order
+///
+/// Awaiting the handles individually takes the fabric's locks for every handle on every wake.
+/// Instead, each poll of the joined future takes the locks once for the whole batch and
+/// registers a single waker, on the first result that is not yet ready. The handles must be
+/// allocated in the same fabric
+pub fn join_results<T: From<ResultValue>>(handles: Vec<ResultHandle<T>>) -> JoinResults<T> {
+    JoinResults {
+        handles,
+        n_ready: 0,
+    }
+}
+
+/// A future that resolves to the values of a batch of results, see `join_results`
+#[derive(Debug)]
+pub struct JoinResults<T: From<ResultValue>> {
+    /// The handles of the results
+    handles: Vec<ResultHandle<T>>,
+    /// The number of leading results found ready by earlier polls
+    n_ready: usize,
+}
+
+impl<T: From<ResultValue> + 'static> JoinResults<T> {
+    /// Poll the results, returning an error if the computation has failed
+    fn poll_results(&mut self, cx: &mut Context<'_>) -> Poll<Result<Vec<T>, MpcError>> {
+        let Some(first) = self.handles.first() else {
+            return Poll::Ready(Ok(Vec::new()));
+        };
+
+        let inner = first.fabric.inner.clone();
+        let locked_results = inner.results.read().expect("results poisoned");
+        let mut locked_wakers = inner.wakers.write().expect("wakers poisoned");
+
+        // Results stay in the fabric once computed, so only those after the ready prefix of
+        // the last poll are checked
+        while let Some(handle) = self.handles.get(self.n_ready) {
+            if locked_results.get(handle.id).is_none() {
+                break;
             }
+            self.n_ready += 1;
+        }
+
+        if self.n_ready == self.handles.len() {
+            let values = self
+                .handles
+                .iter()
+                .map(|handle| {
+                    let value = locked_results.get(handle.id).unwrap().value.clone();
+                    cast_value(handle.id, value)
+                })
+                .collect();
+            return Poll::Ready(values);
+        }
+
+        // As in `ResultHandle::poll_result`, the failure is checked while holding the wakers
+        // lock so the waker registered below is not missed
+        if let Some(err) = inner.failure.read().expect("failure poisoned").clone() {
+            return Poll::Ready(Err(err));
+        }
+
+        locked_wakers
+            .entry(self.handles[self.n_ready].id)
+            .or_insert_with(Vec::new)
+            .push(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+// The future never pins its handles, which hold no values of type `T`
+impl<T: From<ResultValue>> Unpin for JoinResults<T> {}
+
+impl<T: From<ResultValue> + 'static> Future for JoinResults<T> {
+    type Output = Vec<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut()
+            .poll_results(cx)
+            .map(|res| res.unwrap_or_else(|err| panic!("error awaiting results: {err}")))
+    }
+}
+
+/// A barrier that both parties reach, see `MpcFabric::barrier`
+///
+/// Awaiting the barrier resolves once the peer has reached it, or to an error if the
+/// computation fails first
+#[derive(Clone, Debug)]
+pub struct BarrierResult {
+    /// The token received from the peer when it reaches the barrier
+    pub(crate) peer_token: ScalarResult,
+}
+
+impl Future for BarrierResult {
+    type Output = Result<(), MpcError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.peer_token.poll_result(cx).map(|res| res.map(|_| ()))
+    }
+}
+
+/// The result of a broadcast of a public value, i.e. a value shared in the clear along
+/// with a check that both parties hold the same value
+///
+/// Awaiting the result returns the value if the check passes and an error otherwise
+#[derive(Clone, Debug)]
+pub struct BroadcastResult<T: From<ResultValue>> {
+    /// The broadcast value
+    pub value: ResultHandle<T>,
+    /// The result of the consistency check, one if both parties hold the same value
+    pub check: ScalarResult,
+}
+
+impl<T: From<ResultValue> + Unpin + 'static> Future for BroadcastResult<T> {
+    type Output = Result<T, MpcError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let value = futures::ready!(self.as_mut().value.poll_unpin(cx));
+        let check = futures::ready!(self.as_mut().check.poll_unpin(cx));
+
+        if check == Scalar::one() {
+            Poll::Ready(Ok(value))
+        } else {
+            Poll::Ready(Err(MpcError::BroadcastError))
         }
     }
 }
@@ -0,0 +1,337 @@
+//! Defines a fabric that simulates an MPC in the clear, for testing the functional
+//! correctness of circuits without running a peer
+//!
+//! The local party plays the first party and holds the plaintext of every value, the peer
+//! is simulated as holding a share of zero. Values shared by either party are allocated in
+//! the clear, and every message received from the peer is a zero of the same shape as the
+//! local party's last message, as the parties exchange values symmetrically. Gates are
+//! evaluated as they would be in the MPC, so circuits built on the fabric's API compute the
+//! same results under simulation
+
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::{runtime::Handle, sync::broadcast};
+use zeroize::Zeroize;
+
+use crate::{
+    algebra::{mpc_scalar::MpcScalarResult, scalar::Scalar, stark_curve::StarkPoint},
+    beaver::SharedValueSource,
+    network::PayloadShape,
+    PARTY0,
+};
+
+use super::{
+    executor::{Executor, ExecutorQueue},
+    network_sender::outbound_channel,
+    worker::{spawn_worker, WorkerThread},
+    CustomValue, FabricConfig, FabricInner, MpcFabric, OperationType, ResultId, ResultValue,
+    SecurityMode, ShutdownHandle,
+};
+
+/// A fabric that computes in the clear locally, with no shares and no network
+///
+/// Dereferences to an `MpcFabric`, so circuits written against the fabric's API may be run
+/// on a simulation unchanged. Values may be shared by either party, the simulation holds
+/// the plaintext of both. MAC checks are skipped, as the simulated peer holds no MACs, so
+/// options that require the peer to participate in a protocol, e.g. correlated masks, are
+/// not supported
+///
+/// Must be constructed within a Tokio runtime, the fabric's executor runs as a blocking task
+#[derive(Clone, Debug)]
+pub struct SimulationFabric {
+    /// The underlying fabric
+    fabric: MpcFabric,
+}
+
+impl Deref for SimulationFabric {
+    type Target = MpcFabric;
+
+    fn deref(&self) -> &Self::Target {
+        &self.fabric
+    }
+}
+
+impl SimulationFabric {
+    /// Constructor
+    pub fn new() -> Self {
+        let size_hint = FabricConfig::default().size_hint;
+        let execution_queue = Arc::new(ExecutorQueue::new());
+        let (outbound_sender, _) = outbound_channel(None /* bound */);
+        let (shutdown_sender, _) = broadcast::channel(1 /* capacity */);
+
+        let mut inner = FabricInner::new(
+            size_hint,
+            PARTY0,
+            execution_queue.clone(),
+            outbound_sender,
+            SimulationSource::default(),
+        );
+        inner.security_mode = SecurityMode::SemiHonest;
+        inner.simulated_peer = Some(Arc::new(SimulatedPeer::default()));
+
+        let executor = Executor::new(size_hint, execution_queue, inner.clone());
+        let executor_exit = spawn_worker(
+            "mpc-executor",
+            &Handle::current(),
+            WorkerThread::BlockingPool,
+            move || executor.run(),
+        );
+
+        let mut fabric = MpcFabric {
+            inner: Arc::new(inner),
+            shutdown: shutdown_sender,
+            workers: ShutdownHandle::new(Some(executor_exit), None /* network_sender */),
+            mac_key: None,
+            mask_prg: None,
+        };
+        let mac_key_share = fabric.random_shared_scalars(1 /* n */).remove(0);
+        fabric
+            .mac_key
+            .replace(Arc::new(MpcScalarResult::new_shared(mac_key_share)));
+
+        Self { fabric }
+    }
+
+    /// Get the underlying fabric
+    pub fn fabric(&self) -> &MpcFabric {
+        &self.fabric
+    }
+
+    /// Shutdown the fabric and the executor backing it
+    pub fn shutdown(self) -> ShutdownHandle {
+        self.fabric.shutdown()
+    }
+}
+
+impl Default for SimulationFabric {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ------------------
+// | Simulated Peer |
+// ------------------
+
+/// The peer of a simulation, mirrors the local party's messages with zeros
+#[derive(Debug, Default)]
+pub(crate) struct SimulatedPeer {
+    /// The result of the local party's most recent send
+    last_send: Mutex<Option<ResultId>>,
+}
+
+impl SimulatedPeer {
+    /// Simulate an operation allocated with the given result ID
+    ///
+    /// Network operations are evaluated locally as gates, with their payload as their result
+    pub fn simulate_op(&self, op_type: OperationType, result_id: ResultId) -> OperationType {
+        match op_type {
+            OperationType::Network { function } => {
+                self.last_send
+                    .lock()
+                    .expect("simulated peer poisoned")
+                    .replace(result_id);
+                OperationType::Gate {
+                    function: Box::new(move |args| function(args).into()),
+                }
+            }
+            op_type => op_type,
+        }
+    }
+
+    /// Simulate receiving a value from the peer, returning the ID of the received value
+    pub fn receive(&self, fabric: &FabricInner, shape: Option<PayloadShape>) -> ResultId {
+        let last_send = *self.last_send.lock().expect("simulated peer poisoned");
+        match last_send {
+            Some(send_id) => fabric.new_op(
+                vec![send_id],
+                1, /* output_arity */
+                OperationType::Gate {
+                    function: Box::new(|mut args| zero_like(&args.remove(0))),
+                },
+            )[0],
+            None => fabric.allocate_value(zero_of_shape(shape)),
+        }
+    }
+
+    /// Combine the shares of a value shared by the local party into its plaintext
+    pub fn combine_shares(my_share: ResultValue, their_share: ResultValue) -> ResultValue {
+        match (my_share, their_share) {
+            (ResultValue::Scalar(a), ResultValue::Scalar(b)) => ResultValue::Scalar(a + b),
+            (ResultValue::Point(a), ResultValue::Point(b)) => ResultValue::Point(a + b),
+            (ResultValue::ScalarBatch(a), ResultValue::ScalarBatch(b)) => {
+                ResultValue::ScalarBatch(a.iter().zip(b.iter()).map(|(a, b)| a + b).collect_vec())
+            }
+            (ResultValue::PointBatch(a), ResultValue::PointBatch(b)) => {
+                ResultValue::PointBatch(a.iter().zip(b.iter()).map(|(a, b)| a + b).collect_vec())
+            }
+            (my_share, _) => my_share,
+        }
+    }
+}
+
+/// A zero of the same shape as the given value
+fn zero_like(value: &ResultValue) -> ResultValue {
+    match value {
+        ResultValue::Bytes(bytes) => ResultValue::Bytes(vec![0; bytes.len()]),
+        ResultValue::Scalar(_) => ResultValue::Scalar(Scalar::zero()),
+        ResultValue::ScalarBatch(scalars) => {
+            ResultValue::ScalarBatch(vec![Scalar::zero(); scalars.len()])
+        }
+        ResultValue::Point(_) => ResultValue::Point(StarkPoint::identity()),
+        ResultValue::PointBatch(points) => {
+            ResultValue::PointBatch(vec![StarkPoint::identity(); points.len()])
+        }
+        // Custom values have no zero, values are shared in the clear
+        ResultValue::Custom(value) => ResultValue::Custom(value.clone()),
+    }
+}
+
+/// A zero of the given payload shape
+fn zero_of_shape(shape: Option<PayloadShape>) -> ResultValue {
+    match shape {
+        Some(PayloadShape::Bytes) => ResultValue::Bytes(Vec::new()),
+        Some(PayloadShape::ScalarBatch(n)) => {
+            ResultValue::ScalarBatch(vec![Scalar::zero(); n.unwrap_or_default()])
+        }
+        Some(PayloadShape::Point) => ResultValue::Point(StarkPoint::identity()),
+        Some(PayloadShape::PointBatch(n)) => {
+            ResultValue::PointBatch(vec![StarkPoint::identity(); n.unwrap_or_default()])
+        }
+        Some(PayloadShape::Custom) => ResultValue::Custom(CustomValue::Serialized(Vec::new())),
+        Some(PayloadShape::Scalar) | None => ResultValue::Scalar(Scalar::zero()),
+    }
+}
+
+// ---------------------
+// | Simulation Source |
+// ---------------------
+
+/// The beaver source of a simulation, samples the plaintext of random values as the
+/// simulated peer's shares are zero
+struct SimulationSource {
+    /// The RNG values are sampled from
+    rng: StdRng,
+}
+
+impl Default for SimulationSource {
+    fn default() -> Self {
+        Self {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Zeroize for SimulationSource {
+    fn zeroize(&mut self) {
+        // The source holds no secret values, a simulation computes in the clear
+    }
+}
+
+impl SharedValueSource for SimulationSource {
+    fn next_shared_bit(&mut self) -> Scalar {
+        Scalar::from(self.rng.gen_bool(0.5) as u8)
+    }
+
+    fn next_shared_value(&mut self) -> Scalar {
+        Scalar::random(&mut self.rng)
+    }
+
+    fn next_shared_inverse_pair(&mut self) -> (Scalar, Scalar) {
+        let value = Scalar::random(&mut self.rng);
+        (value, value.inverse())
+    }
+
+    fn next_triplet(&mut self) -> (Scalar, Scalar, Scalar) {
+        let a = Scalar::random(&mut self.rng);
+        let b = Scalar::random(&mut self.rng);
+        (a, b, a * b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::{
+            authenticated_scalar::AuthenticatedScalarResult,
+            authenticated_stark_point::AuthenticatedStarkPointResult, scalar::Scalar,
+            stark_curve::StarkPoint,
+        },
+        fabric::SimulationFabric,
+        PARTY0, PARTY1,
+    };
+
+    /// Tests arithmetic on values shared by both parties in a simulation
+    #[tokio::test]
+    async fn test_simulation_arithmetic() {
+        let mut rng = thread_rng();
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+
+        let fabric = SimulationFabric::new();
+        let shared_a = fabric.share_scalar(a, PARTY0);
+        let shared_b = fabric.share_scalar(b, PARTY1);
+
+        let res = (&shared_a * &shared_b + &shared_a - Scalar::from(2u8))
+            .open_authenticated()
+            .await
+            .unwrap();
+        assert_eq!(res, a * b + a - Scalar::from(2u8));
+
+        let point = (&shared_b * StarkPoint::generator())
+            .open_authenticated()
+            .await
+            .unwrap();
+        assert_eq!(point, StarkPoint::generator() * b);
+
+        fabric.shutdown();
+    }
+
+    /// Tests batch operations in a simulation
+    #[tokio::test]
+    async fn test_simulation_batch() {
+        const N: usize = 10;
+        let mut rng = thread_rng();
+        let a = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let b = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+
+        let fabric = SimulationFabric::new();
+        let shared_a = fabric.batch_share_scalar(a.clone(), PARTY0);
+        let shared_b = fabric.batch_share_scalar(b.clone(), PARTY1);
+
+        let prods = AuthenticatedScalarResult::batch_mul(&shared_a, &shared_b);
+        let res =
+            futures::future::join_all(AuthenticatedScalarResult::open_authenticated_batch(&prods))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        assert_eq!(
+            res,
+            a.iter().zip(b.iter()).map(|(a, b)| a * b).collect_vec()
+        );
+
+        let points = AuthenticatedStarkPointResult::batch_mul_generator(&shared_a);
+        let res = futures::future::join_all(
+            AuthenticatedStarkPointResult::open_authenticated_batch(&points),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+        assert_eq!(
+            res,
+            a.iter().map(|a| StarkPoint::generator() * a).collect_vec()
+        );
+
+        fabric.shutdown();
+    }
+}
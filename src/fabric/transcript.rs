@@ -0,0 +1,198 @@
+//! Defines the transcript a fabric records of the values opened, the commitments exchanged,
+//! and the outcomes of the MAC checks in a computation when the fabric is configured to
+//! record a transcript
+//!
+//! A party signs its transcript under its identity key and exports it once the computation
+//! completes, so that an auditor may check the computation after the fact
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+    algebra::stark_curve::StarkPoint,
+    buffer::GrowableBuffer,
+    network::{IdentityKeypair, IdentitySignature, NetworkPayload, PartyId, SessionId},
+};
+
+use super::{result::OpResult, ResultId};
+
+/// The domain separator used when computing the digest a transcript is signed over
+const TRANSCRIPT_DOMAIN: &[u8] = b"mpc-stark-transcript";
+
+/// The kind of an event recorded in a transcript
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TranscriptEntryKind {
+    /// A value opened to both parties
+    Opening,
+    /// A commitment the local party sent to the peer
+    LocalCommitment,
+    /// A commitment the peer sent to the local party
+    PeerCommitment,
+    /// The outcome of a MAC check, one if the check passed and zero otherwise
+    MacCheck,
+}
+
+/// An event recorded in a transcript
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// The kind of the event
+    pub kind: TranscriptEntryKind,
+    /// The ID of the result the event produced
+    pub result_id: ResultId,
+    /// The label of the scope the event was recorded in, if any
+    pub label: Option<String>,
+    /// The value of the result, or `None` if it was not computed before the transcript was
+    /// exported
+    pub value: Option<NetworkPayload>,
+}
+
+/// The transcript of a computation as seen by one party
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Transcript {
+    /// The party that recorded the transcript
+    pub party_id: PartyId,
+    /// The ID of the session the computation ran in
+    pub session_id: SessionId,
+    /// The recorded events, in the order they were allocated
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// The digest a transcript is signed over
+    pub fn digest(&self) -> Vec<u8> {
+        let body = serde_json::to_vec(self).expect("transcript serialization cannot fail");
+
+        let mut hasher = Sha3_256::new();
+        hasher.update(TRANSCRIPT_DOMAIN);
+        hasher.update(body);
+        hasher.finalize().to_vec()
+    }
+
+    /// Sign the transcript under the given identity key
+    pub fn sign<R: RngCore + CryptoRng>(
+        self,
+        keypair: &IdentityKeypair,
+        rng: &mut R,
+    ) -> SignedTranscript {
+        let signature = keypair.sign(&self.digest(), rng);
+        SignedTranscript {
+            transcript: self,
+            signer: keypair.public_key(),
+            signature,
+        }
+    }
+}
+
+/// A transcript signed by the party that recorded it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedTranscript {
+    /// The signed transcript
+    pub transcript: Transcript,
+    /// The identity key of the signer
+    pub signer: StarkPoint,
+    /// The signature over the transcript's digest
+    pub signature: IdentitySignature,
+}
+
+impl SignedTranscript {
+    /// Verify the signature on the transcript under the embedded identity key
+    ///
+    /// This does not authenticate the signer, an auditor should also check that `signer` is
+    /// the identity key of the party the transcript claims to be from
+    pub fn verify(&self) -> bool {
+        self.signature
+            .verify(&self.signer, &self.transcript.digest())
+    }
+
+    /// Write the signed transcript to a file as JSON
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), IoError> {
+        let bytes = serde_json::to_vec_pretty(self)
+            .map_err(|err| IoError::new(ErrorKind::InvalidData, err))?;
+        fs::write(path, bytes)
+    }
+
+    /// Read a signed transcript from a JSON file
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, IoError> {
+        let bytes = fs::read(path)?;
+        serde_json::from_slice(&bytes).map_err(|err| IoError::new(ErrorKind::InvalidData, err))
+    }
+}
+
+/// The entries of a transcript recorded so far, along with the entries still waiting on
+/// their values
+#[derive(Debug, Default)]
+struct RecorderState {
+    /// The entries recorded so far
+    entries: Vec<TranscriptEntry>,
+    /// The indices of the entries waiting on the value of each result
+    pending: HashMap<ResultId, Vec<usize>>,
+}
+
+/// Records the entries of a transcript as the fabric allocates them, and captures their values
+/// as the executor computes them
+///
+/// Values are captured when computed rather than read back at export, as results may be
+/// released from the fabric before the transcript is exported
+#[derive(Debug, Default)]
+pub(crate) struct TranscriptRecorder {
+    /// The state of the recorder
+    state: Mutex<RecorderState>,
+}
+
+impl TranscriptRecorder {
+    /// Record the given results as entries of the given kind
+    ///
+    /// The caller holds the fabric's results lock, so that a result is either already in the
+    /// buffer or is captured by the executor once computed
+    pub fn record(
+        &self,
+        kind: TranscriptEntryKind,
+        ids: &[ResultId],
+        label: Option<Arc<str>>,
+        results: &GrowableBuffer<OpResult>,
+    ) {
+        let mut state = self.state.lock().expect("transcript poisoned");
+        for id in ids.iter().copied() {
+            let value = results.get(id).map(|res| res.value.clone().into());
+            if value.is_none() {
+                let idx = state.entries.len();
+                state.pending.entry(id).or_default().push(idx);
+            }
+
+            state.entries.push(TranscriptEntry {
+                kind,
+                result_id: id,
+                label: label.as_deref().map(str::to_string),
+                value,
+            });
+        }
+    }
+
+    /// Capture the value of a result if any entries are waiting on it
+    pub fn capture(&self, result: &OpResult) {
+        let mut state = self.state.lock().expect("transcript poisoned");
+        if let Some(indices) = state.pending.remove(&result.id) {
+            for idx in indices {
+                state.entries[idx].value = Some(result.value.clone().into());
+            }
+        }
+    }
+
+    /// A snapshot of the entries recorded so far
+    pub fn entries(&self) -> Vec<TranscriptEntry> {
+        self.state
+            .lock()
+            .expect("transcript poisoned")
+            .entries
+            .clone()
+    }
+}
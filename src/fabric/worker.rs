@@ -0,0 +1,160 @@
+//! Defines how the fabric's blocking workers, the executor and network sender, are spawned,
+//! and the handle that resolves once they exit
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    thread::Builder as ThreadBuilder,
+};
+
+use futures::{
+    channel::oneshot::{self, Receiver as OneshotReceiver},
+    future::Shared,
+    ready, FutureExt,
+};
+use tokio::runtime::Handle;
+
+use crate::error::{MpcError, MpcNetworkError};
+
+/// Error message emitted when the network sender panics before it exits
+const ERR_NETWORK_SENDER_PANICKED: &str = "network sender panicked";
+
+/// Resolves with a worker's output once the worker exits, or with an error if the worker
+/// panicked
+pub(crate) type WorkerExit<R> = Shared<OneshotReceiver<R>>;
+
+/// Where a blocking worker runs
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum WorkerThread {
+    /// A thread from the runtime's blocking pool
+    BlockingPool,
+    /// A dedicated OS thread, optionally pinned to a CPU core
+    Dedicated {
+        /// The core to pin the thread to, if any
+        core: Option<usize>,
+    },
+}
+
+/// Spawn a blocking worker with the given thread name, returning a handle that resolves
+/// once the worker exits
+///
+/// Dedicated threads enter the runtime before running the worker, so that the worker may
+/// use the runtime's timers and IO as it would on the blocking pool
+pub(crate) fn spawn_worker<R: 'static + Clone + Send, F: 'static + FnOnce() -> R + Send>(
+    name: &str,
+    runtime: &Handle,
+    thread: WorkerThread,
+    worker: F,
+) -> WorkerExit<R> {
+    let (exit_send, exit_recv) = oneshot::channel();
+    let worker = move || {
+        // The receiver may have been dropped if no handle awaits the worker
+        let _ = exit_send.send(worker());
+    };
+
+    match thread {
+        WorkerThread::BlockingPool => {
+            runtime.spawn_blocking(worker);
+        }
+        WorkerThread::Dedicated { core } => {
+            let runtime = runtime.clone();
+            ThreadBuilder::new()
+                .name(name.to_string())
+                .spawn(move || {
+                    let _guard = runtime.enter();
+                    if let Some(core) = core {
+                        pin_to_core(core);
+                    }
+
+                    worker()
+                })
+                .expect("failed to spawn worker thread");
+        }
+    }
+
+    exit_recv.shared()
+}
+
+/// Resolves once a fabric's executor and network sender have exited
+///
+/// Resolves to an error if the network sender exited before the fabric was shut down, e.g.
+/// because the peer closed the connection first, in which case messages queued for the peer
+/// may not have been sent
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    /// Resolves once the executor exits, `None` once it has exited
+    executor: Option<WorkerExit<()>>,
+    /// Resolves with the outcome of the network sender's drain, `None` if the fabric has no
+    /// network sender
+    network_sender: Option<WorkerExit<Result<(), MpcError>>>,
+}
+
+impl ShutdownHandle {
+    /// Constructor
+    pub(crate) fn new(
+        executor: Option<WorkerExit<()>>,
+        network_sender: Option<WorkerExit<Result<(), MpcError>>>,
+    ) -> Self {
+        Self {
+            executor,
+            network_sender,
+        }
+    }
+}
+
+impl Future for ShutdownHandle {
+    type Output = Result<(), MpcError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // An executor that panicked has also exited
+        if let Some(executor) = self.executor.as_mut() {
+            let _ = ready!(executor.poll_unpin(cx));
+            self.executor = None;
+        }
+
+        let res = match self.network_sender.as_mut() {
+            Some(network_sender) => ready!(network_sender.poll_unpin(cx)).unwrap_or_else(|_| {
+                Err(MpcError::NetworkError(MpcNetworkError::SendError(
+                    ERR_NETWORK_SENDER_PANICKED.to_string(),
+                )))
+            }),
+            None => Ok(()),
+        };
+
+        Poll::Ready(res)
+    }
+}
+
+/// Pin the current thread to the given CPU core, logging a warning if it cannot be pinned
+#[cfg(all(feature = "thread_affinity", target_os = "linux"))]
+#[allow(unsafe_code)]
+fn pin_to_core(core: usize) {
+    if core >= libc::CPU_SETSIZE as usize {
+        tracing::log::warn!("cannot pin worker to core {core}, core out of range");
+        return;
+    }
+
+    // SAFETY: `cpu_set_t` is a plain bitmask for which all zeros is the empty set, and the
+    // set passed to `sched_setaffinity` is valid for its full size
+    let res = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(
+            0, /* pid, the calling thread */
+            std::mem::size_of::<libc::cpu_set_t>(),
+            &set,
+        )
+    };
+
+    if res != 0 {
+        let err = std::io::Error::last_os_error();
+        tracing::log::warn!("cannot pin worker to core {core}: {err}");
+    }
+}
+
+/// Pin the current thread to the given CPU core, logging a warning if it cannot be pinned
+#[cfg(not(all(feature = "thread_affinity", target_os = "linux")))]
+fn pin_to_core(core: usize) {
+    tracing::log::warn!("cannot pin worker to core {core}, thread affinity is unsupported");
+}
@@ -0,0 +1,245 @@
+//! Defines gadgets for accessing arrays of shared values at shared indices
+
+use itertools::Itertools;
+
+use crate::algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar};
+
+use super::{
+    bits::{batch_bit_decompose, AuthenticatedBit},
+    linear_combination,
+    mux::batch_mux,
+};
+
+/// The number of bits needed to represent an index into an array of length `n`
+fn index_bits(n: usize) -> usize {
+    n.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Compute the one-hot indicator vectors of length `n` for a batch of shared indices
+///
+/// The result for index `i` is a vector of shared bits that is one at position `i` and zero
+/// elsewhere. Indices outside of `[0, n)` result in an all-zero vector if they are smaller than
+/// the next power of two above `n`, and are undefined otherwise.
+///
+/// The indices are first decomposed into bits, after which the indicators are expanded one bit
+/// at a time: an indicator `e_j` over the lower `k` bits splits into `e_j * (1 - b_k)` and
+/// `e_j * b_k`. Each layer requires one batched multiplication across all indices
+pub fn batch_one_hot(
+    indices: &[AuthenticatedScalarResult],
+    n: usize,
+) -> Vec<Vec<AuthenticatedBit>> {
+    if indices.is_empty() {
+        return vec![];
+    }
+
+    let fabric = indices[0].fabric();
+    let n_bits = index_bits(n);
+    if n_bits == 0 {
+        return vec![fabric.ones_authenticated(n); indices.len()];
+    }
+
+    let bits = batch_bit_decompose(indices, n_bits);
+
+    // The first layer is linear in the lowest bit
+    let mut indicators = bits
+        .iter()
+        .map(|bits| vec![Scalar::one() - &bits[0], bits[0].clone()])
+        .collect_vec();
+
+    for k in 1..n_bits {
+        let layer_size = indicators[0].len();
+        let lhs = indicators.iter().flatten().cloned().collect_vec();
+        let rhs = bits
+            .iter()
+            .flat_map(|bits| vec![bits[k].clone(); layer_size])
+            .collect_vec();
+        let mut products = AuthenticatedScalarResult::batch_mul(&lhs, &rhs);
+
+        for indicator in indicators.iter_mut() {
+            let set = products.drain(..layer_size).collect_vec();
+            let unset = AuthenticatedScalarResult::batch_sub(indicator, &set);
+            *indicator = [unset, set].concat();
+        }
+    }
+
+    indicators
+        .into_iter()
+        .map(|mut indicator| {
+            indicator.truncate(n);
+            indicator
+        })
+        .collect_vec()
+}
+
+/// Read `array[index]` for a shared index without revealing the index
+pub fn oblivious_read(
+    array: &[AuthenticatedScalarResult],
+    index: &AuthenticatedScalarResult,
+) -> AuthenticatedScalarResult {
+    batch_oblivious_read(array, std::slice::from_ref(index))
+        .pop()
+        .unwrap()
+}
+
+/// Read `array[i]` for each `i` in a batch of shared indices without revealing the indices
+///
+/// Each read is computed as the inner product of the array with the one-hot indicator of the
+/// index. The indicators and the inner products are batched across all reads
+pub fn batch_oblivious_read(
+    array: &[AuthenticatedScalarResult],
+    indices: &[AuthenticatedScalarResult],
+) -> Vec<AuthenticatedScalarResult> {
+    assert!(!array.is_empty(), "cannot read from an empty array");
+    if indices.is_empty() {
+        return vec![];
+    }
+
+    let n = array.len();
+    let indicators = batch_one_hot(indices, n);
+
+    let all_indicators = indicators.into_iter().flatten().collect_vec();
+    let repeated_array = (0..indices.len())
+        .flat_map(|_| array.iter().cloned())
+        .collect_vec();
+    let products = AuthenticatedScalarResult::batch_mul(&all_indicators, &repeated_array);
+
+    let ones = vec![Scalar::one(); n];
+    products
+        .chunks(n)
+        .map(|terms| linear_combination(terms, &ones))
+        .collect_vec()
+}
+
+/// Write `value` to `array[index]` for a shared index without revealing the index
+///
+/// Returns the updated array, see `batch_oblivious_write`
+pub fn oblivious_write(
+    array: &[AuthenticatedScalarResult],
+    index: &AuthenticatedScalarResult,
+    value: &AuthenticatedScalarResult,
+) -> Vec<AuthenticatedScalarResult> {
+    batch_oblivious_write(
+        array,
+        std::slice::from_ref(index),
+        std::slice::from_ref(value),
+    )
+}
+
+/// Write `values[i]` to `array[indices[i]]` for each `i` in a batch of shared indices, in
+/// order, without revealing the indices
+///
+/// Each write selects between the value and the current entry at every position with the
+/// one-hot indicator of the index, so every entry is rewritten and the written position stays
+/// hidden. The indicators of all writes are computed in a single batch, after which each write
+/// takes one batched multiplication. When indices repeat the last write wins.
+///
+/// Returns the updated array
+pub fn batch_oblivious_write(
+    array: &[AuthenticatedScalarResult],
+    indices: &[AuthenticatedScalarResult],
+    values: &[AuthenticatedScalarResult],
+) -> Vec<AuthenticatedScalarResult> {
+    assert_eq!(
+        indices.len(),
+        values.len(),
+        "indices and values must be of equal length"
+    );
+
+    let n = array.len();
+    let indicators = batch_one_hot(indices, n);
+
+    let mut array = array.to_vec();
+    for (indicator, value) in indicators.iter().zip(values.iter()) {
+        let written = vec![value.clone(); n];
+        array = batch_mux(indicator, &written, &array);
+    }
+
+    array
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        gadgets::array::{batch_oblivious_read, batch_oblivious_write, oblivious_write},
+        test_helpers::execute_mock_mpc,
+        PARTY0, PARTY1,
+    };
+
+    /// Tests reading from a shared array at a batch of shared indices
+    #[tokio::test]
+    async fn test_oblivious_read() {
+        const N: usize = 6;
+        let mut rng = thread_rng();
+        let array = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let indices = (0..3).map(|_| rng.gen_range(0..N) as u64).collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let array = array.clone();
+            let indices = indices.clone();
+            async move {
+                let shared_array = fabric.batch_share_scalar(array, PARTY0);
+                let shared_indices = fabric.batch_share_scalar(indices, PARTY1);
+                let reads = batch_oblivious_read(&shared_array, &shared_indices);
+
+                let mut res = Vec::with_capacity(reads.len());
+                for read in AuthenticatedScalarResult::open_authenticated_batch(&reads) {
+                    res.push(read.await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        let expected = indices.iter().map(|i| array[*i as usize]).collect_vec();
+        assert_eq!(res, expected);
+    }
+
+    /// Tests writing to a shared array at shared indices
+    #[tokio::test]
+    async fn test_oblivious_write() {
+        const N: usize = 5;
+        let mut rng = thread_rng();
+        let array = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let values = (0..4).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let indices = vec![3u64, 0, 3, 4];
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let array = array.clone();
+            let values = values.clone();
+            let indices = indices.clone();
+            async move {
+                let shared_array = fabric.batch_share_scalar(array, PARTY0);
+                let shared_values = fabric.batch_share_scalar(values, PARTY0);
+                let shared_indices = fabric.batch_share_scalar(indices, PARTY1);
+
+                let single = oblivious_write(&shared_array, &shared_indices[1], &shared_values[1]);
+                let batch = batch_oblivious_write(&shared_array, &shared_indices, &shared_values);
+
+                let mut res = Vec::with_capacity(2 * N);
+                let all = [single, batch].concat();
+                for val in AuthenticatedScalarResult::open_authenticated_batch(&all) {
+                    res.push(val.await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        let mut expected_single = array.clone();
+        expected_single[0] = values[1];
+
+        // The second write to index 3 overwrites the first
+        let mut expected_batch = array.clone();
+        expected_batch[0] = values[1];
+        expected_batch[3] = values[2];
+        expected_batch[4] = values[3];
+
+        assert_eq!(res, [expected_single, expected_batch].concat());
+    }
+}
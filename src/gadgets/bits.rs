@@ -0,0 +1,185 @@
+//! Defines gadgets for decomposing shared values into shared bits
+
+use itertools::Itertools;
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        scalar::{Scalar, ScalarResult},
+    },
+    fabric::ResultValue,
+};
+
+use super::linear_combination;
+
+/// A shared value that is assumed to be either zero or one
+pub type AuthenticatedBit = AuthenticatedScalarResult;
+
+/// The statistical security parameter used when masking a value before opening it
+///
+/// A value of `m` bits is masked with a random value of `m + STATISTICAL_SECURITY` bits,
+/// so that the opened sum is statistically close to uniform
+pub const STATISTICAL_SECURITY: usize = 40;
+/// The maximum number of bits that may be decomposed
+///
+/// The masked value must not wrap around the field modulus, which is slightly larger
+/// than 2^251
+pub const MAX_DECOMPOSITION_BITS: usize = 250 - STATISTICAL_SECURITY;
+
+/// Compute the powers of two `[1, 2, 4, ..., 2^{n-1}]` as scalars
+pub(crate) fn powers_of_two(n: usize) -> Vec<Scalar> {
+    let two = Scalar::from(2u64);
+    let mut res = Vec::with_capacity(n);
+    let mut curr = Scalar::one();
+    for _ in 0..n {
+        res.push(curr);
+        curr *= two;
+    }
+
+    res
+}
+
+/// Decompose a shared value into its `n_bits` least significant bits
+///
+/// The value is assumed to be in the range `[0, 2^n_bits)`, the result is undefined otherwise.
+/// Bits are returned in little-endian order
+pub fn bit_decompose(x: &AuthenticatedScalarResult, n_bits: usize) -> Vec<AuthenticatedBit> {
+    batch_bit_decompose(std::slice::from_ref(x), n_bits)
+        .pop()
+        .unwrap()
+}
+
+/// Decompose a batch of shared values into their `n_bits` least significant bits
+///
+/// Each value is masked by a random value built from shared random bits, and the masked values
+/// are opened in a single round. The bits of each value are then recovered by a ripple-borrow
+/// subtraction of the shared mask bits from the public masked value. The subtraction requires
+/// one Beaver multiplication per bit, and each bit position is computed for the whole batch at
+/// once, so the decomposition takes `n_bits` rounds regardless of the batch size
+pub fn batch_bit_decompose(
+    values: &[AuthenticatedScalarResult],
+    n_bits: usize,
+) -> Vec<Vec<AuthenticatedBit>> {
+    assert!(
+        n_bits <= MAX_DECOMPOSITION_BITS,
+        "cannot decompose more than {MAX_DECOMPOSITION_BITS} bits"
+    );
+    if values.is_empty() {
+        return vec![];
+    }
+
+    let n = values.len();
+    let fabric = values[0].fabric();
+
+    // Sample a random mask for each value from a set of shared random bits
+    let mask_len = n_bits + STATISTICAL_SECURITY;
+    let mask_bits = fabric.random_shared_bits(n * mask_len);
+    let coeffs = powers_of_two(mask_len);
+    let masks = mask_bits
+        .chunks(mask_len)
+        .map(|bits| linear_combination(bits, &coeffs))
+        .collect_vec();
+
+    // Open the masked values and decompose them into public bits
+    let masked = AuthenticatedScalarResult::batch_add(values, &masks);
+    let masked_open = AuthenticatedScalarResult::open_batch(&masked);
+    let public_bits = masked_open
+        .iter()
+        .map(|val| {
+            fabric.new_batch_gate_op(
+                vec![val.id()],
+                n_bits, /* output_arity */
+                move |mut args| {
+                    let val: Scalar = args.remove(0).into();
+                    let val_bigint = val.to_biguint();
+
+                    (0..n_bits)
+                        .map(|i| ResultValue::Scalar(Scalar::from(val_bigint.bit(i as u64))))
+                        .collect_vec()
+                },
+            )
+        })
+        .collect::<Vec<Vec<ScalarResult>>>();
+
+    // Subtract the mask from the public value bit by bit, tracking the borrow:
+    //  - the output bit is `c_i XOR r_i XOR borrow_i`
+    //  - the next borrow is `(!c_i AND (r_i OR borrow_i)) OR (c_i AND r_i AND borrow_i)`
+    // for public bit `c_i`, mask bit `r_i`. The only shared product is `r_i * borrow_i`
+    let mut borrows = fabric.zeros_authenticated(n);
+    let mut result_bits = vec![Vec::with_capacity(n_bits); n];
+    for i in 0..n_bits {
+        let mask_bits_i = (0..n)
+            .map(|j| mask_bits[j * mask_len + i].clone())
+            .collect_vec();
+
+        // The first borrow is zero, so the product may be skipped
+        let products = if i == 0 {
+            fabric.zeros_authenticated(n)
+        } else {
+            AuthenticatedScalarResult::batch_mul(&mask_bits_i, &borrows)
+        };
+
+        for j in 0..n {
+            let r = &mask_bits_i[j];
+            let c = &public_bits[j][i];
+            let borrow = &borrows[j];
+            let prod = &products[j];
+
+            // y = r XOR borrow
+            let y = r + borrow - Scalar::from(2u64) * prod;
+            let cy = &y * c;
+
+            result_bits[j].push(&y + c - Scalar::from(2u64) * &cy);
+            borrows[j] = r + borrow - prod - cy;
+        }
+    }
+
+    result_bits
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        gadgets::bits::batch_bit_decompose,
+        test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    /// Tests decomposing a batch of values into bits
+    #[tokio::test]
+    async fn test_bit_decompose() {
+        const N: usize = 5;
+        const N_BITS: usize = 64;
+        let mut rng = thread_rng();
+        let values = (0..N).map(|_| rng.gen::<u64>()).collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let values = values.clone();
+            async move {
+                let shared = fabric.batch_share_scalar(values, PARTY0);
+                let bits = batch_bit_decompose(&shared, N_BITS)
+                    .into_iter()
+                    .flatten()
+                    .collect_vec();
+
+                let mut res = Vec::with_capacity(bits.len());
+                for bit in AuthenticatedScalarResult::open_authenticated_batch(&bits) {
+                    res.push(bit.await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        let expected = values
+            .into_iter()
+            .flat_map(|val| (0..N_BITS).map(move |i| Scalar::from((val >> i) & 1)))
+            .collect_vec();
+        assert_eq!(res, expected);
+    }
+}
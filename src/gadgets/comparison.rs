@@ -0,0 +1,468 @@
+//! Defines comparison gadgets on shared values
+
+use futures::future::join_all;
+use itertools::Itertools;
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        scalar::{Scalar, ScalarResult},
+    },
+    error::MpcError,
+    fabric::ResultValue,
+};
+
+use super::{
+    bits::{batch_bit_decompose, powers_of_two, AuthenticatedBit},
+    linear_combination,
+    mux::batch_mux,
+};
+
+// ------------
+// | Equality |
+// ------------
+
+/// Check a batch of pairs of shared values for equality, revealing the result of each check
+///
+/// Each difference `a[i] - b[i]` is multiplied by a fresh shared random value and the products
+/// are opened in a single batch with their MACs checked. A product is zero exactly when the
+/// pair is equal, except with negligible probability, and otherwise reveals nothing about the
+/// difference
+///
+/// Returns a public bit for each pair that is one if the values are equal. Fails if the MAC
+/// check of any opened product fails
+pub async fn batch_eq(
+    a: &[AuthenticatedScalarResult],
+    b: &[AuthenticatedScalarResult],
+) -> Result<Vec<Scalar>, MpcError> {
+    assert_eq!(a.len(), b.len(), "values must be of equal length");
+    if a.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let n = a.len();
+    let fabric = a[0].fabric();
+    let masks = fabric.random_shared_scalars_authenticated(n);
+
+    let diffs = AuthenticatedScalarResult::batch_sub(a, b);
+    let masked_diffs = AuthenticatedScalarResult::batch_mul(&masks, &diffs);
+    let opened = join_all(AuthenticatedScalarResult::open_authenticated_batch(
+        &masked_diffs,
+    ))
+    .await;
+
+    opened
+        .into_iter()
+        .map(|val| Ok(Scalar::from(val? == Scalar::zero())))
+        .collect()
+}
+
+/// Check whether all pairs in a batch of shared values are equal, revealing only a single
+/// aggregate bit
+///
+/// The parties toss a public random challenge `r` with committed contributions once the
+/// inputs are computed, and compute the random linear combination `\sum_i r^i * (a[i] - b[i])`,
+/// which is zero only if all pairs are equal, except with negligible probability. The
+/// combination is masked by a shared random value and opened with its MAC checked
+///
+/// Returns a public bit that is one if all pairs are equal. Fails if the MAC check of the
+/// opened combination fails
+pub async fn batch_eq_all(
+    a: &[AuthenticatedScalarResult],
+    b: &[AuthenticatedScalarResult],
+) -> Result<Scalar, MpcError> {
+    assert_eq!(a.len(), b.len(), "values must be of equal length");
+    assert!(!a.is_empty(), "cannot check an empty batch for equality");
+
+    let n = a.len();
+    let fabric = a[0].fabric();
+    let mask = fabric.random_shared_scalars_authenticated(1).remove(0);
+    let challenge =
+        fabric.committed_challenge(a.iter().chain(b.iter()).flat_map(|v| v.ids()).collect_vec());
+
+    // Compute the powers of the challenge
+    let challenge_powers: Vec<ScalarResult> = fabric.new_batch_gate_op(
+        vec![challenge.id()],
+        n, /* output_arity */
+        move |mut args| {
+            let challenge: Scalar = args.remove(0).into();
+            let mut curr = Scalar::one();
+            let mut res = Vec::with_capacity(n);
+            for _ in 0..n {
+                res.push(ResultValue::Scalar(curr));
+                curr *= challenge;
+            }
+
+            res
+        },
+    );
+
+    let diffs = AuthenticatedScalarResult::batch_sub(a, b);
+    let weighted_diffs = AuthenticatedScalarResult::batch_mul_public(&diffs, &challenge_powers);
+    let combination = linear_combination(&weighted_diffs, &vec![Scalar::one(); n]);
+
+    let masked = (mask * combination).open_authenticated().await?;
+    Ok(Scalar::from(masked == Scalar::zero()))
+}
+
+/// Compute shared bits indicating whether `a[i] == b[i]`, without revealing the results
+///
+/// All values are assumed to be in the range `[0, 2^D)`. A pair is equal exactly when neither
+/// value is less than the other, so the comparisons in both directions are computed in a
+/// single batch and the equality bit is `1 - (a[i] < b[i]) - (b[i] < a[i])`
+pub fn batch_eq_shared<const D: usize>(
+    a: &[AuthenticatedScalarResult],
+    b: &[AuthenticatedScalarResult],
+) -> Vec<AuthenticatedBit> {
+    assert_eq!(a.len(), b.len(), "values must be of equal length");
+
+    let n = a.len();
+    let lt = batch_lt::<D>(&[a, b].concat(), &[b, a].concat());
+    lt[..n]
+        .iter()
+        .zip(lt[n..].iter())
+        .map(|(a_lt_b, b_lt_a)| Scalar::one() - a_lt_b - b_lt_a)
+        .collect_vec()
+}
+
+// ---------------
+// | Less Than |
+// ---------------
+
+/// Compute a shared bit indicating whether `value < bound` for a public `bound`
+///
+/// Both the value and the bound are assumed to be in the range `[0, 2^D)`
+pub fn lt_public<const D: usize>(
+    value: &AuthenticatedScalarResult,
+    bound: Scalar,
+) -> AuthenticatedBit {
+    batch_lt_public::<D>(std::slice::from_ref(value), &[bound])
+        .pop()
+        .unwrap()
+}
+
+/// Compute shared bits indicating whether `values[i] < bounds[i]` for public `bounds`
+///
+/// All values and bounds are assumed to be in the range `[0, 2^D)`. The comparison computes
+/// `2^D + values[i] - bounds[i]`, which lies in `[1, 2^{D+1})`, and returns the negation of its
+/// most significant bit. The bit decompositions of all values in the batch share their rounds
+pub fn batch_lt_public<const D: usize>(
+    values: &[AuthenticatedScalarResult],
+    bounds: &[Scalar],
+) -> Vec<AuthenticatedBit> {
+    assert_eq!(
+        values.len(),
+        bounds.len(),
+        "values and bounds must be of equal length"
+    );
+
+    let offset = powers_of_two(D + 1)[D];
+    let shifted = values
+        .iter()
+        .zip(bounds.iter())
+        .map(|(value, bound)| value + (offset - bound))
+        .collect_vec();
+
+    batch_bit_decompose(&shifted, D + 1)
+        .into_iter()
+        .map(|bits| Scalar::one() - &bits[D])
+        .collect_vec()
+}
+
+/// Compute a shared bit indicating whether `a < b` for shared `a` and `b`
+///
+/// Both values are assumed to be in the range `[0, 2^D)`
+pub fn lt<const D: usize>(
+    a: &AuthenticatedScalarResult,
+    b: &AuthenticatedScalarResult,
+) -> AuthenticatedBit {
+    batch_lt::<D>(std::slice::from_ref(a), std::slice::from_ref(b))
+        .pop()
+        .unwrap()
+}
+
+/// Compute shared bits indicating whether `a[i] < b[i]` for shared `a` and `b`
+///
+/// All values are assumed to be in the range `[0, 2^D)`, see `batch_lt_public` for details
+pub fn batch_lt<const D: usize>(
+    a: &[AuthenticatedScalarResult],
+    b: &[AuthenticatedScalarResult],
+) -> Vec<AuthenticatedBit> {
+    assert_eq!(a.len(), b.len(), "values must be of equal length");
+    if a.is_empty() {
+        return vec![];
+    }
+
+    let offset = powers_of_two(D + 1)[D];
+    let diffs = AuthenticatedScalarResult::batch_sub(a, b);
+    let shifted = diffs.iter().map(|diff| diff + offset).collect_vec();
+
+    batch_bit_decompose(&shifted, D + 1)
+        .into_iter()
+        .map(|bits| Scalar::one() - &bits[D])
+        .collect_vec()
+}
+
+// ------------------
+// | Max and Argmax |
+// ------------------
+
+/// Compute the maximum of a non-empty set of shared values
+///
+/// All values are assumed to be in the range `[0, 2^D)`
+pub fn max<const D: usize>(values: &[AuthenticatedScalarResult]) -> AuthenticatedScalarResult {
+    max_with_index::<D>(values).0
+}
+
+/// Compute the index of the maximum of a non-empty set of shared values as a shared value
+///
+/// All values are assumed to be in the range `[0, 2^D)`. If the maximum appears more than once,
+/// the lowest index at which it appears is returned
+pub fn argmax<const D: usize>(values: &[AuthenticatedScalarResult]) -> AuthenticatedScalarResult {
+    max_with_index::<D>(values).1
+}
+
+/// Compute the maximum of a non-empty set of shared values along with its index
+///
+/// The maximum is computed by a tournament: each layer compares adjacent pairs in one
+/// batched comparison, then selects the winning values and indices in one batched mux. This
+/// takes `ceil(log2(n))` layers
+pub fn max_with_index<const D: usize>(
+    values: &[AuthenticatedScalarResult],
+) -> (AuthenticatedScalarResult, AuthenticatedScalarResult) {
+    assert!(!values.is_empty(), "cannot take the max of an empty set");
+
+    let fabric = values[0].fabric();
+    let mut curr_values = values.to_vec();
+    let mut curr_indices = (0..values.len())
+        .map(|i| fabric.zero_authenticated() + Scalar::from(i as u64))
+        .collect_vec();
+
+    while curr_values.len() > 1 {
+        let n_pairs = curr_values.len() / 2;
+        let (lhs_values, rhs_values): (Vec<_>, Vec<_>) = curr_values[..2 * n_pairs]
+            .chunks_exact(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .unzip();
+        let (lhs_indices, rhs_indices): (Vec<_>, Vec<_>) = curr_indices[..2 * n_pairs]
+            .chunks_exact(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .unzip();
+
+        // The right hand side wins only if it is strictly larger, so ties go to the lower index
+        let rhs_wins = batch_lt::<D>(&lhs_values, &rhs_values);
+        let selectors = [rhs_wins.clone(), rhs_wins].concat();
+        let mut winners = batch_mux(
+            &selectors,
+            &[rhs_values, rhs_indices].concat(),
+            &[lhs_values, lhs_indices].concat(),
+        );
+        let mut winning_indices = winners.split_off(n_pairs);
+
+        // An unpaired value moves on to the next layer directly
+        if curr_values.len() % 2 == 1 {
+            winners.push(curr_values.pop().unwrap());
+            winning_indices.push(curr_indices.pop().unwrap());
+        }
+
+        curr_values = winners;
+        curr_indices = winning_indices;
+    }
+
+    (curr_values.pop().unwrap(), curr_indices.pop().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        gadgets::comparison::{
+            batch_eq, batch_eq_all, batch_eq_shared, batch_lt_public, max_with_index,
+        },
+        network::{MockNetwork, Tamper, TamperHandle, TamperingNetwork, UnboundedDuplexStream},
+        test_helpers::{execute_mock_mpc, PartyIDBeaverSource},
+        MpcFabric, PARTY0, PARTY1,
+    };
+
+    /// Tests a batch of comparisons against public bounds
+    #[tokio::test]
+    async fn test_batch_lt_public() {
+        const N: usize = 10;
+        let mut rng = thread_rng();
+        let values = (0..N).map(|_| rng.gen::<u32>() as u64).collect_vec();
+        let mut bounds = (0..N).map(|_| rng.gen::<u32>() as u64).collect_vec();
+        bounds[0] = values[0];
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let values = values.clone();
+            let bounds = bounds.clone();
+            async move {
+                let shared = fabric.batch_share_scalar(values, PARTY0);
+                let bounds = bounds.into_iter().map(Scalar::from).collect_vec();
+                let lt = batch_lt_public::<32>(&shared, &bounds);
+
+                let mut res = Vec::with_capacity(N);
+                for bit in AuthenticatedScalarResult::open_authenticated_batch(&lt) {
+                    res.push(bit.await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        let expected = values
+            .iter()
+            .zip(bounds.iter())
+            .map(|(v, b)| Scalar::from(v < b))
+            .collect_vec();
+        assert_eq!(res, expected);
+    }
+
+    /// Tests computing the max and argmax of a shared vector
+    #[tokio::test]
+    async fn test_max_argmax() {
+        const N: usize = 7;
+        let mut rng = thread_rng();
+        let values = (0..N).map(|_| rng.gen::<u16>() as u64).collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let values = values.clone();
+            async move {
+                let shared = fabric.batch_share_scalar(values, PARTY0);
+                let (max, argmax) = max_with_index::<16>(&shared);
+
+                (
+                    max.open_authenticated().await.unwrap(),
+                    argmax.open_authenticated().await.unwrap(),
+                )
+            }
+        })
+        .await;
+
+        let expected_max = *values.iter().max().unwrap();
+        let expected_argmax = values.iter().position(|v| *v == expected_max).unwrap();
+        assert_eq!(res.0, Scalar::from(expected_max));
+        assert_eq!(res.1, Scalar::from(expected_argmax as u64));
+    }
+
+    /// Tests per-pair and aggregate equality checks
+    #[tokio::test]
+    async fn test_batch_eq() {
+        const N: usize = 5;
+        let mut rng = thread_rng();
+        let a = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let mut b = a.clone();
+        b[1] = Scalar::random(&mut rng);
+        b[3] = Scalar::random(&mut rng);
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let a = a.clone();
+            let b = b.clone();
+            async move {
+                let shared_a = fabric.batch_share_scalar(a, PARTY0);
+                let shared_b = fabric.batch_share_scalar(b, PARTY0);
+
+                let pairwise = batch_eq(&shared_a, &shared_b).await.unwrap();
+                let all_eq = batch_eq_all(&shared_a, &shared_b).await.unwrap();
+                let all_eq_self = batch_eq_all(&shared_a, &shared_a).await.unwrap();
+
+                (pairwise, all_eq, all_eq_self)
+            }
+        })
+        .await;
+
+        let expected = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| Scalar::from(a == b))
+            .collect_vec();
+        assert_eq!(res.0, expected);
+        assert_eq!(res.1, Scalar::zero());
+        assert_eq!(res.2, Scalar::one());
+    }
+
+    /// Tests that equality checks fail when party 0 tampers with the messages it sends
+    #[tokio::test]
+    async fn test_batch_eq_tampered() {
+        const N: usize = 3;
+        /// An upper bound on the number of results allocated by the checks
+        const MAX_IDS: usize = 1_000;
+
+        let (stream0, stream1) = UnboundedDuplexStream::new_duplex_pair();
+        let network0 = TamperingNetwork::new(MockNetwork::new(PARTY0, stream0));
+        let tamper = network0.handle();
+        let fabric0 = MpcFabric::new(network0, PartyIDBeaverSource::new(PARTY0));
+        let fabric1 = MpcFabric::new(
+            MockNetwork::new(PARTY1, stream1),
+            PartyIDBeaverSource::new(PARTY1),
+        );
+
+        let mut rng = thread_rng();
+        let a = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let run = |fabric: MpcFabric, tamper: Option<TamperHandle>| {
+            let a = a.clone();
+            async move {
+                let shared = fabric.batch_share_scalar(a, PARTY0);
+                shared[0].share().await;
+
+                let start = fabric.peek_next_result_id();
+                if let Some(tamper) = tamper {
+                    (start..start + MAX_IDS).for_each(|id| tamper.tamper(id, Tamper::Flip));
+                }
+
+                let pairwise = batch_eq(&shared, &shared).await;
+                let all_eq = batch_eq_all(&shared, &shared).await;
+                (pairwise.is_err(), all_eq.is_err())
+            }
+        };
+
+        let party0 = tokio::spawn(run(fabric0.clone(), Some(tamper.clone())));
+        let party1 = tokio::spawn(run(fabric1.clone(), None));
+        let res = (party0.await.unwrap(), party1.await.unwrap());
+        fabric0.shutdown();
+        fabric1.shutdown();
+
+        assert!(!tamper.take_applied().is_empty());
+        assert_eq!(res, ((true, true), (true, true)));
+    }
+
+    /// Tests equality checks whose results stay shared
+    #[tokio::test]
+    async fn test_batch_eq_shared() {
+        const N: usize = 5;
+        let mut rng = thread_rng();
+        let a = (0..N).map(|_| rng.gen::<u32>() as u64).collect_vec();
+        let mut b = a.clone();
+        b[1] = rng.gen::<u32>() as u64;
+        b[3] = a[3] + 1;
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let a = a.clone();
+            let b = b.clone();
+            async move {
+                let shared_a = fabric.batch_share_scalar(a, PARTY0);
+                let shared_b = fabric.batch_share_scalar(b, PARTY0);
+                let eq = batch_eq_shared::<33>(&shared_a, &shared_b);
+
+                let mut res = Vec::with_capacity(N);
+                for bit in AuthenticatedScalarResult::open_authenticated_batch(&eq) {
+                    res.push(bit.await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        let expected = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| Scalar::from(a == b))
+            .collect_vec();
+        assert_eq!(res, expected);
+    }
+}
@@ -0,0 +1,194 @@
+//! Defines gadgets for integer division of shared values by public divisors
+
+use itertools::Itertools;
+use num_bigint::BigUint;
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        scalar::{Scalar, ScalarResult},
+    },
+    fabric::ResultValue,
+};
+
+use super::{
+    bits::{batch_bit_decompose, powers_of_two, MAX_DECOMPOSITION_BITS, STATISTICAL_SECURITY},
+    linear_combination,
+};
+
+/// Compute the quotient `floor(value / divisor)` of a shared value and a public divisor
+///
+/// The value is assumed to be in the range `[0, 2^D)`
+pub fn div_public<const D: usize>(
+    value: &AuthenticatedScalarResult,
+    divisor: u64,
+) -> AuthenticatedScalarResult {
+    div_rem_public::<D>(value, divisor).0
+}
+
+/// Compute the quotient and remainder of a shared value and a public divisor
+///
+/// The value is assumed to be in the range `[0, 2^D)`
+pub fn div_rem_public<const D: usize>(
+    value: &AuthenticatedScalarResult,
+    divisor: u64,
+) -> (AuthenticatedScalarResult, AuthenticatedScalarResult) {
+    let (mut quotients, mut remainders) =
+        batch_div_rem_public::<D>(std::slice::from_ref(value), divisor);
+    (quotients.pop().unwrap(), remainders.pop().unwrap())
+}
+
+/// Compute the quotients and remainders of a batch of shared values and a public divisor
+///
+/// All values are assumed to be in the range `[0, 2^D)`. Each quotient is first approximated
+/// by multiplying the value with `m = ceil(2^s / divisor)` and truncating the product by `s`
+/// bits, where the truncation opens the product masked by a random value built from shared
+/// bits. For `s = D + log(divisor)` the approximation is either the quotient or one more than
+/// it, which is corrected by checking the sign of the approximate remainder
+pub fn batch_div_rem_public<const D: usize>(
+    values: &[AuthenticatedScalarResult],
+    divisor: u64,
+) -> (
+    Vec<AuthenticatedScalarResult>,
+    Vec<AuthenticatedScalarResult>,
+) {
+    assert!(divisor > 0, "cannot divide by zero");
+    if values.is_empty() {
+        return (vec![], vec![]);
+    }
+
+    let divisor_bits = (u64::BITS - divisor.leading_zeros()) as usize;
+    let shift = D + divisor_bits;
+    let multiplier = ((BigUint::from(1u8) << shift) + divisor - 1u8) / divisor;
+    let product_bits = D + multiplier.bits() as usize;
+
+    let mask_len = shift.max(product_bits) + STATISTICAL_SECURITY;
+    assert!(
+        mask_len <= MAX_DECOMPOSITION_BITS + STATISTICAL_SECURITY,
+        "values of {D} bits are too large to divide by {divisor}"
+    );
+
+    let n = values.len();
+    let fabric = values[0].fabric();
+
+    // Sample a random mask for each product along with the mask's bits above the shift
+    let mask_bits = fabric.random_shared_bits(n * mask_len);
+    let coeffs = powers_of_two(mask_len);
+    let (masks, high_masks): (Vec<_>, Vec<_>) = mask_bits
+        .chunks(mask_len)
+        .map(|bits| {
+            let mask = linear_combination(bits, &coeffs);
+            let high_mask = linear_combination(&bits[shift..], &coeffs[..mask_len - shift]);
+            (mask, high_mask)
+        })
+        .unzip();
+
+    // Truncate the masked products, the carry out of the low bits of the mask makes the
+    // approximate quotient at most one larger than the quotient
+    let multiplier = Scalar::from_biguint(&multiplier);
+    let products = values.iter().map(|v| v * multiplier).collect_vec();
+    let masked = AuthenticatedScalarResult::batch_add(&products, &masks);
+    let truncated = AuthenticatedScalarResult::open_batch(&masked)
+        .iter()
+        .map(|val| {
+            fabric.new_gate_op(vec![val.id()], move |mut args| {
+                let val: Scalar = args.remove(0).into();
+                ResultValue::Scalar(Scalar::from_biguint(&(val.to_biguint() >> shift)))
+            })
+        })
+        .collect::<Vec<ScalarResult>>();
+
+    let approx_quotients = truncated
+        .iter()
+        .zip(high_masks.iter())
+        .map(|(trunc, high_mask)| trunc + &(-high_mask))
+        .collect_vec();
+    let approx_remainders = values
+        .iter()
+        .zip(approx_quotients.iter())
+        .map(|(value, quotient)| value - quotient * Scalar::from(divisor))
+        .collect_vec();
+
+    // The approximate remainder lies in `[-divisor, divisor)`, so after an offset of
+    // `2^divisor_bits` its top bit is set exactly when it is non-negative
+    let offset = powers_of_two(divisor_bits + 1)[divisor_bits];
+    let shifted = approx_remainders.iter().map(|r| r + offset).collect_vec();
+    let negative = batch_bit_decompose(&shifted, divisor_bits + 1)
+        .into_iter()
+        .map(|bits| Scalar::one() - &bits[divisor_bits])
+        .collect_vec();
+
+    let quotients = AuthenticatedScalarResult::batch_sub(&approx_quotients, &negative);
+    let remainders = approx_remainders
+        .iter()
+        .zip(negative.iter())
+        .map(|(remainder, neg)| remainder + neg * Scalar::from(divisor))
+        .collect_vec();
+
+    (quotients, remainders)
+}
+
+#[cfg(test)]
+mod test {
+    use futures::future::join_all;
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    use super::{batch_div_rem_public, div_public};
+
+    /// The bit length of the values divided in tests
+    const D: usize = 32;
+
+    /// Tests dividing a batch of values by public divisors
+    #[tokio::test]
+    async fn test_div_rem_public() {
+        let mut rng = thread_rng();
+        let mut values = (0..10).map(|_| rng.gen::<u32>() as u64).collect_vec();
+        values.extend([0, 1, 6, 7, u32::MAX as u64]);
+
+        for divisor in [1u64, 2, 7, 1000, rng.gen::<u32>() as u64 + 1, 1 << 40] {
+            let (res, _) = execute_mock_mpc(|fabric| {
+                let values = values.clone();
+                async move {
+                    let shared = fabric.batch_share_scalar(values, PARTY0);
+                    let (quotients, remainders) = batch_div_rem_public::<D>(&shared, divisor);
+
+                    let all = quotients.into_iter().chain(remainders).collect_vec();
+                    join_all(AuthenticatedScalarResult::open_authenticated_batch(&all))
+                        .await
+                        .into_iter()
+                        .collect::<Result<Vec<_>, _>>()
+                }
+            })
+            .await;
+
+            let expected = values
+                .iter()
+                .map(|v| v / divisor)
+                .chain(values.iter().map(|v| v % divisor))
+                .map(Scalar::from)
+                .collect_vec();
+            assert_eq!(res, Ok(expected), "divisor: {divisor}");
+        }
+    }
+
+    /// Tests computing only the quotient of a single value
+    #[tokio::test]
+    async fn test_div_public() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared = fabric.share_scalar(100u8, PARTY0);
+            div_public::<D>(&shared, 7 /* divisor */)
+                .open_authenticated()
+                .await
+        })
+        .await;
+
+        assert_eq!(res, Ok(Scalar::from(14u8)));
+    }
+}
@@ -0,0 +1,714 @@
+//! Defines a secret shared floating point type along with arithmetic and comparison gadgets
+//!
+//! A non-zero float is represented by shares of a mantissa `v` in `[2^{L-1}, 2^L)`, a signed
+//! exponent `p`, and a sign bit `s`, such that its value is `(1 - 2s) * v * 2^p` for
+//! `L = MANTISSA_BITS`. A fourth shared bit flags the float as zero, in which case the other
+//! three fields are zero as well
+//!
+//! The results of arithmetic are truncated towards zero. Exponents are assumed to stay in the
+//! range `[-2^{E-1}, 2^{E-1})` for `E = EXPONENT_BITS`, which is not checked
+
+use std::ops::{Add, Mul, Neg};
+
+use itertools::Itertools;
+use num_bigint::BigUint;
+
+use crate::{
+    algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+    error::MpcError,
+    network::PartyId,
+    MpcFabric,
+};
+
+use super::{
+    bits::{batch_bit_decompose, powers_of_two, AuthenticatedBit},
+    comparison::{batch_lt, batch_lt_public},
+    division::batch_div_rem_public,
+    linear_combination,
+    mux::batch_mux,
+};
+
+/// The number of bits in the mantissa of a float
+pub const MANTISSA_BITS: usize = 32;
+/// The number of bits that the signed exponent of a float is assumed to fit in
+pub const EXPONENT_BITS: usize = 16;
+
+/// The number of bits in the magnitude key of a float, see `magnitude_keys`
+const KEY_BITS: usize = EXPONENT_BITS + MANTISSA_BITS;
+/// The number of bits in the unnormalized mantissa of a sum, i.e. the larger mantissa shifted
+/// by at most `L + 1` bits plus the smaller mantissa
+const SUM_BITS: usize = 2 * MANTISSA_BITS + 2;
+/// The number of bits needed to represent the shift that aligns the mantissas of a sum, which
+/// is at most `L + 1`
+const ALIGN_BITS: usize = 6;
+
+/// A secret shared floating point value
+#[derive(Clone, Debug)]
+pub struct AuthenticatedFloat {
+    /// The mantissa, in `[2^{L-1}, 2^L)` for a non-zero value
+    pub mantissa: AuthenticatedScalarResult,
+    /// The signed exponent
+    pub exponent: AuthenticatedScalarResult,
+    /// The sign bit, set for a negative value
+    pub sign: AuthenticatedBit,
+    /// The zero flag, set for a zero value
+    pub zero: AuthenticatedBit,
+}
+
+impl AuthenticatedFloat {
+    /// Share a float with the counterparty
+    ///
+    /// As with the fabric's other sharing methods, the value passed by the receiving party is
+    /// ignored. The mantissa of the value is truncated to `MANTISSA_BITS` bits
+    pub fn share(value: f64, sender: PartyId, fabric: &MpcFabric) -> Self {
+        let fields = if fabric.party_id() == sender {
+            encode(value)
+        } else {
+            [Scalar::zero(); 4]
+        };
+
+        let mut shared = fabric
+            .batch_share_scalar(fields.to_vec(), sender)
+            .into_iter();
+        Self {
+            mantissa: shared.next().unwrap(),
+            exponent: shared.next().unwrap(),
+            sign: shared.next().unwrap(),
+            zero: shared.next().unwrap(),
+        }
+    }
+
+    /// Open the float and check the MACs of its fields
+    pub async fn open_authenticated(&self) -> Result<f64, MpcError> {
+        let fields = [
+            self.mantissa.clone(),
+            self.exponent.clone(),
+            self.sign.clone(),
+            self.zero.clone(),
+        ];
+
+        let mut opened = Vec::with_capacity(fields.len());
+        for field in AuthenticatedScalarResult::open_authenticated_batch(&fields) {
+            opened.push(field.await?);
+        }
+
+        Ok(decode(opened[0], opened[1], opened[2], opened[3]))
+    }
+
+    /// Compute a shared bit indicating whether `self < other`
+    pub fn lt(&self, other: &AuthenticatedFloat) -> AuthenticatedBit {
+        Self::batch_lt(std::slice::from_ref(self), std::slice::from_ref(other))
+            .pop()
+            .unwrap()
+    }
+
+    /// Add two batches of floats elementwise
+    ///
+    /// Each pair is ordered by magnitude, and the mantissa of the larger operand is shifted
+    /// left by the difference of the exponents so that the mantissas may be added or
+    /// subtracted as integers. The smaller operand is dropped if the difference exceeds
+    /// `L + 1`, as it cannot affect the truncated sum. The sum is then normalized by counting
+    /// its leading zeros from a bit decomposition, and truncated back to `L` bits
+    pub fn batch_add(
+        a: &[AuthenticatedFloat],
+        b: &[AuthenticatedFloat],
+    ) -> Vec<AuthenticatedFloat> {
+        assert_eq!(a.len(), b.len(), "floats must be of equal length");
+        if a.is_empty() {
+            return vec![];
+        }
+
+        let n = a.len();
+        let (a, b) = (FloatBatch::new(a), FloatBatch::new(b));
+
+        // Order each pair so that the first operand has the larger magnitude
+        let swaps = batch_lt::<KEY_BITS>(&a.magnitude_keys(), &b.magnitude_keys());
+        let selectors = tile(&swaps, 4);
+        let large = FloatBatch::unflatten(batch_mux(&selectors, &b.flatten(), &a.flatten()));
+        let small = FloatBatch::unflatten(batch_mux(&selectors, &a.flatten(), &b.flatten()));
+
+        // Drop the smaller operand if it is zero or too small to affect the sum, in which case
+        // the sum is the larger operand, aligned at its own exponent
+        let shifts = AuthenticatedScalarResult::batch_sub(&large.exponents, &small.exponents);
+        let max_shift = Scalar::from(MANTISSA_BITS as u64 + 2);
+        let too_small = batch_lt_public::<EXPONENT_BITS>(&shifts, &vec![max_shift; n])
+            .iter()
+            .map(|lt| Scalar::one() - lt)
+            .collect_vec();
+        let both = AuthenticatedScalarResult::batch_mul(&too_small, &small.zeros);
+        let skips = izip_map3(&too_small, &small.zeros, &both, |t, z, tz| t + z - tz);
+
+        let skipped = AuthenticatedScalarResult::batch_mul(
+            &tile(&skips, 2),
+            &[shifts.clone(), small.mantissas.clone()].concat(),
+        );
+        let shifts = AuthenticatedScalarResult::batch_sub(&shifts, &skipped[..n]);
+        let small_mantissas = AuthenticatedScalarResult::batch_sub(&small.mantissas, &skipped[n..]);
+        let base_exponents = AuthenticatedScalarResult::batch_add(&small.exponents, &skipped[..n]);
+
+        // Compute `2^shift` from the bits of each shift as the product of `2^{2^i}` over its set
+        // bits `i`
+        let shift_bits = batch_bit_decompose(&shifts, ALIGN_BITS);
+        let factor = |i: usize| {
+            let offset = Scalar::from((1u64 << (1 << i)) - 1);
+            shift_bits
+                .iter()
+                .map(|bits| &bits[i] * offset + Scalar::one())
+                .collect_vec()
+        };
+
+        let mut powers = factor(0);
+        for i in 1..ALIGN_BITS {
+            powers = AuthenticatedScalarResult::batch_mul(&powers, &factor(i));
+        }
+
+        // Add the aligned mantissas, or subtract the smaller if the signs differ
+        let sign_products = AuthenticatedScalarResult::batch_mul(&large.signs, &small.signs);
+        let differ = izip_map3(&large.signs, &small.signs, &sign_products, |a, b, ab| {
+            a + b - Scalar::from(2u8) * ab
+        });
+        let aligned = AuthenticatedScalarResult::batch_mul(&large.mantissas, &powers);
+        let subtracted = AuthenticatedScalarResult::batch_mul(&differ, &small_mantissas);
+        let sums = izip_map3(&aligned, &small_mantissas, &subtracted, |a, b, sub| {
+            a + b - Scalar::from(2u8) * sub
+        });
+
+        // Compute the prefix ORs `c_i` of the bits of each sum from its most significant bit
+        // down, so that `c_i` is set exactly when the sum's leading bit is at or above `i`
+        let bits = batch_bit_decompose(&sums, SUM_BITS);
+        let mut prefix_ors = vec![Vec::with_capacity(SUM_BITS); n];
+        let mut curr = bits.iter().map(|b| b[SUM_BITS - 1].clone()).collect_vec();
+        for i in (0..SUM_BITS - 1).rev() {
+            let bits_i = bits.iter().map(|b| b[i].clone()).collect_vec();
+            let products = AuthenticatedScalarResult::batch_mul(&curr, &bits_i);
+            for (ors, c) in prefix_ors.iter_mut().zip(curr.iter()) {
+                ors.push(c.clone());
+            }
+
+            curr = izip_map3(&curr, &bits_i, &products, |c, b, cb| c + b - cb);
+        }
+        for (ors, c) in prefix_ors.iter_mut().zip(curr.iter()) {
+            ors.push(c.clone());
+        }
+
+        // Shift each sum's leading bit to position `SUM_BITS - 1` by multiplying by `2^lz` for
+        // the count of leading zeros `lz = \sum_i (1 - c_i)`, which is linear in the `c_i` as
+        // `2^lz = 2^{SUM_BITS} - \sum_i c_i * 2^{SUM_BITS - 1 - i}`, the prefix ORs are stored
+        // from the most significant position down so that the coefficients are increasing
+        let top = powers_of_two(SUM_BITS + 1)[SUM_BITS];
+        let coeffs = powers_of_two(SUM_BITS);
+        let ones = vec![Scalar::one(); SUM_BITS];
+        let scales = prefix_ors
+            .iter()
+            .map(|ors| top - linear_combination(ors, &coeffs))
+            .collect_vec();
+        let n_significant = prefix_ors
+            .iter()
+            .map(|ors| linear_combination(ors, &ones))
+            .collect_vec();
+
+        let normalized = AuthenticatedScalarResult::batch_mul(&sums, &scales);
+        let (mantissas, _) =
+            batch_div_rem_public::<SUM_BITS>(&normalized, 1 << (MANTISSA_BITS + 2));
+
+        // The leading bit of the sum is at position `k - 1` for its number of significant bits
+        // `k`, so the sum is approximately `mantissa * 2^{k - L}`
+        let exponents = base_exponents
+            .iter()
+            .zip(n_significant.iter())
+            .map(|(p, k)| p + k - Scalar::from(MANTISSA_BITS as u64))
+            .collect_vec();
+        let nonzero = prefix_ors
+            .iter()
+            .map(|ors| ors[SUM_BITS - 1].clone())
+            .collect_vec();
+        let zeros = nonzero.iter().map(|nz| Scalar::one() - nz).collect_vec();
+
+        let cleared = AuthenticatedScalarResult::batch_mul(
+            &tile(&nonzero, 2),
+            &[exponents, large.signs].concat(),
+        );
+
+        FloatBatch {
+            mantissas,
+            exponents: cleared[..n].to_vec(),
+            signs: cleared[n..].to_vec(),
+            zeros,
+        }
+        .into_floats()
+    }
+
+    /// Multiply two batches of floats elementwise
+    ///
+    /// The product of two mantissas lies in `[2^{2L-2}, 2^{2L})`, so it is truncated by `L - 1`
+    /// bits and then by one more bit if it still has `L + 1` bits
+    pub fn batch_mul(
+        a: &[AuthenticatedFloat],
+        b: &[AuthenticatedFloat],
+    ) -> Vec<AuthenticatedFloat> {
+        assert_eq!(a.len(), b.len(), "floats must be of equal length");
+        if a.is_empty() {
+            return vec![];
+        }
+
+        let n = a.len();
+        let (a, b) = (FloatBatch::new(a), FloatBatch::new(b));
+
+        let products = AuthenticatedScalarResult::batch_mul(&a.mantissas, &b.mantissas);
+        let (truncated, _) =
+            batch_div_rem_public::<{ 2 * MANTISSA_BITS }>(&products, 1 << (MANTISSA_BITS - 1));
+        let (halved, _) = batch_div_rem_public::<{ MANTISSA_BITS + 1 }>(&truncated, 2);
+
+        let bound = Scalar::from(1u64 << MANTISSA_BITS);
+        let overflows = batch_lt_public::<{ MANTISSA_BITS + 1 }>(&truncated, &vec![bound; n])
+            .iter()
+            .map(|lt| Scalar::one() - lt)
+            .collect_vec();
+        let mantissas = batch_mux(&overflows, &halved, &truncated);
+
+        // The sign is the XOR of the signs, and the product is zero if either operand is
+        let sign_products = AuthenticatedScalarResult::batch_mul(&a.signs, &b.signs);
+        let zero_products = AuthenticatedScalarResult::batch_mul(&a.zeros, &b.zeros);
+        let signs = izip_map3(&a.signs, &b.signs, &sign_products, |a, b, ab| {
+            a + b - Scalar::from(2u8) * ab
+        });
+        let zeros = izip_map3(&a.zeros, &b.zeros, &zero_products, |a, b, ab| a + b - ab);
+        let exponent_offset = Scalar::from(MANTISSA_BITS as u64 - 1);
+        let exponents = izip_map3(&a.exponents, &b.exponents, &overflows, |a, b, of| {
+            a + b + of + exponent_offset
+        });
+
+        // Clear the exponent and sign of a zero product
+        let nonzero = zeros.iter().map(|z| Scalar::one() - z).collect_vec();
+        let cleared =
+            AuthenticatedScalarResult::batch_mul(&tile(&nonzero, 2), &[exponents, signs].concat());
+
+        FloatBatch {
+            mantissas,
+            exponents: cleared[..n].to_vec(),
+            signs: cleared[n..].to_vec(),
+            zeros,
+        }
+        .into_floats()
+    }
+
+    /// Compute shared bits indicating whether `a[i] < b[i]` for two batches of floats
+    ///
+    /// The magnitude keys of the floats are signed by the floats' signs, which orders them as
+    /// the floats themselves are ordered
+    pub fn batch_lt(a: &[AuthenticatedFloat], b: &[AuthenticatedFloat]) -> Vec<AuthenticatedBit> {
+        assert_eq!(a.len(), b.len(), "floats must be of equal length");
+        if a.is_empty() {
+            return vec![];
+        }
+
+        let (a, b) = (FloatBatch::new(a), FloatBatch::new(b));
+        let offset = powers_of_two(KEY_BITS + 1)[KEY_BITS];
+        let signed_keys = |batch: &FloatBatch| {
+            let signs = batch
+                .signs
+                .iter()
+                .map(|s| Scalar::one() - Scalar::from(2u8) * s)
+                .collect_vec();
+            AuthenticatedScalarResult::batch_mul(&batch.magnitude_keys(), &signs)
+                .iter()
+                .map(|key| key + offset)
+                .collect_vec()
+        };
+
+        batch_lt::<{ KEY_BITS + 1 }>(&signed_keys(&a), &signed_keys(&b))
+    }
+}
+
+impl Add<&AuthenticatedFloat> for &AuthenticatedFloat {
+    type Output = AuthenticatedFloat;
+
+    fn add(self, rhs: &AuthenticatedFloat) -> Self::Output {
+        AuthenticatedFloat::batch_add(std::slice::from_ref(self), std::slice::from_ref(rhs))
+            .pop()
+            .unwrap()
+    }
+}
+
+impl Mul<&AuthenticatedFloat> for &AuthenticatedFloat {
+    type Output = AuthenticatedFloat;
+
+    fn mul(self, rhs: &AuthenticatedFloat) -> Self::Output {
+        AuthenticatedFloat::batch_mul(std::slice::from_ref(self), std::slice::from_ref(rhs))
+            .pop()
+            .unwrap()
+    }
+}
+
+impl Neg for &AuthenticatedFloat {
+    type Output = AuthenticatedFloat;
+
+    fn neg(self) -> Self::Output {
+        // The sign of zero stays clear
+        AuthenticatedFloat {
+            mantissa: self.mantissa.clone(),
+            exponent: self.exponent.clone(),
+            sign: Scalar::one() - &self.sign - &self.zero,
+            zero: self.zero.clone(),
+        }
+    }
+}
+
+/// The fields of a batch of floats, stored field by field
+struct FloatBatch {
+    /// The mantissas of the floats
+    mantissas: Vec<AuthenticatedScalarResult>,
+    /// The exponents of the floats
+    exponents: Vec<AuthenticatedScalarResult>,
+    /// The sign bits of the floats
+    signs: Vec<AuthenticatedBit>,
+    /// The zero flags of the floats
+    zeros: Vec<AuthenticatedBit>,
+}
+
+impl FloatBatch {
+    /// Split a batch of floats into their fields
+    fn new(floats: &[AuthenticatedFloat]) -> Self {
+        Self {
+            mantissas: floats.iter().map(|f| f.mantissa.clone()).collect(),
+            exponents: floats.iter().map(|f| f.exponent.clone()).collect(),
+            signs: floats.iter().map(|f| f.sign.clone()).collect(),
+            zeros: floats.iter().map(|f| f.zero.clone()).collect(),
+        }
+    }
+
+    /// Concatenate the fields of the batch
+    fn flatten(&self) -> Vec<AuthenticatedScalarResult> {
+        [
+            self.mantissas.as_slice(),
+            &self.exponents,
+            &self.signs,
+            &self.zeros,
+        ]
+        .concat()
+    }
+
+    /// Split a concatenation of fields back into a batch
+    fn unflatten(mut fields: Vec<AuthenticatedScalarResult>) -> Self {
+        let n = fields.len() / 4;
+        let zeros = fields.split_off(3 * n);
+        let signs = fields.split_off(2 * n);
+        let exponents = fields.split_off(n);
+
+        Self {
+            mantissas: fields,
+            exponents,
+            signs,
+            zeros,
+        }
+    }
+
+    /// Reassemble the floats of the batch
+    fn into_floats(self) -> Vec<AuthenticatedFloat> {
+        itertools::izip!(self.mantissas, self.exponents, self.signs, self.zeros)
+            .map(|(mantissa, exponent, sign, zero)| AuthenticatedFloat {
+                mantissa,
+                exponent,
+                sign,
+                zero,
+            })
+            .collect()
+    }
+
+    /// Compute the magnitude keys `(1 - z) * ((p + 2^{E-1}) * 2^L + v)` of the floats, which
+    /// are in `[0, 2^{E + L})` and ordered as the floats' absolute values
+    fn magnitude_keys(&self) -> Vec<AuthenticatedScalarResult> {
+        let exponent_offset = powers_of_two(EXPONENT_BITS)[EXPONENT_BITS - 1];
+        let exponent_scale = powers_of_two(MANTISSA_BITS + 1)[MANTISSA_BITS];
+        let keys = self
+            .exponents
+            .iter()
+            .zip(self.mantissas.iter())
+            .map(|(p, v)| (p + exponent_offset) * exponent_scale + v)
+            .collect_vec();
+        let nonzero = self.zeros.iter().map(|z| Scalar::one() - z).collect_vec();
+
+        AuthenticatedScalarResult::batch_mul(&nonzero, &keys)
+    }
+}
+
+/// Concatenate a number of copies of a batch of values
+fn tile(values: &[AuthenticatedScalarResult], copies: usize) -> Vec<AuthenticatedScalarResult> {
+    (0..copies).flat_map(|_| values.iter().cloned()).collect()
+}
+
+/// Map three equal length slices elementwise
+fn izip_map3<F>(
+    a: &[AuthenticatedScalarResult],
+    b: &[AuthenticatedScalarResult],
+    c: &[AuthenticatedScalarResult],
+    f: F,
+) -> Vec<AuthenticatedScalarResult>
+where
+    F: Fn(
+        &AuthenticatedScalarResult,
+        &AuthenticatedScalarResult,
+        &AuthenticatedScalarResult,
+    ) -> AuthenticatedScalarResult,
+{
+    itertools::izip!(a, b, c)
+        .map(|(a, b, c)| f(a, b, c))
+        .collect()
+}
+
+/// Encode a finite float as its mantissa, exponent, sign bit, and zero flag
+fn encode(value: f64) -> [Scalar; 4] {
+    assert!(value.is_finite(), "cannot encode a non-finite float");
+    if value == 0.0 {
+        return [
+            Scalar::zero(),
+            Scalar::zero(),
+            Scalar::zero(),
+            Scalar::one(),
+        ];
+    }
+
+    // A normal float is `1.fraction * 2^{e - 1023}` for its biased exponent `e`, a subnormal
+    // float has no implicit leading bit and the minimum exponent
+    let bits = value.to_bits();
+    let biased_exponent = ((bits >> 52) & 0x7ff) as i64;
+    let fraction = bits & ((1 << 52) - 1);
+    let (mut mantissa, mut exponent) = if biased_exponent == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), biased_exponent - 1075)
+    };
+
+    // Normalize the mantissa to 53 bits, then truncate it to the mantissa width
+    let shift = mantissa.leading_zeros() as i64 - 11;
+    mantissa <<= shift;
+    exponent -= shift;
+    let truncation = 53 - MANTISSA_BITS as i64;
+
+    [
+        Scalar::from(mantissa >> truncation),
+        Scalar::from(exponent + truncation),
+        Scalar::from(bits >> 63),
+        Scalar::zero(),
+    ]
+}
+
+/// Decode a float from its opened mantissa, exponent, sign bit, and zero flag
+fn decode(mantissa: Scalar, exponent: Scalar, sign: Scalar, zero: Scalar) -> f64 {
+    if zero == Scalar::one() {
+        return 0.0;
+    }
+
+    let mantissa = u64::try_from(mantissa.to_biguint()).expect("mantissa exceeds 64 bits") as f64;
+    let exponent = scalar_to_i32(exponent);
+    let sign = if sign == Scalar::one() { -1.0 } else { 1.0 };
+
+    // Scale in two steps so that the intermediate power of two does not under or overflow
+    let half = exponent / 2;
+    sign * mantissa * 2f64.powi(half) * 2f64.powi(exponent - half)
+}
+
+/// Interpret a scalar as a signed integer, where values above half the modulus are negative
+fn scalar_to_i32(val: Scalar) -> i32 {
+    let magnitude = |val: Scalar| -> i32 {
+        let val: BigUint = val.to_biguint();
+        u32::try_from(val)
+            .ok()
+            .and_then(|val| i32::try_from(val).ok())
+            .expect("exponent out of range")
+    };
+
+    let negated = -val;
+    if negated.to_biguint() < val.to_biguint() {
+        -magnitude(negated)
+    } else {
+        magnitude(val)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+
+    use crate::{algebra::scalar::Scalar, test_helpers::execute_mock_mpc, PARTY0, PARTY1};
+
+    use super::{decode, encode, AuthenticatedFloat, MANTISSA_BITS};
+
+    /// The relative error allowed in the results of arithmetic, a few units in the last place
+    /// of the mantissa to account for truncation
+    const TOLERANCE: f64 = 8.0 / (1u64 << MANTISSA_BITS) as f64;
+
+    /// Assert that a result is within the tolerance of the expected value
+    fn assert_close(res: f64, expected: f64) {
+        assert!(
+            (res - expected).abs() <= expected.abs() * TOLERANCE,
+            "{res} != {expected}"
+        );
+    }
+
+    /// Share each pair of values in a batch of pairs, and open the result of an operation
+    async fn run_binary_op<F>(pairs: Vec<(f64, f64)>, op: F) -> Vec<f64>
+    where
+        F: Fn(&[AuthenticatedFloat], &[AuthenticatedFloat]) -> Vec<AuthenticatedFloat>
+            + Clone
+            + Send
+            + 'static,
+    {
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let pairs = pairs.clone();
+            let op = op.clone();
+            async move {
+                let (a, b): (Vec<_>, Vec<_>) = pairs
+                    .iter()
+                    .map(|(a, b)| {
+                        (
+                            AuthenticatedFloat::share(*a, PARTY0, &fabric),
+                            AuthenticatedFloat::share(*b, PARTY1, &fabric),
+                        )
+                    })
+                    .unzip();
+
+                let mut res = Vec::with_capacity(a.len());
+                for float in op(&a, &b) {
+                    res.push(float.open_authenticated().await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        res
+    }
+
+    /// Tests encoding and decoding floats
+    #[test]
+    fn test_encode_decode() {
+        let mut rng = thread_rng();
+        let mut values = (0..100)
+            .map(|_| rng.gen_range(-1e6..1e6) * 10f64.powi(rng.gen_range(-300..300)))
+            .collect_vec();
+        values.extend([0.0, 1.0, -1.0, 0.5, f64::MAX, f64::MIN_POSITIVE, 5e-324]);
+
+        for value in values {
+            let [mantissa, exponent, sign, zero] = encode(value);
+            assert_close(decode(mantissa, exponent, sign, zero), value);
+        }
+    }
+
+    /// Tests sharing and opening floats
+    #[tokio::test]
+    async fn test_share_open() {
+        let values = vec![0.0, 1.5, -3.25e10, 7e-200];
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let values = values.clone();
+            async move {
+                let mut res = Vec::with_capacity(values.len());
+                for value in values {
+                    let shared = AuthenticatedFloat::share(value, PARTY0, &fabric);
+                    res.push(shared.open_authenticated().await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        for (res, expected) in res.into_iter().zip(values) {
+            assert_close(res, expected);
+        }
+    }
+
+    /// Tests adding floats, including sums that cancel and operands of disparate magnitudes
+    #[tokio::test]
+    async fn test_add() {
+        let mut rng = thread_rng();
+        let mut pairs = (0..5)
+            .map(|_| (rng.gen_range(-1e3..1e3), rng.gen_range(-1e3..1e3)))
+            .collect_vec();
+        pairs.extend([
+            (1.0, 2.0),
+            (1.5, -1.5),
+            (0.0, -2.5),
+            (3.0, 0.0),
+            (0.0, 0.0),
+            (1e30, 1.0),
+            (-1.0, 1e-30),
+            (1.0, -0.999999),
+            (1.0, 1.0 / (1u64 << 33) as f64),
+        ]);
+
+        let res = run_binary_op(pairs.clone(), AuthenticatedFloat::batch_add).await;
+        for (res, (a, b)) in res.into_iter().zip(pairs) {
+            // Cancellation loses the precision of the operands, so compare to their magnitude
+            let expected = a + b;
+            let scale = a.abs().max(b.abs());
+            assert!(
+                (res - expected).abs() <= scale * TOLERANCE,
+                "{a} + {b}: {res} != {expected}"
+            );
+        }
+    }
+
+    /// Tests multiplying floats
+    #[tokio::test]
+    async fn test_mul() {
+        let mut rng = thread_rng();
+        let mut pairs = (0..5)
+            .map(|_| (rng.gen_range(-1e6..1e6), rng.gen_range(-1e-6..1e-6)))
+            .collect_vec();
+        pairs.extend([
+            (1.0, 1.0),
+            (-2.5, 4.0),
+            (0.0, 3.0),
+            (-3.0, 0.0),
+            (1e100, 1e-90),
+        ]);
+
+        let res = run_binary_op(pairs.clone(), AuthenticatedFloat::batch_mul).await;
+        for (res, (a, b)) in res.into_iter().zip(pairs) {
+            assert_close(res, a * b);
+        }
+    }
+
+    /// Tests comparing and negating floats
+    #[tokio::test]
+    async fn test_lt_neg() {
+        let pairs = vec![
+            (1.0, 2.0),
+            (2.0, 1.0),
+            (-1.0, 1.0),
+            (-1.0, -2.0),
+            (0.0, 1e-100),
+            (-1e-100, 0.0),
+            (0.0, 0.0),
+            (3.5, 3.5),
+            (1e10, 1e-10),
+        ];
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let pairs = pairs.clone();
+            async move {
+                let mut res = Vec::with_capacity(pairs.len());
+                for (a, b) in pairs {
+                    let a = AuthenticatedFloat::share(a, PARTY0, &fabric);
+                    let b = AuthenticatedFloat::share(b, PARTY1, &fabric);
+
+                    let lt = a.lt(&b).open_authenticated().await.unwrap();
+                    let neg_lt = (-&b).lt(&-&a).open_authenticated().await.unwrap();
+                    res.push((lt, neg_lt));
+                }
+
+                res
+            }
+        })
+        .await;
+
+        for ((lt, neg_lt), (a, b)) in res.into_iter().zip(pairs) {
+            let expected = Scalar::from(a < b);
+            assert_eq!(lt, expected, "{a} < {b}");
+            assert_eq!(neg_lt, expected, "-{b} < -{a}");
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Defines gadgets for evaluating public lookup tables at shared indices
+
+use itertools::Itertools;
+
+use crate::algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar};
+
+use super::{bits::AuthenticatedBit, mux::batch_mux};
+
+/// Evaluate a public table at a shared index given by its little-endian bits
+///
+/// Entries past the end of the table are treated as zero
+pub fn lookup_table(
+    table: &[Scalar],
+    index_bits: &[AuthenticatedBit],
+) -> AuthenticatedScalarResult {
+    batch_lookup_table(table, &[index_bits.to_vec()])
+        .pop()
+        .unwrap()
+}
+
+/// Evaluate a public table at a batch of shared indices given by their little-endian bits
+///
+/// The table is evaluated by a multiplexer tree. The first layer selects between public entries,
+/// which is linear in the lowest index bit and requires no communication. Each subsequent layer
+/// selects between shared values with one batched mux across all lookups, so a table of size
+/// `2^k` costs `2^{k-1} - 1` Beaver multiplications per lookup in `k - 1` rounds
+pub fn batch_lookup_table(
+    table: &[Scalar],
+    indices_bits: &[Vec<AuthenticatedBit>],
+) -> Vec<AuthenticatedScalarResult> {
+    if indices_bits.is_empty() {
+        return vec![];
+    }
+
+    let n_bits = indices_bits[0].len();
+    assert!(n_bits > 0, "index must have at least one bit");
+    assert!(
+        indices_bits.iter().all(|bits| bits.len() == n_bits),
+        "all indices must have the same number of bits"
+    );
+    assert!(
+        table.len() <= 1 << n_bits,
+        "table is too large for the number of index bits"
+    );
+
+    // Pad the table to a power of two
+    let mut table = table.to_vec();
+    table.resize(1 << n_bits, Scalar::zero());
+
+    // Select between the public entries using the lowest bit
+    let mut layers = indices_bits
+        .iter()
+        .map(|bits| {
+            table
+                .chunks_exact(2)
+                .map(|pair| &bits[0] * (pair[1] - pair[0]) + pair[0])
+                .collect_vec()
+        })
+        .collect_vec();
+
+    // Select between the shared values of each layer using the next bit
+    for k in 1..n_bits {
+        let mut selectors = Vec::new();
+        let mut if_true = Vec::new();
+        let mut if_false = Vec::new();
+        for (bits, layer) in indices_bits.iter().zip(layers.iter()) {
+            for pair in layer.chunks_exact(2) {
+                selectors.push(bits[k].clone());
+                if_false.push(pair[0].clone());
+                if_true.push(pair[1].clone());
+            }
+        }
+
+        let mut selected = batch_mux(&selectors, &if_true, &if_false);
+        for layer in layers.iter_mut() {
+            let layer_size = layer.len() / 2;
+            *layer = selected.drain(..layer_size).collect_vec();
+        }
+    }
+
+    layers
+        .into_iter()
+        .map(|mut layer| layer.pop().unwrap())
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        gadgets::lookup::batch_lookup_table,
+        test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    /// Tests evaluating a public table at shared indices
+    #[tokio::test]
+    async fn test_lookup_table() {
+        const N_BITS: usize = 3;
+        let mut rng = thread_rng();
+        let table = (0..7).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let indices = (0..4).map(|_| rng.gen_range(0..8u64)).collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let table = table.clone();
+            let indices = indices.clone();
+            async move {
+                let indices_bits = indices
+                    .into_iter()
+                    .map(|i| {
+                        let bits = (0..N_BITS).map(|k| (i >> k) & 1).collect_vec();
+                        fabric.batch_share_scalar(bits, PARTY0)
+                    })
+                    .collect_vec();
+                let results = batch_lookup_table(&table, &indices_bits);
+
+                let mut res = Vec::with_capacity(results.len());
+                for value in AuthenticatedScalarResult::open_authenticated_batch(&results) {
+                    res.push(value.await.unwrap());
+                }
+
+                res
+            }
+        })
+        .await;
+
+        let expected = indices
+            .iter()
+            .map(|i| table.get(*i as usize).cloned().unwrap_or(Scalar::zero()))
+            .collect_vec();
+        assert_eq!(res, expected);
+    }
+}
@@ -0,0 +1,77 @@
+//! Defines gadgets built on top of the MPC algebra, i.e. common sub-circuits that are
+//! composed from the fabric's authenticated arithmetic
+
+use itertools::Itertools;
+
+use crate::algebra::{
+    authenticated_scalar::{AuthenticatedScalarResult, AUTHENTICATED_SCALAR_RESULT_LEN},
+    scalar::{Scalar, ScalarResult},
+};
+use crate::fabric::ResultValue;
+
+pub mod array;
+pub mod bits;
+pub mod comparison;
+pub mod division;
+pub mod float;
+pub mod lookup;
+pub mod mux;
+pub mod pedersen;
+pub mod permutation;
+pub mod polynomial;
+pub mod range;
+pub mod residue;
+
+/// Compute the linear combination `\sum_i coeffs[i] * values[i]` of shared values with
+/// public coefficients in a single gate
+///
+/// Assumes that `values` is non-empty
+pub(crate) fn linear_combination(
+    values: &[AuthenticatedScalarResult],
+    coeffs: &[Scalar],
+) -> AuthenticatedScalarResult {
+    assert_eq!(
+        values.len(),
+        coeffs.len(),
+        "values and coefficients must be of equal length"
+    );
+
+    let fabric = values[0].fabric();
+    let coeffs = coeffs.to_vec();
+    let all_ids = values.iter().flat_map(|v| v.ids()).collect_vec();
+
+    let gate_results: Vec<ScalarResult> = fabric.new_batch_gate_op(
+        all_ids,
+        AUTHENTICATED_SCALAR_RESULT_LEN, /* output_arity */
+        move |args| {
+            let mut share = Scalar::zero();
+            let mut mac = Scalar::zero();
+            let mut modifier = Scalar::zero();
+
+            for (mut value, coeff) in args
+                .into_iter()
+                .chunks(AUTHENTICATED_SCALAR_RESULT_LEN)
+                .into_iter()
+                .zip(coeffs)
+            {
+                let value_share: Scalar = value.next().unwrap().into();
+                let value_mac: Scalar = value.next().unwrap().into();
+                let value_modifier: Scalar = value.next().unwrap().into();
+
+                share += coeff * value_share;
+                mac += coeff * value_mac;
+                modifier += coeff * value_modifier;
+            }
+
+            vec![
+                ResultValue::Scalar(share),
+                ResultValue::Scalar(mac),
+                ResultValue::Scalar(modifier),
+            ]
+        },
+    );
+
+    AuthenticatedScalarResult::from_flattened_iterator(gate_results.into_iter())
+        .pop()
+        .unwrap()
+}
@@ -0,0 +1,127 @@
+//! Defines multiplexer gadgets that select between shared values using shared bits
+
+use crate::algebra::authenticated_scalar::AuthenticatedScalarResult;
+
+use super::bits::AuthenticatedBit;
+
+/// Select `if_true` when the shared `selector` bit is one, and `if_false` otherwise
+pub fn mux(
+    selector: &AuthenticatedBit,
+    if_true: &AuthenticatedScalarResult,
+    if_false: &AuthenticatedScalarResult,
+) -> AuthenticatedScalarResult {
+    if_false + selector * (if_true - if_false)
+}
+
+/// Select between a batch of values using a batch of shared selector bits
+///
+/// Computes `if_false[i] + selectors[i] * (if_true[i] - if_false[i])`, which requires a single
+/// batched Beaver multiplication for the whole batch
+pub fn batch_mux(
+    selectors: &[AuthenticatedBit],
+    if_true: &[AuthenticatedScalarResult],
+    if_false: &[AuthenticatedScalarResult],
+) -> Vec<AuthenticatedScalarResult> {
+    assert_eq!(
+        selectors.len(),
+        if_true.len(),
+        "selectors and values must be of equal length"
+    );
+    assert_eq!(
+        if_true.len(),
+        if_false.len(),
+        "values must be of equal length"
+    );
+    if selectors.is_empty() {
+        return vec![];
+    }
+
+    let diffs = AuthenticatedScalarResult::batch_sub(if_true, if_false);
+    let selected_diffs = AuthenticatedScalarResult::batch_mul(selectors, &diffs);
+    AuthenticatedScalarResult::batch_add(if_false, &selected_diffs)
+}
+
+/// Swap `a` and `b` when the shared `swap` bit is one, returning the pair in order otherwise
+///
+/// The swap takes a single multiplication, computing `d = swap * (b - a)` and returning
+/// `(a + d, b - d)`. With the result of a comparison as the bit, this is the compare-exchange
+/// step of sorting networks
+pub fn cond_swap(
+    swap: &AuthenticatedBit,
+    a: &AuthenticatedScalarResult,
+    b: &AuthenticatedScalarResult,
+) -> (AuthenticatedScalarResult, AuthenticatedScalarResult) {
+    let (mut first, mut second) = batch_cond_swap(
+        std::slice::from_ref(swap),
+        std::slice::from_ref(a),
+        std::slice::from_ref(b),
+    );
+
+    (first.pop().unwrap(), second.pop().unwrap())
+}
+
+/// Conditionally swap a batch of pairs using a batch of shared bits
+///
+/// Returns the first and second elements of the pairs as separate batches, the swaps require a
+/// single batched Beaver multiplication for the whole batch
+pub fn batch_cond_swap(
+    swaps: &[AuthenticatedBit],
+    a: &[AuthenticatedScalarResult],
+    b: &[AuthenticatedScalarResult],
+) -> (
+    Vec<AuthenticatedScalarResult>,
+    Vec<AuthenticatedScalarResult>,
+) {
+    assert_eq!(
+        swaps.len(),
+        a.len(),
+        "swap bits and values must be of equal length"
+    );
+    assert_eq!(a.len(), b.len(), "values must be of equal length");
+    if swaps.is_empty() {
+        return (vec![], vec![]);
+    }
+
+    let diffs = AuthenticatedScalarResult::batch_sub(b, a);
+    let swapped_diffs = AuthenticatedScalarResult::batch_mul(swaps, &diffs);
+
+    (
+        AuthenticatedScalarResult::batch_add(a, &swapped_diffs),
+        AuthenticatedScalarResult::batch_sub(b, &swapped_diffs),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use futures::future::join_all;
+    use itertools::Itertools;
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        test_helpers::execute_mock_mpc,
+        PARTY0, PARTY1,
+    };
+
+    use super::batch_cond_swap;
+
+    /// Tests conditionally swapping a batch of pairs
+    #[tokio::test]
+    async fn test_cond_swap() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let swaps = fabric.batch_share_scalar(vec![0u8, 1, 1, 0], PARTY0);
+            let a = fabric.batch_share_scalar(vec![1u8, 2, 3, 4], PARTY1);
+            let b = fabric.batch_share_scalar(vec![5u8, 6, 7, 8], PARTY0);
+
+            let (first, second) = batch_cond_swap(&swaps, &a, &b);
+            let pairs = first.into_iter().chain(second).collect_vec();
+            join_all(AuthenticatedScalarResult::open_authenticated_batch(&pairs))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await;
+
+        let expected = [1u8, 6, 7, 4, 5, 2, 3, 8].map(Scalar::from).to_vec();
+        assert_eq!(res, Ok(expected));
+    }
+}
@@ -0,0 +1,211 @@
+//! Defines the Starknet Pedersen hash over shared inputs
+//!
+//! The Starknet Pedersen hash of two field elements `a` and `b` is the x coordinate of
+//!     `P0 + a_low * P1 + a_high * P2 + b_low * P3 + b_high * P4`
+//! where `a_low` is the low 248 bits of `a` and `a_high` the remaining high bits. See
+//! https://docs.starkware.co/starkex/crypto/pedersen-hash-function.html
+//!
+//! The point is a linear function of the inputs, so it is computed over shared inputs with
+//! a single MSM and no communication. The x coordinate is not, so the point is opened and the
+//! hash is read from the opened point with `pedersen_hash_output`
+
+use ark_ec::short_weierstrass::Affine;
+use ark_ff::{MontFp, PrimeField};
+use num_bigint::BigUint;
+
+use crate::algebra::{
+    authenticated_scalar::AuthenticatedScalarResult,
+    authenticated_stark_point::AuthenticatedStarkPointResult,
+    scalar::Scalar,
+    stark_curve::{StarkPoint, StarkPointInner, StarknetCurveConfig},
+};
+
+/// The number of low bits of each input that are multiplied by the first point of the input's
+/// pair of generators
+pub const PEDERSEN_LOW_BITS: usize = 248;
+
+/// The constant points of the Starknet Pedersen hash, `P0` through `P4`
+const PEDERSEN_POINTS: [Affine<StarknetCurveConfig>; 5] = [
+    Affine {
+        x: MontFp!("2089986280348253421170679821480865132823066470938446095505822317253594081284"),
+        y: MontFp!("1713931329540660377023406109199410414810705867260802078187082345529207694986"),
+        infinity: false,
+    },
+    Affine {
+        x: MontFp!("996781205833008774514500082376783249102396023663454813447423147977397232763"),
+        y: MontFp!("1668503676786377725805489344771023921079126552019160156920634619255970485781"),
+        infinity: false,
+    },
+    Affine {
+        x: MontFp!("2251563274489750535117886426533222435294046428347329203627021249169616184184"),
+        y: MontFp!("1798716007562728905295480679789526322175868328062420237419143593021674992973"),
+        infinity: false,
+    },
+    Affine {
+        x: MontFp!("2138414695194151160943305727036575959195309218611738193261179310511854807447"),
+        y: MontFp!("113410276730064486255102093846540133784865286929052426931474106396135072156"),
+        infinity: false,
+    },
+    Affine {
+        x: MontFp!("2379962749567351885752724891227938183011949129833673362440656643086021394946"),
+        y: MontFp!("776496453633298175483985398648758586525933812536653089401905292063708816422"),
+        infinity: false,
+    },
+];
+
+/// Get the `i`th constant point of the hash
+fn pedersen_point(i: usize) -> StarkPoint {
+    StarkPoint::from(StarkPointInner::from(PEDERSEN_POINTS[i]))
+}
+
+/// Compute the Starknet Pedersen hash point of two shared inputs
+///
+/// The inputs must be less than `2^PEDERSEN_LOW_BITS` so that their high parts are zero, as is
+/// the case for values composed from at most `PEDERSEN_LOW_BITS` shared bits. Use
+/// `pedersen_hash_limbs` to hash larger inputs whose limbs are held separately
+pub fn pedersen_hash(
+    a: &AuthenticatedScalarResult,
+    b: &AuthenticatedScalarResult,
+) -> AuthenticatedStarkPointResult {
+    StarkPoint::msm_authenticated(
+        &[a.clone(), b.clone()],
+        &[pedersen_point(1), pedersen_point(3)],
+    ) + pedersen_point(0)
+}
+
+/// Compute the Starknet Pedersen hash point of two shared inputs given as limbs
+///
+/// Each input is given as its low `PEDERSEN_LOW_BITS` bits and its remaining high bits
+pub fn pedersen_hash_limbs(
+    a_low: &AuthenticatedScalarResult,
+    a_high: &AuthenticatedScalarResult,
+    b_low: &AuthenticatedScalarResult,
+    b_high: &AuthenticatedScalarResult,
+) -> AuthenticatedStarkPointResult {
+    StarkPoint::msm_authenticated(
+        &[a_low.clone(), a_high.clone(), b_low.clone(), b_high.clone()],
+        &[
+            pedersen_point(1),
+            pedersen_point(2),
+            pedersen_point(3),
+            pedersen_point(4),
+        ],
+    ) + pedersen_point(0)
+}
+
+/// Read the hash from an opened hash point, i.e. its x coordinate
+pub fn pedersen_hash_output(point: &StarkPoint) -> BigUint {
+    point.to_affine().x.into_bigint().into()
+}
+
+/// Compute the Starknet Pedersen hash of two plaintext inputs
+pub fn pedersen_hash_plaintext(a: Scalar, b: Scalar) -> BigUint {
+    /// Split a value into its low and high limbs
+    fn split(value: Scalar) -> (Scalar, Scalar) {
+        let value = value.to_biguint();
+        let low = &value % (BigUint::from(1u8) << PEDERSEN_LOW_BITS);
+        let high = &value >> PEDERSEN_LOW_BITS;
+        (Scalar::from_biguint(&low), Scalar::from_biguint(&high))
+    }
+
+    let (a_low, a_high) = split(a);
+    let (b_low, b_high) = split(b);
+    let point = pedersen_point(0)
+        + StarkPoint::msm(
+            &[a_low, a_high, b_low, b_high],
+            &[
+                pedersen_point(1),
+                pedersen_point(2),
+                pedersen_point(3),
+                pedersen_point(4),
+            ],
+        );
+
+    pedersen_hash_output(&point)
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+    use rand::{thread_rng, RngCore};
+
+    use crate::{
+        algebra::scalar::Scalar,
+        gadgets::pedersen::{
+            pedersen_hash, pedersen_hash_limbs, pedersen_hash_output, pedersen_hash_plaintext,
+            PEDERSEN_LOW_BITS,
+        },
+        test_helpers::execute_mock_mpc,
+        PARTY0, PARTY1,
+    };
+
+    /// Parse a hex string into a `BigUint`
+    fn from_hex(hex: &str) -> BigUint {
+        BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()
+    }
+
+    /// Tests the plaintext hash against a test vector from Starknet
+    #[test]
+    fn test_pedersen_hash_vector() {
+        let a = from_hex("03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb");
+        let b = from_hex("0208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a");
+        let expected = from_hex("030e480bed5fe53fa909cc0f8c4d99b8f9f2c016be4c41e13a4848797979c662");
+
+        let res = pedersen_hash_plaintext(Scalar::from_biguint(&a), Scalar::from_biguint(&b));
+        assert_eq!(res, expected);
+    }
+
+    /// Tests hashing shared inputs less than `2^PEDERSEN_LOW_BITS`
+    #[tokio::test]
+    async fn test_pedersen_hash() {
+        let mut rng = thread_rng();
+        let mut random_input = || {
+            let mut bytes = [0u8; PEDERSEN_LOW_BITS / 8];
+            rng.fill_bytes(&mut bytes);
+            Scalar::from_be_bytes_mod_order(&bytes)
+        };
+        let a = random_input();
+        let b = random_input();
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared_a = fabric.share_scalar(a, PARTY0);
+            let shared_b = fabric.share_scalar(b, PARTY1);
+            pedersen_hash(&shared_a, &shared_b)
+                .open_authenticated()
+                .await
+        })
+        .await;
+
+        assert_eq!(
+            pedersen_hash_output(&res.unwrap()),
+            pedersen_hash_plaintext(a, b)
+        );
+    }
+
+    /// Tests hashing shared inputs given as limbs against a test vector from Starknet
+    #[tokio::test]
+    async fn test_pedersen_hash_limbs() {
+        let a = from_hex("03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb");
+        let b = from_hex("0208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a");
+        let expected = from_hex("030e480bed5fe53fa909cc0f8c4d99b8f9f2c016be4c41e13a4848797979c662");
+
+        let mask = (BigUint::from(1u8) << PEDERSEN_LOW_BITS) - 1u8;
+        let limbs = [
+            &a & &mask,
+            &a >> PEDERSEN_LOW_BITS,
+            &b & &mask,
+            &b >> PEDERSEN_LOW_BITS,
+        ]
+        .map(|limb| Scalar::from_biguint(&limb));
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared = fabric.batch_share_scalar(limbs.to_vec(), PARTY0);
+            pedersen_hash_limbs(&shared[0], &shared[1], &shared[2], &shared[3])
+                .open_authenticated()
+                .await
+        })
+        .await;
+
+        assert_eq!(pedersen_hash_output(&res.unwrap()), expected);
+    }
+}
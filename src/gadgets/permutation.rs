@@ -0,0 +1,503 @@
+//! Defines gadgets for applying secret shared permutations to vectors of shared values
+//!
+//! A permutation is held as the shared control bits of the switches of a Benes network, a
+//! network of `2 log(n) - 1` layers of `n / 2` two-input switches that can realize any
+//! permutation of `n = 2^k` wires. Each switch either passes its inputs through or crosses
+//! them, so evaluating a layer takes a single batched conditional swap
+//!
+//! The party that knows a permutation routes it through the network with the looping
+//! algorithm. The routing always leaves the first output switch of every subnetwork straight,
+//! as in a Waksman network, so that these switches need not be shared
+
+use itertools::Itertools;
+
+use crate::{
+    algebra::authenticated_scalar::AuthenticatedScalarResult, network::PartyId, MpcFabric,
+};
+
+use super::{bits::AuthenticatedBit, mux::batch_cond_swap};
+
+/// The number of switch layers in a Benes network on `n_wires` wires
+pub fn n_layers(n_wires: usize) -> usize {
+    assert!(
+        n_wires.is_power_of_two(),
+        "a Benes network has a power of two wires"
+    );
+
+    let log_n = n_wires.trailing_zeros() as usize;
+    (2 * log_n).saturating_sub(1)
+}
+
+/// The wiring of a switch, i.e. the wires its two inputs are read from and the wires its two
+/// outputs are written to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Switch {
+    /// The wires of the top and bottom inputs
+    pub inputs: (usize, usize),
+    /// The wires of the top and bottom outputs
+    pub outputs: (usize, usize),
+}
+
+/// The wiring of the switches in a layer of a Benes network on `n_wires` wires
+///
+/// The network on `n` wires is an input layer that sends the top output of each switch to an
+/// upper subnetwork on `n / 2` wires and the bottom output to a lower subnetwork, followed by
+/// the two subnetworks and an output layer that recombines them. The subnetworks at each level
+/// of this recursion are laid out side by side, so that their layers coincide
+pub(crate) fn layer_wiring(n_wires: usize, layer: usize) -> Vec<Switch> {
+    let n_layers = n_layers(n_wires);
+    assert!(layer < n_layers, "layer out of range");
+
+    let log_n = n_wires.trailing_zeros() as usize;
+    let middle = log_n - 1;
+    (0..n_wires / 2)
+        .map(|j| {
+            if layer == middle {
+                return Switch {
+                    inputs: (2 * j, 2 * j + 1),
+                    outputs: (2 * j, 2 * j + 1),
+                };
+            }
+
+            // The input and output layers at a recursion level are mirror images
+            let level = if layer < middle {
+                layer
+            } else {
+                n_layers - 1 - layer
+            };
+            let half = n_wires >> (level + 1);
+            let base = (j / half) * 2 * half;
+            let i = j % half;
+
+            let interleaved = (base + 2 * i, base + 2 * i + 1);
+            let split = (base + i, base + half + i);
+            if layer < middle {
+                Switch {
+                    inputs: interleaved,
+                    outputs: split,
+                }
+            } else {
+                Switch {
+                    inputs: split,
+                    outputs: interleaved,
+                }
+            }
+        })
+        .collect_vec()
+}
+
+/// Whether a switch of a Benes network on `n_wires` wires is left straight by the routing,
+/// i.e. whether it is the first switch of an output layer in its subnetwork
+pub(crate) fn is_fixed_switch(n_wires: usize, layer: usize, switch: usize) -> bool {
+    let log_n = n_wires.trailing_zeros() as usize;
+    let middle = log_n.saturating_sub(1);
+    if layer <= middle {
+        return false;
+    }
+
+    let level = n_layers(n_wires) - 1 - layer;
+    let half = n_wires >> (level + 1);
+    switch & (half - 1) == 0
+}
+
+/// Compute the control bits of a Benes network that realizes a permutation, such that the
+/// network's output `i` is its input `permutation[i]`
+///
+/// Permutations whose length is not a power of two are extended by fixing the remaining wires,
+/// so the network has `n.next_power_of_two()` wires
+pub fn route_permutation(permutation: &[usize]) -> Vec<Vec<bool>> {
+    let n = permutation.len();
+    assert!(
+        permutation.iter().sorted().copied().eq(0..n),
+        "not a permutation of 0..{n}"
+    );
+
+    let n_wires = n.next_power_of_two();
+    let padded = permutation.iter().copied().chain(n..n_wires).collect_vec();
+    let mut switches = vec![vec![false; n_wires / 2]; n_layers(n_wires)];
+    route_subnetwork(
+        &padded,
+        0, /* level */
+        0, /* block */
+        &mut switches,
+    );
+
+    switches
+}
+
+/// Route a permutation through the subnetwork at the given level and block of a Benes network
+///
+/// The looping algorithm assigns each input of the subnetwork to its upper or lower half such
+/// that the two inputs of each input switch, and the two outputs of each output switch, are
+/// routed through different halves. The assignment follows cycles that alternate between
+/// sibling inputs and sibling outputs, starting each cycle from a straight output switch
+fn route_subnetwork(permutation: &[usize], level: usize, block: usize, switches: &mut [Vec<bool>]) {
+    let n = permutation.len();
+    if n == 1 {
+        return;
+    }
+
+    let n_layers = switches.len();
+    if n == 2 {
+        switches[level][block] = permutation[0] == 1;
+        return;
+    }
+
+    let mut inverse = vec![0; n];
+    for (output, input) in permutation.iter().enumerate() {
+        inverse[*input] = output;
+    }
+
+    // The half that each output is routed through, zero for the upper half
+    let mut output_halves: Vec<Option<usize>> = vec![None; n];
+    let mut input_halves = vec![0; n];
+    for start in (0..n).step_by(2) {
+        let mut output = start;
+        while output_halves[output].is_none() {
+            output_halves[output] = Some(0);
+            output_halves[output ^ 1] = Some(1);
+
+            // The sibling output is routed through the lower half, and so is its input, which
+            // forces the input's sibling through the upper half along with its output
+            let input = permutation[output ^ 1];
+            input_halves[input] = 1;
+            input_halves[input ^ 1] = 0;
+            output = inverse[input ^ 1];
+        }
+    }
+
+    let half = n / 2;
+    let mut upper = vec![0; half];
+    let mut lower = vec![0; half];
+    for i in 0..half {
+        switches[level][block * half + i] = input_halves[2 * i] == 1;
+        switches[n_layers - 1 - level][block * half + i] = output_halves[2 * i] == Some(1);
+
+        let (to_upper, to_lower) = if output_halves[2 * i] == Some(0) {
+            (2 * i, 2 * i + 1)
+        } else {
+            (2 * i + 1, 2 * i)
+        };
+        upper[i] = permutation[to_upper] / 2;
+        lower[i] = permutation[to_lower] / 2;
+    }
+
+    route_subnetwork(&upper, level + 1, 2 * block, switches);
+    route_subnetwork(&lower, level + 1, 2 * block + 1, switches);
+}
+
+/// A permutation of `n` elements, held as the shared control bits of a Benes network on
+/// `n.next_power_of_two()` wires
+#[derive(Clone, Debug)]
+pub struct SharedPermutation {
+    /// The number of elements permuted
+    len: usize,
+    /// The number of wires in the network
+    n_wires: usize,
+    /// The control bits of the switches, by layer, a set bit crosses its switch
+    switches: Vec<Vec<AuthenticatedBit>>,
+}
+
+impl SharedPermutation {
+    /// Construct a permutation from the shared control bits of a Benes network on `n_wires`
+    /// wires, given layer by layer
+    ///
+    /// The bits are assumed to be zero or one, which is not checked
+    pub fn new(n_wires: usize, switches: Vec<Vec<AuthenticatedBit>>) -> Self {
+        assert_eq!(
+            switches.len(),
+            n_layers(n_wires),
+            "wrong number of switch layers"
+        );
+        assert!(
+            switches.iter().all(|layer| layer.len() == n_wires / 2),
+            "each layer must have one switch per pair of wires"
+        );
+
+        Self {
+            len: n_wires,
+            n_wires,
+            switches,
+        }
+    }
+
+    /// Share a permutation held by the sender, such that applying the shared permutation to
+    /// a vector moves element `permutation[i]` to position `i`
+    ///
+    /// The sender routes the permutation through a Benes network and shares the control bits
+    /// of its switches in a single batch, skipping the switches that the routing leaves
+    /// straight. As with the fabric's other sharing methods, the receiving party passes a
+    /// permutation of the same length whose value is ignored. The control bits are not checked
+    /// to be binary
+    pub fn share(permutation: &[usize], sender: PartyId, fabric: &MpcFabric) -> Self {
+        let len = permutation.len();
+        assert!(len > 0, "cannot share an empty permutation");
+
+        let n_wires = len.next_power_of_two();
+        let switches = if fabric.party_id() == sender {
+            route_permutation(permutation)
+        } else {
+            vec![vec![false; n_wires / 2]; n_layers(n_wires)]
+        };
+
+        let free_bits = switches
+            .iter()
+            .enumerate()
+            .flat_map(|(layer, bits)| {
+                bits.iter()
+                    .enumerate()
+                    .filter(move |(switch, _)| !is_fixed_switch(n_wires, layer, *switch))
+                    .map(|(_, bit)| u8::from(*bit))
+            })
+            .collect_vec();
+        let mut shared = if free_bits.is_empty() {
+            Vec::new()
+        } else {
+            fabric.batch_share_scalar(free_bits, sender)
+        }
+        .into_iter();
+
+        let switches = (0..switches.len())
+            .map(|layer| {
+                (0..n_wires / 2)
+                    .map(|switch| {
+                        if is_fixed_switch(n_wires, layer, switch) {
+                            fabric.zero_authenticated()
+                        } else {
+                            shared.next().unwrap()
+                        }
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        Self {
+            len,
+            n_wires,
+            switches,
+        }
+    }
+
+    /// The number of elements the permutation acts on
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the permutation acts on no elements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The shared control bits of the network's switches, by layer
+    pub fn switches(&self) -> &[Vec<AuthenticatedBit>] {
+        &self.switches
+    }
+
+    /// Apply the permutation to a vector of shared values
+    ///
+    /// Each layer of the network is evaluated with a single batched conditional swap, so the
+    /// permutation takes `2 log(n) - 1` rounds of multiplications
+    pub fn apply(&self, values: &[AuthenticatedScalarResult]) -> Vec<AuthenticatedScalarResult> {
+        assert_eq!(
+            values.len(),
+            self.len,
+            "permutation and values must be of equal length"
+        );
+
+        // The wires beyond the permutation's length are routed to themselves
+        let mut wires = values.to_vec();
+        if self.n_wires > self.len {
+            let fabric = values[0].fabric();
+            wires.extend(fabric.zeros_authenticated(self.n_wires - self.len));
+        }
+
+        for (layer, bits) in self.switches.iter().enumerate() {
+            let wiring = layer_wiring(self.n_wires, layer);
+            let tops = wiring
+                .iter()
+                .map(|s| wires[s.inputs.0].clone())
+                .collect_vec();
+            let bottoms = wiring
+                .iter()
+                .map(|s| wires[s.inputs.1].clone())
+                .collect_vec();
+
+            let (tops, bottoms) = batch_cond_swap(bits, &tops, &bottoms);
+            for (switch, (top, bottom)) in wiring.iter().zip(tops.into_iter().zip(bottoms)) {
+                wires[switch.outputs.0] = top;
+                wires[switch.outputs.1] = bottom;
+            }
+        }
+
+        wires.truncate(self.len);
+        wires
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::future::join_all;
+    use itertools::Itertools;
+    use rand::{seq::SliceRandom, thread_rng, Rng};
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        test_helpers::execute_mock_mpc,
+        PARTY0, PARTY1,
+    };
+
+    use super::{is_fixed_switch, layer_wiring, n_layers, route_permutation, SharedPermutation};
+
+    /// Evaluate a Benes network on plaintext values
+    fn apply_plaintext(values: &[u64], switches: &[Vec<bool>]) -> Vec<u64> {
+        let mut wires = values.to_vec();
+        for (layer, bits) in switches.iter().enumerate() {
+            let prev = wires.clone();
+            for (switch, cross) in layer_wiring(values.len(), layer).iter().zip(bits) {
+                let (mut top, mut bottom) = (prev[switch.inputs.0], prev[switch.inputs.1]);
+                if *cross {
+                    std::mem::swap(&mut top, &mut bottom);
+                }
+
+                wires[switch.outputs.0] = top;
+                wires[switch.outputs.1] = bottom;
+            }
+        }
+
+        wires
+    }
+
+    /// Sample a random permutation of `0..n`
+    fn random_permutation(n: usize) -> Vec<usize> {
+        let mut permutation = (0..n).collect_vec();
+        permutation.shuffle(&mut thread_rng());
+        permutation
+    }
+
+    /// Tests that every layer of the network routes each wire exactly once
+    #[test]
+    fn test_layer_wiring() {
+        for n_wires in [2, 4, 8, 16, 64] {
+            for layer in 0..n_layers(n_wires) {
+                let wiring = layer_wiring(n_wires, layer);
+                let inputs = wiring
+                    .iter()
+                    .flat_map(|s| [s.inputs.0, s.inputs.1])
+                    .sorted()
+                    .collect_vec();
+                let outputs = wiring
+                    .iter()
+                    .flat_map(|s| [s.outputs.0, s.outputs.1])
+                    .sorted()
+                    .collect_vec();
+
+                assert_eq!(inputs, (0..n_wires).collect_vec());
+                assert_eq!(outputs, (0..n_wires).collect_vec());
+            }
+        }
+
+        assert_eq!(n_layers(1), 0);
+        assert_eq!(n_layers(2), 1);
+        assert_eq!(n_layers(8), 5);
+    }
+
+    /// Tests applying a permutation given by random shared control bits
+    #[tokio::test]
+    async fn test_apply_permutation() {
+        const N: usize = 16;
+        let mut rng = thread_rng();
+        let values = (0..N as u64).map(|i| i * 10).collect_vec();
+        let switches = (0..n_layers(N))
+            .map(|_| (0..N / 2).map(|_| rng.gen_bool(0.5)).collect_vec())
+            .collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let (values, switches) = (values.clone(), switches.clone());
+            async move {
+                let values = fabric.batch_share_scalar(values, PARTY1);
+                let switches = switches
+                    .into_iter()
+                    .map(|layer| {
+                        fabric.batch_share_scalar(layer.into_iter().map(u8::from).collect(), PARTY0)
+                    })
+                    .collect_vec();
+
+                let permutation = SharedPermutation::new(N, switches);
+                let permuted = permutation.apply(&values);
+                join_all(AuthenticatedScalarResult::open_authenticated_batch(
+                    &permuted,
+                ))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+            }
+        })
+        .await;
+
+        let expected = apply_plaintext(&values, &switches);
+        assert_eq!(
+            expected.iter().sorted().collect_vec(),
+            values.iter().collect_vec()
+        );
+        assert_eq!(
+            res,
+            Ok(expected.into_iter().map(Scalar::from).collect_vec())
+        );
+    }
+
+    /// Tests that routed control bits realize their permutations and leave the fixed switches
+    /// straight
+    #[test]
+    fn test_route_permutation() {
+        for n in [1, 2, 3, 4, 7, 8, 16, 33, 64] {
+            for _ in 0..10 {
+                let permutation = random_permutation(n);
+                let switches = route_permutation(&permutation);
+
+                let n_wires = n.next_power_of_two();
+                let inputs = (0..n_wires as u64).collect_vec();
+                let outputs = apply_plaintext(&inputs, &switches);
+                let expected = permutation.iter().map(|i| *i as u64).collect_vec();
+                assert_eq!(outputs[..n], expected);
+
+                for (layer, bits) in switches.iter().enumerate() {
+                    for (switch, bit) in bits.iter().enumerate() {
+                        assert!(!(is_fixed_switch(n_wires, layer, switch) && *bit));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tests sharing a permutation and applying it to a vector whose length is not a power
+    /// of two
+    #[tokio::test]
+    async fn test_share_permutation() {
+        const N: usize = 11;
+        let permutation = random_permutation(N);
+        let values = (0..N as u64).map(|i| i * i).collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let (permutation, values) = (permutation.clone(), values.clone());
+            async move {
+                let values = fabric.batch_share_scalar(values, PARTY1);
+                let permutation = SharedPermutation::share(&permutation, PARTY0, &fabric);
+
+                let permuted = permutation.apply(&values);
+                join_all(AuthenticatedScalarResult::open_authenticated_batch(
+                    &permuted,
+                ))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+            }
+        })
+        .await;
+
+        let expected = permutation
+            .iter()
+            .map(|i| Scalar::from(values[*i]))
+            .collect_vec();
+        assert_eq!(res, Ok(expected));
+    }
+}
@@ -0,0 +1,114 @@
+//! Defines gadgets for evaluating public polynomials at secret shared points
+
+use crate::algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar};
+
+use super::linear_combination;
+
+/// Compute the powers `[x, x^2, ..., x^n]` of a shared value
+///
+/// The powers are built by repeated doubling: given `x^1, ..., x^k`, the next layer
+/// computes `x^{k+1}, ..., x^{2k}` as `x^k * x^i` in a single batched multiplication.
+/// This uses `n - 1` Beaver multiplications in `ceil(log2(n))` rounds of communication
+pub fn powers(x: &AuthenticatedScalarResult, n: usize) -> Vec<AuthenticatedScalarResult> {
+    if n == 0 {
+        return vec![];
+    }
+
+    let mut pows = Vec::with_capacity(n);
+    pows.push(x.clone());
+
+    while pows.len() < n {
+        let k = pows.len();
+        let layer_size = usize::min(k, n - k);
+
+        let lhs = vec![pows[k - 1].clone(); layer_size];
+        let next_layer = AuthenticatedScalarResult::batch_mul(&lhs, &pows[..layer_size]);
+        pows.extend(next_layer);
+    }
+
+    pows
+}
+
+/// Evaluate a polynomial with public coefficients at a shared point
+///
+/// The coefficients are given in ascending order of degree, i.e. `coeffs[i]` is the
+/// coefficient of `x^i`
+///
+/// A direct Horner evaluation requires one round of communication per degree of the
+/// polynomial. Instead, we compute all powers of `x` in a logarithmic number of batched
+/// rounds, after which the evaluation is a public linear combination that requires no
+/// communication. The total number of Beaver multiplications is `deg - 1`, the same as Horner
+pub fn eval_polynomial(
+    coeffs: &[Scalar],
+    x: &AuthenticatedScalarResult,
+) -> AuthenticatedScalarResult {
+    let fabric = x.fabric();
+    if coeffs.is_empty() {
+        return fabric.zero_authenticated();
+    }
+
+    let pows = powers(x, coeffs.len() - 1);
+    if pows.is_empty() {
+        return fabric.zero_authenticated() + coeffs[0];
+    }
+
+    // The evaluation is now a linear combination of the powers, offset by the constant term
+    linear_combination(&pows, &coeffs[1..]) + coeffs[0]
+}
+
+#[cfg(test)]
+mod test {
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::scalar::Scalar, gadgets::polynomial::eval_polynomial,
+        test_helpers::execute_mock_mpc, PARTY0,
+    };
+
+    /// Tests evaluating a random polynomial at a shared point
+    #[tokio::test]
+    async fn test_eval_polynomial() {
+        const DEGREE: usize = 10;
+        let mut rng = thread_rng();
+        let coeffs = (0..=DEGREE)
+            .map(|_| Scalar::random(&mut rng))
+            .collect::<Vec<_>>();
+        let x = Scalar::random(&mut rng);
+
+        // Evaluate the polynomial in the clear
+        let expected = coeffs
+            .iter()
+            .rev()
+            .fold(Scalar::zero(), |acc, coeff| acc * x + coeff);
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let coeffs = coeffs.clone();
+            async move {
+                let shared_x = fabric.share_scalar(x, PARTY0);
+                eval_polynomial(&coeffs, &shared_x)
+                    .open_authenticated()
+                    .await
+            }
+        })
+        .await;
+
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    /// Tests evaluating a constant polynomial
+    #[tokio::test]
+    async fn test_eval_constant_polynomial() {
+        let mut rng = thread_rng();
+        let constant = Scalar::random(&mut rng);
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared_x = fabric.share_scalar(Scalar::random(&mut thread_rng()), PARTY0);
+            eval_polynomial(&[constant], &shared_x)
+                .open_authenticated()
+                .await
+        })
+        .await;
+
+        assert_eq!(res.unwrap(), constant);
+    }
+}
@@ -0,0 +1,116 @@
+//! Defines gadgets for checking that shared values lie in a range of integers
+//!
+//! Integer arithmetic in the prime field is only sound when the operands are known to be
+//! small enough that no result wraps around the modulus. These gadgets let the parties check
+//! that an input, e.g. one provided by a possibly malicious party, fits in a number of bits
+//! before operating on it
+
+use futures::future::join_all;
+use itertools::Itertools;
+
+use crate::{
+    algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+    error::MpcError,
+};
+
+use super::{
+    bits::{batch_bit_decompose, powers_of_two, MAX_DECOMPOSITION_BITS},
+    linear_combination,
+};
+
+/// Check that a shared value lies in the range `[0, 2^k)`
+///
+/// Fails with `MpcError::ArithmeticError` if the value does not fit in `k` bits
+pub async fn assert_in_range(value: &AuthenticatedScalarResult, k: usize) -> Result<(), MpcError> {
+    batch_assert_in_range(std::slice::from_ref(value), k).await
+}
+
+/// Check that each of a batch of shared values lies in the range `[0, 2^k)`
+///
+/// Each value is decomposed into its `k` least significant bits, which are recomposed and
+/// subtracted from the value. The difference is zero if and only if the value fits in `k`
+/// bits, and is multiplied by a random shared value before it is opened so that an out of
+/// range value leaks nothing beyond the failure of the check. Fails with
+/// `MpcError::ArithmeticError` if any value does not fit in `k` bits
+pub async fn batch_assert_in_range(
+    values: &[AuthenticatedScalarResult],
+    k: usize,
+) -> Result<(), MpcError> {
+    assert!(
+        k <= MAX_DECOMPOSITION_BITS,
+        "cannot range check more than {MAX_DECOMPOSITION_BITS} bits"
+    );
+    if values.is_empty() {
+        return Ok(());
+    }
+
+    let n = values.len();
+    let fabric = values[0].fabric();
+
+    // A decomposition into zero bits recomposes to zero
+    let recomposed = if k == 0 {
+        fabric.zeros_authenticated(n)
+    } else {
+        let coeffs = powers_of_two(k);
+        batch_bit_decompose(values, k)
+            .iter()
+            .map(|bits| linear_combination(bits, &coeffs))
+            .collect_vec()
+    };
+
+    let diffs = AuthenticatedScalarResult::batch_sub(values, &recomposed);
+    let masks = fabric.random_shared_scalars_authenticated(n);
+    let masked = AuthenticatedScalarResult::batch_mul(&diffs, &masks);
+
+    let opened = join_all(AuthenticatedScalarResult::open_authenticated_batch(&masked)).await;
+    for val in opened {
+        if val? != Scalar::zero() {
+            return Err(MpcError::ArithmeticError(format!(
+                "shared value does not fit in {k} bits"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::MpcError, test_helpers::execute_mock_mpc, PARTY0};
+
+    use super::{assert_in_range, batch_assert_in_range};
+
+    /// Tests range checks on values that fit in the range
+    #[tokio::test]
+    async fn test_in_range() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let values = fabric.batch_share_scalar(vec![0u64, 1, 255, 100], PARTY0);
+            batch_assert_in_range(&values, 8 /* k */).await?;
+
+            let zero = fabric.share_scalar(0u8, PARTY0);
+            assert_in_range(&zero, 0 /* k */).await
+        })
+        .await;
+
+        assert_eq!(res, Ok(()));
+    }
+
+    /// Tests range checks on values that do not fit in the range
+    #[tokio::test]
+    async fn test_out_of_range() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let just_over = fabric.share_scalar(256u64, PARTY0);
+            let res1 = assert_in_range(&just_over, 8 /* k */).await;
+
+            // A negative value wraps around to a large field element
+            let negative = -fabric.share_scalar(1u8, PARTY0);
+            let res2 = batch_assert_in_range(&[fabric.one_authenticated(), negative], 64).await;
+
+            (res1, res2)
+        })
+        .await;
+
+        assert!(matches!(res.0, Err(MpcError::ArithmeticError(_))));
+        assert!(matches!(res.1, Err(MpcError::ArithmeticError(_))));
+    }
+}
@@ -0,0 +1,225 @@
+//! Defines gadgets for the quadratic character and square roots of shared values
+//!
+//! Both gadgets mask the shared value multiplicatively before opening it, so that the opened
+//! value reveals nothing beyond what the gadget outputs
+
+use ark_ff::FftField;
+use futures::future::join_all;
+use itertools::Itertools;
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        scalar::{Scalar, ScalarInner, ScalarResult},
+    },
+    error::MpcError,
+    fabric::ResultValue,
+};
+
+/// A quadratic non-residue in the scalar field
+///
+/// A generator of the field's multiplicative group is never a square
+fn quadratic_non_residue() -> Scalar {
+    Scalar(ScalarInner::GENERATOR)
+}
+
+/// Compute the Legendre symbol of a shared value, i.e. a shared zero for zero, one for a
+/// non-zero quadratic residue, and minus one for a quadratic non-residue
+pub fn legendre(x: &AuthenticatedScalarResult) -> AuthenticatedScalarResult {
+    batch_legendre(std::slice::from_ref(x)).pop().unwrap()
+}
+
+/// Compute the Legendre symbols of a batch of shared values
+///
+/// Each value `x` is masked as `y = x * r^2 * t` for a random shared `r` and a shared `t`
+/// that is either one or a non-residue, chosen by a random shared bit `b`. The masked value
+/// is uniformly distributed when `x` is non-zero, so it may be opened, and the symbol of `x`
+/// is the public symbol of `y` times the shared symbol `1 - 2b` of `t`
+pub fn batch_legendre(values: &[AuthenticatedScalarResult]) -> Vec<AuthenticatedScalarResult> {
+    if values.is_empty() {
+        return vec![];
+    }
+
+    let n = values.len();
+    let fabric = values[0].fabric();
+    let masks = fabric.random_shared_scalars_authenticated(n);
+    let bits = fabric.random_shared_bits(n);
+
+    // t = 1 + b * (n - 1) for the non-residue n
+    let non_residue_offset = quadratic_non_residue() - Scalar::one();
+    let selectors = bits
+        .iter()
+        .map(|b| b * non_residue_offset + Scalar::one())
+        .collect_vec();
+
+    let squares = AuthenticatedScalarResult::batch_mul(&masks, &masks);
+    let selected = AuthenticatedScalarResult::batch_mul(values, &selectors);
+    let masked = AuthenticatedScalarResult::batch_mul(&selected, &squares);
+
+    let public_symbols = AuthenticatedScalarResult::open_batch(&masked)
+        .iter()
+        .map(|y| {
+            fabric.new_gate_op(vec![y.id()], |mut args| {
+                let y: Scalar = args.remove(0).into();
+                ResultValue::Scalar(y.legendre())
+            })
+        })
+        .collect::<Vec<ScalarResult>>();
+
+    let signs = bits
+        .iter()
+        .map(|b| Scalar::one() - Scalar::from(2u8) * b)
+        .collect_vec();
+    AuthenticatedScalarResult::batch_mul_public(&signs, &public_symbols)
+}
+
+/// Compute a square root of a shared value
+///
+/// Fails with `MpcError::ArithmeticError` if the value is not a quadratic residue. Which of
+/// the two roots is returned is unspecified
+pub async fn sqrt(x: &AuthenticatedScalarResult) -> Result<AuthenticatedScalarResult, MpcError> {
+    Ok(batch_sqrt(std::slice::from_ref(x)).await?.remove(0))
+}
+
+/// Compute a square root of each of a batch of shared values
+///
+/// Each value `x` is masked as `y = x / r^2` for a random shared `r`, where the inverse of
+/// `r` is computed by opening `r * s` for another random shared `s`. The masked value is
+/// uniformly distributed over the squares when `x` is a non-zero square, so it may be opened,
+/// and a root of `x` is the public root of `y` times `r`. The opening reveals whether each
+/// value is a square, and fails with `MpcError::ArithmeticError` if any value is not
+pub async fn batch_sqrt(
+    values: &[AuthenticatedScalarResult],
+) -> Result<Vec<AuthenticatedScalarResult>, MpcError> {
+    if values.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let n = values.len();
+    let fabric = values[0].fabric();
+    let masks = fabric.random_shared_scalars_authenticated(n);
+    let blinders = fabric.random_shared_scalars_authenticated(n);
+
+    // r^{-1} = s * (r * s)^{-1}
+    let blinded = AuthenticatedScalarResult::batch_mul(&masks, &blinders);
+    let blinded_inv = ScalarResult::batch_inverse(&AuthenticatedScalarResult::open_batch(&blinded));
+    let mask_inv = AuthenticatedScalarResult::batch_mul_public(&blinders, &blinded_inv);
+
+    let inv_squares = AuthenticatedScalarResult::batch_mul(&mask_inv, &mask_inv);
+    let masked = AuthenticatedScalarResult::batch_mul(values, &inv_squares);
+
+    // The root is derived from the masked value, so it is opened with a MAC check
+    let opened = join_all(AuthenticatedScalarResult::open_authenticated_batch(&masked)).await;
+    let mut roots = Vec::with_capacity(n);
+    for (y, mask) in opened.into_iter().zip(masks.iter()) {
+        let root = y?.sqrt().ok_or_else(|| {
+            MpcError::ArithmeticError("shared value is not a quadratic residue".to_string())
+        })?;
+
+        roots.push(mask * root);
+    }
+
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod test {
+    use futures::future::join_all;
+    use itertools::Itertools;
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        error::MpcError,
+        test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    use super::{batch_legendre, batch_sqrt, quadratic_non_residue, sqrt};
+
+    /// Tests that the non-residue used to mask values is not a square
+    #[test]
+    fn test_quadratic_non_residue() {
+        assert_eq!(quadratic_non_residue().legendre(), -Scalar::one());
+        assert_eq!(quadratic_non_residue().sqrt(), None);
+    }
+
+    /// Tests computing the Legendre symbols of shared values
+    #[tokio::test]
+    async fn test_legendre() {
+        let mut rng = thread_rng();
+        let square = Scalar::random(&mut rng);
+        let values = vec![
+            Scalar::zero(),
+            square * square,
+            square * square * quadratic_non_residue(),
+            Scalar::random(&mut rng),
+        ];
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let values = values.clone();
+            async move {
+                let shared = fabric.batch_share_scalar(values, PARTY0);
+                let symbols = batch_legendre(&shared);
+
+                join_all(AuthenticatedScalarResult::open_authenticated_batch(
+                    &symbols,
+                ))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+            }
+        })
+        .await;
+
+        let expected = values.iter().map(Scalar::legendre).collect_vec();
+        assert_eq!(res, Ok(expected));
+        assert_eq!(
+            &res.unwrap()[..3],
+            &[Scalar::zero(), Scalar::one(), -Scalar::one()]
+        );
+    }
+
+    /// Tests computing square roots of shared values
+    #[tokio::test]
+    async fn test_sqrt() {
+        let mut rng = thread_rng();
+        let roots = (0..3).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let squares = roots
+            .iter()
+            .map(|r| r * r)
+            .chain([Scalar::zero()])
+            .collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let squares = squares.clone();
+            async move {
+                let shared = fabric.batch_share_scalar(squares, PARTY0);
+                let roots = batch_sqrt(&shared).await?;
+
+                join_all(AuthenticatedScalarResult::open_authenticated_batch(&roots))
+                    .await
+                    .into_iter()
+                    .collect::<Result<Vec<_>, _>>()
+            }
+        })
+        .await;
+
+        let res = res.unwrap();
+        for (root, square) in res.into_iter().zip(squares) {
+            assert_eq!(root * root, square);
+        }
+    }
+
+    /// Tests that the square root of a non-residue fails
+    #[tokio::test]
+    async fn test_sqrt_non_residue() {
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared = fabric.share_scalar(quadratic_non_residue(), PARTY0);
+            sqrt(&shared).await.map(|_| ())
+        })
+        .await;
+
+        assert!(matches!(res, Err(MpcError::ArithmeticError(_))));
+    }
+}
@@ -25,14 +25,26 @@ pub mod beaver;
 pub mod buffer;
 #[cfg(not(feature = "benchmarks"))]
 pub(crate) mod buffer;
+pub mod circuit;
 pub mod commitment;
 pub mod error;
 mod fabric;
 #[cfg(feature = "benchmarks")]
 pub use fabric::*;
 #[cfg(not(feature = "benchmarks"))]
-pub use fabric::{FabricInner, MpcFabric, ResultHandle, ResultId, ResultValue};
+pub use fabric::{
+    join_results, BarrierResult, BroadcastResult, CostEstimate, Custom, CustomResult, CustomValue,
+    FabricConfig, FabricInner, FabricMetrics, FabricRng, FallibleResultHandle, GateProfile,
+    JoinResults, MpcFabric, OperationKind, OwnedResultHandle, ResultHandle, ResultId, ResultValue,
+    SecurityMode, ShutdownHandle, SignedTranscript, SimulationFabric, TimingHistogram, Transcript,
+    TranscriptEntry, TranscriptEntryKind, HISTOGRAM_BUCKETS,
+};
+pub mod gadgets;
 pub mod network;
+pub mod protocols;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod sync;
 
 // -------------
 // | Constants |
@@ -65,17 +77,21 @@ pub type SharedNetwork<N: MpcNetwork + Send> = Rc<RefCell<N>>;
 #[allow(type_alias_bounds)]
 pub type BeaverSource<S: SharedValueSource> = Rc<RefCell<S>>;
 
-#[cfg(test)]
-pub(crate) mod test_helpers {
+/// Helpers for testing protocols built on the fabric against a mock counterparty
+#[cfg(any(feature = "test_helpers", test))]
+pub mod test_helpers {
     use futures::Future;
 
     use crate::{
-        beaver::PartyIDBeaverSource,
         network::{MockNetwork, NoRecvNetwork, UnboundedDuplexStream},
         MpcFabric, PARTY0, PARTY1,
     };
 
-    /// Create a mock fabric
+    pub use crate::beaver::PartyIDBeaverSource;
+
+    /// Create a mock fabric whose network never receives a message
+    ///
+    /// Useful for testing local computation, any gate awaiting the counterparty will hang
     pub fn mock_fabric() -> MpcFabric {
         let network = NoRecvNetwork::default();
         let beaver_source = PartyIDBeaverSource::default();
@@ -85,7 +101,8 @@ pub(crate) mod test_helpers {
 
     /// Run a mock MPC connected by a duplex stream as the mock network
     ///
-    /// This will spawn two tasks to execute either side of the MPC
+    /// This will spawn two tasks to execute either side of the MPC, so it must be called from
+    /// within a tokio runtime
     ///
     /// Returns the outputs of both parties
     pub async fn execute_mock_mpc<T, S, F>(mut f: F) -> (T, T)
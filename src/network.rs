@@ -1,19 +1,46 @@
 //! The `network` module defines abstractions of the transport used to
 //! communicate during the course of an MPC
+pub(crate) mod buffer_pool;
 mod cert_verifier;
 mod config;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod identity;
 mod mock;
+mod quic_stream;
+#[cfg(feature = "relay")]
+mod relay;
+#[cfg(any(feature = "test_helpers", test))]
+mod replay;
 mod stream_buffer;
+#[cfg(any(feature = "test_helpers", test))]
+mod tamper;
+mod wire_auth;
 
 use futures::{Future, Sink, Stream};
+#[cfg(feature = "grpc")]
+pub use grpc::GrpcTwoPartyNet;
+pub use identity::{IdentityKeypair, IdentitySignature};
 #[cfg(any(feature = "test_helpers", test))]
 pub use mock::{MockNetwork, NoRecvNetwork, UnboundedDuplexStream};
+#[cfg(feature = "relay")]
+pub use relay::{
+    derive_room_id, RekeyInterval, RelayServer, RelayTwoPartyNet, RoomId, ROOM_ID_BYTES,
+};
+#[cfg(any(feature = "test_helpers", test))]
+pub use replay::{
+    MessageDirection, NetworkRecording, RecordedMessage, RecordingHandle, RecordingNetwork,
+    ReplayNetwork,
+};
+#[cfg(any(feature = "test_helpers", test))]
+pub use tamper::{Tamper, TamperHandle, TamperingNetwork};
 
 use async_trait::async_trait;
-use quinn::{Endpoint, RecvStream, SendStream};
-use serde::{Deserialize, Serialize};
+use quinn::{Connection, Endpoint};
+use rand::thread_rng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 use std::{
-    convert::TryInto,
     net::SocketAddr,
     pin::Pin,
     task::{Context, Poll},
@@ -23,14 +50,26 @@ use tracing::log;
 use crate::{
     algebra::{scalar::Scalar, stark_curve::StarkPoint},
     error::{MpcNetworkError, SetupError},
-    fabric::ResultId,
-    PARTY0,
+    fabric::{Custom, CustomResult, ResultId, ResultValue},
+    PARTY0, PARTY1,
 };
 
-use self::stream_buffer::BufferWithCursor;
+use self::{
+    buffer_pool::{deserialize_pooled, recycle, recycle_payload},
+    identity::{handshake_transcript, HandshakeHello},
+    quic_stream::QuicStream,
+    wire_auth::{derive_wire_key, StreamAuthenticator, WireKey},
+};
 
 /// A type alias of the id of a party in an MPC for readability
 pub type PartyId = u64;
+/// An identifier for an MPC session that both parties agree on when the network is set up
+///
+/// The session ID is mixed into network frames and commitments to prevent messages from one
+/// session being replayed in another
+pub type SessionId = [u8; SESSION_ID_BYTES];
+/// The number of bytes in a session ID
+pub const SESSION_ID_BYTES: usize = 32;
 /// The number of bytes in a u64
 const BYTES_PER_U64: usize = 8;
 
@@ -40,6 +79,10 @@ const ERR_READ_MESSAGE_LENGTH: &str = "error reading message length from stream"
 const ERR_STREAM_FINISHED_EARLY: &str = "stream finished early";
 /// Error message emitted when the the send `Sink` is not ready
 const ERR_SEND_BUFFER_FULL: &str = "send buffer full";
+/// Error message emitted when a frame is received with a different session ID
+const ERR_SESSION_MISMATCH: &str = "received frame from a different session";
+/// The domain separator used when deriving a session ID
+const SESSION_ID_DOMAIN: &[u8] = b"mpc-stark-session";
 
 // ---------
 // | Trait |
@@ -62,11 +105,66 @@ pub enum NetworkPayload {
     /// A scalar value
     Scalar(Scalar),
     /// A batch of scalar values
-    ScalarBatch(Vec<Scalar>),
+    ScalarBatch(#[serde(deserialize_with = "deserialize_pooled")] Vec<Scalar>),
     /// A point on the curve
     Point(StarkPoint),
     /// A batch of points on the curve
-    PointBatch(Vec<StarkPoint>),
+    PointBatch(#[serde(deserialize_with = "deserialize_pooled")] Vec<StarkPoint>),
+    /// A serialized value of a user-defined type
+    Custom(Vec<u8>),
+    /// A batch of messages packed into a single wire message by the sender, these are
+    /// unpacked by the receiver before delivery and may not be nested
+    Coalesced(Vec<NetworkOutbound>),
+    /// A batch of single scalar or point openings coalesced by the sender's executor, the
+    /// values are held in a single `ScalarBatch` or `PointBatch` and are unpacked to the
+    /// given results by the receiver before delivery
+    Openings {
+        /// The results the values are delivered to, in order
+        result_ids: Vec<ResultId>,
+        /// The batch of values
+        values: Box<NetworkPayload>,
+    },
+}
+
+impl NetworkPayload {
+    /// Unpack a batch of coalesced openings into a message for each opening
+    ///
+    /// Returns `None` if the values are not a batch or their number does not match the
+    /// number of results
+    pub(crate) fn unpack_openings(
+        result_ids: Vec<ResultId>,
+        values: NetworkPayload,
+    ) -> Option<Vec<NetworkOutbound>> {
+        // Copy the values out of the batch so that its vector may be returned to the pool
+        let values: Vec<NetworkPayload> = match values {
+            NetworkPayload::ScalarBatch(scalars) => {
+                let values = scalars
+                    .iter()
+                    .copied()
+                    .map(NetworkPayload::Scalar)
+                    .collect();
+                recycle(scalars);
+                values
+            }
+            NetworkPayload::PointBatch(points) => {
+                let values = points.iter().copied().map(NetworkPayload::Point).collect();
+                recycle(points);
+                values
+            }
+            _ => return None,
+        };
+        if values.len() != result_ids.len() {
+            return None;
+        }
+
+        Some(
+            result_ids
+                .into_iter()
+                .zip(values)
+                .map(|(result_id, payload)| NetworkOutbound { result_id, payload })
+                .collect(),
+        )
+    }
 }
 
 impl From<Vec<u8>> for NetworkPayload {
@@ -99,6 +197,96 @@ impl From<Vec<StarkPoint>> for NetworkPayload {
     }
 }
 
+/// The shape of a payload a party expects to receive from its peer
+///
+/// Inbound payloads are checked against the shape expected by the receiving result before
+/// they enter the computation graph
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadShape {
+    /// A byte value
+    Bytes,
+    /// A scalar value
+    Scalar,
+    /// A batch of scalars, optionally of a known length
+    ScalarBatch(Option<usize>),
+    /// A point on the curve
+    Point,
+    /// A batch of points, optionally of a known length
+    PointBatch(Option<usize>),
+    /// A serialized value of a user-defined type
+    Custom,
+}
+
+impl PayloadShape {
+    /// Set the expected length of a batch shape, this is a no-op for other shapes
+    pub fn with_len(self, n: usize) -> Self {
+        match self {
+            PayloadShape::ScalarBatch(_) => PayloadShape::ScalarBatch(Some(n)),
+            PayloadShape::PointBatch(_) => PayloadShape::PointBatch(Some(n)),
+            _ => self,
+        }
+    }
+
+    /// Whether the given payload has this shape
+    pub fn matches(&self, payload: &NetworkPayload) -> bool {
+        match (self, payload) {
+            (PayloadShape::Bytes, NetworkPayload::Bytes(_))
+            | (PayloadShape::Scalar, NetworkPayload::Scalar(_))
+            | (PayloadShape::Point, NetworkPayload::Point(_))
+            | (PayloadShape::Custom, NetworkPayload::Custom(_)) => true,
+            (PayloadShape::ScalarBatch(len), NetworkPayload::ScalarBatch(scalars)) => {
+                !matches!(len, Some(n) if *n != scalars.len())
+            }
+            (PayloadShape::PointBatch(len), NetworkPayload::PointBatch(points)) => {
+                !matches!(len, Some(n) if *n != points.len())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A type that may be received from the peer as the result of a network operation
+pub trait PayloadType: From<ResultValue> {
+    /// The shape of the payload that holds a value of this type
+    fn payload_shape() -> PayloadShape;
+}
+
+impl PayloadType for Vec<u8> {
+    fn payload_shape() -> PayloadShape {
+        PayloadShape::Bytes
+    }
+}
+
+impl PayloadType for Scalar {
+    fn payload_shape() -> PayloadShape {
+        PayloadShape::Scalar
+    }
+}
+
+impl PayloadType for Vec<Scalar> {
+    fn payload_shape() -> PayloadShape {
+        PayloadShape::ScalarBatch(None)
+    }
+}
+
+impl PayloadType for StarkPoint {
+    fn payload_shape() -> PayloadShape {
+        PayloadShape::Point
+    }
+}
+
+impl PayloadType for Vec<StarkPoint> {
+    fn payload_shape() -> PayloadShape {
+        PayloadShape::PointBatch(None)
+    }
+}
+
+impl<T: CustomResult> PayloadType for Custom<T> {
+    fn payload_shape() -> PayloadShape {
+        PayloadShape::Custom
+    }
+}
+
 /// The `MpcNetwork` trait defines shared functionality for a network implementing a
 /// connection between two parties in a 2PC
 ///
@@ -112,12 +300,45 @@ pub trait MpcNetwork:
 {
     /// Get the party ID of the local party in the MPC
     fn party_id(&self) -> PartyId;
+    /// Get the identity key of the peer, if it was authenticated when the network was set up
+    fn peer_identity(&self) -> Option<StarkPoint> {
+        None
+    }
+    /// Get the ID of the session the parties agreed on when the network was set up, if any
+    fn session_id(&self) -> Option<SessionId> {
+        None
+    }
     /// Closes the connections opened in the handshake phase
     async fn close(&mut self) -> Result<(), MpcNetworkError>;
 }
 
 // -----------
 // | Helpers |
+// -----------
+
+/// Derive a session ID from the ephemeral public keys contributed by each party
+pub(crate) fn derive_keyed_session_id(
+    party0_key: &StarkPoint,
+    party1_key: &StarkPoint,
+) -> SessionId {
+    let mut hasher = Sha3_256::new();
+    hasher.update(SESSION_ID_DOMAIN);
+    hasher.update(party0_key.to_bytes());
+    hasher.update(party1_key.to_bytes());
+
+    hasher.finalize().into()
+}
+
+/// Derive a session ID from random nonces contributed by each party
+#[cfg(any(feature = "grpc", test))]
+pub(crate) fn derive_session_id(party0_nonce: &Scalar, party1_nonce: &Scalar) -> SessionId {
+    let mut hasher = Sha3_256::new();
+    hasher.update(SESSION_ID_DOMAIN);
+    hasher.update(party0_nonce.to_bytes_be());
+    hasher.update(party1_nonce.to_bytes_be());
+
+    hasher.finalize().into()
+}
 
 /// The order in which the local party should read when exchanging values
 #[derive(Clone, Debug)]
@@ -138,25 +359,25 @@ pub struct QuicTwoPartyNet {
     local_addr: SocketAddr,
     /// Addresses of the counterparties in the MPC
     peer_addr: SocketAddr,
-    /// A buffered message length read from the stream
-    ///
-    /// In the case that the whole message is not available yet, reads may block
-    /// and the `read_message` future may be cancelled by the executor.
-    /// We buffer the message length to avoid re-reading the message length incorrectly from
-    /// the stream
-    buffered_message_length: Option<u64>,
-    /// A buffered partial message read from the stream
-    ///
-    /// This buffer exists to provide cancellation safety to a `read` future as the underlying `quinn`
-    /// stream is not cancellation safe, i.e. if a `ReadBuf` future is dropped, the buffer is dropped with
-    /// it and the partially read data is skipped
-    buffered_inbound: Option<BufferWithCursor>,
-    /// A buffered partial message written to the stream
-    buffered_outbound: Option<BufferWithCursor>,
-    /// The send side of the bidirectional stream
-    send_stream: Option<SendStream>,
-    /// The receive side of the bidirectional stream
-    recv_stream: Option<RecvStream>,
+    /// The number of parallel streams to open to the peer
+    n_streams: usize,
+    /// The bidirectional streams to the peer, the first carries the handshake
+    streams: Vec<QuicStream>,
+    /// The index of the stream the next outbound message is written to
+    next_send_stream: usize,
+    /// The index of the stream that is polled first for the next inbound message
+    next_recv_stream: usize,
+    /// The local party's identity keypair, used to authenticate to the peer
+    identity: Option<IdentityKeypair>,
+    /// The identity key the peer is expected to authenticate with, if known ahead of time
+    expected_peer_identity: Option<StarkPoint>,
+    /// The identity key the peer authenticated with during the handshake
+    peer_identity: Option<StarkPoint>,
+    /// The ID of the session, agreed on when connecting
+    session_id: Option<SessionId>,
+    /// The keys that tag the messages sent by the local party and by the peer, agreed on
+    /// when connecting
+    wire_keys: Option<(WireKey, WireKey)>,
 }
 
 #[allow(clippy::redundant_closure)] // For readability of error handling
@@ -169,14 +390,48 @@ impl<'a> QuicTwoPartyNet {
             local_addr,
             peer_addr,
             connected: false,
-            buffered_message_length: None,
-            buffered_inbound: None,
-            buffered_outbound: None,
-            send_stream: None,
-            recv_stream: None,
+            n_streams: 1,
+            streams: Vec::new(),
+            next_send_stream: 0,
+            next_recv_stream: 0,
+            identity: None,
+            expected_peer_identity: None,
+            peer_identity: None,
+            session_id: None,
+            wire_keys: None,
+        }
+    }
+
+    /// Create a new network that authenticates the parties' identities when it connects
+    ///
+    /// Both parties must be constructed with an identity. If `expected_peer_identity` is given,
+    /// the handshake fails unless the peer authenticates with that key
+    pub fn new_with_identity(
+        party_id: PartyId,
+        local_addr: SocketAddr,
+        peer_addr: SocketAddr,
+        identity: IdentityKeypair,
+        expected_peer_identity: Option<StarkPoint>,
+    ) -> Self {
+        Self {
+            identity: Some(identity),
+            expected_peer_identity,
+            ..Self::new(party_id, local_addr, peer_addr)
         }
     }
 
+    /// Stripe messages across the given number of parallel streams to the peer
+    ///
+    /// Each stream is ordered independently, so a message delayed on one stream does not
+    /// block those on the others, improving throughput on high bandwidth-delay links. Messages
+    /// are reassembled by result ID on receipt. Both parties must use the same number of
+    /// streams
+    pub fn with_parallel_streams(mut self, n_streams: usize) -> Self {
+        assert!(n_streams > 0, "at least one stream is required");
+        self.n_streams = n_streams;
+        self
+    }
+
     /// Returns true if the local party is party 0
     fn local_party0(&self) -> bool {
         self.party_id() == PARTY0
@@ -251,89 +506,157 @@ impl<'a> QuicTwoPartyNet {
 
         // Update MpcNet state
         self.connected = true;
-        self.send_stream = Some(send);
-        self.recv_stream = Some(recv);
+        self.streams = vec![QuicStream::new(send, recv)];
 
-        Ok(())
+        // Agree on a session ID, then authenticate the peer if the parties have identities
+        self.establish_session().await?;
+        if self.identity.is_some() {
+            self.identity_handshake().await?;
+        }
+
+        self.open_parallel_streams(&connection).await
     }
 
-    /// Write the current buffer to the stream
-    async fn write_bytes(&mut self) -> Result<(), MpcNetworkError> {
-        // If no pending writes are available, return
-        if self.buffered_outbound.is_none() {
-            return Ok(());
-        }
+    /// Open the streams beyond the first to the peer
+    ///
+    /// The peer only learns of a stream once data is written to it, so the king writes the
+    /// session ID to each stream it opens and the peer checks it on each stream it accepts
+    async fn open_parallel_streams(
+        &mut self,
+        connection: &Connection,
+    ) -> Result<(), MpcNetworkError> {
+        let session_id = self.session_id.unwrap_or_default();
+        for _ in 1..self.n_streams {
+            let mut stream = if self.local_party0() {
+                let (send, recv) = connection.open_bi().await.map_err(|err| {
+                    log::error!("error opening parallel stream: {err}");
+                    MpcNetworkError::ConnectionSetupError(SetupError::ConnectionError(err))
+                })?;
 
-        // While the outbound buffer has elements remaining, write them
-        let buf = self.buffered_outbound.as_mut().unwrap();
-        while !buf.is_depleted() {
-            let bytes_written = self
-                .send_stream
-                .as_mut()
-                .unwrap()
-                .write(buf.get_remaining())
-                .await
-                .map_err(|e| MpcNetworkError::SendError(e.to_string()))?;
+                let mut stream = QuicStream::new(send, recv);
+                stream.buffer_frame(&session_id);
+                stream.write_bytes().await?;
+                stream
+            } else {
+                let (send, recv) = connection.accept_bi().await.map_err(|err| {
+                    log::error!("error accepting parallel stream: {err}");
+                    MpcNetworkError::ConnectionSetupError(SetupError::ConnectionError(err))
+                })?;
 
-            buf.advance_cursor(bytes_written);
+                let mut stream = QuicStream::new(send, recv);
+                if stream.receive_frame().await?[..] != session_id {
+                    return Err(MpcNetworkError::RecvError(ERR_SESSION_MISMATCH.to_string()));
+                }
+                stream
+            };
+
+            stream.set_authenticator(self.stream_authenticator(self.streams.len()));
+            self.streams.push(stream);
         }
 
-        self.buffered_outbound = None;
         Ok(())
     }
 
-    /// Read exactly `n` bytes from the stream
-    async fn read_bytes(&mut self, num_bytes: usize) -> Result<Vec<u8>, MpcNetworkError> {
-        // Allocate a buffer for the next message if one does not already exist
-        if self.buffered_inbound.is_none() {
-            self.buffered_inbound = Some(BufferWithCursor::new(vec![0u8; num_bytes]));
-        }
-
-        // Read until the buffer is full
-        let read_buffer = self.buffered_inbound.as_mut().unwrap();
-        while !read_buffer.is_depleted() {
-            let bytes_read = self
-                .recv_stream
-                .as_mut()
-                .unwrap()
-                .read(read_buffer.get_remaining())
-                .await
-                .map_err(|e| MpcNetworkError::RecvError(e.to_string()))?
-                .ok_or(MpcNetworkError::RecvError(
-                    ERR_STREAM_FINISHED_EARLY.to_string(),
-                ))?;
-
-            read_buffer.advance_cursor(bytes_read);
-        }
+    /// Agree on a session ID and message authentication keys with the peer by exchanging
+    /// ephemeral Diffie-Hellman keys
+    ///
+    /// The session ID binds the ephemeral keys, so that the identity handshake, when run,
+    /// authenticates the keys the messages are tagged under
+    async fn establish_session(&mut self) -> Result<(), MpcNetworkError> {
+        let ephemeral_key = Scalar::random(&mut thread_rng());
+        let my_key = StarkPoint::generator() * ephemeral_key;
+        let peer_key: StarkPoint = self.exchange_handshake_message(&my_key).await?;
+
+        let session_id = if self.local_party0() {
+            derive_keyed_session_id(&my_key, &peer_key)
+        } else {
+            derive_keyed_session_id(&peer_key, &my_key)
+        };
 
-        // Take ownership of the buffer, and reset the buffered message to `None`
-        Ok(self.buffered_inbound.take().unwrap().into_vec())
+        let shared_secret = peer_key * ephemeral_key;
+        let peer_id = if self.local_party0() { PARTY1 } else { PARTY0 };
+        self.wire_keys = Some((
+            derive_wire_key(&shared_secret, &session_id, self.party_id),
+            derive_wire_key(&shared_secret, &session_id, peer_id),
+        ));
+        self.session_id = Some(session_id);
+
+        // Handshake messages are sent on the first stream before its messages are tagged
+        let auth = self.stream_authenticator(0 /* stream */);
+        self.streams[0].set_authenticator(auth);
+        Ok(())
     }
 
-    /// Read a message length from the stream
-    async fn read_message_length(&mut self) -> Result<u64, MpcNetworkError> {
-        let read_buffer = self.read_bytes(BYTES_PER_U64).await?;
-        Ok(u64::from_le_bytes(read_buffer.try_into().map_err(
-            |_| MpcNetworkError::SerializationError(ERR_READ_MESSAGE_LENGTH.to_string()),
-        )?))
+    /// Build the authenticator for the stream at the given index from the session's keys
+    fn stream_authenticator(&self, stream: usize) -> StreamAuthenticator {
+        let (send_key, recv_key) = self.wire_keys.expect("session keys not yet agreed");
+        StreamAuthenticator::new(send_key, recv_key, stream)
     }
 
-    /// Receive a message from the peer
-    async fn receive_message(&mut self) -> Result<NetworkOutbound, MpcNetworkError> {
-        // Read the message length from the buffer if available
-        if self.buffered_message_length.is_none() {
-            self.buffered_message_length = Some(self.read_message_length().await?);
+    /// Authenticate the parties to one another using their identity keys
+    ///
+    /// The parties exchange their public keys, then each signs the transcript of the exchange,
+    /// bound to the session ID, and verifies the peer's signature
+    async fn identity_handshake(&mut self) -> Result<(), MpcNetworkError> {
+        let keypair = self.identity.clone().unwrap();
+        let session_id = self.session_id.unwrap();
+
+        let my_hello = HandshakeHello {
+            party_id: self.party_id,
+            public_key: keypair.public_key(),
+        };
+        let peer_hello: HandshakeHello = self.exchange_handshake_message(&my_hello).await?;
+
+        if matches!(self.expected_peer_identity, Some(expected) if expected != peer_hello.public_key)
+        {
+            log::error!("peer authenticated with an unexpected identity");
+            return Err(MpcNetworkError::ConnectionSetupError(
+                SetupError::PeerAuthenticationError,
+            ));
+        }
+
+        // Sign the transcript and verify the peer's signature
+        let transcript = if self.local_party0() {
+            handshake_transcript(&session_id, &my_hello, &peer_hello)
+        } else {
+            handshake_transcript(&session_id, &peer_hello, &my_hello)
+        };
+
+        let my_sig = keypair.sign(&transcript, &mut thread_rng());
+        let peer_sig: IdentitySignature = self.exchange_handshake_message(&my_sig).await?;
+        if !peer_sig.verify(&peer_hello.public_key, &transcript) {
+            log::error!("invalid peer signature on handshake transcript");
+            return Err(MpcNetworkError::ConnectionSetupError(
+                SetupError::PeerAuthenticationError,
+            ));
         }
 
-        // Read the data from the stream
-        let len = self.buffered_message_length.unwrap();
-        let bytes = self.read_bytes(len as usize).await?;
+        self.peer_identity = Some(peer_hello.public_key);
+        Ok(())
+    }
+
+    /// Exchange a handshake message with the peer, party 0 sends first
+    async fn exchange_handshake_message<T: Serialize + DeserializeOwned>(
+        &mut self,
+        message: &T,
+    ) -> Result<T, MpcNetworkError> {
+        let bytes = serde_json::to_vec(message)
+            .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))?;
 
-        // Reset the message length buffer after the data has been pulled from the stream
-        self.buffered_message_length = None;
+        let local_party0 = self.local_party0();
+        let stream = &mut self.streams[0];
+        let peer_bytes = if local_party0 {
+            stream.buffer_frame(&bytes);
+            stream.write_bytes().await?;
+            stream.receive_frame().await?
+        } else {
+            let peer_bytes = stream.receive_frame().await?;
+            stream.buffer_frame(&bytes);
+            stream.write_bytes().await?;
+            peer_bytes
+        };
 
-        // Deserialize the message
-        serde_json::from_slice(&bytes)
+        serde_json::from_slice(&peer_bytes)
             .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))
     }
 }
@@ -344,15 +667,54 @@ impl MpcNetwork for QuicTwoPartyNet {
         self.party_id
     }
 
+    fn peer_identity(&self) -> Option<StarkPoint> {
+        self.peer_identity
+    }
+
+    fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+
     async fn close(&mut self) -> Result<(), MpcNetworkError> {
         self.assert_connected()?;
 
-        self.send_stream
-            .as_mut()
-            .unwrap()
-            .finish()
-            .await
-            .map_err(|_| MpcNetworkError::ConnectionTeardownError)
+        for stream in self.streams.iter_mut() {
+            stream.finish().await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl QuicTwoPartyNet {
+    /// Poll the pending writes on all streams, returning whether they have all completed
+    fn poll_writes(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), MpcNetworkError>> {
+        let mut done = true;
+        for stream in self
+            .streams
+            .iter_mut()
+            .filter(|s| s.has_buffered_outbound())
+        {
+            match Box::pin(stream.write_bytes()).as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => done = false,
+            }
+        }
+
+        if done {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// The index of the next stream in round-robin order with no pending write, if any
+    fn next_free_stream(&self) -> Option<usize> {
+        let n = self.streams.len();
+        (0..n)
+            .map(|offset| (self.next_send_stream + offset) % n)
+            .find(|&idx| !self.streams[idx].has_buffered_outbound())
     }
 }
 
@@ -360,7 +722,27 @@ impl Stream for QuicTwoPartyNet {
     type Item = Result<NetworkOutbound, MpcNetworkError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Box::pin(self.receive_message()).as_mut().poll(cx).map(Some)
+        if !self.connected {
+            return Poll::Ready(Some(Err(MpcNetworkError::NetworkUninitialized)));
+        }
+
+        // Poll the streams in round-robin order so that no stream starves the others, reads
+        // are buffered so that a stream not ready is safely polled again later
+        let session_id = self.session_id.unwrap_or_default();
+        let n = self.streams.len();
+        for offset in 0..n {
+            let idx = (self.next_recv_stream + offset) % n;
+            let poll = Box::pin(self.streams[idx].receive_message(&session_id))
+                .as_mut()
+                .poll(cx);
+
+            if let Poll::Ready(res) = poll {
+                self.next_recv_stream = (idx + 1) % n;
+                return Poll::Ready(Some(res));
+            }
+        }
+
+        Poll::Pending
     }
 }
 
@@ -372,29 +754,36 @@ impl Sink<NetworkOutbound> for QuicTwoPartyNet {
             return Err(MpcNetworkError::NetworkUninitialized);
         }
 
-        // Must call `poll_flush` before calling `start_send` again
-        if self.buffered_outbound.is_some() {
-            return Err(MpcNetworkError::SendError(ERR_SEND_BUFFER_FULL.to_string()));
-        }
+        // Must call `poll_ready` until a stream is free before calling `start_send` again
+        let idx = self
+            .next_free_stream()
+            .ok_or_else(|| MpcNetworkError::SendError(ERR_SEND_BUFFER_FULL.to_string()))?;
 
-        // Serialize the message and buffer it for writing
-        let bytes = serde_json::to_vec(&msg)
-            .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))?;
-        let mut payload = (bytes.len() as u64).to_le_bytes().to_vec();
-        payload.extend_from_slice(&bytes);
+        let session_id = self.session_id.unwrap_or_default();
+        self.streams[idx].buffer_message(&msg, &session_id)?;
+        recycle_payload(msg.payload);
 
-        self.buffered_outbound = Some(BufferWithCursor::new(payload));
+        // Stripe the next message onto the next stream
+        self.next_send_stream = (idx + 1) % self.streams.len();
         Ok(())
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        // Poll the write future
-        Box::pin(self.write_bytes()).as_mut().poll(cx)
+        self.poll_writes(cx)
     }
 
-    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        // The network is always ready to send
-        self.poll_flush(cx)
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The network is ready to send once any stream has finished its pending write
+        let writes = self.poll_writes(cx);
+        if let Poll::Ready(Err(err)) = writes {
+            return Poll::Ready(Err(err));
+        }
+
+        if self.streams.is_empty() || self.next_free_stream().is_some() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -402,3 +791,81 @@ impl Sink<NetworkOutbound> for QuicTwoPartyNet {
         self.poll_flush(cx)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use futures::{SinkExt, StreamExt};
+    use itertools::Itertools;
+    use rand::thread_rng;
+
+    use super::{IdentityKeypair, MpcNetwork, NetworkOutbound, NetworkPayload, QuicTwoPartyNet};
+
+    /// Tests that an authenticated connection can be spawned onto a multi-threaded runtime
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_spawn_connect_with_identity() {
+        let addr0 = "127.0.0.1:18152".parse().unwrap();
+        let addr1 = "127.0.0.1:18153".parse().unwrap();
+        let [task0, task1] = [(0, addr0, addr1), (1, addr1, addr0)].map(|(party, local, peer)| {
+            let identity = IdentityKeypair::random(&mut thread_rng());
+            tokio::spawn(async move {
+                let mut net =
+                    QuicTwoPartyNet::new_with_identity(party, local, peer, identity, None);
+                net.connect().await.map(|_| net)
+            })
+        });
+
+        // Hold both networks until both handshakes complete
+        let res0 = task0.await.unwrap();
+        let res1 = task1.await.unwrap();
+        assert!(res0.is_ok() && res1.is_ok());
+    }
+
+    /// Tests striping messages across parallel streams to the peer
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_parallel_streams() {
+        const N_STREAMS: usize = 3;
+        const N: u64 = 100;
+
+        let addr0 = "127.0.0.1:18150".parse().unwrap();
+        let addr1 = "127.0.0.1:18151".parse().unwrap();
+        let mut net0 = QuicTwoPartyNet::new(0, addr0, addr1).with_parallel_streams(N_STREAMS);
+        let mut net1 = QuicTwoPartyNet::new(1, addr1, addr0).with_parallel_streams(N_STREAMS);
+
+        let (res0, res1) = tokio::join!(net0.connect(), net1.connect());
+        res0.unwrap();
+        res1.unwrap();
+        assert_eq!(net0.streams.len(), N_STREAMS);
+        assert_eq!(net1.streams.len(), N_STREAMS);
+
+        // Send in both directions, messages may arrive out of order across streams
+        async fn send(net: &mut QuicTwoPartyNet) {
+            for id in 0..N {
+                let msg = NetworkOutbound {
+                    result_id: id as usize,
+                    payload: NetworkPayload::Bytes(id.to_le_bytes().to_vec()),
+                };
+                net.send(msg).await.unwrap();
+            }
+        }
+        tokio::join!(send(&mut net0), send(&mut net1));
+
+        for net in [&mut net0, &mut net1] {
+            let mut received = Vec::new();
+            for _ in 0..N {
+                let msg = net.next().await.unwrap().unwrap();
+                let NetworkPayload::Bytes(bytes) = msg.payload else {
+                    panic!("unexpected payload");
+                };
+
+                assert_eq!(bytes, (msg.result_id as u64).to_le_bytes());
+                received.push(msg.result_id);
+            }
+
+            received.sort_unstable();
+            assert_eq!(received, (0..N as usize).collect_vec());
+        }
+
+        MpcNetwork::close(&mut net0).await.unwrap();
+        MpcNetwork::close(&mut net1).await.unwrap();
+    }
+}
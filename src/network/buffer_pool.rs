@@ -0,0 +1,238 @@
+//! Defines pools of the vectors that batch payloads are held in, so that sending and
+//! receiving large batches reuses the vectors of earlier messages instead of allocating
+//! fresh vectors for every message
+//!
+//! Vectors are taken from the pools when the executor copies a payload to send it and when a
+//! batch is deserialized off the wire. They are returned once the transport has serialized an
+//! outbound message, and once the result that a batch was delivered to is dropped. Vectors are
+//! zeroized before they are pooled, and each pool holds a bounded number of vectors of a
+//! bounded size, larger vectors are freed as usual
+
+use std::{
+    fmt::{Formatter, Result as FmtResult},
+    marker::PhantomData,
+    mem::size_of,
+};
+
+use crossbeam::queue::ArrayQueue;
+use once_cell::sync::Lazy;
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use zeroize::Zeroize;
+
+use crate::algebra::{scalar::Scalar, stark_curve::StarkPoint};
+
+use super::{NetworkOutbound, NetworkPayload};
+
+/// The maximum number of vectors held by each pool
+const MAX_POOLED_VECS: usize = 16;
+/// The maximum size in bytes of the allocation of a pooled vector
+const MAX_POOLED_BYTES: usize = 1 << 20;
+
+/// The pool of scalar vectors
+static SCALAR_POOL: Lazy<VecPool<Scalar>> = Lazy::new(VecPool::new);
+/// The pool of point vectors
+static POINT_POOL: Lazy<VecPool<StarkPoint>> = Lazy::new(VecPool::new);
+
+/// A bounded pool of vectors
+pub(crate) struct VecPool<T> {
+    /// The pooled vectors, each empty and zeroized
+    vecs: ArrayQueue<Vec<T>>,
+}
+
+impl<T: Zeroize> VecPool<T> {
+    /// Construct an empty pool
+    fn new() -> Self {
+        Self {
+            vecs: ArrayQueue::new(MAX_POOLED_VECS),
+        }
+    }
+
+    /// Take an empty vector with at least the given capacity from the pool, allocating one
+    /// if the pool is empty
+    pub fn take(&self, capacity: usize) -> Vec<T> {
+        match self.vecs.pop() {
+            Some(mut vec) => {
+                vec.reserve(capacity);
+                vec
+            }
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Zeroize a vector and return it to the pool, freeing it if the pool is full or the
+    /// vector is too large to hold on to
+    pub fn recycle(&self, mut vec: Vec<T>) {
+        vec.zeroize();
+        if vec.capacity() == 0 || vec.capacity() * size_of::<T>() > MAX_POOLED_BYTES {
+            return;
+        }
+
+        let _ = self.vecs.push(vec);
+    }
+
+    /// The number of vectors held by the pool
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.vecs.len()
+    }
+}
+
+/// A type whose batches are held in pooled vectors
+pub(crate) trait Pooled: Sized + Zeroize + 'static {
+    /// The pool of vectors of the type
+    fn pool() -> &'static VecPool<Self>;
+}
+
+impl Pooled for Scalar {
+    fn pool() -> &'static VecPool<Self> {
+        &SCALAR_POOL
+    }
+}
+
+impl Pooled for StarkPoint {
+    fn pool() -> &'static VecPool<Self> {
+        &POINT_POOL
+    }
+}
+
+/// Copy a batch into a pooled vector
+pub(crate) fn pooled_copy<T: Pooled + Copy>(values: &[T]) -> Vec<T> {
+    let mut vec = T::pool().take(values.len());
+    vec.extend_from_slice(values);
+    vec
+}
+
+/// Return a vector to its pool
+pub(crate) fn recycle<T: Pooled>(vec: Vec<T>) {
+    T::pool().recycle(vec)
+}
+
+/// Copy a payload, holding its batches in pooled vectors
+pub(crate) fn clone_payload(payload: &NetworkPayload) -> NetworkPayload {
+    match payload {
+        NetworkPayload::ScalarBatch(scalars) => NetworkPayload::ScalarBatch(pooled_copy(scalars)),
+        NetworkPayload::PointBatch(points) => NetworkPayload::PointBatch(pooled_copy(points)),
+        _ => payload.clone(),
+    }
+}
+
+/// Return the batches held in a payload to their pools
+pub(crate) fn recycle_payload(payload: NetworkPayload) {
+    match payload {
+        NetworkPayload::ScalarBatch(scalars) => recycle(scalars),
+        NetworkPayload::PointBatch(points) => recycle(points),
+        NetworkPayload::Coalesced(msgs) => msgs
+            .into_iter()
+            .for_each(|NetworkOutbound { payload, .. }| recycle_payload(payload)),
+        NetworkPayload::Openings { values, .. } => recycle_payload(*values),
+        _ => {}
+    }
+}
+
+/// Deserialize a batch into a pooled vector
+pub(crate) fn deserialize_pooled<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Pooled + Deserialize<'de>,
+{
+    deserializer.deserialize_seq(PooledVecVisitor(PhantomData))
+}
+
+/// Visits a sequence into a pooled vector
+struct PooledVecVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Pooled + Deserialize<'de>> Visitor<'de> for PooledVecVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut vec = T::pool().take(seq.size_hint().unwrap_or_default());
+        while let Some(value) = seq.next_element()? {
+            vec.push(value);
+        }
+
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::{scalar::Scalar, stark_curve::StarkPoint},
+        network::{NetworkOutbound, NetworkPayload},
+    };
+
+    use super::{VecPool, MAX_POOLED_BYTES, MAX_POOLED_VECS};
+
+    /// Tests that the pool hands back recycled allocations, and bounds what it holds on to
+    #[test]
+    fn test_vec_pool() {
+        let pool = VecPool::<Scalar>::new();
+
+        let mut vec = pool.take(100);
+        vec.push(Scalar::one());
+        let ptr = vec.as_ptr();
+        pool.recycle(vec);
+
+        let vec = pool.take(10);
+        assert!(vec.is_empty());
+        assert!(vec.capacity() >= 100);
+        assert_eq!(vec.as_ptr(), ptr);
+
+        // Oversized vectors and vectors past the pool's bound are freed
+        pool.recycle(Vec::with_capacity(MAX_POOLED_BYTES));
+        assert_eq!(pool.len(), 0);
+
+        (0..MAX_POOLED_VECS + 1).for_each(|_| pool.recycle(Vec::with_capacity(1)));
+        assert_eq!(pool.len(), MAX_POOLED_VECS);
+    }
+
+    /// Tests that batches deserialized into pooled vectors round trip
+    #[test]
+    fn test_pooled_deserialization() {
+        let mut rng = thread_rng();
+        let scalars = (0..100).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let points = scalars
+            .iter()
+            .map(|s| StarkPoint::generator() * s)
+            .collect_vec();
+
+        let msg = NetworkOutbound {
+            result_id: 1,
+            payload: NetworkPayload::Coalesced(vec![
+                NetworkOutbound {
+                    result_id: 2,
+                    payload: NetworkPayload::ScalarBatch(scalars.clone()),
+                },
+                NetworkOutbound {
+                    result_id: 3,
+                    payload: NetworkPayload::PointBatch(points.clone()),
+                },
+            ]),
+        };
+
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let recovered: NetworkOutbound = serde_json::from_slice(&bytes).unwrap();
+        let msgs = match recovered.payload {
+            NetworkPayload::Coalesced(msgs) => msgs,
+            _ => panic!("expected a coalesced payload"),
+        };
+
+        match (&msgs[0].payload, &msgs[1].payload) {
+            (NetworkPayload::ScalarBatch(s), NetworkPayload::PointBatch(p)) => {
+                assert_eq!(s, &scalars);
+                assert_eq!(p, &points);
+            }
+            _ => panic!("expected a scalar and a point batch"),
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! Defines an `MpcNetwork` over a bidirectional gRPC stream, for deployments whose
+//! infrastructure, e.g. load balancers, mTLS, or service meshes, is built around gRPC
+//!
+//! Party 1 serves the `mpc_stark.MpcNetwork` service and party 0 dials it, opening a single
+//! `Exchange` stream that carries the protobuf encoded messages in both directions
+
+mod proto;
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::{
+    channel::{mpsc, oneshot},
+    ready,
+    stream::poll_fn,
+    Sink, SinkExt, Stream, StreamExt,
+};
+use rand::thread_rng;
+use tokio::net::TcpListener;
+use tonic::{
+    body::BoxBody,
+    client::Grpc as GrpcClient,
+    codec::{ProstCodec, Streaming},
+    codegen::{http, Body, BoxFuture, BoxStream, Service, StdError},
+    server::{Grpc as GrpcServer, NamedService},
+    transport::{Endpoint, Server},
+    Request, Response, Status,
+};
+use tracing::log;
+
+use crate::{
+    algebra::scalar::Scalar,
+    error::{MpcNetworkError, SetupError},
+    PARTY0,
+};
+
+use self::proto::{OutboundMessage, WireBody, WireMessage};
+
+use super::{
+    derive_session_id, MpcNetwork, NetworkOutbound, PartyId, SessionId, ERR_SESSION_MISMATCH,
+    ERR_STREAM_FINISHED_EARLY,
+};
+
+/// The name of the gRPC service the parties communicate over
+const SERVICE_NAME: &str = "mpc_stark.MpcNetwork";
+/// The path of the bidirectional streaming method the parties communicate over
+const EXCHANGE_PATH: &str = "/mpc_stark.MpcNetwork/Exchange";
+/// The number of messages buffered for the stream before sends wait on the transport
+const OUTBOUND_BUFFER_SIZE: usize = 1024;
+/// The default amount of time party 0 retries dialing a peer that is not yet serving
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 30_000; // 30 seconds
+/// The interval between attempts to dial the peer
+const CONNECT_RETRY_INTERVAL_MS: u64 = 100;
+/// Error message emitted when the peer sends a malformed handshake
+const ERR_INVALID_HANDSHAKE: &str = "invalid handshake message";
+
+/// Implements an MpcNetwork on top of a bidirectional gRPC stream
+///
+/// Unlike the QUIC transport, the gRPC transport does not authenticate the parties' identity
+/// keys, authentication is expected to come from the infrastructure, e.g. mTLS configured on
+/// the endpoint and server
+pub struct GrpcTwoPartyNet {
+    /// The index of the local party in the participants
+    party_id: PartyId,
+    /// The address party 1 serves the exchange on
+    local_addr: SocketAddr,
+    /// The endpoint party 0 dials to reach the peer
+    peer_endpoint: Endpoint,
+    /// The server party 1 serves the exchange with
+    server: Option<Server>,
+    /// The amount of time party 0 retries dialing the peer before giving up
+    connect_timeout: Duration,
+    /// The ID of the session, agreed on when connecting
+    session_id: Option<SessionId>,
+    /// The send side of the exchange stream
+    outbound: Option<mpsc::Sender<WireMessage>>,
+    /// The receive side of the exchange stream
+    inbound: Option<Streaming<WireMessage>>,
+    /// Shuts down the server serving the exchange, if the local party serves it
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl GrpcTwoPartyNet {
+    /// Create a new network, do not connect the network yet
+    ///
+    /// Party 1 serves the exchange on `local_addr` and party 0 dials `peer_addr`
+    pub fn new(party_id: PartyId, local_addr: SocketAddr, peer_addr: SocketAddr) -> Self {
+        let peer_endpoint = Endpoint::from_shared(format!("http://{peer_addr}"))
+            .expect("socket address is a valid uri");
+
+        Self {
+            party_id,
+            local_addr,
+            peer_endpoint,
+            server: None,
+            connect_timeout: Duration::from_millis(DEFAULT_CONNECT_TIMEOUT_MS),
+            session_id: None,
+            outbound: None,
+            inbound: None,
+            shutdown: None,
+        }
+    }
+
+    /// Set the endpoint party 0 dials, e.g. to reach the peer through a load balancer or to
+    /// configure TLS on the connection
+    pub fn with_peer_endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.peer_endpoint = endpoint;
+        self
+    }
+
+    /// Set the server party 1 serves the exchange with, e.g. to configure TLS or HTTP/2
+    /// settings on the server
+    pub fn with_server(mut self, server: Server) -> Self {
+        self.server = Some(server);
+        self
+    }
+
+    /// Set the amount of time party 0 retries dialing a peer that is not yet serving
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Returns true if the local party is party 0
+    fn local_party0(&self) -> bool {
+        self.party_id == PARTY0
+    }
+
+    /// Returns an error if the network is not connected
+    fn assert_connected(&self) -> Result<(), MpcNetworkError> {
+        if self.outbound.is_some() {
+            Ok(())
+        } else {
+            Err(MpcNetworkError::NetworkUninitialized)
+        }
+    }
+
+    /// Establishes the exchange stream with the peer
+    pub async fn connect(&mut self) -> Result<(), MpcNetworkError> {
+        let (outbound_send, outbound_recv) = mpsc::channel(OUTBOUND_BUFFER_SIZE);
+
+        // The king dials the peer who serves the exchange
+        let inbound = if self.local_party0() {
+            self.dial(outbound_recv).await?
+        } else {
+            self.serve(outbound_recv).await?
+        };
+
+        self.outbound = Some(outbound_send);
+        self.inbound = Some(inbound);
+
+        self.establish_session().await
+    }
+
+    /// Dial the peer and open the exchange stream, sending the messages from `outbound`
+    async fn dial(
+        &self,
+        outbound: mpsc::Receiver<WireMessage>,
+    ) -> Result<Streaming<WireMessage>, MpcNetworkError> {
+        // The peer may not be serving yet, retry until the connect timeout elapses
+        let deadline = Instant::now() + self.connect_timeout;
+        let channel = loop {
+            match self.peer_endpoint.connect().await {
+                Ok(channel) => break channel,
+                Err(err) if Instant::now() < deadline => {
+                    log::debug!("error dialing grpc peer, retrying: {err}");
+                    tokio::time::sleep(Duration::from_millis(CONNECT_RETRY_INTERVAL_MS)).await;
+                }
+                Err(err) => {
+                    log::error!("error dialing grpc peer: {err}");
+                    return Err(grpc_setup_error(err));
+                }
+            }
+        };
+
+        let mut client = GrpcClient::new(channel);
+        client.ready().await.map_err(grpc_setup_error)?;
+
+        let path = http::uri::PathAndQuery::from_static(EXCHANGE_PATH);
+        let response = client
+            .streaming(Request::new(outbound), path, ProstCodec::default())
+            .await
+            .map_err(|status| {
+                log::error!("error opening grpc exchange: {status}");
+                grpc_setup_error(status)
+            })?;
+
+        Ok(response.into_inner())
+    }
+
+    /// Serve the exchange and await the peer's stream, replying with the messages from
+    /// `outbound`
+    async fn serve(
+        &mut self,
+        outbound: mpsc::Receiver<WireMessage>,
+    ) -> Result<Streaming<WireMessage>, MpcNetworkError> {
+        let listener = TcpListener::bind(self.local_addr).await.map_err(|err| {
+            log::error!("error binding grpc server: {err}");
+            MpcNetworkError::ConnectionSetupError(SetupError::ServerSetupError)
+        })?;
+        let incoming = poll_fn(move |cx| {
+            listener
+                .poll_accept(cx)
+                .map(|res| Some(res.map(|(stream, _)| stream)))
+        });
+
+        let (inbound_send, inbound_recv) = oneshot::channel();
+        let (shutdown_send, shutdown_recv) = oneshot::channel::<()>();
+        let service = ExchangeServer::new(outbound, inbound_send);
+        let mut server = self.server.take().unwrap_or_default();
+
+        // The server shuts down once signalled or once the network is dropped
+        tokio::spawn(async move {
+            let shutdown = async {
+                let _ = shutdown_recv.await;
+            };
+            if let Err(err) = server
+                .add_service(service)
+                .serve_with_incoming_shutdown(incoming, shutdown)
+                .await
+            {
+                log::error!("error serving grpc exchange: {err}");
+            }
+        });
+        self.shutdown = Some(shutdown_send);
+
+        inbound_recv.await.map_err(|_| {
+            log::error!("grpc server stopped before the peer opened the exchange");
+            MpcNetworkError::ConnectionSetupError(SetupError::NoIncomingConnection)
+        })
+    }
+
+    /// Agree on a session ID with the peer, each party contributes a random nonce
+    async fn establish_session(&mut self) -> Result<(), MpcNetworkError> {
+        let my_nonce = Scalar::random(&mut thread_rng());
+        let hello = WireMessage {
+            session_id: Vec::new(),
+            body: Some(WireBody::Nonce(my_nonce.to_bytes_be())),
+        };
+        self.outbound
+            .as_mut()
+            .unwrap()
+            .send(hello)
+            .await
+            .map_err(|err| MpcNetworkError::SendError(err.to_string()))?;
+
+        let peer_hello = self
+            .inbound
+            .as_mut()
+            .unwrap()
+            .message()
+            .await
+            .map_err(|status| MpcNetworkError::RecvError(status.to_string()))?
+            .ok_or_else(|| MpcNetworkError::RecvError(ERR_STREAM_FINISHED_EARLY.to_string()))?;
+        let peer_nonce = match peer_hello.body {
+            Some(WireBody::Nonce(bytes)) => Scalar::from_be_bytes_mod_order(&bytes),
+            _ => {
+                return Err(MpcNetworkError::RecvError(
+                    ERR_INVALID_HANDSHAKE.to_string(),
+                ))
+            }
+        };
+
+        let session_id = if self.local_party0() {
+            derive_session_id(&my_nonce, &peer_nonce)
+        } else {
+            derive_session_id(&peer_nonce, &my_nonce)
+        };
+
+        self.session_id = Some(session_id);
+        Ok(())
+    }
+
+    /// Get the send side of the exchange stream
+    fn outbound(&mut self) -> Result<&mut mpsc::Sender<WireMessage>, MpcNetworkError> {
+        self.outbound
+            .as_mut()
+            .ok_or(MpcNetworkError::NetworkUninitialized)
+    }
+}
+
+#[async_trait]
+impl MpcNetwork for GrpcTwoPartyNet {
+    fn party_id(&self) -> PartyId {
+        self.party_id
+    }
+
+    fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+
+    async fn close(&mut self) -> Result<(), MpcNetworkError> {
+        self.assert_connected()?;
+
+        // Closing the channel ends the local side of the exchange stream
+        self.outbound.take().unwrap().close_channel();
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+
+        Ok(())
+    }
+}
+
+impl Stream for GrpcTwoPartyNet {
+    type Item = Result<NetworkOutbound, MpcNetworkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let session_id = self.session_id.unwrap_or_default();
+        let inbound = match self.inbound.as_mut() {
+            Some(inbound) => inbound,
+            None => return Poll::Ready(Some(Err(MpcNetworkError::NetworkUninitialized))),
+        };
+
+        let msg = match ready!(inbound.poll_next_unpin(cx)) {
+            Some(Ok(msg)) => msg,
+            Some(Err(status)) => {
+                return Poll::Ready(Some(Err(MpcNetworkError::RecvError(status.to_string()))))
+            }
+            None => {
+                return Poll::Ready(Some(Err(MpcNetworkError::RecvError(
+                    ERR_STREAM_FINISHED_EARLY.to_string(),
+                ))))
+            }
+        };
+
+        // Check that the message is tagged with the session ID
+        if msg.session_id != session_id {
+            return Poll::Ready(Some(Err(MpcNetworkError::RecvError(
+                ERR_SESSION_MISMATCH.to_string(),
+            ))));
+        }
+
+        let res = match msg.body {
+            Some(WireBody::Outbound(outbound)) => NetworkOutbound::try_from(outbound),
+            _ => Err(MpcNetworkError::SerializationError(
+                "expected an outbound message".to_string(),
+            )),
+        };
+        Poll::Ready(Some(res))
+    }
+}
+
+impl Sink<NetworkOutbound> for GrpcTwoPartyNet {
+    type Error = MpcNetworkError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outbound()?
+            .poll_ready(cx)
+            .map_err(|err| MpcNetworkError::SendError(err.to_string()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: NetworkOutbound) -> Result<(), Self::Error> {
+        let msg = WireMessage {
+            session_id: self.session_id.unwrap_or_default().to_vec(),
+            body: Some(WireBody::Outbound(OutboundMessage::from(msg))),
+        };
+
+        self.outbound()?
+            .start_send(msg)
+            .map_err(|err| MpcNetworkError::SendError(err.to_string()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outbound()?
+            .poll_flush_unpin(cx)
+            .map_err(|err| MpcNetworkError::SendError(err.to_string()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The stream is closed by `MpcNetwork::close`
+        self.poll_flush(cx)
+    }
+}
+
+/// Build a setup error from an error in the gRPC transport
+fn grpc_setup_error<E: ToString>(err: E) -> MpcNetworkError {
+    MpcNetworkError::ConnectionSetupError(SetupError::GrpcError(err.to_string()))
+}
+
+// ----------
+// | Server |
+// ----------
+
+/// The state of the exchange before the peer opens its stream
+type PendingExchange = (
+    mpsc::Receiver<WireMessage>,
+    oneshot::Sender<Streaming<WireMessage>>,
+);
+
+/// Serves the exchange, accepting a single stream from the peer
+#[derive(Clone)]
+struct ExchangeServer {
+    /// The local side of the exchange, taken by the first stream the peer opens
+    pending: Arc<Mutex<Option<PendingExchange>>>,
+}
+
+impl ExchangeServer {
+    /// Constructor
+    fn new(
+        outbound: mpsc::Receiver<WireMessage>,
+        inbound: oneshot::Sender<Streaming<WireMessage>>,
+    ) -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(Some((outbound, inbound)))),
+        }
+    }
+}
+
+impl NamedService for ExchangeServer {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+impl Service<Request<Streaming<WireMessage>>> for ExchangeServer {
+    type Response = Response<BoxStream<WireMessage>>;
+    type Error = Status;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Streaming<WireMessage>>) -> Self::Future {
+        let pending = self.pending.lock().expect("exchange lock poisoned").take();
+        Box::pin(async move {
+            let (outbound, inbound) =
+                pending.ok_or_else(|| Status::already_exists("exchange already open"))?;
+            inbound
+                .send(request.into_inner())
+                .map_err(|_| Status::unavailable("network closed"))?;
+
+            let stream: BoxStream<WireMessage> = Box::pin(outbound.map(Ok));
+            Ok(Response::new(stream))
+        })
+    }
+}
+
+impl<B> Service<http::Request<B>> for ExchangeServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move {
+            if req.uri().path() != EXCHANGE_PATH {
+                return Ok(Status::unimplemented("unknown method").to_http());
+            }
+
+            let mut grpc = GrpcServer::new(ProstCodec::default());
+            Ok(grpc.streaming(service, req).await)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{SinkExt, StreamExt};
+    use itertools::Itertools;
+
+    use crate::{
+        algebra::scalar::Scalar,
+        network::{MpcNetwork, NetworkOutbound, NetworkPayload},
+    };
+
+    use super::GrpcTwoPartyNet;
+
+    /// Tests exchanging messages over the gRPC transport
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_grpc_exchange() {
+        const N: u64 = 100;
+
+        let addr0 = "127.0.0.1:18160".parse().unwrap();
+        let addr1 = "127.0.0.1:18161".parse().unwrap();
+        let mut net0 = GrpcTwoPartyNet::new(0, addr0, addr1);
+        let mut net1 = GrpcTwoPartyNet::new(1, addr1, addr0);
+
+        let (res0, res1) = tokio::join!(net0.connect(), net1.connect());
+        res0.unwrap();
+        res1.unwrap();
+        assert!(net0.session_id().is_some());
+        assert_eq!(net0.session_id(), net1.session_id());
+
+        // Send in both directions
+        async fn send(net: &mut GrpcTwoPartyNet) {
+            for id in 0..N {
+                let msg = NetworkOutbound {
+                    result_id: id as usize,
+                    payload: NetworkPayload::Scalar(Scalar::from(id)),
+                };
+                net.send(msg).await.unwrap();
+            }
+        }
+        tokio::join!(send(&mut net0), send(&mut net1));
+
+        for net in [&mut net0, &mut net1] {
+            let mut received = Vec::new();
+            for _ in 0..N {
+                let msg = net.next().await.unwrap().unwrap();
+                let NetworkPayload::Scalar(scalar) = msg.payload else {
+                    panic!("unexpected payload");
+                };
+
+                assert_eq!(scalar, Scalar::from(msg.result_id as u64));
+                received.push(msg.result_id);
+            }
+
+            assert_eq!(received, (0..N as usize).collect_vec());
+        }
+
+        MpcNetwork::close(&mut net0).await.unwrap();
+        MpcNetwork::close(&mut net1).await.unwrap();
+    }
+}
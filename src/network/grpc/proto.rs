@@ -0,0 +1,362 @@
+//! Defines the protobuf messages exchanged over the gRPC transport and their conversions to
+//! and from the network's messages
+//!
+//! The messages correspond to the following schema:
+//!
+//! ```protobuf
+//! syntax = "proto3";
+//! package mpc_stark;
+//!
+//! service MpcNetwork {
+//!     rpc Exchange(stream WireMessage) returns (stream WireMessage);
+//! }
+//!
+//! message WireMessage {
+//!     bytes session_id = 1;
+//!     oneof body {
+//!         bytes nonce = 2;
+//!         Outbound outbound = 3;
+//!     }
+//! }
+//!
+//! message Outbound {
+//!     uint64 result_id = 1;
+//!     oneof payload {
+//!         bytes bytes = 2;
+//!         bytes scalar = 3;
+//!         ElementBatch scalar_batch = 4;
+//!         bytes point = 5;
+//!         ElementBatch point_batch = 6;
+//!         Coalesced coalesced = 7;
+//!         bytes custom = 8;
+//!         Openings openings = 9;
+//!     }
+//! }
+//!
+//! message ElementBatch {
+//!     repeated bytes elements = 1;
+//! }
+//!
+//! message Coalesced {
+//!     repeated Outbound messages = 1;
+//! }
+//!
+//! message Openings {
+//!     repeated uint64 result_ids = 1;
+//!     oneof values {
+//!         ElementBatch scalars = 2;
+//!         ElementBatch points = 3;
+//!     }
+//! }
+//! ```
+//!
+//! Scalars are encoded as 32 big endian bytes and points in compressed form
+
+use itertools::Itertools;
+
+use crate::{
+    algebra::{
+        scalar::{Scalar, SCALAR_BYTES},
+        stark_curve::StarkPoint,
+    },
+    error::MpcNetworkError,
+    network::{NetworkOutbound, NetworkPayload},
+};
+
+/// A message sent over the exchange stream
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct WireMessage {
+    /// The ID of the session the message belongs to, empty in the handshake
+    #[prost(bytes = "vec", tag = "1")]
+    pub session_id: Vec<u8>,
+    /// The body of the message
+    #[prost(oneof = "WireBody", tags = "2, 3")]
+    pub body: Option<WireBody>,
+}
+
+/// The body of a message sent over the exchange stream
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub(crate) enum WireBody {
+    /// The nonce a party contributes to the session ID in the handshake
+    #[prost(bytes = "vec", tag = "2")]
+    Nonce(Vec<u8>),
+    /// A message to the peer
+    #[prost(message, tag = "3")]
+    Outbound(OutboundMessage),
+}
+
+/// An outbound message to the peer
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct OutboundMessage {
+    /// The ID of the result the message is for
+    #[prost(uint64, tag = "1")]
+    pub result_id: u64,
+    /// The payload of the message
+    #[prost(oneof = "Payload", tags = "2, 3, 4, 5, 6, 7, 8, 9")]
+    pub payload: Option<Payload>,
+}
+
+/// The payload of an outbound message
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub(crate) enum Payload {
+    /// A byte value
+    #[prost(bytes = "vec", tag = "2")]
+    Bytes(Vec<u8>),
+    /// A scalar value
+    #[prost(bytes = "vec", tag = "3")]
+    Scalar(Vec<u8>),
+    /// A batch of scalar values
+    #[prost(message, tag = "4")]
+    ScalarBatch(ElementBatch),
+    /// A point on the curve
+    #[prost(bytes = "vec", tag = "5")]
+    Point(Vec<u8>),
+    /// A batch of points on the curve
+    #[prost(message, tag = "6")]
+    PointBatch(ElementBatch),
+    /// A batch of messages packed into a single wire message
+    #[prost(message, tag = "7")]
+    Coalesced(Coalesced),
+    /// A serialized value of a user-defined type
+    #[prost(bytes = "vec", tag = "8")]
+    Custom(Vec<u8>),
+    /// A batch of coalesced openings
+    #[prost(message, tag = "9")]
+    Openings(Openings),
+}
+
+/// A batch of encoded scalars or points
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ElementBatch {
+    /// The encoded elements
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub elements: Vec<Vec<u8>>,
+}
+
+/// A batch of messages packed into a single wire message
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Coalesced {
+    /// The packed messages
+    #[prost(message, repeated, tag = "1")]
+    pub messages: Vec<OutboundMessage>,
+}
+
+/// A batch of coalesced openings
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Openings {
+    /// The results the values are delivered to
+    #[prost(uint64, repeated, tag = "1")]
+    pub result_ids: Vec<u64>,
+    /// The opened values
+    #[prost(oneof = "OpeningValues", tags = "2, 3")]
+    pub values: Option<OpeningValues>,
+}
+
+/// The values of a batch of coalesced openings
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub(crate) enum OpeningValues {
+    /// A batch of encoded scalars
+    #[prost(message, tag = "2")]
+    Scalars(ElementBatch),
+    /// A batch of encoded points
+    #[prost(message, tag = "3")]
+    Points(ElementBatch),
+}
+
+// ---------------
+// | Conversions |
+// ---------------
+
+impl From<NetworkOutbound> for OutboundMessage {
+    fn from(msg: NetworkOutbound) -> Self {
+        let payload = match msg.payload {
+            NetworkPayload::Bytes(bytes) => Payload::Bytes(bytes),
+            NetworkPayload::Scalar(scalar) => Payload::Scalar(scalar.to_bytes_be()),
+            NetworkPayload::ScalarBatch(scalars) => Payload::ScalarBatch(encode_scalars(&scalars)),
+            NetworkPayload::Point(point) => Payload::Point(point.to_bytes()),
+            NetworkPayload::PointBatch(points) => Payload::PointBatch(encode_points(&points)),
+            NetworkPayload::Custom(bytes) => Payload::Custom(bytes),
+            NetworkPayload::Coalesced(msgs) => Payload::Coalesced(Coalesced {
+                messages: msgs.into_iter().map(Into::into).collect(),
+            }),
+            NetworkPayload::Openings { result_ids, values } => {
+                let values = match *values {
+                    NetworkPayload::ScalarBatch(scalars) => {
+                        OpeningValues::Scalars(encode_scalars(&scalars))
+                    }
+                    NetworkPayload::PointBatch(points) => {
+                        OpeningValues::Points(encode_points(&points))
+                    }
+                    _ => unreachable!("openings are batched as scalars or points"),
+                };
+
+                Payload::Openings(Openings {
+                    result_ids: result_ids.into_iter().map(|id| id as u64).collect(),
+                    values: Some(values),
+                })
+            }
+        };
+
+        Self {
+            result_id: msg.result_id as u64,
+            payload: Some(payload),
+        }
+    }
+}
+
+impl TryFrom<OutboundMessage> for NetworkOutbound {
+    type Error = MpcNetworkError;
+
+    fn try_from(msg: OutboundMessage) -> Result<Self, Self::Error> {
+        let payload = match msg
+            .payload
+            .ok_or_else(|| deserialization_error("missing payload"))?
+        {
+            Payload::Bytes(bytes) => NetworkPayload::Bytes(bytes),
+            Payload::Scalar(bytes) => NetworkPayload::Scalar(decode_scalar(&bytes)?),
+            Payload::ScalarBatch(batch) => NetworkPayload::ScalarBatch(decode_scalars(&batch)?),
+            Payload::Point(bytes) => NetworkPayload::Point(decode_point(&bytes)?),
+            Payload::PointBatch(batch) => NetworkPayload::PointBatch(decode_points(&batch)?),
+            Payload::Custom(bytes) => NetworkPayload::Custom(bytes),
+            Payload::Coalesced(coalesced) => NetworkPayload::Coalesced(
+                coalesced
+                    .messages
+                    .into_iter()
+                    .map(NetworkOutbound::try_from)
+                    .try_collect()?,
+            ),
+            Payload::Openings(openings) => {
+                let values = match openings
+                    .values
+                    .ok_or_else(|| deserialization_error("missing opened values"))?
+                {
+                    OpeningValues::Scalars(batch) => {
+                        NetworkPayload::ScalarBatch(decode_scalars(&batch)?)
+                    }
+                    OpeningValues::Points(batch) => {
+                        NetworkPayload::PointBatch(decode_points(&batch)?)
+                    }
+                };
+
+                NetworkPayload::Openings {
+                    result_ids: openings
+                        .result_ids
+                        .into_iter()
+                        .map(|id| id as usize)
+                        .collect(),
+                    values: Box::new(values),
+                }
+            }
+        };
+
+        Ok(Self {
+            result_id: msg.result_id as usize,
+            payload,
+        })
+    }
+}
+
+/// Encode a batch of scalars
+fn encode_scalars(scalars: &[Scalar]) -> ElementBatch {
+    ElementBatch {
+        elements: scalars.iter().map(Scalar::to_bytes_be).collect(),
+    }
+}
+
+/// Encode a batch of points
+fn encode_points(points: &[StarkPoint]) -> ElementBatch {
+    ElementBatch {
+        elements: points.iter().map(StarkPoint::to_bytes).collect(),
+    }
+}
+
+/// Decode a batch of scalars
+fn decode_scalars(batch: &ElementBatch) -> Result<Vec<Scalar>, MpcNetworkError> {
+    batch
+        .elements
+        .iter()
+        .map(|bytes| decode_scalar(bytes))
+        .try_collect()
+}
+
+/// Decode a batch of points
+fn decode_points(batch: &ElementBatch) -> Result<Vec<StarkPoint>, MpcNetworkError> {
+    batch
+        .elements
+        .iter()
+        .map(|bytes| decode_point(bytes))
+        .try_collect()
+}
+
+/// Decode a scalar from its big endian encoding
+fn decode_scalar(bytes: &[u8]) -> Result<Scalar, MpcNetworkError> {
+    if bytes.len() != SCALAR_BYTES {
+        return Err(deserialization_error("invalid scalar length"));
+    }
+
+    Ok(Scalar::from_be_bytes_mod_order(bytes))
+}
+
+/// Decode a point from its compressed encoding
+fn decode_point(bytes: &[u8]) -> Result<StarkPoint, MpcNetworkError> {
+    StarkPoint::from_bytes(bytes).map_err(|err| deserialization_error(&err.to_string()))
+}
+
+/// Build an error for a message that could not be decoded
+fn deserialization_error(msg: &str) -> MpcNetworkError {
+    MpcNetworkError::SerializationError(format!("invalid protobuf message: {msg}"))
+}
+
+#[cfg(test)]
+mod test {
+    use prost::Message;
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::{scalar::Scalar, stark_curve::StarkPoint},
+        network::{NetworkOutbound, NetworkPayload},
+    };
+
+    use super::OutboundMessage;
+
+    /// Tests encoding each kind of payload and decoding it back
+    #[test]
+    fn test_payload_roundtrip() {
+        let mut rng = thread_rng();
+        let scalar = Scalar::random(&mut rng);
+        let point = StarkPoint::generator() * Scalar::random(&mut rng);
+
+        let payloads = vec![
+            NetworkPayload::Bytes(vec![1, 2, 3]),
+            NetworkPayload::Scalar(scalar),
+            NetworkPayload::ScalarBatch(vec![scalar, Scalar::one()]),
+            NetworkPayload::Point(point),
+            NetworkPayload::PointBatch(vec![point, StarkPoint::identity()]),
+            NetworkPayload::Coalesced(vec![NetworkOutbound {
+                result_id: 7,
+                payload: NetworkPayload::Scalar(scalar),
+            }]),
+            NetworkPayload::Openings {
+                result_ids: vec![8, 11],
+                values: Box::new(NetworkPayload::PointBatch(vec![point, point])),
+            },
+        ];
+
+        for (id, payload) in payloads.into_iter().enumerate() {
+            let msg = NetworkOutbound {
+                result_id: id,
+                payload,
+            };
+
+            let bytes = OutboundMessage::from(msg.clone()).encode_to_vec();
+            let decoded = OutboundMessage::decode(bytes.as_slice()).unwrap();
+            let res = NetworkOutbound::try_from(decoded).unwrap();
+
+            assert_eq!(res.result_id, msg.result_id);
+            assert_eq!(
+                serde_json::to_string(&res.payload).unwrap(),
+                serde_json::to_string(&msg.payload).unwrap()
+            );
+        }
+    }
+}
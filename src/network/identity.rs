@@ -0,0 +1,165 @@
+//! Defines the static identity keys of the parties and the signatures used to authenticate
+//! the peer during the network handshake
+//!
+//! Identities are Schnorr keypairs over the Stark curve
+
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::algebra::{scalar::Scalar, stark_curve::StarkPoint};
+
+use super::{PartyId, SessionId};
+
+/// The domain separator used when computing a signature challenge
+const SIGNATURE_DOMAIN: &[u8] = b"mpc-stark-identity-signature";
+/// The domain separator used when computing the handshake transcript
+const HANDSHAKE_DOMAIN: &[u8] = b"mpc-stark-handshake";
+
+/// A static identity keypair for a party
+#[derive(Clone)]
+pub struct IdentityKeypair {
+    /// The secret signing key
+    secret_key: Scalar,
+    /// The public verification key, `secret_key * G`
+    public_key: StarkPoint,
+}
+
+impl IdentityKeypair {
+    /// Generate a new random keypair
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self::from_secret_key(Scalar::random(rng))
+    }
+
+    /// Construct a keypair from a secret key
+    pub fn from_secret_key(secret_key: Scalar) -> Self {
+        Self {
+            secret_key,
+            public_key: StarkPoint::generator() * secret_key,
+        }
+    }
+
+    /// Get the public key of the keypair
+    pub fn public_key(&self) -> StarkPoint {
+        self.public_key
+    }
+
+    /// Sign a message with the keypair
+    pub fn sign<R: RngCore + CryptoRng>(&self, message: &[u8], rng: &mut R) -> IdentitySignature {
+        let nonce = Scalar::random(rng);
+        let commitment = StarkPoint::generator() * nonce;
+        let challenge = signature_challenge(&commitment, &self.public_key, message);
+
+        IdentitySignature {
+            commitment,
+            response: nonce + challenge * self.secret_key,
+        }
+    }
+}
+
+/// A Schnorr signature under an identity key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdentitySignature {
+    /// The commitment to the signing nonce, `R = kG`
+    commitment: StarkPoint,
+    /// The response to the challenge, `s = k + e * sk`
+    response: Scalar,
+}
+
+impl IdentitySignature {
+    /// Verify the signature on a message under the given public key
+    pub fn verify(&self, public_key: &StarkPoint, message: &[u8]) -> bool {
+        let challenge = signature_challenge(&self.commitment, public_key, message);
+        StarkPoint::generator() * self.response == self.commitment + *public_key * challenge
+    }
+}
+
+/// Compute the Fiat-Shamir challenge for a signature
+fn signature_challenge(commitment: &StarkPoint, public_key: &StarkPoint, message: &[u8]) -> Scalar {
+    let mut hasher = Sha3_256::new();
+    hasher.update(SIGNATURE_DOMAIN);
+    hasher.update(commitment.to_bytes());
+    hasher.update(public_key.to_bytes());
+    hasher.update(message);
+
+    Scalar::from_be_bytes_mod_order(&hasher.finalize())
+}
+
+/// The first message sent by each party in the identity handshake
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct HandshakeHello {
+    /// The party ID of the sender
+    pub party_id: PartyId,
+    /// The identity public key of the sender
+    pub public_key: StarkPoint,
+}
+
+/// Compute the transcript of a handshake that both parties sign
+///
+/// The transcript binds both parties' identities to the session ID so that signatures cannot
+/// be replayed across sessions
+pub(crate) fn handshake_transcript(
+    session_id: &SessionId,
+    party0: &HandshakeHello,
+    party1: &HandshakeHello,
+) -> Vec<u8> {
+    let mut hasher = Sha3_256::new();
+    hasher.update(HANDSHAKE_DOMAIN);
+    hasher.update(session_id);
+    for hello in [party0, party1] {
+        hasher.update(hello.party_id.to_le_bytes());
+        hasher.update(hello.public_key.to_bytes());
+    }
+
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use rand::thread_rng;
+
+    use crate::{algebra::scalar::Scalar, network::derive_session_id};
+
+    use super::{handshake_transcript, HandshakeHello, IdentityKeypair};
+
+    /// Tests signing and verifying a message
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = thread_rng();
+        let keypair = IdentityKeypair::random(&mut rng);
+        let other_keypair = IdentityKeypair::random(&mut rng);
+
+        let sig = keypair.sign(b"message", &mut rng);
+        assert!(sig.verify(&keypair.public_key(), b"message"));
+        assert!(!sig.verify(&keypair.public_key(), b"other message"));
+        assert!(!sig.verify(&other_keypair.public_key(), b"message"));
+    }
+
+    /// Tests that the handshake transcript binds the session and the parties' order
+    #[test]
+    fn test_handshake_transcript() {
+        let mut rng = thread_rng();
+        let hello0 = HandshakeHello {
+            party_id: 0,
+            public_key: IdentityKeypair::random(&mut rng).public_key(),
+        };
+        let hello1 = HandshakeHello {
+            party_id: 1,
+            public_key: IdentityKeypair::random(&mut rng).public_key(),
+        };
+
+        let session_id = derive_session_id(&Scalar::random(&mut rng), &Scalar::random(&mut rng));
+        let other_session_id =
+            derive_session_id(&Scalar::random(&mut rng), &Scalar::random(&mut rng));
+
+        let transcript = handshake_transcript(&session_id, &hello0, &hello1);
+        assert_ne!(
+            transcript,
+            handshake_transcript(&other_session_id, &hello0, &hello1)
+        );
+        assert_ne!(
+            transcript,
+            handshake_transcript(&session_id, &hello1, &hello0)
+        );
+    }
+}
@@ -13,6 +13,9 @@ use crate::{error::MpcNetworkError, PARTY0};
 
 use super::{MpcNetwork, NetworkOutbound, PartyId};
 
+/// Error message emitted when sending to a peer whose half of the connection is dropped
+const ERR_PEER_DROPPED: &str = "peer dropped the connection";
+
 /// A dummy MPC network that never receives messages
 #[derive(Default)]
 pub struct NoRecvNetwork;
@@ -126,10 +129,8 @@ impl Stream for MockNetwork {
     type Item = Result<NetworkOutbound, MpcNetworkError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Box::pin(self.mock_conn.recv())
-            .as_mut()
-            .poll(cx)
-            .map(|value| Some(Ok(value)))
+        // The stream ends once the peer's half of the connection is dropped
+        self.mock_conn.recv.poll_recv(cx).map(|value| value.map(Ok))
     }
 }
 
@@ -140,9 +141,11 @@ impl Sink<NetworkOutbound> for MockNetwork {
         Poll::Ready(Ok(()))
     }
 
-    fn start_send(mut self: Pin<&mut Self>, item: NetworkOutbound) -> Result<(), Self::Error> {
-        self.mock_conn.send(item);
-        Ok(())
+    fn start_send(self: Pin<&mut Self>, item: NetworkOutbound) -> Result<(), Self::Error> {
+        self.mock_conn
+            .send
+            .send(item)
+            .map_err(|_| MpcNetworkError::SendError(ERR_PEER_DROPPED.to_string()))
     }
 
     fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -0,0 +1,234 @@
+//! Defines a single bidirectional stream of a QUIC connection, framing messages written to
+//! and read from it
+
+use std::convert::TryInto;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use quinn::{RecvStream, SendStream};
+
+use crate::error::MpcNetworkError;
+
+use super::{
+    stream_buffer::BufferWithCursor, wire_auth::StreamAuthenticator, NetworkOutbound, SessionId,
+    BYTES_PER_U64, ERR_READ_MESSAGE_LENGTH, ERR_SESSION_MISMATCH, ERR_STREAM_FINISHED_EARLY,
+    SESSION_ID_BYTES,
+};
+
+/// A bidirectional QUIC stream, along with the buffers that make reads and writes on it
+/// cancellation safe
+#[derive(Debug)]
+pub(crate) struct QuicStream {
+    /// The send side of the stream
+    send: SendStream,
+    /// The receive side of the stream
+    recv: RecvStream,
+    /// A buffered message length read from the stream
+    ///
+    /// In the case that the whole message is not available yet, reads may block
+    /// and the `read_message` future may be cancelled by the executor.
+    /// We buffer the message length to avoid re-reading the message length incorrectly from
+    /// the stream
+    buffered_message_length: Option<u64>,
+    /// A buffered partial message read from the stream
+    ///
+    /// This buffer exists to provide cancellation safety to a `read` future as the underlying `quinn`
+    /// stream is not cancellation safe, i.e. if a `ReadBuf` future is dropped, the buffer is dropped with
+    /// it and the partially read data is skipped
+    buffered_inbound: Option<BufferWithCursor>,
+    /// A buffered partial message written to the stream
+    buffered_outbound: Option<BufferWithCursor>,
+    /// The buffer that messages are read into, frames read from the stream are split off of
+    /// it so that its allocation is reused once they are dropped
+    recv_buf: BytesMut,
+    /// The buffer that messages are serialized into, reused once a message is written
+    send_buf: BytesMut,
+    /// Sequences and tags the messages on the stream, set once the session keys are agreed
+    auth: Option<StreamAuthenticator>,
+}
+
+impl QuicStream {
+    /// Constructor
+    pub fn new(send: SendStream, recv: RecvStream) -> Self {
+        Self {
+            send,
+            recv,
+            buffered_message_length: None,
+            buffered_inbound: None,
+            buffered_outbound: None,
+            recv_buf: BytesMut::new(),
+            send_buf: BytesMut::new(),
+            auth: None,
+        }
+    }
+
+    /// Authenticate the messages sent and received on the stream from here on
+    pub fn set_authenticator(&mut self, auth: StreamAuthenticator) {
+        self.auth = Some(auth);
+    }
+
+    /// Whether a message is buffered for writing, a stream holds at most one at a time
+    pub fn has_buffered_outbound(&self) -> bool {
+        self.buffered_outbound.is_some()
+    }
+
+    /// Finish the send side of the stream
+    pub async fn finish(&mut self) -> Result<(), MpcNetworkError> {
+        self.send
+            .finish()
+            .await
+            .map_err(|_| MpcNetworkError::ConnectionTeardownError)
+    }
+
+    /// Prefix a message with its length and buffer it for writing
+    pub fn buffer_frame(&mut self, bytes: &[u8]) {
+        let mut frame = self.take_send_buf();
+        frame.put_u64_le(bytes.len() as u64);
+        frame.put_slice(bytes);
+
+        self.buffered_outbound = Some(BufferWithCursor::new(frame));
+    }
+
+    /// Serialize a message, tag it with the session ID and authenticate it, then buffer it
+    /// for writing
+    ///
+    /// The message is serialized in place behind its length prefix and sequence number, so
+    /// that the frame is built without copying the serialized message
+    pub fn buffer_message(
+        &mut self,
+        msg: &NetworkOutbound,
+        session_id: &SessionId,
+    ) -> Result<(), MpcNetworkError> {
+        let mut frame = self.take_send_buf();
+        frame.put_u64_le(0 /* length */);
+        if self.auth.is_some() {
+            frame.put_u64_le(0 /* sequence number */);
+        }
+
+        let payload_start = frame.len();
+        frame.put_slice(session_id);
+        serde_json::to_writer((&mut frame).writer(), msg)
+            .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))?;
+
+        if let Some(auth) = self.auth.as_mut() {
+            auth.seal_in_place(&mut frame, payload_start);
+        }
+
+        let len = (frame.len() - BYTES_PER_U64) as u64;
+        frame[..BYTES_PER_U64].copy_from_slice(&len.to_le_bytes());
+        self.buffered_outbound = Some(BufferWithCursor::new(frame));
+
+        Ok(())
+    }
+
+    /// Take the buffer that outbound frames are built in
+    fn take_send_buf(&mut self) -> BytesMut {
+        let mut buf = std::mem::take(&mut self.send_buf);
+        buf.clear();
+        buf
+    }
+
+    /// Write the current buffer to the stream
+    pub async fn write_bytes(&mut self) -> Result<(), MpcNetworkError> {
+        // If no pending writes are available, return
+        if self.buffered_outbound.is_none() {
+            return Ok(());
+        }
+
+        // While the outbound buffer has elements remaining, write them
+        let buf = self.buffered_outbound.as_mut().unwrap();
+        while !buf.is_depleted() {
+            let bytes_written = self
+                .send
+                .write(buf.get_remaining())
+                .await
+                .map_err(|e| MpcNetworkError::SendError(e.to_string()))?;
+
+            buf.advance_cursor(bytes_written);
+        }
+
+        // Keep the buffer to build the next frame in
+        self.send_buf = self.buffered_outbound.take().unwrap().into_inner();
+        Ok(())
+    }
+
+    /// Read exactly `n` bytes from the stream
+    ///
+    /// The bytes are split off of the stream's receive buffer, whose allocation is reused
+    /// once they are dropped
+    async fn read_bytes(&mut self, num_bytes: usize) -> Result<Bytes, MpcNetworkError> {
+        // Allocate a buffer for the next message if one does not already exist
+        if self.buffered_inbound.is_none() {
+            let mut buf = std::mem::take(&mut self.recv_buf);
+            buf.resize(num_bytes, 0);
+            self.buffered_inbound = Some(BufferWithCursor::new(buf));
+        }
+
+        // Read until the buffer is full
+        let read_buffer = self.buffered_inbound.as_mut().unwrap();
+        while !read_buffer.is_depleted() {
+            let bytes_read = self
+                .recv
+                .read(read_buffer.get_remaining())
+                .await
+                .map_err(|e| MpcNetworkError::RecvError(e.to_string()))?
+                .ok_or(MpcNetworkError::RecvError(
+                    ERR_STREAM_FINISHED_EARLY.to_string(),
+                ))?;
+
+            read_buffer.advance_cursor(bytes_read);
+        }
+
+        // Take ownership of the buffer, and reset the buffered message to `None`
+        let mut buf = self.buffered_inbound.take().unwrap().into_inner();
+        let bytes = buf.split().freeze();
+        self.recv_buf = buf;
+
+        Ok(bytes)
+    }
+
+    /// Read a message length from the stream
+    async fn read_message_length(&mut self) -> Result<u64, MpcNetworkError> {
+        let read_buffer = self.read_bytes(BYTES_PER_U64).await?;
+        Ok(u64::from_le_bytes(read_buffer[..].try_into().map_err(
+            |_| MpcNetworkError::SerializationError(ERR_READ_MESSAGE_LENGTH.to_string()),
+        )?))
+    }
+
+    /// Receive a message tagged with the given session ID from the peer
+    pub async fn receive_message(
+        &mut self,
+        session_id: &SessionId,
+    ) -> Result<NetworkOutbound, MpcNetworkError> {
+        let frame = self.receive_frame().await?;
+        let bytes = match self.auth.as_mut() {
+            Some(auth) => auth.open(&frame)?,
+            None => &frame,
+        };
+
+        // Check that the frame is tagged with the session ID
+        if bytes.len() < SESSION_ID_BYTES || bytes[..SESSION_ID_BYTES] != session_id[..] {
+            return Err(MpcNetworkError::RecvError(ERR_SESSION_MISMATCH.to_string()));
+        }
+
+        // Deserialize the message
+        serde_json::from_slice(&bytes[SESSION_ID_BYTES..])
+            .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))
+    }
+
+    /// Receive a length-prefixed frame from the peer
+    pub async fn receive_frame(&mut self) -> Result<Bytes, MpcNetworkError> {
+        // Read the message length from the buffer if available
+        if self.buffered_message_length.is_none() {
+            self.buffered_message_length = Some(self.read_message_length().await?);
+        }
+
+        // Read the data from the stream
+        let len = self.buffered_message_length.unwrap();
+        let bytes = self.read_bytes(len as usize).await?;
+
+        // Reset the message length buffer after the data has been pulled from the stream
+        self.buffered_message_length = None;
+
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,769 @@
+//! Defines a network mode in which both parties dial out to an untrusted relay that pairs
+//! them and forwards their frames, so that the parties may run an MPC when neither can accept
+//! inbound connections
+//!
+//! The relay only learns the rendezvous ID the parties join under and the size and timing of
+//! their frames. The parties agree on keys with an ephemeral Diffie-Hellman exchange that they
+//! authenticate with their identity keys, and encrypt all subsequent frames under
+//! ChaCha20-Poly1305. Each party periodically updates the key it sends frames under, marking
+//! the last frame under a key so its peer follows, so that a key compromised mid-session
+//! exposes only the frames sent under it
+
+mod server;
+
+pub use server::RelayServer;
+
+use std::{
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use futures::{channel::mpsc, ready, Sink, SinkExt, Stream, StreamExt};
+use rand::thread_rng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+};
+use tracing::log;
+use zeroize::Zeroizing;
+
+use crate::{
+    algebra::{scalar::Scalar, stark_curve::StarkPoint},
+    error::{MpcNetworkError, SetupError},
+    PARTY0,
+};
+
+use super::{
+    identity::{handshake_transcript, HandshakeHello},
+    IdentityKeypair, IdentitySignature, MpcNetwork, NetworkOutbound, PartyId, SessionId,
+    BYTES_PER_U64, ERR_STREAM_FINISHED_EARLY,
+};
+
+/// The number of bytes in a rendezvous ID
+pub const ROOM_ID_BYTES: usize = 32;
+/// The ID of the rendezvous the parties join at the relay
+pub type RoomId = [u8; ROOM_ID_BYTES];
+
+/// The number of bytes in the join frame a party sends the relay, the room ID followed by the
+/// party ID
+const JOIN_FRAME_BYTES: usize = ROOM_ID_BYTES + BYTES_PER_U64;
+/// The maximum size of a frame read from the relay
+const MAX_FRAME_BYTES: u64 = 1 << 30; // 1 GiB
+/// The number of frames buffered in each direction before the network waits on the socket
+const FRAME_BUFFER_SIZE: usize = 1024;
+/// The domain separator used when deriving a room ID from the parties' identities
+const ROOM_ID_DOMAIN: &[u8] = b"mpc-stark-relay-room";
+/// The domain separator used when deriving the session ID from the ephemeral keys
+const RELAY_SESSION_DOMAIN: &[u8] = b"mpc-stark-relay-session";
+/// The domain separator used when deriving the frame keys from the shared secret
+const FRAME_KEY_DOMAIN: &[u8] = b"mpc-stark-relay-frame-key";
+/// The domain separator used when deriving the next frame key from the current one
+const REKEY_DOMAIN: &[u8] = b"mpc-stark-relay-rekey";
+/// The default number of frames sent under a key before it is updated
+const DEFAULT_REKEY_FRAMES: u64 = 1 << 20;
+/// The default number of plaintext bytes sent under a key before it is updated
+const DEFAULT_REKEY_BYTES: u64 = 1 << 32; // 4 GiB
+/// The header of a frame after which the sender keeps its key
+const FRAME_HEADER_DATA: u8 = 0;
+/// The header of the last frame the sender sends under its key
+const FRAME_HEADER_KEY_UPDATE: u8 = 1;
+/// Error message emitted when a frame from the relay exceeds the maximum frame size
+const ERR_FRAME_TOO_LARGE: &str = "frame exceeds the maximum frame size";
+/// Error message emitted when a frame fails to decrypt
+const ERR_FRAME_DECRYPTION: &str = "frame failed to decrypt";
+/// Error message emitted when a decrypted frame has an unknown header
+const ERR_FRAME_HEADER: &str = "frame has an invalid header";
+
+/// Derive the room ID two parties join at the relay from their identity keys
+pub fn derive_room_id(party0_key: &StarkPoint, party1_key: &StarkPoint) -> RoomId {
+    let mut hasher = Sha3_256::new();
+    hasher.update(ROOM_ID_DOMAIN);
+    hasher.update(party0_key.to_bytes());
+    hasher.update(party1_key.to_bytes());
+
+    hasher.finalize().into()
+}
+
+/// The amount of traffic a party sends under a frame key before updating it, the key is
+/// updated once either limit is reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RekeyInterval {
+    /// The number of frames sent under a key
+    pub frames: u64,
+    /// The number of plaintext bytes sent under a key
+    pub bytes: u64,
+}
+
+impl Default for RekeyInterval {
+    fn default() -> Self {
+        Self {
+            frames: DEFAULT_REKEY_FRAMES,
+            bytes: DEFAULT_REKEY_BYTES,
+        }
+    }
+}
+
+/// Implements an MpcNetwork on top of a connection through an untrusted relay
+///
+/// Both parties must know each other's identity keys ahead of time, these authenticate the
+/// key exchange so that the relay can neither read nor forge frames
+pub struct RelayTwoPartyNet {
+    /// The index of the local party in the participants
+    party_id: PartyId,
+    /// The address of the relay
+    relay_addr: SocketAddr,
+    /// The identity keypair of the local party
+    identity: IdentityKeypair,
+    /// The identity key the peer must authenticate with
+    peer_identity: StarkPoint,
+    /// The rendezvous ID the parties join at the relay
+    room_id: RoomId,
+    /// The ID of the session, agreed on when connecting
+    session_id: Option<SessionId>,
+    /// The amount of traffic sent under a frame key before it is updated
+    rekey_interval: RekeyInterval,
+    /// Encrypts frames sent to the peer
+    send_cipher: Option<FrameCipher>,
+    /// Decrypts frames received from the peer
+    recv_cipher: Option<FrameCipher>,
+    /// The frames queued for the writer task
+    outbound: Option<mpsc::Sender<Vec<u8>>>,
+    /// The frames read by the reader task
+    inbound: Option<mpsc::Receiver<Result<Vec<u8>, MpcNetworkError>>>,
+}
+
+impl RelayTwoPartyNet {
+    /// Create a new network, do not connect the network yet
+    ///
+    /// The parties join the room derived from their identity keys unless another is set with
+    /// `with_room_id`
+    pub fn new(
+        party_id: PartyId,
+        relay_addr: SocketAddr,
+        identity: IdentityKeypair,
+        peer_identity: StarkPoint,
+    ) -> Self {
+        let room_id = if party_id == PARTY0 {
+            derive_room_id(&identity.public_key(), &peer_identity)
+        } else {
+            derive_room_id(&peer_identity, &identity.public_key())
+        };
+
+        Self {
+            party_id,
+            relay_addr,
+            identity,
+            peer_identity,
+            room_id,
+            session_id: None,
+            rekey_interval: RekeyInterval::default(),
+            send_cipher: None,
+            recv_cipher: None,
+            outbound: None,
+            inbound: None,
+        }
+    }
+
+    /// Set the room the parties join at the relay, e.g. to run concurrent sessions between
+    /// the same parties
+    pub fn with_room_id(mut self, room_id: RoomId) -> Self {
+        self.room_id = room_id;
+        self
+    }
+
+    /// Set the amount of traffic the local party sends under a frame key before updating it
+    pub fn with_rekey_interval(mut self, interval: RekeyInterval) -> Self {
+        self.rekey_interval = interval;
+        self
+    }
+
+    /// Returns true if the local party is party 0
+    fn local_party0(&self) -> bool {
+        self.party_id == PARTY0
+    }
+
+    /// Returns an error if the network is not connected
+    fn assert_connected(&self) -> Result<(), MpcNetworkError> {
+        if self.outbound.is_some() {
+            Ok(())
+        } else {
+            Err(MpcNetworkError::NetworkUninitialized)
+        }
+    }
+
+    /// Join the room at the relay and establish an encrypted channel with the peer
+    pub async fn connect(&mut self) -> Result<(), MpcNetworkError> {
+        let mut stream = TcpStream::connect(self.relay_addr).await.map_err(|err| {
+            log::error!("error connecting to relay: {err}");
+            relay_setup_error(err)
+        })?;
+        stream.set_nodelay(true).map_err(relay_setup_error)?;
+
+        let mut join = self.room_id.to_vec();
+        join.extend_from_slice(&self.party_id.to_le_bytes());
+        stream.write_all(&join).await.map_err(relay_setup_error)?;
+
+        self.key_exchange(&mut stream).await?;
+
+        // Hand the socket to reader and writer tasks so that reads and writes on the network
+        // are cancellation safe
+        let (read_half, write_half) = stream.into_split();
+        let (outbound_send, outbound_recv) = mpsc::channel(FRAME_BUFFER_SIZE);
+        let (inbound_send, inbound_recv) = mpsc::channel(FRAME_BUFFER_SIZE);
+        tokio::spawn(write_frames(write_half, outbound_recv));
+        tokio::spawn(read_frames(read_half, inbound_send));
+
+        self.outbound = Some(outbound_send);
+        self.inbound = Some(inbound_recv);
+        Ok(())
+    }
+
+    /// Agree on frame keys with the peer and authenticate the peer's identity
+    ///
+    /// The parties exchange ephemeral keys, derive the session ID and frame keys from them,
+    /// then each signs the handshake transcript with its identity key and sends the signature
+    /// as its first encrypted frame
+    async fn key_exchange(&mut self, stream: &mut TcpStream) -> Result<(), MpcNetworkError> {
+        let mut rng = thread_rng();
+        let ephemeral_key = Scalar::random(&mut rng);
+
+        let my_hello = RelayHello {
+            party_id: self.party_id,
+            public_key: self.identity.public_key(),
+            ephemeral_key: StarkPoint::generator() * ephemeral_key,
+        };
+        write_message(stream, &my_hello).await?;
+        let peer_hello: RelayHello = read_message(stream).await?;
+
+        if peer_hello.party_id == self.party_id || peer_hello.public_key != self.peer_identity {
+            log::error!("peer joined the relay with an unexpected identity");
+            return Err(MpcNetworkError::ConnectionSetupError(
+                SetupError::PeerAuthenticationError,
+            ));
+        }
+
+        // Derive the session ID and a key for each direction from the ephemeral keys
+        let (hello0, hello1) = if self.local_party0() {
+            (&my_hello, &peer_hello)
+        } else {
+            (&peer_hello, &my_hello)
+        };
+        let session_id = relay_session_id(&hello0.ephemeral_key, &hello1.ephemeral_key);
+        let shared_secret = peer_hello.ephemeral_key * ephemeral_key;
+
+        let key01 = frame_key(&shared_secret, &session_id, 0);
+        let key10 = frame_key(&shared_secret, &session_id, 1);
+        let (send_key, recv_key) = if self.local_party0() {
+            (key01, key10)
+        } else {
+            (key10, key01)
+        };
+        let mut send_cipher = FrameCipher::new(send_key, self.rekey_interval);
+        let mut recv_cipher = FrameCipher::new(recv_key, self.rekey_interval);
+
+        // Sign the transcript and verify the peer's signature
+        let transcript = handshake_transcript(
+            &session_id,
+            &HandshakeHello::from(hello0),
+            &HandshakeHello::from(hello1),
+        );
+        let my_sig = self.identity.sign(&transcript, &mut rng);
+        let sig_bytes = serde_json::to_vec(&my_sig)
+            .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))?;
+        write_frame(stream, &send_cipher.seal(&sig_bytes)).await?;
+
+        let peer_sig_bytes = recv_cipher.open(&read_frame(stream).await?).map_err(|_| {
+            MpcNetworkError::ConnectionSetupError(SetupError::PeerAuthenticationError)
+        })?;
+        let peer_sig: IdentitySignature = serde_json::from_slice(&peer_sig_bytes)
+            .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))?;
+        if !peer_sig.verify(&self.peer_identity, &transcript) {
+            log::error!("invalid peer signature on relay handshake transcript");
+            return Err(MpcNetworkError::ConnectionSetupError(
+                SetupError::PeerAuthenticationError,
+            ));
+        }
+
+        self.session_id = Some(session_id);
+        self.send_cipher = Some(send_cipher);
+        self.recv_cipher = Some(recv_cipher);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MpcNetwork for RelayTwoPartyNet {
+    fn party_id(&self) -> PartyId {
+        self.party_id
+    }
+
+    fn peer_identity(&self) -> Option<StarkPoint> {
+        self.session_id.map(|_| self.peer_identity)
+    }
+
+    fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+
+    async fn close(&mut self) -> Result<(), MpcNetworkError> {
+        self.assert_connected()?;
+
+        // The writer task shuts down the socket once it has written the queued frames
+        self.outbound.take().unwrap().close_channel();
+        Ok(())
+    }
+}
+
+impl Stream for RelayTwoPartyNet {
+    type Item = Result<NetworkOutbound, MpcNetworkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let inbound = match self.inbound.as_mut() {
+            Some(inbound) => inbound,
+            None => return Poll::Ready(Some(Err(MpcNetworkError::NetworkUninitialized))),
+        };
+
+        let frame = match ready!(inbound.poll_next_unpin(cx)) {
+            Some(Ok(frame)) => frame,
+            Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+            None => {
+                return Poll::Ready(Some(Err(MpcNetworkError::RecvError(
+                    ERR_STREAM_FINISHED_EARLY.to_string(),
+                ))))
+            }
+        };
+
+        let res = self
+            .recv_cipher
+            .as_mut()
+            .unwrap()
+            .open(&frame)
+            .and_then(|bytes| {
+                serde_json::from_slice(&bytes)
+                    .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))
+            });
+        Poll::Ready(Some(res))
+    }
+}
+
+impl Sink<NetworkOutbound> for RelayTwoPartyNet {
+    type Error = MpcNetworkError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outbound
+            .as_mut()
+            .ok_or(MpcNetworkError::NetworkUninitialized)?
+            .poll_ready(cx)
+            .map_err(|err| MpcNetworkError::SendError(err.to_string()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: NetworkOutbound) -> Result<(), Self::Error> {
+        self.assert_connected()?;
+
+        let bytes = serde_json::to_vec(&msg)
+            .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))?;
+        let frame = self.send_cipher.as_mut().unwrap().seal(&bytes);
+
+        self.outbound
+            .as_mut()
+            .unwrap()
+            .start_send(frame)
+            .map_err(|err| MpcNetworkError::SendError(err.to_string()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outbound
+            .as_mut()
+            .ok_or(MpcNetworkError::NetworkUninitialized)?
+            .poll_flush_unpin(cx)
+            .map_err(|err| MpcNetworkError::SendError(err.to_string()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The connection is closed by `MpcNetwork::close`
+        self.poll_flush(cx)
+    }
+}
+
+// ----------------
+// | Key Exchange |
+// ----------------
+
+/// The first message each party sends its peer through the relay
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RelayHello {
+    /// The party ID of the sender
+    party_id: PartyId,
+    /// The identity public key of the sender
+    public_key: StarkPoint,
+    /// The ephemeral public key of the sender
+    ephemeral_key: StarkPoint,
+}
+
+impl From<&RelayHello> for HandshakeHello {
+    fn from(hello: &RelayHello) -> Self {
+        Self {
+            party_id: hello.party_id,
+            public_key: hello.public_key,
+        }
+    }
+}
+
+/// Derive the session ID from the parties' ephemeral keys
+fn relay_session_id(party0_key: &StarkPoint, party1_key: &StarkPoint) -> SessionId {
+    let mut hasher = Sha3_256::new();
+    hasher.update(RELAY_SESSION_DOMAIN);
+    hasher.update(party0_key.to_bytes());
+    hasher.update(party1_key.to_bytes());
+
+    hasher.finalize().into()
+}
+
+/// Derive the key for frames sent by the given party from the shared secret
+fn frame_key(shared_secret: &StarkPoint, session_id: &SessionId, sender: PartyId) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(FRAME_KEY_DOMAIN);
+    hasher.update(shared_secret.to_bytes());
+    hasher.update(session_id);
+    hasher.update(sender.to_le_bytes());
+
+    hasher.finalize().into()
+}
+
+/// Derive the next frame key from the current one
+fn next_frame_key(key: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(REKEY_DOMAIN);
+    hasher.update(key);
+
+    hasher.finalize().into()
+}
+
+/// Encrypts or decrypts the frames sent in one direction
+///
+/// Each frame is sealed under a nonce counting the frames sent before it, so a relay that
+/// drops, replays, or reorders frames causes decryption to fail. The sender updates the key
+/// once it has sealed a rekey interval's worth of frames, setting the header of the last frame
+/// under the old key so that the receiver updates its key after opening it
+struct FrameCipher {
+    /// The key of the cipher, zeroized when it is updated
+    key: Zeroizing<[u8; 32]>,
+    /// The cipher keyed for the direction
+    cipher: ChaCha20Poly1305,
+    /// The number of frames sealed or opened so far
+    counter: u64,
+    /// The amount of traffic sealed under a key before it is updated
+    rekey_interval: RekeyInterval,
+    /// The number of frames sealed under the current key
+    key_frames: u64,
+    /// The number of plaintext bytes sealed under the current key
+    key_bytes: u64,
+}
+
+impl FrameCipher {
+    /// Constructor
+    fn new(key: [u8; 32], rekey_interval: RekeyInterval) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            key: Zeroizing::new(key),
+            counter: 0,
+            rekey_interval,
+            key_frames: 0,
+            key_bytes: 0,
+        }
+    }
+
+    /// Replace the key with the next one derived from it
+    fn update_key(&mut self) {
+        self.key = Zeroizing::new(next_frame_key(&self.key));
+        self.cipher = ChaCha20Poly1305::new(Key::from_slice(self.key.as_ref()));
+        self.key_frames = 0;
+        self.key_bytes = 0;
+    }
+
+    /// The nonce for the next frame
+    fn next_nonce(&mut self) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[..BYTES_PER_U64].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter += 1;
+
+        nonce
+    }
+
+    /// Encrypt the next frame, updating the key after it if the rekey interval is reached
+    fn seal(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        self.key_frames += 1;
+        self.key_bytes += plaintext.len() as u64;
+        let update = self.key_frames >= self.rekey_interval.frames
+            || self.key_bytes >= self.rekey_interval.bytes;
+
+        let mut frame = Vec::with_capacity(plaintext.len() + 1);
+        frame.push(if update {
+            FRAME_HEADER_KEY_UPDATE
+        } else {
+            FRAME_HEADER_DATA
+        });
+        frame.extend_from_slice(plaintext);
+
+        let nonce = self.next_nonce();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), frame.as_slice())
+            .expect("frame encryption cannot fail");
+
+        if update {
+            self.update_key();
+        }
+        ciphertext
+    }
+
+    /// Decrypt the next frame, updating the key after it if the sender marked it as the last
+    /// frame under the key
+    fn open(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, MpcNetworkError> {
+        let nonce = self.next_nonce();
+        let mut frame = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .map_err(|_| MpcNetworkError::RecvError(ERR_FRAME_DECRYPTION.to_string()))?;
+
+        match frame.first() {
+            Some(&FRAME_HEADER_DATA) => {}
+            Some(&FRAME_HEADER_KEY_UPDATE) => self.update_key(),
+            _ => return Err(MpcNetworkError::RecvError(ERR_FRAME_HEADER.to_string())),
+        }
+
+        frame.remove(0);
+        Ok(frame)
+    }
+}
+
+// -----------
+// | Framing |
+// -----------
+
+/// Write a length-prefixed frame
+async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    bytes: &[u8],
+) -> Result<(), MpcNetworkError> {
+    writer
+        .write_all(&(bytes.len() as u64).to_le_bytes())
+        .await
+        .map_err(|err| MpcNetworkError::SendError(err.to_string()))?;
+    writer
+        .write_all(bytes)
+        .await
+        .map_err(|err| MpcNetworkError::SendError(err.to_string()))
+}
+
+/// Read a length-prefixed frame
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>, MpcNetworkError> {
+    let len = reader
+        .read_u64_le()
+        .await
+        .map_err(|err| MpcNetworkError::RecvError(err.to_string()))?;
+    if len > MAX_FRAME_BYTES {
+        return Err(MpcNetworkError::RecvError(ERR_FRAME_TOO_LARGE.to_string()));
+    }
+
+    let mut bytes = vec![0u8; len as usize];
+    reader
+        .read_exact(&mut bytes)
+        .await
+        .map_err(|err| MpcNetworkError::RecvError(err.to_string()))?;
+
+    Ok(bytes)
+}
+
+/// Write a plaintext handshake message
+async fn write_message<T: Serialize>(
+    stream: &mut TcpStream,
+    message: &T,
+) -> Result<(), MpcNetworkError> {
+    let bytes = serde_json::to_vec(message)
+        .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))?;
+    write_frame(stream, &bytes).await
+}
+
+/// Read a plaintext handshake message
+async fn read_message<T: DeserializeOwned>(stream: &mut TcpStream) -> Result<T, MpcNetworkError> {
+    let bytes = read_frame(stream).await?;
+    serde_json::from_slice(&bytes)
+        .map_err(|err| MpcNetworkError::SerializationError(err.to_string()))
+}
+
+/// Write the frames queued by the network until the network is closed, then shut down the
+/// write side of the connection
+async fn write_frames(mut writer: OwnedWriteHalf, mut frames: mpsc::Receiver<Vec<u8>>) {
+    while let Some(frame) = frames.next().await {
+        if let Err(err) = write_frame(&mut writer, &frame).await {
+            log::error!("error writing frame to relay: {err}");
+            return;
+        }
+    }
+
+    let _ = writer.shutdown().await;
+}
+
+/// Read frames from the relay until the connection closes or fails
+async fn read_frames(
+    mut reader: OwnedReadHalf,
+    mut frames: mpsc::Sender<Result<Vec<u8>, MpcNetworkError>>,
+) {
+    loop {
+        let res = read_frame(&mut reader).await;
+        let failed = res.is_err();
+        if frames.send(res).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Build a setup error from an error connecting to the relay
+fn relay_setup_error<E: ToString>(err: E) -> MpcNetworkError {
+    MpcNetworkError::ConnectionSetupError(SetupError::RelayError(err.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{SinkExt, StreamExt};
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::scalar::Scalar,
+        error::{MpcNetworkError, SetupError},
+        network::{IdentityKeypair, MpcNetwork, NetworkOutbound, NetworkPayload},
+    };
+
+    use super::{FrameCipher, RekeyInterval, RelayServer, RelayTwoPartyNet};
+
+    /// Tests exchanging messages between two parties through a relay
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_relay_exchange() {
+        const N: u64 = 100;
+
+        let relay = RelayServer::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let relay_addr = relay.local_addr().unwrap();
+        tokio::spawn(relay.run());
+
+        let mut rng = thread_rng();
+        let key0 = IdentityKeypair::random(&mut rng);
+        let key1 = IdentityKeypair::random(&mut rng);
+        let mut net0 = RelayTwoPartyNet::new(0, relay_addr, key0.clone(), key1.public_key())
+            .with_rekey_interval(RekeyInterval {
+                frames: 7,
+                bytes: u64::MAX,
+            });
+        let mut net1 = RelayTwoPartyNet::new(1, relay_addr, key1.clone(), key0.public_key());
+
+        let (res0, res1) = tokio::join!(net0.connect(), net1.connect());
+        res0.unwrap();
+        res1.unwrap();
+        assert!(net0.session_id().is_some());
+        assert_eq!(net0.session_id(), net1.session_id());
+        assert_eq!(net0.peer_identity(), Some(key1.public_key()));
+
+        // Send in both directions
+        async fn send(net: &mut RelayTwoPartyNet) {
+            for id in 0..N {
+                let msg = NetworkOutbound {
+                    result_id: id as usize,
+                    payload: NetworkPayload::Scalar(Scalar::from(id)),
+                };
+                net.send(msg).await.unwrap();
+            }
+        }
+        tokio::join!(send(&mut net0), send(&mut net1));
+
+        for net in [&mut net0, &mut net1] {
+            for id in 0..N {
+                let msg = net.next().await.unwrap().unwrap();
+                let NetworkPayload::Scalar(scalar) = msg.payload else {
+                    panic!("unexpected payload");
+                };
+
+                assert_eq!(msg.result_id, id as usize);
+                assert_eq!(scalar, Scalar::from(id));
+            }
+        }
+
+        MpcNetwork::close(&mut net0).await.unwrap();
+        MpcNetwork::close(&mut net1).await.unwrap();
+    }
+
+    /// Tests that the sender updates its key once either rekey limit is reached and that the
+    /// receiver follows
+    #[test]
+    fn test_frame_rekey() {
+        let key = [3u8; 32];
+        let interval = RekeyInterval {
+            frames: 3,
+            bytes: 10,
+        };
+        let mut sender = FrameCipher::new(key, interval);
+        let mut receiver = FrameCipher::new(key, RekeyInterval::default());
+
+        // The key is updated after the third frame, then after the frame that reaches 10 bytes
+        let frames: [&[u8]; 6] = [b"a", b"b", b"c", b"0123", b"456789", b"d"];
+        let mut updates = 0;
+        for frame in frames {
+            let prev_key = *sender.key;
+            let ciphertext = sender.seal(frame);
+            if *sender.key != prev_key {
+                updates += 1;
+            }
+
+            assert_eq!(receiver.open(&ciphertext).unwrap(), frame);
+            assert_eq!(*receiver.key, *sender.key);
+        }
+        assert_eq!(updates, 2);
+
+        // A frame sealed under the old key does not open under the updated key
+        let mut stale = FrameCipher::new(key, interval);
+        stale.counter = sender.counter;
+        assert!(receiver.open(&stale.seal(b"e")).is_err());
+    }
+
+    /// Tests that a party rejects a peer joining with an unexpected identity
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_relay_impostor() {
+        let relay = RelayServer::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let relay_addr = relay.local_addr().unwrap();
+        tokio::spawn(relay.run());
+
+        // The impostor joins party 1's room with an identity key party 0 does not expect
+        let mut rng = thread_rng();
+        let key0 = IdentityKeypair::random(&mut rng);
+        let key1 = IdentityKeypair::random(&mut rng);
+        let impostor = IdentityKeypair::random(&mut rng);
+        let room_id = [1u8; 32];
+
+        let mut net0 = RelayTwoPartyNet::new(0, relay_addr, key0.clone(), key1.public_key())
+            .with_room_id(room_id);
+        let mut net1 =
+            RelayTwoPartyNet::new(1, relay_addr, impostor, key0.public_key()).with_room_id(room_id);
+
+        let (res0, _) = tokio::join!(net0.connect(), net1.connect());
+        assert!(matches!(
+            res0,
+            Err(MpcNetworkError::ConnectionSetupError(
+                SetupError::PeerAuthenticationError
+            ))
+        ));
+    }
+}
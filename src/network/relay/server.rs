@@ -0,0 +1,123 @@
+//! Defines the relay server, which pairs parties joining the same room and forwards their
+//! frames without interpreting them
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    io::{copy_bidirectional, AsyncReadExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+use tracing::log;
+
+use crate::{
+    error::{MpcNetworkError, SetupError},
+    network::PartyId,
+    PARTY0, PARTY1,
+};
+
+use super::{RoomId, JOIN_FRAME_BYTES, ROOM_ID_BYTES};
+
+/// The default amount of time a connection may take to send its join frame
+const DEFAULT_JOIN_TIMEOUT_MS: u64 = 10_000; // 10 seconds
+
+/// The parties waiting at the relay for their peer, by room
+type WaitingRoom = Arc<Mutex<HashMap<RoomId, (PartyId, TcpStream)>>>;
+
+/// A relay that pairs the two parties joining a room and forwards the bytes each sends to the
+/// other
+///
+/// The relay is untrusted, the parties encrypt and authenticate their frames end to end. A
+/// party that joins a room the same party is already waiting in replaces the waiting connection
+pub struct RelayServer {
+    /// The listener parties connect to
+    listener: TcpListener,
+    /// The amount of time a connection may take to send its join frame
+    join_timeout: Duration,
+}
+
+impl RelayServer {
+    /// Bind a relay to the given address
+    pub async fn bind(addr: SocketAddr) -> Result<Self, MpcNetworkError> {
+        let listener = TcpListener::bind(addr).await.map_err(|err| {
+            log::error!("error binding relay: {err}");
+            MpcNetworkError::ConnectionSetupError(SetupError::ServerSetupError)
+        })?;
+
+        Ok(Self {
+            listener,
+            join_timeout: Duration::from_millis(DEFAULT_JOIN_TIMEOUT_MS),
+        })
+    }
+
+    /// Set the amount of time a connection may take to send its join frame
+    pub fn with_join_timeout(mut self, join_timeout: Duration) -> Self {
+        self.join_timeout = join_timeout;
+        self
+    }
+
+    /// The address the relay is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr, MpcNetworkError> {
+        self.listener.local_addr().map_err(|err| {
+            MpcNetworkError::ConnectionSetupError(SetupError::RelayError(err.to_string()))
+        })
+    }
+
+    /// Accept and pair connections until the listener fails
+    pub async fn run(self) -> Result<(), MpcNetworkError> {
+        let waiting = WaitingRoom::default();
+        loop {
+            let (stream, addr) = self.listener.accept().await.map_err(|err| {
+                log::error!("error accepting relay connection: {err}");
+                MpcNetworkError::ConnectionSetupError(SetupError::RelayError(err.to_string()))
+            })?;
+
+            log::debug!("relay accepted connection from {addr}");
+            tokio::spawn(handle_join(stream, waiting.clone(), self.join_timeout));
+        }
+    }
+}
+
+/// Read a connection's join frame, then either wait for its peer or pair it with the peer
+/// already waiting and forward between the two until either disconnects
+async fn handle_join(mut stream: TcpStream, waiting: WaitingRoom, join_timeout: Duration) {
+    let mut join = [0u8; JOIN_FRAME_BYTES];
+    match timeout(join_timeout, stream.read_exact(&mut join)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => {
+            log::debug!("error reading relay join frame: {err}");
+            return;
+        }
+        Err(_) => {
+            log::debug!("relay connection timed out before joining");
+            return;
+        }
+    }
+
+    let room_id: RoomId = join[..ROOM_ID_BYTES].try_into().unwrap();
+    let party_id = PartyId::from_le_bytes(join[ROOM_ID_BYTES..].try_into().unwrap());
+    if party_id != PARTY0 && party_id != PARTY1 {
+        log::debug!("relay connection joined as invalid party {party_id}");
+        return;
+    }
+
+    let mut peer = {
+        let mut waiting = waiting.lock().expect("relay waiting room poisoned");
+        match waiting.remove(&room_id) {
+            Some((peer_id, peer)) if peer_id != party_id => peer,
+            _ => {
+                waiting.insert(room_id, (party_id, stream));
+                return;
+            }
+        }
+    };
+
+    if let Err(err) = copy_bidirectional(&mut stream, &mut peer).await {
+        log::debug!("relay connection closed: {err}");
+    }
+}
@@ -0,0 +1,472 @@
+//! Defines a network wrapper that records the messages exchanged in a run, and a network that
+//! replays a recording so that one party of a failing interaction can be reproduced
+//! deterministically without its peer
+//!
+//! A replay only reproduces the recorded run if the local party computes the same messages,
+//! so the fabric under replay should be configured with the seeded RNG and beaver source of
+//! the recorded run
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use async_trait::async_trait;
+use futures::{Sink, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algebra::stark_curve::StarkPoint,
+    error::MpcNetworkError,
+    fabric::{ResultId, HEARTBEAT_RESULT_ID},
+};
+
+use super::{MpcNetwork, NetworkOutbound, NetworkPayload, PartyId, SessionId};
+
+/// Error message emitted when the local party sends a message the recording does not hold
+const ERR_REPLAY_UNEXPECTED: &str = "replayed party sent a message absent from the recording";
+/// Error message emitted when the local party sends a message that differs from the recording
+const ERR_REPLAY_DIVERGED: &str = "replayed party diverged from the recording";
+
+/// The direction of a recorded message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageDirection {
+    /// A message the local party sent to the peer
+    Sent,
+    /// A message the local party received from the peer
+    Received,
+}
+
+/// A message recorded on a network
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// The direction of the message
+    pub direction: MessageDirection,
+    /// The message
+    pub message: NetworkOutbound,
+}
+
+/// The messages exchanged by one party over a network, in the order they were sent and
+/// received
+///
+/// Coalesced messages are recorded as the messages they pack, and heartbeats are not recorded,
+/// as neither is determined by the computation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NetworkRecording {
+    /// The party that recorded the messages
+    pub party_id: PartyId,
+    /// The session the recorded network agreed on, if any
+    pub session_id: Option<SessionId>,
+    /// The identity key the peer authenticated with, if any
+    pub peer_identity: Option<StarkPoint>,
+    /// The recorded messages
+    pub messages: Vec<RecordedMessage>,
+}
+
+impl NetworkRecording {
+    /// The messages recorded in the given direction
+    pub fn messages_in(
+        &self,
+        direction: MessageDirection,
+    ) -> impl Iterator<Item = &NetworkOutbound> {
+        self.messages
+            .iter()
+            .filter(move |msg| msg.direction == direction)
+            .map(|msg| &msg.message)
+    }
+
+    /// Write the recording to a file as JSON
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), IoError> {
+        let bytes =
+            serde_json::to_vec(self).map_err(|err| IoError::new(ErrorKind::InvalidData, err))?;
+        fs::write(path, bytes)
+    }
+
+    /// Read a recording from a JSON file
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, IoError> {
+        let bytes = fs::read(path)?;
+        serde_json::from_slice(&bytes).map_err(|err| IoError::new(ErrorKind::InvalidData, err))
+    }
+}
+
+/// Flatten a message into the messages it packs, dropping heartbeats
+fn flatten_message(msg: NetworkOutbound, out: &mut Vec<NetworkOutbound>) {
+    match msg.payload {
+        NetworkPayload::Coalesced(batch) => {
+            batch.into_iter().for_each(|msg| flatten_message(msg, out))
+        }
+        NetworkPayload::Openings { result_ids, values } => {
+            out.extend(NetworkPayload::unpack_openings(result_ids, *values).unwrap_or_default())
+        }
+        _ if msg.result_id == HEARTBEAT_RESULT_ID => {}
+        _ => out.push(msg),
+    }
+}
+
+// ---------------------
+// | Recording Network |
+// ---------------------
+
+/// A handle to the messages recorded by a `RecordingNetwork`, readable after the network is
+/// moved into a fabric
+#[derive(Clone, Debug)]
+pub struct RecordingHandle {
+    /// The recording
+    recording: Arc<Mutex<NetworkRecording>>,
+}
+
+impl RecordingHandle {
+    /// A snapshot of the messages recorded so far
+    pub fn snapshot(&self) -> NetworkRecording {
+        self.recording.lock().expect("recording poisoned").clone()
+    }
+
+    /// Record a message in the given direction
+    fn record(&self, direction: MessageDirection, msg: NetworkOutbound) {
+        let mut flattened = Vec::new();
+        flatten_message(msg, &mut flattened);
+
+        let mut recording = self.recording.lock().expect("recording poisoned");
+        recording.messages.extend(
+            flattened
+                .into_iter()
+                .map(|message| RecordedMessage { direction, message }),
+        );
+    }
+}
+
+/// A network that records the messages sent and received on an underlying network
+pub struct RecordingNetwork<N: MpcNetwork> {
+    /// The underlying network
+    inner: N,
+    /// The recording of the messages
+    handle: RecordingHandle,
+}
+
+impl<N: MpcNetwork> RecordingNetwork<N> {
+    /// Record the messages sent and received on the given network
+    pub fn new(inner: N) -> Self {
+        let recording = NetworkRecording {
+            party_id: inner.party_id(),
+            session_id: inner.session_id(),
+            peer_identity: inner.peer_identity(),
+            messages: Vec::new(),
+        };
+
+        Self {
+            inner,
+            handle: RecordingHandle {
+                recording: Arc::new(Mutex::new(recording)),
+            },
+        }
+    }
+
+    /// A handle to the recording
+    pub fn handle(&self) -> RecordingHandle {
+        self.handle.clone()
+    }
+}
+
+#[async_trait]
+impl<N: MpcNetwork + Unpin> MpcNetwork for RecordingNetwork<N> {
+    fn party_id(&self) -> PartyId {
+        self.inner.party_id()
+    }
+
+    fn peer_identity(&self) -> Option<StarkPoint> {
+        self.inner.peer_identity()
+    }
+
+    fn session_id(&self) -> Option<SessionId> {
+        self.inner.session_id()
+    }
+
+    async fn close(&mut self) -> Result<(), MpcNetworkError> {
+        self.inner.close().await
+    }
+}
+
+impl<N: MpcNetwork + Unpin> Stream for RecordingNetwork<N> {
+    type Item = Result<NetworkOutbound, MpcNetworkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = Pin::new(&mut self.inner).poll_next(cx);
+        if let Poll::Ready(Some(Ok(msg))) = &res {
+            self.handle.record(MessageDirection::Received, msg.clone());
+        }
+
+        res
+    }
+}
+
+impl<N: MpcNetwork + Unpin> Sink<NetworkOutbound> for RecordingNetwork<N> {
+    type Error = MpcNetworkError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: NetworkOutbound) -> Result<(), Self::Error> {
+        self.handle.record(MessageDirection::Sent, item.clone());
+        Pin::new(&mut self.inner).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+// ------------------
+// | Replay Network |
+// ------------------
+
+/// A network that replays the messages received in a recording, and checks the messages the
+/// local party sends against those it sent in the recording
+///
+/// Sent messages are matched to the recording by result ID rather than by order, as the order
+/// in which the executor evaluates independent network operations is not deterministic. As a
+/// live peer would, the network holds back each received message until the local party has
+/// sent as many messages as it had when the message was recorded. Once the recorded messages
+/// are exhausted the network stays open without delivering messages
+pub struct ReplayNetwork {
+    /// The party replayed
+    party_id: PartyId,
+    /// The session of the recording
+    session_id: Option<SessionId>,
+    /// The identity key the peer authenticated with in the recording
+    peer_identity: Option<StarkPoint>,
+    /// The recorded messages left to deliver to the local party, each with the number of
+    /// messages the local party had sent when it was recorded
+    inbound: VecDeque<(usize, NetworkOutbound)>,
+    /// The recorded messages the local party is expected to send, by result ID
+    expected: HashMap<ResultId, NetworkOutbound>,
+    /// The number of messages the local party has sent
+    n_sent: usize,
+    /// The waker of a read blocked on the local party's sends
+    waker: Option<Waker>,
+}
+
+impl ReplayNetwork {
+    /// Replay the given recording
+    pub fn new(recording: NetworkRecording) -> Self {
+        let mut n_sent = 0;
+        let mut inbound = VecDeque::new();
+        for msg in recording.messages.iter() {
+            match msg.direction {
+                MessageDirection::Sent => n_sent += 1,
+                MessageDirection::Received => inbound.push_back((n_sent, msg.message.clone())),
+            }
+        }
+
+        let expected = recording
+            .messages_in(MessageDirection::Sent)
+            .map(|msg| (msg.result_id, msg.clone()))
+            .collect();
+
+        Self {
+            party_id: recording.party_id,
+            session_id: recording.session_id,
+            peer_identity: recording.peer_identity,
+            inbound,
+            expected,
+            n_sent: 0,
+            waker: None,
+        }
+    }
+
+    /// Whether every message the local party sent in the recording has been sent again
+    pub fn is_exhausted(&self) -> bool {
+        self.expected.is_empty()
+    }
+
+    /// Check a message sent by the local party against the recording
+    fn check_sent(&mut self, msg: NetworkOutbound) -> Result<(), MpcNetworkError> {
+        let expected = self
+            .expected
+            .remove(&msg.result_id)
+            .ok_or_else(|| MpcNetworkError::SendError(ERR_REPLAY_UNEXPECTED.to_string()))?;
+        self.n_sent += 1;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+
+        // Payloads are compared by their serialization, which is canonical for a given value
+        let serialize = |payload: &NetworkPayload| serde_json::to_vec(payload).ok();
+        if serialize(&expected.payload) != serialize(&msg.payload) {
+            return Err(MpcNetworkError::SendError(format!(
+                "{ERR_REPLAY_DIVERGED}: result {}",
+                msg.result_id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MpcNetwork for ReplayNetwork {
+    fn party_id(&self) -> PartyId {
+        self.party_id
+    }
+
+    fn peer_identity(&self) -> Option<StarkPoint> {
+        self.peer_identity
+    }
+
+    fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+
+    async fn close(&mut self) -> Result<(), MpcNetworkError> {
+        Ok(())
+    }
+}
+
+impl Stream for ReplayNetwork {
+    type Item = Result<NetworkOutbound, MpcNetworkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.inbound.front() {
+            Some((sent_before, _)) if *sent_before <= self.n_sent => {
+                let (_, msg) = self.inbound.pop_front().unwrap();
+                Poll::Ready(Some(Ok(msg)))
+            }
+            Some(_) => {
+                self.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Sink<NetworkOutbound> for ReplayNetwork {
+    type Error = MpcNetworkError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: NetworkOutbound) -> Result<(), Self::Error> {
+        let mut flattened = Vec::new();
+        flatten_message(item, &mut flattened);
+        flattened
+            .into_iter()
+            .try_for_each(|msg| self.check_sent(msg))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::try_join;
+
+    use crate::{
+        algebra::scalar::Scalar,
+        beaver::PartyIDBeaverSource,
+        error::MpcError,
+        network::{MockNetwork, UnboundedDuplexStream},
+        FabricConfig, MpcFabric, PARTY0, PARTY1,
+    };
+
+    use super::{NetworkRecording, RecordingNetwork, ReplayNetwork};
+
+    /// The seed of party 0's local randomness in the recorded run
+    const PARTY0_SEED: [u8; 32] = [0; 32];
+    /// The seed of party 1's local randomness in the recorded run
+    const PARTY1_SEED: [u8; 32] = [1; 32];
+
+    /// Evaluate the product of the parties' inputs and open it
+    async fn evaluate_product(fabric: MpcFabric) -> Result<Scalar, MpcError> {
+        let a = fabric.share_scalar(3u8, PARTY0);
+        let b = fabric.share_scalar(5u8, PARTY1);
+        let opened = (&a * &b).open_authenticated();
+
+        let res = try_join!(opened.value.fallible(), opened.mac_check.fallible());
+        fabric.shutdown();
+        res.map(|(value, _)| value)
+    }
+
+    /// Run the product between two parties, recording party 0's network
+    async fn record_product() -> (Scalar, NetworkRecording) {
+        let (party0_stream, party1_stream) = UnboundedDuplexStream::new_duplex_pair();
+        let network = RecordingNetwork::new(MockNetwork::new(PARTY0, party0_stream));
+        let recording = network.handle();
+
+        let party0_fabric = MpcFabric::with_config(
+            network,
+            PartyIDBeaverSource::new(PARTY0),
+            FabricConfig::default().with_rng_seed(PARTY0_SEED),
+        );
+        let party1_fabric = MpcFabric::with_config(
+            MockNetwork::new(PARTY1, party1_stream),
+            PartyIDBeaverSource::new(PARTY1),
+            FabricConfig::default().with_rng_seed(PARTY1_SEED),
+        );
+
+        let party1_task = tokio::spawn(evaluate_product(party1_fabric));
+        let res = evaluate_product(party0_fabric).await.unwrap();
+        party1_task.await.unwrap().unwrap();
+
+        (res, recording.snapshot())
+    }
+
+    /// Tests replaying a recorded party without its peer
+    #[tokio::test]
+    async fn test_replay() {
+        let (expected, recording) = record_product().await;
+
+        // Round trip the recording through a file
+        let path =
+            std::env::temp_dir().join(format!("mpc-stark-replay-{}.json", std::process::id()));
+        recording.write_to_file(&path).unwrap();
+        let recording = NetworkRecording::read_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let fabric = MpcFabric::with_config(
+            ReplayNetwork::new(recording),
+            PartyIDBeaverSource::new(PARTY0),
+            FabricConfig::default().with_rng_seed(PARTY0_SEED),
+        );
+        let res = evaluate_product(fabric).await;
+
+        assert_eq!(res, Ok(expected));
+        assert_eq!(expected, Scalar::from(15u8));
+    }
+
+    /// Tests that a replay in which the local party sends different messages fails
+    #[tokio::test]
+    async fn test_replay_divergence() {
+        let (_, recording) = record_product().await;
+
+        let fabric = MpcFabric::with_config(
+            ReplayNetwork::new(recording),
+            PartyIDBeaverSource::new(PARTY0),
+            FabricConfig::default().with_rng_seed(PARTY1_SEED),
+        );
+        let res = evaluate_product(fabric).await;
+
+        // The divergent send either disconnects the peer or fails a later send, depending on
+        // which the executor observes first
+        assert!(matches!(
+            res,
+            Err(MpcError::PeerDisconnected | MpcError::SendFailed { .. })
+        ));
+    }
+}
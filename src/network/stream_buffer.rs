@@ -4,30 +4,27 @@
 //!
 //! This will be replaced when the more convenient `std::io::Cursor` is stabilized.
 
-/// A wrapper around a raw `&[u8]` buffer that tracks a cursor within the buffer
+use bytes::BytesMut;
+
+/// A wrapper around a `BytesMut` buffer that tracks a cursor within the buffer
 /// to allow partial fills across cancelled futures
 ///
 /// Similar to `tokio::io::ReadBuf` but takes ownership of the underlying buffer to
-/// avoid coloring interfaces with lifetime parameters
+/// avoid coloring interfaces with lifetime parameters. The buffer is returned once
+/// depleted so that its allocation may be reused
 ///
 /// TODO: Replace this with `std::io::Cursor` once it is stabilized
 #[derive(Debug)]
 pub struct BufferWithCursor {
     /// The underlying buffer
-    buffer: Vec<u8>,
+    buffer: BytesMut,
     /// The current cursor position
     cursor: usize,
 }
 
 impl BufferWithCursor {
     /// Create a new buffer with a cursor at the start of the buffer
-    pub fn new(buf: Vec<u8>) -> Self {
-        assert_eq!(
-            buf.len(),
-            buf.capacity(),
-            "buffer must be fully initialized"
-        );
-
+    pub fn new(buf: BytesMut) -> Self {
         Self {
             buffer: buf,
             cursor: 0,
@@ -36,7 +33,7 @@ impl BufferWithCursor {
 
     /// The number of bytes remaining in the buffer
     pub fn remaining(&self) -> usize {
-        self.buffer.capacity() - self.cursor
+        self.buffer.len() - self.cursor
     }
 
     /// Whether the buffer is full
@@ -55,7 +52,7 @@ impl BufferWithCursor {
     }
 
     /// Take ownership of the underlying buffer
-    pub fn into_vec(self) -> Vec<u8> {
+    pub fn into_inner(self) -> BytesMut {
         self.buffer
     }
 }
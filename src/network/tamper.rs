@@ -0,0 +1,258 @@
+//! Defines a network wrapper that tampers with the payloads of chosen outbound messages
+//! before they reach the peer, used to test that the MAC check catches wire-level tampering
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use futures::{Sink, Stream};
+
+use crate::{
+    algebra::{scalar::Scalar, stark_curve::StarkPoint},
+    error::MpcNetworkError,
+    fabric::ResultId,
+};
+
+use super::{MpcNetwork, NetworkOutbound, NetworkPayload, PartyId, SessionId};
+
+/// A modification applied to the payload of an outbound message
+pub enum Tamper {
+    /// Perturb the payload: flip the low bit of the first byte, add one to a scalar, or add
+    /// the generator to a point, batches have their first element perturbed
+    Flip,
+    /// Swap two elements of a batch payload
+    SwapBatchElements(usize, usize),
+    /// Apply an arbitrary modification to the payload
+    Custom(Box<dyn FnOnce(&mut NetworkPayload) + Send>),
+}
+
+impl Debug for Tamper {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Tamper::Flip => write!(f, "Flip"),
+            Tamper::SwapBatchElements(i, j) => write!(f, "SwapBatchElements({i}, {j})"),
+            Tamper::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl Tamper {
+    /// Apply the modification to a payload, returning whether the payload was modified
+    fn apply(self, payload: &mut NetworkPayload) -> bool {
+        match (self, payload) {
+            (Tamper::Flip, NetworkPayload::Bytes(bytes)) => match bytes.first_mut() {
+                Some(byte) => *byte ^= 1,
+                None => bytes.push(1),
+            },
+            (Tamper::Flip, NetworkPayload::Scalar(scalar)) => *scalar += Scalar::one(),
+            (Tamper::Flip, NetworkPayload::ScalarBatch(scalars)) if !scalars.is_empty() => {
+                scalars[0] += Scalar::one()
+            }
+            (Tamper::Flip, NetworkPayload::Point(point)) => *point += StarkPoint::generator(),
+            (Tamper::Flip, NetworkPayload::PointBatch(points)) if !points.is_empty() => {
+                points[0] += StarkPoint::generator()
+            }
+            (Tamper::SwapBatchElements(i, j), NetworkPayload::ScalarBatch(scalars))
+                if i.max(j) < scalars.len() && scalars[i] != scalars[j] =>
+            {
+                scalars.swap(i, j)
+            }
+            (Tamper::SwapBatchElements(i, j), NetworkPayload::PointBatch(points))
+                if i.max(j) < points.len() && points[i] != points[j] =>
+            {
+                points.swap(i, j)
+            }
+            (Tamper::Custom(modify), payload) => modify(payload),
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+/// A handle to the modifications a `TamperingNetwork` applies, usable after the network is
+/// moved into a fabric
+#[derive(Clone, Debug, Default)]
+pub struct TamperHandle {
+    /// The modifications waiting to be applied, by the result ID of the message they target
+    pending: Arc<Mutex<HashMap<ResultId, Tamper>>>,
+    /// The result IDs of the messages modified so far
+    applied: Arc<Mutex<Vec<ResultId>>>,
+}
+
+impl TamperHandle {
+    /// Modify the next outbound message with the given result ID
+    ///
+    /// A message may be sent as soon as its operation is allocated, so the modification
+    /// should be registered before the operation is. A modification that does not apply to
+    /// the message's payload, e.g. swapping the elements of a single scalar, is dropped
+    pub fn tamper(&self, result_id: ResultId, tamper: Tamper) {
+        self.pending
+            .lock()
+            .expect("tamper handle poisoned")
+            .insert(result_id, tamper);
+    }
+
+    /// Drop all modifications that have not been applied
+    pub fn clear(&self) {
+        self.pending.lock().expect("tamper handle poisoned").clear();
+    }
+
+    /// Take the result IDs of the messages modified since the last call
+    pub fn take_applied(&self) -> Vec<ResultId> {
+        std::mem::take(&mut *self.applied.lock().expect("tamper handle poisoned"))
+    }
+
+    /// Apply any modification registered for a message, including those packed into it
+    fn apply(&self, msg: &mut NetworkOutbound) {
+        if let NetworkPayload::Coalesced(batch) = &mut msg.payload {
+            batch.iter_mut().for_each(|msg| self.apply(msg));
+            return;
+        }
+
+        if let NetworkPayload::Openings { result_ids, values } = &mut msg.payload {
+            self.apply_openings(result_ids, values);
+            return;
+        }
+
+        let tamper = self
+            .pending
+            .lock()
+            .expect("tamper handle poisoned")
+            .remove(&msg.result_id);
+        let modified = match tamper {
+            Some(tamper) => tamper.apply(&mut msg.payload),
+            None => false,
+        };
+        if modified {
+            self.applied
+                .lock()
+                .expect("tamper handle poisoned")
+                .push(msg.result_id);
+        }
+    }
+
+    /// Apply the modifications registered for the openings in a batch, each opening is
+    /// modified as if it were sent on its own
+    fn apply_openings(&self, result_ids: &[ResultId], values: &mut NetworkPayload) {
+        let mut singles: Vec<NetworkPayload> = match values {
+            NetworkPayload::ScalarBatch(scalars) => scalars
+                .iter()
+                .copied()
+                .map(NetworkPayload::Scalar)
+                .collect(),
+            NetworkPayload::PointBatch(points) => {
+                points.iter().copied().map(NetworkPayload::Point).collect()
+            }
+            _ => return,
+        };
+
+        for (result_id, payload) in result_ids.iter().zip(singles.iter_mut()) {
+            let mut msg = NetworkOutbound {
+                result_id: *result_id,
+                payload: std::mem::replace(payload, NetworkPayload::Bytes(Vec::new())),
+            };
+            self.apply(&mut msg);
+            *payload = msg.payload;
+        }
+
+        // A modification that changes the kind of an opening drops it from the batch
+        *values = match values {
+            NetworkPayload::ScalarBatch(_) => NetworkPayload::ScalarBatch(
+                singles
+                    .into_iter()
+                    .filter_map(|payload| match payload {
+                        NetworkPayload::Scalar(scalar) => Some(scalar),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => NetworkPayload::PointBatch(
+                singles
+                    .into_iter()
+                    .filter_map(|payload| match payload {
+                        NetworkPayload::Point(point) => Some(point),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+        };
+    }
+}
+
+/// A network that modifies chosen outbound messages before sending them on an underlying
+/// network
+pub struct TamperingNetwork<N: MpcNetwork> {
+    /// The underlying network
+    inner: N,
+    /// The modifications to apply
+    handle: TamperHandle,
+}
+
+impl<N: MpcNetwork> TamperingNetwork<N> {
+    /// Wrap the given network
+    pub fn new(inner: N) -> Self {
+        Self {
+            inner,
+            handle: TamperHandle::default(),
+        }
+    }
+
+    /// A handle used to register modifications
+    pub fn handle(&self) -> TamperHandle {
+        self.handle.clone()
+    }
+}
+
+#[async_trait]
+impl<N: MpcNetwork + Unpin> MpcNetwork for TamperingNetwork<N> {
+    fn party_id(&self) -> PartyId {
+        self.inner.party_id()
+    }
+
+    fn peer_identity(&self) -> Option<StarkPoint> {
+        self.inner.peer_identity()
+    }
+
+    fn session_id(&self) -> Option<SessionId> {
+        self.inner.session_id()
+    }
+
+    async fn close(&mut self) -> Result<(), MpcNetworkError> {
+        self.inner.close().await
+    }
+}
+
+impl<N: MpcNetwork + Unpin> Stream for TamperingNetwork<N> {
+    type Item = Result<NetworkOutbound, MpcNetworkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+impl<N: MpcNetwork + Unpin> Sink<NetworkOutbound> for TamperingNetwork<N> {
+    type Error = MpcNetworkError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, mut item: NetworkOutbound) -> Result<(), Self::Error> {
+        self.handle.apply(&mut item);
+        Pin::new(&mut self.inner).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
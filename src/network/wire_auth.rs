@@ -0,0 +1,175 @@
+//! Defines the sequence numbers and authentication tags applied to the messages on each
+//! stream, so that frames injected, replayed, or reordered on the wire are rejected before
+//! they reach the computation
+//!
+//! The tag keys are derived from an ephemeral Diffie-Hellman exchange in the session
+//! handshake. Tags are computed as `SHA3-256(key || stream || seq || payload)`, SHA3 is not
+//! subject to length extension so a prefix-keyed hash is a secure MAC
+
+use bytes::{BufMut, BytesMut};
+use sha3::{Digest, Sha3_256};
+
+use crate::{algebra::stark_curve::StarkPoint, error::MpcNetworkError};
+
+use super::{PartyId, SessionId, BYTES_PER_U64};
+
+/// The number of bytes in an authentication tag
+pub(crate) const TAG_BYTES: usize = 32;
+/// The domain separator used when deriving a tag key from the shared secret
+const WIRE_KEY_DOMAIN: &[u8] = b"mpc-stark-wire-key";
+/// Error message emitted when a frame is too short to hold a sequence number and tag
+const ERR_FRAME_TOO_SHORT: &str = "frame too short to be authenticated";
+/// Error message emitted when a frame's tag does not verify
+const ERR_INVALID_TAG: &str = "frame failed authentication";
+/// Error message emitted when an authenticated frame arrives out of sequence
+const ERR_UNEXPECTED_SEQUENCE: &str = "replayed or reordered frame";
+
+/// A key that tags the messages sent by one party
+pub(crate) type WireKey = [u8; 32];
+
+/// Derive the key that tags the messages sent by the given party from the shared secret of
+/// the session handshake
+pub(crate) fn derive_wire_key(
+    shared_secret: &StarkPoint,
+    session_id: &SessionId,
+    sender: PartyId,
+) -> WireKey {
+    let mut hasher = Sha3_256::new();
+    hasher.update(WIRE_KEY_DOMAIN);
+    hasher.update(shared_secret.to_bytes());
+    hasher.update(session_id);
+    hasher.update(sender.to_le_bytes());
+
+    hasher.finalize().into()
+}
+
+/// Sequences and tags the messages sent on a stream, and checks those received on it
+#[derive(Debug)]
+pub(crate) struct StreamAuthenticator {
+    /// The key that tags outbound messages
+    send_key: WireKey,
+    /// The key that tags inbound messages
+    recv_key: WireKey,
+    /// The index of the stream, binding each message to the stream it was sent on
+    stream: u64,
+    /// The sequence number of the next outbound message
+    send_seq: u64,
+    /// The sequence number expected of the next inbound message
+    recv_seq: u64,
+}
+
+impl StreamAuthenticator {
+    /// Constructor
+    pub fn new(send_key: WireKey, recv_key: WireKey, stream: usize) -> Self {
+        Self {
+            send_key,
+            recv_key,
+            stream: stream as u64,
+            send_seq: 0,
+            recv_seq: 0,
+        }
+    }
+
+    /// Seal an outbound message written to the end of a frame from `payload_start` on
+    ///
+    /// The next sequence number is written to the bytes reserved for it directly before the
+    /// payload, and the tag is appended to the frame, so the message is sealed without being
+    /// copied
+    pub fn seal_in_place(&mut self, frame: &mut BytesMut, payload_start: usize) {
+        let seq = self.send_seq;
+        self.send_seq += 1;
+
+        frame[payload_start - BYTES_PER_U64..payload_start].copy_from_slice(&seq.to_le_bytes());
+        let tag = compute_tag(&self.send_key, self.stream, seq, &frame[payload_start..]);
+        frame.put_slice(&tag);
+    }
+
+    /// Check the tag and sequence number of an inbound message, returning its payload
+    pub fn open<'a>(&mut self, frame: &'a [u8]) -> Result<&'a [u8], MpcNetworkError> {
+        if frame.len() < BYTES_PER_U64 + TAG_BYTES {
+            return Err(MpcNetworkError::RecvError(ERR_FRAME_TOO_SHORT.to_string()));
+        }
+
+        let (seq_bytes, rest) = frame.split_at(BYTES_PER_U64);
+        let (payload, tag) = rest.split_at(rest.len() - TAG_BYTES);
+        let seq = u64::from_le_bytes(seq_bytes.try_into().unwrap());
+
+        let expected_tag = compute_tag(&self.recv_key, self.stream, seq, payload);
+        if !constant_time_eq(tag, &expected_tag) {
+            return Err(MpcNetworkError::RecvError(ERR_INVALID_TAG.to_string()));
+        }
+
+        if seq != self.recv_seq {
+            return Err(MpcNetworkError::RecvError(
+                ERR_UNEXPECTED_SEQUENCE.to_string(),
+            ));
+        }
+
+        self.recv_seq += 1;
+        Ok(payload)
+    }
+}
+
+/// Compute the tag of a message
+fn compute_tag(key: &WireKey, stream: u64, seq: u64, payload: &[u8]) -> [u8; TAG_BYTES] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(key);
+    hasher.update(stream.to_le_bytes());
+    hasher.update(seq.to_le_bytes());
+    hasher.update(payload);
+
+    hasher.finalize().into()
+}
+
+/// Compare two tags without short circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::{BufMut, BytesMut};
+
+    use super::{StreamAuthenticator, BYTES_PER_U64};
+
+    /// Seal a message into a frame of its own
+    fn seal(auth: &mut StreamAuthenticator, payload: &[u8]) -> BytesMut {
+        let mut frame = BytesMut::new();
+        frame.put_u64_le(0 /* sequence number */);
+        frame.put_slice(payload);
+        auth.seal_in_place(&mut frame, BYTES_PER_U64);
+
+        frame
+    }
+
+    /// Tests that replayed, reordered, and tampered frames are rejected
+    #[test]
+    fn test_reject_replay() {
+        let (key0, key1) = ([0u8; 32], [1u8; 32]);
+        let mut sender = StreamAuthenticator::new(key0, key1, 0 /* stream */);
+        let mut receiver = StreamAuthenticator::new(key1, key0, 0 /* stream */);
+
+        let frame0 = seal(&mut sender, b"first");
+        let frame1 = seal(&mut sender, b"second");
+        let frame2 = seal(&mut sender, b"third");
+
+        // Reordered
+        assert!(receiver.open(&frame1).is_err());
+        assert_eq!(receiver.open(&frame0).unwrap(), b"first");
+
+        // Replayed
+        assert!(receiver.open(&frame0).is_err());
+        assert_eq!(receiver.open(&frame1).unwrap(), b"second");
+
+        // Tampered
+        let mut tampered = frame2.clone();
+        tampered[8] ^= 1;
+        assert!(receiver.open(&tampered).is_err());
+
+        // Moved to another stream
+        let mut other_stream = StreamAuthenticator::new(key1, key0, 1 /* stream */);
+        assert!(other_stream.open(&frame0).is_err());
+
+        assert_eq!(receiver.open(&frame2).unwrap(), b"third");
+    }
+}
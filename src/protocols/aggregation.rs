@@ -0,0 +1,110 @@
+//! Defines secure aggregation of the parties' private vectors
+//!
+//! Each party shares its vector, the shared vectors are summed elementwise, and only the sum
+//! is opened. The opening is authenticated, so a party that tampers with its shares is
+//! detected before the aggregate is returned
+
+use futures::future::join_all;
+use itertools::Itertools;
+
+use crate::{
+    algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+    error::MpcError,
+    MpcFabric, PARTY0, PARTY1,
+};
+
+/// The number of parties whose inputs are aggregated
+const N_PARTIES: u32 = 2;
+
+/// Compute the elementwise sum of the parties' private vectors, revealing only the sum
+///
+/// Both parties must pass vectors of the same length. Fails with
+/// `MpcError::AuthenticationError` if the MAC check on the opened sum fails
+pub async fn secure_sum<T: Into<Scalar>>(
+    fabric: &MpcFabric,
+    inputs: Vec<T>,
+) -> Result<Vec<Scalar>, MpcError> {
+    if inputs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Each party passes a placeholder for the vector it receives
+    let n = inputs.len();
+    let inputs = inputs.into_iter().map(Into::into).collect_vec();
+    let placeholder = vec![Scalar::zero(); n];
+    let (party0_inputs, party1_inputs) = if fabric.party_id() == PARTY0 {
+        (inputs, placeholder)
+    } else {
+        (placeholder, inputs)
+    };
+
+    let party0_shares = fabric.batch_share_scalar(party0_inputs, PARTY0);
+    let party1_shares = fabric.batch_share_scalar(party1_inputs, PARTY1);
+    let sums = AuthenticatedScalarResult::batch_add(&party0_shares, &party1_shares);
+
+    join_all(AuthenticatedScalarResult::open_authenticated_batch(&sums))
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Compute the elementwise mean of the parties' private vectors of integers, revealing only
+/// the mean
+///
+/// Both parties must pass vectors of the same length, see `secure_sum`
+pub async fn secure_mean(fabric: &MpcFabric, inputs: Vec<u64>) -> Result<Vec<f64>, MpcError> {
+    let sums = secure_sum(fabric, inputs).await?;
+    Ok(sums
+        .into_iter()
+        .map(|sum| {
+            let sum = u128::try_from(sum.to_biguint()).expect("sum of u64s exceeds u128");
+            sum as f64 / f64::from(N_PARTIES)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use crate::{algebra::scalar::Scalar, test_helpers::execute_mock_mpc, PARTY0};
+
+    use super::{secure_mean, secure_sum};
+
+    /// Tests summing and averaging the parties' vectors
+    #[tokio::test]
+    async fn test_secure_sum_mean() {
+        let party0_inputs = vec![1u64, 10, u64::MAX, 0];
+        let party1_inputs = vec![2u64, 5, u64::MAX, 0];
+
+        let (res0, res1) = execute_mock_mpc(|fabric| {
+            let inputs = if fabric.party_id() == PARTY0 {
+                party0_inputs.clone()
+            } else {
+                party1_inputs.clone()
+            };
+
+            async move {
+                let sum = secure_sum(&fabric, inputs.clone()).await;
+                let mean = secure_mean(&fabric, inputs).await;
+                let empty = secure_sum::<u64>(&fabric, vec![]).await;
+
+                (sum, mean, empty)
+            }
+        })
+        .await;
+
+        let expected_sum = party0_inputs
+            .iter()
+            .zip(party1_inputs.iter())
+            .map(|(a, b)| Scalar::from(*a) + Scalar::from(*b))
+            .collect_vec();
+        let max = u64::MAX as f64;
+        let expected_mean = vec![1.5, 7.5, max, 0.0];
+
+        assert_eq!(res0, res1);
+        assert_eq!(res0.0, Ok(expected_sum));
+        assert_eq!(res0.1, Ok(expected_mean));
+        assert_eq!(res0.2, Ok(vec![]));
+    }
+}
@@ -0,0 +1,205 @@
+//! Defines ElGamal encryption over the Stark curve to an additively shared secret key,
+//! and the collaborative decryption of ciphertexts under that key
+//!
+//! A ciphertext of the point `M` under the public key `X = x * G` is the pair
+//!     `(C1, C2) = (r * G, M + r * X)`
+//! for a random `r`, and decrypts to `M = C2 - x * C1`. With `x` shared, `x * C1` is a
+//! shared scalar times a public point, so decryption needs no communication and yields the
+//! plaintext as a shared point. It may be computed on further or opened to decrypt
+//! at the end of an MPC
+
+use itertools::Itertools;
+use rand::{CryptoRng, RngCore};
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        authenticated_stark_point::AuthenticatedStarkPointResult,
+        scalar::Scalar,
+        stark_curve::{StarkPoint, StarkPointResult},
+    },
+    error::MpcError,
+    MpcFabric,
+};
+
+/// An ElGamal ciphertext over the Stark curve
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElGamalCiphertext {
+    /// The commitment to the encryption randomness, `C1 = r * G`
+    pub c1: StarkPoint,
+    /// The masked plaintext, `C2 = M + r * X`
+    pub c2: StarkPoint,
+}
+
+/// Encrypt a point to the given public key
+///
+/// Any party, in or outside of the MPC, may encrypt to the public key of a shared key
+pub fn encrypt<R: RngCore + CryptoRng>(
+    public_key: &StarkPoint,
+    message: &StarkPoint,
+    rng: &mut R,
+) -> ElGamalCiphertext {
+    let r = Scalar::random(rng);
+    ElGamalCiphertext {
+        c1: StarkPoint::generator() * r,
+        c2: *message + *public_key * r,
+    }
+}
+
+/// Decrypt a ciphertext under a plaintext secret key
+pub fn decrypt(secret_key: &Scalar, ciphertext: &ElGamalCiphertext) -> StarkPoint {
+    ciphertext.c2 - ciphertext.c1 * secret_key
+}
+
+/// An ElGamal decryption key whose secret is additively shared between the parties
+#[derive(Clone)]
+pub struct SharedElGamalKey {
+    /// The shared secret key `x`
+    secret: AuthenticatedScalarResult,
+    /// The public key `X = x * G`
+    public_key: StarkPoint,
+}
+
+impl SharedElGamalKey {
+    /// Generate a fresh shared decryption key, neither party learns the secret key
+    pub async fn generate(fabric: &MpcFabric) -> Result<Self, MpcError> {
+        let secret = fabric.random_shared_scalars_authenticated(1).pop().unwrap();
+        Self::from_secret(secret).await
+    }
+
+    /// Construct a decryption key from an already shared secret key, opening its public key
+    pub async fn from_secret(secret: AuthenticatedScalarResult) -> Result<Self, MpcError> {
+        let public_key = (&secret * StarkPoint::generator())
+            .open_authenticated()
+            .await?;
+
+        Ok(Self { secret, public_key })
+    }
+
+    /// Get the public key that ciphertexts are encrypted to
+    pub fn public_key(&self) -> StarkPoint {
+        self.public_key
+    }
+
+    /// Decrypt a ciphertext to a shared plaintext
+    pub fn decrypt(&self, ciphertext: &ElGamalCiphertext) -> AuthenticatedStarkPointResult {
+        // Computed as `x * (-C1) + C2`, the shared and public operands of subtraction
+        // commute in the authenticated point arithmetic
+        &self.secret * -ciphertext.c1 + ciphertext.c2
+    }
+
+    /// Decrypt a batch of ciphertexts to shared plaintexts
+    pub fn batch_decrypt(
+        &self,
+        ciphertexts: &[ElGamalCiphertext],
+    ) -> Vec<AuthenticatedStarkPointResult> {
+        if ciphertexts.is_empty() {
+            return Vec::new();
+        }
+
+        let fabric = self.secret.fabric();
+        let n = ciphertexts.len();
+        let neg_c1 = fabric.allocate_points(ciphertexts.iter().map(|c| -c.c1).collect_vec());
+        let c2 = fabric.allocate_points(ciphertexts.iter().map(|c| c.c2).collect_vec());
+
+        let secrets = vec![self.secret.clone(); n];
+        let masks = StarkPointResult::batch_mul_authenticated(&secrets, &neg_c1);
+        AuthenticatedStarkPointResult::batch_add_public(&masks, &c2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, thread_rng, SeedableRng};
+
+    use crate::{
+        algebra::{
+            authenticated_stark_point::AuthenticatedStarkPointResult, scalar::Scalar,
+            stark_curve::StarkPoint,
+        },
+        protocols::elgamal::{decrypt, encrypt, SharedElGamalKey},
+        random_point,
+        test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    /// Tests plaintext encryption and decryption
+    #[test]
+    fn test_encrypt_decrypt() {
+        let mut rng = thread_rng();
+        let secret_key = Scalar::random(&mut rng);
+        let message = random_point();
+
+        let ciphertext = encrypt(&(StarkPoint::generator() * secret_key), &message, &mut rng);
+        assert_eq!(decrypt(&secret_key, &ciphertext), message);
+    }
+
+    /// Tests collaboratively decrypting a ciphertext under a shared key
+    #[tokio::test]
+    async fn test_shared_decrypt() {
+        let mut rng = thread_rng();
+        let secret_key = Scalar::random(&mut rng);
+        let message = random_point();
+        let ciphertext = encrypt(&(StarkPoint::generator() * secret_key), &message, &mut rng);
+
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared = fabric.share_scalar(secret_key, PARTY0);
+            let key = SharedElGamalKey::from_secret(shared).await.unwrap();
+            key.decrypt(&ciphertext).open_authenticated().await
+        })
+        .await;
+
+        assert_eq!(res.unwrap(), message);
+    }
+
+    /// Tests collaboratively decrypting a batch of ciphertexts under a shared key
+    #[tokio::test]
+    async fn test_shared_batch_decrypt() {
+        const N: usize = 5;
+        let mut rng = thread_rng();
+        let secret_key = Scalar::random(&mut rng);
+        let public_key = StarkPoint::generator() * secret_key;
+
+        let messages = (0..N).map(|_| random_point()).collect_vec();
+        let ciphertexts = messages
+            .iter()
+            .map(|m| encrypt(&public_key, m, &mut rng))
+            .collect_vec();
+
+        let (res, _) = execute_mock_mpc(|fabric| {
+            let ciphertexts = ciphertexts.clone();
+            async move {
+                let shared = fabric.share_scalar(secret_key, PARTY0);
+                let key = SharedElGamalKey::from_secret(shared).await.unwrap();
+
+                let decrypted = key.batch_decrypt(&ciphertexts);
+                futures::future::join_all(AuthenticatedStarkPointResult::open_authenticated_batch(
+                    &decrypted,
+                ))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+            }
+        })
+        .await;
+
+        assert_eq!(res.unwrap(), messages);
+    }
+
+    /// Tests that a generated key decrypts ciphertexts encrypted to its public key
+    #[tokio::test]
+    async fn test_generated_key() {
+        let message = random_point();
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let key = SharedElGamalKey::generate(&fabric).await.unwrap();
+
+            // Both parties must decrypt the same ciphertext, so the randomness is fixed
+            let ciphertext = encrypt(&key.public_key(), &message, &mut StdRng::from_seed([1; 32]));
+            key.decrypt(&ciphertext).open_authenticated().await
+        })
+        .await;
+
+        assert_eq!(res.unwrap(), message);
+    }
+}
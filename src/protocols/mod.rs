@@ -0,0 +1,10 @@
+//! Defines cryptographic protocols run on top of the fabric, i.e. protocols whose secret
+//! state is shared between the parties and whose public outputs are opened
+//! with authentication
+
+pub mod aggregation;
+pub mod elgamal;
+pub mod oprf;
+pub mod psi;
+pub mod schnorr;
+pub mod witness;
@@ -0,0 +1,201 @@
+//! Defines an oblivious PRF in the 2HashDH construction, under a key that is additively
+//! shared between the parties
+//!
+//! The PRF of an input `x` under the key `k` is `F_k(x) = H_2(x, k * H_1(x))` where `H_1`
+//! hashes to the curve. The receiver blinds `H_1(x)` with a random scalar `r` and sends
+//!     `B = r * H_1(x)`
+//! in the clear, the parties open `k * B` with authentication, and the receiver unblinds the
+//! opening as `r^{-1} * k * B = k * H_1(x)`. The blinded point is uniformly distributed, so the
+//! other party learns nothing about the receiver's input, and the key is never reconstructed
+
+use futures::future::join_all;
+use itertools::Itertools;
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        authenticated_stark_point::AuthenticatedStarkPointResult,
+        scalar::Scalar,
+        stark_curve::{StarkPoint, StarkPointResult},
+    },
+    error::MpcError,
+    fabric::ResultValue,
+    network::PartyId,
+    MpcFabric,
+};
+
+/// The domain separation tag of the hash of an input to the curve
+const OPRF_HASH_TO_CURVE_DST: &[u8] = b"mpc-stark-oprf-2hashdh-h1";
+/// The domain separator of the hash that derives the PRF output
+const OPRF_OUTPUT_DOMAIN: &[u8] = b"mpc-stark-oprf-2hashdh-h2";
+
+/// The output of the PRF
+pub type OprfOutput = [u8; 32];
+
+/// Evaluate the PRF on an input under a plaintext key
+pub fn oprf(key: Scalar, input: &[u8]) -> OprfOutput {
+    oprf_output(input, &(hash_input(input) * key))
+}
+
+/// Hash an input to the curve
+fn hash_input(input: &[u8]) -> StarkPoint {
+    StarkPoint::hash_to_curve(input, OPRF_HASH_TO_CURVE_DST)
+        .expect("the simplified SWU map is defined for the Stark curve")
+}
+
+/// Derive the PRF output from an input and its keyed point `k * H_1(x)`
+fn oprf_output(input: &[u8], keyed_point: &StarkPoint) -> OprfOutput {
+    let mut hasher = Sha3_256::new();
+    hasher.update(OPRF_OUTPUT_DOMAIN);
+    hasher.update((input.len() as u64).to_be_bytes());
+    hasher.update(input);
+    hasher.update(keyed_point.to_bytes());
+
+    hasher.finalize().into()
+}
+
+/// An OPRF key that is additively shared between the parties
+#[derive(Clone)]
+pub struct SharedOprfKey {
+    /// The shared key `k`
+    key: AuthenticatedScalarResult,
+}
+
+impl SharedOprfKey {
+    /// Generate a fresh shared key, neither party learns the key
+    pub fn generate(fabric: &MpcFabric) -> Self {
+        let key = fabric.random_shared_scalars_authenticated(1).pop().unwrap();
+        Self::from_secret(key)
+    }
+
+    /// Construct a key from an already shared secret
+    pub fn from_secret(key: AuthenticatedScalarResult) -> Self {
+        Self { key }
+    }
+
+    /// The shared key
+    pub fn secret(&self) -> &AuthenticatedScalarResult {
+        &self.key
+    }
+
+    /// Evaluate the PRF on the receiver's private input, see `batch_evaluate`
+    pub async fn evaluate(
+        &self,
+        input: &[u8],
+        receiver: PartyId,
+    ) -> Result<Option<OprfOutput>, MpcError> {
+        let outputs = self.batch_evaluate(&[input], receiver).await?;
+        Ok(outputs.map(|mut outputs| outputs.remove(0)))
+    }
+
+    /// Evaluate the PRF on a batch of the receiver's private inputs
+    ///
+    /// The receiver gets the outputs and the other party gets `None`. As with the fabric's
+    /// sharing methods, the other party passes a batch of the same length whose values are
+    /// ignored. Fails with `MpcError::AuthenticationError` if the MAC check on the keyed
+    /// points fails
+    pub async fn batch_evaluate<T: AsRef<[u8]>>(
+        &self,
+        inputs: &[T],
+        receiver: PartyId,
+    ) -> Result<Option<Vec<OprfOutput>>, MpcError> {
+        if inputs.is_empty() {
+            return Ok((self.fabric().party_id() == receiver).then(Vec::new));
+        }
+
+        // The receiver blinds the hashes of its inputs and sends them in the clear
+        let n = inputs.len();
+        let fabric = self.fabric();
+        let is_receiver = fabric.party_id() == receiver;
+        let blinders = if is_receiver {
+            fabric.random_scalars(n)
+        } else {
+            vec![Scalar::zero(); n]
+        };
+        let blinded = if is_receiver {
+            inputs
+                .iter()
+                .zip(blinders.iter())
+                .map(|(input, r)| hash_input(input.as_ref()) * r)
+                .collect_vec()
+        } else {
+            vec![StarkPoint::identity(); n]
+        };
+
+        let blinded = fabric.batch_share_plaintext(blinded, receiver);
+        let blinded: Vec<StarkPointResult> =
+            fabric.new_batch_gate_op(vec![blinded.id()], n /* output_arity */, |mut args| {
+                let points: Vec<StarkPoint> = args.remove(0).into();
+                points.into_iter().map(ResultValue::Point).collect_vec()
+            });
+
+        let keyed = blinded.iter().map(|b| b * &self.key).collect_vec();
+        let opened: Vec<StarkPoint> = join_all(
+            AuthenticatedStarkPointResult::open_authenticated_batch(&keyed),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+        if !is_receiver {
+            return Ok(None);
+        }
+
+        // Unblind the keyed points and derive the outputs
+        let mut blinder_inverses = blinders;
+        Scalar::batch_inverse(&mut blinder_inverses);
+        let outputs = inputs
+            .iter()
+            .zip(opened.iter().zip(blinder_inverses))
+            .map(|(input, (point, r_inv))| oprf_output(input.as_ref(), &(*point * r_inv)))
+            .collect_vec();
+
+        Ok(Some(outputs))
+    }
+
+    /// The fabric that the key is allocated in
+    fn fabric(&self) -> &MpcFabric {
+        self.key.fabric()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use rand::thread_rng;
+
+    use crate::{algebra::scalar::Scalar, test_helpers::execute_mock_mpc, PARTY0, PARTY1};
+
+    use super::{oprf, SharedOprfKey};
+
+    /// Tests that the oblivious evaluation matches the plaintext PRF, and that only the
+    /// receiver learns the outputs
+    #[tokio::test]
+    async fn test_oprf() {
+        let mut rng = thread_rng();
+        let key = Scalar::random(&mut rng);
+        let inputs = vec![b"alice".to_vec(), b"bob".to_vec(), vec![]];
+
+        let (res0, res1) = execute_mock_mpc(|fabric| {
+            let inputs = inputs.clone();
+            async move {
+                let key = SharedOprfKey::from_secret(fabric.share_scalar(key, PARTY0));
+                let batch = key.batch_evaluate(&inputs, PARTY1).await.unwrap();
+                let single = key.evaluate(b"carol", PARTY0).await.unwrap();
+
+                (batch, single)
+            }
+        })
+        .await;
+
+        let expected = inputs.iter().map(|x| oprf(key, x)).collect_vec();
+        assert_eq!(res0.0, None);
+        assert_eq!(res1.0, Some(expected));
+        assert_eq!(res0.1, Some(oprf(key, b"carol")));
+        assert_eq!(res1.1, None);
+
+        // Distinct keys and inputs give distinct outputs
+        assert_ne!(oprf(key, b"alice"), oprf(key, b"bob"));
+        assert_ne!(oprf(key, b"alice"), oprf(key + Scalar::one(), b"alice"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Defines private set intersection on top of the shared-key OPRF
+//!
+//! Both parties evaluate the OPRF on their own items under a key that neither party knows, so
+//! the outputs can only be compared, and two items match exactly when their outputs do. The
+//! outputs are either compared in the clear by one party, which learns the intersection, or
+//! shared and compared with batched equality checks, which leaves a shared membership bit for
+//! each item. The parties learn the sizes of each other's sets, and the sets are assumed to
+//! not contain duplicates
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{
+    algebra::scalar::Scalar,
+    error::MpcError,
+    gadgets::{bits::AuthenticatedBit, comparison::batch_eq_shared, linear_combination},
+    network::PartyId,
+    MpcFabric, PARTY0, PARTY1,
+};
+
+use super::oprf::{OprfOutput, SharedOprfKey};
+
+/// The number of bits of an OPRF output that are compared for shared membership
+///
+/// Truncation keeps the equality checks cheap, two distinct items collide with probability
+/// `2^{-64}`
+const MEMBERSHIP_TAG_BITS: usize = 64;
+
+/// Compute the intersection of the parties' sets, revealed only to the receiver
+///
+/// Each party passes its own items. The other party sends its OPRF outputs to the receiver in
+/// sorted order, so the order reveals nothing about its items, and the receiver matches them
+/// against its own outputs. The receiver gets the indices of its items that are in the
+/// intersection and the other party gets `None`. Fails with `MpcError::AuthenticationError`
+/// if a MAC check in the OPRF fails
+pub async fn psi_intersection<T: AsRef<[u8]>>(
+    key: &SharedOprfKey,
+    items: &[T],
+    receiver: PartyId,
+) -> Result<Option<Vec<usize>>, MpcError> {
+    let fabric = key.secret().fabric();
+    let (own_outputs, peer_len) = evaluate_sets(key, items).await?;
+
+    // The other party sends its outputs in the clear
+    let is_receiver = fabric.party_id() == receiver;
+    let sent = if is_receiver {
+        vec![Scalar::zero(); peer_len]
+    } else {
+        own_outputs
+            .iter()
+            .sorted()
+            .map(|output| Scalar::from_be_bytes_mod_order(output))
+            .collect_vec()
+    };
+    let received = fabric.batch_share_plaintext(sent, 1 - receiver).await;
+    if !is_receiver {
+        return Ok(None);
+    }
+
+    let peer_outputs: HashSet<Scalar> = received.into_iter().collect();
+    let intersection = own_outputs
+        .iter()
+        .positions(|output| peer_outputs.contains(&Scalar::from_be_bytes_mod_order(output)))
+        .collect_vec();
+
+    Ok(Some(intersection))
+}
+
+/// Compute shared bits indicating whether each of the owner's items is in the other party's
+/// set
+///
+/// Each party passes its own items, and both parties get one shared bit for each of the
+/// owner's items, in order. The owner's truncated OPRF outputs are checked for equality
+/// against each of the other party's, and as the sets contain no duplicates at most one check
+/// succeeds, so the membership bit is the sum of the checks. Fails with
+/// `MpcError::AuthenticationError` if a MAC check in the OPRF fails
+pub async fn psi_membership<T: AsRef<[u8]>>(
+    key: &SharedOprfKey,
+    items: &[T],
+    owner: PartyId,
+) -> Result<Vec<AuthenticatedBit>, MpcError> {
+    let fabric = key.secret().fabric();
+    let (own_outputs, peer_len) = evaluate_sets(key, items).await?;
+    let own_tags = own_outputs.iter().map(membership_tag).collect_vec();
+
+    // Each party shares the tags of its own outputs
+    let (owner_tags, other_tags) = if fabric.party_id() == owner {
+        (own_tags, vec![Scalar::zero(); peer_len])
+    } else {
+        (vec![Scalar::zero(); peer_len], own_tags)
+    };
+    let (n, m) = (owner_tags.len(), other_tags.len());
+    let owner_tags = fabric.batch_share_scalar(owner_tags, owner);
+    let other_tags = fabric.batch_share_scalar(other_tags, 1 - owner);
+    if m == 0 {
+        return Ok(fabric.zeros_authenticated(n));
+    }
+
+    // Compare every pair of tags in a single batch
+    let lhs = owner_tags
+        .iter()
+        .flat_map(|tag| (0..m).map(|_| tag.clone()))
+        .collect_vec();
+    let rhs = (0..n)
+        .flat_map(|_| other_tags.iter().cloned())
+        .collect_vec();
+    let eq = batch_eq_shared::<MEMBERSHIP_TAG_BITS>(&lhs, &rhs);
+
+    let ones = vec![Scalar::one(); m];
+    Ok(eq
+        .chunks(m)
+        .map(|checks| linear_combination(checks, &ones))
+        .collect_vec())
+}
+
+/// Evaluate the OPRF on both parties' sets
+///
+/// Returns the outputs on the local party's items and the size of the other party's set
+async fn evaluate_sets<T: AsRef<[u8]>>(
+    key: &SharedOprfKey,
+    items: &[T],
+) -> Result<(Vec<OprfOutput>, usize), MpcError> {
+    let fabric = key.secret().fabric();
+    let party_id = fabric.party_id();
+    let peer_len = exchange_set_size(fabric, items.len()).await;
+
+    // Each party passes placeholders for the other party's evaluation
+    let items = items.iter().map(AsRef::as_ref).collect_vec();
+    let placeholders: Vec<&[u8]> = vec![&[]; peer_len];
+    let (party0_items, party1_items) = if party_id == PARTY0 {
+        (items, placeholders)
+    } else {
+        (placeholders, items)
+    };
+
+    let party0_outputs = key.batch_evaluate(&party0_items, PARTY0).await?;
+    let party1_outputs = key.batch_evaluate(&party1_items, PARTY1).await?;
+    let own_outputs = party0_outputs.or(party1_outputs).unwrap();
+
+    Ok((own_outputs, peer_len))
+}
+
+/// Send the size of the local party's set and receive the size of the other party's
+async fn exchange_set_size(fabric: &MpcFabric, len: usize) -> usize {
+    let len = Scalar::from(len as u64);
+    let party0_len = fabric.share_plaintext(len, PARTY0).await;
+    let party1_len = fabric.share_plaintext(len, PARTY1).await;
+
+    let peer_len = if fabric.party_id() == PARTY0 {
+        party1_len
+    } else {
+        party0_len
+    };
+    u64::try_from(peer_len.to_biguint()).expect("set size exceeds u64") as usize
+}
+
+/// Truncate an OPRF output to the tag compared for shared membership
+fn membership_tag(output: &OprfOutput) -> Scalar {
+    let tag_bytes = MEMBERSHIP_TAG_BITS / 8;
+    Scalar::from_be_bytes_mod_order(&output[..tag_bytes])
+}
+
+#[cfg(test)]
+mod test {
+    use futures::future::join_all;
+
+    use crate::{
+        algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar},
+        protocols::oprf::SharedOprfKey,
+        test_helpers::execute_mock_mpc,
+        PARTY0, PARTY1,
+    };
+
+    use super::{psi_intersection, psi_membership};
+
+    /// Tests revealing the intersection to one party and sharing membership bits
+    #[tokio::test]
+    async fn test_psi() {
+        let party0_items = vec!["alice", "bob", "carol", "dave"];
+        let party1_items = vec!["erin", "carol", "alice"];
+
+        let (res0, res1) = execute_mock_mpc(|fabric| {
+            let items = if fabric.party_id() == PARTY0 {
+                party0_items.clone()
+            } else {
+                party1_items.clone()
+            };
+
+            async move {
+                let key = SharedOprfKey::generate(&fabric);
+                let intersection = psi_intersection(&key, &items, PARTY0).await.unwrap();
+
+                let membership = psi_membership(&key, &items, PARTY1).await.unwrap();
+                let membership: Result<Vec<_>, _> = join_all(
+                    AuthenticatedScalarResult::open_authenticated_batch(&membership),
+                )
+                .await
+                .into_iter()
+                .collect();
+
+                (intersection, membership.unwrap())
+            }
+        })
+        .await;
+
+        assert_eq!(res0.0, Some(vec![0, 2]));
+        assert_eq!(res1.0, None);
+
+        let expected = [0u8, 1, 1].map(Scalar::from).to_vec();
+        assert_eq!(res0.1, expected);
+        assert_eq!(res1.1, expected);
+    }
+}
@@ -0,0 +1,143 @@
+//! Defines two-party Schnorr signing over the Stark curve under an additively shared
+//! secret key
+//!
+//! A signature on `m` under the key `x` with public key `X = x * G` is a pair `(R, s)` where
+//!     `R = k * G`, `e = H(R || X || m)`, `s = k + e * x`
+//! for a nonce `k`. The nonce is sampled as a fresh shared value for each signature, so
+//! neither party learns it, and `R` and `s` are opened with authentication so that a party
+//! deviating from the protocol is detected by the MAC check before a signature is produced
+
+use digest::Digest;
+use sha3::Sha3_256;
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar, stark_curve::StarkPoint,
+    },
+    error::MpcError,
+    MpcFabric,
+};
+
+/// The domain separation tag prepended to the preimage of a signature challenge
+const SCHNORR_CHALLENGE_DOMAIN: &[u8] = b"mpc-stark-schnorr-challenge";
+
+/// A Schnorr signature over the Stark curve
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchnorrSignature {
+    /// The commitment to the signing nonce, `R = k * G`
+    pub r: StarkPoint,
+    /// The response to the challenge, `s = k + e * x`
+    pub s: Scalar,
+}
+
+impl SchnorrSignature {
+    /// Verify the signature on a message under the given public key
+    pub fn verify(&self, public_key: &StarkPoint, message: &[u8]) -> bool {
+        let challenge = schnorr_challenge(&self.r, public_key, message);
+        StarkPoint::generator() * self.s == self.r + *public_key * challenge
+    }
+}
+
+/// A Schnorr signing key whose secret is additively shared between the parties
+#[derive(Clone)]
+pub struct SharedSchnorrKey {
+    /// The shared secret key `x`
+    secret: AuthenticatedScalarResult,
+    /// The public key `X = x * G`
+    public_key: StarkPoint,
+}
+
+impl SharedSchnorrKey {
+    /// Generate a fresh shared signing key, neither party learns the secret key
+    pub async fn generate(fabric: &MpcFabric) -> Result<Self, MpcError> {
+        let secret = fabric.random_shared_scalars_authenticated(1).pop().unwrap();
+        Self::from_secret(secret).await
+    }
+
+    /// Construct a signing key from an already shared secret key, opening its public key
+    pub async fn from_secret(secret: AuthenticatedScalarResult) -> Result<Self, MpcError> {
+        let public_key = (&secret * StarkPoint::generator())
+            .open_authenticated()
+            .await?;
+
+        Ok(Self { secret, public_key })
+    }
+
+    /// Get the public key of the signing key
+    pub fn public_key(&self) -> StarkPoint {
+        self.public_key
+    }
+
+    /// Jointly sign a message under the shared key
+    ///
+    /// Both parties must sign the same message. Fails with `MpcError::AuthenticationError`
+    /// if either opening fails its MAC check, unless the fabric is configured to be
+    /// semi-honest
+    pub async fn sign(&self, message: &[u8]) -> Result<SchnorrSignature, MpcError> {
+        let fabric = self.secret.fabric();
+        let nonce = fabric.random_shared_scalars_authenticated(1).pop().unwrap();
+        let r = (&nonce * StarkPoint::generator())
+            .open_authenticated()
+            .await?;
+
+        let challenge = schnorr_challenge(&r, &self.public_key, message);
+        let s = (nonce + &self.secret * challenge)
+            .open_authenticated()
+            .await?;
+
+        Ok(SchnorrSignature { r, s })
+    }
+}
+
+/// Compute the challenge `e = H(R || X || m)` of a signature
+fn schnorr_challenge(r: &StarkPoint, public_key: &StarkPoint, message: &[u8]) -> Scalar {
+    let mut hasher = Sha3_256::new();
+    hasher.update(SCHNORR_CHALLENGE_DOMAIN);
+    hasher.update(r.to_bytes());
+    hasher.update(public_key.to_bytes());
+    hasher.update(message);
+
+    Scalar::from_be_bytes_mod_order(&hasher.finalize())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::{scalar::Scalar, stark_curve::StarkPoint},
+        protocols::schnorr::SharedSchnorrKey,
+        test_helpers::execute_mock_mpc,
+        PARTY0,
+    };
+
+    /// Tests that a jointly produced signature verifies under the shared key
+    #[tokio::test]
+    async fn test_sign() {
+        let message = b"transfer 100 to 0xdeadbeef";
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let key = SharedSchnorrKey::generate(&fabric).await.unwrap();
+            let sig = key.sign(message).await.unwrap();
+            (key.public_key(), sig)
+        })
+        .await;
+
+        let (public_key, sig) = res;
+        assert!(sig.verify(&public_key, message));
+        assert!(!sig.verify(&public_key, b"transfer 1000 to 0xdeadbeef"));
+    }
+
+    /// Tests signing under a key shared from a known secret
+    #[tokio::test]
+    async fn test_sign_known_key() {
+        let secret = Scalar::from(42u8);
+        let message = b"hello";
+        let (res, _) = execute_mock_mpc(|fabric| async move {
+            let shared = fabric.share_scalar(secret, PARTY0);
+            let key = SharedSchnorrKey::from_secret(shared).await.unwrap();
+            key.sign(message).await.unwrap()
+        })
+        .await;
+
+        let public_key = StarkPoint::generator() * secret;
+        assert!(res.verify(&public_key, message));
+    }
+}
@@ -0,0 +1,224 @@
+//! Defines the export of shared values as a witness for collaborative SNARK provers
+//!
+//! A collaborative prover runs the prover of a SNARK over a witness that is secret shared
+//! between the parties. The witness is exported from the fabric in the following layout,
+//! for a witness `w` of length `n` held under the MAC key `delta`:
+//!     - `shares[i]` is the local party's additive share of `w[i]`
+//!     - `macs[i]` is the local party's additive share of `delta * w[i]`
+//!     - `mac_key_share` is the local party's additive share of `delta`
+//! so that summing the exports of both parties element-wise gives `w`, `delta * w` and
+//! `delta`. The public modifiers the fabric tracks for public additions are folded into the
+//! MACs before export, so an exported witness is independent of how it was computed
+//!
+//! Before export the parties run a consistency check: a random linear combination of the
+//! witness, masked by a fresh shared value, is opened with authentication. The opened
+//! combination passes its MAC check only if the shares and MACs of the witness are
+//! consistent, and is recorded with the seed of its coefficients in a transcript that both
+//! parties hold identically
+
+use digest::Digest;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sha3::Sha3_256;
+
+use crate::{
+    algebra::{
+        authenticated_scalar::{AuthenticatedScalarResult, AUTHENTICATED_SCALAR_RESULT_LEN},
+        scalar::{Scalar, ScalarResult},
+    },
+    error::MpcError,
+    fabric::{join_results, ResultValue},
+    gadgets::linear_combination,
+    network::SessionId,
+    MpcFabric,
+};
+
+/// The domain separation tag of the coefficients of the witness consistency check
+const WITNESS_CHECK_DOMAIN: &[u8] = b"mpc-stark-shared-witness";
+
+/// A party's export of a shared witness
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedWitness {
+    /// The ID of the party that exported the witness
+    pub party_id: u64,
+    /// The local party's shares of the witness
+    pub shares: Vec<Scalar>,
+    /// The local party's shares of the MACs of the witness
+    pub macs: Vec<Scalar>,
+    /// The local party's share of the MAC key
+    pub mac_key_share: Scalar,
+    /// The transcript of the consistency check run before export
+    pub transcript: WitnessTranscript,
+}
+
+impl SharedWitness {
+    /// The number of values in the witness
+    pub fn len(&self) -> usize {
+        self.shares.len()
+    }
+
+    /// Whether the witness is empty
+    pub fn is_empty(&self) -> bool {
+        self.shares.is_empty()
+    }
+}
+
+/// The transcript of a witness consistency check, identical for both parties
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessTranscript {
+    /// The session the witness was exported in
+    pub session_id: SessionId,
+    /// The number of values in the witness
+    pub len: usize,
+    /// The opened seed that the coefficients of the linear combination are derived from
+    pub seed: Scalar,
+    /// The opened, masked linear combination of the witness
+    pub combination: Scalar,
+}
+
+impl WitnessTranscript {
+    /// The coefficients of the linear combination opened in the check
+    pub fn coefficients(&self) -> Vec<Scalar> {
+        MpcFabric::deferred_check_coefficients(
+            self.session_id,
+            self.seed,
+            WITNESS_CHECK_DOMAIN,
+            self.len,
+        )
+    }
+
+    /// A digest of the transcript, which a prover may bind its proof to
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(WITNESS_CHECK_DOMAIN);
+        hasher.update(self.session_id);
+        hasher.update((self.len as u64).to_le_bytes());
+        hasher.update(self.seed.to_bytes_be());
+        hasher.update(self.combination.to_bytes_be());
+
+        hasher.finalize().into()
+    }
+}
+
+/// Check the consistency of a shared witness and export it
+///
+/// Both parties must export the same witness. Fails with `MpcError::AuthenticationError`
+/// if the consistency check fails
+pub async fn export_shared_witness(
+    witness: &[AuthenticatedScalarResult],
+) -> Result<SharedWitness, MpcError> {
+    assert!(!witness.is_empty(), "cannot export an empty witness");
+    let fabric = witness[0].fabric();
+    let n = witness.len();
+
+    // Toss a random seed for the linear combination once the witness is computed, and mask
+    // the combination with a fresh shared value
+    let seed = fabric
+        .committed_challenge(witness.iter().flat_map(|w| w.ids()).collect_vec())
+        .await;
+    let session_id = fabric.session_id();
+    let mask = fabric
+        .random_shared_scalars_authenticated(1 /* n */)
+        .remove(0);
+
+    let mut coeffs =
+        MpcFabric::deferred_check_coefficients(session_id, seed, WITNESS_CHECK_DOMAIN, n);
+    coeffs.push(Scalar::one());
+    let values = witness.iter().cloned().chain([mask]).collect_vec();
+    let combination = linear_combination(&values, &coeffs)
+        .open_authenticated()
+        .await?;
+
+    // Normalize the MACs by their public modifiers and export the local shares
+    let mut deps = vec![fabric.borrow_mac_key().id()];
+    deps.extend(witness.iter().flat_map(|w| w.ids()));
+    let exported: Vec<ScalarResult> =
+        fabric.new_batch_gate_op(deps, 2 * n + 1 /* output_arity */, move |mut args| {
+            let mac_key_share: Scalar = args.remove(0).into();
+
+            let mut shares = Vec::with_capacity(n);
+            let mut macs = Vec::with_capacity(n);
+            for mut value in args
+                .into_iter()
+                .chunks(AUTHENTICATED_SCALAR_RESULT_LEN)
+                .into_iter()
+            {
+                let share: Scalar = value.next().unwrap().into();
+                let mac: Scalar = value.next().unwrap().into();
+                let modifier: Scalar = value.next().unwrap().into();
+
+                shares.push(ResultValue::Scalar(share));
+                macs.push(ResultValue::Scalar(mac - mac_key_share * modifier));
+            }
+
+            shares
+                .into_iter()
+                .chain(macs)
+                .chain([ResultValue::Scalar(mac_key_share)])
+                .collect_vec()
+        });
+    let mut exported = join_results(exported).await;
+
+    let mac_key_share = exported.pop().unwrap();
+    let macs = exported.split_off(n);
+    Ok(SharedWitness {
+        party_id: fabric.party_id(),
+        shares: exported,
+        macs,
+        mac_key_share,
+        transcript: WitnessTranscript {
+            session_id,
+            len: n,
+            seed,
+            combination,
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::{izip, Itertools};
+    use rand::thread_rng;
+
+    use crate::{
+        algebra::scalar::Scalar, protocols::witness::export_shared_witness,
+        test_helpers::execute_mock_mpc, PARTY0,
+    };
+
+    /// Tests that the exports of both parties recombine to the witness and its MACs
+    #[tokio::test]
+    async fn test_export_shared_witness() {
+        const N: usize = 5;
+        let mut rng = thread_rng();
+        let values = (0..N).map(|_| Scalar::random(&mut rng)).collect_vec();
+        let public = Scalar::random(&mut rng);
+
+        let (party0, party1) = execute_mock_mpc(|fabric| {
+            let values = values.clone();
+            async move {
+                // Add a public value to exercise the normalization of the public modifier
+                let mut witness = fabric.batch_share_scalar(values, PARTY0);
+                witness[0] = &witness[0] + public;
+
+                export_shared_witness(&witness).await.unwrap()
+            }
+        })
+        .await;
+
+        assert_eq!(party0.transcript, party1.transcript);
+        let mac_key = party0.mac_key_share + party1.mac_key_share;
+
+        let mut expected = values;
+        expected[0] += public;
+        for (expected, share0, share1, mac0, mac1) in izip!(
+            expected,
+            party0.shares,
+            party1.shares,
+            party0.macs,
+            party1.macs
+        ) {
+            assert_eq!(share0 + share1, expected);
+            assert_eq!(mac0 + mac1, mac_key * expected);
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Python bindings for the fabric and its authenticated values, built with pyo3
+//!
+//! Values are shared, operated on, and opened from Python as from Rust, the fabric's futures
+//! are bridged to Python awaitables on a tokio runtime managed by `pyo3-asyncio`:
+//!
+//! ```python
+//! import asyncio
+//! from mpc_stark import MpcFabric
+//!
+//! async def main():
+//!     party0, party1 = MpcFabric.mock_pair()
+//!
+//!     async def run(fabric):
+//!         a = fabric.share_scalar(3, sender=0)
+//!         b = fabric.share_scalar(5, sender=1)
+//!         return int(await (a * b).open())
+//!
+//!     print(await asyncio.gather(run(party0), run(party1)))
+//!
+//! asyncio.run(main())
+//! ```
+//!
+//! The module is built as a Python extension with `maturin develop`, which enables the
+//! features listed in `pyproject.toml`: `python`, `test_helpers` for `MpcFabric.mock_pair`,
+//! and pyo3's `extension-module`
+
+use std::{fs, net::SocketAddr};
+
+use num_bigint::{BigInt, BigUint, Sign};
+use pyo3::{create_exception, exceptions::PyException, prelude::*, types::PyBytes};
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        authenticated_stark_point::AuthenticatedStarkPointResult, scalar::Scalar,
+        stark_curve::StarkPoint,
+    },
+    beaver::PreprocessedMaterial,
+    network::{PartyId, QuicTwoPartyNet},
+    FabricConfig, MpcFabric,
+};
+
+create_exception!(
+    mpc_stark,
+    MpcException,
+    PyException,
+    "An error in the MPC computation"
+);
+
+/// Reduce a Python integer, which may be negative, modulo the scalar field's order
+fn scalar_from_int(value: &BigInt) -> Scalar {
+    let magnitude = Scalar::from_biguint(value.magnitude());
+    match value.sign() {
+        Sign::Minus => -magnitude,
+        _ => magnitude,
+    }
+}
+
+/// Convert an error into a Python exception
+fn to_py_err<E: ToString>(err: E) -> PyErr {
+    MpcException::new_err(err.to_string())
+}
+
+/// A fabric configuration whose workers run on the runtime that drives Python awaitables
+fn python_config() -> FabricConfig {
+    let runtime = pyo3_asyncio::tokio::get_runtime();
+    FabricConfig::default().with_runtime(runtime.handle().clone())
+}
+
+// -----------------
+// | Public Values |
+// -----------------
+
+/// A public scalar in the Stark curve's scalar field
+#[pyclass(name = "Scalar")]
+#[derive(Clone)]
+pub struct PyScalar(Scalar);
+
+#[pymethods]
+impl PyScalar {
+    /// Construct a scalar from an integer, reduced modulo the field's order
+    #[new]
+    fn new(value: BigInt) -> Self {
+        PyScalar(scalar_from_int(&value))
+    }
+
+    /// The canonical integer representative of the scalar
+    fn __int__(&self) -> BigUint {
+        self.0.to_biguint()
+    }
+
+    /// The debug representation of the scalar
+    fn __repr__(&self) -> String {
+        format!("Scalar({})", self.0)
+    }
+
+    /// Whether the scalar equals a public operand, a shared operand is never equal
+    fn __eq__(&self, other: ScalarOperand) -> bool {
+        match other.public() {
+            Some(other) => self.0 == other,
+            None => false,
+        }
+    }
+
+    /// Add a scalar operand, the sum is shared if the operand is
+    fn __add__(&self, py: Python<'_>, other: ScalarOperand) -> PyObject {
+        match other {
+            ScalarOperand::Shared(other) => PyAuthenticatedScalar(&other.0 + self.0).into_py(py),
+            other => PyScalar(self.0 + other.public().unwrap()).into_py(py),
+        }
+    }
+
+    /// Add the scalar to a scalar operand on its left
+    fn __radd__(&self, py: Python<'_>, other: ScalarOperand) -> PyObject {
+        self.__add__(py, other)
+    }
+
+    /// Subtract a scalar operand, the difference is shared if the operand is
+    fn __sub__(&self, py: Python<'_>, other: ScalarOperand) -> PyObject {
+        match other {
+            ScalarOperand::Shared(other) => PyAuthenticatedScalar(-&other.0 + self.0).into_py(py),
+            other => PyScalar(self.0 - other.public().unwrap()).into_py(py),
+        }
+    }
+
+    /// Subtract the scalar from a scalar operand on its left
+    fn __rsub__(&self, py: Python<'_>, other: ScalarOperand) -> PyObject {
+        match other {
+            ScalarOperand::Shared(other) => PyAuthenticatedScalar(&other.0 - self.0).into_py(py),
+            other => PyScalar(other.public().unwrap() - self.0).into_py(py),
+        }
+    }
+
+    /// Multiply by a scalar operand, the product is shared if the operand is
+    fn __mul__(&self, py: Python<'_>, other: ScalarOperand) -> PyObject {
+        match other {
+            ScalarOperand::Shared(other) => PyAuthenticatedScalar(&other.0 * self.0).into_py(py),
+            other => PyScalar(self.0 * other.public().unwrap()).into_py(py),
+        }
+    }
+
+    /// Multiply a scalar operand on the left by the scalar
+    fn __rmul__(&self, py: Python<'_>, other: ScalarOperand) -> PyObject {
+        self.__mul__(py, other)
+    }
+
+    /// The additive inverse of the scalar
+    fn __neg__(&self) -> Self {
+        PyScalar(-self.0)
+    }
+}
+
+/// A public point on the Stark curve
+#[pyclass(name = "StarkPoint")]
+#[derive(Clone)]
+pub struct PyStarkPoint(StarkPoint);
+
+#[pymethods]
+impl PyStarkPoint {
+    /// The group generator
+    #[staticmethod]
+    fn generator() -> Self {
+        PyStarkPoint(StarkPoint::generator())
+    }
+
+    /// The additive identity in the curve group
+    #[staticmethod]
+    fn identity() -> Self {
+        PyStarkPoint(StarkPoint::identity())
+    }
+
+    /// Deserialize a point from its compressed encoding
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        StarkPoint::from_bytes(bytes)
+            .map(PyStarkPoint)
+            .map_err(to_py_err)
+    }
+
+    /// Serialize the point to its compressed encoding
+    fn to_bytes<'p>(&self, py: Python<'p>) -> &'p PyBytes {
+        PyBytes::new(py, &self.0.to_bytes())
+    }
+
+    /// The debug representation of the point, its compressed encoding in hex
+    fn __repr__(&self) -> String {
+        format!("StarkPoint({})", hex_string(&self.0.to_bytes()))
+    }
+
+    /// Whether two points are equal
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+
+    /// Add two points
+    fn __add__(&self, other: &Self) -> Self {
+        PyStarkPoint(self.0 + other.0)
+    }
+
+    /// Subtract a point
+    fn __sub__(&self, other: &Self) -> Self {
+        PyStarkPoint(self.0 - other.0)
+    }
+
+    /// The additive inverse of the point
+    fn __neg__(&self) -> Self {
+        PyStarkPoint(-self.0)
+    }
+
+    /// Multiply the point by a scalar operand, the product is shared if the scalar is
+    fn __mul__(&self, py: Python<'_>, scalar: ScalarOperand) -> PyObject {
+        match scalar {
+            ScalarOperand::Shared(scalar) => {
+                PyAuthenticatedStarkPoint(&self.0 * &scalar.0).into_py(py)
+            }
+            scalar => PyStarkPoint(self.0 * scalar.public().unwrap()).into_py(py),
+        }
+    }
+
+    /// Multiply the point by a scalar operand on its left
+    fn __rmul__(&self, py: Python<'_>, scalar: ScalarOperand) -> PyObject {
+        self.__mul__(py, scalar)
+    }
+}
+
+/// Encode bytes as a lowercase hex string
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// A scalar operand of an arithmetic operation, either shared or public
+///
+/// Operands are extracted from Python arguments and immediately consumed, so their size
+/// does not matter
+#[allow(clippy::large_enum_variant)]
+#[derive(FromPyObject)]
+enum ScalarOperand {
+    /// A shared scalar
+    Shared(PyAuthenticatedScalar),
+    /// A public scalar
+    Public(PyScalar),
+    /// A public scalar given as a Python integer, which may be negative
+    Int(BigInt),
+}
+
+impl ScalarOperand {
+    /// The value of the operand if it is public
+    fn public(&self) -> Option<Scalar> {
+        match self {
+            ScalarOperand::Shared(_) => None,
+            ScalarOperand::Public(scalar) => Some(scalar.0),
+            ScalarOperand::Int(value) => Some(scalar_from_int(value)),
+        }
+    }
+}
+
+// -----------------
+// | Shared Values |
+// -----------------
+
+/// An authenticated secret share of a scalar
+#[pyclass(name = "AuthenticatedScalar")]
+#[derive(Clone)]
+pub struct PyAuthenticatedScalar(AuthenticatedScalarResult);
+
+#[pymethods]
+impl PyAuthenticatedScalar {
+    /// Open the value and check its MAC, returns an awaitable resolving to a `Scalar`
+    fn open<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let opened = self.0.open_authenticated();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            opened.await.map(PyScalar).map_err(to_py_err)
+        })
+    }
+
+    /// Open a batch of values and check their MACs, returns an awaitable resolving to a list
+    /// of `Scalar`s
+    #[staticmethod]
+    fn open_batch(py: Python<'_>, values: Vec<PyAuthenticatedScalar>) -> PyResult<&PyAny> {
+        let values = values.into_iter().map(|v| v.0).collect::<Vec<_>>();
+        let opened = AuthenticatedScalarResult::open_authenticated_batch(&values);
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let mut res = Vec::with_capacity(opened.len());
+            for value in opened {
+                res.push(PyScalar(value.await.map_err(to_py_err)?));
+            }
+
+            Ok(res)
+        })
+    }
+
+    /// Add a scalar operand
+    fn __add__(&self, other: ScalarOperand) -> Self {
+        PyAuthenticatedScalar(match other {
+            ScalarOperand::Shared(other) => &self.0 + &other.0,
+            other => &self.0 + other.public().unwrap(),
+        })
+    }
+
+    /// Add the value to a scalar operand on its left
+    fn __radd__(&self, other: ScalarOperand) -> Self {
+        self.__add__(other)
+    }
+
+    /// Subtract a scalar operand
+    fn __sub__(&self, other: ScalarOperand) -> Self {
+        PyAuthenticatedScalar(match other {
+            ScalarOperand::Shared(other) => &self.0 - &other.0,
+            other => &self.0 - other.public().unwrap(),
+        })
+    }
+
+    /// Subtract the value from a scalar operand on its left
+    fn __rsub__(&self, other: ScalarOperand) -> Self {
+        self.__neg__().__add__(other)
+    }
+
+    /// Multiply by a scalar operand
+    fn __mul__(&self, other: ScalarOperand) -> Self {
+        PyAuthenticatedScalar(match other {
+            ScalarOperand::Shared(other) => &self.0 * &other.0,
+            other => &self.0 * other.public().unwrap(),
+        })
+    }
+
+    /// Multiply a scalar operand on the left by the value
+    fn __rmul__(&self, other: ScalarOperand) -> Self {
+        self.__mul__(other)
+    }
+
+    /// The additive inverse of the value
+    fn __neg__(&self) -> Self {
+        PyAuthenticatedScalar(-&self.0)
+    }
+}
+
+/// A point operand of an arithmetic operation, either shared or public
+#[derive(FromPyObject)]
+enum PointOperand {
+    /// A shared point
+    Shared(PyAuthenticatedStarkPoint),
+    /// A public point
+    Public(PyStarkPoint),
+}
+
+/// An authenticated secret share of a point
+#[pyclass(name = "AuthenticatedStarkPoint")]
+#[derive(Clone)]
+pub struct PyAuthenticatedStarkPoint(AuthenticatedStarkPointResult);
+
+#[pymethods]
+impl PyAuthenticatedStarkPoint {
+    /// Open the value and check its MAC, returns an awaitable resolving to a `StarkPoint`
+    fn open<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let opened = self.0.open_authenticated();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            opened.await.map(PyStarkPoint).map_err(to_py_err)
+        })
+    }
+
+    /// Add a point operand
+    fn __add__(&self, other: PointOperand) -> Self {
+        PyAuthenticatedStarkPoint(match other {
+            PointOperand::Shared(other) => &self.0 + &other.0,
+            PointOperand::Public(other) => &self.0 + other.0,
+        })
+    }
+
+    /// Add the value to a point operand on its left
+    fn __radd__(&self, other: PointOperand) -> Self {
+        self.__add__(other)
+    }
+
+    /// Subtract a point operand
+    fn __sub__(&self, other: PointOperand) -> Self {
+        PyAuthenticatedStarkPoint(match other {
+            PointOperand::Shared(other) => &self.0 - &other.0,
+            PointOperand::Public(other) => &self.0 - other.0,
+        })
+    }
+
+    /// Subtract the value from a point operand on its left
+    fn __rsub__(&self, other: PointOperand) -> Self {
+        self.__neg__().__add__(other)
+    }
+
+    /// Multiply by a scalar operand
+    fn __mul__(&self, scalar: ScalarOperand) -> Self {
+        PyAuthenticatedStarkPoint(match scalar {
+            ScalarOperand::Shared(scalar) => &self.0 * &scalar.0,
+            scalar => &self.0 * scalar.public().unwrap(),
+        })
+    }
+
+    /// Multiply the value by a scalar operand on its left
+    fn __rmul__(&self, scalar: ScalarOperand) -> Self {
+        self.__mul__(scalar)
+    }
+
+    /// The additive inverse of the value
+    fn __neg__(&self) -> Self {
+        PyAuthenticatedStarkPoint(-&self.0)
+    }
+}
+
+// ----------
+// | Fabric |
+// ----------
+
+/// A handle to a two party MPC fabric
+#[pyclass(name = "MpcFabric")]
+#[derive(Clone)]
+pub struct PyMpcFabric(MpcFabric);
+
+#[pymethods]
+impl PyMpcFabric {
+    /// Connect to the peer over QUIC, returns an awaitable resolving to a fabric
+    ///
+    /// The fabric draws its preprocessed material from the JSON encoded
+    /// `PreprocessedMaterial` at the given path
+    #[staticmethod]
+    fn connect<'p>(
+        py: Python<'p>,
+        party_id: PartyId,
+        local_addr: &str,
+        peer_addr: &str,
+        preprocessing_path: &str,
+    ) -> PyResult<&'p PyAny> {
+        let local_addr: SocketAddr = local_addr.parse().map_err(to_py_err)?;
+        let peer_addr: SocketAddr = peer_addr.parse().map_err(to_py_err)?;
+        let material: PreprocessedMaterial =
+            serde_json::from_slice(&fs::read(preprocessing_path).map_err(to_py_err)?)
+                .map_err(to_py_err)?;
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let mut network = QuicTwoPartyNet::new(party_id, local_addr, peer_addr);
+            network.connect().await.map_err(to_py_err)?;
+
+            Ok(PyMpcFabric(MpcFabric::with_config(
+                network,
+                material,
+                python_config(),
+            )))
+        })
+    }
+
+    /// Construct the fabrics of both parties connected over an in-memory network, for
+    /// prototyping a computation in a single process
+    ///
+    /// The fabrics use insecure preprocessing known to both parties
+    #[cfg(feature = "test_helpers")]
+    #[staticmethod]
+    fn mock_pair() -> (Self, Self) {
+        use crate::{
+            network::{MockNetwork, UnboundedDuplexStream},
+            test_helpers::PartyIDBeaverSource,
+            PARTY0, PARTY1,
+        };
+
+        // The fabrics spawn their workers onto the runtime, so its context must be entered
+        let _guard = pyo3_asyncio::tokio::get_runtime().enter();
+        let (stream0, stream1) = UnboundedDuplexStream::new_duplex_pair();
+        let [party0, party1] = [(PARTY0, stream0), (PARTY1, stream1)].map(|(party_id, stream)| {
+            PyMpcFabric(MpcFabric::with_config(
+                MockNetwork::new(party_id, stream),
+                PartyIDBeaverSource::new(party_id),
+                python_config(),
+            ))
+        });
+
+        (party0, party1)
+    }
+
+    /// The ID of the local party
+    #[getter]
+    fn party_id(&self) -> PartyId {
+        self.0.party_id()
+    }
+
+    /// Share a scalar sent by the given party
+    ///
+    /// The receiving party's value is ignored and may be omitted
+    #[pyo3(signature = (value=None, *, sender))]
+    fn share_scalar(
+        &self,
+        value: Option<ScalarOperand>,
+        sender: PartyId,
+    ) -> PyResult<PyAuthenticatedScalar> {
+        let value = match value {
+            Some(value) => value
+                .public()
+                .ok_or_else(|| to_py_err("a shared value cannot be shared again"))?,
+            None => Scalar::zero(),
+        };
+
+        Ok(PyAuthenticatedScalar(self.0.share_scalar(value, sender)))
+    }
+
+    /// Share a point sent by the given party
+    ///
+    /// The receiving party's value is ignored and may be omitted
+    #[pyo3(signature = (value=None, *, sender))]
+    fn share_point(
+        &self,
+        value: Option<PyStarkPoint>,
+        sender: PartyId,
+    ) -> PyAuthenticatedStarkPoint {
+        let value = value.map(|v| v.0).unwrap_or_else(StarkPoint::identity);
+        PyAuthenticatedStarkPoint(self.0.share_point(value, sender))
+    }
+
+    /// Shut down the fabric, returns an awaitable that resolves once the queued messages are
+    /// sent to the peer
+    fn shutdown<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let handle = self.0.clone().shutdown();
+        pyo3_asyncio::tokio::future_into_py(py, async move { handle.await.map_err(to_py_err) })
+    }
+}
+
+/// The `mpc_stark` Python module
+#[pymodule]
+fn mpc_stark(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyScalar>()?;
+    m.add_class::<PyStarkPoint>()?;
+    m.add_class::<PyAuthenticatedScalar>()?;
+    m.add_class::<PyAuthenticatedStarkPoint>()?;
+    m.add_class::<PyMpcFabric>()?;
+    m.add("MpcError", py.get_type::<MpcException>())?;
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Blocking wrappers around the fabric's futures, for applications and FFI layers that do not
+//! run an async executor
+//!
+//! The fabric itself still runs on a tokio runtime, a synchronous caller supplies one with
+//! `FabricConfig::with_runtime`. The runtime must be driven independently of the blocking
+//! caller, e.g. a multi-threaded runtime or a current-thread runtime driven on another thread.
+//! Blocking on a result from within one of the runtime's own tasks may deadlock
+
+use futures::{executor::block_on, future::join_all, Future};
+use itertools::Itertools;
+
+use crate::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult,
+        authenticated_stark_point::AuthenticatedStarkPointResult, scalar::Scalar,
+        stark_curve::StarkPoint,
+    },
+    error::MpcError,
+    fabric::{ResultHandle, ResultValue},
+};
+
+/// Block the current thread until a future resolves
+pub fn wait<F: Future>(fut: F) -> F::Output {
+    block_on(fut)
+}
+
+/// Blocking accessors on a result handle
+pub trait BlockingResult<T> {
+    /// Block until the result is computed, returning an error if the computation fails
+    fn await_result(self) -> Result<T, MpcError>;
+}
+
+impl<T: From<ResultValue> + 'static> BlockingResult<T> for ResultHandle<T> {
+    fn await_result(self) -> Result<T, MpcError> {
+        block_on(self.fallible())
+    }
+}
+
+/// Blocking openings of authenticated values
+pub trait OpenBlocking: Sized {
+    /// The type of the opened value
+    type Output;
+
+    /// Open the value and check its MAC, blocking until both complete
+    fn open_blocking(&self) -> Result<Self::Output, MpcError>;
+
+    /// Open a batch of values and check their MACs, blocking until all complete
+    fn open_batch_blocking(values: &[Self]) -> Result<Vec<Self::Output>, MpcError>;
+}
+
+impl OpenBlocking for AuthenticatedScalarResult {
+    type Output = Scalar;
+
+    fn open_blocking(&self) -> Result<Scalar, MpcError> {
+        block_on(self.open_authenticated())
+    }
+
+    fn open_batch_blocking(values: &[Self]) -> Result<Vec<Scalar>, MpcError> {
+        let opened = Self::open_authenticated_batch(values);
+        block_on(join_all(opened)).into_iter().try_collect()
+    }
+}
+
+impl OpenBlocking for AuthenticatedStarkPointResult {
+    type Output = StarkPoint;
+
+    fn open_blocking(&self) -> Result<StarkPoint, MpcError> {
+        block_on(self.open_authenticated())
+    }
+
+    fn open_batch_blocking(values: &[Self]) -> Result<Vec<StarkPoint>, MpcError> {
+        let opened = Self::open_authenticated_batch(values);
+        block_on(join_all(opened)).into_iter().try_collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use tokio::runtime::Builder as RuntimeBuilder;
+
+    use crate::{
+        algebra::{
+            authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar,
+            stark_curve::StarkPoint,
+        },
+        beaver::PartyIDBeaverSource,
+        network::{MockNetwork, UnboundedDuplexStream},
+        FabricConfig, MpcFabric, PARTY0, PARTY1,
+    };
+
+    use super::{BlockingResult, OpenBlocking};
+
+    /// Tests evaluating a circuit from threads outside of any async context
+    #[test]
+    fn test_blocking_open() {
+        let runtime = RuntimeBuilder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (stream0, stream1) = UnboundedDuplexStream::new_duplex_pair();
+
+        let parties = [(PARTY0, stream0), (PARTY1, stream1)].map(|(party_id, stream)| {
+            let handle = runtime.handle().clone();
+            thread::spawn(move || {
+                let fabric = MpcFabric::with_config(
+                    MockNetwork::new(party_id, stream),
+                    PartyIDBeaverSource::new(party_id),
+                    FabricConfig::default().with_runtime(handle),
+                );
+
+                let a = fabric.share_scalar(Scalar::from(3u8), PARTY0);
+                let b = fabric.share_scalar(Scalar::from(5u8), PARTY1);
+                let product = (&a * &b).open_blocking();
+                let batch = AuthenticatedScalarResult::open_batch_blocking(&[a, b]);
+
+                let point = fabric.share_point(StarkPoint::generator(), PARTY1);
+                let opened_point = point.open_blocking();
+                let public = (fabric.allocate_scalar(2u8) * Scalar::from(4u8)).await_result();
+
+                fabric.shutdown();
+                (product, batch, opened_point, public)
+            })
+        });
+
+        for party in parties {
+            let (product, batch, point, public) = party.join().unwrap();
+            assert_eq!(product, Ok(Scalar::from(15u8)));
+            assert_eq!(batch, Ok(vec![Scalar::from(3u8), Scalar::from(5u8)]));
+            assert_eq!(point, Ok(StarkPoint::generator()));
+            assert_eq!(public, Ok(Scalar::from(8u8)));
+        }
+    }
+}